@@ -32,6 +32,7 @@ mod quorum_set;
 mod ring_mlsag;
 mod ristretto_private;
 mod signature_rct_bulletproofs;
+mod signed_contingent_input;
 mod tx;
 mod tx_hash;
 mod tx_in;
@@ -40,6 +41,7 @@ mod tx_out_confirmation_number;
 mod tx_out_membership_element;
 mod tx_out_membership_proof;
 mod tx_prefix;
+mod unmasked_amount;
 mod validated_mint_config;
 mod verification_report;
 mod verification_signature;
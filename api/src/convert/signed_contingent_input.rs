@@ -0,0 +1,157 @@
+// Copyright (c) 2018-2022 The MobileCoin Foundation
+
+//! Convert to/from external::SignedContingentInput
+
+use crate::{external, ConversionError};
+use mc_transaction_core::{
+    ring_signature::RingMLSAG, tx::TxIn, SignedContingentInput, UnmaskedAmount,
+};
+
+/// Convert SignedContingentInput --> external::SignedContingentInput.
+impl From<&SignedContingentInput> for external::SignedContingentInput {
+    fn from(source: &SignedContingentInput) -> Self {
+        let mut sci = external::SignedContingentInput::new();
+        sci.set_block_version(source.block_version);
+        sci.set_tx_in((&source.tx_in).into());
+        sci.set_mlsag((&source.mlsag).into());
+        sci.set_pseudo_output_amount((&source.pseudo_output_amount).into());
+        sci.set_required_output_amounts(
+            source
+                .required_output_amounts
+                .iter()
+                .map(external::UnmaskedAmount::from)
+                .collect(),
+        );
+        sci.set_tx_out_global_indices(source.tx_out_global_indices.clone());
+        sci
+    }
+}
+
+/// Convert external::SignedContingentInput --> SignedContingentInput.
+impl TryFrom<&external::SignedContingentInput> for SignedContingentInput {
+    type Error = ConversionError;
+
+    fn try_from(source: &external::SignedContingentInput) -> Result<Self, Self::Error> {
+        let block_version = source.get_block_version();
+        let tx_in = TxIn::try_from(source.get_tx_in())?;
+        let mlsag = RingMLSAG::try_from(source.get_mlsag())?;
+        let pseudo_output_amount = UnmaskedAmount::try_from(source.get_pseudo_output_amount())?;
+        let required_output_amounts = source
+            .get_required_output_amounts()
+            .iter()
+            .map(UnmaskedAmount::try_from)
+            .collect::<Result<Vec<_>, _>>()?;
+        let tx_out_global_indices = source.get_tx_out_global_indices().to_vec();
+        Ok(SignedContingentInput {
+            block_version,
+            tx_in,
+            mlsag,
+            pseudo_output_amount,
+            required_output_amounts,
+            tx_out_global_indices,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::printable::PrintableWrapper;
+    use mc_account_keys::AccountKey;
+    use mc_crypto_ring_signature_signer::NoKeysRingSigner;
+    use mc_fog_report_validation_test_utils::MockFogResolver;
+    use mc_transaction_core::{
+        constants::MILLIMOB_TO_PICOMOB, tokens::Mob, Amount, BlockVersion, Token, TokenId,
+    };
+    use mc_transaction_std::{
+        test_utils::get_input_credentials, EmptyMemoBuilder, SignedContingentInputBuilder,
+    };
+    use protobuf::Message;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    fn make_sci(block_version: BlockVersion, rng: &mut StdRng) -> SignedContingentInput {
+        let charlie = AccountKey::random(rng);
+        let fpr = MockFogResolver::default();
+
+        // Charlie makes a signed contingent input, offering 1000 token2's for 1 MOB
+        let input_credentials = get_input_credentials(
+            block_version,
+            Amount::new(1000, TokenId::from(2)),
+            &charlie,
+            &fpr,
+            rng,
+        );
+        let mut sci_builder = SignedContingentInputBuilder::new(
+            block_version,
+            input_credentials,
+            fpr,
+            EmptyMemoBuilder::default(),
+        )
+        .unwrap();
+
+        sci_builder
+            .add_required_output(
+                Amount::new(1000 * MILLIMOB_TO_PICOMOB, Mob::ID),
+                &charlie.default_subaddress(),
+                rng,
+            )
+            .unwrap();
+        sci_builder.set_tombstone_block(2000);
+
+        sci_builder.build(&NoKeysRingSigner {}, rng).unwrap()
+    }
+
+    #[test]
+    /// SignedContingentInput --> external::SignedContingentInput -->
+    /// SignedContingentInput should be the identity function
+    fn test_convert_signed_contingent_input() {
+        let mut rng: StdRng = SeedableRng::from_seed([1u8; 32]);
+
+        for block_version in BlockVersion::iterator().skip(3) {
+            let sci = make_sci(block_version, &mut rng);
+
+            // Converting to the api type and back should be the identity function.
+            {
+                let external_sci = external::SignedContingentInput::from(&sci);
+                let recovered_sci = SignedContingentInput::try_from(&external_sci).unwrap();
+                assert_eq!(sci, recovered_sci);
+                recovered_sci.validate().unwrap();
+            }
+
+            // Encoding with prost, decoding with protobuf should be the identity function.
+            {
+                let bytes = mc_util_serial::encode(&sci);
+                let recovered_sci =
+                    external::SignedContingentInput::parse_from_bytes(&bytes).unwrap();
+                assert_eq!(recovered_sci, external::SignedContingentInput::from(&sci));
+            }
+
+            // Encoding with protobuf, decoding with prost should be the identity function.
+            {
+                let bytes = external::SignedContingentInput::from(&sci)
+                    .write_to_bytes()
+                    .unwrap();
+                let recovered_sci: SignedContingentInput = mc_util_serial::decode(&bytes).unwrap();
+                assert_eq!(sci, recovered_sci);
+            }
+        }
+    }
+
+    #[test]
+    /// A signed contingent input should survive a b58 roundtrip through a
+    /// PrintableWrapper
+    fn test_signed_contingent_input_b58_roundtrip() {
+        let mut rng: StdRng = SeedableRng::from_seed([2u8; 32]);
+        let sci = make_sci(BlockVersion::MAX, &mut rng);
+
+        let mut wrapper = PrintableWrapper::new();
+        wrapper.set_signed_contingent_input((&sci).into());
+        let encoded = wrapper.b58_encode().unwrap();
+        let decoded = PrintableWrapper::b58_decode(encoded).unwrap();
+        assert_eq!(wrapper, decoded);
+
+        let recovered_sci =
+            SignedContingentInput::try_from(decoded.get_signed_contingent_input()).unwrap();
+        assert_eq!(sci, recovered_sci);
+    }
+}
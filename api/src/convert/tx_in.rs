@@ -1,7 +1,7 @@
 //! Convert to/from external::TxIn.
 
 use crate::{external, ConversionError};
-use mc_transaction_core::{tx, tx::TxOutMembershipProof, InputRules};
+use mc_transaction_core::{tx, tx::TxOutMembershipProof, InputRules, RevealedTxOut};
 
 /// Convert tx::TxIn --> external::TxIn.
 impl From<&tx::TxIn> for external::TxIn {
@@ -72,6 +72,19 @@ impl From<&InputRules> for external::InputRules {
 
         input_rules.set_max_tombstone_block(source.max_tombstone_block);
 
+        let partial_fill_outputs = source
+            .partial_fill_outputs
+            .iter()
+            .map(external::RevealedTxOut::from)
+            .collect();
+        input_rules.set_partial_fill_outputs(partial_fill_outputs);
+
+        if let Some(partial_fill_change) = source.partial_fill_change.as_ref() {
+            input_rules.set_partial_fill_change(partial_fill_change.into());
+        }
+
+        input_rules.set_min_partial_fill_value(source.min_partial_fill_value);
+
         input_rules
     }
 }
@@ -87,9 +100,47 @@ impl TryFrom<&external::InputRules> for InputRules {
             .map(tx::TxOut::try_from)
             .collect::<Result<Vec<_>, _>>()?;
         let max_tombstone_block = source.max_tombstone_block;
+        let partial_fill_outputs = source
+            .get_partial_fill_outputs()
+            .iter()
+            .map(RevealedTxOut::try_from)
+            .collect::<Result<Vec<_>, _>>()?;
+        let partial_fill_change = source
+            .partial_fill_change
+            .as_ref()
+            .map(RevealedTxOut::try_from)
+            .transpose()?;
+        let min_partial_fill_value = source.min_partial_fill_value;
         Ok(InputRules {
             required_outputs,
             max_tombstone_block,
+            partial_fill_outputs,
+            partial_fill_change,
+            min_partial_fill_value,
+        })
+    }
+}
+
+/// Convert RevealedTxOut --> external::RevealedTxOut.
+impl From<&RevealedTxOut> for external::RevealedTxOut {
+    fn from(source: &RevealedTxOut) -> Self {
+        let mut revealed_tx_out = external::RevealedTxOut::new();
+        revealed_tx_out.set_tx_out((&source.tx_out).into());
+        revealed_tx_out.set_shared_secret((&source.shared_secret).into());
+        revealed_tx_out
+    }
+}
+
+/// Convert external::RevealedTxOut --> RevealedTxOut.
+impl TryFrom<&external::RevealedTxOut> for RevealedTxOut {
+    type Error = ConversionError;
+
+    fn try_from(source: &external::RevealedTxOut) -> Result<Self, Self::Error> {
+        let tx_out = tx::TxOut::try_from(source.get_tx_out())?;
+        let shared_secret = source.get_shared_secret().try_into()?;
+        Ok(RevealedTxOut {
+            tx_out,
+            shared_secret,
         })
     }
 }
@@ -0,0 +1,33 @@
+// Copyright (c) 2018-2022 The MobileCoin Foundation
+
+//! Convert to/from external::UnmaskedAmount
+
+use crate::{external, ConversionError};
+use mc_transaction_core::{ring_signature::CurveScalar, UnmaskedAmount};
+
+/// Convert UnmaskedAmount --> external::UnmaskedAmount.
+impl From<&UnmaskedAmount> for external::UnmaskedAmount {
+    fn from(source: &UnmaskedAmount) -> Self {
+        let mut unmasked_amount = external::UnmaskedAmount::new();
+        unmasked_amount.set_value(source.value);
+        unmasked_amount.set_token_id(source.token_id);
+        unmasked_amount.set_blinding((&source.blinding).into());
+        unmasked_amount
+    }
+}
+
+/// Convert external::UnmaskedAmount --> UnmaskedAmount.
+impl TryFrom<&external::UnmaskedAmount> for UnmaskedAmount {
+    type Error = ConversionError;
+
+    fn try_from(source: &external::UnmaskedAmount) -> Result<Self, Self::Error> {
+        let value = source.get_value();
+        let token_id = source.get_token_id();
+        let blinding = CurveScalar::try_from(source.get_blinding())?;
+        Ok(UnmaskedAmount {
+            value,
+            token_id,
+            blinding,
+        })
+    }
+}
@@ -76,10 +76,10 @@ mod display_tests {
     use super::Error;
     use crate::{
         external,
-        printable::{PaymentRequest, PrintableWrapper, TransferPayload},
+        printable::{PaymentRequest, PaymentRequestLineItem, PrintableWrapper, TransferPayload},
     };
     use mc_test_vectors_b58_encodings::{
-        B58EncodePublicAddressWithFog, B58EncodePublicAddressWithoutFog,
+        B58EncodePaymentRequest, B58EncodePublicAddressWithFog, B58EncodePublicAddressWithoutFog,
     };
     use mc_util_test_vector::TestVector;
     use mc_util_test_with_data::test_with_data;
@@ -178,6 +178,52 @@ mod display_tests {
         assert_eq!(decoded_wrapper, expected);
     }
 
+    fn printable_wrapper_from_b58_encode_payment_request(
+        case: &B58EncodePaymentRequest,
+    ) -> PrintableWrapper {
+        let mut public_address = external::PublicAddress::new();
+
+        let mut view_bytes = external::CompressedRistretto::new();
+        view_bytes.set_data(case.view_public_key.to_vec());
+        public_address.set_view_public_key(view_bytes);
+
+        let mut spend_bytes = external::CompressedRistretto::new();
+        spend_bytes.set_data(case.spend_public_key.to_vec());
+        public_address.set_spend_public_key(spend_bytes);
+
+        let mut payment_request = PaymentRequest::new();
+        payment_request.set_public_address(public_address);
+        payment_request.set_value(case.value);
+        payment_request.set_memo(case.memo.clone());
+        payment_request.set_token_id(case.token_id);
+        payment_request.set_payment_request_id(case.payment_request_id);
+        payment_request.set_expiration_timestamp(case.expiration_timestamp);
+        for case_line_item in case.line_items.iter() {
+            let mut line_item = PaymentRequestLineItem::new();
+            line_item.set_description(case_line_item.description.clone());
+            line_item.set_value(case_line_item.value);
+            payment_request.mut_line_items().push(line_item);
+        }
+
+        let mut wrapper = PrintableWrapper::new();
+        wrapper.set_payment_request(payment_request);
+
+        wrapper
+    }
+
+    #[test_with_data(B58EncodePaymentRequest::from_jsonl("../test-vectors/vectors"))]
+    fn test_b58_encode_payment_request(case: B58EncodePaymentRequest) {
+        let wrapper = printable_wrapper_from_b58_encode_payment_request(&case);
+        assert_eq!(wrapper.b58_encode().unwrap(), case.b58_encoded);
+    }
+
+    #[test_with_data(B58EncodePaymentRequest::from_jsonl("../test-vectors/vectors"))]
+    fn test_b58_decode_payment_request(case: B58EncodePaymentRequest) {
+        let decoded_wrapper = PrintableWrapper::b58_decode(case.b58_encoded.clone()).unwrap();
+        let expected = printable_wrapper_from_b58_encode_payment_request(&case);
+        assert_eq!(decoded_wrapper, expected);
+    }
+
     #[test]
     fn test_payment_request_roundtrip() {
         let public_address = sample_public_address();
@@ -122,6 +122,38 @@ fn signed_contingent_input_examples<T: RngCore + CryptoRng>(
         .unwrap();
     result.push(builder.build(&NoKeysRingSigner {}, rng).unwrap());
 
+    if block_version.partial_fill_input_rules_are_supported() {
+        let input_credentials = get_input_credentials(
+            block_version,
+            Amount::new(300, 1.into()),
+            &sender,
+            &fpr,
+            rng,
+        );
+        let mut builder = SignedContingentInputBuilder::new(
+            block_version,
+            input_credentials,
+            fpr.clone(),
+            EmptyMemoBuilder::default(),
+        )
+        .unwrap();
+        builder
+            .add_required_output(Amount::new(400, 0.into()), &recipient, rng)
+            .unwrap();
+        builder
+            .add_partial_fill_output(
+                Amount::new(600, 2.into()),
+                &sender.default_subaddress(),
+                rng,
+            )
+            .unwrap();
+        builder
+            .add_partial_fill_change_output(Amount::new(300, 1.into()), &sender_change_dest, rng)
+            .unwrap();
+        builder.set_min_partial_fill_value(100);
+        result.push(builder.build(&NoKeysRingSigner {}, rng).unwrap());
+    }
+
     result
 }
 
@@ -0,0 +1,379 @@
+// Copyright (c) 2018-2022 The MobileCoin Foundation
+
+//! Peer health tracking, used by the connection manager to prefer healthy
+//! peers and to quarantine stragglers.
+
+use mc_blockchain_types::BlockIndex;
+use mc_common::ResponderId;
+use std::{
+    collections::BTreeMap,
+    time::{Duration, Instant},
+};
+
+/// Configuration of peer health scoring and quarantine.
+#[derive(Clone, Debug)]
+pub struct PeerHealthConfig {
+    /// Weight given to the newest sample in the latency and error rate moving
+    /// averages, between 0 and 1.
+    pub smoothing: f64,
+
+    /// Latency at which the latency factor of a peer's score is one half.
+    pub reference_latency: Duration,
+
+    /// Number of consecutive failures after which a peer is quarantined.
+    pub max_consecutive_failures: u32,
+
+    /// Number of blocks a peer may be behind the highest block index reported
+    /// by any peer before it is quarantined.
+    pub max_blocks_behind: u64,
+
+    /// Duration of the first quarantine. Each consecutive quarantine of the
+    /// same peer doubles this, up to `max_quarantine`.
+    pub base_quarantine: Duration,
+
+    /// Maximum duration of a quarantine.
+    pub max_quarantine: Duration,
+
+    /// Peers whose score is at least this fraction of the best score share
+    /// requests in round-robin order, rather than all requests going to the
+    /// single best peer.
+    pub load_balance_ratio: f64,
+}
+
+impl Default for PeerHealthConfig {
+    fn default() -> Self {
+        Self {
+            smoothing: 0.2,
+            reference_latency: Duration::from_millis(250),
+            max_consecutive_failures: 3,
+            max_blocks_behind: 2,
+            base_quarantine: Duration::from_secs(5),
+            max_quarantine: Duration::from_secs(300),
+            load_balance_ratio: 0.75,
+        }
+    }
+}
+
+/// A point-in-time view of the health of a peer.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PeerHealthScore {
+    /// Moving average of request latency, if any request succeeded.
+    pub latency: Option<Duration>,
+
+    /// Moving average of the fraction of requests which failed.
+    pub error_rate: f64,
+
+    /// The last block index reported by the peer, if any.
+    pub block_index: Option<BlockIndex>,
+
+    /// How many blocks the peer is behind the highest block index reported by
+    /// any peer.
+    pub blocks_behind: u64,
+
+    /// Remaining quarantine time, if the peer is quarantined.
+    pub quarantine_remaining: Option<Duration>,
+
+    /// Overall score between 0 (unusable) and 1 (perfect). Quarantined peers
+    /// score 0.
+    pub score: f64,
+}
+
+impl PeerHealthScore {
+    /// Whether the peer is currently quarantined.
+    pub fn is_quarantined(&self) -> bool {
+        self.quarantine_remaining.is_some()
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+struct PeerHealth {
+    latency: Option<Duration>,
+    error_rate: f64,
+    consecutive_failures: u32,
+    block_index: Option<BlockIndex>,
+    num_quarantines: u32,
+    quarantined_until: Option<Instant>,
+}
+
+impl PeerHealth {
+    fn is_quarantined(&self, now: Instant) -> bool {
+        self.quarantined_until
+            .map(|until| until > now)
+            .unwrap_or(false)
+    }
+
+    fn quarantine(&mut self, now: Instant, config: &PeerHealthConfig) {
+        let duration = config
+            .base_quarantine
+            .checked_mul(1 << self.num_quarantines.min(16))
+            .unwrap_or(config.max_quarantine)
+            .min(config.max_quarantine);
+        self.quarantined_until = Some(now + duration);
+        self.num_quarantines += 1;
+    }
+}
+
+/// Tracks the health of a set of peers.
+#[derive(Clone, Debug)]
+pub struct PeerHealthTracker {
+    config: PeerHealthConfig,
+    peers: BTreeMap<ResponderId, PeerHealth>,
+}
+
+impl PeerHealthTracker {
+    /// Create a tracker for the given peers.
+    pub fn new(
+        responder_ids: impl IntoIterator<Item = ResponderId>,
+        config: PeerHealthConfig,
+    ) -> Self {
+        Self {
+            config,
+            peers: responder_ids
+                .into_iter()
+                .map(|responder_id| (responder_id, PeerHealth::default()))
+                .collect(),
+        }
+    }
+
+    /// Record a successful request to a peer, and how long it took.
+    pub fn record_success(&mut self, responder_id: &ResponderId, latency: Duration, now: Instant) {
+        let highest_block_index = self.highest_block_index();
+        let config = &self.config;
+        if let Some(peer) = self.peers.get_mut(responder_id) {
+            peer.latency = Some(match peer.latency {
+                Some(prev) => {
+                    prev.mul_f64(1.0 - config.smoothing) + latency.mul_f64(config.smoothing)
+                }
+                None => latency,
+            });
+            peer.error_rate *= 1.0 - config.smoothing;
+            peer.consecutive_failures = 0;
+
+            // A peer which is healthy again once its quarantine ends starts
+            // over with the shortest quarantine.
+            let is_behind = peer.block_index.map_or(false, |block_index| {
+                block_index + config.max_blocks_behind < highest_block_index
+            });
+            if !peer.is_quarantined(now) && !is_behind {
+                peer.num_quarantines = 0;
+            }
+        }
+    }
+
+    /// Record a failed request to a peer.
+    pub fn record_failure(&mut self, responder_id: &ResponderId, now: Instant) {
+        let config = &self.config;
+        if let Some(peer) = self.peers.get_mut(responder_id) {
+            peer.error_rate = peer.error_rate * (1.0 - config.smoothing) + config.smoothing;
+            peer.consecutive_failures += 1;
+            if peer.consecutive_failures >= config.max_consecutive_failures
+                && !peer.is_quarantined(now)
+            {
+                peer.quarantine(now, config);
+            }
+        }
+    }
+
+    /// Record the block index a peer reported, and quarantine any peers which
+    /// are too far behind.
+    pub fn record_block_index(
+        &mut self,
+        responder_id: &ResponderId,
+        block_index: BlockIndex,
+        now: Instant,
+    ) {
+        if let Some(peer) = self.peers.get_mut(responder_id) {
+            peer.block_index = Some(block_index);
+        }
+
+        let highest_block_index = self.highest_block_index();
+        let config = &self.config;
+        for peer in self.peers.values_mut() {
+            if let Some(block_index) = peer.block_index {
+                if block_index + config.max_blocks_behind < highest_block_index
+                    && !peer.is_quarantined(now)
+                {
+                    peer.quarantine(now, config);
+                }
+            }
+        }
+    }
+
+    /// Get the current health of every peer.
+    pub fn scores(&self, now: Instant) -> BTreeMap<ResponderId, PeerHealthScore> {
+        let highest_block_index = self.highest_block_index();
+        self.peers
+            .iter()
+            .map(|(responder_id, peer)| {
+                let blocks_behind = peer
+                    .block_index
+                    .map(|block_index| highest_block_index.saturating_sub(block_index))
+                    .unwrap_or(0);
+                let quarantine_remaining = peer
+                    .quarantined_until
+                    .filter(|until| *until > now)
+                    .map(|until| until - now);
+                let score = if quarantine_remaining.is_some() {
+                    0.0
+                } else {
+                    let latency_factor = peer
+                        .latency
+                        .map(|latency| {
+                            1.0 / (1.0
+                                + latency.as_secs_f64()
+                                    / self.config.reference_latency.as_secs_f64())
+                        })
+                        .unwrap_or(1.0);
+                    (1.0 - peer.error_rate) * latency_factor / (1.0 + blocks_behind as f64)
+                };
+                let score = PeerHealthScore {
+                    latency: peer.latency,
+                    error_rate: peer.error_rate,
+                    block_index: peer.block_index,
+                    blocks_behind,
+                    quarantine_remaining,
+                    score,
+                };
+                (responder_id.clone(), score)
+            })
+            .collect()
+    }
+
+    /// Get the responder ids of all peers, in the order in which requests
+    /// should be attempted.
+    ///
+    /// Peers which are not quarantined come first, best score first. The peers
+    /// scoring within `load_balance_ratio` of the best score are rotated by
+    /// `offset`, so that they share the load. Quarantined peers come last, as
+    /// a last resort, those closest to leaving quarantine first.
+    pub fn ranked_responder_ids(&self, offset: usize, now: Instant) -> Vec<ResponderId> {
+        let scores = self.scores(now);
+        let (mut quarantined, mut available): (Vec<_>, Vec<_>) = scores
+            .into_iter()
+            .partition(|(_, score)| score.is_quarantined());
+
+        available.sort_by(|(_, a), (_, b)| b.score.total_cmp(&a.score));
+        quarantined.sort_by_key(|(_, score)| score.quarantine_remaining);
+
+        if let Some((_, best)) = available.first() {
+            let threshold = best.score * self.config.load_balance_ratio;
+            let num_balanced = available
+                .iter()
+                .take_while(|(_, score)| score.score >= threshold)
+                .count();
+            available[..num_balanced].rotate_left(offset % num_balanced);
+        }
+
+        available
+            .into_iter()
+            .chain(quarantined)
+            .map(|(responder_id, _)| responder_id)
+            .collect()
+    }
+
+    fn highest_block_index(&self) -> BlockIndex {
+        self.peers
+            .values()
+            .filter_map(|peer| peer.block_index)
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn responder_ids() -> Vec<ResponderId> {
+        ["a:443", "b:443", "c:443"]
+            .iter()
+            .map(|s| ResponderId::from_str(s).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn slow_peers_rank_last() {
+        let ids = responder_ids();
+        let mut tracker = PeerHealthTracker::new(ids.clone(), PeerHealthConfig::default());
+        let now = Instant::now();
+
+        tracker.record_success(&ids[0], Duration::from_millis(2000), now);
+        tracker.record_success(&ids[1], Duration::from_millis(10), now);
+        tracker.record_success(&ids[2], Duration::from_millis(20), now);
+
+        for offset in 0..4 {
+            let ranked = tracker.ranked_responder_ids(offset, now);
+            assert_eq!(ranked.len(), 3);
+            assert_eq!(ranked[2], ids[0]);
+        }
+        // The two fast peers share the load.
+        assert_ne!(
+            tracker.ranked_responder_ids(0, now)[0],
+            tracker.ranked_responder_ids(1, now)[0]
+        );
+    }
+
+    #[test]
+    fn failing_peers_are_quarantined_with_backoff() {
+        let ids = responder_ids();
+        let config = PeerHealthConfig::default();
+        let mut tracker = PeerHealthTracker::new(ids.clone(), config.clone());
+        let now = Instant::now();
+
+        for _ in 0..config.max_consecutive_failures {
+            tracker.record_failure(&ids[1], now);
+        }
+        let scores = tracker.scores(now);
+        assert_eq!(
+            scores[&ids[1]].quarantine_remaining,
+            Some(config.base_quarantine)
+        );
+        assert_eq!(scores[&ids[1]].score, 0.0);
+        assert_eq!(tracker.ranked_responder_ids(0, now)[2], ids[1]);
+
+        // Failing again after the quarantine ends doubles the quarantine.
+        let later = now + config.base_quarantine;
+        assert!(!tracker.scores(later)[&ids[1]].is_quarantined());
+        tracker.record_failure(&ids[1], later);
+        assert_eq!(
+            tracker.scores(later)[&ids[1]].quarantine_remaining,
+            Some(config.base_quarantine * 2)
+        );
+
+        // Succeeding after the quarantine ends resets the backoff.
+        let even_later = later + config.base_quarantine * 2;
+        tracker.record_success(&ids[1], Duration::from_millis(10), even_later);
+        for _ in 0..config.max_consecutive_failures {
+            tracker.record_failure(&ids[1], even_later);
+        }
+        assert_eq!(
+            tracker.scores(even_later)[&ids[1]].quarantine_remaining,
+            Some(config.base_quarantine)
+        );
+    }
+
+    #[test]
+    fn peers_behind_are_quarantined() {
+        let ids = responder_ids();
+        let config = PeerHealthConfig::default();
+        let mut tracker = PeerHealthTracker::new(ids.clone(), config.clone());
+        let now = Instant::now();
+
+        tracker.record_block_index(&ids[0], 100, now);
+        tracker.record_block_index(&ids[1], 100 - config.max_blocks_behind, now);
+        tracker.record_block_index(&ids[2], 99 - config.max_blocks_behind, now);
+
+        let scores = tracker.scores(now);
+        assert!(!scores[&ids[0]].is_quarantined());
+        assert!(!scores[&ids[1]].is_quarantined());
+        assert!(scores[&ids[2]].is_quarantined());
+        assert_eq!(scores[&ids[1]].blocks_behind, config.max_blocks_behind);
+        assert!(scores[&ids[0]].score > scores[&ids[1]].score);
+
+        assert_eq!(
+            tracker.ranked_responder_ids(0, now),
+            vec![ids[0].clone(), ids[1].clone(), ids[2].clone()]
+        );
+    }
+}
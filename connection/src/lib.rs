@@ -4,6 +4,7 @@
 
 mod credentials;
 mod error;
+mod health;
 mod manager;
 mod sync;
 mod thick;
@@ -15,12 +16,14 @@ pub use crate::{
         CredentialsProviderError, HardcodedCredentialsProvider, TokenBasicCredentialsProvider,
     },
     error::{Error, Result, RetryError, RetryResult},
+    health::{PeerHealthConfig, PeerHealthScore, PeerHealthTracker},
     manager::ConnectionManager,
     sync::SyncConnection,
     thick::{ThickClient, ThickClientAttestationError},
     traits::{
         AttestationError, AttestedConnection, BlockInfo, BlockchainConnection, Connection,
-        RetryableBlockchainConnection, RetryableUserTxConnection, UserTxConnection,
+        PoolStats, PriorityStats, RetryableBlockchainConnection, RetryableUserTxConnection,
+        UserTxConnection,
     },
 };
 
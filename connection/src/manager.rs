@@ -2,15 +2,25 @@
 
 //! Common connection manager implementation
 
-use crate::{sync::SyncConnection, traits::Connection};
+use crate::{
+    health::{PeerHealthConfig, PeerHealthScore, PeerHealthTracker},
+    sync::SyncConnection,
+    traits::Connection,
+};
+use mc_blockchain_types::BlockIndex;
 use mc_common::{
     logger::{o, Logger},
     ResponderId,
 };
+use mc_crypto_rand::{McRng, RngCore};
 use mc_util_uri::ConnectionUri;
 use std::{
     collections::BTreeMap,
-    sync::{Arc, RwLock, RwLockReadGuard},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex, MutexGuard, RwLock, RwLockReadGuard,
+    },
+    time::{Duration, Instant},
 };
 
 struct ConnectionManagerInner<C: Connection> {
@@ -18,15 +28,25 @@ struct ConnectionManagerInner<C: Connection> {
     id_to_conn: BTreeMap<ResponderId, SyncConnection<C>>,
 }
 
-/// A connection manager manages a list of peers it is connected to.
+/// A connection manager manages a list of peers it is connected to, and
+/// tracks their health so that requests can be sent to the healthiest peers.
 pub struct ConnectionManager<C: Connection> {
     inner: Arc<RwLock<ConnectionManagerInner<C>>>,
+
+    /// Health of each peer.
+    health: Arc<Mutex<PeerHealthTracker>>,
+
+    /// Incremented by each call to ranked_responder_ids, to spread requests
+    /// over the healthiest peers.
+    next_offset: Arc<AtomicUsize>,
 }
 
 impl<C: Connection> Clone for ConnectionManager<C> {
     fn clone(&self) -> Self {
         Self {
             inner: self.inner.clone(),
+            health: self.health.clone(),
+            next_offset: self.next_offset.clone(),
         }
     }
 }
@@ -34,28 +54,41 @@ impl<C: Connection> Clone for ConnectionManager<C> {
 /// A collection of connections
 impl<C: Connection> ConnectionManager<C> {
     pub fn new(conns: Vec<C>, logger: Logger) -> Self {
+        Self::new_with_health_config(conns, PeerHealthConfig::default(), logger)
+    }
+
+    pub fn new_with_health_config(
+        conns: Vec<C>,
+        health_config: PeerHealthConfig,
+        logger: Logger,
+    ) -> Self {
+        let id_to_conn: BTreeMap<ResponderId, SyncConnection<C>> = conns
+            .into_iter()
+            .map(|conn| {
+                let name = conn.to_string();
+                let responder_id = conn
+                    .uri()
+                    .host_and_port_responder_id()
+                    .unwrap_or_else(|err| {
+                        panic!(
+                            "Could not create responder_id from {:?}: {}",
+                            conn.uri().to_string(),
+                            err
+                        )
+                    });
+                let sync_conn =
+                    SyncConnection::new(conn, logger.new(o!("mc.peers.peer_name" => name)));
+                (responder_id, sync_conn)
+            })
+            .collect();
+        let health = PeerHealthTracker::new(id_to_conn.keys().cloned(), health_config);
+
         Self {
-            inner: Arc::new(RwLock::new(ConnectionManagerInner {
-                id_to_conn: conns
-                    .into_iter()
-                    .map(|conn| {
-                        let name = conn.to_string();
-                        let responder_id =
-                            conn.uri()
-                                .host_and_port_responder_id()
-                                .unwrap_or_else(|err| {
-                                    panic!(
-                                        "Could not create responder_id from {:?}: {}",
-                                        conn.uri().to_string(),
-                                        err
-                                    )
-                                });
-                        let sync_conn =
-                            SyncConnection::new(conn, logger.new(o!("mc.peers.peer_name" => name)));
-                        (responder_id, sync_conn)
-                    })
-                    .collect(),
-            })),
+            inner: Arc::new(RwLock::new(ConnectionManagerInner { id_to_conn })),
+            health: Arc::new(Mutex::new(health)),
+            // Start at a random offset, so that separate processes don't all
+            // favor the same peer.
+            next_offset: Arc::new(AtomicUsize::new(McRng::default().next_u64() as usize)),
         }
     }
 
@@ -63,6 +96,12 @@ impl<C: Connection> ConnectionManager<C> {
         self.inner.read().expect("ConnectionManager lock poisoned")
     }
 
+    fn health(&self) -> MutexGuard<PeerHealthTracker> {
+        self.health
+            .lock()
+            .expect("ConnectionManager health lock poisoned")
+    }
+
     /// Retrieve a vector of all the connection URLs owned by this manager.
     pub fn responder_ids(&self) -> Vec<ResponderId> {
         self.read().id_to_conn.keys().cloned().collect()
@@ -92,4 +131,34 @@ impl<C: Connection> ConnectionManager<C> {
     pub fn is_empty(&self) -> bool {
         self.read().id_to_conn.is_empty()
     }
+
+    /// Record a successful request to a peer, and how long it took.
+    pub fn record_success(&self, responder_id: &ResponderId, latency: Duration) {
+        self.health()
+            .record_success(responder_id, latency, Instant::now());
+    }
+
+    /// Record a failed request to a peer.
+    pub fn record_failure(&self, responder_id: &ResponderId) {
+        self.health().record_failure(responder_id, Instant::now());
+    }
+
+    /// Record the block index reported by a peer.
+    pub fn record_block_index(&self, responder_id: &ResponderId, block_index: BlockIndex) {
+        self.health()
+            .record_block_index(responder_id, block_index, Instant::now());
+    }
+
+    /// Retrieve the current health of every peer.
+    pub fn health_scores(&self) -> BTreeMap<ResponderId, PeerHealthScore> {
+        self.health().scores(Instant::now())
+    }
+
+    /// Retrieve the responder ids of all peers, in the order in which a
+    /// request should be attempted: healthy peers first, with load spread
+    /// across the healthiest of them, and quarantined peers last.
+    pub fn ranked_responder_ids(&self) -> Vec<ResponderId> {
+        let offset = self.next_offset.fetch_add(1, Ordering::SeqCst);
+        self.health().ranked_responder_ids(offset, Instant::now())
+    }
 }
@@ -5,7 +5,7 @@
 use crate::{
     error::RetryResult,
     traits::{
-        BlockInfo, BlockchainConnection, Connection, RetryableBlockchainConnection,
+        BlockInfo, BlockchainConnection, Connection, PoolStats, RetryableBlockchainConnection,
         RetryableUserTxConnection, UserTxConnection,
     },
 };
@@ -237,4 +237,11 @@ impl<UTC: UserTxConnection> RetryableUserTxConnection for SyncConnection<UTC> {
     ) -> RetryResult<BlockIndex> {
         impl_sync_connection_retry!(self.write(), self.logger, propose_tx, retry_iterator, tx)
     }
+
+    fn fetch_pool_stats(
+        &self,
+        retry_iterator: impl IntoIterator<Item = Duration>,
+    ) -> RetryResult<PoolStats> {
+        impl_sync_connection_retry!(self.write(), self.logger, fetch_pool_stats, retry_iterator)
+    }
 }
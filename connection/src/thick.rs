@@ -8,7 +8,7 @@ use crate::{
     error::{Error, Result},
     traits::{
         AttestationError, AttestedConnection, BlockInfo, BlockchainConnection, Connection,
-        UserTxConnection,
+        PoolStats, UserTxConnection,
     },
 };
 use aes_gcm::Aes256Gcm;
@@ -39,8 +39,11 @@ use mc_crypto_keys::X25519;
 use mc_crypto_noise::CipherError;
 use mc_crypto_rand::McRng;
 use mc_transaction_core::tx::Tx;
-use mc_util_grpc::{ConnectionUriGrpcioChannel, GrpcCookieStore, CHAIN_ID_GRPC_HEADER};
+use mc_util_grpc::{
+    inject_trace_context, ConnectionUriGrpcioChannel, GrpcCookieStore, CHAIN_ID_GRPC_HEADER,
+};
 use mc_util_serial::encode;
+use mc_util_telemetry::{tracer, TraceContextExt, Tracer, TELEMETRY_TX_HASH_KEY};
 use mc_util_uri::{ConnectionUri, ConsensusClientUri as ClientUri, UriConversionError};
 use secrecy::{ExposeSecret, SecretVec};
 use sha2::Sha512;
@@ -264,9 +267,43 @@ impl<CP: CredentialsProvider> ThickClient<CP> {
                 .expect("Error setting chain-id header");
         }
 
+        // Propagate the current trace context, if any
+        inject_trace_context(&mut metadata_builder);
+
         Ok(CallOption::default().headers(metadata_builder.build()))
     }
 
+    fn propose_tx_impl(&mut self, tx: &Tx) -> Result<u64> {
+        if !self.is_attested() {
+            let _verification_report = self.attest()?;
+        }
+
+        let enclave_connection = self
+            .enclave_connection
+            .as_mut()
+            .expect("no enclave_connection even though attest succeeded");
+
+        let mut msg = Message::new();
+        msg.set_channel_id(Vec::from(enclave_connection.binding()));
+
+        // Don't leave the plaintext serialization floating around
+        let tx_plaintext = SecretVec::new(encode(tx));
+        let tx_ciphertext =
+            enclave_connection.encrypt(&[], tx_plaintext.expose_secret().as_ref())?;
+        msg.set_data(tx_ciphertext);
+
+        let resp = self.authenticated_attested_call(|this, call_option| {
+            this.consensus_client_api_client
+                .client_tx_propose_async_opt(&msg, call_option)
+        })?;
+
+        if resp.get_result() == ProposeTxResult::Ok {
+            Ok(resp.get_block_count())
+        } else {
+            Err(resp.get_result().into())
+        }
+    }
+
     fn handle_rpc_error(&mut self, err: &(impl AuthenticationError + AttestationError)) {
         // If the call failed due to authentication (credentials) error, reset creds so
         // that it gets re-created on the next call.
@@ -403,34 +440,24 @@ impl<CP: CredentialsProvider> UserTxConnection for ThickClient<CP> {
     fn propose_tx(&mut self, tx: &Tx) -> Result<u64> {
         trace_time!(self.logger, "ThickClient::propose_tx");
 
-        if !self.is_attested() {
-            let _verification_report = self.attest()?;
-        }
-
-        let enclave_connection = self
-            .enclave_connection
-            .as_mut()
-            .expect("no enclave_connection even though attest succeeded");
-
-        let mut msg = Message::new();
-        msg.set_channel_id(Vec::from(enclave_connection.binding()));
+        // The span is propagated to the consensus node in the request headers,
+        // see `call_option`.
+        tracer!().in_span("propose_tx", |cx| {
+            cx.span()
+                .set_attribute(TELEMETRY_TX_HASH_KEY.string(tx.tx_hash().to_string()));
+            self.propose_tx_impl(tx)
+        })
+    }
 
-        // Don't leave the plaintext serialization floating around
-        let tx_plaintext = SecretVec::new(encode(tx));
-        let tx_ciphertext =
-            enclave_connection.encrypt(&[], tx_plaintext.expose_secret().as_ref())?;
-        msg.set_data(tx_ciphertext);
+    fn fetch_pool_stats(&mut self) -> Result<PoolStats> {
+        trace_time!(self.logger, "ThickClient::fetch_pool_stats");
 
-        let resp = self.authenticated_attested_call(|this, call_option| {
+        let pool_stats = self.authenticated_attested_call(|this, call_option| {
             this.consensus_client_api_client
-                .client_tx_propose_async_opt(&msg, call_option)
+                .get_pending_pool_stats_async_opt(&Empty::new(), call_option)
         })?;
 
-        if resp.get_result() == ProposeTxResult::Ok {
-            Ok(resp.get_block_count())
-        } else {
-            Err(resp.get_result().into())
-        }
+        Ok(pool_stats.into())
     }
 }
 
@@ -6,7 +6,10 @@ use crate::error::{Result, RetryResult};
 use grpcio::Error as GrpcError;
 use mc_attest_core::VerificationReport;
 use mc_blockchain_types::{Block, BlockID, BlockIndex};
-use mc_consensus_api::consensus_common::LastBlockInfoResponse;
+use mc_consensus_api::{
+    consensus_client::{self, PendingPoolStatsResponse},
+    consensus_common::LastBlockInfoResponse,
+};
 use mc_transaction_core::{tokens::Mob, tx::Tx, Token, TokenId};
 use mc_util_uri::ConnectionUri;
 use std::{
@@ -135,6 +138,81 @@ impl From<BlockInfo> for LastBlockInfoResponse {
     }
 }
 
+/// Statistics of the priorities of a set of pending transactions.
+///
+/// Priorities are normalized across tokens: a transaction paying exactly the
+/// minimum fee of its token has priority 128.
+#[derive(Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct PriorityStats {
+    /// The number of transactions
+    pub num_txs: u64,
+
+    /// The median priority
+    pub p50: u64,
+
+    /// The 90th percentile priority
+    pub p90: u64,
+
+    /// The highest priority
+    pub max: u64,
+}
+
+impl From<&consensus_client::PriorityStats> for PriorityStats {
+    fn from(src: &consensus_client::PriorityStats) -> Self {
+        PriorityStats {
+            num_txs: src.num_txs,
+            p50: src.p50,
+            p90: src.p90,
+            max: src.max,
+        }
+    }
+}
+
+/// A structure meant to contain the results of a GetPendingPoolStats response
+#[derive(Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct PoolStats {
+    /// The number of blocks in the node's ledger
+    pub block_count: u64,
+
+    /// The number of values waiting to be nominated by the node
+    pub num_pending_values: u64,
+
+    /// The maximum number of transactions in a block
+    pub max_transactions_per_block: u64,
+
+    /// The priorities of all pending transactions
+    pub priorities: PriorityStats,
+
+    /// The priorities of the pending transactions, by fee token id
+    pub token_priorities: BTreeMap<TokenId, PriorityStats>,
+}
+
+impl Display for PoolStats {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(
+            f,
+            "{} pending transactions at block count {}, with priorities {:?}",
+            self.priorities.num_txs, self.block_count, self.priorities
+        )
+    }
+}
+
+impl From<PendingPoolStatsResponse> for PoolStats {
+    fn from(src: PendingPoolStatsResponse) -> Self {
+        PoolStats {
+            block_count: src.block_count,
+            num_pending_values: src.num_pending_values,
+            max_transactions_per_block: src.max_transactions_per_block,
+            priorities: src.get_priorities().into(),
+            token_priorities: src
+                .get_token_stats()
+                .iter()
+                .map(|stats| (TokenId::from(stats.token_id), stats.get_priorities().into()))
+                .collect(),
+        }
+    }
+}
+
 /// A connection trait providing APIs for use in retrieving blocks from a
 /// consensus node.
 pub trait BlockchainConnection: Connection {
@@ -158,6 +236,10 @@ pub trait UserTxConnection: Connection {
     /// Returns the number of blocks in the ledger at the time the call was
     /// received.
     fn propose_tx(&mut self, tx: &Tx) -> Result<u64>;
+
+    /// Retrieve statistics of the transactions waiting to be included in a
+    /// block, for estimating fees.
+    fn fetch_pool_stats(&mut self) -> Result<PoolStats>;
 }
 
 // Retryable connections: these traits exist to allow SyncConnection to extend
@@ -203,4 +285,11 @@ pub trait RetryableUserTxConnection {
         tx: &Tx,
         retry_iterator: impl IntoIterator<Item = Duration>,
     ) -> RetryResult<BlockIndex>;
+
+    /// Retrieve statistics of the transactions waiting to be included in a
+    /// block, for estimating fees.
+    fn fetch_pool_stats(
+        &self,
+        retry_iterator: impl IntoIterator<Item = Duration>,
+    ) -> RetryResult<PoolStats>;
 }
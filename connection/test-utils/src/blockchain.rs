@@ -4,7 +4,7 @@
 
 use mc_blockchain_types::{Block, BlockID, BlockIndex, BlockVersion};
 use mc_connection::{
-    BlockInfo, BlockchainConnection, Connection, Error as ConnectionError,
+    BlockInfo, BlockchainConnection, Connection, Error as ConnectionError, PoolStats,
     Result as ConnectionResult, UserTxConnection,
 };
 use mc_consensus_enclave_api::FeeMap;
@@ -36,6 +36,13 @@ pub struct MockBlockchainConnection<L: Ledger + Sync> {
 
     /// The fee map for this peer.
     pub fee_map: FeeMap,
+
+    /// The pending pool statistics reported by this peer.
+    pub pool_stats: PoolStats,
+
+    /// If set, proposed transactions are not accepted, and propose_tx fails
+    /// with the error returned by this function.
+    pub propose_tx_error: Option<fn() -> ConnectionError>,
 }
 
 impl<L: Ledger + Sync> MockBlockchainConnection<L> {
@@ -46,6 +53,8 @@ impl<L: Ledger + Sync> MockBlockchainConnection<L> {
             latency_millis,
             proposed_txs: Vec::new(),
             fee_map,
+            pool_stats: PoolStats::default(),
+            propose_tx_error: None,
         }
     }
 }
@@ -130,9 +139,19 @@ impl<L: Ledger + Sync> BlockchainConnection for MockBlockchainConnection<L> {
 
 impl<L: Ledger + Sync> UserTxConnection for MockBlockchainConnection<L> {
     fn propose_tx(&mut self, tx: &Tx) -> ConnectionResult<BlockIndex> {
+        if let Some(propose_tx_error) = self.propose_tx_error {
+            return Err(propose_tx_error());
+        }
         self.proposed_txs.push(tx.clone());
         Ok(self.ledger.num_blocks().unwrap())
     }
+
+    fn fetch_pool_stats(&mut self) -> ConnectionResult<PoolStats> {
+        Ok(PoolStats {
+            block_count: self.ledger.num_blocks().unwrap(),
+            ..self.pool_stats.clone()
+        })
+    }
 }
 
 #[cfg(test)]
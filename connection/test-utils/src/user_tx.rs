@@ -3,7 +3,7 @@
 //! User Transaction Connection Mock
 
 use mc_blockchain_types::BlockIndex;
-use mc_connection::{Connection, Result as ConnectionResult, UserTxConnection};
+use mc_connection::{Connection, PoolStats, Result as ConnectionResult, UserTxConnection};
 use mc_transaction_core::tx::Tx;
 use mc_util_uri::{ConnectionUri, ConsensusClientUri};
 use std::{
@@ -72,4 +72,8 @@ impl UserTxConnection for MockUserTxConnection {
         self.submitted_txs.push(tx.clone());
         Ok(1)
     }
+
+    fn fetch_pool_stats(&mut self) -> ConnectionResult<PoolStats> {
+        Ok(PoolStats::default())
+    }
 }
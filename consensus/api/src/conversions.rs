@@ -66,6 +66,33 @@ impl From<Error> for ProposeTxResult {
             Error::InputRule(InputRuleError::MaxTombstoneBlockExceeded) => {
                 Self::InputRuleMaxTombstoneBlockExceeded
             }
+            Error::InputRule(InputRuleError::PartialFillInputRulesNotAllowed) => {
+                Self::InputRulePartialFillInputRulesNotAllowed
+            }
+            Error::InputRule(InputRuleError::MissingPartialFillChange) => {
+                Self::InputRuleMissingPartialFillChange
+            }
+            Error::InputRule(InputRuleError::InvalidRevealedTxOut) => {
+                Self::InputRuleInvalidRevealedTxOut
+            }
+            Error::InputRule(InputRuleError::MissingPartialFillOutput) => {
+                Self::InputRuleMissingPartialFillOutput
+            }
+            Error::InputRule(InputRuleError::PartialFillOutputMismatch) => {
+                Self::InputRulePartialFillOutputMismatch
+            }
+            Error::InputRule(InputRuleError::ExcessivePartialFillChange) => {
+                Self::InputRuleExcessivePartialFillChange
+            }
+            Error::InputRule(InputRuleError::MinPartialFillValueNotMet) => {
+                Self::InputRuleMinPartialFillValueNotMet
+            }
+            Error::InputRule(InputRuleError::InsufficientPartialFillOutput) => {
+                Self::InputRuleInsufficientPartialFillOutput
+            }
+            Error::InputRule(InputRuleError::ZeroPartialFillChange) => {
+                Self::InputRuleZeroPartialFillChange
+            }
         }
     }
 }
@@ -122,6 +149,33 @@ impl TryInto<Error> for ProposeTxResult {
             Self::InputRuleMaxTombstoneBlockExceeded => {
                 Ok(Error::InputRule(InputRuleError::MaxTombstoneBlockExceeded))
             }
+            Self::InputRulePartialFillInputRulesNotAllowed => Ok(Error::InputRule(
+                InputRuleError::PartialFillInputRulesNotAllowed,
+            )),
+            Self::InputRuleMissingPartialFillChange => {
+                Ok(Error::InputRule(InputRuleError::MissingPartialFillChange))
+            }
+            Self::InputRuleInvalidRevealedTxOut => {
+                Ok(Error::InputRule(InputRuleError::InvalidRevealedTxOut))
+            }
+            Self::InputRuleMissingPartialFillOutput => {
+                Ok(Error::InputRule(InputRuleError::MissingPartialFillOutput))
+            }
+            Self::InputRulePartialFillOutputMismatch => {
+                Ok(Error::InputRule(InputRuleError::PartialFillOutputMismatch))
+            }
+            Self::InputRuleExcessivePartialFillChange => {
+                Ok(Error::InputRule(InputRuleError::ExcessivePartialFillChange))
+            }
+            Self::InputRuleMinPartialFillValueNotMet => {
+                Ok(Error::InputRule(InputRuleError::MinPartialFillValueNotMet))
+            }
+            Self::InputRuleInsufficientPartialFillOutput => Ok(Error::InputRule(
+                InputRuleError::InsufficientPartialFillOutput,
+            )),
+            Self::InputRuleZeroPartialFillChange => {
+                Ok(Error::InputRule(InputRuleError::ZeroPartialFillChange))
+            }
         }
     }
 }
@@ -63,6 +63,9 @@ pub struct WellFormedTxContext {
     /// Priority assigned to this tx, based on the fee.
     priority: u64,
 
+    /// The token id the fee is paid in.
+    fee_token_id: TokenId,
+
     /// Tx hash.
     tx_hash: TxHash,
 
@@ -83,6 +86,7 @@ impl WellFormedTxContext {
     /// Create a new WellFormedTxContext.
     pub fn new(
         priority: u64,
+        fee_token_id: TokenId,
         tx_hash: TxHash,
         tombstone_block: u64,
         key_images: Vec<KeyImage>,
@@ -91,6 +95,7 @@ impl WellFormedTxContext {
     ) -> Self {
         Self {
             priority,
+            fee_token_id,
             tx_hash,
             tombstone_block,
             key_images,
@@ -103,6 +108,7 @@ impl WellFormedTxContext {
     pub fn from_tx(tx: &Tx, priority: u64) -> Self {
         Self {
             priority,
+            fee_token_id: TokenId::from(tx.prefix.fee_token_id),
             tx_hash: tx.tx_hash(),
             tombstone_block: tx.prefix.tombstone_block,
             key_images: tx.key_images(),
@@ -121,6 +127,11 @@ impl WellFormedTxContext {
         self.priority
     }
 
+    /// Get the token id of the fee
+    pub fn fee_token_id(&self) -> TokenId {
+        self.fee_token_id
+    }
+
     /// Get the tombstone block
     pub fn tombstone_block(&self) -> u64 {
         self.tombstone_block
@@ -156,6 +167,7 @@ impl Ord for WellFormedTxContext {
             // Sort by remaining fields in lexicographic order.
             (
                 &self.tx_hash,
+                &self.fee_token_id,
                 &self.tombstone_block,
                 &self.key_images,
                 &self.highest_indices,
@@ -163,6 +175,7 @@ impl Ord for WellFormedTxContext {
             )
                 .cmp(&(
                     &other.tx_hash,
+                    &other.fee_token_id,
                     &other.tombstone_block,
                     &other.key_images,
                     &other.highest_indices,
@@ -186,9 +199,33 @@ mod well_formed_tx_context_tests {
     #[test]
     /// WellFormedTxContext should be sorted by priority, descending.
     fn test_ordering() {
-        let a = WellFormedTxContext::new(100, Default::default(), 0, vec![], vec![], vec![]);
-        let b = WellFormedTxContext::new(557, Default::default(), 0, vec![], vec![], vec![]);
-        let c = WellFormedTxContext::new(88, Default::default(), 0, vec![], vec![], vec![]);
+        let a = WellFormedTxContext::new(
+            100,
+            Default::default(),
+            Default::default(),
+            0,
+            vec![],
+            vec![],
+            vec![],
+        );
+        let b = WellFormedTxContext::new(
+            557,
+            Default::default(),
+            Default::default(),
+            0,
+            vec![],
+            vec![],
+            vec![],
+        );
+        let c = WellFormedTxContext::new(
+            88,
+            Default::default(),
+            Default::default(),
+            0,
+            vec![],
+            vec![],
+            vec![],
+        );
 
         let mut contexts = vec![a, b, c];
         contexts.sort();
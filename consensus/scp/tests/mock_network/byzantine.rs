@@ -0,0 +1,679 @@
+// Copyright (c) 2018-2022 The MobileCoin Foundation
+
+//! Fault injection for consensus networks.
+//!
+//! Unlike the thread-based simulation in the parent module, these networks run
+//! on a single thread: each step delivers one in-flight message, picked by a
+//! seeded rng. Nodes process timeouts whenever no message is in flight.
+//!
+//! Faulty nodes run the protocol honestly, but are wrapped in a `FaultyNode`
+//! which tampers with the messages they send. The scenario runner checks after
+//! every externalization that no two honest nodes externalized different
+//! values for the same slot, and reports whether every honest node
+//! externalized every submitted value in time.
+
+use crate::mock_network::{NetworkConfig, NodeConfig, TestOptions, CHARACTERS_PER_VALUE};
+use mc_common::{
+    logger::{log, Logger},
+    NodeID,
+};
+use mc_consensus_scp::{
+    ballot::Ballot,
+    msg::{CommitPayload, ExternalizePayload, Msg, NominatePayload, PreparePayload, Topic},
+    slot::SlotMetrics,
+    test_utils::TransactionValidationError,
+    Node, QuorumSet, ScpNode, SlotIndex,
+};
+use mc_util_test_helper::{Rng, RngType, SeedableRng, SliceRandom};
+use std::{
+    collections::{BTreeSet, HashMap, HashSet},
+    ops::Range,
+    thread,
+    time::{Duration, Instant},
+};
+
+/// An adversarial behaviour of a faulty node.
+#[derive(Clone, Debug)]
+pub enum Fault {
+    /// Sends its messages to half of its peers, and messages voting for a
+    /// forged value instead to the other half.
+    Equivocate,
+
+    /// Sends nothing to the given peers. A node that drops its messages to all
+    /// of its peers is silent.
+    SelectiveDrop(HashSet<NodeID>),
+
+    /// Along with each message, re-sends a message it sent or received
+    /// earlier, picked at random.
+    StaleReplay,
+}
+
+/// Splits the network into groups of nodes that cannot reach each other until
+/// the partition heals.
+#[derive(Clone, Debug)]
+pub struct Partition {
+    /// The groups. Nodes that are in no group can only reach each other.
+    pub groups: Vec<HashSet<NodeID>>,
+
+    /// How long after the start of a run the partition heals. Messages sent
+    /// across groups before then are held back, and delivered once it heals.
+    pub heal_after: Duration,
+}
+
+impl Partition {
+    fn group_of(&self, node_id: &NodeID) -> Option<usize> {
+        self.groups.iter().position(|group| group.contains(node_id))
+    }
+
+    fn separates(&self, a: &NodeID, b: &NodeID) -> bool {
+        self.group_of(a) != self.group_of(b)
+    }
+}
+
+/// A node that applies a `Fault` to the messages sent by the node it wraps.
+pub struct FaultyNode<N: ScpNode<String>> {
+    node: N,
+    fault: Fault,
+
+    /// Messages sent and received by this node, for `Fault::StaleReplay`.
+    history: Vec<Msg<String>>,
+}
+
+impl<N: ScpNode<String>> FaultyNode<N> {
+    pub fn new(node: N, fault: Fault) -> Self {
+        Self {
+            node,
+            fault,
+            history: Vec::new(),
+        }
+    }
+
+    /// The messages to send to each of `peers`, in place of broadcasting
+    /// `msg`.
+    pub fn deliveries(
+        &mut self,
+        msg: Msg<String>,
+        peers: &[NodeID],
+        rng: &mut RngType,
+    ) -> Vec<(NodeID, Msg<String>)> {
+        let deliveries = match &self.fault {
+            Fault::Equivocate => {
+                let forged_msg = forge(&msg);
+                peers
+                    .iter()
+                    .enumerate()
+                    .map(|(i, peer)| {
+                        if i % 2 == 0 {
+                            (peer.clone(), msg.clone())
+                        } else {
+                            (peer.clone(), forged_msg.clone())
+                        }
+                    })
+                    .collect()
+            }
+
+            Fault::SelectiveDrop(dropped_peers) => peers
+                .iter()
+                .filter(|peer| !dropped_peers.contains(peer))
+                .map(|peer| (peer.clone(), msg.clone()))
+                .collect(),
+
+            Fault::StaleReplay => {
+                let stale_msg = self.history.choose(rng).cloned();
+                let mut deliveries = Vec::new();
+                for peer in peers {
+                    deliveries.push((peer.clone(), msg.clone()));
+                    if let Some(stale_msg) = &stale_msg {
+                        if stale_msg.sender_id != *peer {
+                            deliveries.push((peer.clone(), stale_msg.clone()));
+                        }
+                    }
+                }
+                deliveries
+            }
+        };
+
+        self.history.push(msg);
+        deliveries
+    }
+}
+
+impl<N: ScpNode<String>> ScpNode<String> for FaultyNode<N> {
+    fn node_id(&self) -> NodeID {
+        self.node.node_id()
+    }
+
+    fn quorum_set(&self) -> QuorumSet {
+        self.node.quorum_set()
+    }
+
+    fn propose_values(&mut self, values: BTreeSet<String>) -> Result<Option<Msg<String>>, String> {
+        self.node.propose_values(values)
+    }
+
+    fn handle_message(&mut self, msg: &Msg<String>) -> Result<Option<Msg<String>>, String> {
+        self.history.push(msg.clone());
+        self.node.handle_message(msg)
+    }
+
+    fn handle_messages(&mut self, msgs: Vec<Msg<String>>) -> Result<Vec<Msg<String>>, String> {
+        self.history.extend(msgs.iter().cloned());
+        self.node.handle_messages(msgs)
+    }
+
+    fn max_externalized_slots(&self) -> usize {
+        self.node.max_externalized_slots()
+    }
+
+    fn set_max_externalized_slots(&mut self, n: usize) {
+        self.node.set_max_externalized_slots(n)
+    }
+
+    fn get_externalized_values(&self, slot_index: SlotIndex) -> Option<Vec<String>> {
+        self.node.get_externalized_values(slot_index)
+    }
+
+    fn process_timeouts(&mut self) -> Vec<Msg<String>> {
+        self.node.process_timeouts()
+    }
+
+    fn current_slot_index(&self) -> SlotIndex {
+        self.node.current_slot_index()
+    }
+
+    fn get_current_slot_metrics(&mut self) -> SlotMetrics {
+        self.node.get_current_slot_metrics()
+    }
+
+    fn get_slot_debug_snapshot(&mut self, slot_index: SlotIndex) -> Option<String> {
+        self.node.get_slot_debug_snapshot(slot_index)
+    }
+
+    fn reset_slot_index(&mut self, slot_index: SlotIndex) {
+        self.node.reset_slot_index(slot_index)
+    }
+}
+
+/// A copy of `msg` that votes for a forged value wherever `msg` votes for
+/// values.
+fn forge(msg: &Msg<String>) -> Msg<String> {
+    let forged_values = vec![format!("forged-{}", msg.slot_index)];
+
+    let forge_nominate = |payload: &NominatePayload<String>| NominatePayload {
+        X: forge_set(&payload.X, &forged_values),
+        Y: forge_set(&payload.Y, &forged_values),
+    };
+    let forge_prepare = |payload: &PreparePayload<String>| PreparePayload {
+        B: Ballot::new(payload.B.N, &forged_values),
+        P: payload
+            .P
+            .as_ref()
+            .map(|ballot| Ballot::new(ballot.N, &forged_values)),
+        // A forged PP would be compatible with the forged P.
+        PP: None,
+        CN: payload.CN,
+        HN: payload.HN,
+    };
+
+    let topic = match &msg.topic {
+        Topic::Nominate(nominate) => Topic::Nominate(forge_nominate(nominate)),
+        Topic::NominatePrepare(nominate, prepare) => {
+            Topic::NominatePrepare(forge_nominate(nominate), forge_prepare(prepare))
+        }
+        Topic::Prepare(prepare) => Topic::Prepare(forge_prepare(prepare)),
+        Topic::Commit(commit) => Topic::Commit(CommitPayload {
+            B: Ballot::new(commit.B.N, &forged_values),
+            ..commit.clone()
+        }),
+        Topic::Externalize(externalize) => Topic::Externalize(ExternalizePayload {
+            C: Ballot::new(externalize.C.N, &forged_values),
+            HN: externalize.HN,
+        }),
+    };
+
+    Msg::new(
+        msg.sender_id.clone(),
+        msg.quorum_set.clone(),
+        msg.slot_index,
+        topic,
+    )
+}
+
+fn forge_set(values: &BTreeSet<String>, forged_values: &[String]) -> BTreeSet<String> {
+    if values.is_empty() {
+        BTreeSet::new()
+    } else {
+        forged_values.iter().cloned().collect()
+    }
+}
+
+/// A network with faulty nodes.
+#[derive(Clone)]
+pub struct Scenario {
+    /// The network. Nodes without a fault are honest.
+    pub network_config: NetworkConfig,
+
+    /// The fault of each faulty node.
+    pub faults: HashMap<NodeID, Fault>,
+
+    /// An optional partition, in effect at the start of each run.
+    pub partition: Option<Partition>,
+}
+
+impl Scenario {
+    pub fn new(network_config: NetworkConfig) -> Self {
+        Self {
+            network_config,
+            faults: HashMap::default(),
+            partition: None,
+        }
+    }
+
+    pub fn with_fault(mut self, node_id: NodeID, fault: Fault) -> Self {
+        self.faults.insert(node_id, fault);
+        self
+    }
+
+    pub fn with_partition(mut self, partition: Partition) -> Self {
+        self.partition = Some(partition);
+        self
+    }
+
+    /// A name describing the network and its faults, for logging.
+    pub fn name(&self) -> String {
+        let mut name = self.network_config.name.clone();
+        for node_config in self.network_config.nodes.iter() {
+            if let Some(fault) = self.faults.get(&node_config.id) {
+                name.push_str(&format!(" {}:{:?}", node_config.name, fault));
+            }
+        }
+        if self.partition.is_some() {
+            name.push_str(" partitioned");
+        }
+        name
+    }
+}
+
+/// The outcome of one run of a scenario.
+#[derive(Clone, Debug)]
+pub struct RunReport {
+    /// The seed of the run.
+    pub seed: u64,
+
+    /// True if every honest node externalized every submitted value within
+    /// the allowed test time.
+    pub live: bool,
+
+    /// The number of messages delivered.
+    pub messages_delivered: usize,
+
+    /// The number of slots externalized by the slowest honest node.
+    pub slots_externalized: usize,
+
+    /// The duration of the run.
+    pub elapsed: Duration,
+}
+
+/// The outcomes of several seeded runs of a scenario.
+#[derive(Clone, Debug)]
+pub struct LivenessReport {
+    pub runs: Vec<RunReport>,
+}
+
+impl LivenessReport {
+    /// The number of runs in which all values were externalized.
+    pub fn live_runs(&self) -> usize {
+        self.runs.iter().filter(|run| run.live).count()
+    }
+
+    /// The seeds of the runs that did not externalize all values.
+    pub fn stalled_seeds(&self) -> Vec<u64> {
+        self.runs
+            .iter()
+            .filter(|run| !run.live)
+            .map(|run| run.seed)
+            .collect()
+    }
+}
+
+// A node in a fault injection run.
+struct SimulatedNode {
+    name: String,
+    id: NodeID,
+
+    /// The nodes to which this node sends, in network config order.
+    peers: Vec<NodeID>,
+
+    scp_node: SimulatedScpNode,
+    pending_values: Vec<String>,
+    ledger: Vec<Vec<String>>,
+}
+
+enum SimulatedScpNode {
+    Honest(Node<String, TransactionValidationError>),
+    Faulty(FaultyNode<Node<String, TransactionValidationError>>),
+}
+
+impl SimulatedNode {
+    fn new(
+        node_config: &NodeConfig,
+        peers: Vec<NodeID>,
+        fault: Option<Fault>,
+        test_options: &TestOptions,
+        logger: Logger,
+    ) -> Self {
+        let mut node = Node::new(
+            node_config.id.clone(),
+            node_config.quorum_set.clone(),
+            test_options.validity_fn.clone(),
+            test_options.combine_fn.clone(),
+            0, // first slot index
+            logger,
+        );
+        node.scp_timebase = test_options.scp_timebase;
+
+        let scp_node = match fault {
+            Some(fault) => SimulatedScpNode::Faulty(FaultyNode::new(node, fault)),
+            None => SimulatedScpNode::Honest(node),
+        };
+
+        Self {
+            name: node_config.name.clone(),
+            id: node_config.id.clone(),
+            peers,
+            scp_node,
+            pending_values: Vec::new(),
+            ledger: Vec::new(),
+        }
+    }
+
+    fn is_honest(&self) -> bool {
+        matches!(self.scp_node, SimulatedScpNode::Honest(_))
+    }
+
+    fn scp_node(&mut self) -> &mut dyn ScpNode<String> {
+        match &mut self.scp_node {
+            SimulatedScpNode::Honest(node) => node,
+            SimulatedScpNode::Faulty(node) => node,
+        }
+    }
+
+    /// Propose pending values, if any.
+    fn propose(&mut self, max_slot_proposed_values: usize) -> Option<Msg<String>> {
+        if self.pending_values.is_empty() {
+            return None;
+        }
+        let values_to_propose: BTreeSet<String> = self
+            .pending_values
+            .iter()
+            .take(max_slot_proposed_values)
+            .cloned()
+            .collect();
+        self.scp_node()
+            .propose_values(values_to_propose)
+            .expect("propose_values() failed")
+    }
+
+    /// The values of the next slot in this node's ledger, if it was
+    /// externalized.
+    fn poll_externalized(&mut self) -> Option<Vec<String>> {
+        let slot_index = self.ledger.len() as SlotIndex;
+        let values = self.scp_node().get_externalized_values(slot_index)?;
+        self.pending_values.retain(|value| !values.contains(value));
+        self.ledger.push(values.clone());
+        Some(values)
+    }
+
+    /// The messages to send to each peer, in place of broadcasting `msg`.
+    fn deliveries(&mut self, msg: Msg<String>, rng: &mut RngType) -> Vec<(NodeID, Msg<String>)> {
+        match &mut self.scp_node {
+            SimulatedScpNode::Honest(_) => self
+                .peers
+                .iter()
+                .map(|peer| (peer.clone(), msg.clone()))
+                .collect(),
+            SimulatedScpNode::Faulty(node) => node.deliveries(msg, &self.peers, rng),
+        }
+    }
+
+    fn has_externalized_all(&self, values: &HashSet<String>) -> bool {
+        let externalized_values: HashSet<&String> = self.ledger.iter().flatten().collect();
+        values
+            .iter()
+            .all(|value| externalized_values.contains(value))
+    }
+}
+
+// A single-threaded fault injection run.
+struct Simulation {
+    nodes: Vec<SimulatedNode>,
+    node_indices: HashMap<NodeID, usize>,
+    partition: Option<Partition>,
+    rng: RngType,
+
+    /// Messages that can be delivered, with the index of their recipient.
+    in_flight: Vec<(usize, Msg<String>)>,
+
+    /// Messages held back by the partition, with the index of their recipient.
+    held_back: Vec<(usize, Msg<String>)>,
+
+    /// The values externalized by honest nodes for each slot, with the name of
+    /// the first node that externalized them.
+    agreed_values: HashMap<SlotIndex, (String, Vec<String>)>,
+}
+
+impl Simulation {
+    fn send(&mut self, from: usize, msg: Msg<String>) {
+        let deliveries = self.nodes[from].deliveries(msg, &mut self.rng);
+        for (to_id, msg) in deliveries {
+            let to = *self
+                .node_indices
+                .get(&to_id)
+                .expect("could not find peer in node_indices");
+            match &self.partition {
+                Some(partition) if partition.separates(&self.nodes[from].id, &to_id) => {
+                    self.held_back.push((to, msg))
+                }
+                _ => self.in_flight.push((to, msg)),
+            }
+        }
+    }
+
+    fn heal_partition(&mut self) {
+        self.partition = None;
+        self.in_flight.append(&mut self.held_back);
+    }
+
+    /// Record newly externalized values, checking that honest nodes agree, and
+    /// propose pending values for the next slot.
+    fn poll_externalized(&mut self, node_index: usize, max_slot_proposed_values: usize) {
+        while let Some(values) = self.nodes[node_index].poll_externalized() {
+            let node = &self.nodes[node_index];
+            let slot_index = node.ledger.len() as SlotIndex - 1;
+            if node.is_honest() {
+                match self.agreed_values.get(&slot_index) {
+                    Some((other_name, other_values)) => assert!(
+                        *other_values == values,
+                        "safety violated in slot {}: {} externalized {:?}, but {} externalized {:?}",
+                        slot_index,
+                        other_name,
+                        other_values,
+                        node.name,
+                        values,
+                    ),
+                    None => {
+                        self.agreed_values
+                            .insert(slot_index, (node.name.clone(), values));
+                    }
+                }
+            }
+
+            if let Some(msg) = self.nodes[node_index].propose(max_slot_proposed_values) {
+                self.send(node_index, msg);
+            }
+        }
+    }
+}
+
+/// Runs a scenario once, with the given seed.
+///
+/// Panics if two honest nodes externalize different values for the same slot.
+pub fn run_scenario(
+    scenario: &Scenario,
+    test_options: &TestOptions,
+    seed: u64,
+    logger: Logger,
+) -> RunReport {
+    let start = Instant::now();
+    let deadline = start + test_options.allowed_test_time;
+    let mut rng = RngType::seed_from_u64(seed);
+
+    let values: Vec<String> = (0..test_options.values_to_submit)
+        .map(|_| mc_util_test_helper::random_str(CHARACTERS_PER_VALUE, &mut rng))
+        .collect();
+    let values_hashset: HashSet<String> = values.iter().cloned().collect();
+
+    let network_config = &scenario.network_config;
+    let nodes: Vec<SimulatedNode> = network_config
+        .nodes
+        .iter()
+        .map(|node_config| {
+            let peers = network_config
+                .nodes
+                .iter()
+                .filter(|other| node_config.peers.contains(&other.id))
+                .map(|other| other.id.clone())
+                .collect();
+            let mut node = SimulatedNode::new(
+                node_config,
+                peers,
+                scenario.faults.get(&node_config.id).cloned(),
+                test_options,
+                logger.clone(),
+            );
+            node.pending_values = values.clone();
+            node
+        })
+        .collect();
+    let node_indices = nodes
+        .iter()
+        .enumerate()
+        .map(|(index, node)| (node.id.clone(), index))
+        .collect();
+
+    let mut simulation = Simulation {
+        nodes,
+        node_indices,
+        partition: scenario.partition.clone(),
+        rng,
+        in_flight: Vec::new(),
+        held_back: Vec::new(),
+        agreed_values: HashMap::default(),
+    };
+
+    for node_index in 0..simulation.nodes.len() {
+        if let Some(msg) =
+            simulation.nodes[node_index].propose(test_options.max_slot_proposed_values)
+        {
+            simulation.send(node_index, msg);
+        }
+    }
+
+    let mut messages_delivered = 0;
+    let is_done = |simulation: &Simulation| {
+        simulation
+            .nodes
+            .iter()
+            .filter(|node| node.is_honest())
+            .all(|node| node.has_externalized_all(&values_hashset))
+    };
+
+    while !is_done(&simulation) && Instant::now() < deadline {
+        if let Some(partition) = &simulation.partition {
+            if start.elapsed() >= partition.heal_after {
+                log::trace!(logger, "( testing ) partition healed");
+                simulation.heal_partition();
+            }
+        }
+
+        if simulation.in_flight.is_empty() {
+            // Nothing to deliver, so give timers a chance to fire.
+            thread::sleep(Duration::from_millis(1));
+            for node_index in 0..simulation.nodes.len() {
+                for msg in simulation.nodes[node_index].scp_node().process_timeouts() {
+                    simulation.send(node_index, msg);
+                }
+                simulation.poll_externalized(node_index, test_options.max_slot_proposed_values);
+            }
+        } else {
+            let i = simulation.rng.gen_range(0..simulation.in_flight.len());
+            let (node_index, msg) = simulation.in_flight.swap_remove(i);
+            messages_delivered += 1;
+
+            let outgoing_msg = simulation.nodes[node_index]
+                .scp_node()
+                .handle_message(&msg)
+                .expect("handle_message() failed");
+            if let Some(outgoing_msg) = outgoing_msg {
+                simulation.send(node_index, outgoing_msg);
+            }
+            simulation.poll_externalized(node_index, test_options.max_slot_proposed_values);
+        }
+    }
+
+    RunReport {
+        seed,
+        live: is_done(&simulation),
+        messages_delivered,
+        slots_externalized: simulation
+            .nodes
+            .iter()
+            .filter(|node| node.is_honest())
+            .map(|node| node.ledger.len())
+            .min()
+            .unwrap_or(0),
+        elapsed: start.elapsed(),
+    }
+}
+
+/// Runs a scenario once for each seed, and logs how many runs were live.
+///
+/// Panics if two honest nodes externalize different values for the same slot
+/// in any run.
+pub fn run_seeded_scenarios(
+    scenario: &Scenario,
+    test_options: &TestOptions,
+    seeds: Range<u64>,
+    logger: Logger,
+) -> LivenessReport {
+    let name = scenario.name();
+    log::info!(
+        logger,
+        "( testing ) begin fault injection for {} with seeds {:?}",
+        name,
+        seeds,
+    );
+
+    let runs: Vec<RunReport> = seeds
+        .map(|seed| {
+            let run = run_scenario(scenario, test_options, seed, logger.clone());
+            log::debug!(logger, "( testing ) {}: {:?}", name, run);
+            run
+        })
+        .collect();
+    let report = LivenessReport { runs };
+
+    log::info!(
+        logger,
+        "fault injection results for {}: {}/{} runs live, stalled seeds: {:?}",
+        name,
+        report.live_runs(),
+        report.runs.len(),
+        report.stalled_seeds(),
+    );
+
+    // allow log to flush
+    thread::sleep(test_options.log_flush_delay);
+
+    report
+}
@@ -23,6 +23,7 @@ use std::{
     time::{Duration, Instant},
 };
 
+pub mod byzantine;
 pub mod cyclic_topology;
 pub mod mesh_topology;
 pub mod metamesh_topology;
@@ -0,0 +1,156 @@
+// Copyright (c) 2018-2022 The MobileCoin Foundation
+
+mod mock_network;
+
+use mc_common::logger::{test_with_logger, Logger};
+use mc_consensus_scp::test_utils::test_node_id;
+use mock_network::byzantine::{run_seeded_scenarios, Fault, LivenessReport, Partition, Scenario};
+use serial_test::serial;
+use std::{collections::HashSet, time::Duration};
+
+/// Runs a scenario with many seeds. Safety is checked during each run.
+fn byzantine_test_helper(
+    scenario: &Scenario,
+    allowed_run_time: Duration,
+    logger: Logger,
+) -> LivenessReport {
+    let num_seeds = if mock_network::skip_slow_tests() {
+        3
+    } else {
+        20
+    };
+
+    let mut test_options = mock_network::TestOptions::new();
+    test_options.values_to_submit = 20;
+    test_options.scp_timebase = Duration::from_millis(10);
+    test_options.allowed_test_time = allowed_run_time;
+
+    run_seeded_scenarios(scenario, &test_options, 0..num_seeds, logger)
+}
+
+/// Asserts that every run externalized every value.
+fn assert_live(report: &LivenessReport) {
+    assert_eq!(
+        report.live_runs(),
+        report.runs.len(),
+        "runs with seeds {:?} did not externalize all values",
+        report.stalled_seeds(),
+    );
+}
+
+// Each node of a 4k2 mesh needs 2 of its 3 peers, so any 3 nodes form a
+// quorum and the network tolerates one faulty node.
+
+#[test_with_logger]
+#[serial]
+fn honest_mesh_4k2(logger: Logger) {
+    let scenario = Scenario::new(mock_network::mesh_topology::dense_mesh(4, 2));
+    assert_live(&byzantine_test_helper(
+        &scenario,
+        Duration::from_secs(30),
+        logger,
+    ));
+}
+
+#[test_with_logger]
+#[serial]
+fn equivocating_node_mesh_4k2(logger: Logger) {
+    let scenario = Scenario::new(mock_network::mesh_topology::dense_mesh(4, 2))
+        .with_fault(test_node_id(3), Fault::Equivocate);
+    assert_live(&byzantine_test_helper(
+        &scenario,
+        Duration::from_secs(30),
+        logger,
+    ));
+}
+
+#[test_with_logger]
+#[serial]
+fn silent_node_mesh_4k2(logger: Logger) {
+    let all_peers: HashSet<_> = (0..3).map(test_node_id).collect();
+    let scenario = Scenario::new(mock_network::mesh_topology::dense_mesh(4, 2))
+        .with_fault(test_node_id(3), Fault::SelectiveDrop(all_peers));
+    assert_live(&byzantine_test_helper(
+        &scenario,
+        Duration::from_secs(30),
+        logger,
+    ));
+}
+
+#[test_with_logger]
+#[serial]
+fn selective_drop_mesh_4k2(logger: Logger) {
+    let dropped_peers: HashSet<_> = [test_node_id(0)].into_iter().collect();
+    let scenario = Scenario::new(mock_network::mesh_topology::dense_mesh(4, 2))
+        .with_fault(test_node_id(3), Fault::SelectiveDrop(dropped_peers));
+    assert_live(&byzantine_test_helper(
+        &scenario,
+        Duration::from_secs(30),
+        logger,
+    ));
+}
+
+#[test_with_logger]
+#[serial]
+fn stale_replay_mesh_4k2(logger: Logger) {
+    let scenario = Scenario::new(mock_network::mesh_topology::dense_mesh(4, 2))
+        .with_fault(test_node_id(3), Fault::StaleReplay);
+    assert_live(&byzantine_test_helper(
+        &scenario,
+        Duration::from_secs(30),
+        logger,
+    ));
+}
+
+#[test_with_logger]
+#[serial]
+fn partition_then_heal_mesh_4k2(logger: Logger) {
+    // Neither half contains a quorum, so no slot can be externalized before the
+    // partition heals.
+    let scenario =
+        Scenario::new(mock_network::mesh_topology::dense_mesh(4, 2)).with_partition(Partition {
+            groups: vec![
+                [test_node_id(0), test_node_id(1)].into_iter().collect(),
+                [test_node_id(2), test_node_id(3)].into_iter().collect(),
+            ],
+            heal_after: Duration::from_millis(200),
+        });
+    assert_live(&byzantine_test_helper(
+        &scenario,
+        Duration::from_secs(30),
+        logger,
+    ));
+}
+
+#[test_with_logger]
+#[serial]
+fn equivocating_node_during_partition_mesh_5k3(logger: Logger) {
+    let scenario = Scenario::new(mock_network::mesh_topology::dense_mesh(5, 3))
+        .with_fault(test_node_id(4), Fault::Equivocate)
+        .with_partition(Partition {
+            groups: vec![
+                [test_node_id(0), test_node_id(1), test_node_id(4)]
+                    .into_iter()
+                    .collect(),
+                [test_node_id(2), test_node_id(3)].into_iter().collect(),
+            ],
+            heal_after: Duration::from_millis(200),
+        });
+    assert_live(&byzantine_test_helper(
+        &scenario,
+        Duration::from_secs(30),
+        logger,
+    ));
+}
+
+#[test_with_logger]
+#[serial]
+fn two_equivocating_nodes_mesh_5k3(logger: Logger) {
+    // Any two quorums of a 5k3 mesh share three nodes, so two equivocating
+    // nodes cannot break safety. They can prevent progress, so liveness is only
+    // reported.
+    let scenario = Scenario::new(mock_network::mesh_topology::dense_mesh(5, 3))
+        .with_fault(test_node_id(3), Fault::Equivocate)
+        .with_fault(test_node_id(4), Fault::Equivocate);
+    byzantine_test_helper(&scenario, Duration::from_secs(5), logger);
+}
@@ -14,6 +14,7 @@ use mc_attest_core::ProviderId;
 use mc_common::{NodeID, ResponderId};
 use mc_crypto_keys::{DistinguishedEncoding, Ed25519Pair, Ed25519Private};
 use mc_transaction_core::BlockVersion;
+use mc_util_grpc::TokenSigningKeys;
 use mc_util_parse::parse_duration_in_seconds;
 use mc_util_uri::{AdminUri, ConsensusClientUri as ClientUri, ConsensusPeerUri as PeerUri};
 use std::{fmt::Debug, path::PathBuf, str::FromStr, sync::Arc, time::Duration};
@@ -100,10 +101,9 @@ pub struct Config {
     #[clap(long, parse(from_os_str), env = "MC_SEALED_BLOCK_SIGNING_KEY")]
     pub sealed_block_signing_key: PathBuf,
 
-    /// Enables authenticating client requests using Authorization tokens using
-    /// the provided hex-encoded 32 bytes shared secret.
-    #[clap(long, parse(try_from_str = hex::FromHex::from_hex), env = "MC_CLIENT_AUTH_TOKEN_SECRET")]
-    pub client_auth_token_secret: Option<[u8; 32]>,
+    /// Shared secrets for authenticating client requests.
+    #[clap(long, env = "MC_CLIENT_AUTH_TOKEN_SECRET", help = TokenSigningKeys::HELP)]
+    pub client_auth_token_secret: Option<TokenSigningKeys>,
 
     /// Maximal client authentication token lifetime, in seconds (only relevant
     /// when --client-auth-token-secret is used. Defaults to 86400 - 24
@@ -2,11 +2,14 @@
 
 //! Serves blockchain-related API requests.
 
-use grpcio::{RpcContext, RpcStatus, RpcStatusCode, UnarySink};
+use futures::{channel::mpsc, executor::block_on, FutureExt, SinkExt, StreamExt, TryFutureExt};
+use grpcio::{RpcContext, RpcStatus, RpcStatusCode, ServerStreamingSink, UnarySink, WriteFlags};
 use mc_common::logger::{log, Logger};
 use mc_consensus_api::{
     blockchain,
-    consensus_common::{BlocksRequest, BlocksResponse, LastBlockInfoResponse},
+    consensus_common::{
+        BlocksRequest, BlocksResponse, LastBlockInfoResponse, SubscribeBlocksRequest,
+    },
     consensus_common_grpc::BlockchainApi,
     empty::Empty,
 };
@@ -16,7 +19,29 @@ use mc_transaction_core::{tokens::Mob, BlockVersion, Token};
 use mc_util_grpc::{rpc_logger, send_result, Authenticator};
 use mc_util_metrics::{self, SVC_COUNTERS};
 use protobuf::RepeatedField;
-use std::{cmp, collections::HashMap, sync::Arc};
+use std::{
+    cmp,
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+/// How often a block streaming thread that caught up with the ledger checks
+/// whether a new block was appended.
+const SUBSCRIBE_BLOCKS_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// The number of blocks that can be buffered for a subscriber before the
+/// streaming thread blocks waiting for the client to catch up.
+const SUBSCRIBE_BLOCKS_STREAM_BUFFER_SIZE: usize = 64;
+
+/// The maximum number of concurrent block subscriptions. Each subscription is
+/// served by its own thread, so further subscriptions are rejected with
+/// RESOURCE_EXHAUSTED.
+const MAX_BLOCK_SUBSCRIPTIONS: usize = 64;
 
 #[derive(Clone)]
 pub struct BlockchainApiService<L: Ledger + Clone> {
@@ -36,6 +61,13 @@ pub struct BlockchainApiService<L: Ledger + Clone> {
     /// Configured block version
     network_block_version: BlockVersion,
 
+    /// Number of open block subscriptions, shared between clones of the
+    /// service.
+    num_block_subscriptions: Arc<AtomicUsize>,
+
+    /// Maximum number of concurrent block subscriptions.
+    max_block_subscriptions: usize,
+
     /// Logger.
     logger: Logger,
 }
@@ -54,6 +86,8 @@ impl<L: Ledger + Clone> BlockchainApiService<L> {
             max_page_size: 2000,
             fee_map,
             network_block_version,
+            num_block_subscriptions: Arc::new(AtomicUsize::new(0)),
+            max_block_subscriptions: MAX_BLOCK_SUBSCRIPTIONS,
             logger,
         }
     }
@@ -64,6 +98,12 @@ impl<L: Ledger + Clone> BlockchainApiService<L> {
         self.max_page_size = max_page_size;
     }
 
+    // Set the maximum number of concurrent block subscriptions.
+    #[cfg(test)]
+    pub fn set_max_block_subscriptions(&mut self, max_block_subscriptions: usize) {
+        self.max_block_subscriptions = max_block_subscriptions;
+    }
+
     /// Returns information about the last block.
     fn get_last_block_info_helper(&mut self) -> Result<LastBlockInfoResponse, mc_ledger_db::Error> {
         let num_blocks = self.ledger.num_blocks()?;
@@ -126,9 +166,87 @@ impl<L: Ledger + Clone> BlockchainApiService<L> {
         response.set_blocks(RepeatedField::from_vec(blocks));
         Ok(response)
     }
+
+    /// Start a thread that streams blocks, starting at `from_index`, into a
+    /// bounded channel, and return the receiving end of that channel.
+    /// Since the channel is bounded, a slow client causes the thread to block
+    /// rather than buffer an unbounded number of blocks in memory.
+    fn start_block_stream_thread(
+        &self,
+        from_index: u64,
+        logger: &Logger,
+    ) -> Result<mpsc::Receiver<Result<blockchain::ArchiveBlock, RpcStatus>>, RpcStatus> {
+        let subscription = BlockSubscription::try_new(
+            self.num_block_subscriptions.clone(),
+            self.max_block_subscriptions,
+        )
+        .ok_or_else(|| {
+            log::info!(logger, "Too many block subscriptions, rejecting");
+            RpcStatus::with_message(
+                RpcStatusCode::RESOURCE_EXHAUSTED,
+                "Too many block subscriptions".to_string(),
+            )
+        })?;
+
+        let (mut sender, receiver) = mpsc::channel(SUBSCRIBE_BLOCKS_STREAM_BUFFER_SIZE);
+
+        let ledger = self.ledger.clone();
+        let thread_logger = logger.clone();
+
+        thread::Builder::new()
+            .name(format!("subscribe_blocks_{}", from_index))
+            .spawn(move || {
+                // Released when the thread exits.
+                let _subscription = subscription;
+                log::debug!(thread_logger, "Streaming blocks starting at {}", from_index);
+
+                let mut next_index = from_index;
+
+                // Stop once the client goes away.
+                while !sender.is_closed() {
+                    match ledger.get_block_data(next_index) {
+                        Ok(block_data) => {
+                            let item = Ok(blockchain::ArchiveBlock::from(&block_data));
+                            if block_on(sender.send(item)).is_err() {
+                                break;
+                            }
+                            next_index += 1;
+                        }
+
+                        // We caught up with the ledger, wait for the next block to be appended.
+                        Err(mc_ledger_db::Error::NotFound) => {
+                            thread::sleep(SUBSCRIBE_BLOCKS_POLL_INTERVAL);
+                        }
+
+                        Err(err) => {
+                            log::error!(
+                                thread_logger,
+                                "Error getting block data {}: {:?}",
+                                next_index,
+                                err
+                            );
+                            let status = RpcStatus::with_message(
+                                RpcStatusCode::INTERNAL,
+                                format!("Error getting block data {}", next_index),
+                            );
+                            let _ = block_on(sender.send(Err(status)));
+                            break;
+                        }
+                    }
+                }
+
+                log::debug!(thread_logger, "Stopped streaming blocks at {}", next_index);
+            })
+            .map_err(|err| {
+                log::error!(logger, "Failed spawning block stream thread: {}", err);
+                RpcStatus::new(RpcStatusCode::INTERNAL)
+            })?;
+
+        Ok(receiver)
+    }
 }
 
-impl<L: Ledger + Clone> BlockchainApi for BlockchainApiService<L> {
+impl<L: Ledger + Clone + 'static> BlockchainApi for BlockchainApiService<L> {
     /// Gets the last block.
     fn get_last_block_info(
         &mut self,
@@ -177,16 +295,84 @@ impl<L: Ledger + Clone> BlockchainApi for BlockchainApiService<L> {
             send_result(ctx, sink, resp, logger);
         });
     }
+
+    /// Streams blocks starting at `from_index`, including blocks appended to
+    /// the ledger while the stream is open.
+    fn subscribe_blocks(
+        &mut self,
+        ctx: RpcContext,
+        request: SubscribeBlocksRequest,
+        sink: ServerStreamingSink<blockchain::ArchiveBlock>,
+    ) {
+        let _timer = SVC_COUNTERS.req(&ctx);
+        let logger = rpc_logger(&ctx, &self.logger);
+
+        let result = match self.authenticator.authenticate_rpc(&ctx) {
+            Ok(_) => self.start_block_stream_thread(request.from_index, &logger),
+            Err(err) => err.into(),
+        };
+
+        match result {
+            Ok(receiver) => ctx.spawn(
+                forward_blocks(receiver, sink)
+                    .map_err(move |err| log::debug!(logger, "block stream ended: {}", err))
+                    .map(|_| ()),
+            ),
+            Err(status) => ctx.spawn(
+                sink.fail(status)
+                    .map_err(move |err| log::error!(logger, "failed to reply: {}", err))
+                    .map(|_| ()),
+            ),
+        }
+    }
+}
+
+/// A slot in the limited number of concurrent block subscriptions, released
+/// when dropped.
+struct BlockSubscription(Arc<AtomicUsize>);
+
+impl BlockSubscription {
+    /// Take a slot, unless `max` subscriptions are already open.
+    fn try_new(count: Arc<AtomicUsize>, max: usize) -> Option<Self> {
+        count
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |current| {
+                (current < max).then(|| current + 1)
+            })
+            .ok()
+            .map(|_| Self(count))
+    }
+}
+
+impl Drop for BlockSubscription {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Forward blocks from the streaming thread to the client.
+async fn forward_blocks(
+    mut receiver: mpsc::Receiver<Result<blockchain::ArchiveBlock, RpcStatus>>,
+    mut sink: ServerStreamingSink<blockchain::ArchiveBlock>,
+) -> grpcio::Result<()> {
+    while let Some(item) = receiver.next().await {
+        match item {
+            Ok(archive_block) => sink.send((archive_block, WriteFlags::default())).await?,
+            Err(status) => return sink.fail(status).await,
+        }
+    }
+
+    sink.close().await
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use grpcio::{ChannelBuilder, Environment, Error as GrpcError, Server, ServerBuilder};
+    use mc_blockchain_types::BlockData;
     use mc_common::{logger::test_with_logger, time::SystemTimeProvider};
     use mc_consensus_api::consensus_common_grpc::{self, BlockchainApiClient};
-    use mc_ledger_db::test_utils::{create_ledger, initialize_ledger};
-    use mc_transaction_core::TokenId;
+    use mc_ledger_db::test_utils::{add_block_to_ledger, create_ledger, initialize_ledger};
+    use mc_transaction_core::{Amount, TokenId};
     use mc_transaction_core_test_utils::AccountKey;
     use mc_util_grpc::{AnonymousAuthenticator, TokenAuthenticator};
     use rand::{rngs::StdRng, SeedableRng};
@@ -430,4 +616,169 @@ mod tests {
             }
         }
     }
+
+    #[test_with_logger]
+    // `subscribe_blocks` should stream the existing blocks starting at the
+    // requested index, followed by blocks appended while the stream is open.
+    fn test_subscribe_blocks(logger: Logger) {
+        let mut ledger_db = create_ledger();
+        let authenticator = Arc::new(AnonymousAuthenticator::default());
+        let mut rng: StdRng = SeedableRng::from_seed([1u8; 32]);
+        let account_key = AccountKey::random(&mut rng);
+        let blocks_data = initialize_ledger(
+            BlockVersion::MAX,
+            &mut ledger_db,
+            10,
+            &account_key,
+            &mut rng,
+        );
+
+        let blockchain_api_service = BlockchainApiService::new(
+            ledger_db.clone(),
+            authenticator,
+            FeeMap::default(),
+            BlockVersion::MAX,
+            logger,
+        );
+
+        let (client, _server) = get_client_server(blockchain_api_service);
+
+        let mut request = SubscribeBlocksRequest::new();
+        request.set_from_index(5);
+        let mut stream = client
+            .subscribe_blocks(&request)
+            .expect("failed to subscribe to blocks");
+
+        for expected_block_data in &blocks_data[5..] {
+            let archive_block = block_on(stream.next()).unwrap().unwrap();
+            assert_eq!(
+                BlockData::try_from(&archive_block).unwrap(),
+                *expected_block_data
+            );
+        }
+
+        // Blocks appended to the ledger get pushed to the open stream.
+        let new_block_data = add_block_to_ledger(
+            &mut ledger_db,
+            BlockVersion::MAX,
+            &[account_key.default_subaddress()],
+            Amount::new(10, Mob::ID),
+            &[],
+            &mut rng,
+        )
+        .unwrap();
+
+        let archive_block = block_on(stream.next()).unwrap().unwrap();
+        assert_eq!(BlockData::try_from(&archive_block).unwrap(), new_block_data);
+
+        // Subscribing again resumes streaming from the requested index.
+        let mut request = SubscribeBlocksRequest::new();
+        request.set_from_index(new_block_data.block().index);
+        let mut resumed_stream = client
+            .subscribe_blocks(&request)
+            .expect("failed to subscribe to blocks");
+
+        let archive_block = block_on(resumed_stream.next()).unwrap().unwrap();
+        assert_eq!(BlockData::try_from(&archive_block).unwrap(), new_block_data);
+    }
+
+    #[test_with_logger]
+    // `subscribe_blocks` should reject subscriptions past the limit, until an
+    // open subscription ends.
+    fn test_subscribe_blocks_rejects_too_many_subscriptions(logger: Logger) {
+        let mut ledger_db = create_ledger();
+        let authenticator = Arc::new(AnonymousAuthenticator::default());
+        let mut rng: StdRng = SeedableRng::from_seed([1u8; 32]);
+        let account_key = AccountKey::random(&mut rng);
+        initialize_ledger(BlockVersion::MAX, &mut ledger_db, 2, &account_key, &mut rng);
+
+        let mut blockchain_api_service = BlockchainApiService::new(
+            ledger_db.clone(),
+            authenticator,
+            FeeMap::default(),
+            BlockVersion::MAX,
+            logger,
+        );
+        blockchain_api_service.set_max_block_subscriptions(1);
+
+        let (client, _server) = get_client_server(blockchain_api_service);
+
+        let mut stream = client
+            .subscribe_blocks(&SubscribeBlocksRequest::default())
+            .expect("failed to subscribe to blocks");
+        assert!(block_on(stream.next()).unwrap().is_ok());
+
+        let mut rejected_stream = client
+            .subscribe_blocks(&SubscribeBlocksRequest::default())
+            .expect("failed to subscribe to blocks");
+        match block_on(rejected_stream.next()) {
+            Some(Err(GrpcError::RpcFailure(rpc_status))) => {
+                assert_eq!(rpc_status.code(), RpcStatusCode::RESOURCE_EXHAUSTED);
+            }
+            other => {
+                panic!("Unexpected result {:?}", other);
+            }
+        }
+
+        // Once the client goes away, the next block sent to it ends the
+        // subscription and frees its slot.
+        drop(stream);
+        let mut accepted = false;
+        for _ in 0..50 {
+            add_block_to_ledger(
+                &mut ledger_db,
+                BlockVersion::MAX,
+                &[account_key.default_subaddress()],
+                Amount::new(10, Mob::ID),
+                &[],
+                &mut rng,
+            )
+            .unwrap();
+            thread::sleep(Duration::from_millis(100));
+
+            let mut stream = client
+                .subscribe_blocks(&SubscribeBlocksRequest::default())
+                .expect("failed to subscribe to blocks");
+            if let Some(Ok(_)) = block_on(stream.next()) {
+                accepted = true;
+                break;
+            }
+        }
+        assert!(accepted, "the subscription slot was never released");
+    }
+
+    #[test_with_logger]
+    // `subscribe_blocks` should reject unauthenticated requests when configured
+    // with an authenticator.
+    fn test_subscribe_blocks_rejects_unauthenticated(logger: Logger) {
+        let ledger_db = create_ledger();
+        let authenticator = Arc::new(TokenAuthenticator::new(
+            [1; 32],
+            Duration::from_secs(60),
+            SystemTimeProvider::default(),
+        ));
+
+        let blockchain_api_service = BlockchainApiService::new(
+            ledger_db,
+            authenticator,
+            FeeMap::default(),
+            BlockVersion::MAX,
+            logger,
+        );
+
+        let (client, _server) = get_client_server(blockchain_api_service);
+
+        let mut stream = client
+            .subscribe_blocks(&SubscribeBlocksRequest::default())
+            .expect("failed to subscribe to blocks");
+
+        match block_on(stream.next()) {
+            Some(Err(GrpcError::RpcFailure(rpc_status))) => {
+                assert_eq!(rpc_status.code(), RpcStatusCode::UNAUTHENTICATED);
+            }
+            other => {
+                panic!("Unexpected result {:?}", other);
+            }
+        }
+    }
 }
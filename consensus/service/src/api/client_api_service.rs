@@ -4,7 +4,7 @@
 
 use crate::{
     api::grpc_error::ConsensusGrpcError,
-    consensus_service::ProposeTxCallback,
+    consensus_service::{PendingTxHashesFn, ProposeTxCallback},
     counters,
     mint_tx_manager::MintTxManager,
     tx_manager::{TxManager, TxManagerError},
@@ -13,7 +13,10 @@ use grpcio::{RpcContext, RpcStatus, UnarySink};
 use mc_attest_api::attest::Message;
 use mc_common::logger::Logger;
 use mc_consensus_api::{
-    consensus_client::{ProposeMintConfigTxResponse, ProposeMintTxResponse},
+    consensus_client::{
+        FeeTokenPoolStats, PendingPoolStatsResponse, PriorityStats, ProposeMintConfigTxResponse,
+        ProposeMintTxResponse,
+    },
     consensus_client_grpc::ConsensusClientApi,
     consensus_common::{ProposeTxResponse, ProposeTxResult},
     consensus_config::{ConsensusNodeConfig, TokenConfig},
@@ -23,10 +26,18 @@ use mc_consensus_enclave::ConsensusEnclave;
 use mc_consensus_service_config::Config;
 use mc_ledger_db::Ledger;
 use mc_peers::ConsensusValue;
-use mc_transaction_core::mint::{MintConfigTx, MintTx};
-use mc_util_grpc::{check_request_chain_id, rpc_logger, send_result, Authenticator};
+use mc_transaction_core::{
+    constants::MAX_TRANSACTIONS_PER_BLOCK,
+    mint::{MintConfigTx, MintTx},
+    TokenId,
+};
+use mc_util_grpc::{
+    check_request_chain_id, rpc_logger, send_result, start_rpc_span, Authenticator,
+};
 use mc_util_metrics::{self, SVC_COUNTERS};
-use std::sync::Arc;
+use mc_util_telemetry::{tracer, Context, TraceContextExt, TELEMETRY_TX_HASH_KEY};
+use protobuf::RepeatedField;
+use std::{collections::BTreeMap, sync::Arc};
 
 /// Maximum number of pending values for consensus service before rejecting
 /// add_transaction requests.
@@ -43,6 +54,8 @@ pub struct ClientApiService {
     propose_tx_callback: ProposeTxCallback,
     /// Returns true if this node is able to process proposed transactions.
     is_serving_fn: Arc<(dyn Fn() -> bool + Sync + Send)>,
+    /// Returns the hashes of the transactions waiting to be nominated.
+    pending_tx_hashes_fn: PendingTxHashesFn,
     authenticator: Arc<dyn Authenticator + Send + Sync>,
     logger: Logger,
 }
@@ -56,6 +69,7 @@ impl ClientApiService {
         tx_manager: Arc<dyn TxManager + Send + Sync>,
         mint_tx_manager: Arc<dyn MintTxManager + Send + Sync>,
         is_serving_fn: Arc<(dyn Fn() -> bool + Sync + Send)>,
+        pending_tx_hashes_fn: PendingTxHashesFn,
         authenticator: Arc<dyn Authenticator + Send + Sync>,
         logger: Logger,
     ) -> Self {
@@ -67,6 +81,7 @@ impl ClientApiService {
             ledger,
             propose_tx_callback: scp_client_value_sender,
             is_serving_fn,
+            pending_tx_hashes_fn,
             authenticator,
             logger,
         }
@@ -94,6 +109,9 @@ impl ClientApiService {
             }
             err
         })?;
+        Context::current()
+            .span()
+            .set_attribute(TELEMETRY_TX_HASH_KEY.string(tx_hash.to_string()));
 
         // Validate the transaction.
         // This is done here as a courtesy to give clients immediate feedback about the
@@ -198,6 +216,60 @@ impl ClientApiService {
 
         Ok(response)
     }
+
+    /// Get statistics of the transactions waiting to be nominated by this
+    /// node.
+    fn get_pending_pool_stats_impl(&self) -> Result<PendingPoolStatsResponse, ConsensusGrpcError> {
+        let tx_hashes = (self.pending_tx_hashes_fn)();
+        let tx_contexts = self.tx_manager.get_well_formed_tx_contexts(&tx_hashes);
+
+        let mut priorities = Vec::with_capacity(tx_contexts.len());
+        let mut priorities_by_token: BTreeMap<TokenId, Vec<u64>> = BTreeMap::new();
+        for tx_context in &tx_contexts {
+            priorities.push(tx_context.priority());
+            priorities_by_token
+                .entry(tx_context.fee_token_id())
+                .or_default()
+                .push(tx_context.priority());
+        }
+
+        let token_stats = priorities_by_token
+            .into_iter()
+            .map(|(token_id, priorities)| {
+                let mut token_stats = FeeTokenPoolStats::new();
+                token_stats.set_token_id(*token_id);
+                token_stats.set_priorities(priority_stats(priorities));
+                token_stats
+            })
+            .collect();
+
+        let mut response = PendingPoolStatsResponse::new();
+        response.set_block_count(self.ledger.num_blocks()?);
+        response.set_block_version(*self.config.block_version);
+        response.set_num_pending_values(counters::CUR_NUM_PENDING_VALUES.get() as u64);
+        response.set_max_transactions_per_block(MAX_TRANSACTIONS_PER_BLOCK as u64);
+        response.set_priorities(priority_stats(priorities));
+        response.set_token_stats(RepeatedField::from_vec(token_stats));
+        Ok(response)
+    }
+}
+
+/// Summarize a list of transaction priorities. Percentiles use the
+/// nearest-rank method.
+fn priority_stats(mut priorities: Vec<u64>) -> PriorityStats {
+    priorities.sort_unstable();
+
+    let percentile = |percent: usize| -> u64 {
+        let rank = (priorities.len() * percent + 99) / 100;
+        priorities.get(rank.saturating_sub(1)).cloned().unwrap_or(0)
+    };
+
+    let mut stats = PriorityStats::new();
+    stats.set_num_txs(priorities.len() as u64);
+    stats.set_p50(percentile(50));
+    stats.set_p90(percentile(90));
+    stats.set_max(priorities.last().cloned().unwrap_or(0));
+    stats
 }
 
 impl ConsensusClientApi for ClientApiService {
@@ -208,6 +280,7 @@ impl ConsensusClientApi for ClientApiService {
         sink: UnarySink<ProposeTxResponse>,
     ) {
         let _timer = SVC_COUNTERS.req(&ctx);
+        let _span = start_rpc_span(&tracer!(), &ctx, "client_tx_propose");
 
         if let Err(err) = check_request_chain_id(&self.config.chain_id, &ctx) {
             return send_result(ctx, sink, Err(err), &self.logger);
@@ -337,6 +410,29 @@ impl ConsensusClientApi for ClientApiService {
             send_result(ctx, sink, result, logger)
         });
     }
+
+    fn get_pending_pool_stats(
+        &mut self,
+        ctx: RpcContext,
+        _empty: Empty,
+        sink: UnarySink<PendingPoolStatsResponse>,
+    ) {
+        let _timer = SVC_COUNTERS.req(&ctx);
+
+        if let Err(err) = check_request_chain_id(&self.config.chain_id, &ctx) {
+            return send_result(ctx, sink, Err(err), &self.logger);
+        }
+
+        if let Err(err) = self.authenticator.authenticate_rpc(&ctx) {
+            return send_result(ctx, sink, err.into(), &self.logger);
+        }
+
+        let result = self.get_pending_pool_stats_impl().map_err(RpcStatus::from);
+
+        mc_common::logger::scoped_global_logger(&rpc_logger(&ctx, &self.logger), |logger| {
+            send_result(ctx, sink, result, logger)
+        });
+    }
 }
 
 #[cfg(test)]
@@ -361,22 +457,24 @@ mod client_api_tests {
     use mc_consensus_api::{
         consensus_client::MintValidationResultCode, consensus_client_grpc,
         consensus_client_grpc::ConsensusClientApiClient, consensus_common::ProposeTxResult,
+        empty::Empty,
     };
-    use mc_consensus_enclave::TxContext;
+    use mc_consensus_enclave::{TxContext, WellFormedTxContext};
     use mc_consensus_enclave_mock::MockConsensusEnclave;
     use mc_consensus_service_config::Config;
     use mc_crypto_keys::Ed25519Pair;
     use mc_ledger_db::MockLedger;
     use mc_peers::ConsensusValue;
     use mc_transaction_core::{
-        mint::MintValidationError, ring_signature::KeyImage, tx::TxHash,
-        validation::TransactionValidationError, TokenId,
+        constants::MAX_TRANSACTIONS_PER_BLOCK, mint::MintValidationError, ring_signature::KeyImage,
+        tx::TxHash, validation::TransactionValidationError, TokenId,
     };
     use mc_transaction_core_test_utils::{create_mint_config_tx, create_mint_tx};
     use mc_util_from_random::FromRandom;
     use mc_util_grpc::{
         AnonymousAuthenticator, TokenAuthenticator, CHAIN_ID_GRPC_HEADER, CHAIN_ID_MISMATCH_ERR_MSG,
     };
+    use mockall::predicate::eq;
     use rand_core::SeedableRng;
     use rand_hc::Hc128Rng;
     use serial_test::serial;
@@ -493,6 +591,7 @@ mod client_api_tests {
             Arc::new(tx_manager),
             Arc::new(MockMintTxManager::new()),
             is_serving_fn,
+            Arc::new(Vec::new),
             Arc::new(authenticator),
             logger,
         );
@@ -562,6 +661,7 @@ mod client_api_tests {
             Arc::new(tx_manager),
             Arc::new(MockMintTxManager::new()),
             is_serving_fn,
+            Arc::new(Vec::new),
             Arc::new(authenticator),
             logger,
         );
@@ -610,6 +710,7 @@ mod client_api_tests {
             Arc::new(tx_manager),
             Arc::new(MockMintTxManager::new()),
             is_serving_fn,
+            Arc::new(Vec::new),
             Arc::new(authenticator),
             logger,
         );
@@ -689,6 +790,7 @@ mod client_api_tests {
             Arc::new(tx_manager),
             Arc::new(MockMintTxManager::new()),
             is_serving_fn,
+            Arc::new(Vec::new),
             Arc::new(authenticator),
             logger,
         );
@@ -757,6 +859,7 @@ mod client_api_tests {
             Arc::new(tx_manager),
             Arc::new(MockMintTxManager::new()),
             is_serving_fn,
+            Arc::new(Vec::new),
             Arc::new(authenticator),
             logger,
         );
@@ -805,6 +908,7 @@ mod client_api_tests {
             Arc::new(MockTxManager::new()),
             Arc::new(MockMintTxManager::new()),
             is_serving_fn,
+            Arc::new(Vec::new),
             Arc::new(authenticator),
             logger,
         );
@@ -855,6 +959,7 @@ mod client_api_tests {
             Arc::new(MockTxManager::new()),
             Arc::new(MockMintTxManager::new()),
             is_serving_fn,
+            Arc::new(Vec::new),
             Arc::new(authenticator),
             logger,
         );
@@ -909,6 +1014,7 @@ mod client_api_tests {
             Arc::new(MockTxManager::new()),
             Arc::new(MockMintTxManager::new()),
             is_serving_fn,
+            Arc::new(Vec::new),
             Arc::new(authenticator),
             logger,
         );
@@ -971,6 +1077,7 @@ mod client_api_tests {
             Arc::new(MockTxManager::new()),
             Arc::new(mint_tx_manager),
             is_serving_fn,
+            Arc::new(Vec::new),
             Arc::new(authenticator),
             logger,
         );
@@ -1041,6 +1148,7 @@ mod client_api_tests {
             Arc::new(MockTxManager::new()),
             Arc::new(mint_tx_manager),
             is_serving_fn,
+            Arc::new(Vec::new),
             Arc::new(authenticator),
             logger,
         );
@@ -1092,6 +1200,7 @@ mod client_api_tests {
             Arc::new(MockTxManager::new()),
             Arc::new(mint_tx_manager),
             is_serving_fn,
+            Arc::new(Vec::new),
             Arc::new(authenticator),
             logger,
         );
@@ -1145,6 +1254,7 @@ mod client_api_tests {
             Arc::new(MockTxManager::new()),
             Arc::new(mint_tx_manager),
             is_serving_fn,
+            Arc::new(Vec::new),
             Arc::new(authenticator),
             logger,
         );
@@ -1207,6 +1317,7 @@ mod client_api_tests {
             Arc::new(MockTxManager::new()),
             Arc::new(mint_tx_manager),
             is_serving_fn,
+            Arc::new(Vec::new),
             Arc::new(authenticator),
             logger,
         );
@@ -1267,6 +1378,7 @@ mod client_api_tests {
             Arc::new(MockTxManager::new()),
             Arc::new(mint_tx_manager),
             is_serving_fn,
+            Arc::new(Vec::new),
             Arc::new(authenticator),
             logger,
         );
@@ -1347,6 +1459,7 @@ mod client_api_tests {
             Arc::new(MockTxManager::new()),
             Arc::new(mint_tx_manager),
             is_serving_fn,
+            Arc::new(Vec::new),
             Arc::new(authenticator),
             logger,
         );
@@ -1403,6 +1516,7 @@ mod client_api_tests {
             Arc::new(MockTxManager::new()),
             Arc::new(mint_tx_manager),
             is_serving_fn,
+            Arc::new(Vec::new),
             Arc::new(authenticator),
             logger,
         );
@@ -1461,6 +1575,7 @@ mod client_api_tests {
             Arc::new(MockTxManager::new()),
             Arc::new(mint_tx_manager),
             is_serving_fn,
+            Arc::new(Vec::new),
             Arc::new(authenticator),
             logger,
         );
@@ -1528,6 +1643,7 @@ mod client_api_tests {
             Arc::new(MockTxManager::new()),
             Arc::new(mint_tx_manager),
             is_serving_fn,
+            Arc::new(Vec::new),
             Arc::new(authenticator),
             logger,
         );
@@ -1546,4 +1662,86 @@ mod client_api_tests {
 
         assert!(submitted_values.lock().unwrap().is_empty());
     }
+
+    #[test_with_logger]
+    #[serial(counters)]
+    fn test_get_pending_pool_stats(logger: Logger) {
+        let num_blocks = 42;
+        let mut ledger = MockLedger::new();
+        ledger.expect_num_blocks().return_const(Ok(num_blocks));
+
+        // Ten pending transactions, two of which pay their fee in token 1.
+        let pending_tx_hashes: Vec<_> = (0..10).map(|i| TxHash([i as u8; 32])).collect();
+        let tx_contexts: Vec<_> = pending_tx_hashes
+            .iter()
+            .enumerate()
+            .map(|(i, tx_hash)| {
+                let fee_token_id = if i < 2 { 1 } else { 0 };
+                Arc::new(WellFormedTxContext::new(
+                    128 * (i as u64 + 1),
+                    TokenId::from(fee_token_id),
+                    *tx_hash,
+                    Default::default(),
+                    Default::default(),
+                    Default::default(),
+                    Default::default(),
+                ))
+            })
+            .collect();
+
+        let mut tx_manager = MockTxManager::new();
+        tx_manager
+            .expect_get_well_formed_tx_contexts()
+            .with(eq(pending_tx_hashes.clone()))
+            .times(1)
+            .return_const(tx_contexts);
+
+        counters::CUR_NUM_PENDING_VALUES.set(11);
+
+        let instance = ClientApiService::new(
+            get_config(),
+            Arc::new(MockConsensusEnclave::new()),
+            Arc::new(
+                |_value: ConsensusValue,
+                 _node_id: Option<&NodeID>,
+                 _responder_id: Option<&ResponderId>| {},
+            ),
+            Arc::new(ledger),
+            Arc::new(tx_manager),
+            Arc::new(MockMintTxManager::new()),
+            Arc::new(|| -> bool { true }),
+            Arc::new(move || pending_tx_hashes.clone()),
+            Arc::new(AnonymousAuthenticator::default()),
+            logger,
+        );
+
+        // gRPC client and server.
+        let (client, _server) = get_client_server(instance);
+        let response = client.get_pending_pool_stats(&Empty::new()).unwrap();
+        counters::CUR_NUM_PENDING_VALUES.set(0);
+
+        assert_eq!(response.get_block_count(), num_blocks);
+        assert_eq!(response.get_num_pending_values(), 11);
+        assert_eq!(
+            response.get_max_transactions_per_block(),
+            MAX_TRANSACTIONS_PER_BLOCK as u64
+        );
+
+        let priorities = response.get_priorities();
+        assert_eq!(priorities.get_num_txs(), 10);
+        assert_eq!(priorities.get_p50(), 128 * 5);
+        assert_eq!(priorities.get_p90(), 128 * 9);
+        assert_eq!(priorities.get_max(), 128 * 10);
+
+        let token_stats = response.get_token_stats();
+        assert_eq!(token_stats.len(), 2);
+        assert_eq!(token_stats[0].get_token_id(), 0);
+        assert_eq!(token_stats[0].get_priorities().get_num_txs(), 8);
+        assert_eq!(token_stats[0].get_priorities().get_p50(), 128 * 6);
+        assert_eq!(token_stats[0].get_priorities().get_max(), 128 * 10);
+        assert_eq!(token_stats[1].get_token_id(), 1);
+        assert_eq!(token_stats[1].get_priorities().get_num_txs(), 2);
+        assert_eq!(token_stats[1].get_priorities().get_p50(), 128);
+        assert_eq!(token_stats[1].get_priorities().get_p90(), 128 * 2);
+    }
 }
@@ -31,9 +31,11 @@ use mc_peers::{ConsensusValue, TxProposeAAD};
 use mc_transaction_core::tx::TxHash;
 use mc_util_grpc::{
     rpc_internal_error, rpc_invalid_arg_error, rpc_logger, rpc_permissions_error, send_result,
+    start_rpc_span,
 };
 use mc_util_metrics::SVC_COUNTERS;
 use mc_util_serial::deserialize;
+use mc_util_telemetry::{tracer, Context, TraceContextExt, TELEMETRY_TX_HASH_KEY};
 use std::{str::FromStr, sync::Arc};
 
 // Callback method for returning the latest SCP message issued by the local
@@ -146,6 +148,10 @@ impl PeerApiService {
         // Handle each transaction.
         for tx_context in tx_contexts {
             let tx_hash = tx_context.tx_hash;
+            Context::current().span().add_event(
+                "tx",
+                vec![TELEMETRY_TX_HASH_KEY.string(tx_hash.to_string())],
+            );
 
             match self.tx_manager.insert(tx_context) {
                 Ok(tx_hash) => {
@@ -235,6 +241,7 @@ impl ConsensusPeerApi for PeerApiService {
         sink: UnarySink<ProposeTxResponse>,
     ) {
         let _timer = SVC_COUNTERS.req(&ctx);
+        let _span = start_rpc_span(&tracer!(), &ctx, "peer_tx_propose");
 
         let enclave_msg: EnclaveMessage<PeerSession> = request.into();
 
@@ -265,6 +272,7 @@ impl ConsensusPeerApi for PeerApiService {
         sink: UnarySink<ConsensusMsgResponse>,
     ) {
         let _timer = SVC_COUNTERS.req(&ctx);
+        let _span = start_rpc_span(&tracer!(), &ctx, "send_consensus_msg");
         mc_common::logger::scoped_global_logger(&rpc_logger(&ctx, &self.logger), |logger| {
             // The peer who delivered this message to us.
             let from_responder_id = match ResponderId::from_str(request.get_from_responder_id()) {
@@ -350,6 +358,7 @@ impl ConsensusPeerApi for PeerApiService {
         sink: UnarySink<GetTxsResponse>,
     ) {
         let _timer = SVC_COUNTERS.req(&ctx);
+        let _span = start_rpc_span(&tracer!(), &ctx, "get_txs");
         mc_common::logger::scoped_global_logger(&rpc_logger(&ctx, &self.logger), |logger| {
             let mut tx_hashes: Vec<TxHash> = Vec::new();
             for tx_hash_bytes in request.get_tx_hashes() {
@@ -29,7 +29,10 @@ use mc_ledger_sync::{LedgerSyncService, ReqwestTransactionsFetcher};
 use mc_peers::{
     Broadcast, ConsensusConnection, ConsensusMsg, ConsensusValue, VerifiedConsensusMsg,
 };
-use mc_transaction_core::mint::constants::{MAX_MINT_CONFIG_TXS_PER_BLOCK, MAX_MINT_TXS_PER_BLOCK};
+use mc_transaction_core::{
+    mint::constants::{MAX_MINT_CONFIG_TXS_PER_BLOCK, MAX_MINT_TXS_PER_BLOCK},
+    tx::TxHash,
+};
 use mc_util_metered_channel::Sender;
 use std::{
     path::PathBuf,
@@ -67,6 +70,9 @@ pub struct ByzantineLedger {
 
     // Highest consensus message issued by this node. (Set by the worker)
     highest_issued_msg: Arc<Mutex<Option<ConsensusMsg>>>,
+
+    // The hashes of the pending transactions. (Set by the worker)
+    pending_tx_hashes: Arc<Mutex<Vec<TxHash>>>,
 }
 
 /// An error type for mc-consensus-scp validation/combine callbacks.
@@ -210,6 +216,7 @@ impl ByzantineLedger {
         let is_behind = Arc::new(AtomicBool::new(false));
         let highest_peer_block = Arc::new(AtomicU64::new(0));
         let highest_issued_msg = Arc::new(Mutex::new(Option::<ConsensusMsg>::None));
+        let pending_tx_hashes = Arc::new(Mutex::new(Vec::new()));
 
         // Start worker thread
         let worker_handle = {
@@ -241,6 +248,7 @@ impl ByzantineLedger {
                 is_behind.clone(),
                 highest_peer_block.clone(),
                 highest_issued_msg.clone(),
+                pending_tx_hashes.clone(),
                 logger,
             );
 
@@ -263,6 +271,7 @@ impl ByzantineLedger {
             is_behind,
             highest_peer_block,
             highest_issued_msg,
+            pending_tx_hashes,
         }
     }
 
@@ -312,6 +321,15 @@ impl ByzantineLedger {
     pub fn highest_peer_block(&self) -> u64 {
         self.highest_peer_block.load(Ordering::SeqCst)
     }
+
+    /// Get the hashes of the transactions waiting to be nominated, in the
+    /// order they were received.
+    pub fn pending_tx_hashes(&self) -> Vec<TxHash> {
+        self.pending_tx_hashes
+            .lock()
+            .expect("mutex poisoned")
+            .clone()
+    }
 }
 
 impl Drop for ByzantineLedger {
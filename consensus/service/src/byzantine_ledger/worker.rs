@@ -95,6 +95,9 @@ pub struct ByzantineLedgerWorker<
     // Highest consensus message issued by this node.
     highest_issued_msg: Arc<Mutex<Option<ConsensusMsg>>>,
 
+    // The hashes of the pending transactions. (Set by the worker whenever pending values change)
+    pending_tx_hashes: Arc<Mutex<Vec<TxHash>>>,
+
     // Network state, used to track if we've fallen behind.
     network_state: SCPNetworkState,
 
@@ -142,6 +145,8 @@ impl<
     ///   network agrees on.
     /// * `highest_issued_msg` - Worker sets to highest consensus message issued
     ///   by this node.
+    /// * `pending_tx_hashes` - Worker sets to the hashes of the pending
+    ///   transactions.
     /// * `logger` - Logger instance.
     #[allow(clippy::too_many_arguments)]
     pub fn new(
@@ -158,6 +163,7 @@ impl<
         is_behind: Arc<AtomicBool>,
         highest_peer_block: Arc<AtomicU64>,
         highest_issued_msg: Arc<Mutex<Option<ConsensusMsg>>>,
+        pending_tx_hashes: Arc<Mutex<Vec<TxHash>>>,
         logger: Logger,
     ) -> Self {
         let current_slot_index = ledger.num_blocks().unwrap();
@@ -172,6 +178,7 @@ impl<
             is_behind,
             highest_peer_block,
             highest_issued_msg,
+            pending_tx_hashes,
             ledger,
             tx_manager: tx_manager.clone(),
             mint_tx_manager: mint_tx_manager.clone(),
@@ -250,6 +257,7 @@ impl<
                 self.scp_node.reset_slot_index(self.current_slot_index);
                 // Clear any pending values that might no longer be valid.
                 self.pending_values.clear_invalid_values();
+                self.publish_pending_tx_hashes();
                 if !self.pending_values.is_empty() {
                     // These values should be proposed for nomination.
                     self.need_nominate = true;
@@ -392,6 +400,7 @@ impl<
     // Reads tasks from the task queue.
     // Returns false if the worker has been asked to stop.
     fn receive_tasks(&mut self) -> bool {
+        let mut pending_values_changed = false;
         for task_msg in self.tasks.try_iter() {
             match task_msg {
                 // Transactions submitted by clients. These are assumed to be well-formed, but may
@@ -400,6 +409,7 @@ impl<
                     for tx_hash in new_values {
                         if self.pending_values.push(tx_hash, timestamp) {
                             self.need_nominate = true;
+                            pending_values_changed = true;
                         }
                     }
                 }
@@ -420,6 +430,10 @@ impl<
             };
         }
 
+        if pending_values_changed {
+            self.publish_pending_tx_hashes();
+        }
+
         // Update highest_peer_block.
         if let Some(peer_block) = self.network_state.highest_block_index_on_network() {
             self.highest_peer_block.store(peer_block, Ordering::SeqCst);
@@ -613,6 +627,7 @@ impl<
         // Drop pending values that are no longer considered valid.
         let pending_values_len_before_clear_invalid = self.pending_values.len();
         self.pending_values.clear_invalid_values();
+        self.publish_pending_tx_hashes();
 
         log::info!(
             self.logger,
@@ -794,6 +809,19 @@ impl<
         Ok(())
     }
 
+    // Share the hashes of the pending transactions with the rest of the service.
+    fn publish_pending_tx_hashes(&self) {
+        let tx_hashes = self
+            .pending_values
+            .iter()
+            .filter_map(|value| match value {
+                ConsensusValue::TxHash(tx_hash) => Some(*tx_hash),
+                ConsensusValue::MintConfigTx(_) | ConsensusValue::MintTx(_) => None,
+            })
+            .collect();
+        *self.pending_tx_hashes.lock().expect("mutex poisoned") = tx_hashes;
+    }
+
     fn update_current_slot_metrics(&mut self) {
         let slot_metrics = self.scp_node.get_current_slot_metrics();
         counters::CUR_NUM_PENDING_VALUES.set(self.pending_values.len() as i64);
@@ -1039,6 +1067,7 @@ mod tests {
             Arc::new(AtomicBool::new(false)),
             Arc::new(AtomicU64::new(0)),
             Arc::new(Mutex::new(Option::<ConsensusMsg>::None)),
+            Arc::new(Mutex::new(Vec::new())),
             logger,
         );
 
@@ -1091,6 +1120,7 @@ mod tests {
             Arc::new(AtomicBool::new(false)),
             Arc::new(AtomicU64::new(0)),
             Arc::new(Mutex::new(Option::<ConsensusMsg>::None)),
+            Arc::new(Mutex::new(Vec::new())),
             logger,
         );
 
@@ -1208,6 +1238,7 @@ mod tests {
             Arc::new(AtomicBool::new(false)),
             Arc::new(AtomicU64::new(0)),
             Arc::new(Mutex::new(Option::<ConsensusMsg>::None)),
+            Arc::new(Mutex::new(Vec::new())),
             logger,
         );
 
@@ -1269,6 +1300,7 @@ mod tests {
             Arc::new(AtomicBool::new(false)),
             Arc::new(AtomicU64::new(0)),
             Arc::new(Mutex::new(Option::<ConsensusMsg>::None)),
+            Arc::new(Mutex::new(Vec::new())),
             logger,
         );
 
@@ -1356,6 +1388,7 @@ mod tests {
         let verified_consensus_msg =
             get_verified_consensus_msg(&peers[0].id, &peers[0].signer_key, &ledger);
 
+        let pending_tx_hashes = Arc::new(Mutex::new(Vec::new()));
         let mut worker = ByzantineLedgerWorker::new(
             enclave,
             Box::new(scp_node),
@@ -1370,6 +1403,7 @@ mod tests {
             Arc::new(AtomicBool::new(false)),
             Arc::new(AtomicU64::new(0)),
             Arc::new(Mutex::new(Option::<ConsensusMsg>::None)),
+            pending_tx_hashes.clone(),
             logger,
         );
 
@@ -1395,6 +1429,17 @@ mod tests {
         // corresponding to transactions that are valid w.r.t. the current ledger.
         assert_eq!(worker.pending_values.len(), tx_hashes.len() - 3);
 
+        // The hashes of the pending transactions should be published, in order.
+        let expected_pending_tx_hashes: Vec<_> = tx_hashes[..100]
+            .iter()
+            .chain(&tx_hashes[103..])
+            .cloned()
+            .collect();
+        assert_eq!(
+            *pending_tx_hashes.lock().unwrap(),
+            expected_pending_tx_hashes
+        );
+
         let responder_id = ResponderId::default();
 
         task_sender
@@ -1456,6 +1501,7 @@ mod tests {
             Arc::new(AtomicBool::new(false)),
             Arc::new(AtomicU64::new(0)),
             Arc::new(Mutex::new(Option::<ConsensusMsg>::None)),
+            Arc::new(Mutex::new(Vec::new())),
             logger,
         );
 
@@ -1553,6 +1599,7 @@ mod tests {
             Arc::new(AtomicBool::new(false)),
             Arc::new(AtomicU64::new(0)),
             Arc::new(Mutex::new(Option::<ConsensusMsg>::None)),
+            Arc::new(Mutex::new(Vec::new())),
             logger,
         );
 
@@ -1702,6 +1749,7 @@ mod tests {
             Arc::new(AtomicBool::new(false)),
             Arc::new(AtomicU64::new(0)),
             Arc::new(Mutex::new(Option::<ConsensusMsg>::None)),
+            Arc::new(Mutex::new(Vec::new())),
             logger,
         );
 
@@ -31,6 +31,7 @@ use mc_crypto_keys::DistinguishedEncoding;
 use mc_ledger_db::{Error as LedgerDbError, Ledger, LedgerDB};
 use mc_peers::{ConsensusValue, PeerConnection, ThreadedBroadcaster, VerifiedConsensusMsg};
 use mc_sgx_report_cache_untrusted::{Error as ReportCacheError, ReportCacheThread};
+use mc_transaction_core::tx::TxHash;
 use mc_util_grpc::{
     AdminServer, AnonymousAuthenticator, Authenticator, BuildInfoService,
     ConnectionUriGrpcioServer, GetConfigJsonFn, HealthCheckStatus, HealthService,
@@ -103,6 +104,10 @@ pub struct IncomingConsensusMsg {
 pub type ProposeTxCallback =
     Arc<dyn Fn(ConsensusValue, Option<&NodeID>, Option<&ResponderId>) + Sync + Send>;
 
+/// A function that returns the hashes of the transactions waiting to be
+/// nominated by this node.
+pub type PendingTxHashesFn = Arc<dyn Fn() -> Vec<TxHash> + Sync + Send>;
+
 pub struct ConsensusService<
     E: ConsensusEnclave + Clone + Send + Sync + 'static,
     R: RaClient + Send + Sync + 'static,
@@ -207,9 +212,9 @@ impl<
 
         // Authenticator
         let client_authenticator: Arc<dyn Authenticator + Sync + Send> =
-            if let Some(shared_secret) = config.client_auth_token_secret.as_ref() {
-                Arc::new(TokenAuthenticator::new(
-                    *shared_secret,
+            if let Some(signing_keys) = config.client_auth_token_secret.as_ref() {
+                Arc::new(TokenAuthenticator::new_with_keys(
+                    signing_keys.clone(),
                     config.client_auth_token_max_lifetime,
                     time_provider,
                 ))
@@ -343,6 +348,7 @@ impl<
                 self.tx_manager.clone(),
                 self.mint_tx_manager.clone(),
                 self.create_is_serving_user_requests_fn(),
+                self.create_pending_tx_hashes_fn(),
                 self.client_authenticator.clone(),
                 self.logger.clone(),
             ));
@@ -581,6 +587,23 @@ impl<
         })
     }
 
+    /// Creates a function that returns the hashes of the transactions waiting
+    /// to be nominated by this node.
+    fn create_pending_tx_hashes_fn(&self) -> PendingTxHashesFn {
+        let byzantine_ledger = self
+            .byzantine_ledger
+            .as_ref()
+            .map(Arc::downgrade)
+            .expect("Server was not initialized");
+
+        Arc::new(move || {
+            byzantine_ledger
+                .upgrade()
+                .and_then(|ledger| ledger.get().map(|ledger| ledger.pending_tx_hashes()))
+                .unwrap_or_default()
+        })
+    }
+
     /// Creates a function that feeds client values into ByzantineLedger and
     /// broadcasts it to our peers.
     fn create_scp_client_value_sender_fn(&self) -> ProposeTxCallback {
@@ -756,7 +779,7 @@ impl<
                     "admin_listen_uri": config.admin_listen_uri,
                     "ledger_path": config.ledger_path,
                     "scp_debug_dump": config.scp_debug_dump,
-                    "client_auth_token_enabled": config.client_auth_token_secret.map(|_| true).unwrap_or(false),
+                    "client_auth_token_enabled": config.client_auth_token_secret.is_some(),
                     "client_auth_token_max_lifetime": config.client_auth_token_max_lifetime.as_secs(),
                 },
                 "network": config.network(),
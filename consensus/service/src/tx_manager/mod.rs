@@ -329,6 +329,16 @@ impl<E: ConsensusEnclave + Send, UI: UntrustedInterfaces + Send> TxManager
             .get(tx_hash)
             .map(|entry| entry.encrypted_tx().clone())
     }
+
+    /// Get the contexts of the transactions corresponding to the given hashes.
+    /// Hashes that are not in the cache are skipped.
+    fn get_well_formed_tx_contexts(&self, tx_hashes: &[TxHash]) -> Vec<Arc<WellFormedTxContext>> {
+        let cache = self.lock_cache();
+        tx_hashes
+            .iter()
+            .filter_map(|tx_hash| cache.get(tx_hash).map(|entry| entry.context().clone()))
+            .collect()
+    }
 }
 
 #[cfg(test)]
@@ -337,7 +347,7 @@ mod tests {
     use crate::tx_manager::untrusted_interfaces::MockUntrustedInterfaces;
     use mc_common::logger::test_with_logger;
     use mc_consensus_enclave_mock::{Error as EnclaveError, MockConsensusEnclave};
-    use mc_transaction_core::validation::TransactionValidationError;
+    use mc_transaction_core::{validation::TransactionValidationError, TokenId};
 
     #[test_with_logger]
     // Should return Ok when a well-formed Tx is inserted.
@@ -360,6 +370,7 @@ mod tests {
         let well_formed_encrypted_tx = WellFormedEncryptedTx::default();
         let well_formed_tx_context = WellFormedTxContext::new(
             0,
+            Default::default(),
             tx_hash,
             Default::default(),
             Default::default(),
@@ -401,6 +412,7 @@ mod tests {
         let well_formed_encrypted_tx = WellFormedEncryptedTx::default();
         let well_formed_tx_context = WellFormedTxContext::new(
             0,
+            Default::default(),
             tx_hash,
             Default::default(),
             Default::default(),
@@ -484,6 +496,7 @@ mod tests {
         // Fill the cache with entries that have different tombstone blocks.
         for tombstone_block in 10..24 {
             let context = WellFormedTxContext::new(
+                Default::default(),
                 Default::default(),
                 TxHash([tombstone_block as u8; 32]),
                 tombstone_block,
@@ -642,6 +655,7 @@ mod tests {
         // Add transactions to the cache.
         for tx_hash in &tx_hashes {
             let context = WellFormedTxContext::new(
+                Default::default(),
                 Default::default(),
                 *tx_hash,
                 Default::default(),
@@ -685,6 +699,7 @@ mod tests {
         // Add some transactions, but not all, to the cache.
         for tx_hash in &tx_hashes[2..] {
             let context = WellFormedTxContext::new(
+                Default::default(),
                 Default::default(),
                 *tx_hash,
                 Default::default(),
@@ -815,6 +830,7 @@ mod tests {
         let tx_hashes: Vec<_> = (0..10).map(|i| TxHash([i as u8; 32])).collect();
         for tx_hash in &tx_hashes {
             let context = WellFormedTxContext::new(
+                Default::default(),
                 Default::default(),
                 *tx_hash,
                 Default::default(),
@@ -879,6 +895,7 @@ mod tests {
         let tx_hashes: Vec<_> = (0..10).map(|i| TxHash([i as u8; 32])).collect();
         for tx_hash in &tx_hashes {
             let context = WellFormedTxContext::new(
+                Default::default(),
                 Default::default(),
                 *tx_hash,
                 Default::default(),
@@ -939,6 +956,50 @@ mod tests {
         assert_eq!(tx_manager.get_encrypted_tx(&TxHash([88u8; 32])), None);
     }
 
+    #[test_with_logger]
+    // Should return the contexts of the cached transactions, skipping the rest.
+    fn test_get_well_formed_tx_contexts(logger: Logger) {
+        let mock_untrusted = MockUntrustedInterfaces::new();
+        let mock_enclave = MockConsensusEnclave::new();
+        let tx_manager = TxManagerImpl::new(mock_enclave, mock_untrusted, logger.clone());
+
+        // Add transactions to the cache.
+        let tx_hashes: Vec<_> = (0..3).map(|i| TxHash([i as u8; 32])).collect();
+        for (i, tx_hash) in tx_hashes.iter().enumerate() {
+            let context = WellFormedTxContext::new(
+                100 + i as u64,
+                TokenId::from(i as u64),
+                *tx_hash,
+                Default::default(),
+                Default::default(),
+                Default::default(),
+                Default::default(),
+            );
+
+            let cache_entry = CacheEntry {
+                encrypted_tx: Default::default(),
+                context: Arc::new(context),
+            };
+
+            tx_manager
+                .cache
+                .lock()
+                .unwrap()
+                .insert(*tx_hash, cache_entry);
+        }
+
+        let contexts = tx_manager.get_well_formed_tx_contexts(&[
+            tx_hashes[2],
+            TxHash([88u8; 32]),
+            tx_hashes[0],
+        ]);
+        assert_eq!(contexts.len(), 2);
+        assert_eq!(contexts[0].tx_hash(), &tx_hashes[2]);
+        assert_eq!(contexts[0].priority(), 102);
+        assert_eq!(contexts[0].fee_token_id(), TokenId::from(2));
+        assert_eq!(contexts[1].tx_hash(), &tx_hashes[0]);
+    }
+
     #[test_with_logger]
     // Should return the number of elements in the cache.
     fn test_get_num_entries(logger: Logger) {
@@ -953,6 +1014,7 @@ mod tests {
         let tx_hashes: Vec<_> = (0..10).map(|i| TxHash([i as u8; 32])).collect();
         for tx_hash in &tx_hashes {
             let context = WellFormedTxContext::new(
+                Default::default(),
                 Default::default(),
                 *tx_hash,
                 Default::default(),
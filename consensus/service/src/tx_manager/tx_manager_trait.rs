@@ -3,8 +3,9 @@
 use crate::tx_manager::TxManagerResult;
 use mc_attest_enclave_api::{EnclaveMessage, PeerSession};
 use mc_common::HashSet;
-use mc_consensus_enclave::{TxContext, WellFormedEncryptedTx};
+use mc_consensus_enclave::{TxContext, WellFormedEncryptedTx, WellFormedTxContext};
 use mc_transaction_core::tx::{TxHash, TxOutMembershipProof};
+use std::sync::Arc;
 
 #[cfg(test)]
 use mockall::*;
@@ -61,4 +62,8 @@ pub trait TxManager: Send {
 
     /// Get the encrypted transaction corresponding to the given hash.
     fn get_encrypted_tx(&self, tx_hash: &TxHash) -> Option<WellFormedEncryptedTx>;
+
+    /// Get the contexts of the transactions corresponding to the given hashes.
+    /// Hashes that are not in the cache are skipped.
+    fn get_well_formed_tx_contexts(&self, tx_hashes: &[TxHash]) -> Vec<Arc<WellFormedTxContext>>;
 }
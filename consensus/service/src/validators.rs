@@ -282,6 +282,7 @@ mod is_valid_tests {
             ];
 
             WellFormedTxContext::new(
+                Default::default(),
                 Default::default(),
                 Default::default(),
                 num_blocks + 17,
@@ -324,6 +325,7 @@ mod is_valid_tests {
         let num_blocks = 53;
 
         let well_formed_tx_context = WellFormedTxContext::new(
+            Default::default(),
             Default::default(),
             Default::default(),
             17, // The local ledger has advanced beyond the tombstone block.
@@ -357,6 +359,7 @@ mod is_valid_tests {
         let num_blocks = 53;
 
         let well_formed_tx_context = WellFormedTxContext::new(
+            Default::default(),
             Default::default(),
             Default::default(),
             num_blocks + MAX_TOMBSTONE_BLOCKS + 1,
@@ -396,6 +399,7 @@ mod is_valid_tests {
             ];
 
             WellFormedTxContext::new(
+                Default::default(),
                 Default::default(),
                 Default::default(),
                 num_blocks + 17,
@@ -448,6 +452,7 @@ mod is_valid_tests {
             ];
 
             WellFormedTxContext::new(
+                Default::default(),
                 Default::default(),
                 Default::default(),
                 num_blocks + 17,
@@ -1106,9 +1111,33 @@ mod combine_tests {
     #[test]
     // `combine` should return hashes in the order defined by WellformedTxContext.
     fn combine_sort_order() {
-        let a = WellFormedTxContext::new(100, TxHash([1u8; 32]), 0, vec![], vec![], vec![]);
-        let b = WellFormedTxContext::new(557, TxHash([2u8; 32]), 0, vec![], vec![], vec![]);
-        let c = WellFormedTxContext::new(88, TxHash([3u8; 32]), 0, vec![], vec![], vec![]);
+        let a = WellFormedTxContext::new(
+            100,
+            Default::default(),
+            TxHash([1u8; 32]),
+            0,
+            vec![],
+            vec![],
+            vec![],
+        );
+        let b = WellFormedTxContext::new(
+            557,
+            Default::default(),
+            TxHash([2u8; 32]),
+            0,
+            vec![],
+            vec![],
+            vec![],
+        );
+        let c = WellFormedTxContext::new(
+            88,
+            Default::default(),
+            TxHash([3u8; 32]),
+            0,
+            vec![],
+            vec![],
+            vec![],
+        );
 
         let tx_contexts = vec![a, b, c];
 
@@ -0,0 +1,133 @@
+// Copyright (c) 2018-2022 The MobileCoin Foundation
+#![deny(missing_docs)]
+
+//! Reference signer for the remote signing protocol.
+//!
+//! Holds an account key read from a keyfile, and signs the rings sent by a
+//! `RemoteRingSigner`, either over stdio or over a Unix domain socket.
+
+use clap::Parser;
+use mc_common::logger::{create_app_logger, log, o, Logger};
+use mc_crypto_ring_signature_signer::LocalRingSigner;
+use mc_crypto_ring_signature_signer_remote::serve_connection;
+use std::{env, io, path::PathBuf, sync::Arc};
+
+#[cfg(unix)]
+use std::{
+    fs,
+    os::unix::{
+        fs::{DirBuilderExt, PermissionsExt},
+        net::UnixListener,
+    },
+    path::Path,
+    process, thread,
+};
+
+/// Command line configuration for the reference signer.
+#[derive(Debug, Parser)]
+#[clap(
+    name = "mc-remote-ring-signer",
+    about = "Sign transaction inputs on behalf of a RemoteRingSigner",
+    version
+)]
+struct Config {
+    /// Path to the account keyfile (root entropy or mnemonic json).
+    #[clap(long, env = "MC_KEYFILE")]
+    pub keyfile: PathBuf,
+
+    /// Listen on this Unix domain socket instead of serving a single client
+    /// over stdin and stdout. The socket is only accessible by the current
+    /// user.
+    #[clap(long, env = "MC_LISTEN_UNIX")]
+    pub listen_unix: Option<PathBuf>,
+}
+
+fn main() {
+    let config = Config::parse();
+
+    // In stdio mode, stdout carries the protocol, so logs have to go elsewhere.
+    if config.listen_unix.is_none() {
+        env::set_var("MC_LOG_STDERR", "1");
+    }
+    let (logger, _global_logger_guard) = create_app_logger(o!());
+
+    let account_key =
+        mc_util_keyfile::read_keyfile(&config.keyfile).expect("Could not read keyfile");
+    let signer = Arc::new(LocalRingSigner::from(&account_key));
+
+    match config.listen_unix {
+        Some(path) => serve_unix(signer, path, logger),
+        None => {
+            log::info!(logger, "Serving sign requests over stdio");
+            serve_connection(
+                &*signer,
+                &mut io::stdin().lock(),
+                &mut io::stdout().lock(),
+                &logger,
+            )
+            .expect("Remote signer connection failed");
+        }
+    }
+}
+
+#[cfg(unix)]
+fn serve_unix(signer: Arc<LocalRingSigner>, path: PathBuf, logger: Logger) {
+    let listener = bind_private(&path).expect("Could not bind unix socket");
+    log::info!(logger, "Serving sign requests on {:?}", path);
+
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(err) => {
+                log::error!(logger, "Could not accept connection: {}", err);
+                continue;
+            }
+        };
+
+        let signer = signer.clone();
+        let logger = logger.clone();
+        thread::spawn(move || {
+            let mut reader = match stream.try_clone() {
+                Ok(reader) => reader,
+                Err(err) => {
+                    log::error!(logger, "Could not clone unix stream: {}", err);
+                    return;
+                }
+            };
+            let mut writer = stream;
+            if let Err(err) = serve_connection(&*signer, &mut reader, &mut writer, &logger) {
+                log::error!(logger, "Remote signer connection failed: {}", err);
+            }
+        });
+    }
+}
+
+/// Bind a Unix socket at `path` that only the current user can connect to.
+///
+/// The socket is bound inside a fresh 0700 directory next to `path`, restricted
+/// to 0600, and only then moved into place, so that no other user can connect
+/// in between.
+#[cfg(unix)]
+fn bind_private(path: &Path) -> io::Result<UnixListener> {
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let private_dir = parent.join(format!(".mc-remote-ring-signer.{}", process::id()));
+    fs::DirBuilder::new().mode(0o700).create(&private_dir)?;
+
+    let private_path = private_dir.join("signer.sock");
+    let result = UnixListener::bind(&private_path).and_then(|listener| {
+        fs::set_permissions(&private_path, fs::Permissions::from_mode(0o600))?;
+        fs::rename(&private_path, path)?;
+        Ok(listener)
+    });
+    let _ = fs::remove_file(&private_path);
+    let _ = fs::remove_dir(&private_dir);
+    result
+}
+
+#[cfg(not(unix))]
+fn serve_unix(_signer: Arc<LocalRingSigner>, _path: PathBuf, _logger: Logger) {
+    panic!("Unix domain sockets are not supported on this platform");
+}
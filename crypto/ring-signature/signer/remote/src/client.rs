@@ -0,0 +1,474 @@
+// Copyright (c) 2018-2022 The MobileCoin Foundation
+
+//! A RingSigner that forwards signing requests to an external signer.
+
+use crate::{
+    framing::{read_message, write_message},
+    messages::{SignRequest, SignResponse, SignResult},
+    Error, PROTOCOL_VERSION,
+};
+use mc_crypto_ring_signature::{CryptoRngCore, RingMLSAG, Scalar};
+use mc_crypto_ring_signature_signer::{Error as SignerError, RingSigner, SignableInputRing};
+use std::{
+    ffi::{OsStr, OsString},
+    io::{self, Read, Write},
+    path::Path,
+    process::{Child, Command, Stdio},
+    sync::Mutex,
+    time::Duration,
+};
+
+#[cfg(unix)]
+use std::os::unix::net::UnixStream;
+
+/// How long `connect_unix` waits on each read or write of the signer socket,
+/// e.g. for a signer to produce a signature, before giving up on the
+/// connection.
+pub const DEFAULT_SOCKET_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// An implementation of RingSigner that holds no keys, and instead sends each
+/// ring to an external signer process, e.g. one backed by an HSM.
+///
+/// Requests are sent one at a time over a single connection. The rng passed to
+/// `sign` is not used, the signer supplies its own randomness.
+///
+/// A connection that failed mid-request is dropped, since the stream may hold
+/// a partial frame. Signers created with `connect_unix` or `spawn` open a new
+/// connection for the next request.
+pub struct RemoteRingSigner {
+    connection: Mutex<Option<Connection>>,
+    reconnect: Option<Box<dyn Fn() -> io::Result<Connection> + Send + Sync>>,
+}
+
+struct Connection {
+    reader: Box<dyn Read + Send>,
+    writer: Box<dyn Write + Send>,
+    child: Option<Child>,
+}
+
+impl RemoteRingSigner {
+    /// Speak the signing protocol over an arbitrary pair of streams.
+    ///
+    /// Once the streams fail, every later request fails too.
+    pub fn new(reader: impl Read + Send + 'static, writer: impl Write + Send + 'static) -> Self {
+        Self {
+            connection: Mutex::new(Some(Connection {
+                reader: Box::new(reader),
+                writer: Box::new(writer),
+                child: None,
+            })),
+            reconnect: None,
+        }
+    }
+
+    /// Connect to a signer listening on a Unix domain socket, with
+    /// `DEFAULT_SOCKET_TIMEOUT` on each read and write.
+    #[cfg(unix)]
+    pub fn connect_unix(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::connect_unix_with_timeout(path, DEFAULT_SOCKET_TIMEOUT)
+    }
+
+    /// Connect to a signer listening on a Unix domain socket. A read or write
+    /// that takes longer than `timeout` fails the request, and the connection
+    /// is replaced for the next one.
+    #[cfg(unix)]
+    pub fn connect_unix_with_timeout(
+        path: impl AsRef<Path>,
+        timeout: Duration,
+    ) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        Self::with_reconnect(move || {
+            let stream = UnixStream::connect(&path)?;
+            stream.set_read_timeout(Some(timeout))?;
+            stream.set_write_timeout(Some(timeout))?;
+            Ok(Connection {
+                reader: Box::new(stream.try_clone()?),
+                writer: Box::new(stream),
+                child: None,
+            })
+        })
+    }
+
+    /// Spawn a signer process, and speak the signing protocol over its stdin
+    /// and stdout. The process is killed when this signer is dropped, or when
+    /// its connection fails and a new process is spawned in its place.
+    pub fn spawn(command: &mut Command) -> io::Result<Self> {
+        let program = command.get_program().to_owned();
+        let args: Vec<OsString> = command.get_args().map(OsStr::to_owned).collect();
+        let envs: Vec<(OsString, Option<OsString>)> = command
+            .get_envs()
+            .map(|(key, value)| (key.to_owned(), value.map(OsStr::to_owned)))
+            .collect();
+        let current_dir = command.get_current_dir().map(Path::to_path_buf);
+
+        Self::with_reconnect(move || {
+            let mut command = Command::new(&program);
+            command.args(&args);
+            for (key, value) in &envs {
+                match value {
+                    Some(value) => command.env(key, value),
+                    None => command.env_remove(key),
+                };
+            }
+            if let Some(dir) = &current_dir {
+                command.current_dir(dir);
+            }
+
+            let mut child = command
+                .stdin(Stdio::piped())
+                .stdout(Stdio::piped())
+                .spawn()?;
+
+            let stdin = child.stdin.take().expect("stdin is piped");
+            let stdout = child.stdout.take().expect("stdout is piped");
+            Ok(Connection {
+                reader: Box::new(stdout),
+                writer: Box::new(stdin),
+                child: Some(child),
+            })
+        })
+    }
+
+    /// Open the first connection now, so that a misconfigured signer is
+    /// reported at startup, and keep `connect` around to replace it.
+    fn with_reconnect(
+        connect: impl Fn() -> io::Result<Connection> + Send + Sync + 'static,
+    ) -> io::Result<Self> {
+        let connection = connect()?;
+        Ok(Self {
+            connection: Mutex::new(Some(connection)),
+            reconnect: Some(Box::new(connect)),
+        })
+    }
+
+    fn request(&self, request: &SignRequest) -> Result<SignResponse, Error> {
+        let mut guard = self.connection.lock().map_err(|_| Error::LockPoisoned)?;
+
+        let connection = match guard.take() {
+            Some(connection) => connection,
+            None => match &self.reconnect {
+                Some(connect) => connect()?,
+                None => return Err(Error::ConnectionClosed),
+            },
+        };
+        let connection = guard.insert(connection);
+
+        let result = Self::exchange(connection, request);
+        if let Err(err) = &result {
+            // These errors mean a whole frame was read, so the stream is still
+            // in sync. Anything else may leave it mid-frame.
+            if !matches!(err, Error::Decode(_) | Error::UnsupportedVersion(_)) {
+                *guard = None;
+            }
+        }
+        result
+    }
+
+    fn exchange(connection: &mut Connection, request: &SignRequest) -> Result<SignResponse, Error> {
+        write_message(&mut connection.writer, request)?;
+        let response: SignResponse =
+            read_message(&mut connection.reader)?.ok_or(Error::ConnectionClosed)?;
+
+        if response.version != PROTOCOL_VERSION {
+            return Err(Error::UnsupportedVersion(response.version));
+        }
+        Ok(response)
+    }
+}
+
+impl RingSigner for RemoteRingSigner {
+    fn sign(
+        &self,
+        message: &[u8],
+        signable_ring: &SignableInputRing,
+        output_blinding: Scalar,
+        _rng: &mut dyn CryptoRngCore,
+    ) -> Result<RingMLSAG, SignerError> {
+        let request = SignRequest::new(message, signable_ring, output_blinding);
+
+        match self.request(&request)?.result {
+            Some(SignResult::Signature(signature)) => Ok(signature),
+            Some(SignResult::Error(err)) => Err(err.into()),
+            None => Err(Error::MissingResult.into()),
+        }
+    }
+}
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        if let Some(mut child) = self.child.take() {
+            // Kill the child rather than wait for it to notice that its stdin
+            // is closed, since a stuck signer would never exit. Then reap it.
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::{
+        handle_request,
+        messages::{ErrorCode, SignResponse},
+        serve_connection,
+    };
+    use mc_account_keys::AccountKey;
+    use mc_common::logger::{test_with_logger, Logger};
+    use mc_crypto_keys::{RistrettoPrivate, RistrettoPublic};
+    use mc_crypto_ring_signature::{
+        generators,
+        onetime_keys::{
+            create_tx_out_public_key, create_tx_out_target_key, recover_onetime_private_key,
+        },
+        CompressedCommitment, KeyImage, ReducedTxOut,
+    };
+    use mc_crypto_ring_signature_signer::{InputSecret, LocalRingSigner, OneTimeKeyDeriveData};
+    use mc_transaction_types::{Amount, TokenId};
+    use mc_util_from_random::FromRandom;
+    use rand::{RngCore, SeedableRng};
+    use rand_hc::Hc128Rng;
+    use std::{os::unix::net::UnixListener, sync::mpsc, thread};
+    use tempdir::TempDir;
+
+    const SUBADDRESS_INDEX: u64 = 3;
+
+    /// A ring of random members, whose real input is owned by the given
+    /// account's subaddress.
+    fn signable_ring(
+        account_key: &AccountKey,
+        amount: Amount,
+        blinding: Scalar,
+        rng: &mut Hc128Rng,
+    ) -> SignableInputRing {
+        let generator = generators(*amount.token_id);
+        let mut members: Vec<ReducedTxOut> = (0..11)
+            .map(|_| ReducedTxOut {
+                public_key: RistrettoPublic::from_random(rng).into(),
+                target_key: RistrettoPublic::from_random(rng).into(),
+                commitment: CompressedCommitment::new(
+                    rng.next_u64(),
+                    Scalar::random(rng),
+                    &generator,
+                ),
+            })
+            .collect();
+
+        let subaddress = account_key.subaddress(SUBADDRESS_INDEX);
+        let tx_private_key = RistrettoPrivate::from_random(rng);
+        let real_input_index = 4;
+        members[real_input_index] = ReducedTxOut {
+            public_key: create_tx_out_public_key(&tx_private_key, subaddress.spend_public_key())
+                .into(),
+            target_key: create_tx_out_target_key(&tx_private_key, &subaddress).into(),
+            commitment: CompressedCommitment::new(amount.value, blinding, &generator),
+        };
+
+        SignableInputRing {
+            members,
+            real_input_index,
+            input_secret: InputSecret {
+                onetime_key_derive_data: OneTimeKeyDeriveData::SubaddressIndex(SUBADDRESS_INDEX),
+                amount,
+                blinding,
+            },
+        }
+    }
+
+    /// A RemoteRingSigner connected to a LocalRingSigner served on another
+    /// thread.
+    fn remote_signer(account_key: &AccountKey, logger: Logger) -> RemoteRingSigner {
+        let (client_stream, server_stream) = UnixStream::pair().unwrap();
+        let local_signer = LocalRingSigner::from(account_key);
+        thread::spawn(move || {
+            let mut reader = server_stream.try_clone().unwrap();
+            let mut writer = server_stream;
+            serve_connection(&local_signer, &mut reader, &mut writer, &logger).unwrap();
+        });
+
+        RemoteRingSigner::new(client_stream.try_clone().unwrap(), client_stream)
+    }
+
+    #[test_with_logger]
+    fn remote_signature_verifies(logger: Logger) {
+        let mut rng = Hc128Rng::from_seed([1u8; 32]);
+        let account_key = AccountKey::random(&mut rng);
+        let signer = remote_signer(&account_key, logger);
+
+        let amount = Amount::new(1_000_000, TokenId::from(1));
+        let blinding = Scalar::random(&mut rng);
+        let output_blinding = Scalar::random(&mut rng);
+        let ring = signable_ring(&account_key, amount, blinding, &mut rng);
+        let message = [7u8; 32];
+
+        // Sign several rings over the same connection.
+        for _ in 0..3 {
+            let signature = signer
+                .sign(&message, &ring, output_blinding, &mut rng)
+                .unwrap();
+
+            let output_commitment = CompressedCommitment::new(
+                amount.value,
+                output_blinding,
+                &generators(*amount.token_id),
+            );
+            signature
+                .verify(&message, &ring.members, &output_commitment)
+                .unwrap();
+
+            let real_input = &ring.members[ring.real_input_index];
+            let onetime_private_key = recover_onetime_private_key(
+                &RistrettoPublic::try_from(&real_input.public_key).unwrap(),
+                account_key.view_private_key(),
+                &account_key.subaddress_spend_private(SUBADDRESS_INDEX),
+            );
+            assert_eq!(signature.key_image, KeyImage::from(&onetime_private_key));
+        }
+    }
+
+    #[test_with_logger]
+    fn remote_errors_are_reported(logger: Logger) {
+        let mut rng = Hc128Rng::from_seed([2u8; 32]);
+        let account_key = AccountKey::random(&mut rng);
+        let other_account_key = AccountKey::random(&mut rng);
+        let signer = remote_signer(&other_account_key, logger);
+
+        let amount = Amount::new(1_000_000, TokenId::from(0));
+        let mut ring = signable_ring(&account_key, amount, Scalar::random(&mut rng), &mut rng);
+        assert_eq!(
+            signer.sign(&[0u8; 32], &ring, Scalar::random(&mut rng), &mut rng),
+            Err(SignerError::TrueInputNotOwned)
+        );
+
+        // The connection is still usable after an error.
+        ring.real_input_index = ring.members.len();
+        assert_eq!(
+            signer.sign(&[0u8; 32], &ring, Scalar::random(&mut rng), &mut rng),
+            Err(SignerError::RealInputIndexOutOfBounds)
+        );
+    }
+
+    #[test]
+    fn unsupported_version_is_rejected() {
+        let mut rng = Hc128Rng::from_seed([3u8; 32]);
+        let account_key = AccountKey::random(&mut rng);
+        let ring = signable_ring(
+            &account_key,
+            Amount::new(10, TokenId::from(0)),
+            Scalar::random(&mut rng),
+            &mut rng,
+        );
+
+        let mut request = SignRequest::new(&[0u8; 32], &ring, Scalar::random(&mut rng));
+        request.version = PROTOCOL_VERSION + 1;
+
+        let response = handle_request(&LocalRingSigner::from(&account_key), &request, &mut rng);
+        match &response.result {
+            Some(SignResult::Error(err)) => assert_eq!(err.code(), ErrorCode::UnsupportedVersion),
+            _ => panic!("expected an error, got {:?}", response),
+        }
+
+        // A request missing its ring is invalid.
+        let response: SignResponse = handle_request(
+            &LocalRingSigner::from(&account_key),
+            &SignRequest {
+                version: PROTOCOL_VERSION,
+                ..Default::default()
+            },
+            &mut rng,
+        );
+        match &response.result {
+            Some(SignResult::Error(err)) => assert_eq!(err.code(), ErrorCode::InvalidRequest),
+            _ => panic!("expected an error, got {:?}", response),
+        }
+    }
+
+    #[test]
+    fn closed_connection_is_reported() {
+        let mut rng = Hc128Rng::from_seed([4u8; 32]);
+        let account_key = AccountKey::random(&mut rng);
+        let ring = signable_ring(
+            &account_key,
+            Amount::new(10, TokenId::from(0)),
+            Scalar::random(&mut rng),
+            &mut rng,
+        );
+
+        let (client_stream, server_stream) = UnixStream::pair().unwrap();
+        drop(server_stream);
+        let signer = RemoteRingSigner::new(client_stream.try_clone().unwrap(), client_stream);
+
+        assert!(matches!(
+            signer.sign(&[0u8; 32], &ring, Scalar::random(&mut rng), &mut rng),
+            Err(SignerError::ConnectionFailed(_))
+        ));
+    }
+
+    #[test_with_logger]
+    fn unix_signer_reconnects_after_connection_failure(logger: Logger) {
+        let mut rng = Hc128Rng::from_seed([5u8; 32]);
+        let account_key = AccountKey::random(&mut rng);
+        let ring = signable_ring(
+            &account_key,
+            Amount::new(10, TokenId::from(0)),
+            Scalar::random(&mut rng),
+            &mut rng,
+        );
+
+        let dir = TempDir::new("remote-signer").unwrap();
+        let path = dir.path().join("signer.sock");
+        let listener = UnixListener::bind(&path).unwrap();
+        let local_signer = LocalRingSigner::from(&account_key);
+        thread::spawn(move || {
+            // Hang up on the first connection without answering.
+            drop(listener.accept().unwrap());
+
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = stream.try_clone().unwrap();
+            let mut writer = stream;
+            serve_connection(&local_signer, &mut reader, &mut writer, &logger).unwrap();
+        });
+
+        let signer = RemoteRingSigner::connect_unix(&path).unwrap();
+        assert!(matches!(
+            signer.sign(&[0u8; 32], &ring, Scalar::random(&mut rng), &mut rng),
+            Err(SignerError::ConnectionFailed(_))
+        ));
+
+        // The failed connection was dropped, and the next request uses a new
+        // one.
+        signer
+            .sign(&[0u8; 32], &ring, Scalar::random(&mut rng), &mut rng)
+            .unwrap();
+    }
+
+    #[test]
+    fn unresponsive_unix_signer_times_out() {
+        let mut rng = Hc128Rng::from_seed([6u8; 32]);
+        let account_key = AccountKey::random(&mut rng);
+        let ring = signable_ring(
+            &account_key,
+            Amount::new(10, TokenId::from(0)),
+            Scalar::random(&mut rng),
+            &mut rng,
+        );
+
+        let dir = TempDir::new("remote-signer").unwrap();
+        let path = dir.path().join("signer.sock");
+        let listener = UnixListener::bind(&path).unwrap();
+        let (done_tx, done_rx) = mpsc::channel::<()>();
+        thread::spawn(move || {
+            // Accept the connection, but never answer.
+            let _stream = listener.accept().unwrap();
+            let _ = done_rx.recv();
+        });
+
+        let signer =
+            RemoteRingSigner::connect_unix_with_timeout(&path, Duration::from_millis(100)).unwrap();
+        assert!(matches!(
+            signer.sign(&[0u8; 32], &ring, Scalar::random(&mut rng), &mut rng),
+            Err(SignerError::ConnectionFailed(_))
+        ));
+        drop(done_tx);
+    }
+}
@@ -0,0 +1,54 @@
+// Copyright (c) 2018-2022 The MobileCoin Foundation
+
+//! Errors of the remote signing protocol transport.
+
+use displaydoc::Display;
+use mc_crypto_ring_signature_signer::Error as SignerError;
+use prost::DecodeError;
+use std::io;
+
+/// An error talking to the other side of a remote signer connection.
+#[derive(Debug, Display)]
+pub enum Error {
+    /// IO error: {0}
+    Io(io::Error),
+
+    /// Could not decode message: {0}
+    Decode(DecodeError),
+
+    /// Frame of {0} bytes exceeds the maximum frame length
+    FrameTooLarge(usize),
+
+    /// Connection closed after {0} bytes of a frame length prefix
+    TruncatedLengthPrefix(usize),
+
+    /// Connection closed by the peer
+    ConnectionClosed,
+
+    /// Unsupported protocol version {0}
+    UnsupportedVersion(u32),
+
+    /// Response does not hold a result
+    MissingResult,
+
+    /// Connection lock poisoned
+    LockPoisoned,
+}
+
+impl From<io::Error> for Error {
+    fn from(src: io::Error) -> Self {
+        Self::Io(src)
+    }
+}
+
+impl From<DecodeError> for Error {
+    fn from(src: DecodeError) -> Self {
+        Self::Decode(src)
+    }
+}
+
+impl From<Error> for SignerError {
+    fn from(src: Error) -> Self {
+        Self::ConnectionFailed(src.to_string())
+    }
+}
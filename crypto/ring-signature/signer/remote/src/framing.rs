@@ -0,0 +1,110 @@
+// Copyright (c) 2018-2022 The MobileCoin Foundation
+
+//! Length-prefixed framing of protocol messages.
+//!
+//! Each message is preceded by its length, as a 4 byte big-endian integer.
+
+use crate::Error;
+use prost::Message;
+use std::io::{ErrorKind, Read, Write};
+
+/// The largest frame either side accepts. Requests for rings of the maximum
+/// size are a few kilobytes, so this leaves plenty of headroom while keeping a
+/// misbehaving peer from making us allocate arbitrary amounts of memory.
+pub const MAX_FRAME_LEN: u32 = 1024 * 1024;
+
+/// Encode a message and write it as a single frame.
+pub fn write_message<M: Message>(writer: &mut impl Write, message: &M) -> Result<(), Error> {
+    let bytes = mc_util_serial::encode(message);
+    let len = u32::try_from(bytes.len())
+        .ok()
+        .filter(|len| *len <= MAX_FRAME_LEN)
+        .ok_or(Error::FrameTooLarge(bytes.len()))?;
+
+    writer.write_all(&len.to_be_bytes())?;
+    writer.write_all(&bytes)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Read a single frame and decode it.
+///
+/// Returns `Ok(None)` if the peer closed the stream cleanly before the start of
+/// a frame. A stream closed partway through the length prefix is an error.
+pub fn read_message<M: Message + Default>(reader: &mut impl Read) -> Result<Option<M>, Error> {
+    let mut len_bytes = [0u8; 4];
+    let mut num_read = 0;
+    while num_read < len_bytes.len() {
+        match reader.read(&mut len_bytes[num_read..]) {
+            Ok(0) if num_read == 0 => return Ok(None),
+            Ok(0) => return Err(Error::TruncatedLengthPrefix(num_read)),
+            Ok(n) => num_read += n,
+            Err(err) if err.kind() == ErrorKind::Interrupted => {}
+            Err(err) => return Err(err.into()),
+        }
+    }
+
+    let len = u32::from_be_bytes(len_bytes);
+    if len > MAX_FRAME_LEN {
+        return Err(Error::FrameTooLarge(len as usize));
+    }
+
+    let mut bytes = vec![0u8; len as usize];
+    reader.read_exact(&mut bytes)?;
+    Ok(Some(mc_util_serial::decode(&bytes)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::messages::RemoteError;
+    use std::io::Cursor;
+
+    #[test]
+    fn round_trip() {
+        let message = RemoteError::invalid_request("test");
+
+        let mut buf = Vec::new();
+        write_message(&mut buf, &message).unwrap();
+        write_message(&mut buf, &message).unwrap();
+
+        let mut reader = Cursor::new(buf);
+        let first: Option<RemoteError> = read_message(&mut reader).unwrap();
+        let second: Option<RemoteError> = read_message(&mut reader).unwrap();
+        let end: Option<RemoteError> = read_message(&mut reader).unwrap();
+        assert_eq!(first, Some(message.clone()));
+        assert_eq!(second, Some(message));
+        assert_eq!(end, None);
+    }
+
+    #[test]
+    fn oversized_frame_is_rejected() {
+        let mut reader = Cursor::new((MAX_FRAME_LEN + 1).to_be_bytes().to_vec());
+        assert!(matches!(
+            read_message::<RemoteError>(&mut reader),
+            Err(Error::FrameTooLarge(_))
+        ));
+    }
+
+    #[test]
+    fn truncated_length_prefix_is_an_error() {
+        let mut reader = Cursor::new(vec![0u8, 0]);
+        assert!(matches!(
+            read_message::<RemoteError>(&mut reader),
+            Err(Error::TruncatedLengthPrefix(2))
+        ));
+    }
+
+    #[test]
+    fn truncated_frame_is_an_error() {
+        let mut buf = Vec::new();
+        write_message(&mut buf, &RemoteError::invalid_request("test")).unwrap();
+        buf.pop();
+
+        let mut reader = Cursor::new(buf);
+        assert!(matches!(
+            read_message::<RemoteError>(&mut reader),
+            Err(Error::Io(_))
+        ));
+    }
+}
@@ -0,0 +1,27 @@
+// Copyright (c) 2018-2022 The MobileCoin Foundation
+
+//! A RingSigner that keeps spend keys out of the application process.
+//!
+//! `RemoteRingSigner` sends each `SignableInputRing` to an external signer over
+//! a Unix socket or the stdio of a child process, using the protocol described
+//! in `proto/remote_signer.proto` and the README. `serve_connection` implements
+//! the signer side of the protocol on top of any `RingSigner`, and the
+//! `mc-remote-ring-signer` binary is a reference signer holding an account key.
+
+#![deny(missing_docs)]
+
+mod client;
+mod error;
+mod server;
+
+pub mod framing;
+pub mod messages;
+
+pub use client::{RemoteRingSigner, DEFAULT_SOCKET_TIMEOUT};
+pub use error::Error;
+pub use server::{handle_request, serve_connection};
+
+/// The version of the signing protocol implemented by this crate. Requests and
+/// responses carry the version of their sender, and signers reject requests
+/// for a version they do not speak.
+pub const PROTOCOL_VERSION: u32 = 1;
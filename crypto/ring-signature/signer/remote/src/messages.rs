@@ -0,0 +1,309 @@
+// Copyright (c) 2018-2022 The MobileCoin Foundation
+
+//! Wire types of the remote signing protocol.
+//!
+//! These mirror `proto/remote_signer.proto`. Key, scalar and commitment fields
+//! use the same encoding as the corresponding messages in `external.proto`, so
+//! implementations in other languages can reuse those definitions.
+
+use crate::PROTOCOL_VERSION;
+use mc_crypto_keys::{CompressedRistrettoPublic, RistrettoPrivate};
+use mc_crypto_ring_signature::{
+    CompressedCommitment, CurveScalar, ReducedTxOut, RingMLSAG, Scalar,
+};
+use mc_crypto_ring_signature_signer::{
+    Error as SignerError, InputSecret as SignerInputSecret, OneTimeKeyDeriveData, SignableInputRing,
+};
+use mc_transaction_types::{Amount, TokenId};
+use prost::{Enumeration, Message, Oneof};
+
+/// A request to sign a single input ring.
+#[derive(Clone, Message)]
+pub struct SignRequest {
+    /// The protocol version the client speaks.
+    #[prost(uint32, tag = "1")]
+    pub version: u32,
+
+    /// The digest of the transaction context to sign.
+    #[prost(bytes, tag = "2")]
+    pub message: Vec<u8>,
+
+    /// The ring to sign, and the secrets of its real input.
+    #[prost(message, optional, tag = "3")]
+    pub ring: Option<SignableRing>,
+
+    /// The blinding factor of the resulting pseudo-output.
+    #[prost(message, optional, tag = "4")]
+    pub output_blinding: Option<CurveScalar>,
+}
+
+/// An input ring, as in `SignableInputRing`.
+#[derive(Clone, Message)]
+pub struct SignableRing {
+    /// The ring members.
+    #[prost(message, repeated, tag = "1")]
+    pub members: Vec<RingMember>,
+
+    /// The index of the real input among the ring members.
+    #[prost(uint64, tag = "2")]
+    pub real_input_index: u64,
+
+    /// The secrets needed to spend the real input.
+    #[prost(message, optional, tag = "3")]
+    pub input_secret: Option<InputSecret>,
+}
+
+/// A ring member, as in `ReducedTxOut`.
+#[derive(Clone, Message)]
+pub struct RingMember {
+    /// The tx_out.public_key field.
+    #[prost(message, required, tag = "1")]
+    pub public_key: CompressedRistrettoPublic,
+
+    /// The tx_out.target_key field.
+    #[prost(message, required, tag = "2")]
+    pub target_key: CompressedRistrettoPublic,
+
+    /// The tx_out.masked_amount.commitment field.
+    #[prost(message, required, tag = "3")]
+    pub commitment: CompressedCommitment,
+}
+
+/// The secrets of the real input, as in `InputSecret`.
+#[derive(Clone, Message)]
+pub struct InputSecret {
+    /// The onetime private key, or how to derive it.
+    #[prost(oneof = "OneTimeKey", tags = "1, 2")]
+    pub onetime_key: Option<OneTimeKey>,
+
+    /// The value of the real input.
+    #[prost(uint64, tag = "3")]
+    pub value: u64,
+
+    /// The token id of the real input.
+    #[prost(fixed64, tag = "4")]
+    pub token_id: u64,
+
+    /// The blinding factor of the real input.
+    #[prost(message, optional, tag = "5")]
+    pub blinding: Option<CurveScalar>,
+}
+
+/// How the signer gets the onetime private key of the real input.
+#[derive(Clone, Oneof)]
+pub enum OneTimeKey {
+    /// The onetime private key itself, e.g. for gift code inputs.
+    #[prost(message, tag = "1")]
+    OnetimePrivateKey(RistrettoPrivate),
+
+    /// The subaddress index owning the input. The signer derives the onetime
+    /// private key from its account keys.
+    #[prost(uint64, tag = "2")]
+    SubaddressIndex(u64),
+}
+
+/// The response to a `SignRequest`.
+#[derive(Clone, Message)]
+pub struct SignResponse {
+    /// The protocol version the signer speaks.
+    #[prost(uint32, tag = "1")]
+    pub version: u32,
+
+    /// The signature, or why signing failed.
+    #[prost(oneof = "SignResult", tags = "2, 3")]
+    pub result: Option<SignResult>,
+}
+
+/// The outcome of a `SignRequest`.
+#[derive(Clone, Oneof)]
+pub enum SignResult {
+    /// The signature over the ring.
+    #[prost(message, tag = "2")]
+    Signature(RingMLSAG),
+
+    /// Signing failed.
+    #[prost(message, tag = "3")]
+    Error(RemoteError),
+}
+
+/// An error reported by the signer.
+#[derive(Clone, Eq, PartialEq, Message)]
+pub struct RemoteError {
+    /// What kind of error occurred.
+    #[prost(enumeration = "ErrorCode", tag = "1")]
+    pub code: i32,
+
+    /// A human readable description of the error.
+    #[prost(string, tag = "2")]
+    pub message: String,
+}
+
+/// The kinds of errors a signer can report.
+#[derive(Clone, Copy, Debug, Enumeration, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[repr(i32)]
+pub enum ErrorCode {
+    /// An error not covered by the other codes.
+    Unknown = 0,
+    /// The signer does not support the request's protocol version.
+    UnsupportedVersion = 1,
+    /// The request is missing fields or holds invalid values.
+    InvalidRequest = 2,
+    /// The real input is not owned by the signer's account.
+    TrueInputNotOwned = 3,
+    /// The real input index is out of the ring's bounds.
+    RealInputIndexOutOfBounds = 4,
+    /// The signer has no way to get the onetime private key.
+    NoPathToSpendKey = 5,
+}
+
+impl SignRequest {
+    /// Build a request for the given ring.
+    pub fn new(message: &[u8], ring: &SignableInputRing, output_blinding: Scalar) -> Self {
+        let input_secret = &ring.input_secret;
+        let onetime_key = match &input_secret.onetime_key_derive_data {
+            OneTimeKeyDeriveData::OneTimeKey(key) => OneTimeKey::OnetimePrivateKey(*key),
+            OneTimeKeyDeriveData::SubaddressIndex(index) => OneTimeKey::SubaddressIndex(*index),
+        };
+
+        Self {
+            version: PROTOCOL_VERSION,
+            message: message.to_vec(),
+            ring: Some(SignableRing {
+                members: ring.members.iter().map(RingMember::from).collect(),
+                real_input_index: ring.real_input_index as u64,
+                input_secret: Some(InputSecret {
+                    onetime_key: Some(onetime_key),
+                    value: input_secret.amount.value,
+                    token_id: *input_secret.amount.token_id,
+                    blinding: Some(CurveScalar::from(input_secret.blinding)),
+                }),
+            }),
+            output_blinding: Some(CurveScalar::from(output_blinding)),
+        }
+    }
+
+    /// The ring to sign, in the form `RingSigner` expects.
+    pub fn signable_ring(&self) -> Result<SignableInputRing, RemoteError> {
+        let ring = self
+            .ring
+            .as_ref()
+            .ok_or_else(|| RemoteError::invalid_request("missing ring"))?;
+        let input_secret = ring
+            .input_secret
+            .as_ref()
+            .ok_or_else(|| RemoteError::invalid_request("missing input_secret"))?;
+
+        let onetime_key_derive_data = match input_secret.onetime_key.as_ref() {
+            Some(OneTimeKey::OnetimePrivateKey(key)) => OneTimeKeyDeriveData::OneTimeKey(*key),
+            Some(OneTimeKey::SubaddressIndex(index)) => {
+                OneTimeKeyDeriveData::SubaddressIndex(*index)
+            }
+            None => return Err(RemoteError::invalid_request("missing onetime_key")),
+        };
+        let blinding = input_secret
+            .blinding
+            .ok_or_else(|| RemoteError::invalid_request("missing blinding"))?;
+
+        Ok(SignableInputRing {
+            members: ring.members.iter().map(ReducedTxOut::from).collect(),
+            real_input_index: ring.real_input_index as usize,
+            input_secret: SignerInputSecret {
+                onetime_key_derive_data,
+                amount: Amount::new(input_secret.value, TokenId::from(input_secret.token_id)),
+                blinding: blinding.scalar,
+            },
+        })
+    }
+
+    /// The blinding factor of the resulting pseudo-output.
+    pub fn output_blinding(&self) -> Result<Scalar, RemoteError> {
+        self.output_blinding
+            .map(|blinding| blinding.scalar)
+            .ok_or_else(|| RemoteError::invalid_request("missing output_blinding"))
+    }
+}
+
+impl SignResponse {
+    /// A response holding a signature.
+    pub fn signature(signature: RingMLSAG) -> Self {
+        Self {
+            version: PROTOCOL_VERSION,
+            result: Some(SignResult::Signature(signature)),
+        }
+    }
+
+    /// A response holding an error.
+    pub fn error(error: RemoteError) -> Self {
+        Self {
+            version: PROTOCOL_VERSION,
+            result: Some(SignResult::Error(error)),
+        }
+    }
+}
+
+impl RemoteError {
+    /// Create an error with the given code.
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            code: code as i32,
+            message: message.into(),
+        }
+    }
+
+    /// Create an `InvalidRequest` error.
+    pub fn invalid_request(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::InvalidRequest, message)
+    }
+
+    /// The error code, treating codes this version does not know as
+    /// `Unknown`.
+    pub fn code(&self) -> ErrorCode {
+        ErrorCode::from_i32(self.code).unwrap_or(ErrorCode::Unknown)
+    }
+}
+
+impl From<&SignerError> for RemoteError {
+    fn from(src: &SignerError) -> Self {
+        let code = match src {
+            SignerError::TrueInputNotOwned => ErrorCode::TrueInputNotOwned,
+            SignerError::RealInputIndexOutOfBounds => ErrorCode::RealInputIndexOutOfBounds,
+            SignerError::NoPathToSpendKey => ErrorCode::NoPathToSpendKey,
+            _ => ErrorCode::Unknown,
+        };
+        Self::new(code, src.to_string())
+    }
+}
+
+impl From<RemoteError> for SignerError {
+    fn from(src: RemoteError) -> Self {
+        match src.code() {
+            ErrorCode::TrueInputNotOwned => SignerError::TrueInputNotOwned,
+            ErrorCode::RealInputIndexOutOfBounds => SignerError::RealInputIndexOutOfBounds,
+            ErrorCode::NoPathToSpendKey => SignerError::NoPathToSpendKey,
+            code => SignerError::ConnectionFailed(format!(
+                "remote signer error {:?}: {}",
+                code, src.message
+            )),
+        }
+    }
+}
+
+impl From<&ReducedTxOut> for RingMember {
+    fn from(src: &ReducedTxOut) -> Self {
+        Self {
+            public_key: src.public_key,
+            target_key: src.target_key,
+            commitment: src.commitment,
+        }
+    }
+}
+
+impl From<&RingMember> for ReducedTxOut {
+    fn from(src: &RingMember) -> Self {
+        Self {
+            public_key: src.public_key,
+            target_key: src.target_key,
+            commitment: src.commitment,
+        }
+    }
+}
@@ -0,0 +1,85 @@
+// Copyright (c) 2018-2022 The MobileCoin Foundation
+
+//! The signer side of the remote signing protocol.
+
+use crate::{
+    framing::{read_message, write_message},
+    messages::{ErrorCode, RemoteError, SignRequest, SignResponse, SignResult},
+    Error, PROTOCOL_VERSION,
+};
+use mc_common::logger::{log, Logger};
+use mc_crypto_rand::McRng;
+use mc_crypto_ring_signature::CryptoRngCore;
+use mc_crypto_ring_signature_signer::RingSigner;
+use std::io::{Read, Write};
+
+/// Answer a single signing request using the given signer.
+pub fn handle_request<S: RingSigner + ?Sized>(
+    signer: &S,
+    request: &SignRequest,
+    rng: &mut dyn CryptoRngCore,
+) -> SignResponse {
+    if request.version != PROTOCOL_VERSION {
+        return SignResponse::error(RemoteError::new(
+            ErrorCode::UnsupportedVersion,
+            format!(
+                "requested version {}, signer speaks version {}",
+                request.version, PROTOCOL_VERSION
+            ),
+        ));
+    }
+
+    let result = request.signable_ring().and_then(|ring| {
+        let output_blinding = request.output_blinding()?;
+        signer
+            .sign(&request.message, &ring, output_blinding, rng)
+            .map_err(|err| RemoteError::from(&err))
+    });
+
+    match result {
+        Ok(signature) => SignResponse::signature(signature),
+        Err(err) => SignResponse::error(err),
+    }
+}
+
+/// Serve signing requests read from `reader`, writing the responses to
+/// `writer`, until the peer closes the connection.
+pub fn serve_connection<S: RingSigner + ?Sized>(
+    signer: &S,
+    reader: &mut impl Read,
+    writer: &mut impl Write,
+    logger: &Logger,
+) -> Result<(), Error> {
+    let mut rng = McRng::default();
+
+    loop {
+        let request = match read_message::<SignRequest>(reader) {
+            Ok(Some(request)) => request,
+            Ok(None) => {
+                log::debug!(logger, "Remote signer connection closed");
+                return Ok(());
+            }
+            // The frame was read in full, so the stream is still usable.
+            Err(Error::Decode(err)) => {
+                log::warn!(logger, "Could not decode sign request: {}", err);
+                write_message(
+                    writer,
+                    &SignResponse::error(RemoteError::invalid_request(err.to_string())),
+                )?;
+                continue;
+            }
+            Err(err) => return Err(err),
+        };
+
+        let response = handle_request(signer, &request, &mut rng);
+        if let Some(SignResult::Error(err)) = response.result.as_ref() {
+            log::info!(
+                logger,
+                "Sign request failed: {:?}: {}",
+                err.code(),
+                err.message
+            );
+        }
+        write_message(writer, &response)?;
+    }
+}
@@ -100,14 +100,33 @@ pub fn create_tx_out_target_key(
     tx_private_key: &RistrettoPrivate,
     recipient: &impl RingCtAddress,
 ) -> RistrettoPublic {
-    // `Hs( r * C)`
-    let Hs: Scalar = {
+    // `r * C`
+    let shared_secret = {
         let r = tx_private_key.as_ref();
         let C = recipient.view_public_key().as_ref();
-        hash_to_scalar(r * C)
+        RistrettoPublic::from(r * C)
     };
 
-    let D = recipient.spend_public_key().as_ref();
+    create_tx_out_target_key_from_shared_secret(&shared_secret, recipient.spend_public_key())
+}
+
+/// Creates target_key `Hs( s ) * G + D` for an output sent to subaddress
+/// (C, D), from the output's shared secret `s = r * C`.
+///
+/// This lets whoever knows the shared secret, but not `r`, check which
+/// subaddress spend key an output pays.
+///
+/// # Arguments
+/// * `shared_secret` - The output's shared secret `s`.
+/// * `recipient_spend_key` - The recipient's public subaddress spend key `D`.
+pub fn create_tx_out_target_key_from_shared_secret(
+    shared_secret: &RistrettoPublic,
+    recipient_spend_key: &RistrettoPublic,
+) -> RistrettoPublic {
+    // `Hs( s )`
+    let Hs: Scalar = hash_to_scalar(*shared_secret.as_ref());
+
+    let D = recipient_spend_key.as_ref();
     RistrettoPublic::from(Hs * G + D)
 }
 
@@ -265,6 +284,44 @@ mod tests {
         })
     }
 
+    #[test]
+    // The target key computed from the shared secret `r * C` should match the
+    // one computed from `r`.
+    fn test_create_target_key_from_shared_secret() {
+        run_with_several_seeds(|mut rng| {
+            let account: AccountKey = AccountKey::random(&mut rng);
+            let recipient = account.subaddress(7);
+
+            let tx_private_key = RistrettoPrivate::from_random(&mut rng);
+            let (tx_target_key, tx_public_key) =
+                get_output_public_keys(&tx_private_key, &recipient);
+
+            // The sender computes `r * C`, the recipient `c * R`.
+            let shared_secret = create_shared_secret(recipient.view_public_key(), &tx_private_key);
+            assert_eq!(
+                shared_secret,
+                create_shared_secret(&tx_public_key, account.view_private_key())
+            );
+
+            assert_eq!(
+                tx_target_key,
+                create_tx_out_target_key_from_shared_secret(
+                    &shared_secret,
+                    recipient.spend_public_key()
+                )
+            );
+
+            let other_recipient = AccountKey::random(&mut rng).default_subaddress();
+            assert_ne!(
+                tx_target_key,
+                create_tx_out_target_key_from_shared_secret(
+                    &shared_secret,
+                    other_recipient.spend_public_key()
+                )
+            );
+        })
+    }
+
     #[test]
     // Should return `r * D`.
     fn test_create_tx_public_key() {
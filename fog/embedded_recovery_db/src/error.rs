@@ -0,0 +1,75 @@
+// Copyright (c) 2018-2022 The MobileCoin Foundation
+
+use displaydoc::Display;
+use lmdb::Error as LmdbError;
+use mc_crypto_keys::CompressedRistrettoPublic;
+use mc_fog_recovery_db_iface::{IngestInvocationId, RecoveryDbError};
+use mc_util_lmdb::MetadataStoreError;
+
+/// Enum for error types.
+#[derive(Display, Debug)]
+pub enum Error {
+    /// LMDB: {0}
+    Lmdb(LmdbError),
+
+    /// Metadata store: {0}
+    MetadataStore(MetadataStoreError),
+
+    /// The following ingress key was not found: {0:?}
+    MissingIngressKey(CompressedRistrettoPublic),
+
+    /// The following ingress key already exists: {0:?}
+    DuplicateIngressKey(CompressedRistrettoPublic),
+
+    /// The following ingest invocation was not found: {0}
+    MissingIngestInvocation(IngestInvocationId),
+
+    /// Database url is not supported by the embedded recovery db: {0}
+    UnsupportedUrl(String),
+
+    /// The data could not be encoded for storage in the database
+    Serialization,
+
+    /// The data in the database could not be decoded
+    Deserialization,
+}
+
+impl RecoveryDbError for Error {
+    /// Policy decision, whether the call should be retried.
+    ///
+    /// There is no network connection involved, so none of our errors are
+    /// transient.
+    fn should_retry(&self) -> bool {
+        false
+    }
+}
+
+impl From<LmdbError> for Error {
+    fn from(src: LmdbError) -> Self {
+        Self::Lmdb(src)
+    }
+}
+
+impl From<MetadataStoreError> for Error {
+    fn from(src: MetadataStoreError) -> Self {
+        Self::MetadataStore(src)
+    }
+}
+
+impl From<mc_util_serial::encode::Error> for Error {
+    fn from(_: mc_util_serial::encode::Error) -> Self {
+        Self::Serialization
+    }
+}
+
+impl From<mc_util_serial::decode::Error> for Error {
+    fn from(_: mc_util_serial::decode::Error) -> Self {
+        Self::Deserialization
+    }
+}
+
+impl From<mc_util_serial::DecodeError> for Error {
+    fn from(_: mc_util_serial::DecodeError) -> Self {
+        Self::Deserialization
+    }
+}
@@ -0,0 +1,470 @@
+// Copyright (c) 2018-2022 The MobileCoin Foundation
+#![deny(missing_docs)]
+
+//! Recovery db implementation that does not require a database server.
+//!
+//! Data is either kept in memory, or persisted to an LMDB database on disk.
+//! This is intended for local development and hermetic tests, see README.md
+//! for details.
+
+pub use error::Error;
+
+mod error;
+mod state;
+mod storage;
+
+use mc_blockchain_types::Block;
+use mc_common::logger::{log, Logger};
+use mc_crypto_keys::CompressedRistrettoPublic;
+use mc_fog_kex_rng::KexRngPubkey;
+use mc_fog_recovery_db_iface::{
+    AddBlockDataStatus, FogUserEvent, IngestInvocationId, IngestableRange, IngressPublicKeyRecord,
+    IngressPublicKeyRecordFilters, IngressPublicKeyStatus, RecoveryDb, ReportData, ReportDb,
+};
+use mc_fog_types::{common::BlockRange, view::TxOutSearchResult, ETxOutRecord};
+use std::path::Path;
+use storage::Storage;
+
+/// The url scheme selecting the LMDB flavor of the embedded recovery db, e.g.
+/// `lmdb:///var/lib/fog/recovery-db`.
+pub const LMDB_URL_SCHEME: &str = "lmdb://";
+
+/// Embedded recovery database.
+#[derive(Clone)]
+pub struct EmbeddedRecoveryDb {
+    storage: Storage,
+    logger: Logger,
+}
+
+impl EmbeddedRecoveryDb {
+    /// Create a new, empty, instance that keeps all data in memory.
+    /// Clones of the returned object share the same data.
+    pub fn new_in_memory(logger: Logger) -> Self {
+        Self {
+            storage: Storage::new_in_memory(),
+            logger,
+        }
+    }
+
+    /// Open (or create) an instance that persists all data to an LMDB
+    /// database at the given directory. The directory must already exist.
+    pub fn open(path: impl AsRef<Path>, logger: Logger) -> Result<Self, Error> {
+        log::info!(
+            logger,
+            "Opening embedded recovery db at {:?}",
+            path.as_ref()
+        );
+        Ok(Self {
+            storage: Storage::open_lmdb(path.as_ref(), &logger)?,
+            logger,
+        })
+    }
+
+    /// Check whether a database url refers to an embedded recovery db.
+    pub fn is_embedded_url(database_url: &str) -> bool {
+        database_url.starts_with(LMDB_URL_SCHEME)
+    }
+
+    /// Open (or create) an instance using a database url of the form
+    /// `lmdb://<path>`.
+    pub fn new_from_url(database_url: &str, logger: Logger) -> Result<Self, Error> {
+        let path = database_url
+            .strip_prefix(LMDB_URL_SCHEME)
+            .ok_or_else(|| Error::UnsupportedUrl(database_url.to_owned()))?;
+        Self::open(path, logger)
+    }
+}
+
+/// See trait `fog_recovery_db_iface::RecoveryDb` for documentation.
+impl RecoveryDb for EmbeddedRecoveryDb {
+    type Error = Error;
+
+    fn get_ingress_key_status(
+        &self,
+        key: &CompressedRistrettoPublic,
+    ) -> Result<Option<IngressPublicKeyStatus>, Self::Error> {
+        self.storage
+            .read(|state, _| Ok(state.get_ingress_key_status(key)))
+    }
+
+    fn new_ingress_key(
+        &self,
+        key: &CompressedRistrettoPublic,
+        start_block_count: u64,
+    ) -> Result<u64, Self::Error> {
+        self.storage
+            .write(|state, _| state.new_ingress_key(key, start_block_count))
+    }
+
+    fn retire_ingress_key(
+        &self,
+        key: &CompressedRistrettoPublic,
+        set_retired: bool,
+    ) -> Result<(), Self::Error> {
+        self.storage.write(|state, _| {
+            state.retire_ingress_key(key, set_retired);
+            Ok(())
+        })
+    }
+
+    fn get_last_scanned_block_index(
+        &self,
+        key: &CompressedRistrettoPublic,
+    ) -> Result<Option<u64>, Self::Error> {
+        self.storage
+            .read(|state, _| Ok(state.get_last_scanned_block_index(key)))
+    }
+
+    fn get_ingress_key_records(
+        &self,
+        start_block_at_least: u64,
+        ingress_public_key_record_filters: &IngressPublicKeyRecordFilters,
+    ) -> Result<Vec<IngressPublicKeyRecord>, Self::Error> {
+        self.storage.read(|state, _| {
+            Ok(state
+                .get_ingress_key_records(start_block_at_least, ingress_public_key_record_filters))
+        })
+    }
+
+    fn new_ingest_invocation(
+        &self,
+        prev_ingest_invocation_id: Option<IngestInvocationId>,
+        ingress_public_key: &CompressedRistrettoPublic,
+        egress_public_key: &KexRngPubkey,
+        start_block: u64,
+    ) -> Result<IngestInvocationId, Self::Error> {
+        self.storage.write(|state, tables| {
+            state.new_ingest_invocation(
+                tables,
+                prev_ingest_invocation_id,
+                ingress_public_key,
+                egress_public_key,
+                start_block,
+            )
+        })
+    }
+
+    fn get_ingestable_ranges(&self) -> Result<Vec<IngestableRange>, Self::Error> {
+        self.storage
+            .read(|state, _| Ok(state.get_ingestable_ranges()))
+    }
+
+    fn decommission_ingest_invocation(
+        &self,
+        ingest_invocation_id: &IngestInvocationId,
+    ) -> Result<(), Self::Error> {
+        self.storage.write(|state, tables| {
+            state.decommission_ingest_invocation(tables, ingest_invocation_id)
+        })
+    }
+
+    fn add_block_data(
+        &self,
+        ingest_invocation_id: &IngestInvocationId,
+        block: &Block,
+        block_signature_timestamp: u64,
+        txs: &[ETxOutRecord],
+    ) -> Result<AddBlockDataStatus, Self::Error> {
+        let status = self.storage.write(|state, tables| {
+            state.add_block_data(
+                tables,
+                ingest_invocation_id,
+                block,
+                block_signature_timestamp,
+                txs,
+            )
+        })?;
+        if status.block_already_scanned_with_this_key {
+            log::debug!(
+                self.logger,
+                "Block {} was already scanned, ingest invocation {} did not add any data",
+                block.index,
+                ingest_invocation_id
+            );
+        }
+        Ok(status)
+    }
+
+    fn report_lost_ingress_key(
+        &self,
+        lost_ingress_key: CompressedRistrettoPublic,
+    ) -> Result<(), Self::Error> {
+        self.storage
+            .write(|state, tables| state.report_lost_ingress_key(tables, lost_ingress_key))
+    }
+
+    fn get_missed_block_ranges(&self) -> Result<Vec<BlockRange>, Self::Error> {
+        self.storage
+            .read(|state, tables| state.get_missed_block_ranges(tables))
+    }
+
+    fn search_user_events(
+        &self,
+        start_from_user_event_id: i64,
+    ) -> Result<(Vec<FogUserEvent>, i64), Self::Error> {
+        self.storage
+            .read(|state, tables| state.search_user_events(tables, start_from_user_event_id))
+    }
+
+    /// Get any TxOutSearchResults corresponding to given search keys.
+    /// Nonzero start_block can be provided as an optimization opportunity.
+    ///
+    /// Note: This scans every ingested block and should not be used except in
+    /// tests.
+    fn get_tx_outs(
+        &self,
+        start_block: u64,
+        search_keys: &[Vec<u8>],
+    ) -> Result<Vec<TxOutSearchResult>, Self::Error> {
+        self.storage
+            .read(|state, tables| state.get_tx_outs(tables, start_block, search_keys))
+    }
+
+    fn update_last_active_at(
+        &self,
+        ingest_invocation_id: &IngestInvocationId,
+    ) -> Result<(), Self::Error> {
+        self.storage.write(|state, _| {
+            state.update_last_active_at(ingest_invocation_id);
+            Ok(())
+        })
+    }
+
+    fn get_tx_outs_by_block_and_key(
+        &self,
+        ingress_key: CompressedRistrettoPublic,
+        block_index: u64,
+    ) -> Result<Option<Vec<ETxOutRecord>>, Self::Error> {
+        self.storage.read(|state, tables| {
+            state.get_tx_outs_by_block_and_key(tables, ingress_key, block_index)
+        })
+    }
+
+    fn get_invocation_id_by_block_and_key(
+        &self,
+        ingress_key: CompressedRistrettoPublic,
+        block_index: u64,
+    ) -> Result<Option<IngestInvocationId>, Self::Error> {
+        self.storage.read(|state, tables| {
+            state.get_invocation_id_by_block_and_key(tables, ingress_key, block_index)
+        })
+    }
+
+    fn get_cumulative_txo_count_for_block(
+        &self,
+        block_index: u64,
+    ) -> Result<Option<u64>, Self::Error> {
+        self.storage
+            .read(|state, tables| state.get_cumulative_txo_count_for_block(tables, block_index))
+    }
+
+    fn get_block_signature_timestamp_for_block(
+        &self,
+        block_index: u64,
+    ) -> Result<Option<u64>, Self::Error> {
+        self.storage.read(|state, tables| {
+            state.get_block_signature_timestamp_for_block(tables, block_index)
+        })
+    }
+
+    fn get_highest_known_block_index(&self) -> Result<Option<u64>, Self::Error> {
+        self.storage
+            .read(|state, _| Ok(state.get_highest_known_block_index()))
+    }
+}
+
+/// See trait `fog_recovery_db_iface::ReportDb` for documentation.
+impl ReportDb for EmbeddedRecoveryDb {
+    type Error = Error;
+
+    fn get_all_reports(&self) -> Result<Vec<(String, ReportData)>, Self::Error> {
+        self.storage
+            .read(|state, tables| state.get_all_reports(tables))
+    }
+
+    fn set_report(
+        &self,
+        ingress_key: &CompressedRistrettoPublic,
+        report_id: &str,
+        data: &ReportData,
+    ) -> Result<IngressPublicKeyStatus, Self::Error> {
+        let status = self
+            .storage
+            .write(|state, tables| state.set_report(tables, ingress_key, report_id, data))?;
+        if status.retired {
+            log::info!(self.logger, "Cannot publish key because it is retired");
+        }
+        Ok(status)
+    }
+
+    fn remove_report(&self, report_id: &str) -> Result<(), Self::Error> {
+        self.storage
+            .write(|state, tables| state.remove_report(tables, report_id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mc_attest_core::VerificationReport;
+    use mc_common::logger::{test_with_logger, Logger};
+    use mc_fog_test_infra::db_tests::{random_block, random_kex_rng_pubkey};
+    use mc_util_from_random::FromRandom;
+    use rand::{rngs::StdRng, SeedableRng};
+    use tempdir::TempDir;
+
+    #[test_with_logger]
+    fn test_lmdb_data_is_persisted(logger: Logger) {
+        let mut rng: StdRng = SeedableRng::from_seed([123u8; 32]);
+        let db_dir = TempDir::new("embedded_recovery_db").unwrap();
+
+        let ingress_key = CompressedRistrettoPublic::from_random(&mut rng);
+        let (block, records) = random_block(&mut rng, 3, 5);
+        let invoc_id = {
+            let db = EmbeddedRecoveryDb::open(db_dir.path(), logger.clone()).unwrap();
+            db.new_ingress_key(&ingress_key, 0).unwrap();
+            let invoc_id = db
+                .new_ingest_invocation(None, &ingress_key, &random_kex_rng_pubkey(&mut rng), 0)
+                .unwrap();
+            db.add_block_data(&invoc_id, &block, 10, &records).unwrap();
+            db.set_report(
+                &ingress_key,
+                "",
+                &ReportData {
+                    pubkey_expiry: 20,
+                    ingest_invocation_id: Some(invoc_id),
+                    report: VerificationReport::default(),
+                },
+            )
+            .unwrap();
+            invoc_id
+        };
+
+        // Re-opening the database sees everything that was written.
+        let db = EmbeddedRecoveryDb::open(db_dir.path(), logger).unwrap();
+        assert_eq!(
+            db.get_ingress_key_status(&ingress_key).unwrap(),
+            Some(IngressPublicKeyStatus {
+                start_block: 0,
+                pubkey_expiry: 20,
+                retired: false,
+                lost: false,
+            })
+        );
+        assert_eq!(db.get_highest_known_block_index().unwrap(), Some(3));
+        assert_eq!(
+            db.get_tx_outs_by_block_and_key(ingress_key, 3).unwrap(),
+            Some(records.clone())
+        );
+        assert_eq!(
+            db.get_tx_outs_by_block_and_key(ingress_key, 2).unwrap(),
+            None
+        );
+        assert_eq!(
+            db.get_invocation_id_by_block_and_key(ingress_key, 3)
+                .unwrap(),
+            Some(invoc_id)
+        );
+        assert_eq!(
+            db.get_block_signature_timestamp_for_block(3).unwrap(),
+            Some(10)
+        );
+        assert_eq!(
+            db.get_last_scanned_block_index(&ingress_key).unwrap(),
+            Some(3)
+        );
+        assert_eq!(
+            db.get_ingestable_ranges().unwrap()[0].last_ingested_block,
+            Some(3)
+        );
+        assert_eq!(db.search_user_events(0).unwrap().1, 1);
+        assert_eq!(db.get_all_reports().unwrap().len(), 1);
+
+        // Adding the same block again is reported, and does not change the data.
+        let (_block, other_records) = random_block(&mut rng, 3, 5);
+        assert!(
+            db.add_block_data(&invoc_id, &block, 10, &other_records)
+                .unwrap()
+                .block_already_scanned_with_this_key
+        );
+        assert_eq!(
+            db.get_tx_outs_by_block_and_key(ingress_key, 3).unwrap(),
+            Some(records)
+        );
+    }
+
+    #[test_with_logger]
+    fn test_reports_keep_insertion_order(logger: Logger) {
+        let mut rng: StdRng = SeedableRng::from_seed([125u8; 32]);
+        let db_dir = TempDir::new("embedded_recovery_db").unwrap();
+
+        for db in [
+            EmbeddedRecoveryDb::new_in_memory(logger.clone()),
+            EmbeddedRecoveryDb::open(db_dir.path(), logger.clone()).unwrap(),
+        ] {
+            let ingress_key = CompressedRistrettoPublic::from_random(&mut rng);
+            db.new_ingress_key(&ingress_key, 0).unwrap();
+
+            let report_ids = || {
+                db.get_all_reports()
+                    .unwrap()
+                    .into_iter()
+                    .map(|(report_id, data)| (report_id, data.pubkey_expiry))
+                    .collect::<Vec<_>>()
+            };
+            for (report_id, pubkey_expiry) in [("z", 10), ("", 20), ("z", 30)] {
+                db.set_report(
+                    &ingress_key,
+                    report_id,
+                    &ReportData {
+                        pubkey_expiry,
+                        ingest_invocation_id: None,
+                        report: VerificationReport::default(),
+                    },
+                )
+                .unwrap();
+            }
+            // Replacing a report keeps its place.
+            assert_eq!(
+                report_ids(),
+                vec![("z".to_owned(), 30), ("".to_owned(), 20)]
+            );
+
+            db.remove_report("z").unwrap();
+            assert_eq!(report_ids(), vec![("".to_owned(), 20)]);
+        }
+    }
+
+    #[test_with_logger]
+    fn test_failed_writes_do_not_change_state(logger: Logger) {
+        let mut rng: StdRng = SeedableRng::from_seed([124u8; 32]);
+        let db_dir = TempDir::new("embedded_recovery_db").unwrap();
+
+        for db in [
+            EmbeddedRecoveryDb::new_in_memory(logger.clone()),
+            EmbeddedRecoveryDb::open(db_dir.path(), logger.clone()).unwrap(),
+        ] {
+            let ingress_key = CompressedRistrettoPublic::from_random(&mut rng);
+            db.new_ingress_key(&ingress_key, 0).unwrap();
+
+            // Replacing an unknown ingest invocation fails, and creates nothing.
+            assert!(db
+                .new_ingest_invocation(
+                    Some(IngestInvocationId::from(1000)),
+                    &ingress_key,
+                    &random_kex_rng_pubkey(&mut rng),
+                    0,
+                )
+                .is_err());
+            assert_eq!(db.get_ingestable_ranges().unwrap(), vec![]);
+            assert_eq!(db.search_user_events(0).unwrap(), (vec![], 0));
+
+            // Reporting an unknown key lost fails.
+            let unknown_key = CompressedRistrettoPublic::from_random(&mut rng);
+            assert!(matches!(
+                db.report_lost_ingress_key(unknown_key),
+                Err(Error::MissingIngressKey(_))
+            ));
+        }
+    }
+}
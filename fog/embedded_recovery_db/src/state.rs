@@ -0,0 +1,618 @@
+// Copyright (c) 2018-2022 The MobileCoin Foundation
+
+//! The tables of the embedded recovery db, and the logic that operates on
+//! them. This mirrors the schema and queries of the SQL recovery db.
+//!
+//! Every mutating method validates its inputs before making any changes, so
+//! that an error leaves the state untouched.
+
+use crate::{
+    error::Error,
+    storage::{TablesReader, TablesWriter},
+};
+use mc_attest_core::VerificationReport;
+use mc_blockchain_types::Block;
+use mc_common::HashMap;
+use mc_crypto_keys::CompressedRistrettoPublic;
+use mc_fog_kex_rng::KexRngPubkey;
+use mc_fog_recovery_db_iface::{
+    AddBlockDataStatus, FogUserEvent, IngestInvocationId, IngestableRange, IngressPublicKeyRecord,
+    IngressPublicKeyRecordFilters, IngressPublicKeyStatus, ReportData,
+};
+use mc_fog_types::{
+    common::BlockRange,
+    view::{DecommissionedIngestInvocation, RngRecord, TxOutSearchResult, TxOutSearchResultCode},
+    ETxOutRecord,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    cmp::max,
+    collections::BTreeMap,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// A row in the ingress keys table.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+struct IngressKey {
+    start_block: u64,
+    pubkey_expiry: u64,
+    retired: bool,
+    lost: bool,
+}
+
+impl From<&IngressKey> for IngressPublicKeyStatus {
+    fn from(src: &IngressKey) -> Self {
+        Self {
+            start_block: src.start_block,
+            pubkey_expiry: src.pubkey_expiry,
+            retired: src.retired,
+            lost: src.lost,
+        }
+    }
+}
+
+/// A row in the ingest invocations table.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+struct IngestInvocation {
+    ingress_public_key: CompressedRistrettoPublic,
+    egress_public_key: KexRngPubkey,
+    start_block: u64,
+    decommissioned: bool,
+    /// Seconds since the unix epoch.
+    last_active_at: u64,
+    /// The highest block index ingested by this invocation.
+    last_ingested_block: Option<u64>,
+}
+
+/// A row in the ingested blocks table. The ETxOutRecords of the block are
+/// stored next to it by the storage backend.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct IngestedBlock {
+    ingest_invocation_id: i64,
+    cumulative_txo_count: u64,
+    block_signature_timestamp: u64,
+}
+
+/// A row in the user events table.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum UserEvent {
+    NewIngestInvocation(i64),
+    DecommissionIngestInvocation(i64),
+    MissingBlocks(BlockRange),
+}
+
+/// A row in the reports table.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Report {
+    /// The order in which reports were first inserted.
+    id: u64,
+    pub fog_report_id: String,
+    ingress_public_key: CompressedRistrettoPublic,
+    ingest_invocation_id: Option<i64>,
+    report: VerificationReport,
+    pubkey_expiry: u64,
+}
+
+/// The tables of the embedded recovery db whose size does not grow with the
+/// ledger. The ingested blocks, user events and reports are held by the
+/// storage backend, and accessed through `TablesReader` and `TablesWriter`.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct State {
+    ingress_keys: BTreeMap<CompressedRistrettoPublic, IngressKey>,
+
+    /// Ingest invocations, by id. Ids start at 1.
+    ingest_invocations: BTreeMap<i64, IngestInvocation>,
+
+    /// The highest block index ingested with each ingress key.
+    last_scanned_blocks: BTreeMap<CompressedRistrettoPublic, u64>,
+
+    /// The number of user events. Ids start at 1, so that a cursor value of 0
+    /// captures everything.
+    num_user_events: u64,
+
+    /// The id of the next report to be inserted.
+    next_report_id: u64,
+}
+
+impl State {
+    ////
+    // RecoveryDb functions
+    ////
+
+    pub fn get_ingress_key_status(
+        &self,
+        key: &CompressedRistrettoPublic,
+    ) -> Option<IngressPublicKeyStatus> {
+        self.ingress_keys.get(key).map(IngressPublicKeyStatus::from)
+    }
+
+    pub fn new_ingress_key(
+        &mut self,
+        key: &CompressedRistrettoPublic,
+        start_block_count: u64,
+    ) -> Result<u64, Error> {
+        if self.ingress_keys.contains_key(key) {
+            return Err(Error::DuplicateIngressKey(*key));
+        }
+
+        let highest_known_block_count = self
+            .get_highest_known_block_index()
+            .map(|index| index + 1)
+            .unwrap_or(0);
+        let accepted_start_block_count = max(start_block_count, highest_known_block_count);
+
+        self.ingress_keys.insert(
+            *key,
+            IngressKey {
+                start_block: accepted_start_block_count,
+                pubkey_expiry: 0,
+                retired: false,
+                lost: false,
+            },
+        );
+
+        Ok(accepted_start_block_count)
+    }
+
+    pub fn retire_ingress_key(&mut self, key: &CompressedRistrettoPublic, set_retired: bool) {
+        if let Some(ingress_key) = self.ingress_keys.get_mut(key) {
+            ingress_key.retired = set_retired;
+        }
+    }
+
+    pub fn get_last_scanned_block_index(&self, key: &CompressedRistrettoPublic) -> Option<u64> {
+        self.last_scanned_blocks.get(key).cloned()
+    }
+
+    pub fn get_ingress_key_records(
+        &self,
+        start_block_at_least: u64,
+        ingress_public_key_record_filters: &IngressPublicKeyRecordFilters,
+    ) -> Vec<IngressPublicKeyRecord> {
+        self.ingress_keys
+            .iter()
+            .filter(|(_key, ingress_key)| ingress_key.start_block >= start_block_at_least)
+            .filter(|(_key, ingress_key)| {
+                ingress_public_key_record_filters.should_include_lost_keys || !ingress_key.lost
+            })
+            .filter(|(_key, ingress_key)| {
+                ingress_public_key_record_filters.should_include_retired_keys
+                    || !ingress_key.retired
+            })
+            .map(|(key, ingress_key)| IngressPublicKeyRecord {
+                key: *key,
+                status: ingress_key.into(),
+                last_scanned_block: self.get_last_scanned_block_index(key),
+            })
+            .filter(|record| {
+                !ingress_public_key_record_filters.should_only_include_unexpired_keys
+                    || record
+                        .last_scanned_block
+                        .map(|last_scanned_block| record.status.pubkey_expiry > last_scanned_block)
+                        .unwrap_or(false)
+            })
+            .collect()
+    }
+
+    pub fn new_ingest_invocation(
+        &mut self,
+        tables: &mut dyn TablesWriter,
+        prev_ingest_invocation_id: Option<IngestInvocationId>,
+        ingress_public_key: &CompressedRistrettoPublic,
+        egress_public_key: &KexRngPubkey,
+        start_block: u64,
+    ) -> Result<IngestInvocationId, Error> {
+        // Optionally decommission old invocation.
+        if let Some(prev_ingest_invocation_id) = prev_ingest_invocation_id {
+            self.decommission_ingest_invocation(tables, &prev_ingest_invocation_id)?;
+        }
+
+        // Write new invocation.
+        let id = self
+            .ingest_invocations
+            .keys()
+            .next_back()
+            .map(|id| id + 1)
+            .unwrap_or(1);
+        self.ingest_invocations.insert(
+            id,
+            IngestInvocation {
+                ingress_public_key: *ingress_public_key,
+                egress_public_key: egress_public_key.clone(),
+                start_block,
+                decommissioned: false,
+                last_active_at: now(),
+                last_ingested_block: None,
+            },
+        );
+
+        // Write a user event.
+        self.add_user_event(tables, UserEvent::NewIngestInvocation(id))?;
+
+        Ok(IngestInvocationId::from(id))
+    }
+
+    pub fn get_ingestable_ranges(&self) -> Vec<IngestableRange> {
+        self.ingest_invocations
+            .iter()
+            .map(|(id, invocation)| IngestableRange {
+                id: IngestInvocationId::from(*id),
+                start_block: invocation.start_block,
+                decommissioned: invocation.decommissioned,
+                last_ingested_block: invocation.last_ingested_block,
+            })
+            .collect()
+    }
+
+    pub fn decommission_ingest_invocation(
+        &mut self,
+        tables: &mut dyn TablesWriter,
+        ingest_invocation_id: &IngestInvocationId,
+    ) -> Result<(), Error> {
+        let invocation = self
+            .ingest_invocations
+            .get_mut(ingest_invocation_id.as_ref())
+            .ok_or(Error::MissingIngestInvocation(*ingest_invocation_id))?;
+
+        // Mark the ingest invocation as decommissioned.
+        invocation.decommissioned = true;
+        invocation.last_active_at = now();
+
+        // Write a user event.
+        self.add_user_event(
+            tables,
+            UserEvent::DecommissionIngestInvocation(**ingest_invocation_id),
+        )
+    }
+
+    pub fn add_block_data(
+        &mut self,
+        tables: &mut dyn TablesWriter,
+        ingest_invocation_id: &IngestInvocationId,
+        block: &Block,
+        block_signature_timestamp: u64,
+        txs: &[ETxOutRecord],
+    ) -> Result<AddBlockDataStatus, Error> {
+        let invocation = self
+            .ingest_invocations
+            .get_mut(ingest_invocation_id.as_ref())
+            .ok_or(Error::MissingIngestInvocation(*ingest_invocation_id))?;
+        let ingress_key = invocation.ingress_public_key;
+
+        if tables
+            .get_ingested_block(&ingress_key, block.index)?
+            .is_some()
+        {
+            return Ok(AddBlockDataStatus {
+                block_already_scanned_with_this_key: true,
+            });
+        }
+
+        tables.put_ingested_block(
+            &ingress_key,
+            block.index,
+            &IngestedBlock {
+                ingest_invocation_id: **ingest_invocation_id,
+                cumulative_txo_count: block.cumulative_txo_count,
+                block_signature_timestamp,
+            },
+            txs,
+        )?;
+
+        // Update last ingested block and last active at.
+        invocation.last_ingested_block = max(invocation.last_ingested_block, Some(block.index));
+        invocation.last_active_at = now();
+
+        let last_scanned_block = self
+            .last_scanned_blocks
+            .entry(ingress_key)
+            .or_insert(block.index);
+        *last_scanned_block = max(*last_scanned_block, block.index);
+
+        Ok(AddBlockDataStatus {
+            block_already_scanned_with_this_key: false,
+        })
+    }
+
+    pub fn report_lost_ingress_key(
+        &mut self,
+        tables: &mut dyn TablesWriter,
+        lost_ingress_key: CompressedRistrettoPublic,
+    ) -> Result<(), Error> {
+        let last_scanned_block = self.get_last_scanned_block_index(&lost_ingress_key);
+
+        // Find the ingress key and mark it lost
+        let ingress_key = self
+            .ingress_keys
+            .get_mut(&lost_ingress_key)
+            .ok_or(Error::MissingIngressKey(lost_ingress_key))?;
+        ingress_key.lost = true;
+
+        // Compute a missed block range based on looking at the key status,
+        // which is correct if no blocks have actually been scanned using the key.
+        let mut missed_block_range =
+            BlockRange::new(ingress_key.start_block, ingress_key.pubkey_expiry);
+
+        if let Some(block_index) = last_scanned_block {
+            if block_index + 1 >= missed_block_range.end_block {
+                // There aren't actually any blocks that need to be scanned, so we are done
+                // without creating a user event.
+                return Ok(());
+            }
+            // If we did actually scan some blocks, then report a smaller range
+            if block_index + 1 > missed_block_range.start_block {
+                missed_block_range.start_block = block_index + 1;
+            }
+        }
+
+        // If the missed block range is invalid (empty), we don't have to add it.
+        // This can happen if the ingress key was never actually published to the report
+        // server, and then pubkey_expiry is zero.
+        if !missed_block_range.is_valid() {
+            return Ok(());
+        }
+
+        // Add new range.
+        self.add_user_event(tables, UserEvent::MissingBlocks(missed_block_range))
+    }
+
+    pub fn get_missed_block_ranges(
+        &self,
+        tables: &dyn TablesReader,
+    ) -> Result<Vec<BlockRange>, Error> {
+        let mut ranges = Vec::new();
+        tables.for_each_user_event(0, &mut |_id, event| {
+            if let UserEvent::MissingBlocks(range) = event {
+                ranges.push(range);
+            }
+        })?;
+        Ok(ranges)
+    }
+
+    pub fn search_user_events(
+        &self,
+        tables: &dyn TablesReader,
+        start_from_user_event_id: i64,
+    ) -> Result<(Vec<FogUserEvent>, i64), Error> {
+        // Early return if start_from_user_event_id is max
+        if start_from_user_event_id == i64::MAX {
+            return Ok((Default::default(), i64::MAX));
+        }
+
+        let mut events = Vec::new();
+        tables.for_each_user_event(
+            max(start_from_user_event_id, 0) as u64,
+            &mut |_id, event| {
+                events.push(match event {
+                    UserEvent::NewIngestInvocation(id) => {
+                        let invocation = &self.ingest_invocations[&id];
+                        FogUserEvent::NewRngRecord(RngRecord {
+                            ingest_invocation_id: id,
+                            pubkey: invocation.egress_public_key.clone(),
+                            start_block: invocation.start_block,
+                        })
+                    }
+                    UserEvent::DecommissionIngestInvocation(id) => {
+                        FogUserEvent::DecommissionIngestInvocation(DecommissionedIngestInvocation {
+                            ingest_invocation_id: id,
+                            last_ingested_block: self.ingest_invocations[&id]
+                                .last_ingested_block
+                                .unwrap_or(0),
+                        })
+                    }
+                    UserEvent::MissingBlocks(range) => FogUserEvent::MissingBlocks(range),
+                })
+            },
+        )?;
+
+        // If no events are found, return start_from_user_event_id and not 0
+        let max_user_event_id = max(start_from_user_event_id, self.num_user_events as i64);
+
+        Ok((events, max_user_event_id))
+    }
+
+    pub fn get_tx_outs(
+        &self,
+        tables: &dyn TablesReader,
+        start_block: u64,
+        search_keys: &[Vec<u8>],
+    ) -> Result<Vec<TxOutSearchResult>, Error> {
+        let mut search_key_to_payload = HashMap::<Vec<u8>, Vec<u8>>::default();
+        tables.for_each_block_data(&mut |block_index, e_tx_out_records| {
+            if block_index >= start_block {
+                for e_tx_out_record in e_tx_out_records {
+                    search_key_to_payload
+                        .insert(e_tx_out_record.search_key, e_tx_out_record.payload);
+                }
+            }
+        })?;
+
+        Ok(search_keys
+            .iter()
+            .map(|search_key| match search_key_to_payload.get(search_key) {
+                Some(payload) => TxOutSearchResult {
+                    search_key: search_key.clone(),
+                    result_code: TxOutSearchResultCode::Found as u32,
+                    ciphertext: payload.clone(),
+                },
+
+                None => TxOutSearchResult {
+                    search_key: search_key.clone(),
+                    result_code: TxOutSearchResultCode::NotFound as u32,
+                    ciphertext: Default::default(),
+                },
+            })
+            .collect())
+    }
+
+    pub fn update_last_active_at(&mut self, ingest_invocation_id: &IngestInvocationId) {
+        if let Some(invocation) = self
+            .ingest_invocations
+            .get_mut(ingest_invocation_id.as_ref())
+        {
+            invocation.last_active_at = now();
+        }
+    }
+
+    pub fn get_tx_outs_by_block_and_key(
+        &self,
+        tables: &dyn TablesReader,
+        ingress_key: CompressedRistrettoPublic,
+        block_index: u64,
+    ) -> Result<Option<Vec<ETxOutRecord>>, Error> {
+        tables.get_block_data(&ingress_key, block_index)
+    }
+
+    pub fn get_invocation_id_by_block_and_key(
+        &self,
+        tables: &dyn TablesReader,
+        ingress_key: CompressedRistrettoPublic,
+        block_index: u64,
+    ) -> Result<Option<IngestInvocationId>, Error> {
+        Ok(tables
+            .get_ingested_block(&ingress_key, block_index)?
+            .map(|block| IngestInvocationId::from(block.ingest_invocation_id)))
+    }
+
+    pub fn get_cumulative_txo_count_for_block(
+        &self,
+        tables: &dyn TablesReader,
+        block_index: u64,
+    ) -> Result<Option<u64>, Error> {
+        Ok(self
+            .find_ingested_block(tables, block_index)?
+            .map(|block| block.cumulative_txo_count))
+    }
+
+    pub fn get_block_signature_timestamp_for_block(
+        &self,
+        tables: &dyn TablesReader,
+        block_index: u64,
+    ) -> Result<Option<u64>, Error> {
+        Ok(self
+            .find_ingested_block(tables, block_index)?
+            .map(|block| block.block_signature_timestamp))
+    }
+
+    pub fn get_highest_known_block_index(&self) -> Option<u64> {
+        self.last_scanned_blocks.values().max().cloned()
+    }
+
+    ////
+    // ReportDb functions
+    ////
+
+    pub fn get_all_reports(
+        &self,
+        tables: &dyn TablesReader,
+    ) -> Result<Vec<(String, ReportData)>, Error> {
+        let mut reports = tables.get_reports()?;
+        reports.sort_by_key(|report| report.id);
+
+        Ok(reports
+            .into_iter()
+            .map(|report| {
+                (
+                    report.fog_report_id,
+                    ReportData {
+                        ingest_invocation_id: report
+                            .ingest_invocation_id
+                            .map(IngestInvocationId::from),
+                        report: report.report,
+                        pubkey_expiry: report.pubkey_expiry,
+                    },
+                )
+            })
+            .collect())
+    }
+
+    pub fn set_report(
+        &mut self,
+        tables: &mut dyn TablesWriter,
+        ingress_key: &CompressedRistrettoPublic,
+        report_id: &str,
+        data: &ReportData,
+    ) -> Result<IngressPublicKeyStatus, Error> {
+        let key_record = self
+            .ingress_keys
+            .get_mut(ingress_key)
+            .ok_or(Error::MissingIngressKey(*ingress_key))?;
+
+        // Don't publish reports for a retired key.
+        if key_record.retired {
+            return Ok(IngressPublicKeyStatus::from(&*key_record));
+        }
+
+        // A report which replaces an existing one keeps its place in the order.
+        let id = match tables.get_report(report_id)? {
+            Some(existing) => existing.id,
+            None => {
+                let id = self.next_report_id;
+                self.next_report_id += 1;
+                id
+            }
+        };
+        tables.put_report(&Report {
+            id,
+            fog_report_id: report_id.to_owned(),
+            ingress_public_key: *ingress_key,
+            ingest_invocation_id: data.ingest_invocation_id.map(i64::from),
+            report: data.report.clone(),
+            pubkey_expiry: data.pubkey_expiry,
+        })?;
+
+        // The pubkey_expiry value of the key is only allowed to increase.
+        if key_record.pubkey_expiry < data.pubkey_expiry {
+            key_record.pubkey_expiry = data.pubkey_expiry;
+        }
+
+        Ok(IngressPublicKeyStatus::from(&*key_record))
+    }
+
+    pub fn remove_report(
+        &mut self,
+        tables: &mut dyn TablesWriter,
+        report_id: &str,
+    ) -> Result<(), Error> {
+        tables.remove_report(report_id)
+    }
+
+    ////
+    // Helpers
+    ////
+
+    /// Store a user event with the next id.
+    fn add_user_event(
+        &mut self,
+        tables: &mut dyn TablesWriter,
+        event: UserEvent,
+    ) -> Result<(), Error> {
+        let id = self.num_user_events + 1;
+        tables.put_user_event(id, &event)?;
+        self.num_user_events = id;
+        Ok(())
+    }
+
+    /// Find an ingested block with a given block index, ingested with any
+    /// ingress key.
+    fn find_ingested_block(
+        &self,
+        tables: &dyn TablesReader,
+        block_index: u64,
+    ) -> Result<Option<IngestedBlock>, Error> {
+        for ingress_key in self.last_scanned_blocks.keys() {
+            if let Some(block) = tables.get_ingested_block(ingress_key, block_index)? {
+                return Ok(Some(block));
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// Seconds since the unix epoch.
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
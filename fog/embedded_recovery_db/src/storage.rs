@@ -0,0 +1,577 @@
+// Copyright (c) 2018-2022 The MobileCoin Foundation
+
+//! Storage backends for the embedded recovery db.
+//!
+//! Both backends keep the small tables, whose size does not grow with the
+//! ledger, in a single `State` object. The tables that do grow with the
+//! ledger (ingested blocks and their ETxOutRecords, user events and reports)
+//! are kept apart, so that an operation only touches the rows it needs.
+//! * The in-memory backend keeps everything behind a mutex.
+//! * The LMDB backend stores the serialized `State` under a single key, and
+//!   each of the other tables in an LMDB database of its own. Every operation
+//!   runs in an LMDB transaction, which makes it safe for several processes to
+//!   share a database directory.
+
+use crate::{
+    error::Error,
+    state::{IngestedBlock, Report, State, UserEvent},
+};
+use lmdb::{Cursor, Database, DatabaseFlags, Environment, RwTransaction, Transaction, WriteFlags};
+use mc_common::logger::{log, Logger};
+use mc_crypto_keys::CompressedRistrettoPublic;
+use mc_fog_types::ETxOutRecord;
+use mc_util_lmdb::{MetadataStore, MetadataStoreSettings};
+use prost::Message;
+use std::{
+    collections::BTreeMap,
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+// LMDB Constants
+const MAX_LMDB_FILE_SIZE: usize = 1_099_511_627_776; // 1 TB
+const STATE_DB_NAME: &str = "embedded_recovery_db:state";
+const INGESTED_BLOCKS_DB_NAME: &str = "embedded_recovery_db:ingested_blocks";
+const BLOCK_DATA_DB_NAME: &str = "embedded_recovery_db:block_data";
+const USER_EVENTS_DB_NAME: &str = "embedded_recovery_db:user_events";
+const REPORTS_DB_NAME: &str = "embedded_recovery_db:reports";
+
+/// The key the serialized `State` is stored under.
+const STATE_KEY: &[u8] = b"state";
+
+/// Metadata store settings that are used for version control.
+#[derive(Clone, Default, Debug)]
+pub struct EmbeddedRecoveryDbMetadataStoreSettings;
+impl MetadataStoreSettings for EmbeddedRecoveryDbMetadataStoreSettings {
+    // Default database version. This should be bumped when breaking changes are
+    // introduced. If this is properly maintained, we could check during
+    // database opening for any incompatibilities, and either refuse to open or
+    // perform a migration.
+    #[allow(clippy::unreadable_literal)]
+    const LATEST_VERSION: u64 = 20221018;
+
+    /// The current crate version that manages the database.
+    const CRATE_VERSION: &'static str = env!("CARGO_PKG_VERSION");
+
+    /// LMDB Database name to use for storing the metadata information.
+    const DB_NAME: &'static str = "embedded_recovery_db_metadata";
+}
+
+/// Protobuf data stored for each ingested block.
+#[derive(Message)]
+struct ProtoIngestedBlockData {
+    /// Any ETxOutRecord's that fog ingest emitted in connection to this block
+    #[prost(repeated, message, tag = 1)]
+    pub e_tx_out_records: Vec<ETxOutRecord>,
+}
+
+/// Read access to the tables which are not part of `State`.
+pub trait TablesReader {
+    /// Get the ingested block for a given ingress key and block index, if any.
+    fn get_ingested_block(
+        &self,
+        ingress_key: &CompressedRistrettoPublic,
+        block_index: u64,
+    ) -> Result<Option<IngestedBlock>, Error>;
+
+    /// Get the ETxOutRecords that were added for a given ingress key and block
+    /// index, if any.
+    fn get_block_data(
+        &self,
+        ingress_key: &CompressedRistrettoPublic,
+        block_index: u64,
+    ) -> Result<Option<Vec<ETxOutRecord>>, Error>;
+
+    /// Call `f` with the block index and ETxOutRecords of every ingested block.
+    fn for_each_block_data(&self, f: &mut dyn FnMut(u64, Vec<ETxOutRecord>)) -> Result<(), Error>;
+
+    /// Call `f` with the id and contents of every user event whose id is
+    /// greater than `after_id`, in order of their ids.
+    fn for_each_user_event(
+        &self,
+        after_id: u64,
+        f: &mut dyn FnMut(u64, UserEvent),
+    ) -> Result<(), Error>;
+
+    /// Get the report with a given report id, if any.
+    fn get_report(&self, fog_report_id: &str) -> Result<Option<Report>, Error>;
+
+    /// Get all the reports, in no particular order.
+    fn get_reports(&self) -> Result<Vec<Report>, Error>;
+}
+
+/// Write access to the tables which are not part of `State`.
+pub trait TablesWriter: TablesReader {
+    /// Store an ingested block and its ETxOutRecords, for a given ingress key
+    /// and block index.
+    fn put_ingested_block(
+        &mut self,
+        ingress_key: &CompressedRistrettoPublic,
+        block_index: u64,
+        block: &IngestedBlock,
+        records: &[ETxOutRecord],
+    ) -> Result<(), Error>;
+
+    /// Store a user event with a given id.
+    fn put_user_event(&mut self, id: u64, event: &UserEvent) -> Result<(), Error>;
+
+    /// Store a report, replacing any report with the same report id.
+    fn put_report(&mut self, report: &Report) -> Result<(), Error>;
+
+    /// Remove the report with a given report id, if any.
+    fn remove_report(&mut self, fog_report_id: &str) -> Result<(), Error>;
+}
+
+/// A storage backend.
+#[derive(Clone)]
+pub enum Storage {
+    /// Everything is kept in memory.
+    Memory(Arc<Mutex<MemoryStorage>>),
+
+    /// Everything is persisted to an LMDB database.
+    Lmdb(Arc<LmdbStorage>),
+}
+
+impl Storage {
+    /// Create an empty in-memory storage.
+    pub fn new_in_memory() -> Self {
+        Self::Memory(Default::default())
+    }
+
+    /// Open (or create) an LMDB storage at a given directory.
+    pub fn open_lmdb(path: &Path, logger: &Logger) -> Result<Self, Error> {
+        Ok(Self::Lmdb(Arc::new(LmdbStorage::open(path, logger)?)))
+    }
+
+    /// Run a read-only operation against a consistent snapshot of the
+    /// database.
+    pub fn read<R>(
+        &self,
+        f: impl FnOnce(&State, &dyn TablesReader) -> Result<R, Error>,
+    ) -> Result<R, Error> {
+        match self {
+            Self::Memory(memory) => {
+                let memory = memory.lock().expect("mutex poisoned");
+                f(&memory.state, &memory.tables)
+            }
+            Self::Lmdb(lmdb) => lmdb.read(f),
+        }
+    }
+
+    /// Run an operation that may modify the database.
+    ///
+    /// `f` is expected to validate its inputs before making any changes, so
+    /// that an error leaves the database untouched. With the LMDB backend, an
+    /// error additionally aborts the underlying transaction.
+    pub fn write<R>(
+        &self,
+        f: impl FnOnce(&mut State, &mut dyn TablesWriter) -> Result<R, Error>,
+    ) -> Result<R, Error> {
+        match self {
+            Self::Memory(memory) => {
+                let mut memory = memory.lock().expect("mutex poisoned");
+                let MemoryStorage { state, tables } = &mut *memory;
+                f(state, tables)
+            }
+            Self::Lmdb(lmdb) => lmdb.write(f),
+        }
+    }
+}
+
+/// In-memory storage.
+#[derive(Default)]
+pub struct MemoryStorage {
+    state: State,
+    tables: MemoryTables,
+}
+
+/// The in-memory tables which are not part of `State`.
+#[derive(Default)]
+struct MemoryTables {
+    /// (ingress key, block index) -> ingested block and its ETxOutRecords
+    ingested_blocks: BTreeMap<(CompressedRistrettoPublic, u64), (IngestedBlock, Vec<ETxOutRecord>)>,
+
+    /// User event id -> user event
+    user_events: BTreeMap<u64, UserEvent>,
+
+    /// Report id -> report
+    reports: BTreeMap<String, Report>,
+}
+
+impl TablesReader for MemoryTables {
+    fn get_ingested_block(
+        &self,
+        ingress_key: &CompressedRistrettoPublic,
+        block_index: u64,
+    ) -> Result<Option<IngestedBlock>, Error> {
+        Ok(self
+            .ingested_blocks
+            .get(&(*ingress_key, block_index))
+            .map(|(block, _records)| block.clone()))
+    }
+
+    fn get_block_data(
+        &self,
+        ingress_key: &CompressedRistrettoPublic,
+        block_index: u64,
+    ) -> Result<Option<Vec<ETxOutRecord>>, Error> {
+        Ok(self
+            .ingested_blocks
+            .get(&(*ingress_key, block_index))
+            .map(|(_block, records)| records.clone()))
+    }
+
+    fn for_each_block_data(&self, f: &mut dyn FnMut(u64, Vec<ETxOutRecord>)) -> Result<(), Error> {
+        for ((_ingress_key, block_index), (_block, records)) in self.ingested_blocks.iter() {
+            f(*block_index, records.clone());
+        }
+        Ok(())
+    }
+
+    fn for_each_user_event(
+        &self,
+        after_id: u64,
+        f: &mut dyn FnMut(u64, UserEvent),
+    ) -> Result<(), Error> {
+        for (id, event) in self.user_events.range(after_id.saturating_add(1)..) {
+            f(*id, event.clone());
+        }
+        Ok(())
+    }
+
+    fn get_report(&self, fog_report_id: &str) -> Result<Option<Report>, Error> {
+        Ok(self.reports.get(fog_report_id).cloned())
+    }
+
+    fn get_reports(&self) -> Result<Vec<Report>, Error> {
+        Ok(self.reports.values().cloned().collect())
+    }
+}
+
+impl TablesWriter for MemoryTables {
+    fn put_ingested_block(
+        &mut self,
+        ingress_key: &CompressedRistrettoPublic,
+        block_index: u64,
+        block: &IngestedBlock,
+        records: &[ETxOutRecord],
+    ) -> Result<(), Error> {
+        self.ingested_blocks.insert(
+            (*ingress_key, block_index),
+            (block.clone(), records.to_vec()),
+        );
+        Ok(())
+    }
+
+    fn put_user_event(&mut self, id: u64, event: &UserEvent) -> Result<(), Error> {
+        self.user_events.insert(id, event.clone());
+        Ok(())
+    }
+
+    fn put_report(&mut self, report: &Report) -> Result<(), Error> {
+        self.reports
+            .insert(report.fog_report_id.clone(), report.clone());
+        Ok(())
+    }
+
+    fn remove_report(&mut self, fog_report_id: &str) -> Result<(), Error> {
+        self.reports.remove(fog_report_id);
+        Ok(())
+    }
+}
+
+/// LMDB-backed storage.
+pub struct LmdbStorage {
+    /// LMDB Environment.
+    env: Environment,
+
+    /// Holds the serialized `State`.
+    state_db: Database,
+
+    /// The databases of the tables which are not part of `State`.
+    databases: LmdbDatabases,
+}
+
+/// The LMDB databases of the tables which are not part of `State`.
+#[derive(Clone, Copy)]
+struct LmdbDatabases {
+    /// (ingress key, block index) -> IngestedBlock
+    ingested_blocks: Database,
+
+    /// (ingress key, block index) -> ProtoIngestedBlockData
+    block_data: Database,
+
+    /// User event id -> UserEvent
+    user_events: Database,
+
+    /// Report key -> Report
+    reports: Database,
+}
+
+impl LmdbStorage {
+    /// Open (or create) the LMDB database at a given directory.
+    pub fn open(path: &Path, logger: &Logger) -> Result<Self, Error> {
+        let env = Environment::new()
+            .set_max_dbs(10)
+            .set_map_size(MAX_LMDB_FILE_SIZE)
+            .open(path)?;
+
+        let metadata_store =
+            MetadataStore::<EmbeddedRecoveryDbMetadataStoreSettings>::open_or_create(&env)?;
+
+        let db_txn = env.begin_ro_txn()?;
+        let version = metadata_store.get_version(&db_txn)?;
+        log::info!(
+            logger,
+            "Embedded recovery db is currently at version: {:?}",
+            version
+        );
+        db_txn.commit()?;
+
+        version.is_compatible_with_latest()?;
+
+        let state_db = env.create_db(Some(STATE_DB_NAME), DatabaseFlags::empty())?;
+        let databases = LmdbDatabases {
+            ingested_blocks: env
+                .create_db(Some(INGESTED_BLOCKS_DB_NAME), DatabaseFlags::empty())?,
+            block_data: env.create_db(Some(BLOCK_DATA_DB_NAME), DatabaseFlags::empty())?,
+            user_events: env.create_db(Some(USER_EVENTS_DB_NAME), DatabaseFlags::empty())?,
+            reports: env.create_db(Some(REPORTS_DB_NAME), DatabaseFlags::empty())?,
+        };
+
+        Ok(Self {
+            env,
+            state_db,
+            databases,
+        })
+    }
+
+    fn read<R>(
+        &self,
+        f: impl FnOnce(&State, &dyn TablesReader) -> Result<R, Error>,
+    ) -> Result<R, Error> {
+        let db_txn = self.env.begin_ro_txn()?;
+        let state = self.load_state(&db_txn)?;
+        f(
+            &state,
+            &LmdbTables {
+                db_txn,
+                databases: self.databases,
+            },
+        )
+    }
+
+    fn write<R>(
+        &self,
+        f: impl FnOnce(&mut State, &mut dyn TablesWriter) -> Result<R, Error>,
+    ) -> Result<R, Error> {
+        let db_txn = self.env.begin_rw_txn()?;
+        let mut state = self.load_state(&db_txn)?;
+        let mut tables = LmdbTables {
+            db_txn,
+            databases: self.databases,
+        };
+
+        let result = f(&mut state, &mut tables)?;
+
+        let state_bytes = mc_util_serial::serialize(&state)?;
+        let mut db_txn = tables.db_txn;
+        db_txn.put(self.state_db, &STATE_KEY, &state_bytes, WriteFlags::empty())?;
+        db_txn.commit()?;
+
+        Ok(result)
+    }
+
+    fn load_state(&self, db_txn: &impl Transaction) -> Result<State, Error> {
+        match db_txn.get(self.state_db, &STATE_KEY) {
+            Ok(state_bytes) => Ok(mc_util_serial::deserialize(state_bytes)?),
+            Err(lmdb::Error::NotFound) => Ok(State::default()),
+            Err(err) => Err(err.into()),
+        }
+    }
+}
+
+/// LMDB key for the rows of a given ingress key and block index.
+fn block_key(ingress_key: &CompressedRistrettoPublic, block_index: u64) -> Vec<u8> {
+    let mut key = AsRef::<[u8]>::as_ref(ingress_key).to_vec();
+    key.extend_from_slice(&block_index.to_be_bytes());
+    key
+}
+
+/// Extract the block index out of a key created by `block_key`.
+fn block_index_from_key(key: &[u8]) -> Result<u64, Error> {
+    let index_bytes = key
+        .len()
+        .checked_sub(8)
+        .map(|start| &key[start..])
+        .ok_or(Error::Deserialization)?;
+    Ok(u64::from_be_bytes(
+        index_bytes.try_into().map_err(|_| Error::Deserialization)?,
+    ))
+}
+
+/// LMDB key for the report with a given report id. LMDB does not allow empty
+/// keys, and the default report id is the empty string, so report ids are
+/// prefixed.
+fn report_key(fog_report_id: &str) -> Vec<u8> {
+    let mut key = b"report:".to_vec();
+    key.extend_from_slice(fog_report_id.as_bytes());
+    key
+}
+
+/// Get and decode a row, if it exists.
+fn get_row<T: serde::de::DeserializeOwned>(
+    db_txn: &impl Transaction,
+    db: Database,
+    key: impl AsRef<[u8]>,
+) -> Result<Option<T>, Error> {
+    match db_txn.get(db, &key) {
+        Ok(bytes) => Ok(Some(mc_util_serial::deserialize(bytes)?)),
+        Err(lmdb::Error::NotFound) => Ok(None),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// The tables which are not part of `State`, accessed through an LMDB
+/// transaction.
+struct LmdbTables<T: Transaction> {
+    db_txn: T,
+    databases: LmdbDatabases,
+}
+
+impl<T: Transaction> TablesReader for LmdbTables<T> {
+    fn get_ingested_block(
+        &self,
+        ingress_key: &CompressedRistrettoPublic,
+        block_index: u64,
+    ) -> Result<Option<IngestedBlock>, Error> {
+        get_row(
+            &self.db_txn,
+            self.databases.ingested_blocks,
+            block_key(ingress_key, block_index),
+        )
+    }
+
+    fn get_block_data(
+        &self,
+        ingress_key: &CompressedRistrettoPublic,
+        block_index: u64,
+    ) -> Result<Option<Vec<ETxOutRecord>>, Error> {
+        match self.db_txn.get(
+            self.databases.block_data,
+            &block_key(ingress_key, block_index),
+        ) {
+            Ok(proto_bytes) => {
+                let proto: ProtoIngestedBlockData = mc_util_serial::decode(proto_bytes)?;
+                Ok(Some(proto.e_tx_out_records))
+            }
+            Err(lmdb::Error::NotFound) => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    fn for_each_block_data(&self, f: &mut dyn FnMut(u64, Vec<ETxOutRecord>)) -> Result<(), Error> {
+        let mut cursor = self.db_txn.open_ro_cursor(self.databases.block_data)?;
+        for result in cursor.iter_start() {
+            let (key, proto_bytes) = result?;
+            let proto: ProtoIngestedBlockData = mc_util_serial::decode(proto_bytes)?;
+            f(block_index_from_key(key)?, proto.e_tx_out_records);
+        }
+        Ok(())
+    }
+
+    fn for_each_user_event(
+        &self,
+        after_id: u64,
+        f: &mut dyn FnMut(u64, UserEvent),
+    ) -> Result<(), Error> {
+        let first_id = match after_id.checked_add(1) {
+            Some(first_id) => first_id,
+            None => return Ok(()),
+        };
+        let mut cursor = self.db_txn.open_ro_cursor(self.databases.user_events)?;
+        for result in cursor.iter_from(first_id.to_be_bytes()) {
+            let (key, event_bytes) = result?;
+            let id = u64::from_be_bytes(key.try_into().map_err(|_| Error::Deserialization)?);
+            f(id, mc_util_serial::deserialize(event_bytes)?);
+        }
+        Ok(())
+    }
+
+    fn get_report(&self, fog_report_id: &str) -> Result<Option<Report>, Error> {
+        get_row(
+            &self.db_txn,
+            self.databases.reports,
+            report_key(fog_report_id),
+        )
+    }
+
+    fn get_reports(&self) -> Result<Vec<Report>, Error> {
+        let mut cursor = self.db_txn.open_ro_cursor(self.databases.reports)?;
+        let mut reports = Vec::new();
+        for result in cursor.iter_start() {
+            let (_key, report_bytes) = result?;
+            reports.push(mc_util_serial::deserialize(report_bytes)?);
+        }
+        Ok(reports)
+    }
+}
+
+impl<'env> TablesWriter for LmdbTables<RwTransaction<'env>> {
+    fn put_ingested_block(
+        &mut self,
+        ingress_key: &CompressedRistrettoPublic,
+        block_index: u64,
+        block: &IngestedBlock,
+        records: &[ETxOutRecord],
+    ) -> Result<(), Error> {
+        let key = block_key(ingress_key, block_index);
+        self.db_txn.put(
+            self.databases.ingested_blocks,
+            &key,
+            &mc_util_serial::serialize(block)?,
+            WriteFlags::NO_OVERWRITE,
+        )?;
+
+        let proto_bytes = mc_util_serial::encode(&ProtoIngestedBlockData {
+            e_tx_out_records: records.to_vec(),
+        });
+        self.db_txn.put(
+            self.databases.block_data,
+            &key,
+            &proto_bytes,
+            WriteFlags::NO_OVERWRITE,
+        )?;
+        Ok(())
+    }
+
+    fn put_user_event(&mut self, id: u64, event: &UserEvent) -> Result<(), Error> {
+        self.db_txn.put(
+            self.databases.user_events,
+            &id.to_be_bytes(),
+            &mc_util_serial::serialize(event)?,
+            WriteFlags::NO_OVERWRITE,
+        )?;
+        Ok(())
+    }
+
+    fn put_report(&mut self, report: &Report) -> Result<(), Error> {
+        self.db_txn.put(
+            self.databases.reports,
+            &report_key(&report.fog_report_id),
+            &mc_util_serial::serialize(report)?,
+            WriteFlags::empty(),
+        )?;
+        Ok(())
+    }
+
+    fn remove_report(&mut self, fog_report_id: &str) -> Result<(), Error> {
+        match self
+            .db_txn
+            .del(self.databases.reports, &report_key(fog_report_id), None)
+        {
+            Ok(()) | Err(lmdb::Error::NotFound) => Ok(()),
+            Err(err) => Err(err.into()),
+        }
+    }
+}
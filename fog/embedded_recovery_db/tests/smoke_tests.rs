@@ -0,0 +1,47 @@
+// Copyright (c) 2018-2022 The MobileCoin Foundation
+
+use mc_common::logger::{test_with_logger, Logger};
+use mc_fog_embedded_recovery_db::EmbeddedRecoveryDb;
+use mc_fog_test_infra::db_tests::*;
+use tempdir::TempDir;
+
+/// Run a test against both the in-memory and the LMDB flavors of the
+/// embedded recovery db.
+fn with_each_db(logger: &Logger, mut test: impl FnMut(&EmbeddedRecoveryDb)) {
+    test(&EmbeddedRecoveryDb::new_in_memory(logger.clone()));
+
+    let db_dir = TempDir::new("embedded_recovery_db").unwrap();
+    test(&EmbeddedRecoveryDb::open(db_dir.path(), logger.clone()).unwrap());
+}
+
+#[test_with_logger]
+fn embedded_recovery_db_smoke_tests_new_apis(logger: Logger) {
+    mc_util_test_helper::run_with_several_seeds(|mut rng| {
+        with_each_db(&logger, |db| recovery_db_smoke_tests_new_apis(&mut rng, db));
+    })
+}
+
+#[test_with_logger]
+fn embedded_recovery_db_missed_blocks_reporting(logger: Logger) {
+    mc_util_test_helper::run_with_several_seeds(|mut rng| {
+        with_each_db(&logger, |db| {
+            recovery_db_missed_blocks_reporting(&mut rng, db)
+        });
+    })
+}
+
+#[test_with_logger]
+fn embedded_recovery_db_rng_records_decommissioning(logger: Logger) {
+    mc_util_test_helper::run_with_several_seeds(|mut rng| {
+        with_each_db(&logger, |db| {
+            recovery_db_rng_records_decommissioning(&mut rng, db)
+        });
+    })
+}
+
+#[test_with_logger]
+fn embedded_recovery_db_ingress_keys(logger: Logger) {
+    mc_util_test_helper::run_with_several_seeds(|mut rng| {
+        with_each_db(&logger, |db| test_recovery_db_ingress_keys(&mut rng, db));
+    })
+}
@@ -4,19 +4,22 @@
 //! Fog Ingest target
 
 use mc_attest_net::{Client, RaClient};
-use mc_common::logger::{log, o};
+use mc_common::logger::{log, o, Logger};
+use mc_fog_embedded_recovery_db::EmbeddedRecoveryDb;
 use mc_fog_ingest_enclave::ENCLAVE_FILE;
 use mc_fog_ingest_server::{
     config::IngestConfig,
+    error::IngestServiceError,
     server::{IngestServer, IngestServerConfig},
     state_file::StateFile,
 };
+use mc_fog_recovery_db_iface::{RecoveryDb, ReportDb};
 use mc_fog_sql_recovery_db::SqlRecoveryDb;
 use mc_ledger_db::LedgerDB;
 use mc_util_cli::ParserWithBuildInfo;
 use mc_util_grpc::AdminServer;
 use mc_watcher::watcher_db::WatcherDB;
-use std::{env, sync::Arc};
+use std::{env, path::PathBuf, sync::Arc};
 
 fn main() {
     mc_common::setup_panic_handler();
@@ -53,18 +56,57 @@ fn main() {
     // Open databases.
     let database_url =
         std::env::var("DATABASE_URL").expect("DATABASE_URL environment variable missing");
-    let recovery_db = SqlRecoveryDb::new_from_url(
-        &database_url,
-        config.postgres_config.clone(),
-        logger.clone(),
-    )
-    .unwrap_or_else(|err| {
-        panic!(
-            "fog-ingest cannot connect to database '{}': {:?}",
-            database_url, err
+    if EmbeddedRecoveryDb::is_embedded_url(&database_url) {
+        let recovery_db = EmbeddedRecoveryDb::new_from_url(&database_url, logger.clone())
+            .unwrap_or_else(|err| {
+                panic!(
+                    "fog-ingest cannot open embedded database '{}': {:?}",
+                    database_url, err
+                )
+            });
+        run(
+            config,
+            ias_client,
+            recovery_db,
+            state_file_path,
+            enclave_path,
+            logger,
         )
-    });
+    } else {
+        let recovery_db = SqlRecoveryDb::new_from_url(
+            &database_url,
+            config.postgres_config.clone(),
+            logger.clone(),
+        )
+        .unwrap_or_else(|err| {
+            panic!(
+                "fog-ingest cannot connect to database '{}': {:?}",
+                database_url, err
+            )
+        });
+        run(
+            config,
+            ias_client,
+            recovery_db,
+            state_file_path,
+            enclave_path,
+            logger,
+        )
+    }
+}
 
+fn run<DB>(
+    config: IngestConfig,
+    ias_client: Client,
+    recovery_db: DB,
+    state_file_path: PathBuf,
+    enclave_path: PathBuf,
+    logger: Logger,
+) -> !
+where
+    DB: RecoveryDb + ReportDb + Clone + Send + Sync + 'static,
+    IngestServiceError: From<<DB as RecoveryDb>::Error>,
+{
     let ledger_db = LedgerDB::open(&config.ledger_db).expect("Could not read ledger DB");
 
     let watcher =
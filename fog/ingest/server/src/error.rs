@@ -10,6 +10,7 @@ use mc_api::ConversionError;
 use mc_common::ResponderId;
 use mc_crypto_keys::CompressedRistrettoPublic;
 use mc_fog_api::report_parse::ReportParseError;
+use mc_fog_embedded_recovery_db::Error as EmbeddedRecoveryDbError;
 use mc_fog_ingest_enclave::Error as EnclaveError;
 use mc_fog_recovery_db_iface::RecoveryDbError;
 use mc_fog_sql_recovery_db::Error as SqlRecoveryDbError;
@@ -82,6 +83,12 @@ impl From<SqlRecoveryDbError> for IngestServiceError {
     }
 }
 
+impl From<EmbeddedRecoveryDbError> for IngestServiceError {
+    fn from(src: EmbeddedRecoveryDbError) -> Self {
+        Self::RecoveryDb(Box::new(src))
+    }
+}
+
 impl From<mc_util_serial::encode::Error> for IngestServiceError {
     fn from(_: mc_util_serial::encode::Error) -> Self {
         Self::Serialization
@@ -40,6 +40,12 @@ pub enum Error {
 
     /// Prost decode error
     ProstDecode,
+
+    /// Key image store responses do not match the query
+    StoreResponseMismatch,
+
+    /// Attested channel to a key image store failed: {0}
+    StoreChannel(AttestEnclaveError),
 }
 
 /// An error when something goes wrong with adding a record
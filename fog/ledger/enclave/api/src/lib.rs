@@ -15,7 +15,10 @@ pub use crate::{
 };
 use alloc::vec::Vec;
 use core::result::Result as StdResult;
-use mc_attest_enclave_api::{ClientAuthRequest, ClientAuthResponse, ClientSession, EnclaveMessage};
+use mc_attest_enclave_api::{
+    ClientAuthRequest, ClientAuthResponse, ClientSession, EnclaveMessage, PeerAuthRequest,
+    PeerAuthResponse, PeerSession,
+};
 use mc_common::ResponderId;
 use mc_crypto_keys::X25519Public;
 pub use mc_fog_types::ledger::{
@@ -98,6 +101,58 @@ pub trait LedgerEnclave: ReportableEnclave {
 
     /// Add a key image data to the oram Using thrm -rf targete key image
     fn add_key_image_data(&self, records: Vec<KeyImageData>) -> Result<()>;
+
+    // ROUTER-FACING METHODS
+    //
+    // When the key images are sharded across several key image stores, a fog
+    // ledger router accepts the client connections, and forwards their key
+    // image checks to the stores over a mutually attested channel between the
+    // enclaves.
+
+    /// Begin a connection to the key image store with the given responder id
+    fn key_image_store_init(&self, key_image_store_id: ResponderId) -> Result<PeerAuthRequest>;
+
+    /// Complete a connection to the key image store with the given responder
+    /// id, which accepted our auth request
+    fn key_image_store_connect(
+        &self,
+        key_image_store_id: ResponderId,
+        key_image_store_auth_response: PeerAuthResponse,
+    ) -> Result<PeerSession>;
+
+    /// Accept an inbound authentication request from a fog ledger router
+    fn router_accept(&self, req: PeerAuthRequest) -> Result<(PeerAuthResponse, PeerSession)>;
+
+    /// Destroy a fog ledger router or key image store association
+    fn peer_close(&self, session: PeerSession) -> Result<()>;
+
+    /// Decrypt a user's CheckKeyImagesRequest, and encrypt it for each of the
+    /// given key image stores. Errors of the channels to the stores are
+    /// returned as Error::StoreChannel, any other error leaves them untouched.
+    fn create_key_image_store_queries(
+        &self,
+        client_query: EnclaveMessage<ClientSession>,
+        key_image_stores: Vec<PeerSession>,
+    ) -> Result<Vec<EnclaveMessage<PeerSession>>>;
+
+    /// Service a CheckKeyImagesRequest forwarded by a fog ledger router, with
+    /// the key images of this store
+    fn check_key_image_store(
+        &self,
+        query: EnclaveMessage<PeerSession>,
+    ) -> Result<EnclaveMessage<PeerSession>>;
+
+    /// Merge the responses of the key image stores to a user's
+    /// CheckKeyImagesRequest into a CheckKeyImagesResponse, encrypted for the
+    /// user. Errors of the channels to the stores are returned as
+    /// Error::StoreChannel, any other error means that every response was
+    /// decrypted.
+    fn collate_key_image_store_responses(
+        &self,
+        client_session: ClientSession,
+        store_responses: Vec<EnclaveMessage<PeerSession>>,
+        untrusted_key_image_query_response: UntrustedKeyImageQueryResponse,
+    ) -> Result<Vec<u8>>;
 }
 
 /// Helper trait which reduces boiler-plate in untrusted side
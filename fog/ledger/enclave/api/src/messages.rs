@@ -4,7 +4,10 @@
 use crate::UntrustedKeyImageQueryResponse;
 use alloc::vec::Vec;
 use mc_attest_core::{Quote, Report, TargetInfo, VerificationReport};
-use mc_attest_enclave_api::{ClientAuthRequest, ClientSession, EnclaveMessage};
+use mc_attest_enclave_api::{
+    ClientAuthRequest, ClientSession, EnclaveMessage, PeerAuthRequest, PeerAuthResponse,
+    PeerSession,
+};
 use mc_common::ResponderId;
 use mc_fog_types::ledger::GetOutputsResponse;
 use mc_transaction_core::ring_signature::KeyImage;
@@ -101,4 +104,44 @@ pub enum EnclaveCall {
     ///
     ///  Add key image data to the ORAM.
     AddKeyImageData(Vec<KeyImageData>),
+
+    /// The [LedgerEnclave::key_image_store_init()] method.
+    ///
+    /// Begin a connection to a key image store, as a fog ledger router.
+    KeyImageStoreInit(ResponderId),
+
+    /// The [LedgerEnclave::key_image_store_connect()] method.
+    ///
+    /// Complete a connection to a key image store, as a fog ledger router.
+    KeyImageStoreConnect(ResponderId, PeerAuthResponse),
+
+    /// The [LedgerEnclave::router_accept()] method.
+    ///
+    /// Accept a connection from a fog ledger router, as a key image store.
+    RouterAccept(PeerAuthRequest),
+
+    /// The [LedgerEnclave::peer_close()] method.
+    ///
+    /// Tears down any in-enclave state about a router or store association.
+    PeerClose(PeerSession),
+
+    /// The [LedgerEnclave::create_key_image_store_queries()] method.
+    ///
+    /// Forward a key image check from a client to each of the key image
+    /// stores.
+    CreateKeyImageStoreQueries(EnclaveMessage<ClientSession>, Vec<PeerSession>),
+
+    /// The [LedgerEnclave::check_key_image_store()] method.
+    ///
+    /// Check key images forwarded by a fog ledger router.
+    CheckKeyImageStore(EnclaveMessage<PeerSession>),
+
+    /// The [LedgerEnclave::collate_key_image_store_responses()] method.
+    ///
+    /// Merge the responses of the key image stores for a client.
+    CollateKeyImageStoreResponses(
+        ClientSession,
+        Vec<EnclaveMessage<PeerSession>>,
+        UntrustedKeyImageQueryResponse,
+    ),
 }
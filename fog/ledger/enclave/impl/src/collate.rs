@@ -0,0 +1,163 @@
+// Copyright (c) 2018-2022 The MobileCoin Foundation
+
+//! Merges the key image results of several key image stores.
+//!
+//! When the key images are sharded across key image stores by block range, a
+//! fog ledger router forwards the same key image check to every store. A key
+//! image is spent in at most one block, so at most one store reports it as
+//! spent, and the others report it as not spent. Every store's result is
+//! visited, and the spent one is selected with constant-time conditional
+//! assignments, so the timing of the router does not tell which block range a
+//! spent key image falls in.
+//!
+//! A spent result takes precedence over an error, and an error takes
+//! precedence over not spent, so that a store failing to check its shard is
+//! not reported as the key image being unspent.
+
+use aligned_cmov::subtle::{ConditionallySelectable, ConstantTimeEq};
+use alloc::vec::Vec;
+use mc_fog_ledger_enclave_api::{Error, Result};
+use mc_fog_types::ledger::{KeyImageResult, KeyImageResultCode};
+
+/// Merge the key image results each key image store returned for the same
+/// list of key images.
+pub fn collate_key_image_results(
+    store_results: Vec<Vec<KeyImageResult>>,
+) -> Result<Vec<KeyImageResult>> {
+    let mut store_results = store_results.into_iter();
+    let mut collated = match store_results.next() {
+        Some(results) => results,
+        None => return Ok(Vec::new()),
+    };
+
+    for results in store_results {
+        if results.len() != collated.len() {
+            return Err(Error::StoreResponseMismatch);
+        }
+
+        for (collated_result, result) in collated.iter_mut().zip(results) {
+            if collated_result.key_image != result.key_image {
+                return Err(Error::StoreResponseMismatch);
+            }
+            select_by_precedence(collated_result, &result);
+        }
+    }
+
+    Ok(collated)
+}
+
+// Replace `dst` with `src` if `src` takes precedence, in constant time.
+//
+// Spent takes precedence over any error code, which takes precedence over
+// NotSpent.
+fn select_by_precedence(dst: &mut KeyImageResult, src: &KeyImageResult) {
+    let spent = KeyImageResultCode::Spent as u32;
+    let not_spent = KeyImageResultCode::NotSpent as u32;
+
+    let src_spent = src.key_image_result_code.ct_eq(&spent);
+    let src_error = !src_spent & !src.key_image_result_code.ct_eq(&not_spent);
+    let dst_spent = dst.key_image_result_code.ct_eq(&spent);
+    let dst_not_spent = dst.key_image_result_code.ct_eq(&not_spent);
+    let select = (src_spent & !dst_spent) | (src_error & dst_not_spent);
+
+    dst.spent_at.conditional_assign(&src.spent_at, select);
+    dst.timestamp.conditional_assign(&src.timestamp, select);
+    dst.timestamp_result_code
+        .conditional_assign(&src.timestamp_result_code, select);
+    dst.key_image_result_code
+        .conditional_assign(&src.key_image_result_code, select);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+    use mc_transaction_core::ring_signature::KeyImage;
+    use mc_watcher_api::TimestampResultCode;
+
+    fn spent(key_image: u64, spent_at: u64) -> KeyImageResult {
+        KeyImageResult {
+            key_image: KeyImage::from(key_image),
+            spent_at,
+            timestamp: spent_at * 10,
+            timestamp_result_code: TimestampResultCode::TimestampFound as u32,
+            key_image_result_code: KeyImageResultCode::Spent as u32,
+        }
+    }
+
+    fn not_spent(key_image: u64) -> KeyImageResult {
+        KeyImageResult {
+            key_image: KeyImage::from(key_image),
+            spent_at: 1,
+            timestamp: u64::MAX,
+            timestamp_result_code: TimestampResultCode::TimestampFound as u32,
+            key_image_result_code: KeyImageResultCode::NotSpent as u32,
+        }
+    }
+
+    fn error(key_image: u64) -> KeyImageResult {
+        KeyImageResult {
+            key_image_result_code: KeyImageResultCode::KeyImageError as u32,
+            ..not_spent(key_image)
+        }
+    }
+
+    #[test]
+    fn collate_picks_spent_results() {
+        let store_results = vec![
+            vec![spent(1, 3), not_spent(2), not_spent(3)],
+            vec![not_spent(1), spent(2, 12), not_spent(3)],
+        ];
+
+        assert_eq!(
+            collate_key_image_results(store_results).unwrap(),
+            vec![spent(1, 3), spent(2, 12), not_spent(3)]
+        );
+    }
+
+    #[test]
+    fn collate_prefers_errors_to_not_spent() {
+        assert_eq!(
+            collate_key_image_results(vec![vec![error(1)], vec![not_spent(1)]]).unwrap(),
+            vec![error(1)]
+        );
+        assert_eq!(
+            collate_key_image_results(vec![vec![not_spent(1)], vec![error(1)]]).unwrap(),
+            vec![error(1)]
+        );
+    }
+
+    #[test]
+    fn collate_prefers_spent_to_errors() {
+        assert_eq!(
+            collate_key_image_results(vec![vec![error(1)], vec![spent(1, 3)]]).unwrap(),
+            vec![spent(1, 3)]
+        );
+        assert_eq!(
+            collate_key_image_results(vec![vec![spent(1, 3)], vec![error(1)]]).unwrap(),
+            vec![spent(1, 3)]
+        );
+    }
+
+    #[test]
+    fn collate_single_and_no_stores() {
+        let results = vec![spent(1, 3), not_spent(2)];
+        assert_eq!(
+            collate_key_image_results(vec![results.clone()]).unwrap(),
+            results
+        );
+        assert_eq!(collate_key_image_results(vec![]).unwrap(), vec![]);
+    }
+
+    #[test]
+    fn collate_rejects_mismatched_responses() {
+        assert_eq!(
+            collate_key_image_results(vec![vec![not_spent(1)], vec![not_spent(2)]]),
+            Err(Error::StoreResponseMismatch)
+        );
+        assert_eq!(
+            collate_key_image_results(vec![vec![not_spent(1)], vec![]]),
+            Err(Error::StoreResponseMismatch)
+        );
+    }
+}
@@ -12,11 +12,16 @@
 #![deny(missing_docs)]
 extern crate alloc;
 
+mod collate;
 mod key_image_store;
 use alloc::vec::Vec;
+use collate::collate_key_image_results;
 use key_image_store::{KeyImageStore, StorageDataSize, StorageMetaSize};
 use mc_attest_core::{IasNonce, Quote, QuoteNonce, Report, TargetInfo, VerificationReport};
-use mc_attest_enclave_api::{ClientAuthRequest, ClientAuthResponse, ClientSession, EnclaveMessage};
+use mc_attest_enclave_api::{
+    ClientAuthRequest, ClientAuthResponse, ClientSession, EnclaveMessage, PeerAuthRequest,
+    PeerAuthResponse, PeerSession,
+};
 use mc_common::{
     logger::{log, Logger},
     ResponderId,
@@ -28,6 +33,7 @@ use mc_fog_ledger_enclave_api::{
 };
 use mc_fog_types::ledger::{
     CheckKeyImagesRequest, CheckKeyImagesResponse, GetOutputsRequest, GetOutputsResponse,
+    KeyImageResult,
 };
 use mc_oblivious_traits::ORAMStorageCreator;
 use mc_sgx_compat::sync::Mutex;
@@ -61,6 +67,37 @@ where
             logger,
         }
     }
+
+    fn decode_check_key_images_request(&self, plaintext: &[u8]) -> Result<CheckKeyImagesRequest> {
+        mc_util_serial::decode(plaintext).map_err(|e| {
+            log::error!(self.logger, "Could not decode user request: {}", e);
+            Error::ProstDecode
+        })
+    }
+
+    fn find_key_images(&self, req: &CheckKeyImagesRequest) -> Result<Vec<KeyImageResult>> {
+        let mut lk = self.key_image_store.lock()?;
+        let store = lk.as_mut().ok_or(Error::EnclaveNotInitialized)?;
+
+        Ok(req
+            .queries
+            .iter() //  get the key images used to find the key image data using the oram
+            .map(|key| store.find_record(&key.key_image))
+            .collect())
+    }
+
+    fn untrusted_key_image_query_response_to_response(
+        untrusted_key_image_query_response: UntrustedKeyImageQueryResponse,
+    ) -> CheckKeyImagesResponse {
+        CheckKeyImagesResponse {
+            num_blocks: untrusted_key_image_query_response.highest_processed_block_count,
+            results: Default::default(),
+            global_txo_count: untrusted_key_image_query_response
+                .last_known_block_cumulative_txo_count,
+            latest_block_version: untrusted_key_image_query_response.latest_block_version,
+            max_block_version: untrusted_key_image_query_response.max_block_version,
+        }
+    }
 }
 
 /// Implementation of the reportable enclave for sgxledger enclave
@@ -92,7 +129,9 @@ where
     OSC: ORAMStorageCreator<StorageDataSize, StorageMetaSize>,
 {
     fn enclave_init(&self, self_id: &ResponderId, desired_capacity: u64) -> Result<()> {
-        self.ake.init(Default::default(), self_id.clone())?;
+        // Fog ledger routers connect to key image stores using the same
+        // responder id as clients.
+        self.ake.init(self_id.clone(), self_id.clone())?;
         let mut lk = self.key_image_store.lock()?;
 
         *lk = Some(KeyImageStore::new(desired_capacity, self.logger.clone()));
@@ -145,31 +184,12 @@ where
         let channel_id = msg.channel_id.clone(); //client session does not implement copy trait so clone
         let user_plaintext = self.ake.client_decrypt(msg)?;
 
-        let req: CheckKeyImagesRequest = mc_util_serial::decode(&user_plaintext).map_err(|e| {
-            log::error!(self.logger, "Could not decode user request: {}", e);
-            Error::ProstDecode
-        })?;
+        let req = self.decode_check_key_images_request(&user_plaintext)?;
 
-        let mut resp = CheckKeyImagesResponse {
-            num_blocks: untrusted_key_image_query_response.highest_processed_block_count,
-            results: Default::default(),
-            global_txo_count: untrusted_key_image_query_response
-                .last_known_block_cumulative_txo_count,
-            latest_block_version: untrusted_key_image_query_response.latest_block_version,
-            max_block_version: untrusted_key_image_query_response.max_block_version,
-        };
-
-        // Do the scope lock of keyimagetore
-        {
-            let mut lk = self.key_image_store.lock()?;
-            let store = lk.as_mut().ok_or(Error::EnclaveNotInitialized)?;
-
-            resp.results = req
-                .queries
-                .iter() //  get the key images used to find the key image data using the oram
-                .map(|key| store.find_record(&key.key_image))
-                .collect();
-        }
+        let mut resp = Self::untrusted_key_image_query_response_to_response(
+            untrusted_key_image_query_response,
+        );
+        resp.results = self.find_key_images(&req)?;
 
         let response_plaintext_bytes = mc_util_serial::encode(&resp);
 
@@ -191,6 +211,111 @@ where
 
         Ok(())
     }
+
+    // Router-facing
+
+    fn key_image_store_init(&self, key_image_store_id: ResponderId) -> Result<PeerAuthRequest> {
+        Ok(self.ake.peer_init(&key_image_store_id)?)
+    }
+
+    fn key_image_store_connect(
+        &self,
+        key_image_store_id: ResponderId,
+        key_image_store_auth_response: PeerAuthResponse,
+    ) -> Result<PeerSession> {
+        let (session, _verification_report) = self
+            .ake
+            .peer_connect(&key_image_store_id, key_image_store_auth_response)?;
+        Ok(session)
+    }
+
+    fn router_accept(&self, req: PeerAuthRequest) -> Result<(PeerAuthResponse, PeerSession)> {
+        Ok(self.ake.peer_accept(req)?)
+    }
+
+    fn peer_close(&self, session: PeerSession) -> Result<()> {
+        Ok(self.ake.peer_close(&session)?)
+    }
+
+    fn create_key_image_store_queries(
+        &self,
+        client_query: EnclaveMessage<ClientSession>,
+        key_image_stores: Vec<PeerSession>,
+    ) -> Result<Vec<EnclaveMessage<PeerSession>>> {
+        let user_plaintext = self.ake.client_decrypt(client_query)?;
+
+        // Make sure we only forward well-formed requests.
+        self.decode_check_key_images_request(&user_plaintext)?;
+
+        key_image_stores
+            .iter()
+            .map(|key_image_store| {
+                self.ake
+                    .peer_encrypt(key_image_store, &[], &user_plaintext)
+                    .map_err(Error::StoreChannel)
+            })
+            .collect()
+    }
+
+    fn check_key_image_store(
+        &self,
+        query: EnclaveMessage<PeerSession>,
+    ) -> Result<EnclaveMessage<PeerSession>> {
+        let channel_id = query.channel_id.clone();
+        let router_plaintext = self.ake.peer_decrypt(query)?;
+
+        let req = self.decode_check_key_images_request(&router_plaintext)?;
+
+        // The router fills in everything but the results.
+        let resp = CheckKeyImagesResponse {
+            results: self.find_key_images(&req)?,
+            ..Default::default()
+        };
+
+        let response_plaintext_bytes = mc_util_serial::encode(&resp);
+
+        Ok(self
+            .ake
+            .peer_encrypt(&channel_id, &[], &response_plaintext_bytes)?)
+    }
+
+    fn collate_key_image_store_responses(
+        &self,
+        client_session: ClientSession,
+        store_responses: Vec<EnclaveMessage<PeerSession>>,
+        untrusted_key_image_query_response: UntrustedKeyImageQueryResponse,
+    ) -> Result<Vec<u8>> {
+        // Decrypt every response before decoding any, so that the channels to
+        // the stores are left in a usable state if a response is malformed.
+        let store_plaintexts = store_responses
+            .into_iter()
+            .map(|store_response| {
+                self.ake
+                    .peer_decrypt(store_response)
+                    .map_err(Error::StoreChannel)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let store_results = store_plaintexts
+            .iter()
+            .map(|store_plaintext| {
+                let store_resp: CheckKeyImagesResponse = mc_util_serial::decode(store_plaintext)?;
+                Ok(store_resp.results)
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut resp = Self::untrusted_key_image_query_response_to_response(
+            untrusted_key_image_query_response,
+        );
+        resp.results = collate_key_image_results(store_results)?;
+
+        let response_plaintext_bytes = mc_util_serial::encode(&resp);
+
+        let response = self
+            .ake
+            .client_encrypt(&client_session, &[], &response_plaintext_bytes)?;
+
+        Ok(response.data)
+    }
 }
 
 #[cfg(test)]
@@ -61,6 +61,28 @@ pub fn ecall_dispatcher(inbuf: &[u8]) -> Result<Vec<u8>, sgx_status_t> {
         }
         // Add Key Image Data
         EnclaveCall::AddKeyImageData(records) => serialize(&ENCLAVE.add_key_image_data(records)),
+        // Router / store sharding
+        EnclaveCall::KeyImageStoreInit(key_image_store_id) => {
+            serialize(&ENCLAVE.key_image_store_init(key_image_store_id))
+        }
+        EnclaveCall::KeyImageStoreConnect(key_image_store_id, auth_response) => {
+            serialize(&ENCLAVE.key_image_store_connect(key_image_store_id, auth_response))
+        }
+        EnclaveCall::RouterAccept(auth_request) => serialize(&ENCLAVE.router_accept(auth_request)),
+        EnclaveCall::PeerClose(session) => serialize(&ENCLAVE.peer_close(session)),
+        EnclaveCall::CreateKeyImageStoreQueries(client_query, key_image_stores) => {
+            serialize(&ENCLAVE.create_key_image_store_queries(client_query, key_image_stores))
+        }
+        EnclaveCall::CheckKeyImageStore(query) => serialize(&ENCLAVE.check_key_image_store(query)),
+        EnclaveCall::CollateKeyImageStoreResponses(
+            client_session,
+            store_responses,
+            untrusted_keyimagequery_response,
+        ) => serialize(&ENCLAVE.collate_key_image_store_responses(
+            client_session,
+            store_responses,
+            untrusted_keyimagequery_response,
+        )),
     }
     .or(Err(sgx_status_t::SGX_ERROR_UNEXPECTED))
 }
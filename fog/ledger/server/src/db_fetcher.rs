@@ -3,6 +3,10 @@
 //! A background thread, in the server side, that continuously checks the
 //! LedgerDB for new blocks, then gets all the key images associated to those
 //! blocks and adds them to the enclave.
+//!
+//! When the key images are sharded, only the key images of the blocks in the
+//! server's range are added to the enclave, but every block is still walked so
+//! that the reported block counts stay in sync with the ledger.
 use crate::{counters, server::DbPollSharedState};
 use mc_common::{
     logger::{log, Logger},
@@ -10,6 +14,7 @@ use mc_common::{
 };
 use mc_fog_ledger_enclave::LedgerEnclaveProxy;
 use mc_fog_ledger_enclave_api::KeyImageData;
+use mc_fog_types::sharding::EpochShardingStrategy;
 use mc_ledger_db::{self, Error as LedgerError, Ledger};
 use mc_util_grpc::ReadinessIndicator;
 use mc_util_telemetry::{
@@ -43,6 +48,7 @@ impl DbFetcher {
         db: DB,
         enclave: E,
         watcher: WatcherDB,
+        sharding_strategy: EpochShardingStrategy,
         db_poll_shared_state: Arc<Mutex<DbPollSharedState>>,
         readiness_indicator: ReadinessIndicator,
         logger: Logger,
@@ -60,6 +66,7 @@ impl DbFetcher {
                         0,
                         enclave,
                         watcher,
+                        sharding_strategy,
                         thread_shared_state,
                         readiness_indicator,
                         logger,
@@ -97,6 +104,7 @@ struct DbFetcherThread<DB: Ledger, E: LedgerEnclaveProxy + Clone + Send + Sync +
     next_block_index: u64,
     enclave: E,
     watcher: WatcherDB,
+    sharding_strategy: EpochShardingStrategy,
     db_poll_shared_state: Arc<Mutex<DbPollSharedState>>,
     readiness_indicator: ReadinessIndicator,
     logger: Logger,
@@ -114,6 +122,7 @@ impl<DB: Ledger, E: LedgerEnclaveProxy + Clone + Send + Sync + 'static> DbFetche
         next_block_index: u64,
         enclave: E,
         watcher: WatcherDB,
+        sharding_strategy: EpochShardingStrategy,
         db_poll_shared_state: Arc<Mutex<DbPollSharedState>>,
         readiness_indicator: ReadinessIndicator,
         logger: Logger,
@@ -124,6 +133,7 @@ impl<DB: Ledger, E: LedgerEnclaveProxy + Clone + Send + Sync + 'static> DbFetche
             next_block_index,
             enclave,
             watcher,
+            sharding_strategy,
             db_poll_shared_state,
             readiness_indicator,
             logger,
@@ -205,26 +215,31 @@ impl<DB: Ledger, E: LedgerEnclaveProxy + Clone + Send + Sync + 'static> DbFetche
 
                 let _active = mark_span_as_active(span);
 
-                // Get the timestamp for the block.
-                let timestamp = tracer.in_span("poll_block_timestamp", |_cx| {
-                    self.watcher
-                        .poll_block_timestamp(self.next_block_index, watcher_timeout)
-                });
+                // Add block to enclave, if it is part of our shard.
+                if self
+                    .sharding_strategy
+                    .should_process_block(self.next_block_index)
+                {
+                    // Get the timestamp for the block.
+                    let timestamp = tracer.in_span("poll_block_timestamp", |_cx| {
+                        self.watcher
+                            .poll_block_timestamp(self.next_block_index, watcher_timeout)
+                    });
 
-                // Add block to enclave.
-                let records = block_contents
-                    .key_images
-                    .iter()
-                    .map(|key_image| KeyImageData {
-                        key_image: *key_image,
-                        block_index: self.next_block_index,
-                        timestamp,
-                    })
-                    .collect();
+                    let records = block_contents
+                        .key_images
+                        .iter()
+                        .map(|key_image| KeyImageData {
+                            key_image: *key_image,
+                            block_index: self.next_block_index,
+                            timestamp,
+                        })
+                        .collect();
 
-                tracer.in_span("add_records_to_enclave", |_cx| {
-                    self.add_records_to_enclave(self.next_block_index, records);
-                });
+                    tracer.in_span("add_records_to_enclave", |_cx| {
+                        self.add_records_to_enclave(self.next_block_index, records);
+                    });
+                }
 
                 // Update shared state.
                 tracer.in_span("update_shared_state", |_cx| {
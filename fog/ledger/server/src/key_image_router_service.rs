@@ -0,0 +1,255 @@
+// Copyright (c) 2018-2022 The MobileCoin Foundation
+
+//! The client-facing key image service of a ledger router, which fans client
+//! key image checks out to the key image stores and merges their results.
+
+use crate::key_image_store::{
+    merge_key_image_store_statuses, KeyImageStoreApi, KeyImageStoreConnection, KeyImageStoreError,
+    KeyImageStoreStatus,
+};
+use grpcio::{RpcContext, RpcStatus, UnarySink};
+use mc_attest_api::{
+    attest,
+    attest::{AuthMessage, Message},
+};
+use mc_attest_enclave_api::{ClientSession, EnclaveMessage, PeerSession};
+use mc_blockchain_types::MAX_BLOCK_VERSION;
+use mc_common::logger::{log, Logger};
+use mc_fog_api::{ledger::KeyImageStoreQueryResponse, ledger_grpc::FogKeyImageApi};
+use mc_fog_ledger_enclave::LedgerEnclaveProxy;
+use mc_fog_ledger_enclave_api::{Error as EnclaveError, UntrustedKeyImageQueryResponse};
+use mc_fog_store_connection::StoreSessions;
+use mc_util_grpc::{
+    check_request_chain_id, rpc_internal_error, rpc_invalid_arg_error, rpc_logger,
+    rpc_permissions_error, rpc_unavailable_error, send_result, Authenticator,
+};
+use mc_util_metrics::SVC_COUNTERS;
+use std::sync::Arc;
+
+#[derive(Clone)]
+pub struct KeyImageRouterService<E: LedgerEnclaveProxy> {
+    chain_id: String,
+    enclave: E,
+    /// The key image stores. Each client check uses attested channels to the
+    /// stores of its own, so checks are fanned out concurrently.
+    key_image_stores: Arc<Vec<KeyImageStoreConnection<E>>>,
+    authenticator: Arc<dyn Authenticator + Send + Sync>,
+    logger: Logger,
+}
+
+impl<E: LedgerEnclaveProxy> KeyImageRouterService<E> {
+    pub fn new(
+        chain_id: String,
+        enclave: E,
+        key_image_stores: Arc<Vec<KeyImageStoreConnection<E>>>,
+        authenticator: Arc<dyn Authenticator + Send + Sync>,
+        logger: Logger,
+    ) -> Self {
+        Self {
+            chain_id,
+            enclave,
+            key_image_stores,
+            authenticator,
+            logger,
+        }
+    }
+
+    /// Fan the key image check out to the key image stores, and merge their
+    /// responses
+    fn check_key_images_auth(
+        &mut self,
+        request: attest::Message,
+    ) -> Result<attest::Message, RpcStatus> {
+        log::trace!(self.logger, "Getting encrypted request");
+
+        let client_session = ClientSession::from(request.get_channel_id());
+        let sessions =
+            StoreSessions::take(&self.key_image_stores).map_err(|(key_image_store, err)| {
+                self.key_image_store_err_to_rpc_status(key_image_store, err)
+            })?;
+
+        let (sessions, store_responses) = self.check_key_image_stores(sessions, request.into())?;
+
+        let statuses = store_responses
+            .iter()
+            .map(KeyImageStoreStatus::from)
+            .collect::<Vec<_>>();
+        let merged_status = merge_key_image_store_statuses(&statuses);
+
+        let untrusted_query_response = UntrustedKeyImageQueryResponse {
+            highest_processed_block_count: merged_status.highest_processed_block_count,
+            last_known_block_cumulative_txo_count: merged_status
+                .last_known_block_cumulative_txo_count,
+            latest_block_version: merged_status.latest_block_version,
+            max_block_version: merged_status.latest_block_version.max(*MAX_BLOCK_VERSION),
+        };
+
+        let store_messages: Vec<EnclaveMessage<PeerSession>> = store_responses
+            .into_iter()
+            .map(|mut store_response| store_response.take_query_response().into())
+            .collect();
+
+        let result = self.enclave.collate_key_image_store_responses(
+            client_session,
+            store_messages,
+            untrusted_query_response,
+        );
+        match result {
+            Err(EnclaveError::StoreChannel(_)) => sessions.close(),
+            _ => sessions.release(),
+        }
+        let result_blob =
+            result.map_err(|e| self.enclave_err_to_rpc_status("enclave request", e))?;
+
+        let mut resp = attest::Message::new();
+        resp.set_data(result_blob);
+        Ok(resp)
+    }
+
+    /// Encrypt the client's key image check for each of the key image stores,
+    /// and send it to all of them at once. The channels of the check are
+    /// returned with the responses, in the order of the stores.
+    ///
+    /// On failure, the channels are given back to the stores, unless they
+    /// consumed a message whose response will not be decrypted.
+    fn check_key_image_stores<'a>(
+        &self,
+        sessions: StoreSessions<'a, KeyImageStoreApi<E>>,
+        client_query: EnclaveMessage<ClientSession>,
+    ) -> Result<
+        (
+            StoreSessions<'a, KeyImageStoreApi<E>>,
+            Vec<KeyImageStoreQueryResponse>,
+        ),
+        RpcStatus,
+    > {
+        let store_queries = match self
+            .enclave
+            .create_key_image_store_queries(client_query, sessions.sessions().to_vec())
+        {
+            Ok(store_queries) => store_queries,
+            Err(err) => {
+                // Errors caused by the client's check leave the channels
+                // untouched.
+                match err {
+                    EnclaveError::StoreChannel(_) => sessions.close(),
+                    _ => sessions.release(),
+                }
+                return Err(self.enclave_err_to_rpc_status("enclave request", err));
+            }
+        };
+
+        // Send all the checks before waiting for any response, so that the
+        // stores search their shards in parallel.
+        let store_responses = self
+            .key_image_stores
+            .iter()
+            .zip(store_queries)
+            .map(|(key_image_store, store_query)| {
+                key_image_store
+                    .query_async(store_query)
+                    .map_err(|err| self.key_image_store_err_to_rpc_status(key_image_store, err))
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .and_then(|receivers| {
+                self.key_image_stores
+                    .iter()
+                    .zip(receivers)
+                    .map(|(key_image_store, receiver)| {
+                        receiver.receive_sync().map_err(|err| {
+                            self.key_image_store_err_to_rpc_status(key_image_store, err.into())
+                        })
+                    })
+                    .collect::<Result<Vec<_>, _>>()
+            });
+        match store_responses {
+            Ok(store_responses) => Ok((sessions, store_responses)),
+            Err(err) => {
+                sessions.close();
+                Err(err)
+            }
+        }
+    }
+
+    fn key_image_store_err_to_rpc_status(
+        &self,
+        key_image_store: &KeyImageStoreConnection<E>,
+        src: KeyImageStoreError,
+    ) -> RpcStatus {
+        rpc_unavailable_error(
+            format!("key image store {}", key_image_store.uri()),
+            src,
+            &self.logger,
+        )
+    }
+
+    // Helper function that is common
+    fn enclave_err_to_rpc_status(&self, context: &str, src: EnclaveError) -> RpcStatus {
+        // Treat prost-decode error as an invalid arg,
+        // treat attest error as permission denied,
+        // everything else is an internal error
+        match src {
+            EnclaveError::ProstDecode => {
+                rpc_invalid_arg_error(context, "Prost decode failed", &self.logger)
+            }
+            EnclaveError::Attest(err) => rpc_permissions_error(context, err, &self.logger),
+            EnclaveError::StoreChannel(err) => rpc_unavailable_error(context, err, &self.logger),
+            other => rpc_internal_error(context, format!("{}", &other), &self.logger),
+        }
+    }
+}
+
+impl<E: LedgerEnclaveProxy> FogKeyImageApi for KeyImageRouterService<E> {
+    fn check_key_images(&mut self, ctx: RpcContext, request: Message, sink: UnarySink<Message>) {
+        let _timer = SVC_COUNTERS.req(&ctx);
+        mc_common::logger::scoped_global_logger(&rpc_logger(&ctx, &self.logger), |logger| {
+            if let Err(err) = check_request_chain_id(&self.chain_id, &ctx) {
+                return send_result(ctx, sink, Err(err), logger);
+            }
+
+            if let Err(err) = self.authenticator.authenticate_rpc(&ctx) {
+                return send_result(ctx, sink, err.into(), logger);
+            }
+
+            send_result(ctx, sink, self.check_key_images_auth(request), logger)
+        })
+    }
+
+    fn auth(&mut self, ctx: RpcContext, request: AuthMessage, sink: UnarySink<AuthMessage>) {
+        let _timer = SVC_COUNTERS.req(&ctx);
+        mc_common::logger::scoped_global_logger(&rpc_logger(&ctx, &self.logger), |logger| {
+            if let Err(err) = check_request_chain_id(&self.chain_id, &ctx) {
+                return send_result(ctx, sink, Err(err), logger);
+            }
+
+            if let Err(err) = self.authenticator.authenticate_rpc(&ctx) {
+                return send_result(ctx, sink, err.into(), logger);
+            }
+
+            match self.enclave.client_accept(request.into()) {
+                Ok((response, _session_id)) => {
+                    send_result(ctx, sink, Ok(response.into()), logger);
+                }
+                Err(client_error) => {
+                    // This is debug because there's no requirement on the remote party to trigger
+                    // it.
+                    log::debug!(
+                        logger,
+                        "LedgerEnclave::client_accept failed: {}",
+                        client_error
+                    );
+                    send_result(
+                        ctx,
+                        sink,
+                        Err(rpc_permissions_error(
+                            "client_auth",
+                            "Permission denied",
+                            logger,
+                        )),
+                        logger,
+                    );
+                }
+            }
+        });
+    }
+}
@@ -7,9 +7,13 @@ use mc_attest_api::{
 };
 use mc_blockchain_types::MAX_BLOCK_VERSION;
 use mc_common::logger::{log, Logger};
-use mc_fog_api::ledger_grpc::FogKeyImageApi;
+use mc_fog_api::{
+    ledger::KeyImageStoreQueryResponse,
+    ledger_grpc::{FogKeyImageApi, FogKeyImageStoreApi},
+};
 use mc_fog_ledger_enclave::LedgerEnclaveProxy;
 use mc_fog_ledger_enclave_api::{Error as EnclaveError, UntrustedKeyImageQueryResponse};
+use mc_fog_types::sharding::EpochShardingStrategy;
 use mc_ledger_db::Ledger;
 use mc_util_grpc::{
     check_request_chain_id, rpc_internal_error, rpc_invalid_arg_error, rpc_logger,
@@ -29,6 +33,8 @@ pub struct KeyImageService<L: Ledger + Clone, E: LedgerEnclaveProxy> {
     logger: Logger,
     /// Shared state from db polling thread.
     db_poll_shared_state: Arc<Mutex<DbPollSharedState>>,
+    /// The range of blocks whose key images are loaded in the enclave.
+    sharding_strategy: EpochShardingStrategy,
 }
 
 impl<L: Ledger + Clone, E: LedgerEnclaveProxy> KeyImageService<L, E> {
@@ -38,6 +44,7 @@ impl<L: Ledger + Clone, E: LedgerEnclaveProxy> KeyImageService<L, E> {
         watcher: WatcherDB,
         enclave: E,
         db_poll_shared_state: Arc<Mutex<DbPollSharedState>>,
+        sharding_strategy: EpochShardingStrategy,
         authenticator: Arc<dyn Authenticator + Send + Sync>,
         logger: Logger,
     ) -> Self {
@@ -49,6 +56,7 @@ impl<L: Ledger + Clone, E: LedgerEnclaveProxy> KeyImageService<L, E> {
             authenticator,
             logger,
             db_poll_shared_state,
+            sharding_strategy,
         }
     }
 
@@ -106,6 +114,31 @@ impl<L: Ledger + Clone, E: LedgerEnclaveProxy> KeyImageService<L, E> {
         Ok(resp)
     }
 
+    /// Unwrap and forward a key image check from a ledger router to the enclave
+    fn check_key_image_store_auth(
+        &mut self,
+        request: attest::Message,
+    ) -> Result<KeyImageStoreQueryResponse, RpcStatus> {
+        log::trace!(self.logger, "Getting encrypted request from router");
+
+        let query_response = self
+            .enclave
+            .check_key_image_store(request.into())
+            .map_err(|e| self.enclave_err_to_rpc_status("enclave request", e))?;
+
+        let mut resp = KeyImageStoreQueryResponse::new();
+        resp.set_query_response(query_response.into());
+        resp.set_block_range(self.sharding_strategy.block_range().into());
+
+        let shared_state = self.db_poll_shared_state.lock().expect("mutex poisoned");
+        resp.set_highest_processed_block_count(shared_state.highest_processed_block_count);
+        resp.set_last_known_block_cumulative_txo_count(
+            shared_state.last_known_block_cumulative_txo_count,
+        );
+        resp.set_latest_block_version(shared_state.latest_block_version);
+        Ok(resp)
+    }
+
     // Helper function that is common
     fn enclave_err_to_rpc_status(&self, context: &str, src: EnclaveError) -> RpcStatus {
         // Treat prost-decode error as an invalid arg,
@@ -177,3 +210,64 @@ impl<L: Ledger + Clone, E: LedgerEnclaveProxy> FogKeyImageApi for KeyImageServic
         });
     }
 }
+
+// Implement grpc trait for ledger routers
+impl<L: Ledger + Clone, E: LedgerEnclaveProxy> FogKeyImageStoreApi for KeyImageService<L, E> {
+    fn auth(&mut self, ctx: RpcContext, request: AuthMessage, sink: UnarySink<AuthMessage>) {
+        let _timer = SVC_COUNTERS.req(&ctx);
+        mc_common::logger::scoped_global_logger(&rpc_logger(&ctx, &self.logger), |logger| {
+            if let Err(err) = check_request_chain_id(&self.chain_id, &ctx) {
+                return send_result(ctx, sink, Err(err), logger);
+            }
+
+            if let Err(err) = self.authenticator.authenticate_rpc(&ctx) {
+                return send_result(ctx, sink, err.into(), logger);
+            }
+
+            match self.enclave.router_accept(request.into()) {
+                Ok((response, _session_id)) => {
+                    send_result(ctx, sink, Ok(response.into()), logger);
+                }
+                Err(peer_error) => {
+                    // This is debug because there's no requirement on the remote party to trigger
+                    // it.
+                    log::debug!(
+                        logger,
+                        "LedgerEnclave::router_accept failed: {}",
+                        peer_error
+                    );
+                    send_result(
+                        ctx,
+                        sink,
+                        Err(rpc_permissions_error(
+                            "router_auth",
+                            "Permission denied",
+                            logger,
+                        )),
+                        logger,
+                    );
+                }
+            }
+        });
+    }
+
+    fn check_key_images(
+        &mut self,
+        ctx: RpcContext,
+        request: Message,
+        sink: UnarySink<KeyImageStoreQueryResponse>,
+    ) {
+        let _timer = SVC_COUNTERS.req(&ctx);
+        mc_common::logger::scoped_global_logger(&rpc_logger(&ctx, &self.logger), |logger| {
+            if let Err(err) = check_request_chain_id(&self.chain_id, &ctx) {
+                return send_result(ctx, sink, Err(err), logger);
+            }
+
+            if let Err(err) = self.authenticator.authenticate_rpc(&ctx) {
+                return send_result(ctx, sink, err.into(), logger);
+            }
+
+            send_result(ctx, sink, self.check_key_image_store_auth(request), logger)
+        })
+    }
+}
@@ -0,0 +1,171 @@
+// Copyright (c) 2018-2022 The MobileCoin Foundation
+
+//! The connections of a ledger router to the key image stores it fans client
+//! key image checks out to, and the merging of the untrusted state the stores
+//! report.
+
+use grpcio::{CallOption, Channel, ClientUnaryReceiver, Error as GrpcError};
+use mc_attest_api::attest::{AuthMessage, Message};
+use mc_attest_enclave_api::{PeerAuthRequest, PeerAuthResponse, PeerSession};
+use mc_common::ResponderId;
+use mc_fog_api::{ledger::KeyImageStoreQueryResponse, ledger_grpc::FogKeyImageStoreApiClient};
+use mc_fog_ledger_enclave::{Error as EnclaveError, LedgerEnclaveProxy};
+use mc_fog_store_connection::{AttestedStoreConnection, StoreApi, StoreConnectionError};
+use mc_fog_types::common::BlockRange;
+use mc_fog_uri::FogLedgerUri;
+use std::marker::PhantomData;
+
+/// An error of the connection from a ledger router to a key image store
+pub type KeyImageStoreError = StoreConnectionError<EnclaveError>;
+
+/// A connection from a ledger router to a key image store.
+pub type KeyImageStoreConnection<E> = AttestedStoreConnection<KeyImageStoreApi<E>>;
+
+/// The calls a ledger router makes to its enclave and to key image stores.
+pub struct KeyImageStoreApi<E: LedgerEnclaveProxy>(PhantomData<E>);
+
+impl<E: LedgerEnclaveProxy> StoreApi for KeyImageStoreApi<E> {
+    type Uri = FogLedgerUri;
+    type Enclave = E;
+    type EnclaveError = EnclaveError;
+    type Client = FogKeyImageStoreApiClient;
+    type QueryResponse = KeyImageStoreQueryResponse;
+
+    const NAME: &'static str = "key image store";
+
+    fn client(channel: Channel) -> Self::Client {
+        FogKeyImageStoreApiClient::new(channel)
+    }
+
+    fn init(enclave: &E, store_id: ResponderId) -> Result<PeerAuthRequest, EnclaveError> {
+        enclave.key_image_store_init(store_id)
+    }
+
+    fn connect(
+        enclave: &E,
+        store_id: ResponderId,
+        auth_response: PeerAuthResponse,
+    ) -> Result<PeerSession, EnclaveError> {
+        enclave.key_image_store_connect(store_id, auth_response)
+    }
+
+    fn close(enclave: &E, session: PeerSession) -> Result<(), EnclaveError> {
+        enclave.peer_close(session)
+    }
+
+    fn auth(
+        client: &Self::Client,
+        request: &AuthMessage,
+        opt: CallOption,
+    ) -> Result<AuthMessage, GrpcError> {
+        client.auth_opt(request, opt)
+    }
+
+    fn query_async(
+        client: &Self::Client,
+        request: &Message,
+        opt: CallOption,
+    ) -> Result<ClientUnaryReceiver<KeyImageStoreQueryResponse>, GrpcError> {
+        client.check_key_images_async_opt(request, opt)
+    }
+}
+
+/// The untrusted state a key image store reports alongside its responses
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct KeyImageStoreStatus {
+    /// The blocks the store loads key images for
+    pub block_range: BlockRange,
+
+    /// The number of blocks of the ledger the store has scanned
+    pub highest_processed_block_count: u64,
+
+    /// The cumulative txo count of the last known block
+    pub last_known_block_cumulative_txo_count: u64,
+
+    /// The latest block version the store has seen
+    pub latest_block_version: u32,
+}
+
+impl From<&KeyImageStoreQueryResponse> for KeyImageStoreStatus {
+    fn from(src: &KeyImageStoreQueryResponse) -> Self {
+        Self {
+            block_range: src.get_block_range().into(),
+            highest_processed_block_count: src.get_highest_processed_block_count(),
+            last_known_block_cumulative_txo_count: src.get_last_known_block_cumulative_txo_count(),
+            latest_block_version: src.get_latest_block_version(),
+        }
+    }
+}
+
+/// The untrusted state of all the key image stores, as seen by a client
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct MergedKeyImageStoreStatus {
+    /// The number of blocks of the ledger every store has scanned
+    pub highest_processed_block_count: u64,
+
+    /// The cumulative txo count of the last known block
+    pub last_known_block_cumulative_txo_count: u64,
+
+    /// The latest block version
+    pub latest_block_version: u32,
+}
+
+/// Merge the states of the key image stores.
+///
+/// A key image can only be reported as unspent up to the blocks every store
+/// has scanned, so the state of the store which is furthest behind is used.
+pub fn merge_key_image_store_statuses(
+    statuses: &[KeyImageStoreStatus],
+) -> MergedKeyImageStoreStatus {
+    let furthest_behind = statuses
+        .iter()
+        .min_by_key(|status| status.highest_processed_block_count)
+        .cloned()
+        .unwrap_or_default();
+
+    MergedKeyImageStoreStatus {
+        highest_processed_block_count: furthest_behind.highest_processed_block_count,
+        last_known_block_cumulative_txo_count: furthest_behind
+            .last_known_block_cumulative_txo_count,
+        latest_block_version: furthest_behind.latest_block_version,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn status(start: u64, end: u64, processed: u64, version: u32) -> KeyImageStoreStatus {
+        KeyImageStoreStatus {
+            block_range: BlockRange::new(start, end),
+            highest_processed_block_count: processed,
+            last_known_block_cumulative_txo_count: processed * 10,
+            latest_block_version: version,
+        }
+    }
+
+    #[test]
+    fn merge_uses_store_furthest_behind() {
+        let merged = merge_key_image_store_statuses(&[
+            status(5, u64::MAX, 8, 3),
+            status(0, 5, 6, 2),
+            status(10, 20, 9, 3),
+        ]);
+        assert_eq!(
+            merged,
+            MergedKeyImageStoreStatus {
+                highest_processed_block_count: 6,
+                last_known_block_cumulative_txo_count: 60,
+                latest_block_version: 2,
+            }
+        );
+    }
+
+    #[test]
+    fn merge_no_stores() {
+        assert_eq!(
+            merge_key_image_store_statuses(&[]),
+            MergedKeyImageStoreStatus::default()
+        );
+    }
+}
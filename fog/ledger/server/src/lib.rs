@@ -4,14 +4,20 @@ mod block_service;
 mod config;
 mod counters;
 mod db_fetcher;
+mod key_image_router_service;
 mod key_image_service;
+mod key_image_store;
 mod merkle_proof_service;
+mod router_server;
 mod server;
 mod untrusted_tx_out_service;
 
 pub use block_service::BlockService;
-pub use config::LedgerServerConfig;
+pub use config::{LedgerRouterConfig, LedgerServerConfig};
+pub use key_image_router_service::KeyImageRouterService;
 pub use key_image_service::KeyImageService;
+pub use key_image_store::{KeyImageStoreConnection, KeyImageStoreError};
 pub use merkle_proof_service::MerkleProofService;
+pub use router_server::{LedgerRouterServer, ROUTER_OMAP_CAPACITY};
 pub use server::LedgerServer;
 pub use untrusted_tx_out_service::UntrustedTxOutService;
@@ -0,0 +1,169 @@
+// Copyright (c) 2018-2022 The MobileCoin Foundation
+
+//! Server object containing a ledger router node, which serves client key
+//! image checks by fanning them out to the key image stores.
+
+use crate::{
+    config::LedgerRouterConfig, counters, key_image_router_service::KeyImageRouterService,
+    key_image_store::KeyImageStoreConnection, server::LedgerServerError,
+};
+use futures::executor::block_on;
+use mc_attest_net::RaClient;
+use mc_common::{
+    logger::{log, Logger},
+    time::TimeProvider,
+};
+use mc_fog_api::ledger_grpc;
+use mc_fog_ledger_enclave::LedgerEnclaveProxy;
+use mc_sgx_report_cache_untrusted::ReportCacheThread;
+use mc_util_grpc::{
+    AnonymousAuthenticator, Authenticator, ConnectionUriGrpcioServer, TokenAuthenticator,
+};
+use mc_util_uri::ConnectionUri;
+use std::sync::Arc;
+
+/// The capacity of the ORAM hash table of a ledger router's enclave. The
+/// router does not load any key images, so it is kept small.
+pub const ROUTER_OMAP_CAPACITY: u64 = 512;
+
+pub struct LedgerRouterServer<E: LedgerEnclaveProxy, R: RaClient + Send + Sync + 'static> {
+    config: LedgerRouterConfig,
+    server: Option<grpcio::Server>,
+    key_image_router_service: KeyImageRouterService<E>,
+    enclave: E,
+    ra_client: R,
+    report_cache_thread: Option<ReportCacheThread>,
+    logger: Logger,
+}
+
+impl<E: LedgerEnclaveProxy, R: RaClient + Send + Sync + 'static> LedgerRouterServer<E, R> {
+    pub fn new(
+        config: LedgerRouterConfig,
+        enclave: E,
+        ra_client: R,
+        time_provider: impl TimeProvider + 'static,
+        logger: Logger,
+    ) -> Self {
+        let env = Arc::new(
+            grpcio::EnvBuilder::new()
+                .name_prefix("LedgerRouter-Store".to_string())
+                .build(),
+        );
+
+        let key_image_stores = config
+            .key_image_store_uris
+            .iter()
+            .map(|key_image_store_uri| {
+                KeyImageStoreConnection::new(
+                    config.chain_id.clone(),
+                    key_image_store_uri.clone(),
+                    enclave.clone(),
+                    env.clone(),
+                    logger.clone(),
+                )
+                .unwrap_or_else(|err| {
+                    panic!(
+                        "Invalid key image store uri {}: {}",
+                        key_image_store_uri, err
+                    )
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let client_authenticator: Arc<dyn Authenticator + Sync + Send> =
+            if let Some(signing_keys) = config.client_auth_token_secret.as_ref() {
+                Arc::new(TokenAuthenticator::new_with_keys(
+                    signing_keys.clone(),
+                    config.client_auth_token_max_lifetime,
+                    time_provider,
+                ))
+            } else {
+                Arc::new(AnonymousAuthenticator::default())
+            };
+
+        let key_image_router_service = KeyImageRouterService::new(
+            config.chain_id.clone(),
+            enclave.clone(),
+            Arc::new(key_image_stores),
+            client_authenticator,
+            logger.clone(),
+        );
+
+        Self {
+            config,
+            server: None,
+            key_image_router_service,
+            enclave,
+            ra_client,
+            report_cache_thread: None,
+            logger,
+        }
+    }
+
+    pub fn start(&mut self) -> Result<(), LedgerServerError> {
+        let ret = {
+            self.report_cache_thread = Some(ReportCacheThread::start(
+                self.enclave.clone(),
+                self.ra_client.clone(),
+                self.config.ias_spid,
+                &counters::ENCLAVE_REPORT_TIMESTAMP,
+                self.logger.clone(),
+            )?);
+
+            let env = Arc::new(
+                grpcio::EnvBuilder::new()
+                    .name_prefix("LedgerRouter-RPC".to_string())
+                    .build(),
+            );
+
+            // Package endpoints into grpc service
+            let key_image_router_service =
+                ledger_grpc::create_fog_key_image_api(self.key_image_router_service.clone());
+
+            // Health check service
+            let health_service =
+                mc_util_grpc::HealthService::new(None, self.logger.clone()).into_service();
+
+            // Package service into grpc server
+            log::info!(
+                self.logger,
+                "Starting Ledger router on {}",
+                self.config.client_listen_uri.addr(),
+            );
+            let server_builder = grpcio::ServerBuilder::new(env)
+                .register_service(key_image_router_service)
+                .register_service(health_service)
+                .bind_using_uri(&self.config.client_listen_uri, self.logger.clone());
+
+            let mut server = server_builder.build()?;
+            server.start();
+
+            self.server = Some(server);
+
+            // Success.
+            Ok(())
+        };
+        if ret.is_err() {
+            self.stop();
+        }
+        ret
+    }
+
+    pub fn stop(&mut self) {
+        if let Some(ref mut server) = self.server {
+            block_on(server.shutdown()).expect("Could not stop grpc server");
+        }
+
+        if let Some(ref mut report_cache_thread) = self.report_cache_thread.take() {
+            report_cache_thread
+                .stop()
+                .expect("Could not stop report cache thread");
+        }
+    }
+}
+
+impl<E: LedgerEnclaveProxy, R: RaClient + Send + Sync + 'static> Drop for LedgerRouterServer<E, R> {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
@@ -90,9 +90,9 @@ impl<E: LedgerEnclaveProxy, R: RaClient + Send + Sync + 'static> LedgerServer<E,
         logger: Logger,
     ) -> Self {
         let client_authenticator: Arc<dyn Authenticator + Sync + Send> =
-            if let Some(shared_secret) = config.client_auth_token_secret.as_ref() {
-                Arc::new(TokenAuthenticator::new(
-                    *shared_secret,
+            if let Some(signing_keys) = config.client_auth_token_secret.as_ref() {
+                Arc::new(TokenAuthenticator::new_with_keys(
+                    signing_keys.clone(),
                     config.client_auth_token_max_lifetime,
                     time_provider,
                 ))
@@ -108,6 +108,7 @@ impl<E: LedgerEnclaveProxy, R: RaClient + Send + Sync + 'static> LedgerServer<E,
             watcher.clone(),
             enclave.clone(),
             shared_state,
+            config.sharding_strategy.clone(),
             client_authenticator.clone(),
             logger.clone(),
         );
@@ -164,6 +165,7 @@ impl<E: LedgerEnclaveProxy, R: RaClient + Send + Sync + 'static> LedgerServer<E,
                 self.key_image_service.get_ledger(),
                 self.enclave.clone(),
                 self.key_image_service.get_watcher(),
+                self.config.sharding_strategy.clone(),
                 self.key_image_service.get_db_poll_shared_state(),
                 readiness_indicator.clone(),
                 self.logger.clone(),
@@ -178,6 +180,8 @@ impl<E: LedgerEnclaveProxy, R: RaClient + Send + Sync + 'static> LedgerServer<E,
             // Package endpoints into grpc service
             let key_image_service =
                 ledger_grpc::create_fog_key_image_api(self.key_image_service.clone());
+            let key_image_store_service =
+                ledger_grpc::create_fog_key_image_store_api(self.key_image_service.clone());
             let merkle_proof_service =
                 ledger_grpc::create_fog_merkle_proof_api(self.merkle_proof_service.clone());
             let block_service = ledger_grpc::create_fog_block_api(self.block_service.clone());
@@ -199,6 +203,7 @@ impl<E: LedgerEnclaveProxy, R: RaClient + Send + Sync + 'static> LedgerServer<E,
             );
             let server_builder = grpcio::ServerBuilder::new(env)
                 .register_service(key_image_service)
+                .register_service(key_image_store_service)
                 .register_service(merkle_proof_service)
                 .register_service(block_service)
                 .register_service(untrusted_tx_out_service)
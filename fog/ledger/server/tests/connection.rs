@@ -126,6 +126,7 @@ fn fog_ledger_merkle_proofs_test(logger: Logger) {
                 client_auth_token_secret: None,
                 client_auth_token_max_lifetime: Default::default(),
                 omap_capacity: OMAP_CAPACITY,
+                sharding_strategy: Default::default(),
             };
 
             let enclave = LedgerSgxEnclave::new(
@@ -357,6 +358,7 @@ fn fog_ledger_key_images_test(logger: Logger) {
                 client_auth_token_secret: None,
                 client_auth_token_max_lifetime: Default::default(),
                 omap_capacity: OMAP_CAPACITY,
+                sharding_strategy: Default::default(),
             };
 
             let enclave = LedgerSgxEnclave::new(
@@ -559,6 +561,7 @@ fn fog_ledger_blocks_api_test(logger: Logger) {
             client_auth_token_secret: None,
             client_auth_token_max_lifetime: Default::default(),
             omap_capacity: OMAP_CAPACITY,
+            sharding_strategy: Default::default(),
         };
 
         let enclave = LedgerSgxEnclave::new(
@@ -721,6 +724,7 @@ fn fog_ledger_untrusted_tx_out_api_test(logger: Logger) {
             client_auth_token_secret: None,
             client_auth_token_max_lifetime: Default::default(),
             omap_capacity: OMAP_CAPACITY,
+            sharding_strategy: Default::default(),
         };
 
         let enclave = LedgerSgxEnclave::new(
@@ -0,0 +1,327 @@
+// Copyright (c) 2018-2022 The MobileCoin Foundation
+
+//! Integration tests of a fog ledger router in front of two key image stores,
+//! which shard the key images by block range. A client checking key images
+//! against the router should see the same results as if a single ledger
+//! server held all the key images.
+
+use mc_account_keys::{AccountKey, PublicAddress};
+use mc_attest_net::{Client as AttestClient, RaClient};
+use mc_attest_verifier::{MrSignerVerifier, Verifier, DEBUG_ENCLAVE};
+use mc_blockchain_types::BlockVersion;
+use mc_common::{
+    logger::{log, test_with_logger, Logger},
+    time::SystemTimeProvider,
+    ResponderId,
+};
+use mc_fog_ledger_connection::{FogKeyImageGrpcClient, KeyImageResultExtension};
+use mc_fog_ledger_enclave::LedgerSgxEnclave;
+use mc_fog_ledger_server::{
+    LedgerRouterConfig, LedgerRouterServer, LedgerServer, LedgerServerConfig, ROUTER_OMAP_CAPACITY,
+};
+use mc_fog_test_infra::get_enclave_path;
+use mc_fog_uri::{ConnectionUri, FogLedgerUri};
+use mc_ledger_db::{test_utils::recreate_ledger_db, LedgerDB};
+use mc_transaction_core::{ring_signature::KeyImage, tokens::Mob, Amount, Token};
+use mc_util_grpc::GrpcRetryConfig;
+use mc_util_test_helper::{CryptoRng, RngCore, RngType, SeedableRng};
+use mc_watcher::watcher_db::WatcherDB;
+use std::{path::Path, str::FromStr, sync::Arc, thread::sleep, time::Duration};
+use tempdir::TempDir;
+use url::Url;
+
+const TEST_URL: &str = "http://www.my_url1.com";
+
+const OMAP_CAPACITY: u64 = 128 * 128;
+
+const GRPC_RETRY_CONFIG: GrpcRetryConfig = GrpcRetryConfig {
+    grpc_retry_count: 3,
+    grpc_retry_millis: 20,
+};
+
+fn ledger_uri(port: u16) -> FogLedgerUri {
+    FogLedgerUri::from_str(&format!("insecure-fog-ledger://127.0.0.1:{}", port)).unwrap()
+}
+
+fn start_key_image_store(
+    port: u16,
+    ledger: LedgerDB,
+    ledger_path: &Path,
+    watcher: WatcherDB,
+    watcher_path: &Path,
+    sharding_strategy: &str,
+    logger: Logger,
+) -> (FogLedgerUri, LedgerServer<LedgerSgxEnclave, AttestClient>) {
+    let uri = ledger_uri(port);
+    let config = LedgerServerConfig {
+        chain_id: "local".to_string(),
+        ledger_db: ledger_path.to_path_buf(),
+        watcher_db: watcher_path.to_path_buf(),
+        admin_listen_uri: Default::default(),
+        client_listen_uri: uri.clone(),
+        client_responder_id: ResponderId::from_str(&uri.addr()).unwrap(),
+        ias_spid: Default::default(),
+        ias_api_key: Default::default(),
+        client_auth_token_secret: None,
+        client_auth_token_max_lifetime: Default::default(),
+        omap_capacity: OMAP_CAPACITY,
+        sharding_strategy: FromStr::from_str(sharding_strategy).unwrap(),
+    };
+
+    let enclave = LedgerSgxEnclave::new(
+        get_enclave_path(mc_fog_ledger_enclave::ENCLAVE_FILE),
+        &config.client_responder_id,
+        OMAP_CAPACITY,
+        logger.clone(),
+    );
+
+    let ra_client = AttestClient::new(&config.ias_api_key).expect("Could not create IAS client");
+
+    let mut server = LedgerServer::new(
+        config,
+        enclave,
+        ledger,
+        watcher,
+        ra_client,
+        SystemTimeProvider::default(),
+        logger,
+    );
+    server.start().expect("Failed starting key image store");
+    (uri, server)
+}
+
+fn start_router(
+    port: u16,
+    key_image_store_uris: Vec<FogLedgerUri>,
+    logger: Logger,
+) -> (
+    FogLedgerUri,
+    LedgerRouterServer<LedgerSgxEnclave, AttestClient>,
+) {
+    let uri = ledger_uri(port);
+    let config = LedgerRouterConfig {
+        chain_id: "local".to_string(),
+        client_listen_uri: uri.clone(),
+        client_responder_id: ResponderId::from_str(&uri.addr()).unwrap(),
+        ias_api_key: Default::default(),
+        ias_spid: Default::default(),
+        admin_listen_uri: Default::default(),
+        client_auth_token_secret: None,
+        client_auth_token_max_lifetime: Default::default(),
+        key_image_store_uris,
+    };
+
+    let enclave = LedgerSgxEnclave::new(
+        get_enclave_path(mc_fog_ledger_enclave::ENCLAVE_FILE),
+        &config.client_responder_id,
+        ROUTER_OMAP_CAPACITY,
+        logger.clone(),
+    );
+
+    let ra_client = AttestClient::new(&config.ias_api_key).expect("Could not create IAS client");
+
+    let mut server = LedgerRouterServer::new(
+        config,
+        enclave,
+        ra_client,
+        SystemTimeProvider::default(),
+        logger,
+    );
+    server.start().expect("Failed starting ledger router");
+    (uri, server)
+}
+
+fn key_image_client(uri: FogLedgerUri, logger: Logger) -> FogKeyImageGrpcClient {
+    let grpc_env = Arc::new(grpcio::EnvBuilder::new().build());
+    let mut mr_signer_verifier =
+        MrSignerVerifier::from(mc_fog_ledger_enclave_measurement::sigstruct());
+    mr_signer_verifier
+        .allow_hardening_advisories(mc_fog_ledger_enclave_measurement::HARDENING_ADVISORIES);
+
+    let mut verifier = Verifier::default();
+    verifier.mr_signer(mr_signer_verifier).debug(DEBUG_ENCLAVE);
+
+    FogKeyImageGrpcClient::new(
+        "local".to_string(),
+        uri,
+        GRPC_RETRY_CONFIG,
+        verifier,
+        grpc_env,
+        logger,
+    )
+}
+
+// Test that key images spent in the blocks of either store are found through
+// the router, and that concurrent checks of several clients succeed
+#[test_with_logger]
+fn ledger_router_two_stores(logger: Logger) {
+    let base_port = 3270;
+
+    let mut rng = RngType::from_seed([0u8; 32]);
+    let block_version = BlockVersion::MAX;
+
+    let alice = AccountKey::random_with_fog(&mut rng);
+    let recipients = vec![alice.default_subaddress()];
+    let keys: Vec<KeyImage> = (0..20).map(|x| KeyImage::from(x as u64)).collect();
+
+    // Make LedgerDB
+    let ledger_dir = TempDir::new("fog-ledger").expect("Could not get test_ledger tempdir");
+    let mut ledger = recreate_ledger_db(ledger_dir.path());
+
+    // Make WatcherDB
+    let watcher_dir = TempDir::new("wallet_db").expect("Could not make tempdir for wallet db");
+    WatcherDB::create(watcher_dir.path()).unwrap();
+    let mut watcher = WatcherDB::open_rw(
+        watcher_dir.path(),
+        &[Url::parse(TEST_URL).unwrap()],
+        logger.clone(),
+    )
+    .unwrap();
+
+    // Origin block cannot have key images
+    add_block_to_ledger(
+        block_version,
+        &mut ledger,
+        &recipients,
+        &[],
+        &mut rng,
+        &mut watcher,
+    );
+    for block_keys in [&keys[0..2], &keys[3..6], &keys[6..9]] {
+        add_block_to_ledger(
+            block_version,
+            &mut ledger,
+            &recipients,
+            block_keys,
+            &mut rng,
+            &mut watcher,
+        );
+    }
+    let num_blocks = add_block_to_ledger(
+        block_version,
+        &mut ledger,
+        &recipients,
+        &keys[9..11],
+        &mut rng,
+        &mut watcher,
+    );
+
+    // Blocks 0 to 2 are loaded by the first store, the others by the second.
+    let (store_uri_1, _store_1) = start_key_image_store(
+        base_port + 1,
+        ledger.clone(),
+        ledger_dir.path(),
+        watcher.clone(),
+        watcher_dir.path(),
+        "0-3",
+        logger.clone(),
+    );
+    let (store_uri_2, _store_2) = start_key_image_store(
+        base_port + 2,
+        ledger.clone(),
+        ledger_dir.path(),
+        watcher,
+        watcher_dir.path(),
+        "3-",
+        logger.clone(),
+    );
+    let (router_uri, _router) = start_router(
+        base_port + 3,
+        vec![store_uri_1, store_uri_2],
+        logger.clone(),
+    );
+    let mut client = key_image_client(router_uri.clone(), logger.clone());
+
+    let checked_keys = [keys[0], keys[4], keys[7], keys[10], keys[19]];
+
+    // Wait until both stores have loaded their blocks
+    let mut allowed_tries = 60usize;
+    let response = loop {
+        let response = client
+            .check_key_images(&checked_keys)
+            .expect("check_key_images failed");
+        if response.num_blocks == num_blocks {
+            break response;
+        }
+        if allowed_tries == 0 {
+            panic!("Key image stores did not catch up to the ledger!");
+        }
+        allowed_tries -= 1;
+        log::info!(logger, "Waiting for key image stores to catch up...");
+        sleep(Duration::from_millis(1000));
+    };
+
+    assert_eq!(response.results.len(), checked_keys.len());
+    for (result, key) in response.results.iter().zip(checked_keys) {
+        assert_eq!(result.key_image, key);
+    }
+    // Found by the first store
+    assert_eq!(response.results[0].status(), Ok(Some(1)));
+    assert_eq!(response.results[1].status(), Ok(Some(2)));
+    // Found by the second store
+    assert_eq!(response.results[2].status(), Ok(Some(3)));
+    assert_eq!(response.results[3].status(), Ok(Some(4)));
+    // Not spent
+    assert_eq!(response.results[4].status(), Ok(None));
+
+    // Checks of several clients run concurrently, each with attested channels
+    // to the stores of its own.
+    let handles = [(keys[1], 1), (keys[5], 2), (keys[8], 3), (keys[9], 4)]
+        .into_iter()
+        .map(|(key, block_index)| {
+            let mut client = key_image_client(router_uri.clone(), logger.clone());
+            std::thread::spawn(move || {
+                for _ in 0..5 {
+                    let response = client
+                        .check_key_images(&[key])
+                        .expect("check_key_images failed");
+                    assert_eq!(response.results.len(), 1);
+                    assert_eq!(response.results[0].status(), Ok(Some(block_index)));
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+
+    // Sleep before exiting to give grpcio threads time to join
+    sleep(Duration::from_millis(1000));
+}
+
+/// Adds a block containing one txo for each provided recipient and returns new
+/// block height.
+fn add_block_to_ledger(
+    block_version: BlockVersion,
+    ledger_db: &mut LedgerDB,
+    recipients: &[PublicAddress],
+    key_images: &[KeyImage],
+    rng: &mut (impl CryptoRng + RngCore),
+    watcher: &mut WatcherDB,
+) -> u64 {
+    let amount = Amount::new(10, Mob::ID);
+    let block_data = mc_ledger_db::test_utils::add_block_to_ledger(
+        ledger_db,
+        block_version,
+        recipients,
+        amount,
+        key_images,
+        rng,
+    )
+    .expect("failed to add block");
+    let block_index = block_data.block().index;
+
+    let signature = block_data.signature().expect("missing signature");
+    for src_url in watcher.get_config_urls().unwrap().iter() {
+        watcher
+            .add_block_signature(
+                src_url,
+                block_index,
+                signature.clone(),
+                format!("00/{}", block_index),
+            )
+            .expect("Could not add block signature");
+    }
+
+    block_index + 1
+}
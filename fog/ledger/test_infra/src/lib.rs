@@ -3,7 +3,10 @@
 //! Functionality for mocking and testing components in the ledger server
 
 use mc_attest_core::{IasNonce, Quote, QuoteNonce, Report, TargetInfo, VerificationReport};
-use mc_attest_enclave_api::{ClientAuthRequest, ClientAuthResponse, ClientSession, EnclaveMessage};
+use mc_attest_enclave_api::{
+    ClientAuthRequest, ClientAuthResponse, ClientSession, EnclaveMessage, PeerAuthRequest,
+    PeerAuthResponse, PeerSession,
+};
 use mc_blockchain_types::{
     Block, BlockContents, BlockData, BlockIndex, BlockMetadata, BlockSignature,
 };
@@ -84,6 +87,56 @@ impl LedgerEnclave for MockEnclave {
     ) -> Result<(), mc_fog_ledger_enclave::Error> {
         unimplemented!()
     }
+
+    fn key_image_store_init(
+        &self,
+        _key_image_store_id: ResponderId,
+    ) -> EnclaveResult<PeerAuthRequest> {
+        unimplemented!()
+    }
+
+    fn key_image_store_connect(
+        &self,
+        _key_image_store_id: ResponderId,
+        _key_image_store_auth_response: PeerAuthResponse,
+    ) -> EnclaveResult<PeerSession> {
+        unimplemented!()
+    }
+
+    fn router_accept(
+        &self,
+        _req: PeerAuthRequest,
+    ) -> EnclaveResult<(PeerAuthResponse, PeerSession)> {
+        unimplemented!()
+    }
+
+    fn peer_close(&self, _session: PeerSession) -> EnclaveResult<()> {
+        unimplemented!()
+    }
+
+    fn create_key_image_store_queries(
+        &self,
+        _client_query: EnclaveMessage<ClientSession>,
+        _key_image_stores: Vec<PeerSession>,
+    ) -> EnclaveResult<Vec<EnclaveMessage<PeerSession>>> {
+        unimplemented!()
+    }
+
+    fn check_key_image_store(
+        &self,
+        _query: EnclaveMessage<PeerSession>,
+    ) -> EnclaveResult<EnclaveMessage<PeerSession>> {
+        unimplemented!()
+    }
+
+    fn collate_key_image_store_responses(
+        &self,
+        _client_session: ClientSession,
+        _store_responses: Vec<EnclaveMessage<PeerSession>>,
+        _untrusted_keyimagequery_response: UntrustedKeyImageQueryResponse,
+    ) -> EnclaveResult<Vec<u8>> {
+        unimplemented!()
+    }
 }
 
 #[derive(Clone, Default)]
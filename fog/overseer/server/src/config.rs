@@ -3,6 +3,7 @@
 //! Configuration parameters for Fog Overseer.
 #![deny(missing_docs)]
 
+use crate::failover::FailoverPolicy;
 use clap::Parser;
 use mc_fog_sql_recovery_db::SqlRecoveryDbConnectionConfig;
 use mc_fog_uri::FogIngestUri;
@@ -32,6 +33,10 @@ pub struct OverseerConfig {
     /// Postgres config
     #[clap(flatten)]
     pub postgres_config: SqlRecoveryDbConnectionConfig,
+
+    /// Failover policy
+    #[clap(flatten)]
+    pub failover_policy: FailoverPolicy,
 }
 
 #[cfg(test)]
@@ -55,5 +60,34 @@ mod tests {
 
         assert_eq!(config.ingest_cluster_uris[0].port(), 3226);
         assert_eq!(config.ingest_cluster_uris[1].port(), 3227);
+
+        assert!(config.failover_policy.node_priorities.is_empty());
+        assert_eq!(config.failover_policy.max_failovers_per_hour, None);
+        assert!(!config.failover_policy.dry_run);
+    }
+
+    #[test]
+    fn failover_policy_config_example() {
+        let config = OverseerConfig::try_parse_from(&[
+            "/usr/bin/fog_overseer_server",
+            "--ingest-cluster-uris",
+            "insecure-fog-ingest://0.0.0.0:3226/,insecure-fog-ingest://0.0.0.0:3227/",
+            "--failover-node-priority",
+            "insecure-fog-ingest://0.0.0.0:3227/=0,insecure-fog-ingest://0.0.0.0:3226/=1",
+            "--failover-min-healthy-time",
+            "30",
+            "--failover-max-per-hour",
+            "3",
+            "--failover-dry-run",
+        ])
+        .expect("Could not parse command line arguments.");
+
+        let policy = config.failover_policy;
+        assert_eq!(policy.node_priorities.len(), 2);
+        assert_eq!(policy.node_priorities[0].uri.port(), 3227);
+        assert_eq!(policy.node_priorities[0].priority, 0);
+        assert_eq!(policy.min_healthy_time.as_secs(), 30);
+        assert_eq!(policy.max_failovers_per_hour, Some(3));
+        assert!(policy.dry_run);
     }
 }
@@ -0,0 +1,903 @@
+// Copyright (c) 2018-2022 The MobileCoin Foundation
+
+//! The failover policy used by Fog Overseer when there is no active node in
+//! the Fog Ingest cluster, and the record of the decisions it made.
+//!
+//! The policy determines which idle node is activated (node priorities),
+//! whether a node which recently came back is trusted yet (minimum healthy
+//! time), and how often failover may happen at all (maximum failovers per
+//! hour). In dry-run mode, decisions are recorded but not acted upon.
+
+use clap::Parser;
+use mc_common::logger::{log, Logger};
+use mc_crypto_keys::CompressedRistrettoPublic;
+use mc_fog_ingest_client::FogIngestGrpcClient;
+use mc_fog_uri::FogIngestUri;
+use mc_util_parse::parse_duration_in_seconds;
+use retry::{delay::Fixed, retry_with_index, Error as RetryError, OperationResult};
+use serde::Serialize;
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt,
+    str::FromStr,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+/// The priority of a Fog Ingest node during failover, given as `uri=priority`.
+/// Nodes with lower values are preferred.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct NodePriority {
+    /// The node's gRPC URI, as given in --ingest-cluster-uris
+    pub uri: FogIngestUri,
+    /// The node's priority
+    pub priority: u32,
+}
+
+impl FromStr for NodePriority {
+    type Err = String;
+
+    fn from_str(src: &str) -> Result<Self, String> {
+        let (uri, priority) = src
+            .rsplit_once('=')
+            .ok_or_else(|| format!("Node priority '{}' is not of the form uri=priority", src))?;
+        let uri = FogIngestUri::from_str(uri.trim())
+            .map_err(|err| format!("Invalid uri in node priority '{}': {}", src, err))?;
+        let priority = priority
+            .trim()
+            .parse::<u32>()
+            .map_err(|err| format!("Invalid priority in node priority '{}': {}", src, err))?;
+        Ok(Self { uri, priority })
+    }
+}
+
+/// Failover policy configuration
+#[derive(Clone, Debug, Parser, Serialize)]
+pub struct FailoverPolicy {
+    /// Priorities of Fog Ingest nodes during failover, as `uri=priority`.
+    /// Nodes with lower values are activated first. Nodes which are not
+    /// listed come after all listed nodes, in the order of
+    /// --ingest-cluster-uris.
+    #[clap(
+        long = "failover-node-priority",
+        use_value_delimiter = true,
+        env = "MC_FAILOVER_NODE_PRIORITIES"
+    )]
+    pub node_priorities: Vec<NodePriority>,
+
+    /// How long (in seconds) a node must have been continuously responsive
+    /// before it may be activated. This prevents reactivating a node which is
+    /// flapping.
+    #[clap(long = "failover-min-healthy-time", default_value = "0", parse(try_from_str = parse_duration_in_seconds), env = "MC_FAILOVER_MIN_HEALTHY_TIME")]
+    pub min_healthy_time: Duration,
+
+    /// The maximum number of failovers in any one hour window. Further
+    /// failovers are suppressed until the window moves on. Unlimited if
+    /// omitted.
+    #[clap(long = "failover-max-per-hour", env = "MC_FAILOVER_MAX_PER_HOUR")]
+    pub max_failovers_per_hour: Option<u32>,
+
+    /// Record failover decisions without activating nodes, setting new keys,
+    /// or reporting keys as lost.
+    #[clap(long = "failover-dry-run", env = "MC_FAILOVER_DRY_RUN")]
+    pub dry_run: bool,
+}
+
+impl Default for FailoverPolicy {
+    fn default() -> Self {
+        Self {
+            node_priorities: Vec::new(),
+            min_healthy_time: Duration::from_secs(0),
+            max_failovers_per_hour: None,
+            dry_run: false,
+        }
+    }
+}
+
+/// The action chosen by a failover decision.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize)]
+pub enum FailoverAction {
+    /// Activate an idle node which has the outstanding ingress key.
+    ActivateNode,
+    /// Set new keys on an idle node and activate it, because there is no
+    /// outstanding ingress key.
+    SetNewKeysAndActivateNode,
+    /// Report the outstanding ingress key as lost, then set new keys on an
+    /// idle node and activate it, because no node has the key.
+    ReportLostKeyAndActivateNode,
+    /// Disable Fog Overseer, because the cluster state requires manual
+    /// intervention.
+    DisableOverseer,
+    /// Take no action for now.
+    Wait,
+}
+
+/// A decision made during failover, as shown by the HTTP API.
+#[derive(Clone, Debug, Serialize)]
+pub struct FailoverDecision {
+    /// When the decision was first made, in seconds since the epoch
+    pub timestamp: u64,
+    /// When the decision was last made, in seconds since the epoch. Identical
+    /// consecutive decisions are recorded once.
+    pub last_timestamp: u64,
+    /// How many consecutive times the decision was made
+    pub count: u64,
+    /// The chosen action
+    pub action: FailoverAction,
+    /// The node the action applies to, if any
+    pub node: Option<FogIngestUri>,
+    /// Why this action was chosen
+    pub reason: String,
+    /// Whether the policy was in dry-run mode, so that no action was taken
+    pub dry_run: bool,
+    /// The error, if the action was attempted and failed
+    pub error: Option<String>,
+}
+
+impl FailoverDecision {
+    fn new(action: FailoverAction, node: Option<FogIngestUri>, reason: String) -> Self {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or(0);
+        Self {
+            timestamp,
+            last_timestamp: timestamp,
+            count: 1,
+            action,
+            node,
+            reason,
+            dry_run: false,
+            error: None,
+        }
+    }
+
+    fn is_repeat_of(&self, other: &Self) -> bool {
+        self.action == other.action
+            && self.node == other.node
+            && self.reason == other.reason
+            && self.dry_run == other.dry_run
+            && self.error == other.error
+    }
+}
+
+impl fmt::Display for FailoverDecision {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self.action)?;
+        if let Some(node) = self.node.as_ref() {
+            write!(f, " {}", node)?;
+        }
+        write!(f, ": {}", self.reason)?;
+        if self.dry_run {
+            write!(f, " (dry run)")?;
+        }
+        if let Some(error) = self.error.as_ref() {
+            write!(f, " (failed: {})", error)?;
+        }
+        Ok(())
+    }
+}
+
+/// The most recent failover decisions, shared between the worker, which
+/// records them, and the HTTP API, which shows them.
+#[derive(Clone, Default)]
+pub struct FailoverHistory {
+    decisions: Arc<Mutex<VecDeque<FailoverDecision>>>,
+}
+
+impl FailoverHistory {
+    /// The number of decisions which are kept.
+    const MAX_DECISIONS: usize = 100;
+
+    /// Record a decision, merging it with the previous one if identical.
+    pub fn record(&self, decision: FailoverDecision) {
+        let mut decisions = self.decisions.lock().expect("mutex poisoned");
+        if let Some(last) = decisions.back_mut() {
+            if last.is_repeat_of(&decision) {
+                last.last_timestamp = decision.timestamp;
+                last.count += 1;
+                return;
+            }
+        }
+        if decisions.len() == Self::MAX_DECISIONS {
+            decisions.pop_front();
+        }
+        decisions.push_back(decision);
+    }
+
+    /// The recorded decisions, most recent first.
+    pub fn get(&self) -> Vec<FailoverDecision> {
+        let decisions = self.decisions.lock().expect("mutex poisoned");
+        decisions.iter().rev().cloned().collect()
+    }
+}
+
+/// The operations on a Fog Ingest node which failover needs.
+pub trait IngestNode {
+    /// The node's gRPC URI.
+    fn uri(&self) -> &FogIngestUri;
+
+    /// Activate the node.
+    fn activate(&self) -> Result<(), String>;
+
+    /// Set new keys on the node.
+    fn new_keys(&self) -> Result<(), String>;
+}
+
+impl IngestNode for FogIngestGrpcClient {
+    fn uri(&self) -> &FogIngestUri {
+        self.get_uri()
+    }
+
+    fn activate(&self) -> Result<(), String> {
+        FogIngestGrpcClient::activate(self)
+            .map(|_| ())
+            .map_err(|err| err.to_string())
+    }
+
+    fn new_keys(&self) -> Result<(), String> {
+        FogIngestGrpcClient::new_keys(self)
+            .map(|_| ())
+            .map_err(|err| err.to_string())
+    }
+}
+
+/// Applies a [FailoverPolicy] to the Fog Ingest cluster, tracking the health of
+/// each node and the recent failovers.
+pub struct Failover {
+    policy: FailoverPolicy,
+
+    /// Since when each responsive node has been continuously responsive.
+    healthy_since: HashMap<FogIngestUri, Instant>,
+
+    /// When recent failovers happened, oldest first.
+    recent_failovers: VecDeque<Instant>,
+
+    history: FailoverHistory,
+
+    logger: Logger,
+}
+
+impl Failover {
+    /// The window of the maximum failovers limit.
+    const RATE_LIMIT_WINDOW: Duration = Duration::from_secs(60 * 60);
+
+    /// How many times a failed operation on a node is retried, and how long
+    /// to wait in between.
+    const NUMBER_OF_RETRIES: usize = 3;
+    const RETRY_DELAY_MS: u64 = 200;
+
+    /// Create a new Failover.
+    pub fn new(policy: FailoverPolicy, history: FailoverHistory, logger: Logger) -> Self {
+        Self {
+            policy,
+            healthy_since: HashMap::new(),
+            recent_failovers: VecDeque::new(),
+            history,
+            logger,
+        }
+    }
+
+    /// Record whether a node responded to a status request.
+    pub fn observe_node(&mut self, uri: &FogIngestUri, responsive: bool, now: Instant) {
+        if responsive {
+            self.healthy_since.entry(uri.clone()).or_insert(now);
+        } else {
+            self.healthy_since.remove(uri);
+        }
+    }
+
+    /// Perform failover for a cluster with no active node.
+    ///
+    /// `ingress_keys` has the ingress key of each node in `nodes`, if known.
+    /// `report_lost_key` is called if the outstanding key must be reported as
+    /// lost.
+    ///
+    /// Returns the decision, which has also been recorded in the history.
+    pub fn run<N: IngestNode>(
+        &mut self,
+        nodes: &[N],
+        ingress_keys: &[Option<CompressedRistrettoPublic>],
+        outstanding_keys: &[CompressedRistrettoPublic],
+        report_lost_key: impl FnOnce(CompressedRistrettoPublic) -> Result<(), String>,
+        now: Instant,
+    ) -> FailoverDecision {
+        let (mut decision, candidates) = self.decide(nodes, ingress_keys, outstanding_keys, now);
+        decision.dry_run = self.policy.dry_run;
+
+        let acts_on_node = !matches!(
+            decision.action,
+            FailoverAction::Wait | FailoverAction::DisableOverseer
+        );
+        if acts_on_node {
+            self.recent_failovers.push_back(now);
+            if !self.policy.dry_run {
+                if let Err(err) = self.execute(
+                    &mut decision,
+                    nodes,
+                    &candidates,
+                    outstanding_keys,
+                    report_lost_key,
+                ) {
+                    decision.error = Some(err);
+                }
+            }
+        }
+
+        match (decision.action, decision.error.is_some()) {
+            (FailoverAction::Wait, _) => log::info!(self.logger, "Failover: {}", decision),
+            (_, false) => log::warn!(self.logger, "Failover: {}", decision),
+            (_, true) => log::error!(self.logger, "Failover: {}", decision),
+        }
+        self.history.record(decision.clone());
+        decision
+    }
+
+    /// Decide what to do. If the decision acts on a node, the indices of the
+    /// nodes it may be carried out on are returned too, in order of
+    /// preference, starting with the chosen node.
+    fn decide<N: IngestNode>(
+        &mut self,
+        nodes: &[N],
+        ingress_keys: &[Option<CompressedRistrettoPublic>],
+        outstanding_keys: &[CompressedRistrettoPublic],
+        now: Instant,
+    ) -> (FailoverDecision, Vec<usize>) {
+        if outstanding_keys.len() > 1 {
+            let decision = FailoverDecision::new(
+                FailoverAction::DisableOverseer,
+                None,
+                format!("Multiple inactive outstanding keys require manual intervention; re-enable overseer by calling the /enable endpoint once resolved. Inactive outstanding keys: {:?}", outstanding_keys),
+            );
+            return (decision, vec![]);
+        }
+
+        if let Some(max_failovers) = self.policy.max_failovers_per_hour {
+            while let Some(oldest) = self.recent_failovers.front() {
+                if now.saturating_duration_since(*oldest) < Self::RATE_LIMIT_WINDOW {
+                    break;
+                }
+                self.recent_failovers.pop_front();
+            }
+            if self.recent_failovers.len() >= max_failovers as usize {
+                let decision = FailoverDecision::new(
+                    FailoverAction::Wait,
+                    None,
+                    format!(
+                        "Reached the maximum of {} failovers per hour",
+                        max_failovers
+                    ),
+                );
+                return (decision, vec![]);
+            }
+        }
+
+        // Candidate nodes, in order of preference.
+        let mut candidates: Vec<usize> = (0..nodes.len()).collect();
+        candidates.sort_by_key(|index| (self.priority(nodes[*index].uri()), *index));
+        let (eligible, too_recent): (Vec<usize>, Vec<usize>) = candidates
+            .into_iter()
+            .partition(|index| self.is_eligible(nodes[*index].uri(), now));
+
+        match outstanding_keys.first() {
+            Some(key) => {
+                let has_key = |index: &usize| ingress_keys.get(*index) == Some(&Some(*key));
+                let with_key: Vec<usize> = eligible.iter().copied().filter(has_key).collect();
+                if let Some(index) = with_key.first() {
+                    let decision = FailoverDecision::new(
+                        FailoverAction::ActivateNode,
+                        Some(nodes[*index].uri().clone()),
+                        format!(
+                            "Highest priority healthy node with the outstanding key {}",
+                            key
+                        ),
+                    );
+                    return (decision, with_key);
+                }
+                // Reporting a key as lost cannot be undone, so wait for a
+                // recovering node which has the key rather than doing that.
+                if let Some(index) = too_recent.iter().find(|index| has_key(index)) {
+                    let decision = FailoverDecision::new(
+                        FailoverAction::Wait,
+                        Some(nodes[*index].uri().clone()),
+                        format!(
+                            "Node with the outstanding key {} has not been healthy for {:?} yet",
+                            key, self.policy.min_healthy_time
+                        ),
+                    );
+                    return (decision, vec![]);
+                }
+                match eligible.first() {
+                    Some(index) => {
+                        let decision = FailoverDecision::new(
+                            FailoverAction::ReportLostKeyAndActivateNode,
+                            Some(nodes[*index].uri().clone()),
+                            format!(
+                                "No node has the outstanding key {}; highest priority healthy node",
+                                key
+                            ),
+                        );
+                        (decision, eligible)
+                    }
+                    None => (self.no_eligible_node(), vec![]),
+                }
+            }
+            None => match eligible.first() {
+                Some(index) => {
+                    let decision = FailoverDecision::new(
+                        FailoverAction::SetNewKeysAndActivateNode,
+                        Some(nodes[*index].uri().clone()),
+                        "No outstanding key; highest priority healthy node".to_string(),
+                    );
+                    (decision, eligible)
+                }
+                None => (self.no_eligible_node(), vec![]),
+            },
+        }
+    }
+
+    fn no_eligible_node(&self) -> FailoverDecision {
+        FailoverDecision::new(
+            FailoverAction::Wait,
+            None,
+            format!(
+                "No node has been healthy for {:?} yet",
+                self.policy.min_healthy_time
+            ),
+        )
+    }
+
+    /// Carry out a decision on the first of `candidates` on which it succeeds.
+    /// Each operation on a node is retried a few times before moving on to
+    /// the next candidate. The decision is updated with the node which was
+    /// actually activated.
+    fn execute<N: IngestNode>(
+        &self,
+        decision: &mut FailoverDecision,
+        nodes: &[N],
+        candidates: &[usize],
+        outstanding_keys: &[CompressedRistrettoPublic],
+        report_lost_key: impl FnOnce(CompressedRistrettoPublic) -> Result<(), String>,
+    ) -> Result<(), String> {
+        if decision.action == FailoverAction::ReportLostKeyAndActivateNode {
+            report_lost_key(outstanding_keys[0])?;
+        }
+
+        let mut failures = Vec::new();
+        for node in candidates.iter().map(|index| &nodes[*index]) {
+            let result = if decision.action == FailoverAction::ActivateNode {
+                Ok(())
+            } else {
+                self.with_retries(node, "Setting new keys", || node.new_keys())
+            }
+            .and_then(|_| self.with_retries(node, "Activation", || node.activate()));
+
+            match result {
+                Ok(()) => {
+                    if !failures.is_empty() {
+                        decision.reason = format!(
+                            "{}; after failing on {}",
+                            decision.reason,
+                            failures.join(", ")
+                        );
+                        decision.node = Some(node.uri().clone());
+                    }
+                    return Ok(());
+                }
+                Err(err) => failures.push(format!("{} ({})", node.uri(), err)),
+            }
+        }
+
+        if failures.is_empty() {
+            return Err("Chosen node is not in the cluster".to_string());
+        }
+        Err(format!(
+            "Failed on every eligible node: {}",
+            failures.join(", ")
+        ))
+    }
+
+    /// Run an operation on a node, retrying it a few times if it fails.
+    fn with_retries<N: IngestNode>(
+        &self,
+        node: &N,
+        operation_name: &str,
+        operation: impl Fn() -> Result<(), String>,
+    ) -> Result<(), String> {
+        retry_with_index(
+            Fixed::from_millis(Self::RETRY_DELAY_MS).take(Self::NUMBER_OF_RETRIES),
+            |current_try| match operation() {
+                Ok(()) => OperationResult::Ok(()),
+                Err(err) => {
+                    log::warn!(
+                        self.logger,
+                        "{} failed on ingest node {} (try {}): {}",
+                        operation_name,
+                        node.uri(),
+                        current_try,
+                        err
+                    );
+                    OperationResult::Retry(format!("{} failed: {}", operation_name, err))
+                }
+            },
+        )
+        .map_err(|err| match err {
+            RetryError::Operation { error, .. } => error,
+            RetryError::Internal(error) => error,
+        })
+    }
+
+    fn priority(&self, uri: &FogIngestUri) -> u32 {
+        self.policy
+            .node_priorities
+            .iter()
+            .filter(|node_priority| &node_priority.uri == uri)
+            .map(|node_priority| node_priority.priority)
+            .min()
+            .unwrap_or(u32::MAX)
+    }
+
+    fn is_eligible(&self, uri: &FogIngestUri, now: Instant) -> bool {
+        self.healthy_since.get(uri).map_or(false, |since| {
+            now.saturating_duration_since(*since) >= self.policy.min_healthy_time
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mc_common::logger::{test_with_logger, Logger};
+    use mc_util_from_random::FromRandom;
+    use rand_core::SeedableRng;
+    use rand_hc::Hc128Rng;
+    use std::cell::{Cell, RefCell};
+
+    /// A mock Fog Ingest node, which records the calls made to it.
+    struct MockIngestNode {
+        uri: FogIngestUri,
+        /// How many more activations should fail; usize::MAX means all of
+        /// them.
+        activate_failures: Cell<usize>,
+        calls: RefCell<Vec<&'static str>>,
+    }
+
+    impl MockIngestNode {
+        fn new(port: u16) -> Self {
+            Self {
+                uri: FogIngestUri::from_str(&format!("insecure-fog-ingest://node:{}/", port))
+                    .unwrap(),
+                activate_failures: Cell::new(0),
+                calls: Default::default(),
+            }
+        }
+
+        fn calls(&self) -> Vec<&'static str> {
+            self.calls.borrow().clone()
+        }
+    }
+
+    impl IngestNode for MockIngestNode {
+        fn uri(&self) -> &FogIngestUri {
+            &self.uri
+        }
+
+        fn activate(&self) -> Result<(), String> {
+            self.calls.borrow_mut().push("activate");
+            let failures = self.activate_failures.get();
+            if failures > 0 {
+                if failures != usize::MAX {
+                    self.activate_failures.set(failures - 1);
+                }
+                return Err("mock activation failure".to_string());
+            }
+            Ok(())
+        }
+
+        fn new_keys(&self) -> Result<(), String> {
+            self.calls.borrow_mut().push("new_keys");
+            Ok(())
+        }
+    }
+
+    fn make_failover(
+        policy: FailoverPolicy,
+        nodes: &[MockIngestNode],
+        now: Instant,
+        logger: Logger,
+    ) -> Failover {
+        let mut failover = Failover::new(policy, FailoverHistory::default(), logger);
+        for node in nodes {
+            failover.observe_node(&node.uri, true, now);
+        }
+        failover
+    }
+
+    fn random_key(rng: &mut Hc128Rng) -> CompressedRistrettoPublic {
+        CompressedRistrettoPublic::from_random(rng)
+    }
+
+    #[test_with_logger]
+    fn activates_highest_priority_node_with_outstanding_key(logger: Logger) {
+        let mut rng = Hc128Rng::from_seed([1u8; 32]);
+        let key = random_key(&mut rng);
+        let nodes = vec![
+            MockIngestNode::new(3226),
+            MockIngestNode::new(3227),
+            MockIngestNode::new(3228),
+        ];
+        let policy = FailoverPolicy {
+            node_priorities: vec![
+                NodePriority::from_str(&format!("{}=2", nodes[1].uri)).unwrap(),
+                NodePriority::from_str(&format!("{}=1", nodes[2].uri)).unwrap(),
+            ],
+            ..Default::default()
+        };
+        let now = Instant::now();
+        let mut failover = make_failover(policy, &nodes, now, logger);
+
+        let decision = failover.run(
+            &nodes,
+            &[Some(key), Some(key), Some(key)],
+            &[key],
+            |_| panic!("key should not be reported lost"),
+            now,
+        );
+        assert_eq!(decision.action, FailoverAction::ActivateNode);
+        assert_eq!(decision.node.as_ref(), Some(&nodes[2].uri));
+        assert_eq!(decision.error, None);
+        assert!(nodes[0].calls().is_empty());
+        assert!(nodes[1].calls().is_empty());
+        assert_eq!(nodes[2].calls(), vec!["activate"]);
+
+        // Without the key, the key is reported lost and the highest priority
+        // node gets new keys.
+        let mut reported = None;
+        let other_key = random_key(&mut rng);
+        let decision = failover.run(
+            &nodes,
+            &[None, Some(key), Some(key)],
+            &[other_key],
+            |key| {
+                reported = Some(key);
+                Ok(())
+            },
+            now,
+        );
+        assert_eq!(
+            decision.action,
+            FailoverAction::ReportLostKeyAndActivateNode
+        );
+        assert_eq!(decision.node.as_ref(), Some(&nodes[2].uri));
+        assert_eq!(reported, Some(other_key));
+        assert_eq!(nodes[2].calls(), vec!["activate", "new_keys", "activate"]);
+
+        let history = failover.history.get();
+        assert_eq!(history.len(), 2);
+        assert_eq!(
+            history[0].action,
+            FailoverAction::ReportLostKeyAndActivateNode
+        );
+        assert_eq!(history[1].action, FailoverAction::ActivateNode);
+    }
+
+    #[test_with_logger]
+    fn waits_for_recovering_node_with_outstanding_key(logger: Logger) {
+        let mut rng = Hc128Rng::from_seed([2u8; 32]);
+        let key = random_key(&mut rng);
+        let other_key = random_key(&mut rng);
+        let nodes = vec![MockIngestNode::new(3226), MockIngestNode::new(3227)];
+        let policy = FailoverPolicy {
+            min_healthy_time: Duration::from_secs(60),
+            ..Default::default()
+        };
+        let start = Instant::now();
+        let mut failover = make_failover(policy, &nodes, start, logger);
+
+        // Node 0 has the key, but just came back.
+        let later = start + Duration::from_secs(120);
+        failover.observe_node(&nodes[0].uri, false, later);
+        failover.observe_node(&nodes[0].uri, true, later);
+        let ingress_keys = [Some(key), Some(other_key)];
+
+        let decision = failover.run(
+            &nodes,
+            &ingress_keys,
+            &[key],
+            |_| panic!("key should not be reported lost"),
+            later,
+        );
+        assert_eq!(decision.action, FailoverAction::Wait);
+        assert_eq!(decision.node.as_ref(), Some(&nodes[0].uri));
+        assert!(nodes[0].calls().is_empty());
+        assert!(nodes[1].calls().is_empty());
+
+        // Identical decisions are merged in the history.
+        failover.run(&nodes, &ingress_keys, &[key], |_| Ok(()), later);
+        let history = failover.history.get();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].count, 2);
+
+        let decision = failover.run(
+            &nodes,
+            &ingress_keys,
+            &[key],
+            |_| panic!("key should not be reported lost"),
+            later + Duration::from_secs(60),
+        );
+        assert_eq!(decision.action, FailoverAction::ActivateNode);
+        assert_eq!(decision.node.as_ref(), Some(&nodes[0].uri));
+        assert_eq!(nodes[0].calls(), vec!["activate"]);
+    }
+
+    #[test_with_logger]
+    fn limits_failovers_per_hour(logger: Logger) {
+        let nodes = vec![MockIngestNode::new(3226), MockIngestNode::new(3227)];
+        for node in &nodes {
+            node.activate_failures.set(usize::MAX);
+        }
+        let policy = FailoverPolicy {
+            max_failovers_per_hour: Some(2),
+            ..Default::default()
+        };
+        let start = Instant::now();
+        let mut failover = make_failover(policy, &nodes, start, logger);
+
+        // Failed failovers count against the limit too.
+        for minutes in [0, 10] {
+            let decision = failover.run(
+                &nodes,
+                &[None, None],
+                &[],
+                |_| Ok(()),
+                start + Duration::from_secs(minutes * 60),
+            );
+            assert_eq!(decision.action, FailoverAction::SetNewKeysAndActivateNode);
+            assert!(decision.error.is_some());
+        }
+
+        let decision = failover.run(
+            &nodes,
+            &[None, None],
+            &[],
+            |_| Ok(()),
+            start + Duration::from_secs(20 * 60),
+        );
+        assert_eq!(decision.action, FailoverAction::Wait);
+        // Each failover sets new keys once and tries to activate four times,
+        // on each node.
+        assert_eq!(nodes[0].calls().len(), 10);
+        assert_eq!(nodes[1].calls().len(), 10);
+
+        // An hour after the first failover, one more is allowed.
+        let decision = failover.run(
+            &nodes,
+            &[None, None],
+            &[],
+            |_| Ok(()),
+            start + Duration::from_secs(60 * 60),
+        );
+        assert_eq!(decision.action, FailoverAction::SetNewKeysAndActivateNode);
+        assert_eq!(nodes[0].calls().len(), 15);
+    }
+
+    #[test_with_logger]
+    fn retries_and_falls_back_to_next_node(logger: Logger) {
+        let mut rng = Hc128Rng::from_seed([4u8; 32]);
+        let key = random_key(&mut rng);
+        let nodes = vec![
+            MockIngestNode::new(3226),
+            MockIngestNode::new(3227),
+            MockIngestNode::new(3228),
+        ];
+        let now = Instant::now();
+        let mut failover = make_failover(FailoverPolicy::default(), &nodes, now, logger);
+
+        // A transient failure is retried on the same node.
+        nodes[0].activate_failures.set(2);
+        let decision = failover.run(&nodes, &[None, None, None], &[], |_| Ok(()), now);
+        assert_eq!(decision.action, FailoverAction::SetNewKeysAndActivateNode);
+        assert_eq!(decision.node.as_ref(), Some(&nodes[0].uri));
+        assert_eq!(decision.error, None);
+        assert_eq!(
+            nodes[0].calls(),
+            vec!["new_keys", "activate", "activate", "activate"]
+        );
+
+        // A node which keeps failing is given up on, and the next eligible
+        // node with the key is activated instead. Node 1 doesn't have the key.
+        nodes[0].activate_failures.set(usize::MAX);
+        let decision = failover.run(
+            &nodes,
+            &[Some(key), None, Some(key)],
+            &[key],
+            |_| panic!("key should not be reported lost"),
+            now,
+        );
+        assert_eq!(decision.action, FailoverAction::ActivateNode);
+        assert_eq!(decision.node.as_ref(), Some(&nodes[2].uri));
+        assert_eq!(decision.error, None);
+        assert!(decision.reason.contains(&nodes[0].uri.to_string()));
+        assert_eq!(nodes[0].calls().len(), 4 + 4);
+        assert!(nodes[1].calls().is_empty());
+        assert_eq!(nodes[2].calls(), vec!["activate"]);
+
+        // When every node fails, the key is still only reported lost once.
+        nodes[2].activate_failures.set(usize::MAX);
+        nodes[1].activate_failures.set(usize::MAX);
+        let mut reports = 0;
+        let decision = failover.run(
+            &nodes,
+            &[None, None, None],
+            &[key],
+            |_| {
+                reports += 1;
+                Ok(())
+            },
+            now,
+        );
+        assert_eq!(
+            decision.action,
+            FailoverAction::ReportLostKeyAndActivateNode
+        );
+        assert_eq!(reports, 1);
+        assert!(decision.error.is_some());
+        assert_eq!(nodes[1].calls().len(), 5);
+    }
+
+    #[test_with_logger]
+    fn dry_run_and_multiple_outstanding_keys(logger: Logger) {
+        let mut rng = Hc128Rng::from_seed([3u8; 32]);
+        let key = random_key(&mut rng);
+        let nodes = vec![MockIngestNode::new(3226)];
+        let policy = FailoverPolicy {
+            dry_run: true,
+            ..Default::default()
+        };
+        let now = Instant::now();
+        let mut failover = make_failover(policy, &nodes, now, logger);
+
+        let decision = failover.run(
+            &nodes,
+            &[None],
+            &[key],
+            |_| panic!("dry run should not report keys lost"),
+            now,
+        );
+        assert_eq!(
+            decision.action,
+            FailoverAction::ReportLostKeyAndActivateNode
+        );
+        assert!(decision.dry_run);
+        assert!(nodes[0].calls().is_empty());
+
+        let decision = failover.run(
+            &nodes,
+            &[None],
+            &[key, random_key(&mut rng)],
+            |_| panic!("key should not be reported lost"),
+            now,
+        );
+        assert_eq!(decision.action, FailoverAction::DisableOverseer);
+        assert!(nodes[0].calls().is_empty());
+
+        // Unresponsive nodes are never chosen.
+        failover.observe_node(&nodes[0].uri, false, now);
+        let decision = failover.run(&nodes, &[None], &[], |_| Ok(()), now);
+        assert_eq!(decision.action, FailoverAction::Wait);
+        assert_eq!(decision.node, None);
+    }
+
+    #[test]
+    fn parse_node_priority() {
+        let node_priority =
+            NodePriority::from_str("insecure-fog-ingest://0.0.0.0:3226/=5").unwrap();
+        assert_eq!(node_priority.priority, 5);
+        assert_eq!(
+            node_priority.uri,
+            FogIngestUri::from_str("insecure-fog-ingest://0.0.0.0:3226/").unwrap()
+        );
+        assert!(NodePriority::from_str("insecure-fog-ingest://0.0.0.0:3226/").is_err());
+        assert!(NodePriority::from_str("insecure-fog-ingest://0.0.0.0:3226/=x").is_err());
+    }
+}
@@ -6,6 +6,7 @@
 #![deny(missing_docs)]
 
 pub mod config;
+pub mod failover;
 pub mod metrics;
 pub mod responses;
 pub mod server;
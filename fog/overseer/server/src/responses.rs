@@ -2,6 +2,7 @@
 
 //! Contains responses that are returned by Fog Overseer.
 
+use crate::failover::{FailoverDecision, FailoverPolicy};
 use mc_fog_types::ingest_common::IngestSummary;
 use mc_fog_uri::FogIngestUri;
 use serde::Serialize;
@@ -15,3 +16,14 @@ pub struct GetIngestSummariesResponse {
     /// The ingest summaries.
     pub ingest_summaries: HashMap<FogIngestUri, Result<IngestSummary, String>>,
 }
+
+/// The failover policy that Fog Overseer applies, and the most recent
+/// failover decisions it made, most recent first.
+#[derive(Serialize)]
+pub struct GetFailoverResponse {
+    /// The failover policy.
+    pub policy: FailoverPolicy,
+
+    /// The failover decisions.
+    pub decisions: Vec<FailoverDecision>,
+}
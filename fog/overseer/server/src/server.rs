@@ -6,7 +6,9 @@
 //! HTTP Client -> *Overseer Rocket Server* -> OverseerService -> OverseerWorker
 
 use crate::{
-    error::OverseerError, responses::GetIngestSummariesResponse, service::OverseerService,
+    error::OverseerError,
+    responses::{GetFailoverResponse, GetIngestSummariesResponse},
+    service::OverseerService,
 };
 use mc_fog_recovery_db_iface::RecoveryDb;
 use mc_fog_sql_recovery_db::SqlRecoveryDb;
@@ -34,6 +36,14 @@ fn get_ingest_summaries(
     state.overseer_service.get_ingest_summaries().map(Json)
 }
 
+/// Returns the failover policy and the most recent failover decisions.
+#[get("/failover")]
+fn get_failover(
+    state: &rocket::State<OverseerState<SqlRecoveryDb>>,
+) -> Result<Json<GetFailoverResponse>, String> {
+    state.overseer_service.get_failover().map(Json)
+}
+
 /// Produces metrics for Prometheus.
 ///
 /// Meant to be called only by the Prometheus pull mechanism.
@@ -66,7 +76,8 @@ pub fn initialize_rocket_server<T: rocket::figment::Provider>(
             disable,
             get_status,
             get_metrics,
-            get_ingest_summaries
+            get_ingest_summaries,
+            get_failover
         ],
     )
 }
@@ -7,7 +7,12 @@
 //!
 //! HTTP Client -> Overseer Rocket Server -> *OverseerService* -> OverseerWorker
 
-use crate::{error::OverseerError, responses::GetIngestSummariesResponse, worker::OverseerWorker};
+use crate::{
+    error::OverseerError,
+    failover::{FailoverHistory, FailoverPolicy},
+    responses::{GetFailoverResponse, GetIngestSummariesResponse},
+    worker::OverseerWorker,
+};
 use mc_common::logger::{log, Logger};
 use mc_fog_ingest_client::FogIngestGrpcClient;
 use mc_fog_recovery_db_iface::RecoveryDb;
@@ -33,6 +38,8 @@ where
     overseer_worker: Option<OverseerWorker>,
     recovery_db: DB,
     is_enabled: Arc<AtomicBool>,
+    failover_policy: FailoverPolicy,
+    failover_history: FailoverHistory,
 }
 
 impl<DB: RecoveryDb + Clone + Send + Sync + 'static> OverseerService<DB>
@@ -42,8 +49,13 @@ where
     /// Retry failed GRPC requests every 10 seconds.
     const GRPC_RETRY_SECONDS: Duration = Duration::from_millis(10000);
 
-    /// Insantiate the service with the given URIs and DB.
-    pub fn new(ingest_cluster_uris: Vec<FogIngestUri>, recovery_db: DB, logger: Logger) -> Self {
+    /// Insantiate the service with the given URIs, DB and failover policy.
+    pub fn new(
+        ingest_cluster_uris: Vec<FogIngestUri>,
+        recovery_db: DB,
+        failover_policy: FailoverPolicy,
+        logger: Logger,
+    ) -> Self {
         let grpcio_env = Arc::new(grpcio::EnvBuilder::new().build());
         let ingest_clients: Vec<FogIngestGrpcClient> = ingest_cluster_uris
             .iter()
@@ -62,6 +74,8 @@ where
             overseer_worker: None,
             recovery_db,
             is_enabled: Arc::new(AtomicBool::new(false)),
+            failover_policy,
+            failover_history: FailoverHistory::default(),
         }
     }
 
@@ -84,6 +98,8 @@ where
             self.recovery_db.clone(),
             self.logger.clone(),
             self.is_enabled.clone(),
+            self.failover_policy.clone(),
+            self.failover_history.clone(),
         ));
 
         Ok(())
@@ -144,6 +160,14 @@ where
             .map_err(|err| format!("Get prometheus metrics from_utf8 failed: {}", err))
     }
 
+    /// Get the failover policy and the most recent failover decisions.
+    pub fn get_failover(&self) -> Result<GetFailoverResponse, String> {
+        Ok(GetFailoverResponse {
+            policy: self.failover_policy.clone(),
+            decisions: self.failover_history.get(),
+        })
+    }
+
     /// Try and fetch summaries from all ingest clients.
     pub fn get_ingest_summaries(&self) -> Result<GetIngestSummariesResponse, String> {
         let ingest_summaries: HashMap<FogIngestUri, Result<IngestSummary, String>> = self
@@ -7,7 +7,11 @@
 //!
 //! HTTP Client -> Overseer Rocket Server -> OverseerService -> *OverseerWorker*
 
-use crate::{error::OverseerError, metrics};
+use crate::{
+    error::OverseerError,
+    failover::{Failover, FailoverAction, FailoverHistory, FailoverPolicy},
+    metrics,
+};
 use mc_api::external;
 use mc_common::logger::{log, Logger};
 use mc_crypto_keys::CompressedRistrettoPublic;
@@ -23,7 +27,7 @@ use std::{
         Arc,
     },
     thread::{sleep, Builder as ThreadBuilder, JoinHandle},
-    time::Duration,
+    time::{Duration, Instant},
 };
 
 /// Wraps a thread that is responsible for overseeing the active Fog Ingest
@@ -32,7 +36,8 @@ use std::{
 /// The worker checks to see that there's always one active ingress key. If
 /// there is no active key, then it promotes an idle node to active, and in the
 /// case where none of the idle nodes contain the previously active ingress key,
-/// it reports that key as lost.
+/// it reports that key as lost. Which node is promoted, and when, is governed
+/// by the [FailoverPolicy].
 pub struct OverseerWorker {
     /// Join handle used to wait for the thread to terminate.
     join_handle: Option<JoinHandle<()>>,
@@ -47,11 +52,14 @@ impl OverseerWorker {
         recovery_db: DB,
         logger: Logger,
         is_enabled: Arc<AtomicBool>,
+        failover_policy: FailoverPolicy,
+        failover_history: FailoverHistory,
     ) -> Self
     where
         OverseerError: From<DB::Error>,
     {
         let thread_is_enabled = is_enabled;
+        let failover = Failover::new(failover_policy, failover_history, logger.clone());
         let stop_requested = Arc::new(AtomicBool::new(false));
         let thread_stop_requested = stop_requested.clone();
         let join_handle = Some(
@@ -64,6 +72,7 @@ impl OverseerWorker {
                         thread_is_enabled,
                         thread_stop_requested,
                         HashSet::new(),
+                        failover,
                         logger,
                     )
                 })
@@ -114,6 +123,9 @@ struct OverseerWorkerThread<DB: RecoveryDb> {
     /// This helps us debug when a node starts responding again.
     unresponsive_node_urls: HashSet<FogIngestUri>,
 
+    /// Applies the failover policy, and tracks the health of each node.
+    failover: Failover,
+
     logger: Logger,
 }
 
@@ -143,6 +155,7 @@ where
         is_enabled: Arc<AtomicBool>,
         stop_requested: Arc<AtomicBool>,
         unresponsive_node_urls: HashSet<FogIngestUri>,
+        failover: Failover,
         logger: Logger,
     ) {
         let thread = Self {
@@ -151,6 +164,7 @@ where
             is_enabled,
             stop_requested,
             unresponsive_node_urls,
+            failover,
             logger,
         };
         thread.run();
@@ -204,9 +218,9 @@ where
                         self.logger,
                         "There are currently no active nodes in the Fog Ingest cluster. Initiating automatic failover.",
                     );
-                    match self.perform_automatic_failover(ingest_summary_node_mappings) {
+                    match self.perform_automatic_failover(&ingest_summary_node_mappings) {
                         Ok(_) => {
-                            log::info!(self.logger, "Automatic failover completed successfully.")
+                            log::info!(self.logger, "Automatic failover completed.")
                         }
                         Err(err) => {
                             log::error!(self.logger, "Automatic failover failed: {}", err)
@@ -244,18 +258,25 @@ where
 
     /// Returns the latest round of ingest summaries for each
     /// FogIngestGrpcClient that communicates with a node that is online.
+    ///
+    /// Every node is queried, even if one fails, so that the health of each
+    /// node is tracked for failover.
     fn retrieve_ingest_summary_node_mappings(
         &mut self,
     ) -> Result<Vec<IngestSummaryNodeMapping>, OverseerError> {
         let logger = &self.logger;
         let unresponsive_node_urls = &mut self.unresponsive_node_urls;
-        self.ingest_clients
+        let failover = &mut self.failover;
+        let now = Instant::now();
+        let results: Vec<Result<IngestSummaryNodeMapping, OverseerError>> = self
+            .ingest_clients
             .iter()
             .enumerate()
             .map(|(node_index, ingest_client)| {
                 let uri = ingest_client.get_uri();
                 match ingest_client.get_status() {
                     Ok(ingest_summary) => {
+                        failover.observe_node(uri, true, now);
                         log::trace!(
                             logger,
                             "Ingest summary retrieved from '{}': {:?}",
@@ -281,12 +302,14 @@ where
                             uri, err
                         );
                         log::trace!(logger, "{}", error_message);
+                        failover.observe_node(uri, false, now);
                         unresponsive_node_urls.insert(uri.clone());
                         Err(OverseerError::UnresponsiveNodeError(error_message))
                     }
                 }
             })
-            .collect()
+            .collect();
+        results.into_iter().collect()
     }
 
     /// Performs automatic failover, which means that we try to activate nodes
@@ -299,51 +322,68 @@ where
     ///      idle.
     ///   2. If there are:
     ///         a) 0 outstanding keys:
-    ///              No node will be activated. We now have to:
-    ///                 (i)  Set new keys on an idle node.
-    ///                 (ii) Activate that node.
+    ///              Set new keys on an idle node and activate that node.
     ///         b) 1 outsanding key:
     ///              Try to find an idle node that contains that key.
     ///                 (i)  If you find one, great! Just activate that node. If
     ///                      activation is unsuccessful, then return an error
     ///                      and return to the overseer polling logic.
-    ///                 (ii) If you don't find an idle node with that key,
-    ///                      then you have to report that key as lost, set
-    ///                      new keys on an idle node, and activate that node.
+    ///                 (ii) If a node has the key but has not been healthy for
+    ///                      long enough yet, wait for it.
+    ///                 (iii) Otherwise, report that key as lost, set new keys
+    ///                      on an idle node, and activate that node.
     ///        c) > 1 outstanding key:
     ///             (i) Disable
     ///             (ii) TODO: Send an alert.
+    ///
+    /// The idle node is chosen, and failover may be postponed, according to
+    /// the [FailoverPolicy]. See [Failover] for details.
     fn perform_automatic_failover(
-        &self,
-        ingest_summary_node_mappings: Vec<IngestSummaryNodeMapping>,
+        &mut self,
+        ingest_summary_node_mappings: &[IngestSummaryNodeMapping],
     ) -> Result<(), OverseerError> {
         let inactive_outstanding_keys: Vec<CompressedRistrettoPublic> =
             self.get_inactive_outstanding_keys()?;
+        log::info!(
+            self.logger,
+            "Found {} outstanding keys.",
+            inactive_outstanding_keys.len()
+        );
 
-        match inactive_outstanding_keys.len() {
-            0 => {
-                log::info!(self.logger, "Found 0 outstanding keys.");
-                let activated_node_index = self.set_new_key_on_a_node()?;
-                self.activate_a_node(activated_node_index)?;
-                Ok(())
-            }
-            1 => {
-                log::info!(self.logger, "Found 1 outstanding key.");
-                let inactive_outstanding_key = inactive_outstanding_keys[0];
-                self.handle_one_inactive_outstanding_key(
-                    inactive_outstanding_key,
-                    ingest_summary_node_mappings,
-                )?;
-                Ok(())
-            }
-            _ => {
-                self.is_enabled.store(false, Ordering::SeqCst);
-                let error_message = format!("This is unexpected and requires manual intervention. As such, we've disabled overseer. Take the appropriate action and then re-enable overseer by calling the /enable endpoint. Inactive oustanding keys: {:?}", inactive_outstanding_keys);
-                Err(OverseerError::MultipleInactiveOutstandingKeys(
-                    error_message,
-                ))
-            }
+        let mut ingress_keys: Vec<Option<CompressedRistrettoPublic>> =
+            vec![None; self.ingest_clients.len()];
+        for mapping in ingest_summary_node_mappings {
+            ingress_keys[mapping.node_index] =
+                CompressedRistrettoPublic::try_from(mapping.ingest_summary.get_ingress_pubkey())
+                    .ok();
+        }
+
+        let recovery_db = &self.recovery_db;
+        let logger = &self.logger;
+        let decision = self.failover.run(
+            self.ingest_clients.as_slice(),
+            &ingress_keys,
+            &inactive_outstanding_keys,
+            |key| {
+                Self::report_lost_ingress_key(recovery_db, logger, key)
+                    .map_err(|err| err.to_string())
+            },
+            Instant::now(),
+        );
+
+        if decision.action == FailoverAction::DisableOverseer {
+            self.is_enabled.store(false, Ordering::SeqCst);
+            return Err(OverseerError::MultipleInactiveOutstandingKeys(
+                decision.reason,
+            ));
+        }
+        if let Some(error) = decision.error {
+            return Err(match decision.action {
+                FailoverAction::ActivateNode => OverseerError::ActivateNode(error),
+                _ => OverseerError::SetNewKey(error),
+            });
         }
+        Ok(())
     }
 
     fn get_inactive_outstanding_keys(
@@ -382,85 +422,18 @@ where
             .collect())
     }
 
-    /// Performs the following logic when one inactive outstanding key is found:
-    ///   1) Tries to find an idle node that contains that key.
-    ///      (i)  If it's found, it activates the node that contains it. If
-    ///           activation is unsuccessful, then it returns an error.
-    ///      (ii) If no idle node is found that contains the key, then it
-    ///           reports that key as lost, sets new keys on an idle node, and
-    ///           activates that node.
-    fn handle_one_inactive_outstanding_key(
-        &self,
-        inactive_outstanding_key: CompressedRistrettoPublic,
-        ingest_summary_node_mappings: Vec<IngestSummaryNodeMapping>,
-    ) -> Result<(), OverseerError> {
-        log::info!(
-            self.logger,
-            "Trying to activate an idle node with inactive outstanding key: {:?}",
-            &inactive_outstanding_key
-        );
-        for ingest_summary_node_mapping in &ingest_summary_node_mappings {
-            let node_ingress_key = match CompressedRistrettoPublic::try_from(
-                ingest_summary_node_mapping
-                    .ingest_summary
-                    .get_ingress_pubkey(),
-            ) {
-                Ok(key) => key,
-                Err(_) => continue,
-            };
-            if inactive_outstanding_key.eq(&node_ingress_key) {
-                let node = &self.ingest_clients[ingest_summary_node_mapping.node_index];
-                match node.activate() {
-                    Ok(_) => {
-                        log::info!(
-                            self.logger,
-                            "Successfully activated node {}.",
-                            node.get_uri()
-                        );
-                        return Ok(());
-                    }
-                    Err(err) => {
-                        let error_message = format!(
-                            "Tried activating node {}, but it failed: {}.",
-                            node.get_uri(),
-                            err
-                        );
-                        return Err(OverseerError::ActivateNode(error_message));
-                    }
-                }
-            }
-        }
-
-        // We've gone through all the Fog Ingest nodes' keys,
-        // and none of them matches the inactive outstanding key. We must
-        // report the inactive outstanding key as lost, set new keys
-        // on an idle node, and activate that node.
-        log::warn!(
-            self.logger,
-            "Could not find a node that has the inactive outstanding key: {:?}",
-            &inactive_outstanding_key
-        );
-        self.report_lost_ingress_key(inactive_outstanding_key)?;
-        let activated_node_index = self.set_new_key_on_a_node()?;
-        self.activate_a_node(activated_node_index)?;
-
-        Ok(())
-    }
-
     /// Tries to report a lost ingress key.
     fn report_lost_ingress_key(
-        &self,
+        recovery_db: &DB,
+        logger: &Logger,
         inactive_outstanding_key: CompressedRistrettoPublic,
     ) -> Result<(), OverseerError> {
         let result = retry_with_index(
             Fixed::from_millis(200).take(Self::NUMBER_OF_TRIES),
-            |current_try| match self
-                .recovery_db
-                .report_lost_ingress_key(inactive_outstanding_key)
-            {
+            |current_try| match recovery_db.report_lost_ingress_key(inactive_outstanding_key) {
                 Ok(_) => {
                     log::info!(
-                        self.logger,
+                        logger,
                         "The following key was successfully reported as lost: {}",
                         inactive_outstanding_key
                     );
@@ -476,75 +449,4 @@ where
 
         Ok(result?)
     }
-
-    /// Tries to set a new ingress key on a node. The node is assumed to be
-    /// idle.
-    fn set_new_key_on_a_node(&self) -> Result<usize, OverseerError> {
-        for (i, ingest_client) in self.ingest_clients.iter().enumerate() {
-            let result = retry_with_index(
-                Fixed::from_millis(200).take(Self::NUMBER_OF_TRIES),
-                |current_try| {
-                    match ingest_client.new_keys() {
-                        Ok(_) => {
-                            log::info!(
-                                self.logger,
-                                "New keys successfully set on the ingest node {}.",
-                                ingest_client.get_uri()
-                            );
-                            OperationResult::Ok(())
-                        }
-                        // TODO: We'll need to alert Ops to take manual action at this point.
-                        Err(err) => {
-                            let number_of_remaining_tries =
-                                Self::NUMBER_OF_TRIES - current_try as usize;
-                            let error_message = format!("Did not succeed in setting a new key on ingest node {}. Will try {} more times. Underlying error: {}", ingest_client.get_uri(), number_of_remaining_tries, err);
-                            OperationResult::Retry(OverseerError::SetNewKey(error_message))
-                        }
-                    }
-                },
-            );
-
-            if result.is_ok() {
-                return Ok(i);
-            }
-        }
-
-        Err(OverseerError::SetNewKey(
-            "New keys were not successfully set on any of the idle nodes.".to_string(),
-        ))
-    }
-
-    /// Tries to activate a node. The node is assumed to be idle.
-    fn activate_a_node(&self, activated_node_index: usize) -> Result<(), OverseerError> {
-        let result = retry_with_index(
-            Fixed::from_millis(200).take(Self::NUMBER_OF_TRIES),
-            |current_try| {
-                let ingest_client = &self.ingest_clients[activated_node_index];
-                match ingest_client.activate() {
-                    Ok(_) => {
-                        log::info!(
-                            self.logger,
-                            "Node {} successfully activated.",
-                            ingest_client.get_uri(),
-                        );
-                        OperationResult::Ok(())
-                    }
-                    // TODO: Alert Ops to take manual action at this point.
-                    Err(err) => {
-                        let number_of_remaining_tries =
-                            Self::NUMBER_OF_TRIES - current_try as usize;
-                        let error_message = format!(
-                            "Node {} not activated. Will try {} more times. Underlying error: {}",
-                            ingest_client.get_uri(),
-                            number_of_remaining_tries,
-                            err
-                        );
-                        OperationResult::Retry(OverseerError::ActivateNode(error_message))
-                    }
-                }
-            },
-        );
-
-        Ok(result?)
-    }
 }
@@ -0,0 +1,90 @@
+// Copyright (c) 2018-2022 The MobileCoin Foundation
+
+mod utils;
+
+use mc_common::logger::{test_with_logger, Logger};
+use mc_fog_ingest_server_test_utils::{get_ingress_keys, IngestServerTestHelper};
+use mc_fog_overseer_server::failover::{FailoverPolicy, NodePriority};
+use mc_fog_uri::FogIngestUri;
+use rocket::serde::json::Value;
+use std::{collections::VecDeque, str::FromStr, thread::sleep, time::Duration};
+use utils::TestHelperExt;
+
+const BASE_PORT: u16 = 8900;
+
+// Tests that when the active node goes down, Fog Overseer activates the
+// healthy node with the outstanding key which has the highest priority,
+// skipping a preferred node which doesn't respond, and that the decision is
+// shown by GET /failover.
+#[test_with_logger]
+fn active_node_goes_down_highest_priority_responsive_node_is_activated(logger: Logger) {
+    let mut helper = IngestServerTestHelper::new(BASE_PORT, logger.clone());
+    helper.add_origin_block();
+    let mut nodes = VecDeque::from(helper.make_nodes(3));
+
+    nodes[0].activate().expect("nodes[0] failed to activate");
+    let original_ingress_keys = get_ingress_keys(nodes.make_contiguous());
+
+    // No ingest server listens on this URI.
+    let unresponsive_uri = FogIngestUri::from_str(&format!(
+        "insecure-fog-ingest://0.0.0.0:{}/",
+        BASE_PORT + 20
+    ))
+    .unwrap();
+
+    let mut ingest_uris: Vec<FogIngestUri> = nodes
+        .iter()
+        .map(|node| node.client_listen_uri.clone())
+        .collect();
+    ingest_uris.push(unresponsive_uri.clone());
+    let failover_policy = FailoverPolicy {
+        node_priorities: vec![
+            NodePriority::from_str(&format!("{}=1", unresponsive_uri)).unwrap(),
+            NodePriority::from_str(&format!("{}=2", nodes[2].client_listen_uri)).unwrap(),
+        ],
+        ..Default::default()
+    };
+    let node2_uri = nodes[2].client_listen_uri.clone();
+
+    // Initialize an OverseerService with an associated server.
+    let client = helper.enable_overseer_with_policy(ingest_uris, failover_policy);
+
+    // No failover has happened yet.
+    let failover: Value = client.get("/failover").dispatch().into_json().unwrap();
+    assert_eq!(failover["policy"]["node_priorities"][1]["priority"], 2);
+    assert_eq!(failover["decisions"], Value::Array(vec![]));
+
+    helper.add_test_blocks(11);
+    helper.wait_till_recovery_db_in_sync();
+
+    // Stop the active node, which makes its key outstanding.
+    drop(nodes.pop_front());
+    // Give node0 time to stop.
+    sleep(Duration::from_secs(2));
+
+    helper.add_test_blocks(11);
+    helper.wait_till_recovery_db_in_sync();
+
+    // Node 2 has the highest priority of the responsive nodes.
+    assert!(!nodes[0].is_active());
+    assert!(nodes[1].is_active());
+
+    // The original key is still in use, so it isn't reported lost.
+    assert_eq!(
+        get_ingress_keys(nodes.make_contiguous()),
+        original_ingress_keys[1..].to_vec()
+    );
+    helper.check_ingress_key(&original_ingress_keys[0], false, false);
+
+    let failover: Value = client.get("/failover").dispatch().into_json().unwrap();
+    let decision = &failover["decisions"][0];
+    assert_eq!(decision["action"], "ActivateNode", "{}", failover);
+    assert_eq!(
+        decision["node"],
+        rocket::serde::json::to_value(&node2_uri).unwrap(),
+        "{}",
+        failover
+    );
+    assert_eq!(decision["error"], Value::Null, "{}", failover);
+    assert_eq!(decision["dry_run"], false, "{}", failover);
+}
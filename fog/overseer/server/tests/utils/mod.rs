@@ -4,6 +4,7 @@
 
 use mc_fog_ingest_server_test_utils::{IngestServerTestHelper, TestIngestNode};
 use mc_fog_overseer_server::{
+    failover::FailoverPolicy,
     server::{initialize_rocket_server, OverseerState},
     service::OverseerService,
 };
@@ -12,7 +13,15 @@ use rocket::local::blocking::Client;
 use std::{thread::sleep, time::Duration};
 
 pub trait TestHelperExt {
-    fn enable_overseer(&self, ingest_uris: Vec<FogIngestUri>) -> Client;
+    fn enable_overseer(&self, ingest_uris: Vec<FogIngestUri>) -> Client {
+        self.enable_overseer_with_policy(ingest_uris, FailoverPolicy::default())
+    }
+
+    fn enable_overseer_with_policy(
+        &self,
+        ingest_uris: Vec<FogIngestUri>,
+        failover_policy: FailoverPolicy,
+    ) -> Client;
 
     fn enable_overseer_for_nodes(&self, nodes: &[TestIngestNode]) -> Client {
         let ingest_uris = nodes
@@ -24,9 +33,17 @@ pub trait TestHelperExt {
 }
 
 impl TestHelperExt for IngestServerTestHelper {
-    fn enable_overseer(&self, ingest_uris: Vec<FogIngestUri>) -> Client {
-        let mut overseer_service =
-            OverseerService::new(ingest_uris, self.recovery_db.clone(), self.logger.clone());
+    fn enable_overseer_with_policy(
+        &self,
+        ingest_uris: Vec<FogIngestUri>,
+        failover_policy: FailoverPolicy,
+    ) -> Client {
+        let mut overseer_service = OverseerService::new(
+            ingest_uris,
+            self.recovery_db.clone(),
+            failover_policy,
+            self.logger.clone(),
+        );
         overseer_service
             .start()
             .expect("OverseerService failed to start");
@@ -3,6 +3,7 @@
 //! Main Method for the Fog Report Server
 
 use mc_common::{logger, sentry};
+use mc_fog_embedded_recovery_db::EmbeddedRecoveryDb;
 use mc_fog_report_server::{Config, Materials, Server};
 use mc_fog_sql_recovery_db::SqlRecoveryDb;
 use mc_util_cli::ParserWithBuildInfo;
@@ -20,25 +21,43 @@ fn main() {
     let materials = Materials::try_from(&config).expect("Could not read cryptographic materials");
 
     let database_url = env::var("DATABASE_URL").expect("DATABASE_URL environment variable missing");
-    let db = SqlRecoveryDb::new_from_url(
-        &database_url,
-        config.postgres_config.clone(),
-        logger.clone(),
-    )
-    .unwrap_or_else(|err| {
-        panic!(
-            "fog-report cannot connect to database '{}': {:?}",
-            database_url, err
+    let mut server = if EmbeddedRecoveryDb::is_embedded_url(&database_url) {
+        let db =
+            EmbeddedRecoveryDb::new_from_url(&database_url, logger.clone()).unwrap_or_else(|err| {
+                panic!(
+                    "fog-report cannot open embedded database '{}': {:?}",
+                    database_url, err
+                )
+            });
+
+        Server::new(
+            db,
+            config.chain_id.clone(),
+            &config.client_listen_uri,
+            materials,
+            logger.clone(),
         )
-    });
+    } else {
+        let db = SqlRecoveryDb::new_from_url(
+            &database_url,
+            config.postgres_config.clone(),
+            logger.clone(),
+        )
+        .unwrap_or_else(|err| {
+            panic!(
+                "fog-report cannot connect to database '{}': {:?}",
+                database_url, err
+            )
+        });
 
-    let mut server = Server::new(
-        db,
-        config.chain_id.clone(),
-        &config.client_listen_uri,
-        materials,
-        logger.clone(),
-    );
+        Server::new(
+            db,
+            config.chain_id.clone(),
+            &config.client_listen_uri,
+            materials,
+            logger.clone(),
+        )
+    };
     server.start();
 
     let config_json = serde_json::to_string(&config).expect("failed to serialize config to JSON");
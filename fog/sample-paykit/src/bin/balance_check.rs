@@ -45,6 +45,11 @@ struct Config {
     /// View server URI
     #[clap(long, env = "MC_VIEW_URI")]
     pub view_uri: FogViewUri,
+
+    /// Optional path at which to persist wallet state between runs, so that
+    /// restarts don't rescan all of the fog view history
+    #[clap(long, env = "MC_STATE_PATH")]
+    pub state_path: Option<PathBuf>,
 }
 
 fn main() {
@@ -69,6 +74,7 @@ fn main() {
         account_key,
         logger.clone(),
     )
+    .state_path(config.state_path)
     .build();
 
     loop {
@@ -15,6 +15,7 @@ use mc_blockchain_types::BlockIndex;
 use mc_common::logger::{log, Logger};
 use mc_crypto_keys::RistrettoPublic;
 use mc_fog_api::{fog_common, ledger};
+use mc_fog_kex_rng::VersionedKexRng;
 use mc_fog_ledger_connection::{
     Error as LedgerConnectionError, FogBlockGrpcClient, FogKeyImageGrpcClient,
     KeyImageResultExtension,
@@ -38,7 +39,11 @@ use mc_util_telemetry::{telemetry_static_key, tracer, Key, TraceContextExt, Trac
 use std::collections::{BTreeMap, HashMap, HashSet};
 
 mod memo_handler;
+mod snapshot;
 pub use memo_handler::{MemoHandler, MemoHandlerError};
+pub use snapshot::{
+    CachedTxDataSnapshot, StoredOwnedTxOut, StoredUserRng, CACHED_TX_DATA_SNAPSHOT_VERSION,
+};
 
 /// Maximum number of inputs in a transaction
 const MAX_INPUTS: usize = mc_transaction_core::constants::MAX_INPUTS as usize;
@@ -822,6 +827,123 @@ impl CachedTxData {
         Ok(())
     }
 
+    /// Make a snapshot of the state of this object, which can be saved and
+    /// later restored with `restore_snapshot`, instead of rescanning all of
+    /// the fog view history.
+    pub fn snapshot(&self) -> CachedTxDataSnapshot {
+        CachedTxDataSnapshot {
+            version: CACHED_TX_DATA_SNAPSHOT_VERSION,
+            account_spend_public_key: self
+                .account_key
+                .default_subaddress()
+                .spend_public_key()
+                .to_bytes()
+                .to_vec(),
+            rngs: self
+                .rng_set
+                .get_rngs()
+                .iter()
+                .map(|(nonce, rng)| StoredUserRng {
+                    nonce: nonce.clone(),
+                    rng: rng.clone().into(),
+                })
+                .collect(),
+            highest_processed_block_count: u64::from(
+                self.rng_set.get_highest_processed_block_count(),
+            ),
+            next_start_from_user_event_id: self.rng_set.get_next_start_from_user_event_id(),
+            owned_tx_outs: self
+                .owned_tx_outs
+                .values()
+                .map(|otxo| {
+                    let (spent_at, not_spent_as_of) = match otxo.status {
+                        KeyImageStatus::SpentAt(block_index) => (Some(block_index), 0),
+                        KeyImageStatus::NotSpent(as_of) => (None, u64::from(as_of)),
+                    };
+                    StoredOwnedTxOut {
+                        global_index: otxo.global_index,
+                        block_index: otxo.block_index,
+                        tx_out: otxo.tx_out.clone(),
+                        value: otxo.amount.value,
+                        token_id: *otxo.amount.token_id,
+                        subaddress_index: otxo.subaddress_index,
+                        key_image: otxo.key_image,
+                        spent_at,
+                        not_spent_as_of,
+                    }
+                })
+                .collect(),
+            key_image_data_completeness: u64::from(self.key_image_data_completeness),
+            latest_global_txo_count: self.latest_global_txo_count,
+            latest_block_version: self.latest_block_version,
+            missed_block_ranges: self.missed_block_ranges.clone(),
+        }
+    }
+
+    /// Replace the state of this object with a snapshot previously produced
+    /// by `snapshot`.
+    ///
+    /// After this, polling fog resumes from where the snapshot was taken.
+    /// If an error is returned, the state of this object is unchanged.
+    pub fn restore_snapshot(&mut self, snapshot: CachedTxDataSnapshot) -> Result<()> {
+        if snapshot.version != CACHED_TX_DATA_SNAPSHOT_VERSION {
+            return Err(Error::StateVersion(snapshot.version));
+        }
+        if snapshot.account_spend_public_key
+            != self
+                .account_key
+                .default_subaddress()
+                .spend_public_key()
+                .to_bytes()
+        {
+            return Err(Error::StateAccountMismatch);
+        }
+
+        let mut rng_set = UserRngSet::new();
+        for stored in snapshot.rngs {
+            rng_set.insert_rng(stored.nonce, VersionedKexRng::try_from(stored.rng)?);
+        }
+        rng_set.set_highest_processed_block_count(snapshot.highest_processed_block_count);
+        rng_set.set_next_start_from_user_event_id(snapshot.next_start_from_user_event_id);
+
+        let owned_tx_outs = snapshot
+            .owned_tx_outs
+            .into_iter()
+            .map(|stored| {
+                let status = match stored.spent_at {
+                    Some(block_index) => KeyImageStatus::SpentAt(block_index),
+                    None => KeyImageStatus::NotSpent(BlockCount::from(stored.not_spent_as_of)),
+                };
+                let otxo = OwnedTxOut {
+                    global_index: stored.global_index,
+                    block_index: stored.block_index,
+                    tx_out: stored.tx_out,
+                    amount: Amount::new(stored.value, TokenId::from(stored.token_id)),
+                    subaddress_index: stored.subaddress_index,
+                    key_image: stored.key_image,
+                    status,
+                };
+                (otxo.global_index, otxo)
+            })
+            .collect();
+
+        self.rng_set = rng_set;
+        self.owned_tx_outs = owned_tx_outs;
+        self.key_image_data_completeness = BlockCount::from(snapshot.key_image_data_completeness);
+        self.latest_global_txo_count = snapshot.latest_global_txo_count;
+        self.latest_block_version = snapshot.latest_block_version;
+        self.missed_block_ranges = snapshot.missed_block_ranges;
+
+        log::debug!(
+            self.logger,
+            "Restored {} txos and {} rngs from snapshot, num_blocks = {}",
+            self.owned_tx_outs.len(),
+            self.rng_set.get_rngs().len(),
+            self.get_num_blocks()
+        );
+        Ok(())
+    }
+
     /// Get debug balance information (to help debug a wrong balance
     /// computation)
     pub fn debug_balance(&mut self) -> String {
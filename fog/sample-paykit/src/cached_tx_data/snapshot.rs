@@ -0,0 +1,305 @@
+// Copyright (c) 2018-2022 The MobileCoin Foundation
+
+//! A serializable snapshot of the CachedTxData, which allows a client to be
+//! restarted without rescanning all of the fog view history.
+
+use crate::error::{Error, Result};
+use mc_fog_kex_rng::StoredRng;
+use mc_fog_types::common::BlockRange;
+use mc_transaction_core::{ring_signature::KeyImage, tx::TxOut};
+use prost::Message;
+use std::{
+    fs::{self, OpenOptions},
+    io::Write,
+    path::Path,
+};
+
+#[cfg(unix)]
+use std::os::unix::fs::OpenOptionsExt;
+
+/// The version of the snapshot format written by this revision.
+///
+/// This must be incremented whenever the meaning of any of the fields changes,
+/// so that older snapshots are discarded rather than misinterpreted.
+pub const CACHED_TX_DATA_SNAPSHOT_VERSION: u32 = 1;
+
+/// The persisted state of a CachedTxData object.
+///
+/// Derived data, like the subaddress spend public key map, and transient data,
+/// like the last memo, are not part of the snapshot.
+#[derive(Clone, Eq, PartialEq, Message)]
+pub struct CachedTxDataSnapshot {
+    /// The snapshot format version, see CACHED_TX_DATA_SNAPSHOT_VERSION
+    #[prost(uint32, tag = "1")]
+    pub version: u32,
+
+    /// The default subaddress spend public key of the account which this
+    /// snapshot belongs to. This is used to refuse snapshots made for a
+    /// different account.
+    #[prost(bytes, tag = "2")]
+    pub account_spend_public_key: Vec<u8>,
+
+    /// The kex rngs of the fog view UserRngSet, with their current counters
+    #[prost(message, repeated, tag = "3")]
+    pub rngs: Vec<StoredUserRng>,
+
+    /// The last highest_processed_block_count reported by fog view
+    #[prost(uint64, tag = "4")]
+    pub highest_processed_block_count: u64,
+
+    /// The last next_start_from_user_event_id reported by fog view
+    #[prost(int64, tag = "5")]
+    pub next_start_from_user_event_id: i64,
+
+    /// The TxOuts which we have found and which belong to us
+    #[prost(message, repeated, tag = "6")]
+    pub owned_tx_outs: Vec<StoredOwnedTxOut>,
+
+    /// How fresh our information about unspent key images is
+    #[prost(uint64, tag = "7")]
+    pub key_image_data_completeness: u64,
+
+    /// The latest global txo count that we have heard about
+    #[prost(uint64, tag = "8")]
+    pub latest_global_txo_count: u64,
+
+    /// The latest block version that we have heard about
+    #[prost(uint32, tag = "9")]
+    pub latest_block_version: u32,
+
+    /// Block ranges reported missed by fog view, which were not yet downloaded
+    #[prost(message, repeated, tag = "10")]
+    pub missed_block_ranges: Vec<BlockRange>,
+}
+
+/// A kex rng from the UserRngSet, together with the nonce it is keyed by.
+#[derive(Clone, Eq, PartialEq, Message)]
+pub struct StoredUserRng {
+    /// The nonce (kex rng public key) identifying this rng
+    #[prost(bytes, tag = "1")]
+    pub nonce: Vec<u8>,
+
+    /// The serialized rng
+    #[prost(message, required, tag = "2")]
+    pub rng: StoredRng,
+}
+
+/// A stored OwnedTxOut.
+#[derive(Clone, Eq, PartialEq, Message)]
+pub struct StoredOwnedTxOut {
+    /// The global index of this tx_out
+    #[prost(uint64, tag = "1")]
+    pub global_index: u64,
+
+    /// The block in which this tx_out appeared
+    #[prost(uint64, tag = "2")]
+    pub block_index: u64,
+
+    /// The recovered tx_out
+    #[prost(message, required, tag = "3")]
+    pub tx_out: TxOut,
+
+    /// The value of the tx_out
+    #[prost(uint64, tag = "4")]
+    pub value: u64,
+
+    /// The token id of the tx_out
+    #[prost(uint64, tag = "5")]
+    pub token_id: u64,
+
+    /// The subaddress index this tx_out was sent to
+    #[prost(uint64, tag = "6")]
+    pub subaddress_index: u64,
+
+    /// The key image of the tx_out
+    #[prost(message, required, tag = "7")]
+    pub key_image: KeyImage,
+
+    /// The block index at which the key image was spent, if it is known to be
+    /// spent.
+    #[prost(uint64, optional, tag = "8")]
+    pub spent_at: Option<u64>,
+
+    /// The block count as of which the key image is known not to be spent.
+    /// Only meaningful if spent_at is not set.
+    #[prost(uint64, tag = "9")]
+    pub not_spent_as_of: u64,
+}
+
+impl CachedTxDataSnapshot {
+    /// Load a snapshot from a file.
+    ///
+    /// Returns Ok(None) if the file does not exist.
+    pub fn load(path: &Path) -> Result<Option<Self>> {
+        let bytes = match fs::read(path) {
+            Ok(bytes) => bytes,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(Error::StateFile(err)),
+        };
+        let snapshot: Self = mc_util_serial::decode(&bytes)?;
+        if snapshot.version != CACHED_TX_DATA_SNAPSHOT_VERSION {
+            return Err(Error::StateVersion(snapshot.version));
+        }
+        Ok(Some(snapshot))
+    }
+
+    /// Save a snapshot to a file.
+    ///
+    /// The snapshot is first written and synced to a temporary file next to
+    /// the target path, which is then renamed over the target path, so that
+    /// a crash while saving never leaves a partially written snapshot behind.
+    /// The file is only readable by the current user, since the snapshot
+    /// reveals which TxOuts belong to the account.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let mut tmp_path = path.as_os_str().to_owned();
+        tmp_path.push(".tmp");
+
+        let mut options = OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        options.mode(0o600);
+
+        let mut file = options.open(&tmp_path).map_err(Error::StateFile)?;
+        file.write_all(&mc_util_serial::encode(self))
+            .and_then(|_| file.sync_all())
+            .map_err(Error::StateFile)?;
+        drop(file);
+
+        fs::rename(&tmp_path, path).map_err(Error::StateFile)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cached_tx_data::{CachedTxData, KeyImageStatus};
+    use mc_account_keys::AccountKey;
+    use mc_common::logger::{test_with_logger, Logger};
+    use mc_crypto_keys::RistrettoPublic;
+    use mc_fog_kex_rng::{BufferedRng, KexRngPubkey, NewFromKex, VersionedKexRng};
+    use mc_fog_types::{
+        view::{FogTxOut, FogTxOutMetadata, TxOutRecord},
+        BlockCount,
+    };
+    use mc_transaction_core::{tokens::Mob, Amount, BlockVersion, Token};
+    use mc_transaction_core_test_utils::get_outputs;
+    use mc_util_from_random::FromRandom;
+    use mc_util_test_helper::get_seeded_rng;
+    use tempdir::TempDir;
+
+    // Make a CachedTxData with a few owned txos, one of them spent, and an rng
+    // which has been advanced.
+    fn make_cached_tx_data(account_key: &AccountKey, logger: Logger) -> CachedTxData {
+        let mut rng = get_seeded_rng();
+        let mut tx_data = CachedTxData::new(account_key.clone(), vec![], logger);
+
+        let recipient_and_amount = (1..=3)
+            .map(|i| {
+                (
+                    account_key.default_subaddress(),
+                    Amount::new(i * 100, Mob::ID),
+                )
+            })
+            .collect::<Vec<_>>();
+        let outputs = get_outputs(BlockVersion::MAX, &recipient_and_amount, &mut rng);
+        let records = outputs.iter().enumerate().map(|(idx, tx_out)| {
+            TxOutRecord::new(
+                FogTxOut::from(tx_out),
+                FogTxOutMetadata {
+                    global_index: 10 + idx as u64,
+                    block_index: idx as u64,
+                    timestamp: 0,
+                },
+            )
+        });
+        assert!(tx_data.consume_new_txo_records(records).is_empty());
+
+        tx_data.owned_tx_outs.get_mut(&10).unwrap().status = KeyImageStatus::SpentAt(2);
+        for global_index in [11, 12] {
+            tx_data.owned_tx_outs.get_mut(&global_index).unwrap().status =
+                KeyImageStatus::NotSpent(BlockCount::from(3));
+        }
+        tx_data.key_image_data_completeness = BlockCount::from(3);
+
+        let pubkey = KexRngPubkey {
+            public_key: RistrettoPublic::from_random(&mut rng).to_bytes().to_vec(),
+            version: 0,
+        };
+        let mut kex_rng =
+            VersionedKexRng::try_from_kex_pubkey(&pubkey, account_key.view_private_key()).unwrap();
+        kex_rng.advance();
+        kex_rng.advance();
+        tx_data.rng_set.insert_rng(pubkey.public_key, kex_rng);
+        tx_data.rng_set.set_highest_processed_block_count(3);
+        tx_data.rng_set.set_next_start_from_user_event_id(42);
+
+        tx_data
+    }
+
+    #[test_with_logger]
+    fn snapshot_round_trip_through_file(logger: Logger) {
+        let mut rng = get_seeded_rng();
+        let account_key = AccountKey::random(&mut rng);
+        let tx_data = make_cached_tx_data(&account_key, logger.clone());
+
+        let (balance, num_blocks) = tx_data.get_balance();
+        assert_eq!(balance.get(&Mob::ID), Some(&500));
+        assert_eq!(num_blocks, BlockCount::from(3));
+
+        let dir = TempDir::new("cached_tx_data_snapshot").unwrap();
+        let path = dir.path().join("state");
+        assert_eq!(CachedTxDataSnapshot::load(&path).unwrap(), None);
+
+        let snapshot = tx_data.snapshot();
+        snapshot.save(&path).unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+        let loaded = CachedTxDataSnapshot::load(&path).unwrap().unwrap();
+        assert_eq!(loaded, snapshot);
+
+        let mut restored = CachedTxData::new(account_key, vec![], logger);
+        restored.restore_snapshot(loaded).unwrap();
+
+        assert_eq!(restored.get_balance(), (balance, num_blocks));
+        assert_eq!(restored.snapshot(), snapshot);
+        assert_eq!(restored.rng_set.get_next_start_from_user_event_id(), 42);
+        for (nonce, kex_rng) in tx_data.rng_set.get_rngs() {
+            assert_eq!(restored.rng_set.get_rngs()[nonce].peek(), kex_rng.peek());
+        }
+    }
+
+    #[test_with_logger]
+    fn snapshot_rejects_other_account_and_version(logger: Logger) {
+        let mut rng = get_seeded_rng();
+        let account_key = AccountKey::random(&mut rng);
+        let tx_data = make_cached_tx_data(&account_key, logger.clone());
+        let snapshot = tx_data.snapshot();
+
+        let mut other = CachedTxData::new(AccountKey::random(&mut rng), vec![], logger.clone());
+        assert!(matches!(
+            other.restore_snapshot(snapshot.clone()),
+            Err(Error::StateAccountMismatch)
+        ));
+        assert_eq!(other.get_num_blocks(), BlockCount::from(0));
+
+        let mut future = snapshot;
+        future.version = CACHED_TX_DATA_SNAPSHOT_VERSION + 1;
+        let dir = TempDir::new("cached_tx_data_snapshot").unwrap();
+        let path = dir.path().join("state");
+        future.save(&path).unwrap();
+        assert!(matches!(
+            CachedTxDataSnapshot::load(&path),
+            Err(Error::StateVersion(_))
+        ));
+
+        let mut same = CachedTxData::new(account_key, vec![], logger);
+        assert!(matches!(
+            same.restore_snapshot(future),
+            Err(Error::StateVersion(_))
+        ));
+    }
+}
@@ -3,7 +3,7 @@
 //! MobileCoin Client Object
 
 use crate::{
-    cached_tx_data::{CachedTxData, OwnedTxOut},
+    cached_tx_data::{CachedTxData, CachedTxDataSnapshot, OwnedTxOut},
     error::{Error, Result},
     BlockInfo, MemoHandlerError, TransactionStatus,
 };
@@ -38,7 +38,10 @@ use mc_transaction_std::{
 use mc_util_telemetry::{block_span_builder, telemetry_static_key, tracer, Key, Span};
 use mc_util_uri::{ConnectionUri, FogUri};
 use rand::Rng;
-use std::collections::{HashMap, HashSet};
+use std::{
+    collections::{HashMap, HashSet},
+    path::PathBuf,
+};
 
 /// Default number of blocks used for calculating transaction tombstone block
 /// number. See `new_tx_block_attempts` below.
@@ -62,6 +65,10 @@ pub struct Client {
     account_key: AccountKey,
     tx_data: CachedTxData,
 
+    /// Path at which a snapshot of tx_data is persisted after each balance
+    /// check, if any.
+    state_path: Option<PathBuf>,
+
     /// Number of blocks for which to try and get the new transaction to be
     /// included in the ledger. This value is used to calculate the
     /// tombstone block when generating a new transaction.
@@ -84,9 +91,34 @@ impl Client {
         ring_size: usize,
         account_key: AccountKey,
         address_book: Vec<PublicAddress>,
+        state_path: Option<PathBuf>,
         logger: Logger,
     ) -> Self {
-        let tx_data = CachedTxData::new(account_key.clone(), address_book, logger.clone());
+        let mut tx_data = CachedTxData::new(account_key.clone(), address_book, logger.clone());
+
+        if let Some(path) = state_path.as_ref() {
+            match CachedTxDataSnapshot::load(path)
+                .and_then(|maybe_snapshot| {
+                    maybe_snapshot
+                        .map(|snapshot| tx_data.restore_snapshot(snapshot))
+                        .transpose()
+                })
+            {
+                Ok(Some(())) => log::info!(
+                    logger,
+                    "Restored wallet state from {:?}, num_blocks = {}",
+                    path,
+                    tx_data.get_num_blocks()
+                ),
+                Ok(None) => log::info!(logger, "No wallet state found at {:?}", path),
+                Err(err) => log::warn!(
+                    logger,
+                    "Could not restore wallet state from {:?}, fog view history will be rescanned: {}",
+                    path,
+                    err
+                ),
+            }
+        }
 
         Client {
             consensus_service_conn,
@@ -100,6 +132,7 @@ impl Client {
             ring_size,
             account_key,
             tx_data,
+            state_path,
             new_tx_block_attempts: DEFAULT_NEW_TX_BLOCK_ATTEMPTS,
             logger,
         }
@@ -141,9 +174,23 @@ impl Client {
             &mut self.fog_key_image,
             &mut self.fog_block,
         )?;
+        // The balance is still correct if the state can't be saved, the next
+        // restart just has to poll fog from further back.
+        if let Err(err) = self.save_state() {
+            log::warn!(self.logger, "Could not save wallet state: {}", err);
+        }
         Ok(self.compute_balance())
     }
 
+    /// Persist a snapshot of the cached tx data to the state path, if one was
+    /// configured. This happens automatically after each balance check.
+    pub fn save_state(&self) -> Result<()> {
+        if let Some(path) = self.state_path.as_ref() {
+            self.tx_data.snapshot().save(path)?;
+        }
+        Ok(())
+    }
+
     /// Compute the balance based on locally available data.
     /// Does NOT make any new network calls.
     ///
@@ -19,7 +19,7 @@ use mc_sgx_css::Signature;
 use mc_transaction_core::constants::RING_SIZE;
 use mc_util_grpc::GrpcRetryConfig;
 use mc_util_uri::{ConnectionUri, ConsensusClientUri};
-use std::sync::Arc;
+use std::{path::PathBuf, sync::Arc};
 
 /// Builder object which helps to initialize the sample paykit
 pub struct ClientBuilder {
@@ -47,6 +47,9 @@ pub struct ClientBuilder {
     fog_ingest_sigstruct: Option<Signature>,
     fog_ledger_sigstruct: Option<Signature>,
     fog_view_sigstruct: Option<Signature>,
+
+    // Optional path at which wallet state is persisted
+    state_path: Option<PathBuf>,
 }
 
 impl ClientBuilder {
@@ -73,6 +76,7 @@ impl ClientBuilder {
             fog_ingest_sigstruct: None,
             fog_ledger_sigstruct: None,
             fog_view_sigstruct: None,
+            state_path: None,
         }
     }
 
@@ -125,6 +129,18 @@ impl ClientBuilder {
         self
     }
 
+    /// Sets a path at which the client persists its cached tx data.
+    ///
+    /// If a snapshot exists at this path when the client is built, it is
+    /// restored, and the client resumes polling fog from where it stopped
+    /// rather than rescanning all of the fog view history. The snapshot is
+    /// atomically replaced after each balance check.
+    #[must_use]
+    pub fn state_path(mut self, path: Option<PathBuf>) -> Self {
+        self.state_path = path;
+        self
+    }
+
     /// Create the client
     pub fn build(self) -> Client {
         let grpc_env = Arc::new(
@@ -184,6 +200,7 @@ impl ClientBuilder {
             self.ring_size,
             self.key,
             self.address_book,
+            self.state_path,
             self.logger,
         )
     }
@@ -7,6 +7,7 @@ use mc_connection::Error as ConnectionError;
 use mc_consensus_api::ConversionError;
 use mc_crypto_keys::KeyError;
 use mc_fog_enclave_connection::Error as EnclaveConnectionError;
+use mc_fog_kex_rng::Error as KexRngError;
 use mc_fog_ledger_connection::{Error as LedgerConnectionError, KeyImageQueryError};
 use mc_fog_report_connection::Error as FogResolutionError;
 use mc_fog_types::view::FogTxOutError;
@@ -16,6 +17,7 @@ use mc_transaction_core::{
     SignedContingentInputError,
 };
 use mc_transaction_std::{SignedContingentInputBuilderError, TxBuilderError};
+use mc_util_serial::DecodeError;
 use mc_util_uri::UriParseError;
 use std::result::Result as StdResult;
 
@@ -135,6 +137,21 @@ pub enum Error {
 
     /// Fog merkle proof: {0}
     FogMerkleProof(String),
+
+    /// Wallet state file: {0}
+    StateFile(std::io::Error),
+
+    /// Could not decode wallet state: {0}
+    StateDecode(DecodeError),
+
+    /// Unsupported wallet state version: {0}
+    StateVersion(u32),
+
+    /// Wallet state belongs to a different account
+    StateAccountMismatch,
+
+    /// Could not restore fog view rng from wallet state: {0}
+    StateRng(KexRngError),
 }
 
 impl From<ConnectionError> for Error {
@@ -217,3 +234,15 @@ impl From<BlockVersionError> for Error {
         Self::BlockVersion(src)
     }
 }
+
+impl From<DecodeError> for Error {
+    fn from(src: DecodeError) -> Self {
+        Self::StateDecode(src)
+    }
+}
+
+impl From<KexRngError> for Error {
+    fn from(src: KexRngError) -> Self {
+        Self::StateRng(src)
+    }
+}
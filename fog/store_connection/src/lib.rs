@@ -0,0 +1,321 @@
+// Copyright (c) 2018-2022 The MobileCoin Foundation
+
+//! The connections of a fog router to the stores it fans client requests out
+//! to, over attested channels between the router's enclave and the stores'
+//! enclaves. Fog view routers connect to fog view stores this way, and ledger
+//! routers to key image stores.
+
+#![deny(missing_docs)]
+
+use displaydoc::Display;
+use grpcio::{
+    CallOption, Channel, ChannelBuilder, ClientUnaryReceiver, Environment, Error as GrpcError,
+    MetadataBuilder,
+};
+use mc_attest_api::attest::{AuthMessage, Message};
+use mc_attest_enclave_api::{EnclaveMessage, PeerAuthRequest, PeerAuthResponse, PeerSession};
+use mc_common::{
+    logger::{log, Logger},
+    ResponderId,
+};
+use mc_util_grpc::{BasicCredentials, ConnectionUriGrpcioChannel, CHAIN_ID_GRPC_HEADER};
+use mc_util_uri::{ConnectionUri, UriConversionError};
+use std::{
+    fmt::{Debug, Display},
+    sync::{Arc, Mutex},
+};
+
+/// The calls a router makes to its enclave and to the gRPC API of a kind of
+/// store.
+pub trait StoreApi: 'static {
+    /// The uri of a store
+    type Uri: ConnectionUri;
+
+    /// The router's enclave
+    type Enclave: Clone + Send + Sync;
+
+    /// An error of the router's enclave
+    type EnclaveError: Debug + Display;
+
+    /// The gRPC client of a store
+    type Client: Send + Sync;
+
+    /// The response of a store to a query
+    type QueryResponse;
+
+    /// What the stores are called in logs
+    const NAME: &'static str;
+
+    /// Create the gRPC client of a store
+    fn client(channel: Channel) -> Self::Client;
+
+    /// Begin attesting the store with the given responder id
+    fn init(
+        enclave: &Self::Enclave,
+        store_id: ResponderId,
+    ) -> Result<PeerAuthRequest, Self::EnclaveError>;
+
+    /// Complete attesting the store with the given responder id, which
+    /// accepted our auth request
+    fn connect(
+        enclave: &Self::Enclave,
+        store_id: ResponderId,
+        auth_response: PeerAuthResponse,
+    ) -> Result<PeerSession, Self::EnclaveError>;
+
+    /// Destroy an attested channel to a store
+    fn close(enclave: &Self::Enclave, session: PeerSession) -> Result<(), Self::EnclaveError>;
+
+    /// Send an auth request to a store
+    fn auth(
+        client: &Self::Client,
+        request: &AuthMessage,
+        opt: CallOption,
+    ) -> Result<AuthMessage, GrpcError>;
+
+    /// Send a query, encrypted for the store's enclave, to a store, without
+    /// waiting for the response
+    fn query_async(
+        client: &Self::Client,
+        request: &Message,
+        opt: CallOption,
+    ) -> Result<ClientUnaryReceiver<Self::QueryResponse>, GrpcError>;
+}
+
+/// An error of a connection to a store
+#[derive(Debug, Display)]
+pub enum StoreConnectionError<EE: Debug + Display> {
+    /// Enclave error: {0}
+    Enclave(EE),
+    /// Invalid store uri: {0}
+    Uri(UriConversionError),
+    /// gRPC error: {0}
+    Grpc(GrpcError),
+}
+
+impl<EE: Debug + Display> From<UriConversionError> for StoreConnectionError<EE> {
+    fn from(src: UriConversionError) -> Self {
+        Self::Uri(src)
+    }
+}
+
+impl<EE: Debug + Display> From<GrpcError> for StoreConnectionError<EE> {
+    fn from(src: GrpcError) -> Self {
+        Self::Grpc(src)
+    }
+}
+
+/// A connection from a router to a store.
+///
+/// Each request uses an attested channel to the store's enclave of its own,
+/// so that requests can run concurrently. Channels are kept for later
+/// requests once a request is done with them, and new ones are attested when
+/// none is available.
+pub struct AttestedStoreConnection<S: StoreApi> {
+    /// The chain id of the network
+    chain_id: String,
+
+    /// The uri of the store
+    uri: S::Uri,
+
+    /// The responder id of the store
+    responder_id: ResponderId,
+
+    /// The grpc client of the store
+    grpc_client: S::Client,
+
+    /// Credentials from the uri of the store, if any
+    creds: BasicCredentials,
+
+    /// The router's enclave
+    enclave: S::Enclave,
+
+    /// Attested channels to the store's enclave which no request is using
+    idle_sessions: Mutex<Vec<PeerSession>>,
+
+    /// Held while attesting the store, since the enclave keeps a single
+    /// pending attestation per store
+    attest_lock: Mutex<()>,
+
+    /// Logger
+    logger: Logger,
+}
+
+impl<S: StoreApi> AttestedStoreConnection<S> {
+    /// Create a new connection to the store at the given uri
+    pub fn new(
+        chain_id: String,
+        uri: S::Uri,
+        enclave: S::Enclave,
+        env: Arc<Environment>,
+        logger: Logger,
+    ) -> Result<Self, StoreConnectionError<S::EnclaveError>> {
+        let responder_id = uri.responder_id()?;
+        let creds = BasicCredentials::new(&uri.username(), &uri.password());
+        let ch = ChannelBuilder::default_channel_builder(env).connect_to_uri(&uri, &logger);
+        let grpc_client = S::client(ch);
+
+        Ok(Self {
+            chain_id,
+            uri,
+            responder_id,
+            grpc_client,
+            creds,
+            enclave,
+            idle_sessions: Default::default(),
+            attest_lock: Default::default(),
+            logger,
+        })
+    }
+
+    /// The uri of the store
+    pub fn uri(&self) -> &S::Uri {
+        &self.uri
+    }
+
+    /// Take an attested channel to the store's enclave for a request,
+    /// attesting the store if no channel is available. The channel must be
+    /// given back with `release` or `close` once the request is done.
+    pub fn session(&self) -> Result<PeerSession, StoreConnectionError<S::EnclaveError>> {
+        if let Some(session) = self.idle_sessions.lock().expect("mutex poisoned").pop() {
+            return Ok(session);
+        }
+
+        let _attesting = self.attest_lock.lock().expect("mutex poisoned");
+        let auth_request = S::init(&self.enclave, self.responder_id.clone())
+            .map_err(StoreConnectionError::Enclave)?;
+        let auth_response = S::auth(&self.grpc_client, &auth_request.into(), self.call_option())?;
+        let session = S::connect(
+            &self.enclave,
+            self.responder_id.clone(),
+            auth_response.into(),
+        )
+        .map_err(StoreConnectionError::Enclave)?;
+        log::debug!(self.logger, "Attested {} {}", S::NAME, self.uri);
+
+        Ok(session)
+    }
+
+    /// Send a query, encrypted for the store's enclave, to the store
+    pub fn query(
+        &self,
+        query: EnclaveMessage<PeerSession>,
+    ) -> Result<S::QueryResponse, StoreConnectionError<S::EnclaveError>> {
+        Ok(self.query_async(query)?.receive_sync()?)
+    }
+
+    /// Send a query, encrypted for the store's enclave, to the store, without
+    /// waiting for the response. This lets a router query all of its stores
+    /// at once.
+    pub fn query_async(
+        &self,
+        query: EnclaveMessage<PeerSession>,
+    ) -> Result<ClientUnaryReceiver<S::QueryResponse>, StoreConnectionError<S::EnclaveError>> {
+        Ok(S::query_async(
+            &self.grpc_client,
+            &query.into(),
+            self.call_option(),
+        )?)
+    }
+
+    /// Give back a channel whose messages were all decrypted by both ends, so
+    /// that later requests can use it
+    pub fn release(&self, session: PeerSession) {
+        self.idle_sessions
+            .lock()
+            .expect("mutex poisoned")
+            .push(session);
+    }
+
+    /// Close a channel which lost a message, or may have. The later messages
+    /// of the channel could not be decrypted anymore.
+    pub fn close(&self, session: PeerSession) {
+        if let Err(err) = S::close(&self.enclave, session) {
+            log::warn!(
+                self.logger,
+                "Could not close the channel to {} {}: {}",
+                S::NAME,
+                self.uri,
+                err
+            );
+        }
+    }
+
+    /// The http headers needed for credentials and the chain id
+    fn call_option(&self) -> CallOption {
+        let mut metadata_builder = MetadataBuilder::new();
+        if !self.creds.username().is_empty() && !self.creds.password().is_empty() {
+            metadata_builder
+                .add_str("Authorization", &self.creds.authorization_header())
+                .expect("Error setting authorization header");
+        }
+        if !self.chain_id.is_empty() {
+            metadata_builder
+                .add_str(CHAIN_ID_GRPC_HEADER, &self.chain_id)
+                .expect("Could not add chain-id header");
+        }
+        CallOption::default().headers(metadata_builder.build())
+    }
+}
+
+/// The attested channels of a request to each of a router's stores, in the
+/// order of the stores.
+///
+/// A request takes the channels with `take`, and must give them back with
+/// `release` once every store's response was decrypted, or when no message
+/// was encrypted with them, and with `close` otherwise.
+pub struct StoreSessions<'a, S: StoreApi> {
+    stores: &'a [AttestedStoreConnection<S>],
+    sessions: Vec<PeerSession>,
+}
+
+impl<'a, S: StoreApi> StoreSessions<'a, S> {
+    /// Take an attested channel to each of the stores. On failure, the store
+    /// which could not be reached is returned with the error.
+    pub fn take(
+        stores: &'a [AttestedStoreConnection<S>],
+    ) -> Result<
+        Self,
+        (
+            &'a AttestedStoreConnection<S>,
+            StoreConnectionError<S::EnclaveError>,
+        ),
+    > {
+        let mut result = Self {
+            stores,
+            sessions: Vec::with_capacity(stores.len()),
+        };
+        for store in stores {
+            match store.session() {
+                Ok(session) => result.sessions.push(session),
+                Err(err) => {
+                    result.release();
+                    return Err((store, err));
+                }
+            }
+        }
+        Ok(result)
+    }
+
+    /// The channels, in the order of the stores
+    pub fn sessions(&self) -> &[PeerSession] {
+        &self.sessions
+    }
+
+    /// Give the channels back to the stores, for later requests
+    pub fn release(self) {
+        for (store, session) in self.stores.iter().zip(self.sessions) {
+            store.release(session);
+        }
+    }
+
+    /// Close the channels of a failed request. Once a message of an attested
+    /// channel is lost, the later ones cannot be decrypted anymore, so new
+    /// channels are attested for later requests. Other requests are not
+    /// affected.
+    pub fn close(self) {
+        for (store, session) in self.stores.iter().zip(self.sessions) {
+            store.close(session);
+        }
+    }
+}
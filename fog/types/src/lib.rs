@@ -22,6 +22,9 @@ pub mod ingest;
 pub mod ingest_common;
 /// Types related to fog ledger
 pub mod ledger;
+/// Sharding of fog view and fog ledger records across several stores, when a
+/// single enclave cannot hold all of them
+pub mod sharding;
 /// Types related to fog view
 pub mod view;
 
@@ -0,0 +1,120 @@
+// Copyright (c) 2018-2022 The MobileCoin Foundation
+
+use crate::common::BlockRange;
+use alloc::{format, string::String};
+use core::{fmt, str::FromStr};
+use serde::{Deserialize, Serialize};
+
+/// Shards records by block range: the store loads the records of the blocks in
+/// a given "epoch".
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct EpochShardingStrategy {
+    /// The blocks this store loads records for.
+    epoch_block_range: BlockRange,
+}
+
+impl EpochShardingStrategy {
+    /// Create a new sharding strategy for the given range of blocks.
+    pub fn new(epoch_block_range: BlockRange) -> Self {
+        Self { epoch_block_range }
+    }
+
+    /// The blocks this store loads records for.
+    pub fn block_range(&self) -> &BlockRange {
+        &self.epoch_block_range
+    }
+
+    /// Whether the records of the given block should be loaded.
+    pub fn should_process_block(&self, block_index: u64) -> bool {
+        self.epoch_block_range.contains(block_index)
+    }
+}
+
+/// By default, all the blocks are loaded.
+impl Default for EpochShardingStrategy {
+    fn default() -> Self {
+        Self::new(BlockRange::new(0, u64::MAX))
+    }
+}
+
+/// Formatted as `start-end`, where the end block is not part of the range, or
+/// `start-` when the range has no end.
+impl fmt::Display for EpochShardingStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let range = &self.epoch_block_range;
+        if range.end_block == u64::MAX {
+            write!(f, "{}-", range.start_block)
+        } else {
+            write!(f, "{}-{}", range.start_block, range.end_block)
+        }
+    }
+}
+
+impl FromStr for EpochShardingStrategy {
+    type Err = String;
+
+    fn from_str(src: &str) -> Result<Self, Self::Err> {
+        let (start, end) = src
+            .split_once('-')
+            .ok_or_else(|| format!("Expected a block range as start-end, got '{}'", src))?;
+
+        let start_block = start
+            .trim()
+            .parse::<u64>()
+            .map_err(|err| format!("Invalid start block '{}': {}", start, err))?;
+        let end_block = match end.trim() {
+            "" => u64::MAX,
+            end => end
+                .parse::<u64>()
+                .map_err(|err| format!("Invalid end block '{}': {}", end, err))?,
+        };
+
+        let epoch_block_range = BlockRange::new(start_block, end_block);
+        if !epoch_block_range.is_valid() {
+            return Err(format!("Empty block range '{}'", src));
+        }
+
+        Ok(Self::new(epoch_block_range))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+
+    #[test]
+    fn parse_and_display() {
+        let strategy = EpochShardingStrategy::from_str("10-20").unwrap();
+        assert_eq!(strategy.block_range(), &BlockRange::new(10, 20));
+        assert_eq!(strategy.to_string(), "10-20");
+
+        let strategy = EpochShardingStrategy::from_str("10-").unwrap();
+        assert_eq!(strategy.block_range(), &BlockRange::new(10, u64::MAX));
+        assert_eq!(strategy.to_string(), "10-");
+
+        assert_eq!(
+            EpochShardingStrategy::from_str("0-").unwrap(),
+            EpochShardingStrategy::default()
+        );
+
+        assert!(EpochShardingStrategy::from_str("10").is_err());
+        assert!(EpochShardingStrategy::from_str("x-20").is_err());
+        assert!(EpochShardingStrategy::from_str("10-x").is_err());
+        assert!(EpochShardingStrategy::from_str("20-10").is_err());
+        assert!(EpochShardingStrategy::from_str("10-10").is_err());
+    }
+
+    #[test]
+    fn should_process_block() {
+        let strategy = EpochShardingStrategy::from_str("10-20").unwrap();
+        assert!(!strategy.should_process_block(9));
+        assert!(strategy.should_process_block(10));
+        assert!(strategy.should_process_block(19));
+        assert!(!strategy.should_process_block(20));
+
+        let strategy = EpochShardingStrategy::default();
+        assert!(strategy.should_process_block(0));
+        assert!(strategy.should_process_block(u64::MAX - 1));
+    }
+}
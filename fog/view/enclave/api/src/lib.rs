@@ -14,7 +14,7 @@ use displaydoc::Display;
 use mc_attest_core::{Quote, Report, SgxError, TargetInfo, VerificationReport};
 use mc_attest_enclave_api::{
     ClientAuthRequest, ClientAuthResponse, ClientSession, EnclaveMessage,
-    Error as AttestEnclaveError,
+    Error as AttestEnclaveError, PeerAuthRequest, PeerAuthResponse, PeerSession,
 };
 use mc_common::ResponderId;
 use mc_crypto_keys::X25519Public;
@@ -83,6 +83,30 @@ pub enum ViewEnclaveRequest {
     Query(EnclaveMessage<ClientSession>, UntrustedQueryResponse),
     /// Request from untrusted to add encrypted tx out records to ORAM
     AddRecords(Vec<ETxOutRecord>),
+
+    // Sharding related
+    /// Begin a connection to a fog view store, as a fog view router
+    ViewStoreInit(ResponderId),
+    /// Complete a connection to a fog view store, as a fog view router
+    ViewStoreConnect(ResponderId, PeerAuthResponse),
+    /// Accept a connection from a fog view router, as a fog view store
+    RouterAccept(PeerAuthRequest),
+    /// Close a connection to a fog view router or fog view store
+    PeerClose(PeerSession),
+    /// An encrypted fog_types::view::QueryRequest from a client, to forward
+    /// to each of the given fog view stores
+    CreateStoreQueries(EnclaveMessage<ClientSession>, Vec<PeerSession>),
+    /// An encrypted fog_types::view::QueryRequest from a fog view router
+    /// Respond with an encrypted fog_types::view::QueryResponse holding the
+    /// tx out search results of this store
+    QueryStore(EnclaveMessage<PeerSession>),
+    /// The encrypted responses of fog view stores to a client's query
+    /// Respond with fog_types::view::QueryResponse for the client
+    CollateStoreResponses(
+        ClientSession,
+        Vec<EnclaveMessage<PeerSession>>,
+        UntrustedQueryResponse,
+    ),
 }
 
 /// The parameters needed to initialize the view enclave
@@ -134,6 +158,57 @@ pub trait ViewEnclaveApi: ReportableEnclave {
     /// Add encrypted tx out records from the fog recovery db to the view
     /// enclave's ORAM
     fn add_records(&self, records: Vec<ETxOutRecord>) -> Result<()>;
+
+    // ROUTER-FACING METHODS
+    //
+    // When the tx out records are sharded across several fog view stores, a
+    // fog view router accepts the client connections, and forwards their
+    // queries to the stores over a mutually attested channel between the
+    // enclaves.
+
+    /// Begin a connection to the fog view store with the given responder id
+    fn view_store_init(&self, view_store_id: ResponderId) -> Result<PeerAuthRequest>;
+
+    /// Complete a connection to the fog view store with the given responder
+    /// id, which accepted our auth request
+    fn view_store_connect(
+        &self,
+        view_store_id: ResponderId,
+        view_store_auth_response: PeerAuthResponse,
+    ) -> Result<PeerSession>;
+
+    /// Accept an inbound authentication request from a fog view router
+    fn router_accept(&self, req: PeerAuthRequest) -> Result<(PeerAuthResponse, PeerSession)>;
+
+    /// Destroy a fog view router or fog view store association
+    fn peer_close(&self, session: PeerSession) -> Result<()>;
+
+    /// Decrypt a user's QueryRequest, and encrypt it for each of the given
+    /// fog view stores. Errors of the channels to the stores are returned as
+    /// Error::StoreChannel, any other error leaves them untouched.
+    fn create_store_queries(
+        &self,
+        client_query: EnclaveMessage<ClientSession>,
+        view_stores: Vec<PeerSession>,
+    ) -> Result<Vec<EnclaveMessage<PeerSession>>>;
+
+    /// Service a QueryRequest forwarded by a fog view router, with the tx out
+    /// records of this store
+    fn query_store(
+        &self,
+        query: EnclaveMessage<PeerSession>,
+    ) -> Result<EnclaveMessage<PeerSession>>;
+
+    /// Merge the responses of the fog view stores to a user's QueryRequest
+    /// into a QueryResponse, encrypted for the user. Errors of the channels to
+    /// the stores are returned as Error::StoreChannel, any other error means
+    /// that every response was decrypted.
+    fn collate_store_responses(
+        &self,
+        client_session: ClientSession,
+        store_responses: Vec<EnclaveMessage<PeerSession>>,
+        untrusted_query_response: UntrustedQueryResponse,
+    ) -> Result<Vec<u8>>;
 }
 
 /// Helper trait which reduces boiler-plate in untrusted side
@@ -187,6 +262,10 @@ pub enum Error {
     Poison,
     /// Enclave not initialized
     EnclaveNotInitialized,
+    /// Fog view store responses do not match the query
+    StoreResponseMismatch,
+    /// Attested channel to a fog view store failed: {0}
+    StoreChannel(AttestEnclaveError),
 }
 
 impl From<SgxError> for Error {
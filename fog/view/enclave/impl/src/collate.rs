@@ -0,0 +1,195 @@
+// Copyright (c) 2018-2022 The MobileCoin Foundation
+
+//! Merges the tx out search results of several fog view stores.
+//!
+//! When the tx out records are sharded across fog view stores by block range,
+//! a fog view router forwards the same query to every store. A search key is
+//! found by at most one of the stores, and the others return padding of
+//! about the same size. The results are merged without branching on which
+//! store found the record, so that the router does not reveal it.
+//!
+//! A result that was found takes precedence over any error, and an error takes
+//! precedence over not found, so that a store failing to search its shard is
+//! not reported as the search key not being there.
+
+use aligned_cmov::subtle::{ConditionallySelectable, ConstantTimeEq};
+use alloc::vec::Vec;
+use core::cmp::max;
+use mc_fog_types::view::{TxOutSearchResult, TxOutSearchResultCode};
+use mc_fog_view_enclave_api::{Error, Result};
+
+/// Merge the tx out search results each fog view store returned for the same
+/// list of search keys.
+pub fn collate_tx_out_search_results(
+    store_results: Vec<Vec<TxOutSearchResult>>,
+) -> Result<Vec<TxOutSearchResult>> {
+    let mut store_results = store_results.into_iter();
+    let mut collated = match store_results.next() {
+        Some(results) => results,
+        None => return Ok(Vec::new()),
+    };
+
+    for results in store_results {
+        if results.len() != collated.len() {
+            return Err(Error::StoreResponseMismatch);
+        }
+
+        for (collated_result, result) in collated.iter_mut().zip(results) {
+            if collated_result.search_key != result.search_key {
+                return Err(Error::StoreResponseMismatch);
+            }
+            select_by_precedence(collated_result, result);
+        }
+    }
+
+    Ok(collated)
+}
+
+// Replace `dst` with `src` if `src` takes precedence, in constant time.
+//
+// Found takes precedence over any error code, which takes precedence over
+// NotFound.
+fn select_by_precedence(dst: &mut TxOutSearchResult, mut src: TxOutSearchResult) {
+    let found = TxOutSearchResultCode::Found as u32;
+    let not_found = TxOutSearchResultCode::NotFound as u32;
+
+    let src_found = src.result_code.ct_eq(&found);
+    let src_error = !src_found & !src.result_code.ct_eq(&not_found);
+    let dst_found = dst.result_code.ct_eq(&found);
+    let dst_not_found = dst.result_code.ct_eq(&not_found);
+    let select = (src_found & !dst_found) | (src_error & dst_not_found);
+
+    dst.result_code.conditional_assign(&src.result_code, select);
+
+    let mut len = dst.ciphertext.len() as u64;
+    len.conditional_assign(&(src.ciphertext.len() as u64), select);
+
+    let padded_len = max(dst.ciphertext.len(), src.ciphertext.len());
+    dst.ciphertext.resize(padded_len, 0);
+    src.ciphertext.resize(padded_len, 0);
+    for (dst_byte, src_byte) in dst.ciphertext.iter_mut().zip(src.ciphertext.iter()) {
+        dst_byte.conditional_assign(src_byte, select);
+    }
+    dst.ciphertext.truncate(len as usize);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    fn result(search_key: u8, code: TxOutSearchResultCode, ciphertext: &[u8]) -> TxOutSearchResult {
+        TxOutSearchResult {
+            search_key: vec![search_key; 16],
+            result_code: code as u32,
+            ciphertext: ciphertext.to_vec(),
+        }
+    }
+
+    #[test]
+    fn collate_picks_found_results() {
+        let store_results = vec![
+            vec![
+                result(1, TxOutSearchResultCode::Found, &[1, 1, 1]),
+                result(2, TxOutSearchResultCode::NotFound, &[0, 0, 0]),
+                result(3, TxOutSearchResultCode::NotFound, &[0, 0, 0]),
+            ],
+            vec![
+                result(1, TxOutSearchResultCode::NotFound, &[0, 0]),
+                result(2, TxOutSearchResultCode::Found, &[2, 2]),
+                result(3, TxOutSearchResultCode::NotFound, &[0, 0]),
+            ],
+            vec![
+                result(1, TxOutSearchResultCode::NotFound, &[0, 0, 0, 0]),
+                result(2, TxOutSearchResultCode::NotFound, &[0, 0, 0, 0]),
+                result(3, TxOutSearchResultCode::Found, &[3, 3, 3, 3]),
+            ],
+        ];
+
+        assert_eq!(
+            collate_tx_out_search_results(store_results).unwrap(),
+            vec![
+                result(1, TxOutSearchResultCode::Found, &[1, 1, 1]),
+                result(2, TxOutSearchResultCode::Found, &[2, 2]),
+                result(3, TxOutSearchResultCode::Found, &[3, 3, 3, 3]),
+            ]
+        );
+    }
+
+    #[test]
+    fn collate_keeps_first_result_when_not_found() {
+        let store_results = vec![
+            vec![result(1, TxOutSearchResultCode::NotFound, &[0, 0, 0])],
+            vec![result(1, TxOutSearchResultCode::NotFound, &[0, 0])],
+        ];
+
+        assert_eq!(
+            collate_tx_out_search_results(store_results).unwrap(),
+            vec![result(1, TxOutSearchResultCode::NotFound, &[0, 0, 0])]
+        );
+    }
+
+    #[test]
+    fn collate_prefers_errors_to_not_found() {
+        for (first, second) in [
+            (
+                TxOutSearchResultCode::InternalError,
+                TxOutSearchResultCode::NotFound,
+            ),
+            (
+                TxOutSearchResultCode::NotFound,
+                TxOutSearchResultCode::InternalError,
+            ),
+        ] {
+            let store_results = vec![
+                vec![result(1, first, &[0, 0])],
+                vec![result(1, second, &[0, 0, 0])],
+            ];
+            assert_eq!(
+                collate_tx_out_search_results(store_results).unwrap()[0].result_code,
+                TxOutSearchResultCode::InternalError as u32
+            );
+        }
+    }
+
+    #[test]
+    fn collate_prefers_found_to_errors() {
+        let store_results = vec![
+            vec![result(1, TxOutSearchResultCode::RateLimited, &[0, 0])],
+            vec![result(1, TxOutSearchResultCode::Found, &[1, 1, 1])],
+        ];
+        assert_eq!(
+            collate_tx_out_search_results(store_results).unwrap(),
+            vec![result(1, TxOutSearchResultCode::Found, &[1, 1, 1])]
+        );
+
+        let store_results = vec![
+            vec![result(1, TxOutSearchResultCode::Found, &[1, 1, 1])],
+            vec![result(1, TxOutSearchResultCode::RateLimited, &[0, 0])],
+        ];
+        assert_eq!(
+            collate_tx_out_search_results(store_results).unwrap(),
+            vec![result(1, TxOutSearchResultCode::Found, &[1, 1, 1])]
+        );
+    }
+
+    #[test]
+    fn collate_rejects_mismatched_results() {
+        let store_results = vec![
+            vec![result(1, TxOutSearchResultCode::NotFound, &[0])],
+            vec![result(2, TxOutSearchResultCode::Found, &[2])],
+        ];
+        assert!(collate_tx_out_search_results(store_results).is_err());
+
+        let store_results = vec![
+            vec![result(1, TxOutSearchResultCode::NotFound, &[0])],
+            vec![],
+        ];
+        assert!(collate_tx_out_search_results(store_results).is_err());
+    }
+
+    #[test]
+    fn collate_no_stores() {
+        assert_eq!(collate_tx_out_search_results(vec![]).unwrap(), vec![]);
+    }
+}
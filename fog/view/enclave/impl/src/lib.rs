@@ -6,18 +6,26 @@
 
 extern crate alloc;
 
+mod collate;
 mod e_tx_out_store;
+use collate::collate_tx_out_search_results;
 use e_tx_out_store::{ETxOutStore, StorageDataSize, StorageMetaSize};
 
 use alloc::vec::Vec;
 use mc_attest_core::{IasNonce, Quote, QuoteNonce, Report, TargetInfo, VerificationReport};
-use mc_attest_enclave_api::{ClientAuthRequest, ClientAuthResponse, ClientSession, EnclaveMessage};
-use mc_common::logger::{log, Logger};
+use mc_attest_enclave_api::{
+    ClientAuthRequest, ClientAuthResponse, ClientSession, EnclaveMessage, PeerAuthRequest,
+    PeerAuthResponse, PeerSession,
+};
+use mc_common::{
+    logger::{log, Logger},
+    ResponderId,
+};
 use mc_crypto_ake_enclave::{AkeEnclaveState, NullIdentity};
 use mc_crypto_keys::X25519Public;
 use mc_fog_recovery_db_iface::FogUserEvent;
 use mc_fog_types::{
-    view::{QueryRequest, QueryResponse},
+    view::{QueryRequest, QueryResponse, TxOutSearchResult},
     ETxOutRecord,
 };
 use mc_fog_view_enclave_api::{
@@ -52,6 +60,60 @@ where
             logger,
         }
     }
+
+    /// Decode a QueryRequest
+    fn decode_query_request(&self, plaintext: &[u8]) -> Result<QueryRequest> {
+        mc_util_serial::decode(plaintext).map_err(|e| {
+            log::error!(self.logger, "Could not decode user request: {}", e);
+            Error::ProstDecode
+        })
+    }
+
+    /// Look up the tx out records for the given search keys
+    fn find_records(&self, search_keys: &[Vec<u8>]) -> Result<Vec<TxOutSearchResult>> {
+        let mut lk = self.e_tx_out_store.lock()?;
+        let store = lk.as_mut().ok_or(Error::EnclaveNotInitialized)?;
+
+        Ok(search_keys
+            .iter()
+            .map(|key| store.find_record(&key[..]))
+            .collect())
+    }
+
+    /// Prepare the untrusted part of a QueryResponse
+    fn untrusted_query_response_to_query_response(
+        untrusted_query_response: UntrustedQueryResponse,
+    ) -> QueryResponse {
+        let mut missed_block_ranges = Vec::new();
+        let mut rng_records = Vec::new();
+        let mut decommissioned_ingest_invocations = Vec::new();
+
+        for event in untrusted_query_response.user_events.into_iter() {
+            match event {
+                FogUserEvent::NewRngRecord(rng_record) => rng_records.push(rng_record),
+
+                FogUserEvent::DecommissionIngestInvocation(decommissioned_ingest_invocation) => {
+                    decommissioned_ingest_invocations.push(decommissioned_ingest_invocation)
+                }
+
+                FogUserEvent::MissingBlocks(range) => missed_block_ranges.push(range),
+            }
+        }
+
+        QueryResponse {
+            highest_processed_block_count: untrusted_query_response.highest_processed_block_count,
+            highest_processed_block_signature_timestamp: untrusted_query_response
+                .highest_processed_block_signature_timestamp,
+            next_start_from_user_event_id: untrusted_query_response.next_start_from_user_event_id,
+            missed_block_ranges,
+            rng_records,
+            decommissioned_ingest_invocations,
+            tx_out_search_results: Default::default(),
+            last_known_block_count: untrusted_query_response.last_known_block_count,
+            last_known_block_cumulative_txo_count: untrusted_query_response
+                .last_known_block_cumulative_txo_count,
+        }
+    }
 }
 
 impl<OSC> ReportableEnclave for ViewEnclave<OSC>
@@ -84,7 +146,10 @@ where
         // Note: eid is passed to sgx_enclave_id crate earlier in the system, because
         // that crate is not under sgx_compat and isn't meant to be used outside of
         // enclave
-        self.ake.init(Default::default(), params.self_client_id)?;
+        // Fog view routers connect to fog view stores using the same responder id
+        // as clients.
+        self.ake
+            .init(params.self_client_id.clone(), params.self_client_id)?;
         {
             let mut lk = self.e_tx_out_store.lock()?;
             *lk = Some(ETxOutStore::new(
@@ -119,53 +184,13 @@ where
         let channel_id = msg.channel_id.clone();
         let user_plaintext = self.ake.client_decrypt(msg)?;
 
-        let req: QueryRequest = mc_util_serial::decode(&user_plaintext).map_err(|e| {
-            log::error!(self.logger, "Could not decode user request: {}", e);
-            Error::ProstDecode
-        })?;
+        let req = self.decode_query_request(&user_plaintext)?;
 
         // Prepare the untrusted part of the response.
-        let mut missed_block_ranges = Vec::new();
-        let mut rng_records = Vec::new();
-        let mut decommissioned_ingest_invocations = Vec::new();
-
-        for event in untrusted_query_response.user_events.into_iter() {
-            match event {
-                FogUserEvent::NewRngRecord(rng_record) => rng_records.push(rng_record),
-
-                FogUserEvent::DecommissionIngestInvocation(decommissioned_ingest_invocation) => {
-                    decommissioned_ingest_invocations.push(decommissioned_ingest_invocation)
-                }
-
-                FogUserEvent::MissingBlocks(range) => missed_block_ranges.push(range),
-            }
-        }
+        let mut resp = Self::untrusted_query_response_to_query_response(untrusted_query_response);
 
-        let mut resp = QueryResponse {
-            highest_processed_block_count: untrusted_query_response.highest_processed_block_count,
-            highest_processed_block_signature_timestamp: untrusted_query_response
-                .highest_processed_block_signature_timestamp,
-            next_start_from_user_event_id: untrusted_query_response.next_start_from_user_event_id,
-            missed_block_ranges,
-            rng_records,
-            decommissioned_ingest_invocations,
-            tx_out_search_results: Default::default(),
-            last_known_block_count: untrusted_query_response.last_known_block_count,
-            last_known_block_cumulative_txo_count: untrusted_query_response
-                .last_known_block_cumulative_txo_count,
-        };
-
-        // Do the txos part, scope lock of e_tx_out_store
-        {
-            let mut lk = self.e_tx_out_store.lock()?;
-            let store = lk.as_mut().ok_or(Error::EnclaveNotInitialized)?;
-
-            resp.tx_out_search_results = req
-                .get_txos
-                .iter()
-                .map(|key| store.find_record(&key[..]))
-                .collect();
-        }
+        // Do the txos part
+        resp.tx_out_search_results = self.find_records(&req.get_txos)?;
 
         let response_plaintext_bytes = mc_util_serial::encode(&resp);
 
@@ -184,4 +209,108 @@ where
         }
         Ok(())
     }
+
+    // Router-facing
+
+    fn view_store_init(&self, view_store_id: ResponderId) -> Result<PeerAuthRequest> {
+        Ok(self.ake.peer_init(&view_store_id)?)
+    }
+
+    fn view_store_connect(
+        &self,
+        view_store_id: ResponderId,
+        view_store_auth_response: PeerAuthResponse,
+    ) -> Result<PeerSession> {
+        let (session, _verification_report) = self
+            .ake
+            .peer_connect(&view_store_id, view_store_auth_response)?;
+        Ok(session)
+    }
+
+    fn router_accept(&self, req: PeerAuthRequest) -> Result<(PeerAuthResponse, PeerSession)> {
+        Ok(self.ake.peer_accept(req)?)
+    }
+
+    fn peer_close(&self, session: PeerSession) -> Result<()> {
+        self.ake.peer_close(&session)?;
+        Ok(())
+    }
+
+    fn create_store_queries(
+        &self,
+        client_query: EnclaveMessage<ClientSession>,
+        view_stores: Vec<PeerSession>,
+    ) -> Result<Vec<EnclaveMessage<PeerSession>>> {
+        let user_plaintext = self.ake.client_decrypt(client_query)?;
+
+        // Make sure we only forward well-formed requests.
+        self.decode_query_request(&user_plaintext)?;
+
+        view_stores
+            .iter()
+            .map(|view_store| {
+                self.ake
+                    .peer_encrypt(view_store, &[], &user_plaintext)
+                    .map_err(Error::StoreChannel)
+            })
+            .collect()
+    }
+
+    fn query_store(
+        &self,
+        query: EnclaveMessage<PeerSession>,
+    ) -> Result<EnclaveMessage<PeerSession>> {
+        let channel_id = query.channel_id.clone();
+        let router_plaintext = self.ake.peer_decrypt(query)?;
+
+        let req = self.decode_query_request(&router_plaintext)?;
+
+        // The router fills in everything but the txos part.
+        let resp = QueryResponse {
+            tx_out_search_results: self.find_records(&req.get_txos)?,
+            ..Default::default()
+        };
+
+        let response_plaintext_bytes = mc_util_serial::encode(&resp);
+
+        Ok(self
+            .ake
+            .peer_encrypt(&channel_id, &[], &response_plaintext_bytes)?)
+    }
+
+    fn collate_store_responses(
+        &self,
+        client_session: ClientSession,
+        store_responses: Vec<EnclaveMessage<PeerSession>>,
+        untrusted_query_response: UntrustedQueryResponse,
+    ) -> Result<Vec<u8>> {
+        // Decrypt every response before decoding any, so that the channels to
+        // the stores are left in a usable state if a response is malformed.
+        let store_plaintexts = store_responses
+            .into_iter()
+            .map(|store_response| {
+                self.ake
+                    .peer_decrypt(store_response)
+                    .map_err(Error::StoreChannel)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let store_results = store_plaintexts
+            .iter()
+            .map(|store_plaintext| {
+                let store_resp: QueryResponse = mc_util_serial::decode(store_plaintext)?;
+                Ok(store_resp.tx_out_search_results)
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut resp = Self::untrusted_query_response_to_query_response(untrusted_query_response);
+        resp.tx_out_search_results = collate_tx_out_search_results(store_results)?;
+
+        let response_plaintext_bytes = mc_util_serial::encode(&resp);
+
+        let response = self
+            .ake
+            .client_encrypt(&client_session, &[], &response_plaintext_bytes)?;
+
+        Ok(response.data)
+    }
 }
@@ -120,6 +120,27 @@ pub fn ecall_dispatcher(inbuf: &[u8]) -> Result<Vec<u8>, sgx_status_t> {
             serialize(&ENCLAVE.query(req, untrusted_query_response))
         }
         ViewEnclaveRequest::AddRecords(records) => serialize(&ENCLAVE.add_records(records)),
+        ViewEnclaveRequest::ViewStoreInit(view_store_id) => {
+            serialize(&ENCLAVE.view_store_init(view_store_id))
+        }
+        ViewEnclaveRequest::ViewStoreConnect(view_store_id, view_store_auth_response) => {
+            serialize(&ENCLAVE.view_store_connect(view_store_id, view_store_auth_response))
+        }
+        ViewEnclaveRequest::RouterAccept(req) => serialize(&ENCLAVE.router_accept(req)),
+        ViewEnclaveRequest::PeerClose(session) => serialize(&ENCLAVE.peer_close(session)),
+        ViewEnclaveRequest::CreateStoreQueries(client_query, view_stores) => {
+            serialize(&ENCLAVE.create_store_queries(client_query, view_stores))
+        }
+        ViewEnclaveRequest::QueryStore(query) => serialize(&ENCLAVE.query_store(query)),
+        ViewEnclaveRequest::CollateStoreResponses(
+            client_session,
+            store_responses,
+            untrusted_query_response,
+        ) => serialize(&ENCLAVE.collate_store_responses(
+            client_session,
+            store_responses,
+            untrusted_query_response,
+        )),
     }
     .or(Err(sgx_status_t::SGX_ERROR_UNEXPECTED))
 }
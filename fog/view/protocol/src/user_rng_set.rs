@@ -40,6 +40,13 @@ impl UserRngSet {
         &self.rngs
     }
 
+    // Insert an rng which was previously obtained from `get_rngs`, e.g. when
+    // restoring a UserRngSet from storage. Any existing rng for this nonce is
+    // replaced.
+    pub fn insert_rng(&mut self, nonce: Vec<u8>, rng: VersionedKexRng) {
+        self.rngs.insert(nonce, rng);
+    }
+
     pub fn get_highest_processed_block_count(&self) -> BlockCount {
         BlockCount::from(self.highest_processed_block_count)
     }
@@ -3,7 +3,12 @@
 
 //! MobileCoin Fog View target
 use mc_attest_net::{Client, RaClient};
-use mc_common::{logger::log, time::SystemTimeProvider};
+use mc_common::{
+    logger::{log, Logger},
+    time::SystemTimeProvider,
+};
+use mc_fog_embedded_recovery_db::EmbeddedRecoveryDb;
+use mc_fog_recovery_db_iface::RecoveryDb;
 use mc_fog_sql_recovery_db::SqlRecoveryDb;
 use mc_fog_view_enclave::{SgxViewEnclave, ENCLAVE_FILE};
 use mc_fog_view_server::{config::MobileAcctViewConfig, server::ViewServer};
@@ -19,18 +24,35 @@ fn main() {
     let config = MobileAcctViewConfig::parse();
 
     let database_url = env::var("DATABASE_URL").expect("Missing DATABASE_URL environment variable");
-    let recovery_db = SqlRecoveryDb::new_from_url(
-        &database_url,
-        config.postgres_config.clone(),
-        logger.clone(),
-    )
-    .unwrap_or_else(|err| {
-        panic!(
-            "fog-view cannot connect to database '{}': {:?}",
-            database_url, err
+    if EmbeddedRecoveryDb::is_embedded_url(&database_url) {
+        let recovery_db = EmbeddedRecoveryDb::new_from_url(&database_url, logger.clone())
+            .unwrap_or_else(|err| {
+                panic!(
+                    "fog-view cannot open embedded database '{}': {:?}",
+                    database_url, err
+                )
+            });
+        run(config, recovery_db, logger)
+    } else {
+        let recovery_db = SqlRecoveryDb::new_from_url(
+            &database_url,
+            config.postgres_config.clone(),
+            logger.clone(),
         )
-    });
+        .unwrap_or_else(|err| {
+            panic!(
+                "fog-view cannot connect to database '{}': {:?}",
+                database_url, err
+            )
+        });
+        run(config, recovery_db, logger)
+    }
+}
 
+fn run<DB>(config: MobileAcctViewConfig, recovery_db: DB, logger: Logger) -> !
+where
+    DB: RecoveryDb + Clone + Send + Sync + 'static,
+{
     let _tracer = mc_util_telemetry::setup_default_tracer_with_tags(
         env!("CARGO_PKG_NAME"),
         &[(
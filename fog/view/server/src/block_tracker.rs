@@ -3,7 +3,8 @@
 use mc_common::logger::{log, Logger};
 use mc_crypto_keys::CompressedRistrettoPublic;
 use mc_fog_recovery_db_iface::IngressPublicKeyRecord;
-use std::collections::HashMap;
+use mc_fog_types::sharding::EpochShardingStrategy;
+use std::{cmp::max, collections::HashMap};
 
 /// A utility object that keeps track of which block number was processed for
 /// every known ingress key. This provides utilities such as:
@@ -12,17 +13,22 @@ use std::collections::HashMap;
 /// - Finding out what is the highest block index we have encountered so far.
 /// - Finding out for which block index have we processed data for all ingress
 ///   keys, while taking into account ingress keys reported lost
+///
+/// Only the blocks selected by the sharding strategy are tracked.
 pub struct BlockTracker {
     processed_block_per_ingress_key: HashMap<CompressedRistrettoPublic, u64>,
     last_highest_processed_block_count: u64,
+    sharding_strategy: EpochShardingStrategy,
     logger: Logger,
 }
 
 impl BlockTracker {
-    pub fn new(logger: Logger) -> Self {
+    pub fn new(logger: Logger, sharding_strategy: EpochShardingStrategy) -> Self {
         Self {
             processed_block_per_ingress_key: HashMap::default(),
-            last_highest_processed_block_count: 0,
+            // Blocks before our range are not ours to process.
+            last_highest_processed_block_count: sharding_strategy.block_range().start_block,
+            sharding_strategy,
             logger,
         }
     }
@@ -36,22 +42,28 @@ impl BlockTracker {
         let mut next_blocks = HashMap::default();
 
         for rec in ingress_key_records {
-            if let Some(last_processed_block) = self.processed_block_per_ingress_key.get(&rec.key) {
+            let next_block = if let Some(last_processed_block) =
+                self.processed_block_per_ingress_key.get(&rec.key)
+            {
                 // A block has previously been processed for this ingress key. See if the
                 // next one can be provided by it, and if so add it to the list of next blocks
                 // we would like to process.
-                let next_block = last_processed_block + 1;
-                if rec.covers_block_index(next_block) {
-                    next_blocks.insert(rec.key, next_block);
-                }
+                last_processed_block + 1
             } else {
                 // No block has been processed for this ingress key, so the next block is the
-                // first one, assuming it can actually be provided by the ingress key.
-                // (It will not be able to provide the start block if it got lost
+                // first one in our range, assuming it can actually be provided by the ingress
+                // key. (It will not be able to provide the start block if it got lost
                 // immediately after starting before scanning any blocks)
-                if rec.covers_block_index(rec.status.start_block) {
-                    next_blocks.insert(rec.key, rec.status.start_block);
-                }
+                max(
+                    rec.status.start_block,
+                    self.sharding_strategy.block_range().start_block,
+                )
+            };
+
+            if self.sharding_strategy.should_process_block(next_block)
+                && rec.covers_block_index(next_block)
+            {
+                next_blocks.insert(rec.key, next_block);
             }
         }
 
@@ -125,6 +137,18 @@ impl BlockTracker {
                 break 'outer;
             }
 
+            // Blocks past our range are not ours to process.
+            if !self
+                .sharding_strategy
+                .should_process_block(next_block_index)
+            {
+                log::trace!(
+                    self.logger,
+                    "We processed everything up to our block range end"
+                );
+                break 'outer;
+            }
+
             // Go over all known ingress keys and check if
             // any of them need to provide this block and have not provided it
             for rec in ingress_keys {
@@ -189,13 +213,14 @@ mod tests {
     use super::*;
     use mc_common::logger::test_with_logger;
     use mc_fog_recovery_db_iface::IngressPublicKeyStatus;
+    use mc_fog_types::common::BlockRange;
     use mc_util_from_random::FromRandom;
     use rand::{rngs::StdRng, SeedableRng};
     use std::cmp::min;
 
     #[test_with_logger]
     fn next_blocks_empty(logger: Logger) {
-        let block_tracker = BlockTracker::new(logger.clone());
+        let block_tracker = BlockTracker::new(logger.clone(), Default::default());
         assert_eq!(block_tracker.next_blocks(&[]).len(), 0);
     }
 
@@ -203,7 +228,7 @@ mod tests {
     #[test_with_logger]
     fn next_blocks_single_key_hasnt_scanned(logger: Logger) {
         let mut rng: StdRng = SeedableRng::from_seed([123u8; 32]);
-        let mut block_tracker = BlockTracker::new(logger);
+        let mut block_tracker = BlockTracker::new(logger, Default::default());
         let rec = IngressPublicKeyRecord {
             key: CompressedRistrettoPublic::from_random(&mut rng),
             status: IngressPublicKeyStatus {
@@ -245,7 +270,7 @@ mod tests {
     #[test_with_logger]
     fn next_blocks_single_range_commissioned_scanned_some(logger: Logger) {
         let mut rng: StdRng = SeedableRng::from_seed([123u8; 32]);
-        let mut block_tracker = BlockTracker::new(logger);
+        let mut block_tracker = BlockTracker::new(logger, Default::default());
         let rec = IngressPublicKeyRecord {
             key: CompressedRistrettoPublic::from_random(&mut rng),
             status: IngressPublicKeyStatus {
@@ -281,7 +306,7 @@ mod tests {
     #[test_with_logger]
     fn next_blocks_single_key_retired_hasnt_scanned(logger: Logger) {
         let mut rng: StdRng = SeedableRng::from_seed([123u8; 32]);
-        let mut block_tracker = BlockTracker::new(logger);
+        let mut block_tracker = BlockTracker::new(logger, Default::default());
 
         let key = CompressedRistrettoPublic::from_random(&mut rng);
         let rec = IngressPublicKeyRecord {
@@ -333,7 +358,7 @@ mod tests {
     #[test_with_logger]
     fn next_blocks_single_range_retired_scanned_some(logger: Logger) {
         let mut rng: StdRng = SeedableRng::from_seed([123u8; 32]);
-        let mut block_tracker = BlockTracker::new(logger);
+        let mut block_tracker = BlockTracker::new(logger, Default::default());
         let last_ingested_block = 126;
         let rec = IngressPublicKeyRecord {
             key: CompressedRistrettoPublic::from_random(&mut rng),
@@ -377,7 +402,7 @@ mod tests {
     #[test_with_logger]
     fn next_blocks_single_key_lost_hasnt_scanned(logger: Logger) {
         let mut rng: StdRng = SeedableRng::from_seed([123u8; 32]);
-        let block_tracker = BlockTracker::new(logger);
+        let block_tracker = BlockTracker::new(logger, Default::default());
 
         let key = CompressedRistrettoPublic::from_random(&mut rng);
         let rec = IngressPublicKeyRecord {
@@ -403,7 +428,7 @@ mod tests {
     #[test_with_logger]
     fn next_blocks_single_key_lost_scanned_some(logger: Logger) {
         let mut rng: StdRng = SeedableRng::from_seed([123u8; 32]);
-        let mut block_tracker = BlockTracker::new(logger);
+        let mut block_tracker = BlockTracker::new(logger, Default::default());
 
         let key = CompressedRistrettoPublic::from_random(&mut rng);
         let rec = IngressPublicKeyRecord {
@@ -452,7 +477,7 @@ mod tests {
     #[test_with_logger]
     fn next_blocks_multiple_keys(logger: Logger) {
         let mut rng: StdRng = SeedableRng::from_seed([123u8; 32]);
-        let mut block_tracker = BlockTracker::new(logger);
+        let mut block_tracker = BlockTracker::new(logger, Default::default());
         let rec1 = IngressPublicKeyRecord {
             key: CompressedRistrettoPublic::from_random(&mut rng),
             status: IngressPublicKeyStatus {
@@ -509,7 +534,7 @@ mod tests {
     // highest_fully_processed_block_count behaves as expected
     #[test_with_logger]
     fn highest_fully_processed_block_count_all_empty(logger: Logger) {
-        let mut block_tracker = BlockTracker::new(logger.clone());
+        let mut block_tracker = BlockTracker::new(logger.clone(), Default::default());
 
         assert_eq!(
             block_tracker.highest_fully_processed_block_count(&[]),
@@ -520,7 +545,7 @@ mod tests {
     // Check with a key that hasn't yet processed anything.
     #[test_with_logger]
     fn highest_fully_processed_block_missing_blocks_nothing_processed1(logger: Logger) {
-        let mut block_tracker = BlockTracker::new(logger.clone());
+        let mut block_tracker = BlockTracker::new(logger.clone(), Default::default());
         let mut rng: StdRng = SeedableRng::from_seed([123u8; 32]);
         let rec = IngressPublicKeyRecord {
             key: CompressedRistrettoPublic::from_random(&mut rng),
@@ -543,7 +568,7 @@ mod tests {
     // are processed when the start block is 0.
     #[test_with_logger]
     fn highest_fully_processed_block_tracks_block_processed1(logger: Logger) {
-        let mut block_tracker = BlockTracker::new(logger.clone());
+        let mut block_tracker = BlockTracker::new(logger.clone(), Default::default());
 
         let mut rng: StdRng = SeedableRng::from_seed([123u8; 32]);
         let rec = IngressPublicKeyRecord {
@@ -578,7 +603,7 @@ mod tests {
     // when the start block is greater than zero
     #[test_with_logger]
     fn highest_fully_processed_block_tracks_block_processed2(logger: Logger) {
-        let mut block_tracker = BlockTracker::new(logger.clone());
+        let mut block_tracker = BlockTracker::new(logger.clone(), Default::default());
 
         let mut rng: StdRng = SeedableRng::from_seed([123u8; 32]);
         let rec = IngressPublicKeyRecord {
@@ -614,7 +639,7 @@ mod tests {
     // then the key is reported lost
     #[test_with_logger]
     fn highest_fully_processed_block_tracks_block_processed3(logger: Logger) {
-        let mut block_tracker = BlockTracker::new(logger.clone());
+        let mut block_tracker = BlockTracker::new(logger.clone(), Default::default());
 
         let mut rng: StdRng = SeedableRng::from_seed([123u8; 32]);
         let mut rec = IngressPublicKeyRecord {
@@ -661,7 +686,7 @@ mod tests {
     // When the slow one is marked lost, that unblocks progress.
     #[test_with_logger]
     fn highest_fully_processed_block_tracks_multiple_recs(logger: Logger) {
-        let mut block_tracker = BlockTracker::new(logger.clone());
+        let mut block_tracker = BlockTracker::new(logger.clone(), Default::default());
 
         let mut rng: StdRng = SeedableRng::from_seed([123u8; 32]);
         let mut rec1 = IngressPublicKeyRecord {
@@ -760,7 +785,7 @@ mod tests {
     // key is loaded
     #[test_with_logger]
     fn highest_fully_processed_block_tracks_multiple_recs_some_lost2(logger: Logger) {
-        let mut block_tracker = BlockTracker::new(logger.clone());
+        let mut block_tracker = BlockTracker::new(logger.clone(), Default::default());
 
         let mut rng: StdRng = SeedableRng::from_seed([123u8; 32]);
         let mut rec1 = IngressPublicKeyRecord {
@@ -876,7 +901,7 @@ mod tests {
     /// key, makes progress
     #[test_with_logger]
     fn highest_fully_processed_block_tracks_retired_key_followed_by_gap(logger: Logger) {
-        let mut block_tracker = BlockTracker::new(logger.clone());
+        let mut block_tracker = BlockTracker::new(logger.clone(), Default::default());
 
         let mut rng: StdRng = SeedableRng::from_seed([123u8; 32]);
         let mut rec1 = IngressPublicKeyRecord {
@@ -975,7 +1000,7 @@ mod tests {
     /// when everything works.
     #[test_with_logger]
     fn highest_fully_processed_block_tracks_retired_key_concurrent_with_active(logger: Logger) {
-        let mut block_tracker = BlockTracker::new(logger.clone());
+        let mut block_tracker = BlockTracker::new(logger.clone(), Default::default());
 
         let mut rng: StdRng = SeedableRng::from_seed([123u8; 32]);
         let mut rec1 = IngressPublicKeyRecord {
@@ -1123,7 +1148,7 @@ mod tests {
     fn highest_fully_processed_block_tracks_retired_key_concurrent_with_active_both_lost(
         logger: Logger,
     ) {
-        let mut block_tracker = BlockTracker::new(logger.clone());
+        let mut block_tracker = BlockTracker::new(logger.clone(), Default::default());
 
         let mut rng: StdRng = SeedableRng::from_seed([123u8; 32]);
         let mut rec1 = IngressPublicKeyRecord {
@@ -1298,7 +1323,7 @@ mod tests {
     // Highest known block count is 0 when there are no inputs.
     #[test_with_logger]
     fn highest_known_block_count_when_empty(logger: Logger) {
-        let block_tracker = BlockTracker::new(logger);
+        let block_tracker = BlockTracker::new(logger, Default::default());
 
         assert_eq!(block_tracker.highest_known_block_count(), 0);
     }
@@ -1308,7 +1333,7 @@ mod tests {
     #[test_with_logger]
     fn highest_known_block_count_tracks_processed(logger: Logger) {
         let mut rng: StdRng = SeedableRng::from_seed([123u8; 32]);
-        let mut block_tracker = BlockTracker::new(logger);
+        let mut block_tracker = BlockTracker::new(logger, Default::default());
 
         block_tracker.block_processed(CompressedRistrettoPublic::from_random(&mut rng), 100);
         assert_eq!(block_tracker.highest_known_block_count(), 101);
@@ -1319,4 +1344,93 @@ mod tests {
         block_tracker.block_processed(CompressedRistrettoPublic::from_random(&mut rng), 101);
         assert_eq!(block_tracker.highest_known_block_count(), 102);
     }
+
+    // A block tracker with a sharding strategy only asks for the blocks of its
+    // range.
+    #[test_with_logger]
+    fn next_blocks_sharded(logger: Logger) {
+        let mut rng: StdRng = SeedableRng::from_seed([123u8; 32]);
+        let mut block_tracker =
+            BlockTracker::new(logger, EpochShardingStrategy::new(BlockRange::new(10, 13)));
+        let rec1 = IngressPublicKeyRecord {
+            key: CompressedRistrettoPublic::from_random(&mut rng),
+            status: IngressPublicKeyStatus {
+                start_block: 0,
+                pubkey_expiry: 100,
+                retired: false,
+                lost: false,
+            },
+            last_scanned_block: None,
+        };
+        let rec2 = IngressPublicKeyRecord {
+            key: CompressedRistrettoPublic::from_random(&mut rng),
+            status: IngressPublicKeyStatus {
+                start_block: 11,
+                pubkey_expiry: 100,
+                retired: false,
+                lost: false,
+            },
+            last_scanned_block: None,
+        };
+        let recs = [rec1.clone(), rec2.clone()];
+
+        // Keys start at the beginning of our range, or their own start block if later.
+        assert_eq!(
+            block_tracker.next_blocks(&recs),
+            HashMap::from_iter(vec![(rec1.key, 10), (rec2.key, 11)])
+        );
+
+        block_tracker.block_processed(rec1.key, 10);
+        block_tracker.block_processed(rec1.key, 11);
+        block_tracker.block_processed(rec2.key, 11);
+        assert_eq!(
+            block_tracker.next_blocks(&recs),
+            HashMap::from_iter(vec![(rec1.key, 12), (rec2.key, 12)])
+        );
+
+        // Nothing is asked for past the end of our range.
+        block_tracker.block_processed(rec1.key, 12);
+        block_tracker.block_processed(rec2.key, 12);
+        assert_eq!(block_tracker.next_blocks(&recs), HashMap::default());
+    }
+
+    // A block tracker with a sharding strategy starts at the beginning of its
+    // range, and stops at its end.
+    #[test_with_logger]
+    fn highest_fully_processed_block_sharded(logger: Logger) {
+        let mut rng: StdRng = SeedableRng::from_seed([123u8; 32]);
+        let mut block_tracker =
+            BlockTracker::new(logger, EpochShardingStrategy::new(BlockRange::new(10, 13)));
+        let rec = IngressPublicKeyRecord {
+            key: CompressedRistrettoPublic::from_random(&mut rng),
+            status: IngressPublicKeyStatus {
+                start_block: 0,
+                pubkey_expiry: 100,
+                retired: false,
+                lost: false,
+            },
+            last_scanned_block: None,
+        };
+
+        assert_eq!(
+            block_tracker.highest_fully_processed_block_count(&[rec.clone()]),
+            (10, None)
+        );
+
+        for i in 10..13 {
+            block_tracker.block_processed(rec.key, i);
+            assert_eq!(
+                block_tracker.highest_fully_processed_block_count(&[rec.clone()]),
+                (i + 1, None)
+            );
+        }
+
+        // Even if blocks past the end of our range were somehow processed, we do not
+        // vouch for them.
+        block_tracker.block_processed(rec.key, 13);
+        assert_eq!(
+            block_tracker.highest_fully_processed_block_count(&[rec.clone()]),
+            (13, None)
+        );
+    }
 }
@@ -7,7 +7,9 @@ use clap::Parser;
 use mc_attest_core::ProviderId;
 use mc_common::ResponderId;
 use mc_fog_sql_recovery_db::SqlRecoveryDbConnectionConfig;
+use mc_fog_types::sharding::EpochShardingStrategy;
 use mc_fog_uri::FogViewUri;
+use mc_util_grpc::TokenSigningKeys;
 use mc_util_parse::parse_duration_in_seconds;
 use mc_util_uri::AdminUri;
 use serde::Serialize;
@@ -44,10 +46,9 @@ pub struct MobileAcctViewConfig {
     #[clap(long, env = "MC_ADMIN_LISTEN_URI")]
     pub admin_listen_uri: Option<AdminUri>,
 
-    /// Enables authenticating client requests using Authorization tokens using
-    /// the provided hex-encoded 32 bytes shared secret.
-    #[clap(long, parse(try_from_str = hex::FromHex::from_hex), env = "MC_CLIENT_AUTH_TOKEN_SECRET")]
-    pub client_auth_token_secret: Option<[u8; 32]>,
+    /// Shared secrets for authenticating client requests.
+    #[clap(long, env = "MC_CLIENT_AUTH_TOKEN_SECRET", help = TokenSigningKeys::HELP)]
+    pub client_auth_token_secret: Option<TokenSigningKeys>,
 
     /// Maximal client authentication token lifetime, in seconds (only relevant
     /// when --client-auth-token-secret is used. Defaults to 86400 - 24
@@ -68,6 +69,72 @@ pub struct MobileAcctViewConfig {
     #[clap(long, default_value = "1048576", env = "MC_OMAP_CAPACITY")]
     pub omap_capacity: u64,
 
+    /// The range of blocks to load ETxOutRecords for, as `start-end` (the end
+    /// block is not part of the range), or `start-` for a range with no end.
+    ///
+    /// When there are too many ETxOutRecords for a single enclave, they are
+    /// sharded by block range across several fog view servers, and a fog view
+    /// router fans client queries out to all of them. By default, all blocks
+    /// are loaded.
+    #[clap(long, default_value = "0-", env = "MC_SHARDING_STRATEGY")]
+    pub sharding_strategy: EpochShardingStrategy,
+
+    /// Postgres config
+    #[clap(flatten)]
+    pub postgres_config: SqlRecoveryDbConnectionConfig,
+}
+
+/// Configuration parameters for the MobileCoin Fog View Router, which fans
+/// client queries out to fog view stores when the ETxOutRecords are sharded
+/// across several of them.
+#[derive(Clone, Parser, Serialize)]
+#[clap(version)]
+pub struct FogViewRouterConfig {
+    /// The chain id of the network we are a part of
+    #[clap(long, env = "MC_CHAIN_ID")]
+    pub chain_id: String,
+
+    /// The ID with which to respond to client attestation requests.
+    ///
+    /// This ID needs to match the host:port clients use in their URI when
+    /// referencing this node.
+    #[clap(long, env = "MC_CLIENT_RESPONDER_ID")]
+    pub client_responder_id: ResponderId,
+
+    /// PEM-formatted keypair to send with an Attestation Request.
+    #[clap(long, env = "MC_IAS_API_KEY")]
+    pub ias_api_key: String,
+
+    /// The IAS SPID to use when getting a quote
+    #[clap(long, env = "MC_IAS_SPID")]
+    pub ias_spid: ProviderId,
+
+    /// gRPC listening URI for client requests.
+    #[clap(long, env = "MC_CLIENT_LISTEN_URI")]
+    pub client_listen_uri: FogViewUri,
+
+    /// Optional admin listening URI.
+    #[clap(long, env = "MC_ADMIN_LISTEN_URI")]
+    pub admin_listen_uri: Option<AdminUri>,
+
+    /// Shared secrets for authenticating client requests.
+    #[clap(long, env = "MC_CLIENT_AUTH_TOKEN_SECRET", help = TokenSigningKeys::HELP)]
+    pub client_auth_token_secret: Option<TokenSigningKeys>,
+
+    /// Maximal client authentication token lifetime, in seconds (only relevant
+    /// when --client-auth-token-secret is used. Defaults to 86400 - 24
+    /// hours).
+    #[clap(long, default_value = "86400", parse(try_from_str = parse_duration_in_seconds), env = "MC_CLIENT_AUTH_TOKEN_MAX_LIFETIME")]
+    pub client_auth_token_max_lifetime: Duration,
+
+    /// The fog view stores to fan client queries out to, as a comma-separated
+    /// list of URIs. Together, their sharding strategies should cover every
+    /// block.
+    ///
+    /// Credentials in a URI are used to authenticate to that store.
+    #[clap(long, use_value_delimiter = true, env = "MC_VIEW_STORE_URIS")]
+    pub view_store_uris: Vec<FogViewUri>,
+
     /// Postgres config
     #[clap(flatten)]
     pub postgres_config: SqlRecoveryDbConnectionConfig,
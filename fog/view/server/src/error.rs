@@ -1,6 +1,7 @@
 // Copyright (c) 2018-2022 The MobileCoin Foundation
 
 use displaydoc::Display;
+use mc_fog_store_connection::StoreConnectionError;
 use mc_fog_view_enclave::Error as ViewEnclaveError;
 use mc_sgx_report_cache_untrusted::Error as ReportCacheError;
 
@@ -27,3 +28,6 @@ impl From<ReportCacheError> for ViewServerError {
         Self::ReportCache(src)
     }
 }
+
+/// An error of the connection from a fog view router to a fog view store
+pub type ViewStoreError = StoreConnectionError<ViewEnclaveError>;
@@ -4,7 +4,10 @@ use crate::{config::MobileAcctViewConfig, server::DbPollSharedState};
 use grpcio::{RpcContext, RpcStatus, RpcStatusCode, UnarySink};
 use mc_attest_api::attest;
 use mc_common::logger::{log, Logger};
-use mc_fog_api::view_grpc::FogViewApi;
+use mc_fog_api::{
+    view::ViewStoreQueryResponse,
+    view_grpc::{FogViewApi, FogViewStoreApi},
+};
 use mc_fog_recovery_db_iface::RecoveryDb;
 use mc_fog_types::view::QueryRequestAAD;
 use mc_fog_view_enclave::{Error as ViewEnclaveError, ViewEnclaveProxy};
@@ -117,6 +120,35 @@ impl<E: ViewEnclaveProxy, DB: RecoveryDb + Send + Sync> FogViewService<E, DB> {
         })
     }
 
+    /// Unwrap and forward a query from a fog view router to the enclave
+    pub fn query_store_impl(
+        &mut self,
+        request: attest::Message,
+    ) -> Result<ViewStoreQueryResponse, RpcStatus> {
+        log::trace!(self.logger, "Getting encrypted request from router");
+        let tracer = tracer!();
+
+        tracer.in_span("query_store_impl", |_cx| {
+            let query_response = tracer.in_span("enclave_query_store", |_cx| {
+                self.enclave
+                    .query_store(request.into())
+                    .map_err(|e| self.enclave_err_to_rpc_status("enclave request", e))
+            })?;
+
+            let mut resp = ViewStoreQueryResponse::new();
+            resp.set_query_response(query_response.into());
+            resp.set_block_range(self.config.sharding_strategy.block_range().into());
+
+            let shared_state = self.db_poll_shared_state.lock().expect("mutex poisoned");
+            resp.set_highest_processed_block_count(shared_state.highest_processed_block_count);
+            resp.set_last_known_block_count(shared_state.last_known_block_count);
+            resp.set_last_known_block_cumulative_txo_count(
+                shared_state.last_known_block_cumulative_txo_count,
+            );
+            Ok(resp)
+        })
+    }
+
     // Helper function that is common
     fn enclave_err_to_rpc_status(&self, context: &str, src: ViewEnclaveError) -> RpcStatus {
         // Treat prost-decode error as an invalid arg,
@@ -202,3 +234,69 @@ impl<E: ViewEnclaveProxy, DB: RecoveryDb + Send + Sync> FogViewApi for FogViewSe
         })
     }
 }
+
+// Implement grpc trait for fog view routers
+impl<E: ViewEnclaveProxy, DB: RecoveryDb + Send + Sync> FogViewStoreApi for FogViewService<E, DB> {
+    fn auth(
+        &mut self,
+        ctx: RpcContext,
+        request: attest::AuthMessage,
+        sink: UnarySink<attest::AuthMessage>,
+    ) {
+        let _timer = SVC_COUNTERS.req(&ctx);
+        mc_common::logger::scoped_global_logger(&rpc_logger(&ctx, &self.logger), |logger| {
+            if let Err(err) = check_request_chain_id(&self.config.chain_id, &ctx) {
+                return send_result(ctx, sink, Err(err), logger);
+            }
+
+            if let Err(err) = self.authenticator.authenticate_rpc(&ctx) {
+                return send_result(ctx, sink, err.into(), logger);
+            }
+
+            match self.enclave.router_accept(request.into()) {
+                Ok((response, _)) => {
+                    send_result(ctx, sink, Ok(response.into()), logger);
+                }
+                Err(peer_error) => {
+                    // This is debug because there's no requirement on the remote party to trigger
+                    // it.
+                    log::debug!(
+                        logger,
+                        "ViewEnclaveApi::router_accept failed: {}",
+                        peer_error
+                    );
+                    send_result(
+                        ctx,
+                        sink,
+                        Err(rpc_permissions_error(
+                            "router_auth",
+                            format!("Permission denied: {}", peer_error),
+                            logger,
+                        )),
+                        logger,
+                    );
+                }
+            }
+        });
+    }
+
+    fn query(
+        &mut self,
+        ctx: RpcContext,
+        request: attest::Message,
+        sink: UnarySink<ViewStoreQueryResponse>,
+    ) {
+        let _timer = SVC_COUNTERS.req(&ctx);
+        mc_common::logger::scoped_global_logger(&rpc_logger(&ctx, &self.logger), |logger| {
+            if let Err(err) = check_request_chain_id(&self.config.chain_id, &ctx) {
+                return send_result(ctx, sink, Err(err), logger);
+            }
+
+            if let Err(err) = self.authenticator.authenticate_rpc(&ctx) {
+                return send_result(ctx, sink, err.into(), logger);
+            }
+
+            send_result(ctx, sink, self.query_store_impl(request), logger)
+        })
+    }
+}
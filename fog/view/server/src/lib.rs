@@ -5,7 +5,10 @@
 pub mod config;
 pub mod error;
 pub mod fog_view_service;
+pub mod router_server;
+pub mod router_service;
 pub mod server;
+pub mod view_store;
 
 mod block_tracker;
 mod counters;
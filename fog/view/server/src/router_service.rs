@@ -0,0 +1,328 @@
+// Copyright (c) 2018-2022 The MobileCoin Foundation
+
+//! The client-facing service of a fog view router, which fans client queries
+//! out to the fog view stores and merges their results.
+
+use crate::{
+    error::ViewStoreError,
+    view_store::{merge_view_store_statuses, ViewStoreApi, ViewStoreConnection, ViewStoreStatus},
+};
+use grpcio::{RpcContext, RpcStatus, RpcStatusCode, UnarySink};
+use mc_attest_api::attest;
+use mc_attest_enclave_api::{ClientSession, EnclaveMessage, PeerSession};
+use mc_common::logger::{log, Logger};
+use mc_fog_api::{view::ViewStoreQueryResponse, view_grpc::FogViewApi};
+use mc_fog_recovery_db_iface::RecoveryDb;
+use mc_fog_store_connection::StoreSessions;
+use mc_fog_types::view::QueryRequestAAD;
+use mc_fog_view_enclave::{Error as ViewEnclaveError, ViewEnclaveProxy};
+use mc_fog_view_enclave_api::UntrustedQueryResponse;
+use mc_util_grpc::{
+    check_request_chain_id, rpc_internal_error, rpc_invalid_arg_error, rpc_logger,
+    rpc_permissions_error, rpc_unavailable_error, send_result, Authenticator,
+};
+use mc_util_metrics::SVC_COUNTERS;
+use mc_util_telemetry::{tracer, Tracer};
+use std::sync::Arc;
+
+#[derive(Clone)]
+pub struct FogViewRouterService<E: ViewEnclaveProxy, DB: RecoveryDb + Send + Sync> {
+    /// The chain id of the network
+    chain_id: String,
+
+    /// Enclave which forwards client queries to the fog view stores
+    enclave: E,
+
+    /// Recovery DB.
+    db: Arc<DB>,
+
+    /// The fog view stores.
+    view_stores: Arc<Vec<ViewStoreConnection<E>>>,
+
+    /// GRPC request authenticator.
+    authenticator: Arc<dyn Authenticator + Send + Sync>,
+
+    /// Slog logger object
+    logger: Logger,
+}
+
+impl<E: ViewEnclaveProxy, DB: RecoveryDb + Send + Sync> FogViewRouterService<E, DB> {
+    /// Creates a new fog-view-router-service (but does not create sockets and
+    /// start it etc.)
+    pub fn new(
+        chain_id: String,
+        enclave: E,
+        db: Arc<DB>,
+        view_stores: Arc<Vec<ViewStoreConnection<E>>>,
+        authenticator: Arc<dyn Authenticator + Send + Sync>,
+        logger: Logger,
+    ) -> Self {
+        Self {
+            chain_id,
+            enclave,
+            db,
+            view_stores,
+            authenticator,
+            logger,
+        }
+    }
+
+    /// Fan the query out to the fog view stores, and merge their responses
+    pub fn query_impl(&mut self, request: attest::Message) -> Result<attest::Message, RpcStatus> {
+        log::trace!(self.logger, "Getting encrypted request");
+        let tracer = tracer!();
+
+        tracer.in_span("query_impl", |_cx| {
+            // Attempt and deserialize the untrusted portion of this request.
+            let query_request_aad: QueryRequestAAD = mc_util_serial::decode(request.get_aad())
+                .map_err(|err| {
+                    RpcStatus::with_message(
+                        RpcStatusCode::INVALID_ARGUMENT,
+                        format!("AAD deserialization error: {}", err),
+                    )
+                })?;
+
+            let (user_events, next_start_from_user_event_id) =
+                tracer.in_span("search_user_events", |_cx| {
+                    self.db
+                        .search_user_events(query_request_aad.start_from_user_event_id)
+                        .map_err(|e| rpc_internal_error("search_user_events", e, &self.logger))
+                })?;
+
+            let client_session = ClientSession::from(request.get_channel_id());
+            let sessions = StoreSessions::take(&self.view_stores)
+                .map_err(|(view_store, err)| self.view_store_err_to_rpc_status(view_store, err))?;
+
+            let (sessions, store_responses) = tracer.in_span("query_view_stores", |_cx| {
+                self.query_view_stores(sessions, request.into())
+            })?;
+
+            let statuses = store_responses
+                .iter()
+                .map(ViewStoreStatus::from)
+                .collect::<Vec<_>>();
+            let merged_status = merge_view_store_statuses(&statuses);
+
+            let untrusted_query_response = UntrustedQueryResponse {
+                user_events,
+                next_start_from_user_event_id,
+                highest_processed_block_count: merged_status.highest_processed_block_count,
+                highest_processed_block_signature_timestamp: self
+                    .get_block_signature_timestamp_for_block_count(
+                        merged_status.highest_processed_block_count,
+                    ),
+                last_known_block_count: merged_status.last_known_block_count,
+                last_known_block_cumulative_txo_count: merged_status
+                    .last_known_block_cumulative_txo_count,
+            };
+
+            let store_messages: Vec<EnclaveMessage<PeerSession>> = store_responses
+                .into_iter()
+                .map(|mut store_response| store_response.take_query_response().into())
+                .collect();
+
+            let result_blob = tracer.in_span("enclave_collate_store_responses", |_cx| {
+                let result = self.enclave.collate_store_responses(
+                    client_session,
+                    store_messages,
+                    untrusted_query_response,
+                );
+                match result {
+                    Err(ViewEnclaveError::StoreChannel(_)) => sessions.close(),
+                    _ => sessions.release(),
+                }
+                result.map_err(|e| self.enclave_err_to_rpc_status("enclave request", e))
+            })?;
+
+            let mut resp = attest::Message::new();
+            resp.set_data(result_blob);
+            Ok(resp)
+        })
+    }
+
+    /// Encrypt the client query for each of the fog view stores, and send it
+    /// to all of them at once. The channels of the query are returned with the
+    /// responses, in the order of the stores.
+    ///
+    /// On failure, the channels are given back to the stores, unless they
+    /// consumed a message whose response will not be decrypted.
+    fn query_view_stores<'a>(
+        &self,
+        sessions: StoreSessions<'a, ViewStoreApi<E>>,
+        client_query: EnclaveMessage<ClientSession>,
+    ) -> Result<
+        (
+            StoreSessions<'a, ViewStoreApi<E>>,
+            Vec<ViewStoreQueryResponse>,
+        ),
+        RpcStatus,
+    > {
+        let store_queries = match self
+            .enclave
+            .create_store_queries(client_query, sessions.sessions().to_vec())
+        {
+            Ok(store_queries) => store_queries,
+            Err(err) => {
+                // Errors caused by the client's query leave the channels
+                // untouched.
+                match err {
+                    ViewEnclaveError::StoreChannel(_) => sessions.close(),
+                    _ => sessions.release(),
+                }
+                return Err(self.enclave_err_to_rpc_status("enclave request", err));
+            }
+        };
+
+        // Send all the queries before waiting for any response, so that the
+        // stores search their shards in parallel.
+        let store_responses = self
+            .view_stores
+            .iter()
+            .zip(store_queries)
+            .map(|(view_store, store_query)| {
+                view_store
+                    .query_async(store_query)
+                    .map_err(|err| self.view_store_err_to_rpc_status(view_store, err))
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .and_then(|receivers| {
+                self.view_stores
+                    .iter()
+                    .zip(receivers)
+                    .map(|(view_store, receiver)| {
+                        receiver.receive_sync().map_err(|err| {
+                            self.view_store_err_to_rpc_status(view_store, err.into())
+                        })
+                    })
+                    .collect::<Result<Vec<_>, _>>()
+            });
+        match store_responses {
+            Ok(store_responses) => Ok((sessions, store_responses)),
+            Err(err) => {
+                sessions.close();
+                Err(err)
+            }
+        }
+    }
+
+    fn get_block_signature_timestamp_for_block_count(&self, block_count: u64) -> u64 {
+        // The origin block has no block signature and hence no timestamp
+        if block_count <= 1 {
+            return 0;
+        }
+        match self
+            .db
+            .get_block_signature_timestamp_for_block(block_count - 1)
+        {
+            Ok(timestamp) => timestamp.unwrap_or_default(),
+            Err(err) => {
+                log::warn!(
+                    self.logger,
+                    "Error while querying timestamp for block count {}: {}",
+                    block_count,
+                    err
+                );
+                0
+            }
+        }
+    }
+
+    fn view_store_err_to_rpc_status(
+        &self,
+        view_store: &ViewStoreConnection<E>,
+        src: ViewStoreError,
+    ) -> RpcStatus {
+        rpc_unavailable_error(
+            format!("fog view store {}", view_store.uri()),
+            src,
+            &self.logger,
+        )
+    }
+
+    // Helper function that is common
+    fn enclave_err_to_rpc_status(&self, context: &str, src: ViewEnclaveError) -> RpcStatus {
+        // Treat prost-decode error as an invalid arg,
+        // treat attest error as permission denied,
+        // everything else is an internal error
+        match src {
+            ViewEnclaveError::ProstDecode => {
+                rpc_invalid_arg_error(context, "Prost decode failed", &self.logger)
+            }
+            ViewEnclaveError::AttestEnclave(err) => {
+                rpc_permissions_error(context, err, &self.logger)
+            }
+            ViewEnclaveError::StoreChannel(err) => {
+                rpc_unavailable_error(context, err, &self.logger)
+            }
+            other => rpc_internal_error(context, format!("{}", &other), &self.logger),
+        }
+    }
+}
+
+// Implement grpc trait
+impl<E: ViewEnclaveProxy, DB: RecoveryDb + Send + Sync> FogViewApi for FogViewRouterService<E, DB> {
+    fn auth(
+        &mut self,
+        ctx: RpcContext,
+        mut request: attest::AuthMessage,
+        sink: UnarySink<attest::AuthMessage>,
+    ) {
+        let _timer = SVC_COUNTERS.req(&ctx);
+        mc_common::logger::scoped_global_logger(&rpc_logger(&ctx, &self.logger), |logger| {
+            if let Err(err) = check_request_chain_id(&self.chain_id, &ctx) {
+                return send_result(ctx, sink, Err(err), logger);
+            }
+
+            if let Err(err) = self.authenticator.authenticate_rpc(&ctx) {
+                return send_result(ctx, sink, err.into(), logger);
+            }
+
+            match self.enclave.client_accept(request.take_data().into()) {
+                Ok((response, _)) => {
+                    let mut result = attest::AuthMessage::new();
+                    result.set_data(response.into());
+                    send_result(ctx, sink, Ok(result), logger);
+                }
+                Err(client_error) => {
+                    // This is debug because there's no requirement on the remote party to trigger
+                    // it.
+                    log::debug!(
+                        logger,
+                        "ViewEnclaveApi::client_accept failed: {}",
+                        client_error
+                    );
+                    send_result(
+                        ctx,
+                        sink,
+                        Err(rpc_permissions_error(
+                            "client_auth",
+                            format!("Permission denied: {}", client_error),
+                            logger,
+                        )),
+                        logger,
+                    );
+                }
+            }
+        });
+    }
+
+    fn query(
+        &mut self,
+        ctx: RpcContext,
+        request: attest::Message,
+        sink: UnarySink<attest::Message>,
+    ) {
+        let _timer = SVC_COUNTERS.req(&ctx);
+        mc_common::logger::scoped_global_logger(&rpc_logger(&ctx, &self.logger), |logger| {
+            if let Err(err) = check_request_chain_id(&self.chain_id, &ctx) {
+                return send_result(ctx, sink, Err(err), logger);
+            }
+
+            if let Err(err) = self.authenticator.authenticate_rpc(&ctx) {
+                return send_result(ctx, sink, err.into(), logger);
+            }
+
+            send_result(ctx, sink, self.query_impl(request), logger)
+        })
+    }
+}
@@ -18,7 +18,7 @@ use mc_common::{
 use mc_crypto_keys::CompressedRistrettoPublic;
 use mc_fog_api::view_grpc;
 use mc_fog_recovery_db_iface::RecoveryDb;
-use mc_fog_types::ETxOutRecord;
+use mc_fog_types::{sharding::EpochShardingStrategy, ETxOutRecord};
 use mc_fog_uri::ConnectionUri;
 use mc_fog_view_enclave::ViewEnclaveProxy;
 use mc_sgx_report_cache_untrusted::ReportCacheThread;
@@ -74,6 +74,7 @@ where
             enclave.clone(),
             recovery_db.clone(),
             readiness_indicator.clone(),
+            config.sharding_strategy.clone(),
             logger.clone(),
         );
 
@@ -84,9 +85,9 @@ where
         );
 
         let client_authenticator: Arc<dyn Authenticator + Sync + Send> =
-            if let Some(shared_secret) = config.client_auth_token_secret.as_ref() {
-                Arc::new(TokenAuthenticator::new(
-                    *shared_secret,
+            if let Some(signing_keys) = config.client_auth_token_secret.as_ref() {
+                Arc::new(TokenAuthenticator::new_with_keys(
+                    signing_keys.clone(),
                     config.client_auth_token_max_lifetime,
                     time_provider,
                 ))
@@ -94,14 +95,17 @@ where
                 Arc::new(AnonymousAuthenticator::default())
             };
 
-        let fog_view_service = view_grpc::create_fog_view_api(FogViewService::new(
+        let fog_view_service = FogViewService::new(
             config.clone(),
             enclave.clone(),
             Arc::new(recovery_db),
             db_poll_thread.get_shared_state(),
             client_authenticator,
             logger.clone(),
-        ));
+        );
+        // Fog view routers query this server as a fog view store.
+        let fog_view_store_service = view_grpc::create_fog_view_store_api(fog_view_service.clone());
+        let fog_view_service = view_grpc::create_fog_view_api(fog_view_service);
         log::debug!(logger, "Constructed View GRPC Service");
 
         // Health check service
@@ -117,6 +121,7 @@ where
         );
         let server_builder = grpcio::ServerBuilder::new(env)
             .register_service(fog_view_service)
+            .register_service(fog_view_store_service)
             .register_service(health_service)
             .bind_using_uri(&config.client_listen_uri, logger.clone());
 
@@ -228,6 +233,9 @@ where
     /// Readiness indicator.
     readiness_indicator: ReadinessIndicator,
 
+    /// Which blocks to load into the enclave.
+    sharding_strategy: EpochShardingStrategy,
+
     /// Logger.
     logger: Logger,
 }
@@ -250,6 +258,7 @@ where
         enclave: E,
         db: DB,
         readiness_indicator: ReadinessIndicator,
+        sharding_strategy: EpochShardingStrategy,
         logger: Logger,
     ) -> Self {
         let stop_requested = Arc::new(AtomicBool::new(false));
@@ -262,6 +271,7 @@ where
             stop_requested,
             shared_state,
             readiness_indicator,
+            sharding_strategy,
             logger,
         }
     }
@@ -280,6 +290,7 @@ where
         let thread_stop_requested = self.stop_requested.clone();
         let thread_shared_state = self.shared_state.clone();
         let thread_readiness_indicator = self.readiness_indicator.clone();
+        let thread_sharding_strategy = self.sharding_strategy.clone();
         let thread_logger = self.logger.clone();
 
         self.join_handle = Some(
@@ -292,6 +303,7 @@ where
                         thread_stop_requested,
                         thread_shared_state,
                         thread_readiness_indicator,
+                        thread_sharding_strategy,
                         thread_logger,
                     )
                 })
@@ -315,6 +327,7 @@ where
         stop_requested: Arc<AtomicBool>,
         shared_state: Arc<Mutex<DbPollSharedState>>,
         readiness_indicator: ReadinessIndicator,
+        sharding_strategy: EpochShardingStrategy,
         logger: Logger,
     ) {
         log::debug!(logger, "Db poll thread started");
@@ -325,6 +338,7 @@ where
             db,
             shared_state,
             readiness_indicator,
+            sharding_strategy,
             logger.clone(),
         );
         loop {
@@ -407,6 +421,7 @@ where
         db: DB,
         shared_state: Arc<Mutex<DbPollSharedState>>,
         readiness_indicator: ReadinessIndicator,
+        sharding_strategy: EpochShardingStrategy,
         logger: Logger,
     ) -> Self {
         Self {
@@ -414,8 +429,13 @@ where
             enclave,
             db: db.clone(),
             shared_state,
-            db_fetcher: DbFetcher::new(db, readiness_indicator, logger.clone()),
-            enclave_block_tracker: BlockTracker::new(logger.clone()),
+            db_fetcher: DbFetcher::new(
+                db,
+                readiness_indicator,
+                sharding_strategy.clone(),
+                logger.clone(),
+            ),
+            enclave_block_tracker: BlockTracker::new(logger.clone(), sharding_strategy),
             last_unblocked_at: Instant::now(),
             logger,
         }
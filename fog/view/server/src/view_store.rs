@@ -0,0 +1,200 @@
+// Copyright (c) 2018-2022 The MobileCoin Foundation
+
+//! The connections of a fog view router to the fog view stores it fans client
+//! queries out to, and the merging of the untrusted state the stores report.
+
+use grpcio::{CallOption, Channel, ClientUnaryReceiver, Error as GrpcError};
+use mc_attest_api::attest::{AuthMessage, Message};
+use mc_attest_enclave_api::{PeerAuthRequest, PeerAuthResponse, PeerSession};
+use mc_common::ResponderId;
+use mc_fog_api::{view::ViewStoreQueryResponse, view_grpc::FogViewStoreApiClient};
+use mc_fog_store_connection::{AttestedStoreConnection, StoreApi};
+use mc_fog_types::common::BlockRange;
+use mc_fog_uri::FogViewUri;
+use mc_fog_view_enclave::{Error as ViewEnclaveError, ViewEnclaveProxy};
+use std::marker::PhantomData;
+
+/// A connection from a fog view router to a fog view store.
+pub type ViewStoreConnection<E> = AttestedStoreConnection<ViewStoreApi<E>>;
+
+/// The calls a fog view router makes to its enclave and to fog view stores.
+pub struct ViewStoreApi<E: ViewEnclaveProxy>(PhantomData<E>);
+
+impl<E: ViewEnclaveProxy> StoreApi for ViewStoreApi<E> {
+    type Uri = FogViewUri;
+    type Enclave = E;
+    type EnclaveError = ViewEnclaveError;
+    type Client = FogViewStoreApiClient;
+    type QueryResponse = ViewStoreQueryResponse;
+
+    const NAME: &'static str = "fog view store";
+
+    fn client(channel: Channel) -> Self::Client {
+        FogViewStoreApiClient::new(channel)
+    }
+
+    fn init(enclave: &E, store_id: ResponderId) -> Result<PeerAuthRequest, ViewEnclaveError> {
+        enclave.view_store_init(store_id)
+    }
+
+    fn connect(
+        enclave: &E,
+        store_id: ResponderId,
+        auth_response: PeerAuthResponse,
+    ) -> Result<PeerSession, ViewEnclaveError> {
+        enclave.view_store_connect(store_id, auth_response)
+    }
+
+    fn close(enclave: &E, session: PeerSession) -> Result<(), ViewEnclaveError> {
+        enclave.peer_close(session)
+    }
+
+    fn auth(
+        client: &Self::Client,
+        request: &AuthMessage,
+        opt: CallOption,
+    ) -> Result<AuthMessage, GrpcError> {
+        client.auth_opt(request, opt)
+    }
+
+    fn query_async(
+        client: &Self::Client,
+        request: &Message,
+        opt: CallOption,
+    ) -> Result<ClientUnaryReceiver<ViewStoreQueryResponse>, GrpcError> {
+        client.query_async_opt(request, opt)
+    }
+}
+
+/// The untrusted state a fog view store reports alongside its query responses
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ViewStoreStatus {
+    /// The blocks the store loads ETxOutRecords for
+    pub block_range: BlockRange,
+
+    /// The block count for which the store has loaded all the ETxOutRecords
+    /// of its range
+    pub highest_processed_block_count: u64,
+
+    /// The last block count for which the store was able to load data
+    pub last_known_block_count: u64,
+
+    /// The cumulative txo count of the last known block
+    pub last_known_block_cumulative_txo_count: u64,
+}
+
+impl From<&ViewStoreQueryResponse> for ViewStoreStatus {
+    fn from(src: &ViewStoreQueryResponse) -> Self {
+        Self {
+            block_range: src.get_block_range().into(),
+            highest_processed_block_count: src.get_highest_processed_block_count(),
+            last_known_block_count: src.get_last_known_block_count(),
+            last_known_block_cumulative_txo_count: src.get_last_known_block_cumulative_txo_count(),
+        }
+    }
+}
+
+/// The untrusted state of all the fog view stores, as seen by a client
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct MergedViewStoreStatus {
+    /// The block count for which all the ETxOutRecords are loaded by some store
+    pub highest_processed_block_count: u64,
+
+    /// The last block count for which some store was able to load data
+    pub last_known_block_count: u64,
+
+    /// The cumulative txo count of the last known block
+    pub last_known_block_cumulative_txo_count: u64,
+}
+
+/// Merge the states of the fog view stores.
+///
+/// Blocks are only processed once every block before them is, so the stores
+/// are walked in block order until one of them has not processed its whole
+/// range, or there is a gap between the ranges of the stores.
+pub fn merge_view_store_statuses(statuses: &[ViewStoreStatus]) -> MergedViewStoreStatus {
+    let mut sorted_statuses = statuses.iter().collect::<Vec<_>>();
+    sorted_statuses.sort_by_key(|status| status.block_range.start_block);
+
+    let mut highest_processed_block_count = 0;
+    for status in sorted_statuses {
+        if status.block_range.start_block > highest_processed_block_count {
+            break;
+        }
+        highest_processed_block_count =
+            highest_processed_block_count.max(status.highest_processed_block_count);
+        if status.highest_processed_block_count < status.block_range.end_block {
+            break;
+        }
+    }
+
+    let last_known = statuses
+        .iter()
+        .max_by_key(|status| status.last_known_block_count)
+        .cloned()
+        .unwrap_or_default();
+
+    MergedViewStoreStatus {
+        highest_processed_block_count,
+        last_known_block_count: last_known.last_known_block_count,
+        last_known_block_cumulative_txo_count: last_known.last_known_block_cumulative_txo_count,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn status(start: u64, end: u64, processed: u64, last_known: u64) -> ViewStoreStatus {
+        ViewStoreStatus {
+            block_range: BlockRange::new(start, end),
+            highest_processed_block_count: processed,
+            last_known_block_count: last_known,
+            last_known_block_cumulative_txo_count: last_known * 10,
+        }
+    }
+
+    #[test]
+    fn merge_contiguous_stores() {
+        // Both stores processed everything they know about.
+        let merged = merge_view_store_statuses(&[status(5, u64::MAX, 8, 8), status(0, 5, 5, 5)]);
+        assert_eq!(
+            merged,
+            MergedViewStoreStatus {
+                highest_processed_block_count: 8,
+                last_known_block_count: 8,
+                last_known_block_cumulative_txo_count: 80,
+            }
+        );
+
+        // The first store is behind, so the second one's blocks don't count.
+        let merged = merge_view_store_statuses(&[status(0, 5, 3, 4), status(5, u64::MAX, 8, 8)]);
+        assert_eq!(merged.highest_processed_block_count, 3);
+        assert_eq!(merged.last_known_block_count, 8);
+
+        // Nothing was processed yet.
+        let merged = merge_view_store_statuses(&[status(0, 5, 0, 0), status(5, u64::MAX, 5, 0)]);
+        assert_eq!(merged, MergedViewStoreStatus::default());
+    }
+
+    #[test]
+    fn merge_gaps_and_overlaps() {
+        // No store loads blocks 5 to 10.
+        let merged = merge_view_store_statuses(&[status(0, 5, 5, 5), status(10, u64::MAX, 12, 12)]);
+        assert_eq!(merged.highest_processed_block_count, 5);
+        assert_eq!(merged.last_known_block_count, 12);
+
+        // Overlapping ranges, e.g. while resharding.
+        let merged = merge_view_store_statuses(&[
+            status(0, 10, 10, 10),
+            status(5, u64::MAX, 12, 12),
+            status(0, 6, 6, 6),
+        ]);
+        assert_eq!(merged.highest_processed_block_count, 12);
+
+        assert_eq!(
+            merge_view_store_statuses(&[]),
+            MergedViewStoreStatus::default()
+        );
+    }
+}
@@ -0,0 +1,271 @@
+// Copyright (c) 2018-2022 The MobileCoin Foundation
+
+// This integration-level test shards the ETxOutRecords across two fog view
+// stores by block range, and checks that a client querying the fog view router
+// in front of them sees the same results as if a single view server held all
+// the records.
+
+use mc_attest_net::{Client as AttestClient, RaClient};
+use mc_attest_verifier::{MrSignerVerifier, Verifier, DEBUG_ENCLAVE};
+use mc_blockchain_types::{Block, BlockID, BlockVersion};
+use mc_common::{
+    logger::{log, test_with_logger, Logger},
+    time::SystemTimeProvider,
+    ResponderId,
+};
+use mc_crypto_keys::{CompressedRistrettoPublic, RistrettoPublic};
+use mc_fog_kex_rng::KexRngPubkey;
+use mc_fog_recovery_db_iface::RecoveryDb;
+use mc_fog_sql_recovery_db::{test_utils::SqlRecoveryDbTestContext, SqlRecoveryDb};
+use mc_fog_test_infra::get_enclave_path;
+use mc_fog_types::{sharding::EpochShardingStrategy, view::TxOutSearchResultCode, ETxOutRecord};
+use mc_fog_uri::{ConnectionUri, FogViewUri};
+use mc_fog_view_connection::FogViewGrpcClient;
+use mc_fog_view_enclave::SgxViewEnclave;
+use mc_fog_view_protocol::FogViewConnection;
+use mc_fog_view_server::{
+    config::{FogViewRouterConfig, MobileAcctViewConfig as ViewConfig},
+    router_server::{FogViewRouterServer, ROUTER_OMAP_CAPACITY},
+    server::ViewServer,
+};
+use mc_util_from_random::FromRandom;
+use mc_util_grpc::GrpcRetryConfig;
+use rand::{rngs::StdRng, SeedableRng};
+use std::{str::FromStr, sync::Arc, thread::sleep, time::Duration};
+
+const GRPC_RETRY_CONFIG: GrpcRetryConfig = GrpcRetryConfig {
+    grpc_retry_count: 3,
+    grpc_retry_millis: 20,
+};
+
+fn pick_uri() -> FogViewUri {
+    let port = portpicker::pick_unused_port().expect("pick_unused_port");
+    FogViewUri::from_str(&format!("insecure-fog-view://127.0.0.1:{}", port)).unwrap()
+}
+
+fn start_view_store(
+    db: SqlRecoveryDb,
+    sharding_strategy: &str,
+    logger: Logger,
+) -> (
+    FogViewUri,
+    ViewServer<SgxViewEnclave, AttestClient, SqlRecoveryDb>,
+) {
+    let uri = pick_uri();
+    let config = ViewConfig {
+        chain_id: "local".to_string(),
+        client_responder_id: ResponderId::from_str(&uri.addr()).unwrap(),
+        client_listen_uri: uri.clone(),
+        client_auth_token_secret: None,
+        omap_capacity: 512,
+        ias_spid: Default::default(),
+        ias_api_key: Default::default(),
+        admin_listen_uri: Default::default(),
+        client_auth_token_max_lifetime: Default::default(),
+        sharding_strategy: EpochShardingStrategy::from_str(sharding_strategy).unwrap(),
+        postgres_config: Default::default(),
+    };
+
+    let enclave = SgxViewEnclave::new(
+        get_enclave_path(mc_fog_view_enclave::ENCLAVE_FILE),
+        config.client_responder_id.clone(),
+        config.omap_capacity,
+        logger.clone(),
+    );
+
+    let ra_client = AttestClient::new(&config.ias_api_key).expect("Could not create IAS client");
+
+    let mut server = ViewServer::new(
+        config,
+        enclave,
+        db,
+        ra_client,
+        SystemTimeProvider::default(),
+        logger,
+    );
+    server.start();
+    (uri, server)
+}
+
+fn start_router(
+    db: SqlRecoveryDb,
+    view_store_uris: Vec<FogViewUri>,
+    logger: Logger,
+) -> (
+    FogViewUri,
+    FogViewRouterServer<SgxViewEnclave, AttestClient>,
+) {
+    let uri = pick_uri();
+    let config = FogViewRouterConfig {
+        chain_id: "local".to_string(),
+        client_responder_id: ResponderId::from_str(&uri.addr()).unwrap(),
+        client_listen_uri: uri.clone(),
+        client_auth_token_secret: None,
+        ias_spid: Default::default(),
+        ias_api_key: Default::default(),
+        admin_listen_uri: Default::default(),
+        client_auth_token_max_lifetime: Default::default(),
+        view_store_uris,
+        postgres_config: Default::default(),
+    };
+
+    let enclave = SgxViewEnclave::new(
+        get_enclave_path(mc_fog_view_enclave::ENCLAVE_FILE),
+        config.client_responder_id.clone(),
+        ROUTER_OMAP_CAPACITY,
+        logger.clone(),
+    );
+
+    let ra_client = AttestClient::new(&config.ias_api_key).expect("Could not create IAS client");
+
+    let mut server = FogViewRouterServer::new(
+        config,
+        enclave,
+        db,
+        ra_client,
+        SystemTimeProvider::default(),
+        logger,
+    );
+    server.start();
+    (uri, server)
+}
+
+fn view_client(uri: FogViewUri, logger: Logger) -> FogViewGrpcClient {
+    let grpcio_env = Arc::new(grpcio::EnvBuilder::new().build());
+    let mut mr_signer_verifier =
+        MrSignerVerifier::from(mc_fog_view_enclave_measurement::sigstruct());
+    mr_signer_verifier
+        .allow_hardening_advisories(mc_fog_view_enclave_measurement::HARDENING_ADVISORIES);
+
+    let mut verifier = Verifier::default();
+    verifier.mr_signer(mr_signer_verifier).debug(DEBUG_ENCLAVE);
+
+    FogViewGrpcClient::new(
+        "local".to_string(),
+        uri,
+        GRPC_RETRY_CONFIG,
+        verifier,
+        grpcio_env,
+        logger,
+    )
+}
+
+#[test_with_logger]
+fn test_view_router_two_stores(logger: Logger) {
+    let mut rng: StdRng = SeedableRng::from_seed([123u8; 32]);
+    let db_context = SqlRecoveryDbTestContext::new(logger.clone());
+    let db = db_context.get_db_instance();
+
+    // Blocks 0 to 2 are loaded by the first store, the others by the second.
+    let (store_uri_1, store_1) = start_view_store(db.clone(), "0-3", logger.clone());
+    let (store_uri_2, store_2) = start_view_store(db.clone(), "3-", logger.clone());
+    let (router_uri, _router) =
+        start_router(db.clone(), vec![store_uri_1, store_uri_2], logger.clone());
+    let mut client = view_client(router_uri.clone(), logger.clone());
+
+    let ingress_key = CompressedRistrettoPublic::from(RistrettoPublic::from_random(&mut rng));
+    db.new_ingress_key(&ingress_key, 0).unwrap();
+
+    let pubkey = KexRngPubkey {
+        public_key: [1; 32].to_vec(),
+        version: 0,
+    };
+    let invoc_id = db
+        .new_ingest_invocation(None, &ingress_key, &pubkey, 0)
+        .unwrap();
+
+    // Six blocks with two records each.
+    let txs: Vec<ETxOutRecord> = (1u8..13u8)
+        .map(|x| ETxOutRecord {
+            search_key: vec![x; 16],
+            payload: vec![x; 232],
+        })
+        .collect();
+    for block_index in 0..6u64 {
+        let first_tx = 2 * block_index as usize;
+        db.add_block_data(
+            &invoc_id,
+            &Block::new(
+                BlockVersion::ZERO,
+                &BlockID::default(),
+                block_index,
+                first_tx as u64 + 2,
+                &Default::default(),
+                &Default::default(),
+            ),
+            0,
+            &txs[first_tx..first_tx + 2],
+        )
+        .unwrap();
+    }
+
+    // Wait until both stores have loaded their blocks
+    let mut allowed_tries = 60usize;
+    while store_1.highest_processed_block_count() < 3 || store_2.highest_processed_block_count() < 6
+    {
+        if allowed_tries == 0 {
+            panic!("Stores did not catch up to database!");
+        }
+        allowed_tries -= 1;
+        log::info!(logger, "Waiting for stores to catch up to db...");
+        sleep(Duration::from_millis(1000));
+    }
+
+    let result = client
+        .request(
+            0,
+            0,
+            vec![
+                vec![1u8; 16],
+                vec![6u8; 16],
+                vec![7u8; 16],
+                vec![12u8; 16],
+                vec![200u8; 16],
+            ],
+        )
+        .unwrap();
+    assert_eq!(result.highest_processed_block_count, 6);
+    assert_eq!(result.last_known_block_count, 6);
+    assert_eq!(result.last_known_block_cumulative_txo_count, 12);
+    assert_eq!(result.rng_records.len(), 1);
+    assert_eq!(result.rng_records[0].pubkey, pubkey);
+    assert_eq!(result.tx_out_search_results.len(), 5);
+
+    let mut sort_txs = result.tx_out_search_results;
+    sort_txs.sort_by(|x, y| x.search_key.cmp(&y.search_key));
+    // Records from both stores are found.
+    for (tx, key) in sort_txs.iter().zip([1u8, 6, 7, 12]) {
+        assert_eq!(tx.search_key, vec![key; 16]);
+        assert_eq!(tx.result_code, TxOutSearchResultCode::Found as u32);
+        assert_eq!(tx.ciphertext, vec![key; 232]);
+    }
+    assert_eq!(sort_txs[4].search_key, vec![200u8; 16]);
+    assert_eq!(
+        sort_txs[4].result_code,
+        TxOutSearchResultCode::NotFound as u32
+    );
+
+    // Queries of several clients run concurrently, each with attested
+    // channels to the stores of its own.
+    let handles = (1u8..5)
+        .map(|key| {
+            let mut client = view_client(router_uri.clone(), logger.clone());
+            std::thread::spawn(move || {
+                for _ in 0..5 {
+                    let result = client.request(0, 0, vec![vec![key; 16]]).unwrap();
+                    assert_eq!(result.tx_out_search_results.len(), 1);
+                    assert_eq!(
+                        result.tx_out_search_results[0].result_code,
+                        TxOutSearchResultCode::Found as u32
+                    );
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+
+    // Sleep before exiting to give view server threads time to join
+    sleep(Duration::from_millis(1000));
+}
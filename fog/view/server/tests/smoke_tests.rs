@@ -69,6 +69,7 @@ fn get_test_environment(
             ias_api_key: Default::default(),
             admin_listen_uri: Default::default(),
             client_auth_token_max_lifetime: Default::default(),
+            sharding_strategy: Default::default(),
             postgres_config: Default::default(),
         };
 
@@ -14,6 +14,7 @@ mod mint_config_store;
 mod mint_tx_store;
 
 pub mod ledger_db;
+pub mod snapshot;
 #[cfg(any(test, feature = "test_utils"))]
 pub mod test_utils;
 pub mod tx_out_store;
@@ -28,3 +29,9 @@ pub use crate::{
     tx_out_store::TxOutStore,
 };
 pub use mc_util_lmdb::{MetadataStore, MetadataStoreError, MetadataStoreSettings};
+
+#[cfg(feature = "migration_support")]
+pub use crate::{
+    mint_config_store::VALIDATED_MINT_CONFIG_TXS_BY_BLOCK_DB_NAME,
+    mint_tx_store::MINT_TXS_BY_BLOCK_DB_NAME,
+};
@@ -0,0 +1,658 @@
+// Copyright (c) 2018-2022 The MobileCoin Foundation
+
+//! Ledger snapshots, used to bootstrap a new node without replaying the
+//! blockchain from a remote source.
+//!
+//! A snapshot contains every block up to and including a given block index,
+//! together with their contents, signatures and metadata. The file layout is:
+//! * An 8 byte magic value, followed by the format version as a little endian
+//!   u32.
+//! * The length of the [SnapshotHeader] as a little endian u32, followed by the
+//!   protobuf encoded header.
+//! * A sequence of chunks, each holding up to `blocks_per_chunk` blocks. A
+//!   chunk is stored as its length as a little endian u64, followed by the
+//!   DEFLATE compressed protobuf encoding of a [SnapshotChunk].
+//!
+//! Nothing in a snapshot is trusted on its own: importing requires a
+//! [TrustAnchor], either the ID of the last block or a set of keys trusted to
+//! sign blocks, and the last block is authenticated against it. Since every
+//! block links to its parent by ID and commits to its contents by hash, this
+//! authenticates the whole snapshot. Importing also checks the block
+//! signatures and metadata signatures, and that the TxOut Merkle root of the
+//! imported ledger matches the snapshot header. Once imported, the ledger can
+//! be synced as usual, starting at the block after the snapshot.
+
+use crate::{Error, Ledger};
+use displaydoc::Display;
+use flate2::{read::DeflateDecoder, write::DeflateEncoder, Compression};
+use mc_blockchain_types::{Block, BlockData, BlockID, BlockIndex};
+use mc_crypto_keys::Ed25519Public;
+use mc_transaction_core::{
+    membership_proofs::{hash_leaf, hash_nodes, Range, NIL_HASH},
+    tx::TxOutMembershipElement,
+};
+use prost::Message;
+use std::io::{self, Read, Write};
+
+/// Magic value at the start of every snapshot file.
+pub const SNAPSHOT_MAGIC: &[u8; 8] = b"MCLEDGSN";
+
+/// The snapshot format version written by this build.
+pub const SNAPSHOT_FORMAT_VERSION: u32 = 1;
+
+/// The default number of blocks stored in each chunk.
+pub const DEFAULT_BLOCKS_PER_CHUNK: usize = 1000;
+
+/// Upper bound on the size of a single encoded chunk or header, to avoid
+/// allocating unbounded amounts of memory for a corrupt file.
+const MAX_ENCODED_LEN: u64 = 1 << 30;
+
+/// Describes the contents of a snapshot.
+#[derive(Clone, Eq, Message, PartialEq)]
+pub struct SnapshotHeader {
+    /// Index of the last block in the snapshot.
+    #[prost(uint64, tag = 1)]
+    pub block_index: BlockIndex,
+
+    /// ID of the last block in the snapshot.
+    #[prost(message, required, tag = 2)]
+    pub last_block_id: BlockID,
+
+    /// Number of TxOuts in the ledger after the last block in the snapshot.
+    #[prost(uint64, tag = 3)]
+    pub num_txos: u64,
+
+    /// Root element of the TxOut Merkle tree after the last block in the
+    /// snapshot.
+    #[prost(message, required, tag = 4)]
+    pub root_element: TxOutMembershipElement,
+
+    /// Maximum number of blocks in each chunk.
+    #[prost(uint64, tag = 5)]
+    pub blocks_per_chunk: u64,
+}
+
+impl SnapshotHeader {
+    /// Number of blocks in the snapshot.
+    pub fn num_blocks(&self) -> u64 {
+        self.block_index + 1
+    }
+}
+
+/// A consecutive run of blocks in a snapshot.
+#[derive(Clone, Eq, Message, PartialEq)]
+pub struct SnapshotChunk {
+    /// The blocks, in increasing order of block index.
+    #[prost(message, repeated, tag = 1)]
+    pub blocks: Vec<BlockData>,
+}
+
+/// What a snapshot is authenticated against when it is imported.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum TrustAnchor {
+    /// The ID of the last block in the snapshot, obtained from a trusted
+    /// source.
+    LastBlockId(BlockID),
+
+    /// Keys trusted to sign blocks. The last block in the snapshot must be
+    /// signed by one of them.
+    SignerKeys(Vec<Ed25519Public>),
+}
+
+/// An error which can occur when exporting or importing a snapshot.
+#[derive(Debug, Display)]
+pub enum SnapshotError {
+    /// IO: {0}
+    Io(io::Error),
+
+    /// Ledger: {0}
+    Ledger(Error),
+
+    /// Not a ledger snapshot
+    InvalidMagic,
+
+    /// Unsupported snapshot format version: {0}
+    UnsupportedVersion(u32),
+
+    /// The snapshot data could not be decoded
+    Decode,
+
+    /// Blocks per chunk must be greater than zero
+    InvalidBlocksPerChunk,
+
+    /// Block {0} is not in the ledger, which has {1} blocks
+    BlockNotInLedger(BlockIndex, u64),
+
+    /// Snapshots can only be imported into an empty ledger
+    LedgerNotEmpty,
+
+    /// Expected block {0}, found block {1}
+    UnexpectedBlockIndex(BlockIndex, BlockIndex),
+
+    /// Block {0} has an invalid block ID
+    InvalidBlockId(BlockIndex),
+
+    /// Block {0} does not link to the previous block
+    InvalidParentId(BlockIndex),
+
+    /// The contents of block {0} do not match its contents hash
+    InvalidContentsHash(BlockIndex),
+
+    /// Block {0} is not signed
+    MissingSignature(BlockIndex),
+
+    /// The signature of block {0} is invalid
+    InvalidSignature(BlockIndex),
+
+    /// The metadata of block {0} is invalid
+    InvalidMetadata(BlockIndex),
+
+    /// The snapshot ended after {0} blocks, but should contain {1}
+    Truncated(u64, u64),
+
+    /// The last block does not match the snapshot header
+    LastBlockMismatch,
+
+    /// The last block has ID {0}, but {1} was expected
+    UnexpectedLastBlockId(BlockID, BlockID),
+
+    /// Block {0} is not signed by a trusted key
+    UntrustedSigner(BlockIndex),
+
+    /// The TxOut Merkle root of the imported ledger does not match the
+    /// snapshot header
+    RootElementMismatch,
+}
+
+impl From<io::Error> for SnapshotError {
+    fn from(src: io::Error) -> Self {
+        Self::Io(src)
+    }
+}
+
+impl From<Error> for SnapshotError {
+    fn from(src: Error) -> Self {
+        Self::Ledger(src)
+    }
+}
+
+impl From<prost::DecodeError> for SnapshotError {
+    fn from(_: prost::DecodeError) -> Self {
+        Self::Decode
+    }
+}
+
+/// Export the blocks of a ledger, up to and including `block_index`, to a
+/// snapshot.
+///
+/// # Arguments
+/// * `ledger` - The ledger to export.
+/// * `block_index` - Index of the last block to include in the snapshot.
+/// * `blocks_per_chunk` - Maximum number of blocks to store in each chunk.
+/// * `writer` - Where to write the snapshot to.
+pub fn export_snapshot(
+    ledger: &impl Ledger,
+    block_index: BlockIndex,
+    blocks_per_chunk: usize,
+    mut writer: impl Write,
+) -> Result<SnapshotHeader, SnapshotError> {
+    if blocks_per_chunk == 0 {
+        return Err(SnapshotError::InvalidBlocksPerChunk);
+    }
+    let num_blocks = ledger.num_blocks()?;
+    if block_index >= num_blocks {
+        return Err(SnapshotError::BlockNotInLedger(block_index, num_blocks));
+    }
+
+    let last_block = ledger.get_block(block_index)?;
+    let header = SnapshotHeader {
+        block_index,
+        last_block_id: last_block.id.clone(),
+        num_txos: last_block.cumulative_txo_count,
+        root_element: tx_out_root_element(ledger, last_block.cumulative_txo_count)?,
+        blocks_per_chunk: blocks_per_chunk as u64,
+    };
+
+    writer.write_all(SNAPSHOT_MAGIC)?;
+    writer.write_all(&SNAPSHOT_FORMAT_VERSION.to_le_bytes())?;
+    let header_bytes = header.encode_to_vec();
+    writer.write_all(&(header_bytes.len() as u32).to_le_bytes())?;
+    writer.write_all(&header_bytes)?;
+
+    let mut chunk_start = 0;
+    while chunk_start <= block_index {
+        let chunk_end = (chunk_start + blocks_per_chunk as u64 - 1).min(block_index);
+        let chunk = SnapshotChunk {
+            blocks: (chunk_start..=chunk_end)
+                .map(|index| ledger.get_block_data(index))
+                .collect::<Result<_, _>>()?,
+        };
+
+        let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&chunk.encode_to_vec())?;
+        let compressed = encoder.finish()?;
+
+        writer.write_all(&(compressed.len() as u64).to_le_bytes())?;
+        writer.write_all(&compressed)?;
+
+        chunk_start = chunk_end + 1;
+    }
+    writer.flush()?;
+
+    Ok(header)
+}
+
+/// Import a snapshot into an empty ledger.
+///
+/// Every block is verified before it is appended. If an error is returned, the
+/// ledger may contain some of the blocks of the snapshot, and should be
+/// discarded.
+///
+/// # Arguments
+/// * `reader` - Where to read the snapshot from.
+/// * `ledger` - The ledger to import into. Must be empty.
+/// * `trust_anchor` - What the last block in the snapshot is authenticated
+///   against. Since blocks are linked by their IDs, this authenticates the
+///   whole snapshot.
+pub fn import_snapshot(
+    mut reader: impl Read,
+    ledger: &mut impl Ledger,
+    trust_anchor: &TrustAnchor,
+) -> Result<SnapshotHeader, SnapshotError> {
+    if ledger.num_blocks()? != 0 {
+        return Err(SnapshotError::LedgerNotEmpty);
+    }
+
+    let mut magic = [0u8; 8];
+    reader.read_exact(&mut magic)?;
+    if &magic != SNAPSHOT_MAGIC {
+        return Err(SnapshotError::InvalidMagic);
+    }
+    let version = read_u32(&mut reader)?;
+    if version != SNAPSHOT_FORMAT_VERSION {
+        return Err(SnapshotError::UnsupportedVersion(version));
+    }
+    let header_len = read_u32(&mut reader)? as u64;
+    let header = SnapshotHeader::decode(read_bytes(&mut reader, header_len)?.as_slice())?;
+    if header.blocks_per_chunk == 0 {
+        return Err(SnapshotError::InvalidBlocksPerChunk);
+    }
+    // Check the header against a trusted block ID before importing anything,
+    // the last block itself is checked against it below.
+    if let TrustAnchor::LastBlockId(expected) = trust_anchor {
+        if expected != &header.last_block_id {
+            return Err(SnapshotError::UnexpectedLastBlockId(
+                header.last_block_id.clone(),
+                expected.clone(),
+            ));
+        }
+    }
+
+    let mut parent: Option<BlockData> = None;
+    let mut next_index: BlockIndex = 0;
+    while next_index < header.num_blocks() {
+        let chunk = match read_chunk(&mut reader)? {
+            Some(chunk) => chunk,
+            None => return Err(SnapshotError::Truncated(next_index, header.num_blocks())),
+        };
+        if chunk.blocks.is_empty() || chunk.blocks.len() as u64 > header.blocks_per_chunk {
+            return Err(SnapshotError::Decode);
+        }
+
+        for block_data in chunk.blocks {
+            verify_block_data(
+                &block_data,
+                next_index,
+                parent.as_ref().map(BlockData::block),
+            )?;
+            ledger.append_block_data(&block_data)?;
+            parent = Some(block_data);
+            next_index += 1;
+        }
+    }
+
+    let last_block_data = match parent {
+        Some(block_data)
+            if block_data.block().index == header.block_index
+                && block_data.block().id == header.last_block_id
+                && block_data.block().cumulative_txo_count == header.num_txos =>
+        {
+            block_data
+        }
+        _ => return Err(SnapshotError::LastBlockMismatch),
+    };
+    verify_trust_anchor(&last_block_data, trust_anchor)?;
+    if ledger.get_root_tx_out_membership_element()? != header.root_element {
+        return Err(SnapshotError::RootElementMismatch);
+    }
+
+    Ok(header)
+}
+
+/// Check that a block is consistent with itself and with its parent, and that
+/// its signature and metadata are valid.
+fn verify_block_data(
+    block_data: &BlockData,
+    expected_index: BlockIndex,
+    parent: Option<&Block>,
+) -> Result<(), SnapshotError> {
+    let block = block_data.block();
+    if block.index != expected_index {
+        return Err(SnapshotError::UnexpectedBlockIndex(
+            expected_index,
+            block.index,
+        ));
+    }
+    if !block.is_block_id_valid() {
+        return Err(SnapshotError::InvalidBlockId(block.index));
+    }
+    let expected_parent_id = parent.map(|parent| parent.id.clone()).unwrap_or_default();
+    if block.parent_id != expected_parent_id {
+        return Err(SnapshotError::InvalidParentId(block.index));
+    }
+    if block_data.contents().hash() != block.contents_hash {
+        return Err(SnapshotError::InvalidContentsHash(block.index));
+    }
+
+    // The origin block is not signed by consensus, every other block must be.
+    match block_data.signature() {
+        Some(signature) => signature
+            .verify(block)
+            .map_err(|_| SnapshotError::InvalidSignature(block.index))?,
+        None if block.index == 0 => {}
+        None => return Err(SnapshotError::MissingSignature(block.index)),
+    }
+
+    if let Some(metadata) = block_data.metadata() {
+        if metadata.contents().block_id() != &block.id || metadata.verify().is_err() {
+            return Err(SnapshotError::InvalidMetadata(block.index));
+        }
+    }
+
+    Ok(())
+}
+
+/// Check that the last block of a snapshot is authenticated by the trust
+/// anchor. Its signature has already been verified by [verify_block_data].
+fn verify_trust_anchor(
+    last_block_data: &BlockData,
+    trust_anchor: &TrustAnchor,
+) -> Result<(), SnapshotError> {
+    let last_block = last_block_data.block();
+    match trust_anchor {
+        TrustAnchor::LastBlockId(expected) if expected != &last_block.id => Err(
+            SnapshotError::UnexpectedLastBlockId(last_block.id.clone(), expected.clone()),
+        ),
+        TrustAnchor::LastBlockId(_) => Ok(()),
+        TrustAnchor::SignerKeys(keys) => match last_block_data.signature() {
+            Some(signature) if keys.contains(signature.signer()) => Ok(()),
+            _ => Err(SnapshotError::UntrustedSigner(last_block.index)),
+        },
+    }
+}
+
+/// Compute the root element of the TxOut Merkle tree formed by the first
+/// `num_txos` TxOuts of a ledger.
+///
+/// This matches what `Ledger::get_root_tx_out_membership_element` returns for
+/// a ledger containing exactly `num_txos` TxOuts: empty subtrees hash to the
+/// nil hash, and the tree is padded to a power of two leaves.
+fn tx_out_root_element(
+    ledger: &impl Ledger,
+    num_txos: u64,
+) -> Result<TxOutMembershipElement, SnapshotError> {
+    if num_txos == 0 {
+        return Err(Error::NoOutputs.into());
+    }
+    let num_leaves_full_tree = num_txos
+        .checked_next_power_of_two()
+        .ok_or(Error::CapacityExceeded)?;
+
+    let mut level = (0..num_txos)
+        .map(|index| Ok(hash_leaf(&ledger.get_tx_out_by_index(index)?)))
+        .collect::<Result<Vec<_>, Error>>()?;
+    let mut width = 1;
+    while width < num_leaves_full_tree {
+        level = level
+            .chunks(2)
+            .map(|pair| hash_nodes(&pair[0], pair.get(1).unwrap_or(&*NIL_HASH)))
+            .collect();
+        width *= 2;
+    }
+
+    let range = Range::new(0, num_leaves_full_tree - 1).map_err(Error::from)?;
+    Ok(TxOutMembershipElement::new(range, level[0]))
+}
+
+fn read_u32(reader: &mut impl Read) -> Result<u32, SnapshotError> {
+    let mut bytes = [0u8; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+fn read_bytes(reader: &mut impl Read, len: u64) -> Result<Vec<u8>, SnapshotError> {
+    if len > MAX_ENCODED_LEN {
+        return Err(SnapshotError::Decode);
+    }
+    let mut bytes = vec![0u8; len as usize];
+    reader.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+/// Read the next chunk, or None if the end of the snapshot was reached.
+fn read_chunk(reader: &mut impl Read) -> Result<Option<SnapshotChunk>, SnapshotError> {
+    let mut len_bytes = [0u8; 8];
+    match reader.read_exact(&mut len_bytes) {
+        Ok(()) => {}
+        Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(err) => return Err(err.into()),
+    }
+    let compressed = read_bytes(reader, u64::from_le_bytes(len_bytes))?;
+
+    let mut encoded = Vec::new();
+    DeflateDecoder::new(compressed.as_slice())
+        .take(MAX_ENCODED_LEN)
+        .read_to_end(&mut encoded)?;
+    Ok(Some(SnapshotChunk::decode(encoded.as_slice())?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::LedgerDB;
+    use mc_blockchain_test_utils::get_blocks;
+    use mc_crypto_keys::Ed25519Pair;
+    use mc_transaction_core::BlockVersion;
+    use mc_util_from_random::FromRandom;
+    use mc_util_test_helper::get_seeded_rng;
+    use tempdir::TempDir;
+
+    fn create_db() -> LedgerDB {
+        let temp_dir = TempDir::new("test").unwrap();
+        let path = temp_dir.path();
+        LedgerDB::create(path).unwrap();
+        LedgerDB::open(path).unwrap()
+    }
+
+    fn populated_db(num_blocks: usize) -> (LedgerDB, Vec<BlockData>) {
+        let mut db = create_db();
+        let blocks = get_blocks(
+            BlockVersion::MAX,
+            num_blocks,
+            2,
+            2,
+            3,
+            1 << 20,
+            None,
+            &mut get_seeded_rng(),
+        );
+        for block_data in &blocks {
+            db.append_block_data(block_data).unwrap();
+        }
+        (db, blocks)
+    }
+
+    #[test]
+    fn export_and_import_whole_ledger() {
+        let (db, blocks) = populated_db(25);
+
+        let mut snapshot = Vec::new();
+        let header = export_snapshot(&db, 24, 10, &mut snapshot).unwrap();
+        assert_eq!(header.num_blocks(), 25);
+        assert_eq!(
+            header.root_element,
+            db.get_root_tx_out_membership_element().unwrap()
+        );
+
+        let mut imported = create_db();
+        let imported_header = import_snapshot(
+            snapshot.as_slice(),
+            &mut imported,
+            &TrustAnchor::LastBlockId(blocks[24].block().id.clone()),
+        )
+        .unwrap();
+        assert_eq!(imported_header, header);
+
+        assert_eq!(imported.num_blocks().unwrap(), 25);
+        assert_eq!(imported.num_txos().unwrap(), db.num_txos().unwrap());
+        for block_data in &blocks {
+            let index = block_data.block().index;
+            assert_eq!(&imported.get_block_data(index).unwrap(), block_data);
+        }
+    }
+
+    #[test]
+    fn export_and_import_up_to_a_block_then_sync() {
+        let (db, blocks) = populated_db(12);
+
+        // Export a prefix of the ledger, where the TxOut Merkle root differs
+        // from the current one.
+        let mut snapshot = Vec::new();
+        export_snapshot(&db, 6, 4, &mut snapshot).unwrap();
+
+        let signer = *blocks[6].signature().unwrap().signer();
+        let mut imported = create_db();
+        import_snapshot(
+            snapshot.as_slice(),
+            &mut imported,
+            &TrustAnchor::SignerKeys(vec![signer]),
+        )
+        .unwrap();
+        assert_eq!(imported.num_blocks().unwrap(), 7);
+
+        // Normal syncing continues from the next block.
+        for block_data in &blocks[7..] {
+            imported.append_block_data(block_data).unwrap();
+        }
+        assert_eq!(
+            imported.get_root_tx_out_membership_element().unwrap(),
+            db.get_root_tx_out_membership_element().unwrap()
+        );
+    }
+
+    #[test]
+    fn import_rejects_bad_signature() {
+        let mut blocks = get_blocks(
+            BlockVersion::MAX,
+            5,
+            1,
+            1,
+            1,
+            1 << 20,
+            None,
+            &mut get_seeded_rng(),
+        );
+        // Sign block 3 with the signature of block 2.
+        let wrong_signature = blocks[2].signature().cloned();
+        blocks[3] = blocks[3]
+            .clone()
+            .mutate(|_, _, signature, _| *signature = wrong_signature);
+
+        let mut db = create_db();
+        for block_data in &blocks {
+            db.append_block_data(block_data).unwrap();
+        }
+
+        let mut snapshot = Vec::new();
+        export_snapshot(&db, 4, 2, &mut snapshot).unwrap();
+
+        let mut imported = create_db();
+        assert!(matches!(
+            import_snapshot(
+                snapshot.as_slice(),
+                &mut imported,
+                &TrustAnchor::LastBlockId(blocks[4].block().id.clone())
+            ),
+            Err(SnapshotError::InvalidSignature(3))
+        ));
+    }
+
+    #[test]
+    fn import_rejects_unexpected_last_block() {
+        let (db, blocks) = populated_db(5);
+
+        let mut snapshot = Vec::new();
+        export_snapshot(&db, 4, 2, &mut snapshot).unwrap();
+
+        let mut imported = create_db();
+        assert!(matches!(
+            import_snapshot(
+                snapshot.as_slice(),
+                &mut imported,
+                &TrustAnchor::LastBlockId(blocks[3].block().id.clone())
+            ),
+            Err(SnapshotError::UnexpectedLastBlockId(_, _))
+        ));
+        assert_eq!(imported.num_blocks().unwrap(), 0);
+    }
+
+    #[test]
+    fn import_rejects_untrusted_signer() {
+        let (db, blocks) = populated_db(5);
+
+        let mut snapshot = Vec::new();
+        export_snapshot(&db, 4, 2, &mut snapshot).unwrap();
+
+        // The snapshot is internally consistent, but signed by a key the
+        // importer does not trust.
+        let untrusted = Ed25519Pair::from_random(&mut get_seeded_rng()).public_key();
+        assert_ne!(&untrusted, blocks[4].signature().unwrap().signer());
+        let mut imported = create_db();
+        assert!(matches!(
+            import_snapshot(
+                snapshot.as_slice(),
+                &mut imported,
+                &TrustAnchor::SignerKeys(vec![untrusted])
+            ),
+            Err(SnapshotError::UntrustedSigner(4))
+        ));
+    }
+
+    #[test]
+    fn import_rejects_truncated_snapshot() {
+        let (db, blocks) = populated_db(5);
+
+        let mut snapshot = Vec::new();
+        export_snapshot(&db, 4, 2, &mut snapshot).unwrap();
+
+        // Drop the last chunk.
+        let mut reader = &snapshot[12..];
+        let header_len = read_u32(&mut reader).unwrap() as usize;
+        let mut offset = 16 + header_len;
+        for _ in 0..2 {
+            let len = u64::from_le_bytes(snapshot[offset..offset + 8].try_into().unwrap());
+            offset += 8 + len as usize;
+        }
+        snapshot.truncate(offset);
+
+        let mut imported = create_db();
+        assert!(matches!(
+            import_snapshot(
+                snapshot.as_slice(),
+                &mut imported,
+                &TrustAnchor::LastBlockId(blocks[4].block().id.clone())
+            ),
+            Err(SnapshotError::Truncated(4, 5))
+        ));
+    }
+}
@@ -17,11 +17,83 @@ use mc_ledger_db::{
     tx_out_store::TX_OUT_INDEX_BY_PUBLIC_KEY_DB_NAME,
     u64_to_key_bytes, Error, MetadataStore, MintConfigStore, MintTxStore, TxOutStore,
 };
-use mc_util_lmdb::MetadataStoreError;
+use mc_util_lmdb::{MigrationError, MigrationPlan, MigrationStep, Migrations, StepProgress};
 use mc_util_serial::decode;
 use std::path::Path;
 
+/// The number of records each migration step processes per LMDB transaction.
+const BATCH_SIZE: u64 = 100_000;
+
+/// The migration steps of the ledger db.
+pub fn ledger_db_migrations() -> Migrations<LedgerDbMetadataStoreSettings> {
+    ledger_db_migrations_with_batch_size(BATCH_SIZE)
+}
+
+/// The migration steps of the ledger db, processing `batch_size` records per
+/// LMDB transaction.
+fn ledger_db_migrations_with_batch_size(
+    batch_size: u64,
+) -> Migrations<LedgerDbMetadataStoreSettings> {
+    Migrations::new()
+        .with_step(TxOutIndexByPublicKeyStep { batch_size })
+        .with_step(BlockNumberByTxOutIndexStep { batch_size })
+        .with_step(MintStoresStep { batch_size })
+}
+
+/// Migrate the ledger db to the latest version.
+///
+/// Migrating is resumable: if it is interrupted, running it again continues
+/// from the last batch it committed.
 pub fn migrate(ledger_db_path: impl AsRef<Path>, logger: &Logger) {
+    let (env, metadata_store) = open_ledger_db(ledger_db_path);
+
+    let version = ledger_db_migrations()
+        .migrate(&env, &metadata_store, |event| {
+            log::info!(logger, "Ledger db {}", event)
+        })
+        .unwrap_or_else(|err| panic!("Error while migrating: {}", err));
+    log::info!(
+        logger,
+        "Ledger db is compatible with latest version: {:?}",
+        version
+    );
+}
+
+/// Find the migration steps the ledger db needs, without modifying it.
+pub fn dry_run(ledger_db_path: impl AsRef<Path>, logger: &Logger) -> MigrationPlan {
+    let (env, metadata_store) = open_ledger_db(ledger_db_path);
+
+    let plan = ledger_db_migrations()
+        .plan(&env, &metadata_store)
+        .unwrap_or_else(|err| panic!("Error while planning migration: {}", err));
+    log::info!(
+        logger,
+        "Ledger db is currently at version {}, latest version is {}",
+        plan.current_version,
+        plan.latest_version
+    );
+    if let Some(checkpoint) = plan.resume_checkpoint {
+        log::info!(
+            logger,
+            "An interrupted migration will resume from checkpoint {}",
+            checkpoint
+        );
+    }
+    for step in &plan.steps {
+        log::info!(
+            logger,
+            "Would migrate from version {} to {}: {}",
+            step.from_version,
+            step.to_version,
+            step.description
+        );
+    }
+    plan
+}
+
+fn open_ledger_db(
+    ledger_db_path: impl AsRef<Path>,
+) -> (Environment, MetadataStore<LedgerDbMetadataStoreSettings>) {
     // Open the LMDB database.
     let env = Environment::new()
         .set_max_dbs(MAX_LMDB_DATABASES)
@@ -33,89 +105,96 @@ pub fn migrate(ledger_db_path: impl AsRef<Path>, logger: &Logger) {
     let metadata_store = MetadataStore::<LedgerDbMetadataStoreSettings>::new(&env)
         .expect("Failed creating MetadataStore");
 
-    // Incrementally perform upgrades until we reach the current version.
-    loop {
-        // Check if the database we opened is compatible with the current
-        // implementation.
-        let db_txn = env.begin_ro_txn().expect("Failed starting ro transaction");
-        let version = metadata_store
-            .get_version(&db_txn)
-            .expect("Failed getting metadata version");
-        log::info!(logger, "Ledger db is currently at version: {:?}", version);
-        db_txn.commit().expect("Failed committing transaction");
-
-        match version.is_compatible_with_latest() {
-            Ok(_) => {
-                log::info!(logger, "Ledger db is compatible with latest version");
-                break;
-            }
-            // Version 2020_06_10 came after 2020_04_27 and introduced the TxOut public key -> index
-            // store.
-            Err(MetadataStoreError::VersionIncompatible(2020_04_27, _)) => {
-                log::info!(logger, "Ledger db migrating from version 2020_04_27 to 2020_06_10, this might take awhile...");
-
-                construct_tx_out_index_by_public_key_from_existing_data(&env, logger)
-                    .expect("Failed constructing tx out index by public key database");
-
-                let mut db_txn = env.begin_rw_txn().expect("Failed starting rw transaction");
-                metadata_store
-                    .set_version(&mut db_txn, 2020_06_10)
-                    .expect("Failed setting metadata version");
-                log::info!(
-                    logger,
-                    "Ledger db migration complete, now at version: {:?}",
-                    metadata_store.get_version(&db_txn),
-                );
-                db_txn.commit().expect("Failed committing transaction");
-            }
-            // Version 2020_07_07 came after 2020_06_10 and introduced the TxOut global index ->
-            // block index store.
-            Err(MetadataStoreError::VersionIncompatible(2020_06_10, _)) => {
-                log::info!(logger, "Ledger db migrating from version 2020_06_10 to 2020_07_07, this might take awhile...");
-
-                construct_block_number_by_tx_out_index_from_existing_data(&env, logger)
-                    .expect("Failed constructing block number by tx out index database");
-
-                let mut db_txn = env.begin_rw_txn().expect("Failed starting rw transaction");
-                metadata_store
-                    .set_version(&mut db_txn, 2020_07_07)
-                    .expect("Failed setting metadata version");
-                log::info!(
-                    logger,
-                    "Ledger db migration complete, now at version: {:?}",
-                    metadata_store.get_version(&db_txn),
-                );
-                db_txn.commit().expect("Failed committing transaction");
-            }
-            // Version 2022_02_22 came after 2020_07_07 and introduced minting.
-            Err(MetadataStoreError::VersionIncompatible(2020_07_07, _)) => {
-                log::info!(
-                    logger,
-                    "Ledger db migrating from version 2020_07_07 to 2022_02_22..."
-                );
-                MintConfigStore::create(&env).expect("Failed creating MintConfigStore");
-                MintTxStore::create(&env).expect("Failed creating MintTxStore");
-
-                backfill_empty_mint_stores(&env, logger)
-                    .expect("Failed backfilling empty mint stores");
-
-                let mut db_txn = env.begin_rw_txn().expect("Failed starting rw transaction");
-                metadata_store
-                    .set_version(&mut db_txn, 2022_02_22)
-                    .expect("Failed setting metadata version");
-                log::info!(
-                    logger,
-                    "Ledger db migration complete, now at version: {:?}",
-                    metadata_store.get_version(&db_txn),
-                );
-                db_txn.commit().expect("Failed committing transaction");
-            }
+    (env, metadata_store)
+}
 
-            // Don't know how to migrate.
-            Err(err) => {
-                panic!("Error while migrating: {:?}", err);
-            }
-        };
+fn step_failed(err: Error) -> MigrationError {
+    MigrationError::StepFailed(err.to_string())
+}
+
+/// Version 2020_06_10 came after 2020_04_27 and introduced the TxOut public key
+/// -> index store.
+struct TxOutIndexByPublicKeyStep {
+    batch_size: u64,
+}
+
+impl MigrationStep<LedgerDbMetadataStoreSettings> for TxOutIndexByPublicKeyStep {
+    fn from_version(&self) -> u64 {
+        2020_04_27
+    }
+
+    fn to_version(&self) -> u64 {
+        2020_06_10
+    }
+
+    fn description(&self) -> &'static str {
+        "construct tx_out_index_by_public_key"
+    }
+
+    fn run(
+        &self,
+        env: &Environment,
+        progress: &mut StepProgress<LedgerDbMetadataStoreSettings>,
+    ) -> Result<(), MigrationError> {
+        construct_tx_out_index_by_public_key_from_existing_data(env, progress, self.batch_size)
+    }
+}
+
+/// Version 2020_07_07 came after 2020_06_10 and introduced the TxOut global
+/// index -> block index store.
+struct BlockNumberByTxOutIndexStep {
+    batch_size: u64,
+}
+
+impl MigrationStep<LedgerDbMetadataStoreSettings> for BlockNumberByTxOutIndexStep {
+    fn from_version(&self) -> u64 {
+        2020_06_10
+    }
+
+    fn to_version(&self) -> u64 {
+        2020_07_07
+    }
+
+    fn description(&self) -> &'static str {
+        "construct block_number_by_tx_out_index"
+    }
+
+    fn run(
+        &self,
+        env: &Environment,
+        progress: &mut StepProgress<LedgerDbMetadataStoreSettings>,
+    ) -> Result<(), MigrationError> {
+        construct_block_number_by_tx_out_index_from_existing_data(env, progress, self.batch_size)
+    }
+}
+
+/// Version 2022_02_22 came after 2020_07_07 and introduced minting.
+struct MintStoresStep {
+    batch_size: u64,
+}
+
+impl MigrationStep<LedgerDbMetadataStoreSettings> for MintStoresStep {
+    fn from_version(&self) -> u64 {
+        2020_07_07
+    }
+
+    fn to_version(&self) -> u64 {
+        2022_02_22
+    }
+
+    fn description(&self) -> &'static str {
+        "backfill empty mint stores"
+    }
+
+    fn run(
+        &self,
+        env: &Environment,
+        progress: &mut StepProgress<LedgerDbMetadataStoreSettings>,
+    ) -> Result<(), MigrationError> {
+        MintConfigStore::create(env).map_err(step_failed)?;
+        MintTxStore::create(env).map_err(step_failed)?;
+
+        backfill_empty_mint_stores(env, progress, self.batch_size)
     }
 }
 
@@ -123,8 +202,9 @@ pub fn migrate(ledger_db_path: impl AsRef<Path>, logger: &Logger) {
 /// using existing data.
 fn construct_tx_out_index_by_public_key_from_existing_data(
     env: &Environment,
-    logger: &Logger,
-) -> Result<(), Error> {
+    progress: &mut StepProgress<LedgerDbMetadataStoreSettings>,
+    batch_size: u64,
+) -> Result<(), MigrationError> {
     // When constructing the tx out index by public key database, we first need to
     // create it.
     env.create_db(
@@ -133,43 +213,41 @@ fn construct_tx_out_index_by_public_key_from_existing_data(
     )?;
 
     // After the database has been created, we can use TxOutStore as normal.
-    let instance = TxOutStore::new(env)?;
-
-    let mut db_txn = env.begin_rw_txn()?;
-
-    let num_tx_outs = instance.num_tx_outs(&db_txn)?;
-    let mut percents: u64 = 0;
+    let instance = TxOutStore::new(env).map_err(step_failed)?;
     let tx_out_index_by_public_key = instance.get_tx_out_index_by_public_key_database();
 
-    for tx_out_index in 0..num_tx_outs {
-        let tx_out = instance.get_tx_out_by_index(tx_out_index, &db_txn)?;
-        db_txn.put(
-            tx_out_index_by_public_key,
-            &tx_out.public_key,
-            &u64_to_key_bytes(tx_out_index),
-            WriteFlags::NO_OVERWRITE,
-        )?;
-
-        // Throttled logging.
-        let new_percents = tx_out_index * 100 / num_tx_outs;
-        if new_percents != percents {
-            percents = new_percents;
-            log::info!(
-                logger,
-                "Constructing tx_out_index_by_public_key: {}% complete",
-                percents
-            );
+    let db_txn = env.begin_ro_txn()?;
+    let num_tx_outs = instance.num_tx_outs(&db_txn).map_err(step_failed)?;
+    db_txn.commit()?;
+
+    let mut start = progress.checkpoint();
+    while start < num_tx_outs {
+        let end = (start + batch_size).min(num_tx_outs);
+        let mut db_txn = env.begin_rw_txn()?;
+        for tx_out_index in start..end {
+            let tx_out = instance
+                .get_tx_out_by_index(tx_out_index, &db_txn)
+                .map_err(step_failed)?;
+            db_txn.put(
+                tx_out_index_by_public_key,
+                &tx_out.public_key,
+                &u64_to_key_bytes(tx_out_index),
+                WriteFlags::NO_OVERWRITE,
+            )?;
         }
+        progress.commit_batch(db_txn, end, num_tx_outs)?;
+        start = end;
     }
-    Ok(db_txn.commit()?)
+    Ok(())
 }
 
 /// A utility function for constructing the block_number_by_tx_out_index store
 /// using existing data.
 fn construct_block_number_by_tx_out_index_from_existing_data(
     env: &Environment,
-    logger: &Logger,
-) -> Result<(), Error> {
+    progress: &mut StepProgress<LedgerDbMetadataStoreSettings>,
+    batch_size: u64,
+) -> Result<(), MigrationError> {
     // When constructing the block index by tx out index database, we first need to
     // create it.
     let block_number_by_tx_out_index_db =
@@ -177,80 +255,183 @@ fn construct_block_number_by_tx_out_index_from_existing_data(
 
     // Open pre-existing databases that has data we need.
     let tx_outs_by_block_db = env.open_db(Some(TX_OUTS_BY_BLOCK_DB_NAME))?;
-    let counts_db = env.open_db(Some(COUNTS_DB_NAME))?;
+    let num_blocks = num_blocks(env)?;
 
     // After the database has been created, populate it with the existing data.
-    let mut db_txn = env.begin_rw_txn()?;
-
-    let num_blocks = key_bytes_to_u64(db_txn.get(counts_db, &NUM_BLOCKS_KEY)?);
-
-    let mut percents: u64 = 0;
-    for block_num in 0..num_blocks {
-        // Get information about the TxOuts in the block.
-        let bytes = db_txn.get(tx_outs_by_block_db, &u64_to_key_bytes(block_num))?;
-        let tx_outs_by_block: TxOutsByBlockValue = decode(bytes)?;
-
-        log::trace!(
-            logger,
-            "Assigning tx outs #{} - #{} to block #{}",
-            tx_outs_by_block.first_tx_out_index,
-            tx_outs_by_block.first_tx_out_index + tx_outs_by_block.num_tx_outs,
-            block_num,
-        );
-
-        for i in 0..tx_outs_by_block.num_tx_outs {
-            let tx_out_index = tx_outs_by_block.first_tx_out_index + i;
-
-            db_txn.put(
-                block_number_by_tx_out_index_db,
-                &u64_to_key_bytes(tx_out_index),
-                &u64_to_key_bytes(block_num),
-                WriteFlags::NO_OVERWRITE,
-            )?;
-        }
-
-        // Throttled logging.
-        let new_percents = block_num * 100 / num_blocks;
-        if new_percents != percents {
-            percents = new_percents;
-            log::info!(
-                logger,
-                "Constructing block_number_by_tx_out_index: {}% complete",
-                percents
-            );
+    let mut start = progress.checkpoint();
+    while start < num_blocks {
+        let end = (start + batch_size).min(num_blocks);
+        let mut db_txn = env.begin_rw_txn()?;
+        for block_num in start..end {
+            // Get information about the TxOuts in the block.
+            let bytes = db_txn.get(tx_outs_by_block_db, &u64_to_key_bytes(block_num))?;
+            let tx_outs_by_block: TxOutsByBlockValue = decode(bytes)?;
+
+            for i in 0..tx_outs_by_block.num_tx_outs {
+                let tx_out_index = tx_outs_by_block.first_tx_out_index + i;
+
+                db_txn.put(
+                    block_number_by_tx_out_index_db,
+                    &u64_to_key_bytes(tx_out_index),
+                    &u64_to_key_bytes(block_num),
+                    WriteFlags::NO_OVERWRITE,
+                )?;
+            }
         }
+        progress.commit_batch(db_txn, end, num_blocks)?;
+        start = end;
     }
-    Ok(db_txn.commit()?)
+    Ok(())
 }
 
 /// A utility function for backfilling empty mint tx data for all existing
 /// blocks. This is necessary because we store an empty list of mint txs for
 /// blocks that did not contain any.
-fn backfill_empty_mint_stores(env: &Environment, logger: &Logger) -> Result<(), Error> {
+fn backfill_empty_mint_stores(
+    env: &Environment,
+    progress: &mut StepProgress<LedgerDbMetadataStoreSettings>,
+    batch_size: u64,
+) -> Result<(), MigrationError> {
     // Open pre-existing databases that has data we need.
-    let mint_config_store = MintConfigStore::new(env)?;
-    let mint_tx_store = MintTxStore::new(env)?;
+    let mint_config_store = MintConfigStore::new(env).map_err(step_failed)?;
+    let mint_tx_store = MintTxStore::new(env).map_err(step_failed)?;
+    let num_blocks = num_blocks(env)?;
+
+    let mut start = progress.checkpoint();
+    while start < num_blocks {
+        let end = (start + batch_size).min(num_blocks);
+        let mut db_txn = env.begin_rw_txn()?;
+        for block_index in start..end {
+            mint_config_store
+                .write_validated_mint_config_txs(block_index, &[], &mut db_txn)
+                .map_err(step_failed)?;
+            mint_tx_store
+                .write_mint_txs(block_index, &[], &mint_config_store, &mut db_txn)
+                .map_err(step_failed)?;
+        }
+        progress.commit_batch(db_txn, end, num_blocks)?;
+        start = end;
+    }
+    Ok(())
+}
+
+/// The number of blocks in the ledger.
+fn num_blocks(env: &Environment) -> Result<u64, MigrationError> {
     let counts_db = env.open_db(Some(COUNTS_DB_NAME))?;
+    let db_txn = env.begin_ro_txn()?;
+    let num_blocks = key_bytes_to_u64(db_txn.get(counts_db, &NUM_BLOCKS_KEY)?);
+    db_txn.commit()?;
+    Ok(num_blocks)
+}
 
-    let mut db_txn = env.begin_rw_txn()?;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mc_account_keys::AccountKey;
+    use mc_blockchain_types::BlockVersion;
+    use mc_common::logger::{test_with_logger, Logger};
+    use mc_ledger_db::{
+        create_ledger_in, test_utils::initialize_ledger, Ledger, LedgerDB,
+        MINT_TXS_BY_BLOCK_DB_NAME, VALIDATED_MINT_CONFIG_TXS_BY_BLOCK_DB_NAME,
+    };
+    use mc_util_lmdb::{MigrationEvent, PlannedStep};
+    use mc_util_test_helper::{RngType, SeedableRng};
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+    use tempdir::TempDir;
+
+    const NUM_BLOCKS: u64 = 5;
+
+    /// Create a ledger with `NUM_BLOCKS` blocks, and turn it into a ledger at
+    /// version 2020_07_07 by removing the empty mint txs and mint config txs
+    /// of its blocks. The other mint stores are empty without any mints.
+    fn create_2020_07_07_ledger(path: &Path) {
+        let mut rng = RngType::from_seed([1u8; 32]);
+        let mut ledger = create_ledger_in(path);
+        let account_key = AccountKey::random(&mut rng);
+        initialize_ledger(
+            BlockVersion::MAX,
+            &mut ledger,
+            NUM_BLOCKS,
+            &account_key,
+            &mut rng,
+        );
+        drop(ledger);
+
+        let (env, metadata_store) = open_ledger_db(path);
+        let mut db_txn = env.begin_rw_txn().unwrap();
+        for db_name in [
+            VALIDATED_MINT_CONFIG_TXS_BY_BLOCK_DB_NAME,
+            MINT_TXS_BY_BLOCK_DB_NAME,
+        ] {
+            let db = env.open_db(Some(db_name)).unwrap();
+            db_txn.clear_db(db).unwrap();
+        }
+        metadata_store.set_version(&mut db_txn, 2020_07_07).unwrap();
+        db_txn.commit().unwrap();
+    }
 
-    let num_blocks = key_bytes_to_u64(db_txn.get(counts_db, &NUM_BLOCKS_KEY)?);
+    /// The blocks whose empty mint txs have been backfilled.
+    fn backfilled_blocks(env: &Environment) -> Vec<u64> {
+        let mint_tx_store = MintTxStore::new(env).unwrap();
+        let db_txn = env.begin_ro_txn().unwrap();
+        (0..NUM_BLOCKS)
+            .filter(|block_index| {
+                match mint_tx_store.get_mint_txs_by_block_index(*block_index, &db_txn) {
+                    Ok(mint_txs) => mint_txs.is_empty(),
+                    Err(Error::NotFound) => false,
+                    Err(err) => panic!("Unexpected error: {}", err),
+                }
+            })
+            .collect()
+    }
 
-    let mut percents: u64 = 0;
-    for block_index in 0..num_blocks {
-        mint_config_store.write_validated_mint_config_txs(block_index, &[], &mut db_txn)?;
-        mint_tx_store.write_mint_txs(block_index, &[], &mint_config_store, &mut db_txn)?;
-
-        // Throttled logging.
-        let new_percents = block_index * 100 / num_blocks;
-        if new_percents != percents {
-            percents = new_percents;
-            log::info!(
-                logger,
-                "Backfilling empty mint stores: {}% complete",
-                percents
-            );
+    // Test that a migration from 2020_07_07 which is interrupted in the middle of
+    // its step resumes from the last batch it committed.
+    #[test_with_logger]
+    fn migrate_resumes_interrupted_migration(logger: Logger) {
+        let temp_dir = TempDir::new("ledger_migration").unwrap();
+        create_2020_07_07_ledger(temp_dir.path());
+
+        // Migrate two blocks per batch, and stop once the first batch is
+        // committed, as if the migration was killed.
+        {
+            let (env, metadata_store) = open_ledger_db(temp_dir.path());
+            let migrations = ledger_db_migrations_with_batch_size(2);
+            let result = catch_unwind(AssertUnwindSafe(|| {
+                migrations.migrate(&env, &metadata_store, |event| {
+                    if let MigrationEvent::BatchCommitted { .. } = event {
+                        panic!("interrupted");
+                    }
+                })
+            }));
+            assert!(result.is_err());
+            assert_eq!(backfilled_blocks(&env), vec![0, 1]);
         }
+
+        let plan = dry_run(temp_dir.path(), &logger);
+        assert_eq!(plan.current_version, 2020_07_07);
+        assert_eq!(plan.latest_version, 2022_02_22);
+        assert_eq!(plan.resume_checkpoint, Some(2));
+        assert_eq!(
+            plan.steps,
+            vec![PlannedStep {
+                from_version: 2020_07_07,
+                to_version: 2022_02_22,
+                description: "backfill empty mint stores",
+            }]
+        );
+
+        // Blocks 0 and 1 cannot be backfilled twice, so the migration only
+        // succeeds if it resumes from block 2.
+        migrate(temp_dir.path(), &logger);
+        assert!(dry_run(temp_dir.path(), &logger).is_up_to_date());
+
+        {
+            let (env, _metadata_store) = open_ledger_db(temp_dir.path());
+            assert_eq!(backfilled_blocks(&env), (0..NUM_BLOCKS).collect::<Vec<_>>());
+        }
+
+        let ledger = LedgerDB::open(temp_dir.path()).unwrap();
+        assert_eq!(ledger.num_blocks().unwrap(), NUM_BLOCKS);
     }
-    Ok(db_txn.commit()?)
 }
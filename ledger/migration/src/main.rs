@@ -6,7 +6,7 @@
 
 use clap::Parser;
 use mc_common::logger::{create_app_logger, o};
-use mc_ledger_migration::migrate;
+use mc_ledger_migration::{dry_run, migrate};
 use std::{path::PathBuf, thread::sleep, time::Duration};
 
 /// Command line configuration
@@ -15,6 +15,11 @@ pub struct Config {
     /// Ledger DB path.
     #[clap(long, parse(from_os_str), env = "MC_LEDGER_DB")]
     pub ledger_db: PathBuf,
+
+    /// Only report the migration steps the ledger needs, without running
+    /// them.
+    #[clap(long, env = "MC_DRY_RUN")]
+    pub dry_run: bool,
 }
 
 fn main() {
@@ -24,7 +29,11 @@ fn main() {
     let _sentry_guard = mc_common::sentry::init();
     let (logger, _global_logger_guard) = create_app_logger(o!());
 
-    migrate(&config.ledger_db, &logger);
+    if config.dry_run {
+        dry_run(&config.ledger_db, &logger);
+    } else {
+        migrate(&config.ledger_db, &logger);
+    }
 
     // Give logger a moment to flush.
     sleep(Duration::from_secs(1));
@@ -0,0 +1,212 @@
+// Copyright (c) 2018-2022 The MobileCoin Foundation
+#![deny(missing_docs)]
+#![doc = include_str!("../README.md")]
+#![forbid(unsafe_code)]
+
+use clap::{Parser, Subcommand};
+use mc_blockchain_types::BlockID;
+use mc_common::logger::{create_app_logger, log, o, Logger};
+use mc_crypto_keys::Ed25519Public;
+use mc_ledger_db::{
+    create_ledger_in,
+    snapshot::{export_snapshot, import_snapshot, TrustAnchor, DEFAULT_BLOCKS_PER_CHUNK},
+    Ledger, LedgerDB,
+};
+use std::{
+    fs::{self, File},
+    io::{BufReader, BufWriter},
+    path::{Path, PathBuf},
+};
+
+/// Clap configuration for each subcommand this program supports.
+#[derive(Clone, Subcommand)]
+pub enum Command {
+    /// Export the blocks of a ledger to a snapshot file.
+    Export {
+        /// Path to the ledger db to export.
+        #[clap(long, parse(from_os_str), env = "MC_LEDGER_DB")]
+        ledger_db: PathBuf,
+
+        /// Index of the last block to export (optional, defaults to the last
+        /// block in the ledger).
+        #[clap(long, env = "MC_BLOCK_INDEX")]
+        block_index: Option<u64>,
+
+        /// Path of the snapshot file to write.
+        #[clap(long, parse(from_os_str), env = "MC_SNAPSHOT")]
+        snapshot: PathBuf,
+
+        /// Number of blocks to store in each compressed chunk.
+        #[clap(long, default_value_t = DEFAULT_BLOCKS_PER_CHUNK, env = "MC_BLOCKS_PER_CHUNK")]
+        blocks_per_chunk: usize,
+    },
+
+    /// Create a new ledger db from a snapshot file.
+    Import {
+        /// Path of the snapshot file to read.
+        #[clap(long, parse(from_os_str), env = "MC_SNAPSHOT")]
+        snapshot: PathBuf,
+
+        /// Path of the ledger db to create. Must not exist yet.
+        #[clap(long, parse(from_os_str), env = "MC_LEDGER_DB")]
+        ledger_db: PathBuf,
+
+        /// Hex-encoded ID of the last block in the snapshot, obtained from a
+        /// trusted source. Required unless --signer-key is given.
+        #[clap(
+            long,
+            parse(try_from_str = parse_block_id),
+            required_unless_present = "signer_keys",
+            env = "MC_EXPECTED_BLOCK_ID"
+        )]
+        expected_block_id: Option<BlockID>,
+
+        /// Hex-encoded Ed25519 public key trusted to sign blocks. The last
+        /// block in the snapshot must be signed by one of these keys. Can be
+        /// repeated, and is ignored if --expected-block-id is given.
+        #[clap(
+            long = "signer-key",
+            parse(try_from_str = parse_signer_key),
+            use_value_delimiter = true,
+            env = "MC_SIGNER_KEYS"
+        )]
+        signer_keys: Vec<Ed25519Public>,
+    },
+}
+
+/// Command line configuration.
+#[derive(Parser)]
+#[clap(
+    name = "ledger-snapshot",
+    about = "Export and import verified ledger snapshots."
+)]
+pub struct Config {
+    /// Command to run.
+    #[clap(subcommand)]
+    pub command: Command,
+}
+
+fn parse_block_id(src: &str) -> Result<BlockID, String> {
+    let bytes = hex::decode(src).map_err(|err| format!("Invalid hex: {}", err))?;
+    BlockID::try_from(&bytes[..]).map_err(|err| format!("Invalid block id: {}", err))
+}
+
+fn parse_signer_key(src: &str) -> Result<Ed25519Public, String> {
+    let bytes = hex::decode(src).map_err(|err| format!("Invalid hex: {}", err))?;
+    Ed25519Public::try_from(&bytes[..]).map_err(|err| format!("Invalid signer key: {}", err))
+}
+
+fn main() {
+    mc_common::setup_panic_handler();
+    let (logger, _global_logger_guard) = create_app_logger(o!());
+
+    let config = Config::parse();
+
+    match config.command {
+        Command::Export {
+            ledger_db,
+            block_index,
+            snapshot,
+            blocks_per_chunk,
+        } => export(
+            &ledger_db,
+            block_index,
+            &snapshot,
+            blocks_per_chunk,
+            &logger,
+        ),
+        Command::Import {
+            snapshot,
+            ledger_db,
+            expected_block_id,
+            signer_keys,
+        } => {
+            let trust_anchor = match expected_block_id {
+                Some(block_id) => TrustAnchor::LastBlockId(block_id),
+                None => TrustAnchor::SignerKeys(signer_keys),
+            };
+            import(&snapshot, &ledger_db, &trust_anchor, &logger)
+        }
+    }
+}
+
+fn export(
+    ledger_db: &Path,
+    block_index: Option<u64>,
+    snapshot: &Path,
+    blocks_per_chunk: usize,
+    logger: &Logger,
+) {
+    let ledger = LedgerDB::open(ledger_db).expect("Could not open ledger db");
+    let block_index = block_index.unwrap_or_else(|| {
+        ledger
+            .num_blocks()
+            .expect("Could not get number of blocks")
+            .checked_sub(1)
+            .expect("The ledger is empty")
+    });
+
+    log::info!(
+        logger,
+        "Exporting blocks 0 to {} of {} to {}",
+        block_index,
+        ledger_db.display(),
+        snapshot.display()
+    );
+    let file = File::create(snapshot).expect("Could not create snapshot file");
+    let header = export_snapshot(&ledger, block_index, blocks_per_chunk, BufWriter::new(file))
+        .expect("Could not export snapshot");
+
+    log::info!(
+        logger,
+        "Exported {} blocks, last block id: {}",
+        header.num_blocks(),
+        hex::encode(&header.last_block_id)
+    );
+}
+
+fn import(snapshot: &Path, ledger_db: &Path, trust_anchor: &TrustAnchor, logger: &Logger) {
+    assert!(
+        !ledger_db.exists(),
+        "{} already exists, refusing to overwrite it",
+        ledger_db.display()
+    );
+
+    // Import into a temporary directory next to the destination, so that a
+    // failed import never leaves a partial ledger behind.
+    let mut importing_path = ledger_db.as_os_str().to_owned();
+    importing_path.push(".importing");
+    let importing_path = PathBuf::from(importing_path);
+    if importing_path.exists() {
+        fs::remove_dir_all(&importing_path).expect("Could not remove previous partial import");
+    }
+
+    log::info!(
+        logger,
+        "Importing {} into {}",
+        snapshot.display(),
+        ledger_db.display()
+    );
+    let file = File::open(snapshot).expect("Could not open snapshot file");
+    let result = {
+        let mut ledger = create_ledger_in(&importing_path);
+        import_snapshot(BufReader::new(file), &mut ledger, trust_anchor)
+    };
+
+    match result {
+        Ok(header) => {
+            fs::rename(&importing_path, ledger_db).expect("Could not move imported ledger db");
+            log::info!(
+                logger,
+                "Imported {} blocks, last block id: {}. Syncing can continue from block {}.",
+                header.num_blocks(),
+                hex::encode(&header.last_block_id),
+                header.num_blocks()
+            );
+        }
+        Err(err) => {
+            let _ = fs::remove_dir_all(&importing_path);
+            panic!("Could not import snapshot: {}", err);
+        }
+    }
+}
@@ -4,6 +4,7 @@ mod ledger_sync;
 mod metadata_provider;
 mod network_state;
 mod reqwest_transactions_fetcher;
+mod streaming_transactions_fetcher;
 mod transactions_fetcher_trait;
 
 #[cfg(any(test, feature = "test_utils"))]
@@ -17,5 +18,8 @@ pub use crate::{
     metadata_provider::{BlockMetadataProvider, PassThroughMetadataProvider},
     network_state::{NetworkState, PollingNetworkState, SCPNetworkState},
     reqwest_transactions_fetcher::{ReqwestTransactionsFetcher, ReqwestTransactionsFetcherError},
+    streaming_transactions_fetcher::{
+        StreamingTransactionsFetcher, StreamingTransactionsFetcherError,
+    },
     transactions_fetcher_trait::{TransactionFetcherError, TransactionsFetcher},
 };
@@ -11,17 +11,18 @@ use mc_common::{
     ResponderId,
 };
 use mc_connection::{
-    BlockInfo, BlockchainConnection, Connection, ConnectionManager, RetryableBlockchainConnection,
+    BlockInfo, BlockchainConnection, Connection, ConnectionManager, PeerHealthScore,
+    RetryableBlockchainConnection,
 };
 use mc_consensus_scp::{ballot::Ballot, msg::ExternalizePayload, Msg, QuorumSet, SlotIndex, Topic};
 use mc_util_uri::ConnectionUri;
 use retry::delay::{jitter, Fibonacci};
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{BTreeMap, HashMap, HashSet},
     str::FromStr,
     sync::{Arc, Condvar, Mutex},
     thread,
-    time::Duration,
+    time::{Duration, Instant},
 };
 
 // Since PollingNetworkState is not a full-fledged consensus node, it does not
@@ -83,6 +84,7 @@ impl<BC: BlockchainConnection + 'static> PollingNetworkState<BC> {
                 .expect("Could not get host and port responder_id from URI");
 
             let thread_logger = self.logger.clone();
+            let thread_manager = self.manager.clone();
             let thread_results_and_condvar = results_and_condvar.clone();
             thread::Builder::new()
                 .name(format!("Poll:{}", responder_id))
@@ -91,7 +93,9 @@ impl<BC: BlockchainConnection + 'static> PollingNetworkState<BC> {
 
                     let &(ref lock, ref condvar) = &*thread_results_and_condvar;
 
+                    let start = Instant::now();
                     let block_info_result = conn.fetch_block_info(Self::get_retry_iterator());
+                    let latency = start.elapsed();
 
                     let mut results = lock.lock().expect("mutex poisoned");
 
@@ -103,6 +107,8 @@ impl<BC: BlockchainConnection + 'static> PollingNetworkState<BC> {
                                 conn,
                                 info.block_index
                             );
+                            thread_manager.record_success(&responder_id, latency);
+                            thread_manager.record_block_index(&responder_id, info.block_index);
                             results.insert(responder_id.clone(), Some(info.clone()));
                         }
                         Err(err) => {
@@ -112,6 +118,7 @@ impl<BC: BlockchainConnection + 'static> PollingNetworkState<BC> {
                                 conn,
                                 err
                             );
+                            thread_manager.record_failure(&responder_id);
                             results.insert(responder_id.clone(), None);
                         }
                     }
@@ -161,6 +168,11 @@ impl<BC: BlockchainConnection + 'static> PollingNetworkState<BC> {
         &self.block_infos
     }
 
+    /// The health of each polled peer, as tracked by the connection manager.
+    pub fn peer_health_scores(&self) -> BTreeMap<ResponderId, PeerHealthScore> {
+        self.manager.health_scores()
+    }
+
     fn get_retry_iterator() -> Box<dyn Iterator<Item = Duration>> {
         // Start at 50ms, make 10 attempts (total would be 7150ms)
         Box::new(Fibonacci::from_millis(50).take(10).map(jitter))
@@ -0,0 +1,623 @@
+// Copyright (c) 2018-2022 The MobileCoin Foundation
+
+//! Implementation of the `TransactionsFetcher` trait that receives block data
+//! pushed by a consensus node over the `SubscribeBlocks` streaming RPC, rather
+//! than polling for it.
+
+use crate::transactions_fetcher_trait::{TransactionFetcherError, TransactionsFetcher};
+use displaydoc::Display;
+use futures::{
+    channel::mpsc::{unbounded, UnboundedReceiver, UnboundedSender},
+    executor::block_on,
+    future::{select, Either},
+    StreamExt,
+};
+use grpcio::{CallOption, ChannelBuilder, Environment, MetadataBuilder};
+use mc_blockchain_types::{Block, BlockData, BlockIndex};
+use mc_common::{
+    logger::{log, o, Logger},
+    ResponderId,
+};
+use mc_connection::CredentialsProvider;
+use mc_consensus_api::{
+    consensus_common::SubscribeBlocksRequest, consensus_common_grpc::BlockchainApiClient,
+};
+use mc_ledger_db::Ledger;
+use mc_util_grpc::{ConnectionUriGrpcioChannel, CHAIN_ID_GRPC_HEADER};
+use mc_util_uri::ConsensusClientUri;
+use std::{
+    collections::BTreeMap,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Condvar, Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+/// Default maximum number of received blocks to buffer. Once the buffer is
+/// full, the subscription is dropped, and resumed from the next missing block
+/// once blocks are consumed or appended to the local ledger.
+pub const DEFAULT_MAX_BUFFERED_BLOCKS: usize = 1000;
+
+/// How long `get_block_data` waits for a block to arrive before giving up.
+pub const DEFAULT_BLOCK_WAIT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How long to wait before re-subscribing after the stream ended or failed.
+const RESUBSCRIBE_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Display)]
+pub enum StreamingTransactionsFetcherError {
+    /// Timed out waiting for block #{0}
+    Timeout(BlockIndex),
+
+    /// Received an invalid block #{0}: {1}
+    InvalidBlockReceived(BlockIndex, String),
+}
+
+impl TransactionFetcherError for StreamingTransactionsFetcherError {}
+
+/// State shared between the fetcher and its subscription thread.
+struct SubscriptionState {
+    /// Blocks received from the stream that were not consumed yet.
+    blocks: BTreeMap<BlockIndex, BlockData>,
+
+    /// The index of the next block expected from the stream. This is where the
+    /// subscription resumes from if the stream gets interrupted, and it is
+    /// moved back when a block that is no longer buffered is asked for.
+    next_index: BlockIndex,
+
+    /// Maximum number of blocks to buffer.
+    max_buffered_blocks: usize,
+}
+
+impl SubscriptionState {
+    /// Drop the buffered blocks that are already in the local ledger. Nothing
+    /// else is evicted, since callers may ask for blocks in any order, and ask
+    /// again for a block they failed to append.
+    fn evict_synced_blocks(&mut self, ledger: &impl Ledger, logger: &Logger) {
+        match ledger.num_blocks() {
+            Ok(num_blocks) => self.blocks = self.blocks.split_off(&num_blocks),
+            Err(err) => log::warn!(logger, "Failed getting the number of blocks: {}", err),
+        }
+    }
+
+    fn is_full(&self) -> bool {
+        self.blocks.len() >= self.max_buffered_blocks
+    }
+}
+
+/// A `TransactionsFetcher` that subscribes to the blocks of a single consensus
+/// node, and hands out the blocks it receives. Received blocks are only
+/// returned if they match the block the caller asks for, so the node does not
+/// need to be trusted.
+pub struct StreamingTransactionsFetcher<L: Ledger + Clone + Sync + 'static> {
+    /// State shared with the subscription thread, and a condition variable
+    /// signaled whenever it changes.
+    state: Arc<(Mutex<SubscriptionState>, Condvar)>,
+
+    /// Set when the fetcher is dropped, to stop the subscription thread.
+    stop_requested: Arc<AtomicBool>,
+
+    /// Wakes the subscription thread up while it waits on the stream, when
+    /// `next_index` was moved back or a stop was requested.
+    wakeup_sender: UnboundedSender<()>,
+
+    /// How long to wait for a block to arrive.
+    block_wait_timeout: Duration,
+
+    /// The local ledger, used to drop buffered blocks once they are synced.
+    ledger: L,
+
+    /// Logger.
+    logger: Logger,
+}
+
+impl<L: Ledger + Clone + Sync + 'static> StreamingTransactionsFetcher<L> {
+    /// Subscribe to the blocks of a consensus node, starting after the last
+    /// block in the local ledger.
+    pub fn new<CP: CredentialsProvider + 'static>(
+        uri: &ConsensusClientUri,
+        env: Arc<Environment>,
+        chain_id: String,
+        credentials_provider: CP,
+        ledger: L,
+        logger: Logger,
+    ) -> Self {
+        let logger = logger.new(o!("mc.cxn" => uri.to_string()));
+
+        let ch = ChannelBuilder::default_channel_builder(env).connect_to_uri(uri, &logger);
+        let client = BlockchainApiClient::new(ch);
+
+        let from_index = ledger
+            .num_blocks()
+            .expect("failed getting the number of blocks in the ledger");
+        let state = Arc::new((
+            Mutex::new(SubscriptionState {
+                blocks: BTreeMap::new(),
+                next_index: from_index,
+                max_buffered_blocks: DEFAULT_MAX_BUFFERED_BLOCKS,
+            }),
+            Condvar::new(),
+        ));
+        let stop_requested = Arc::new(AtomicBool::new(false));
+        let (wakeup_sender, wakeup_receiver) = unbounded();
+
+        let thread_state = state.clone();
+        let thread_stop_requested = stop_requested.clone();
+        let thread_ledger = ledger.clone();
+        let thread_logger = logger.clone();
+        thread::Builder::new()
+            .name(format!("subscribe_blocks:{}", uri))
+            .spawn(move || {
+                subscription_thread_entry_point(
+                    client,
+                    chain_id,
+                    credentials_provider,
+                    thread_state,
+                    thread_stop_requested,
+                    wakeup_receiver,
+                    thread_ledger,
+                    thread_logger,
+                )
+            })
+            .expect("failed spawning block subscription thread");
+
+        Self {
+            state,
+            stop_requested,
+            wakeup_sender,
+            block_wait_timeout: DEFAULT_BLOCK_WAIT_TIMEOUT,
+            ledger,
+            logger,
+        }
+    }
+
+    pub fn set_block_wait_timeout(&mut self, block_wait_timeout: Duration) {
+        self.block_wait_timeout = block_wait_timeout;
+    }
+
+    pub fn set_max_buffered_blocks(&mut self, max_buffered_blocks: usize) {
+        let (lock, condvar) = &*self.state;
+        lock.lock().expect("mutex poisoned").max_buffered_blocks = max_buffered_blocks;
+        condvar.notify_all();
+    }
+
+    /// Wait for the given block to be received, and take it out of the
+    /// buffer.
+    pub fn get_block_data_by_index(
+        &self,
+        block_index: BlockIndex,
+        expected_block: Option<&Block>,
+    ) -> Result<BlockData, StreamingTransactionsFetcherError> {
+        // Sanity test.
+        if let Some(expected_block) = expected_block {
+            assert_eq!(block_index, expected_block.index);
+        }
+
+        let (lock, condvar) = &*self.state;
+        let deadline = Instant::now() + self.block_wait_timeout;
+        let mut state = lock.lock().expect("mutex poisoned");
+
+        loop {
+            if let Some(block_data) = state.blocks.remove(&block_index) {
+                state.evict_synced_blocks(&self.ledger, &self.logger);
+                condvar.notify_all();
+
+                if let Some(expected_block) = expected_block {
+                    if block_data.block() != expected_block {
+                        return Err(StreamingTransactionsFetcherError::InvalidBlockReceived(
+                            block_index,
+                            "block data mismatch".to_string(),
+                        ));
+                    }
+                }
+
+                log::trace!(self.logger, "Got block #{} from stream", block_index);
+                return Ok(block_data);
+            }
+
+            if block_index < state.next_index {
+                // The block was streamed and handed out before, e.g. to a caller that
+                // failed to append it. Have the subscription resume from it.
+                log::debug!(
+                    self.logger,
+                    "Block #{} is no longer buffered, re-subscribing from it",
+                    block_index
+                );
+                state.next_index = block_index;
+                condvar.notify_all();
+                let _ = self.wakeup_sender.unbounded_send(());
+            }
+
+            let now = Instant::now();
+            if now >= deadline {
+                return Err(StreamingTransactionsFetcherError::Timeout(block_index));
+            }
+
+            state = condvar
+                .wait_timeout(state, deadline - now)
+                .expect("mutex poisoned")
+                .0;
+        }
+    }
+}
+
+impl<L: Ledger + Clone + Sync + 'static> Drop for StreamingTransactionsFetcher<L> {
+    fn drop(&mut self) {
+        // The subscription thread may be blocked waiting for the next block, so we
+        // do not join it. It exits as soon as it notices the stop request.
+        self.stop_requested.store(true, Ordering::SeqCst);
+        self.state.1.notify_all();
+        let _ = self.wakeup_sender.unbounded_send(());
+    }
+}
+
+impl<L: Ledger + Clone + Sync + 'static> TransactionsFetcher for StreamingTransactionsFetcher<L> {
+    type Error = StreamingTransactionsFetcherError;
+
+    fn get_block_data(
+        &self,
+        _safe_responder_ids: &[ResponderId],
+        block: &Block,
+    ) -> Result<BlockData, Self::Error> {
+        self.get_block_data_by_index(block.index, Some(block))
+    }
+}
+
+fn subscription_thread_entry_point<CP: CredentialsProvider>(
+    client: BlockchainApiClient,
+    chain_id: String,
+    credentials_provider: CP,
+    state: Arc<(Mutex<SubscriptionState>, Condvar)>,
+    stop_requested: Arc<AtomicBool>,
+    mut wakeup_receiver: UnboundedReceiver<()>,
+    ledger: impl Ledger,
+    logger: Logger,
+) {
+    let (lock, condvar) = &*state;
+
+    while !stop_requested.load(Ordering::SeqCst) {
+        // Only subscribe once there is room in the buffer.
+        let from_index = {
+            let mut state = lock.lock().expect("mutex poisoned");
+            loop {
+                state.evict_synced_blocks(&ledger, &logger);
+                if !state.is_full() || stop_requested.load(Ordering::SeqCst) {
+                    break;
+                }
+                state = condvar
+                    .wait_timeout(state, RESUBSCRIBE_INTERVAL)
+                    .expect("mutex poisoned")
+                    .0;
+            }
+            if stop_requested.load(Ordering::SeqCst) {
+                return;
+            }
+            state.next_index
+        };
+        log::debug!(logger, "Subscribing to blocks starting at #{}", from_index);
+
+        let mut request = SubscribeBlocksRequest::new();
+        request.set_from_index(from_index);
+
+        let stream = call_option(&chain_id, &credentials_provider).and_then(|call_option| {
+            client
+                .subscribe_blocks_opt(&request, call_option)
+                .map_err(|err| err.to_string())
+        });
+        let mut stream = match stream {
+            Ok(stream) => stream,
+            Err(err) => {
+                log::warn!(logger, "Failed subscribing to blocks: {}", err);
+                thread::sleep(RESUBSCRIBE_INTERVAL);
+                continue;
+            }
+        };
+
+        // Whether to wait before re-subscribing, which is only needed if the stream
+        // ended or failed.
+        let mut stream_failed = true;
+        let mut expected_index = from_index;
+        loop {
+            let item = match block_on(select(stream.next(), wakeup_receiver.next())) {
+                Either::Left((Some(item), _)) => item,
+                Either::Left((None, _)) => break,
+                Either::Right((Some(()), _)) => {
+                    if stop_requested.load(Ordering::SeqCst) {
+                        return;
+                    }
+                    if lock.lock().expect("mutex poisoned").next_index != expected_index {
+                        stream_failed = false;
+                        break;
+                    }
+                    continue;
+                }
+                // The fetcher was dropped.
+                Either::Right((None, _)) => return,
+            };
+            let block_data = match item
+                .map_err(|err| err.to_string())
+                .and_then(|archive_block| {
+                    BlockData::try_from(&archive_block).map_err(|err| err.to_string())
+                }) {
+                Ok(block_data) => block_data,
+                Err(err) => {
+                    log::warn!(logger, "Block stream failed: {}", err);
+                    break;
+                }
+            };
+
+            let mut state = lock.lock().expect("mutex poisoned");
+            if stop_requested.load(Ordering::SeqCst) {
+                return;
+            }
+
+            // This also happens when a caller asked for a block that is no longer
+            // buffered, and moved `next_index` back.
+            let block_index = block_data.block().index;
+            if block_index != state.next_index {
+                log::debug!(
+                    logger,
+                    "Expected block #{} but got #{}, re-subscribing",
+                    state.next_index,
+                    block_index
+                );
+                stream_failed = expected_index == state.next_index;
+                break;
+            }
+
+            state.blocks.insert(block_index, block_data);
+            state.next_index += 1;
+            expected_index = state.next_index;
+            condvar.notify_all();
+
+            // Rather than leaving the stream stalled while the buffer is full, drop
+            // it, and resume from the next block once there is room.
+            if state.is_full() {
+                state.evict_synced_blocks(&ledger, &logger);
+                if state.is_full() {
+                    log::debug!(
+                        logger,
+                        "Block buffer is full, pausing the subscription at #{}",
+                        state.next_index
+                    );
+                    stream_failed = false;
+                    break;
+                }
+            }
+        }
+
+        if stream_failed {
+            thread::sleep(RESUBSCRIBE_INTERVAL);
+        }
+    }
+}
+
+/// Build the call options for a subscription, including credentials and the
+/// chain id.
+fn call_option<CP: CredentialsProvider>(
+    chain_id: &str,
+    credentials_provider: &CP,
+) -> Result<CallOption, String> {
+    let mut metadata_builder = MetadataBuilder::new();
+
+    if let Some(creds) = credentials_provider
+        .get_credentials()
+        .map_err(|err| err.to_string())?
+    {
+        if !creds.username().is_empty() && !creds.password().is_empty() {
+            metadata_builder
+                .add_str("Authorization", &creds.authorization_header())
+                .expect("Error setting authorization header");
+        }
+    }
+
+    if !chain_id.is_empty() {
+        metadata_builder
+            .add_str(CHAIN_ID_GRPC_HEADER, chain_id)
+            .expect("Error setting chain-id header");
+    }
+
+    Ok(CallOption::default().headers(metadata_builder.build()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::{FutureExt, SinkExt, TryFutureExt};
+    use grpcio::{
+        RpcContext, RpcStatus, RpcStatusCode, Server, ServerBuilder, ServerStreamingSink,
+        UnarySink, WriteFlags,
+    };
+    use mc_blockchain_test_utils::get_blocks;
+    use mc_common::logger::test_with_logger;
+    use mc_connection::HardcodedCredentialsProvider;
+    use mc_consensus_api::{
+        blockchain::ArchiveBlock,
+        consensus_common::{BlocksRequest, BlocksResponse, LastBlockInfoResponse},
+        consensus_common_grpc::{self, BlockchainApi},
+        empty::Empty,
+    };
+    use mc_ledger_db::test_utils::MockLedger;
+    use mc_transaction_core::BlockVersion;
+    use mc_util_test_helper::get_seeded_rng;
+    use std::str::FromStr;
+
+    /// A `BlockchainApi` that streams a fixed set of blocks.
+    #[derive(Clone)]
+    struct TestBlockchainApi {
+        blocks: Arc<Vec<BlockData>>,
+    }
+
+    impl BlockchainApi for TestBlockchainApi {
+        fn get_last_block_info(
+            &mut self,
+            ctx: RpcContext,
+            _request: Empty,
+            sink: UnarySink<LastBlockInfoResponse>,
+        ) {
+            ctx.spawn(
+                sink.fail(RpcStatus::new(RpcStatusCode::UNIMPLEMENTED))
+                    .map(|_| ()),
+            );
+        }
+
+        fn get_blocks(
+            &mut self,
+            ctx: RpcContext,
+            _request: BlocksRequest,
+            sink: UnarySink<BlocksResponse>,
+        ) {
+            ctx.spawn(
+                sink.fail(RpcStatus::new(RpcStatusCode::UNIMPLEMENTED))
+                    .map(|_| ()),
+            );
+        }
+
+        fn subscribe_blocks(
+            &mut self,
+            ctx: RpcContext,
+            request: SubscribeBlocksRequest,
+            mut sink: ServerStreamingSink<ArchiveBlock>,
+        ) {
+            let blocks = self.blocks.clone();
+            ctx.spawn(
+                async move {
+                    for block_data in blocks.iter().skip(request.from_index as usize) {
+                        sink.send((ArchiveBlock::from(block_data), WriteFlags::default()))
+                            .await?;
+                    }
+                    sink.close().await
+                }
+                .map_err(|_: grpcio::Error| ())
+                .map(|_| ()),
+            );
+        }
+    }
+
+    /// Starts a server streaming the given blocks and returns its URI.
+    fn start_server(blocks: Vec<BlockData>) -> (Server, ConsensusClientUri) {
+        let service = consensus_common_grpc::create_blockchain_api(TestBlockchainApi {
+            blocks: Arc::new(blocks),
+        });
+        let env = Arc::new(Environment::new(1));
+        let mut server = ServerBuilder::new(env)
+            .register_service(service)
+            .bind("127.0.0.1", 0)
+            .build()
+            .unwrap();
+        server.start();
+        let (_, port) = server.bind_addrs().next().unwrap();
+        let uri =
+            ConsensusClientUri::from_str(&format!("insecure-mc://127.0.0.1:{}/", port)).unwrap();
+        (server, uri)
+    }
+
+    /// Creates a ledger holding the given blocks.
+    fn create_ledger(blocks: &[BlockData]) -> MockLedger {
+        let mut ledger = MockLedger::default();
+        for block_data in blocks {
+            ledger.set_block_data(block_data.clone());
+        }
+        ledger
+    }
+
+    fn create_fetcher(
+        uri: &ConsensusClientUri,
+        ledger: MockLedger,
+        logger: Logger,
+    ) -> StreamingTransactionsFetcher<MockLedger> {
+        StreamingTransactionsFetcher::new(
+            uri,
+            Arc::new(Environment::new(1)),
+            String::new(),
+            HardcodedCredentialsProvider::default(),
+            ledger,
+            logger,
+        )
+    }
+
+    #[test_with_logger]
+    fn get_block_data_returns_streamed_blocks(logger: Logger) {
+        let mut rng = get_seeded_rng();
+        let blocks = get_blocks(BlockVersion::MAX, 10, 2, 1, 1, 42, None, &mut rng);
+        let (_server, uri) = start_server(blocks.clone());
+
+        let fetcher = create_fetcher(&uri, create_ledger(&blocks[..3]), logger);
+
+        for expected_block_data in &blocks[3..] {
+            let block_data = fetcher
+                .get_block_data(&[], expected_block_data.block())
+                .unwrap();
+            assert_eq!(&block_data, expected_block_data);
+        }
+    }
+
+    #[test_with_logger]
+    fn get_block_data_out_of_order_and_retried(logger: Logger) {
+        let mut rng = get_seeded_rng();
+        let blocks = get_blocks(BlockVersion::MAX, 10, 2, 1, 1, 42, None, &mut rng);
+        let (_server, uri) = start_server(blocks.clone());
+
+        let mut ledger = create_ledger(&blocks[..3]);
+        let fetcher = create_fetcher(&uri, ledger.clone(), logger);
+
+        // Fetching a block does not evict the blocks before it.
+        for index in [6, 4, 3, 5] {
+            let block_data = fetcher.get_block_data(&[], blocks[index].block()).unwrap();
+            assert_eq!(block_data, blocks[index]);
+        }
+
+        // A block that was already handed out, e.g. to a caller that failed to
+        // append it, is streamed again.
+        let block_data = fetcher.get_block_data(&[], blocks[4].block()).unwrap();
+        assert_eq!(block_data, blocks[4]);
+
+        // Once blocks are in the ledger, they are no longer buffered.
+        for block_data in &blocks[3..7] {
+            ledger.set_block_data(block_data.clone());
+        }
+        let block_data = fetcher.get_block_data(&[], blocks[8].block()).unwrap();
+        assert_eq!(block_data, blocks[8]);
+        {
+            let state = fetcher.state.0.lock().unwrap();
+            assert!(state.blocks.keys().all(|index| *index >= 7));
+        }
+
+        for index in [9, 7] {
+            let block_data = fetcher.get_block_data(&[], blocks[index].block()).unwrap();
+            assert_eq!(block_data, blocks[index]);
+        }
+    }
+
+    #[test_with_logger]
+    fn get_block_data_rejects_mismatching_blocks(logger: Logger) {
+        let mut rng = get_seeded_rng();
+        let blocks = get_blocks(BlockVersion::MAX, 3, 2, 1, 1, 42, None, &mut rng);
+        let other_blocks = get_blocks(BlockVersion::MAX, 3, 2, 1, 1, 42, None, &mut rng);
+        let (_server, uri) = start_server(blocks);
+
+        let fetcher = create_fetcher(&uri, MockLedger::default(), logger);
+
+        match fetcher.get_block_data(&[], other_blocks[0].block()) {
+            Err(StreamingTransactionsFetcherError::InvalidBlockReceived(0, _)) => {}
+            other => panic!("Unexpected result {:?}", other),
+        }
+    }
+
+    #[test_with_logger]
+    fn get_block_data_times_out(logger: Logger) {
+        let mut rng = get_seeded_rng();
+        let blocks = get_blocks(BlockVersion::MAX, 4, 2, 1, 1, 42, None, &mut rng);
+        let (_server, uri) = start_server(blocks[..3].to_vec());
+
+        let mut fetcher = create_fetcher(&uri, MockLedger::default(), logger);
+        fetcher.set_block_wait_timeout(Duration::from_millis(500));
+
+        match fetcher.get_block_data(&[], blocks[3].block()) {
+            Err(StreamingTransactionsFetcherError::Timeout(3)) => {}
+            other => panic!("Unexpected result {:?}", other),
+        }
+    }
+}
@@ -7,11 +7,12 @@ use grpcio::{EnvBuilder, ServerBuilder};
 use mc_common::logger::{log, o, Logger};
 use mc_ledger_db::{Ledger, LedgerDB};
 use mc_mint_auditor::{
+    bridge::BridgeSyncThread,
     db::{
         transaction, AuditedBurn, AuditedMint, BlockAuditData, BlockBalance, Conn, MintAuditorDb,
         SyncBlockData,
     },
-    gnosis::{GnosisSafeConfig, GnosisSyncThread},
+    gnosis::GnosisSafeConfig,
     http_api::start_http_server,
     Error, MintAuditorService,
 };
@@ -53,7 +54,8 @@ pub enum Command {
         admin_listen_uri: Option<AdminUri>,
 
         /// Gnosis safe configuration file (json/toml).
-        /// When provided, the configured gnosis safe(s) will be audited.
+        /// When provided, the configured safe(s) will be audited, using either
+        /// the Gnosis API or a ledger file as the source of their transactions.
         #[clap(long, env = "MC_GNOSIS_SAFE_CONFIG", parse(try_from_str = parse_gnosis_safe_config))]
         gnosis_safe_config: Option<GnosisSafeConfig>,
     },
@@ -201,18 +203,18 @@ fn cmd_scan_ledger(
         .expect("Failed starting admin grpc server")
     });
 
-    let _gnosis_safe_fetcher_threads = gnosis_safe_config.as_ref().map(|gnosis_safe_config| {
+    let _bridge_sync_threads = gnosis_safe_config.as_ref().map(|gnosis_safe_config| {
         gnosis_safe_config
             .safes
             .iter()
             .map(|safe_config| {
-                GnosisSyncThread::start(
+                BridgeSyncThread::start_for_safe(
                     safe_config,
                     mint_auditor_db.clone(),
                     poll_interval,
                     logger.clone(),
                 )
-                .expect("Failed starting gnosis safe fetcher thread")
+                .expect("Failed starting bridge sync thread")
             })
             .collect::<Vec<_>>()
     });
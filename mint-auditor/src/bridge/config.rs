@@ -0,0 +1,136 @@
+// Copyright (c) 2018-2022 The MobileCoin Foundation
+
+//! Configuration for where the transactions of an audited safe are obtained
+//! from.
+
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use url::Url;
+
+/// Where the transactions of an audited safe are obtained from. This is
+/// flattened into the safe configuration, so the fields that are present
+/// select the source.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum BridgeSourceConfig {
+    /// Sync from a Gnosis safe transaction service.
+    Gnosis {
+        /// The Gnosis safe transaction service API endpoint to sync from.
+        api_url: Url,
+    },
+
+    /// Sync from a ledger file exported by the custodian.
+    LedgerFile(LedgerFileConfig),
+}
+
+/// Supported ledger file formats.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LedgerFileFormat {
+    /// A JSON array of ledger entries.
+    Json,
+
+    /// A CSV file of ledger entries, with a header row.
+    Csv,
+
+    /// A JSON array of Ethereum logs, as returned by `eth_getLogs`. ERC-20
+    /// `Transfer` events into and out of the safe are treated as deposits and
+    /// withdrawals.
+    Erc20EventLog,
+}
+
+/// Configuration for syncing from a ledger file.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct LedgerFileConfig {
+    /// Path to the ledger file. The file is re-read on every poll, so the
+    /// custodian may keep appending to it.
+    pub ledger_file: PathBuf,
+
+    /// The format of the ledger file.
+    pub ledger_format: LedgerFileFormat,
+
+    /// Hex-encoded Ed25519 public key of the ledger file signer. When set, a
+    /// hex-encoded signature over the ledger file contents is expected at
+    /// `<ledger_file>.sig`. Required for the `json` and `csv` formats.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ledger_signer_public_key: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gnosis::{AuditedSafeConfig, EthAddr, GnosisSafeConfig};
+    use std::str::FromStr;
+
+    static INPUT_TOML: &str = r#"
+        [[safes]]
+        safe_addr = "0x90213de428E9Ce4C77dD4943755Aa69cb2F803b7"
+        api_url = "https://safe-api.example.com"
+        tokens = []
+
+        [[safes]]
+        safe_addr = "0x1111111111111111111111111111111111111111"
+        ledger_file = "/var/lib/custodian/ledger.csv"
+        ledger_format = "csv"
+        ledger_signer_public_key = "cb8fa7d31b2bf1bc8d2bb0fe1b98d6b0b2b5d2a3a1a8a1b7d6f4c3e2d1c0b9a8"
+        tokens = []
+
+        [[safes]]
+        safe_addr = "0x2222222222222222222222222222222222222222"
+        ledger_file = "/var/lib/custodian/logs.json"
+        ledger_format = "erc20_event_log"
+        tokens = []
+    "#;
+
+    #[test]
+    fn sources_are_selected_by_fields() {
+        let config: GnosisSafeConfig = toml::from_str(INPUT_TOML).expect("failed parsing toml");
+
+        assert_eq!(
+            config
+                .safes
+                .iter()
+                .map(|safe| safe.source.clone())
+                .collect::<Vec<_>>(),
+            vec![
+                BridgeSourceConfig::Gnosis {
+                    api_url: Url::parse("https://safe-api.example.com").unwrap(),
+                },
+                BridgeSourceConfig::LedgerFile(LedgerFileConfig {
+                    ledger_file: PathBuf::from("/var/lib/custodian/ledger.csv"),
+                    ledger_format: LedgerFileFormat::Csv,
+                    ledger_signer_public_key: Some(
+                        "cb8fa7d31b2bf1bc8d2bb0fe1b98d6b0b2b5d2a3a1a8a1b7d6f4c3e2d1c0b9a8"
+                            .to_string()
+                    ),
+                }),
+                BridgeSourceConfig::LedgerFile(LedgerFileConfig {
+                    ledger_file: PathBuf::from("/var/lib/custodian/logs.json"),
+                    ledger_format: LedgerFileFormat::Erc20EventLog,
+                    ledger_signer_public_key: None,
+                }),
+            ]
+        );
+        assert_eq!(
+            config.safes[2].safe_addr,
+            EthAddr::from_str("0x2222222222222222222222222222222222222222").unwrap()
+        );
+
+        // Round-tripping through JSON preserves the source.
+        let json = serde_json::to_string(&config.safes).unwrap();
+        let safes: Vec<AuditedSafeConfig> = serde_json::from_str(&json).unwrap();
+        assert_eq!(safes, config.safes);
+    }
+
+    #[test]
+    fn missing_source_is_rejected() {
+        let result = toml::from_str::<GnosisSafeConfig>(
+            r#"
+            [[safes]]
+            safe_addr = "0x90213de428E9Ce4C77dD4943755Aa69cb2F803b7"
+            tokens = []
+            "#,
+        );
+        assert!(result.is_err());
+    }
+}
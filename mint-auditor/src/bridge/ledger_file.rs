@@ -0,0 +1,810 @@
+// Copyright (c) 2018-2022 The MobileCoin Foundation
+
+//! A [BridgeSource] that reads deposits and withdrawals from a ledger file
+//! exported by the custodian of the backing tokens. This allows auditing
+//! tokens whose custody is not a Gnosis safe.
+
+use super::{
+    BridgeEvent, BridgeSource, BridgeSourceConfig, BridgeTx, LedgerFileConfig, LedgerFileFormat,
+};
+use crate::{
+    counters,
+    db::{GnosisSafeDeposit, GnosisSafeWithdrawal},
+    gnosis::{AuditedSafeConfig, EthAddr, EthTxHash},
+    Error,
+};
+use mc_common::logger::{log, o, Logger};
+use mc_crypto_keys::{Ed25519Public, Ed25519Signature, Verifier};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+/// The topic identifying ERC-20 `Transfer(address,address,uint256)` events.
+pub const ERC20_TRANSFER_EVENT_TOPIC: &str =
+    "0xddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef";
+
+/// The type of a ledger entry.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LedgerEntryType {
+    /// Tokens moved into the custody account.
+    Deposit,
+
+    /// Tokens moved out of the custody account.
+    Withdrawal,
+}
+
+/// A single entry of a ledger file.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct LedgerEntry {
+    /// Whether this is a deposit or a withdrawal.
+    #[serde(rename = "type")]
+    pub entry_type: LedgerEntryType,
+
+    /// Hash of the transaction that moved the tokens. For deposits, this is
+    /// what the matching MintTx nonce is derived from.
+    pub tx_hash: EthTxHash,
+
+    /// Block number of the transaction.
+    pub block_number: u64,
+
+    /// Contract address of the token that was moved.
+    pub token_addr: EthAddr,
+
+    /// Amount moved.
+    pub amount: u64,
+
+    /// Hex-encoded public key of the matching MobileCoin burn TxOut. Only
+    /// present for withdrawals.
+    #[serde(default)]
+    pub mc_tx_out_public_key: Option<String>,
+}
+
+/// The ledger entries that share a transaction hash.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct LedgerTx {
+    /// The transaction hash.
+    pub tx_hash: EthTxHash,
+
+    /// The entries of the transaction.
+    pub entries: Vec<LedgerEntry>,
+}
+
+impl BridgeTx for LedgerTx {
+    fn tx_hash(&self) -> Result<EthTxHash, Error> {
+        Ok(self.tx_hash)
+    }
+
+    fn to_json_string(&self) -> String {
+        serde_json::to_string(self).expect("failed serializing LedgerTx")
+    }
+}
+
+/// An Ethereum log, as returned by `eth_getLogs`.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct EthLog {
+    address: EthAddr,
+    topics: Vec<String>,
+    data: String,
+    block_number: String,
+    transaction_hash: EthTxHash,
+    #[serde(default)]
+    removed: bool,
+}
+
+/// A [BridgeSource] that reads a ledger file.
+pub struct LedgerFileSource {
+    audited_safe: AuditedSafeConfig,
+    config: LedgerFileConfig,
+    signer: Option<Ed25519Public>,
+    logger: Logger,
+}
+
+impl LedgerFileSource {
+    /// Instantiate a [LedgerFileSource] for a safe that is configured with a
+    /// ledger file source.
+    pub fn new(audited_safe: AuditedSafeConfig, logger: Logger) -> Result<Self, Error> {
+        let config = match &audited_safe.source {
+            BridgeSourceConfig::LedgerFile(config) => config.clone(),
+            source => {
+                return Err(Error::Other(format!(
+                    "Safe {} is not configured with a ledger file source: {:?}",
+                    audited_safe.safe_addr, source
+                )))
+            }
+        };
+
+        let signer = config
+            .ledger_signer_public_key
+            .as_ref()
+            .map(|public_key_hex| -> Result<_, Error> {
+                Ok(Ed25519Public::try_from(&hex::decode(public_key_hex)?[..])?)
+            })
+            .transpose()?;
+        if signer.is_none() && config.ledger_format != LedgerFileFormat::Erc20EventLog {
+            return Err(Error::Other(format!(
+                "ledger_signer_public_key is required for {:?} ledger files",
+                config.ledger_format
+            )));
+        }
+
+        let logger = logger.new(o!("ledger_file" => config.ledger_file.display().to_string()));
+
+        Ok(Self {
+            audited_safe,
+            config,
+            signer,
+            logger,
+        })
+    }
+
+    /// Read the ledger file, verifying its signature if a signer is
+    /// configured.
+    fn read_ledger_file(&self) -> Result<Vec<u8>, Error> {
+        let data = fs::read(&self.config.ledger_file)?;
+
+        if let Some(signer) = &self.signer {
+            let signature_hex = fs::read_to_string(signature_path(&self.config.ledger_file))?;
+            let signature = Ed25519Signature::try_from(&hex::decode(signature_hex.trim())?[..])?;
+            signer.verify(&data, &signature)?;
+        }
+
+        Ok(data)
+    }
+
+    /// Parse the contents of a ledger file into ledger entries.
+    fn parse_entries(&self, data: &[u8]) -> Result<Vec<LedgerEntry>, Error> {
+        match self.config.ledger_format {
+            LedgerFileFormat::Json => Ok(serde_json::from_slice(data)?),
+            LedgerFileFormat::Csv => Ok(csv::Reader::from_reader(data)
+                .deserialize()
+                .collect::<Result<Vec<_>, _>>()?),
+            LedgerFileFormat::Erc20EventLog => {
+                self.entries_from_event_logs(serde_json::from_slice(data)?)
+            }
+        }
+    }
+
+    /// Convert ERC-20 `Transfer` events of the audited tokens into and out of
+    /// the safe into ledger entries. All other logs are ignored.
+    fn entries_from_event_logs(&self, logs: Vec<EthLog>) -> Result<Vec<LedgerEntry>, Error> {
+        // Logs removed by a chain reorganization never happened.
+        let logs = logs
+            .into_iter()
+            .filter(|log| !log.removed)
+            .collect::<Vec<_>>();
+
+        let mut entries = Vec::new();
+        for log in &logs {
+            if log.topics.len() != 3
+                || !log.topics[0].eq_ignore_ascii_case(ERC20_TRANSFER_EVENT_TOPIC)
+            {
+                continue;
+            }
+
+            // Other contracts may emit logs that look like transfers without
+            // following the ERC-20 encoding, so they are not parsed at all.
+            if self
+                .audited_safe
+                .get_token_by_eth_contract_addr(&log.address)
+                .is_none()
+            {
+                log::debug!(
+                    self.logger,
+                    "Skipping transfer log of non-audited contract {}: {:?}",
+                    log.address,
+                    log
+                );
+                continue;
+            }
+
+            let from = topic_to_eth_addr(&log.topics[1])?;
+            let to = topic_to_eth_addr(&log.topics[2])?;
+            let (entry_type, mc_tx_out_public_key) = if to == self.audited_safe.safe_addr {
+                (LedgerEntryType::Deposit, None)
+            } else if from == self.audited_safe.safe_addr {
+                (
+                    LedgerEntryType::Withdrawal,
+                    self.find_burn_public_key(&logs, log),
+                )
+            } else {
+                continue;
+            };
+
+            entries.push(LedgerEntry {
+                entry_type,
+                tx_hash: log.transaction_hash,
+                block_number: parse_hex_u64(&log.block_number)?,
+                token_addr: log.address.clone(),
+                amount: parse_uint256_as_u64(&log.data)?,
+                mc_tx_out_public_key,
+            });
+        }
+
+        Ok(entries)
+    }
+
+    /// Withdrawals record the public key of the matching burn TxOut by calling
+    /// the auxiliary burn contract of the token in the same transaction. The
+    /// public key is the last 32 bytes of the data of the log it emits.
+    fn find_burn_public_key(&self, logs: &[EthLog], transfer: &EthLog) -> Option<String> {
+        let audited_token = self
+            .audited_safe
+            .get_token_by_eth_contract_addr(&transfer.address)?;
+
+        logs.iter()
+            .filter(|log| {
+                log.transaction_hash == transfer.transaction_hash
+                    && log.address == audited_token.aux_burn_contract_addr
+            })
+            .find_map(|log| {
+                let data = decode_hex_data(&log.data).ok()?;
+                (data.len() >= 32).then(|| hex::encode(&data[data.len() - 32..]))
+            })
+    }
+
+    /// Parse a withdrawal entry into a [GnosisSafeWithdrawal].
+    fn parse_withdrawal(&self, entry: &LedgerEntry) -> Result<GnosisSafeWithdrawal, Error> {
+        let public_key_hex = entry.mc_tx_out_public_key.as_ref().ok_or_else(|| {
+            Error::Other("withdrawal is missing the MobileCoin tx out public key".into())
+        })?;
+        let public_key = decode_hex_data(public_key_hex)?;
+        if public_key.len() != 32 {
+            return Err(Error::InvalidLength(32, public_key.len()));
+        }
+
+        Ok(GnosisSafeWithdrawal::new(
+            None,
+            entry.tx_hash,
+            entry.block_number,
+            self.audited_safe.safe_addr.clone(),
+            entry.token_addr.clone(),
+            entry.amount,
+            hex::encode(public_key),
+        ))
+    }
+}
+
+impl BridgeSource for LedgerFileSource {
+    type Tx = LedgerTx;
+
+    fn description(&self) -> String {
+        format!("ledger file {}", self.config.ledger_file.display())
+    }
+
+    fn audited_safe(&self) -> &AuditedSafeConfig {
+        &self.audited_safe
+    }
+
+    fn fetch_transactions(&self) -> Result<Vec<LedgerTx>, Error> {
+        let result = self
+            .read_ledger_file()
+            .and_then(|data| self.parse_entries(&data))
+            .map(group_by_tx_hash);
+        if result.is_err() {
+            counters::NUM_FAILED_LEDGER_FILE_READS.inc();
+        }
+        result
+    }
+
+    fn decode_events(&self, tx: &LedgerTx) -> Result<Vec<BridgeEvent>, Error> {
+        // The database stores at most one deposit and one withdrawal per
+        // transaction hash, and a deposit is matched to a mint by its
+        // transaction hash alone. Rather than auditing only some of the
+        // transfers of a transaction, such transactions are rejected.
+        for entry_type in [LedgerEntryType::Deposit, LedgerEntryType::Withdrawal] {
+            let count = tx
+                .entries
+                .iter()
+                .filter(|entry| entry.entry_type == entry_type)
+                .count();
+            if count > 1 {
+                log::crit!(
+                    self.logger,
+                    "Rejecting ledger transaction {}: it has {} entries of type {:?}, but only one is supported",
+                    tx.tx_hash,
+                    count,
+                    entry_type
+                );
+                counters::NUM_REJECTED_LEDGER_TXS.inc();
+                return Ok(Vec::new());
+            }
+        }
+
+        let mut events = Vec::new();
+        for entry in &tx.entries {
+            match entry.entry_type {
+                LedgerEntryType::Deposit => {
+                    log::info!(self.logger, "Processing ledger deposit: {:?}", entry);
+                    events.push(BridgeEvent::Deposit(GnosisSafeDeposit::new(
+                        None,
+                        entry.tx_hash,
+                        entry.block_number,
+                        self.audited_safe.safe_addr.clone(),
+                        entry.token_addr.clone(),
+                        entry.amount,
+                    )));
+                }
+                LedgerEntryType::Withdrawal => match self.parse_withdrawal(entry) {
+                    Ok(withdrawal) => {
+                        log::info!(self.logger, "Processing ledger withdrawal: {:?}", entry);
+                        events.push(BridgeEvent::Withdrawal(withdrawal));
+                    }
+                    Err(err) => {
+                        log::warn!(
+                            self.logger,
+                            "Failed parsing a withdrawal from ledger entry {:?}: {}",
+                            entry,
+                            err
+                        );
+                    }
+                },
+            }
+        }
+
+        Ok(events)
+    }
+}
+
+/// The path of the detached signature of a ledger file.
+fn signature_path(ledger_file: &Path) -> PathBuf {
+    let mut path = ledger_file.as_os_str().to_owned();
+    path.push(".sig");
+    PathBuf::from(path)
+}
+
+/// Group ledger entries by transaction hash, preserving the order in which
+/// transactions first appear.
+fn group_by_tx_hash(entries: Vec<LedgerEntry>) -> Vec<LedgerTx> {
+    let mut txs: Vec<LedgerTx> = Vec::new();
+    for entry in entries {
+        match txs.iter_mut().find(|tx| tx.tx_hash == entry.tx_hash) {
+            Some(tx) => tx.entries.push(entry),
+            None => txs.push(LedgerTx {
+                tx_hash: entry.tx_hash,
+                entries: vec![entry],
+            }),
+        }
+    }
+    txs
+}
+
+/// Decode hex data, with an optional `0x` prefix.
+fn decode_hex_data(src: &str) -> Result<Vec<u8>, Error> {
+    Ok(hex::decode(src.strip_prefix("0x").unwrap_or(src))?)
+}
+
+/// Parse a `0x`-prefixed hex quantity.
+fn parse_hex_u64(src: &str) -> Result<u64, Error> {
+    u64::from_str_radix(src.trim_start_matches("0x"), 16)
+        .map_err(|err| Error::Other(format!("Invalid hex quantity '{}': {}", src, err)))
+}
+
+/// Parse an ABI-encoded uint256, which must fit in a u64.
+fn parse_uint256_as_u64(src: &str) -> Result<u64, Error> {
+    let bytes = decode_hex_data(src)?;
+    if bytes.len() != 32 {
+        return Err(Error::InvalidLength(32, bytes.len()));
+    }
+    if bytes[..24].iter().any(|byte| *byte != 0) {
+        return Err(Error::Other(format!(
+            "Amount {} does not fit in a u64",
+            src
+        )));
+    }
+    Ok(u64::from_be_bytes(
+        bytes[24..].try_into().expect("slice is 8 bytes long"),
+    ))
+}
+
+/// Extract the address from an indexed address topic, which is left-padded to
+/// 32 bytes.
+fn topic_to_eth_addr(topic: &str) -> Result<EthAddr, Error> {
+    let bytes = decode_hex_data(topic)?;
+    if bytes.len() != 32 {
+        return Err(Error::InvalidLength(32, bytes.len()));
+    }
+    Ok(EthAddr::from_str(&format!(
+        "0x{}",
+        hex::encode(&bytes[32 - EthAddr::LEN..])
+    ))?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        bridge::BridgeSync,
+        db::{
+            schema::{gnosis_safe_deposits, gnosis_safe_withdrawals},
+            test_utils::{
+                create_and_insert_burn_tx_out, insert_mint_tx_from_deposit, test_gnosis_config,
+                TestDbContext, AUX_BURN_CONTRACT_ADDR, ETH_TOKEN_CONTRACT_ADDR, SAFE_ADDR,
+            },
+            BurnTxOut, MintTx,
+        },
+    };
+    use diesel::prelude::*;
+    use mc_common::logger::{test_with_logger, Logger};
+    use mc_crypto_keys::{Ed25519Pair, Signer};
+    use mc_transaction_core::TokenId;
+    use mc_util_from_random::FromRandom;
+    use serde_json::json;
+    use tempfile::{tempdir, TempDir};
+
+    const DEPOSIT_TX_HASH: &str =
+        "0x1111111111111111111111111111111111111111111111111111111111111111";
+    const WITHDRAWAL_TX_HASH: &str =
+        "0x2222222222222222222222222222222222222222222222222222222222222222";
+
+    /// Write a ledger file, and sign it if a signer is given.
+    fn write_ledger_file(
+        dir: &TempDir,
+        name: &str,
+        contents: &str,
+        signer: Option<&Ed25519Pair>,
+    ) -> PathBuf {
+        let path = dir.path().join(name);
+        fs::write(&path, contents).unwrap();
+        if let Some(signer) = signer {
+            let signature: Ed25519Signature = signer.sign(contents.as_bytes());
+            fs::write(signature_path(&path), hex::encode(signature)).unwrap();
+        }
+        path
+    }
+
+    fn create_source(
+        ledger_file: PathBuf,
+        ledger_format: LedgerFileFormat,
+        signer: Option<&Ed25519Pair>,
+        logger: Logger,
+    ) -> Result<LedgerFileSource, Error> {
+        let mut audited_safe = test_gnosis_config().safes[0].clone();
+        audited_safe.source = BridgeSourceConfig::LedgerFile(LedgerFileConfig {
+            ledger_file,
+            ledger_format,
+            ledger_signer_public_key: signer.map(|signer| hex::encode(signer.public_key())),
+        });
+        LedgerFileSource::new(audited_safe, logger)
+    }
+
+    fn ledger_entries(burn_public_key_hex: &str) -> Vec<LedgerEntry> {
+        vec![
+            LedgerEntry {
+                entry_type: LedgerEntryType::Deposit,
+                tx_hash: EthTxHash::from_str(DEPOSIT_TX_HASH).unwrap(),
+                block_number: 10,
+                token_addr: EthAddr::from_str(ETH_TOKEN_CONTRACT_ADDR).unwrap(),
+                amount: 1000,
+                mc_tx_out_public_key: None,
+            },
+            LedgerEntry {
+                entry_type: LedgerEntryType::Withdrawal,
+                tx_hash: EthTxHash::from_str(WITHDRAWAL_TX_HASH).unwrap(),
+                block_number: 11,
+                token_addr: EthAddr::from_str(ETH_TOKEN_CONTRACT_ADDR).unwrap(),
+                amount: 400,
+                mc_tx_out_public_key: Some(burn_public_key_hex.to_string()),
+            },
+        ]
+    }
+
+    fn ledger_csv(entries: &[LedgerEntry]) -> String {
+        let mut csv =
+            "type,tx_hash,block_number,token_addr,amount,mc_tx_out_public_key\n".to_string();
+        for entry in entries {
+            csv.push_str(&format!(
+                "{},{},{},{},{},{}\n",
+                match entry.entry_type {
+                    LedgerEntryType::Deposit => "deposit",
+                    LedgerEntryType::Withdrawal => "withdrawal",
+                },
+                entry.tx_hash,
+                entry.block_number,
+                entry.token_addr,
+                entry.amount,
+                entry.mc_tx_out_public_key.clone().unwrap_or_default(),
+            ));
+        }
+        csv
+    }
+
+    #[test_with_logger]
+    fn json_ledger_is_synced_and_matched(logger: Logger) {
+        let mut rng = mc_util_test_helper::get_seeded_rng();
+        let test_db_context = TestDbContext::default();
+        let mint_auditor_db = test_db_context.get_db_instance(logger.clone());
+        let conn = mint_auditor_db.get_conn().unwrap();
+        let dir = tempdir().unwrap();
+        let signer = Ed25519Pair::from_random(&mut rng);
+
+        let burn_tx_out = create_and_insert_burn_tx_out(TokenId::from(1), 400, &conn, &mut rng);
+        let entries = ledger_entries(burn_tx_out.public_key_hex());
+        let ledger_file = write_ledger_file(
+            &dir,
+            "ledger.json",
+            &serde_json::to_string(&entries).unwrap(),
+            Some(&signer),
+        );
+
+        let expected_deposit = GnosisSafeDeposit::new(
+            Some(1),
+            EthTxHash::from_str(DEPOSIT_TX_HASH).unwrap(),
+            10,
+            EthAddr::from_str(SAFE_ADDR).unwrap(),
+            EthAddr::from_str(ETH_TOKEN_CONTRACT_ADDR).unwrap(),
+            1000,
+        );
+        insert_mint_tx_from_deposit(&expected_deposit, &conn, &mut rng);
+
+        let source = create_source(
+            ledger_file,
+            LedgerFileFormat::Json,
+            Some(&signer),
+            logger.clone(),
+        )
+        .unwrap();
+        let sync = BridgeSync::new(source, mint_auditor_db, logger);
+
+        // Polling twice does not process the transactions twice.
+        sync.poll();
+        sync.poll();
+
+        let deposits = gnosis_safe_deposits::table
+            .load::<GnosisSafeDeposit>(&conn)
+            .unwrap();
+        assert_eq!(deposits, vec![expected_deposit]);
+
+        let withdrawals = gnosis_safe_withdrawals::table
+            .load::<GnosisSafeWithdrawal>(&conn)
+            .unwrap();
+        assert_eq!(
+            withdrawals,
+            vec![GnosisSafeWithdrawal::new(
+                Some(1),
+                EthTxHash::from_str(WITHDRAWAL_TX_HASH).unwrap(),
+                11,
+                EthAddr::from_str(SAFE_ADDR).unwrap(),
+                EthAddr::from_str(ETH_TOKEN_CONTRACT_ADDR).unwrap(),
+                400,
+                burn_tx_out.public_key_hex().to_string(),
+            )]
+        );
+
+        // Both the mint and the burn got audited.
+        assert_eq!(MintTx::find_unaudited_mint_txs(&conn).unwrap(), vec![]);
+        assert_eq!(
+            BurnTxOut::find_unaudited_burn_tx_outs(&conn).unwrap(),
+            vec![]
+        );
+    }
+
+    #[test_with_logger]
+    fn csv_ledger_matches_json_ledger(logger: Logger) {
+        let mut rng = mc_util_test_helper::get_seeded_rng();
+        let dir = tempdir().unwrap();
+        let signer = Ed25519Pair::from_random(&mut rng);
+        let entries = ledger_entries(&hex::encode([7u8; 32]));
+
+        let json_file = write_ledger_file(
+            &dir,
+            "ledger.json",
+            &serde_json::to_string(&entries).unwrap(),
+            Some(&signer),
+        );
+        let csv_file = write_ledger_file(&dir, "ledger.csv", &ledger_csv(&entries), Some(&signer));
+
+        let json_source = create_source(
+            json_file,
+            LedgerFileFormat::Json,
+            Some(&signer),
+            logger.clone(),
+        )
+        .unwrap();
+        let csv_source =
+            create_source(csv_file, LedgerFileFormat::Csv, Some(&signer), logger).unwrap();
+
+        let txs = json_source.fetch_transactions().unwrap();
+        assert_eq!(txs.len(), 2);
+        assert_eq!(txs, csv_source.fetch_transactions().unwrap());
+    }
+
+    #[test_with_logger]
+    fn tampered_ledger_is_rejected(logger: Logger) {
+        let mut rng = mc_util_test_helper::get_seeded_rng();
+        let dir = tempdir().unwrap();
+        let signer = Ed25519Pair::from_random(&mut rng);
+        let entries = ledger_entries(&hex::encode([7u8; 32]));
+
+        let ledger_file =
+            write_ledger_file(&dir, "ledger.csv", &ledger_csv(&entries), Some(&signer));
+        let source = create_source(
+            ledger_file.clone(),
+            LedgerFileFormat::Csv,
+            Some(&signer),
+            logger.clone(),
+        )
+        .unwrap();
+        assert!(source.fetch_transactions().is_ok());
+
+        // Inflate the deposit amount without re-signing.
+        let tampered = fs::read_to_string(&ledger_file)
+            .unwrap()
+            .replace(",1000,", ",9000,");
+        fs::write(&ledger_file, tampered).unwrap();
+        assert!(matches!(
+            source.fetch_transactions(),
+            Err(Error::Signature(_))
+        ));
+
+        // A file signed by someone else is rejected too.
+        let other_signer = Ed25519Pair::from_random(&mut rng);
+        let ledger_file = write_ledger_file(
+            &dir,
+            "ledger.csv",
+            &ledger_csv(&entries),
+            Some(&other_signer),
+        );
+        let source =
+            create_source(ledger_file, LedgerFileFormat::Csv, Some(&signer), logger).unwrap();
+        assert!(matches!(
+            source.fetch_transactions(),
+            Err(Error::Signature(_))
+        ));
+    }
+
+    #[test_with_logger]
+    fn unsigned_ledger_requires_signer(logger: Logger) {
+        let dir = tempdir().unwrap();
+        let ledger_file = write_ledger_file(&dir, "ledger.json", "[]", None);
+        assert!(create_source(ledger_file, LedgerFileFormat::Json, None, logger).is_err());
+    }
+
+    #[test_with_logger]
+    fn erc20_event_log_transfers_become_entries(logger: Logger) {
+        let dir = tempdir().unwrap();
+        let safe_topic = format!("0x{:0>64}", SAFE_ADDR[2..].to_lowercase());
+        let other_topic = format!("0x{:0>64}", "dc079a637a1417020916ffb8a39ff5a2801a0f07");
+        let amount = |value: u64| format!("0x{:064x}", value);
+        let burn_public_key = [9u8; 32];
+
+        let logs = json!([
+            // A deposit into the safe.
+            {
+                "address": ETH_TOKEN_CONTRACT_ADDR,
+                "topics": [ERC20_TRANSFER_EVENT_TOPIC, other_topic, safe_topic],
+                "data": amount(1000),
+                "blockNumber": "0xa",
+                "transactionHash": DEPOSIT_TX_HASH,
+                "logIndex": "0x0",
+                "removed": false,
+            },
+            // A withdrawal out of the safe, and the auxiliary contract log
+            // recording the burn public key.
+            {
+                "address": ETH_TOKEN_CONTRACT_ADDR,
+                "topics": [ERC20_TRANSFER_EVENT_TOPIC, safe_topic, other_topic],
+                "data": amount(400),
+                "blockNumber": "0xb",
+                "transactionHash": WITHDRAWAL_TX_HASH,
+                "logIndex": "0x0",
+                "removed": false,
+            },
+            {
+                "address": AUX_BURN_CONTRACT_ADDR,
+                "topics": [],
+                "data": format!(
+                    "0x{:064x}{:064x}{}",
+                    32,
+                    32,
+                    hex::encode(burn_public_key)
+                ),
+                "blockNumber": "0xb",
+                "transactionHash": WITHDRAWAL_TX_HASH,
+                "logIndex": "0x1",
+                "removed": false,
+            },
+            // An unrelated transfer.
+            {
+                "address": ETH_TOKEN_CONTRACT_ADDR,
+                "topics": [ERC20_TRANSFER_EVENT_TOPIC, other_topic, other_topic],
+                "data": amount(5),
+                "blockNumber": "0xc",
+                "transactionHash": "0x3333333333333333333333333333333333333333333333333333333333333333",
+                "logIndex": "0x0",
+                "removed": false,
+            },
+            // A transfer-like log of a contract that is not audited, which
+            // does not follow the ERC-20 encoding.
+            {
+                "address": "0x5555555555555555555555555555555555555555",
+                "topics": [ERC20_TRANSFER_EVENT_TOPIC, other_topic, safe_topic],
+                "data": "0x1234",
+                "blockNumber": "0xc",
+                "transactionHash": "0x3333333333333333333333333333333333333333333333333333333333333333",
+                "logIndex": "0x1",
+                "removed": false,
+            },
+            // A deposit that was removed by a reorg.
+            {
+                "address": ETH_TOKEN_CONTRACT_ADDR,
+                "topics": [ERC20_TRANSFER_EVENT_TOPIC, other_topic, safe_topic],
+                "data": amount(7),
+                "blockNumber": "0xd",
+                "transactionHash": "0x4444444444444444444444444444444444444444444444444444444444444444",
+                "logIndex": "0x0",
+                "removed": true,
+            },
+        ]);
+        let ledger_file = write_ledger_file(&dir, "logs.json", &logs.to_string(), None);
+        let source =
+            create_source(ledger_file, LedgerFileFormat::Erc20EventLog, None, logger).unwrap();
+
+        let txs = source.fetch_transactions().unwrap();
+        assert_eq!(
+            txs.iter()
+                .flat_map(|tx| tx.entries.clone())
+                .collect::<Vec<_>>(),
+            ledger_entries(&hex::encode(burn_public_key))
+        );
+
+        let events = txs
+            .iter()
+            .flat_map(|tx| source.decode_events(tx).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            events,
+            vec![
+                BridgeEvent::Deposit(GnosisSafeDeposit::new(
+                    None,
+                    EthTxHash::from_str(DEPOSIT_TX_HASH).unwrap(),
+                    10,
+                    EthAddr::from_str(SAFE_ADDR).unwrap(),
+                    EthAddr::from_str(ETH_TOKEN_CONTRACT_ADDR).unwrap(),
+                    1000,
+                )),
+                BridgeEvent::Withdrawal(GnosisSafeWithdrawal::new(
+                    None,
+                    EthTxHash::from_str(WITHDRAWAL_TX_HASH).unwrap(),
+                    11,
+                    EthAddr::from_str(SAFE_ADDR).unwrap(),
+                    EthAddr::from_str(ETH_TOKEN_CONTRACT_ADDR).unwrap(),
+                    400,
+                    hex::encode(burn_public_key),
+                )),
+            ]
+        );
+    }
+
+    #[test_with_logger]
+    fn transactions_with_several_deposits_are_rejected(logger: Logger) {
+        let mut rng = mc_util_test_helper::get_seeded_rng();
+        let dir = tempdir().unwrap();
+        let signer = Ed25519Pair::from_random(&mut rng);
+        let mut entries = ledger_entries(&hex::encode([7u8; 32]));
+        let mut second_deposit = entries[0].clone();
+        second_deposit.amount = 5;
+        entries.push(second_deposit);
+
+        let ledger_file = write_ledger_file(
+            &dir,
+            "ledger.json",
+            &serde_json::to_string(&entries).unwrap(),
+            Some(&signer),
+        );
+        let source =
+            create_source(ledger_file, LedgerFileFormat::Json, Some(&signer), logger).unwrap();
+
+        let txs = source.fetch_transactions().unwrap();
+        assert_eq!(txs.len(), 2);
+
+        // Neither deposit of the first transaction is audited, but the
+        // withdrawal transaction still is.
+        assert_eq!(source.decode_events(&txs[0]).unwrap(), vec![]);
+        assert_eq!(source.decode_events(&txs[1]).unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,21 @@
+// Copyright (c) 2018-2022 The MobileCoin Foundation
+
+//! Support for auditing mints and burns against the custody account backing a
+//! token. Each [BridgeSource] provides the deposits into and withdrawals from
+//! one audited safe, which are then matched with MobileCoin mints and burns.
+
+mod config;
+mod ledger_file;
+mod source;
+mod sync;
+mod sync_thread;
+
+pub use self::{
+    config::{BridgeSourceConfig, LedgerFileConfig, LedgerFileFormat},
+    ledger_file::{
+        LedgerEntry, LedgerEntryType, LedgerFileSource, LedgerTx, ERC20_TRANSFER_EVENT_TOPIC,
+    },
+    source::{BridgeEvent, BridgeSource, BridgeTx},
+    sync::BridgeSync,
+    sync_thread::BridgeSyncThread,
+};
@@ -0,0 +1,56 @@
+// Copyright (c) 2018-2022 The MobileCoin Foundation
+
+//! The [BridgeSource] trait, implemented by everything that can provide
+//! deposits into and withdrawals from an audited custody account.
+
+use crate::{
+    db::{GnosisSafeDeposit, GnosisSafeWithdrawal},
+    gnosis::{AuditedSafeConfig, EthTxHash},
+    Error,
+};
+use std::fmt::Debug;
+
+/// A deposit into, or a withdrawal from, an audited custody account.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum BridgeEvent {
+    /// A deposit, expected to be matched with a MintTx.
+    Deposit(GnosisSafeDeposit),
+
+    /// A withdrawal, expected to be matched with a BurnTxOut.
+    Withdrawal(GnosisSafeWithdrawal),
+}
+
+/// A transaction, as obtained from a [BridgeSource].
+pub trait BridgeTx: Debug {
+    /// Get the transaction hash. This is used to identify transactions that
+    /// were already processed.
+    fn tx_hash(&self) -> Result<EthTxHash, Error>;
+
+    /// Serialize the transaction into JSON, for storing in the database.
+    fn to_json_string(&self) -> String;
+}
+
+/// A source of transactions that move tokens into and out of a custody account
+/// backing a MobileCoin token.
+pub trait BridgeSource {
+    /// The transaction type this source provides.
+    type Tx: BridgeTx;
+
+    /// A short description of the source, used for logging.
+    fn description(&self) -> String;
+
+    /// The configuration of the custody account this source provides
+    /// transactions for.
+    fn audited_safe(&self) -> &AuditedSafeConfig;
+
+    /// Fetch all the transactions currently available from the source.
+    /// Transactions that were already processed are skipped by the caller, so
+    /// returning them again is fine.
+    fn fetch_transactions(&self) -> Result<Vec<Self::Tx>, Error>;
+
+    /// Extract the deposits and withdrawals contained in a transaction. This
+    /// is called once per transaction, the first time it is seen. Anything
+    /// that is not relevant to the audited custody account should be skipped
+    /// rather than treated as an error.
+    fn decode_events(&self, tx: &Self::Tx) -> Result<Vec<BridgeEvent>, Error>;
+}
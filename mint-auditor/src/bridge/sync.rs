@@ -0,0 +1,166 @@
+// Copyright (c) 2018-2022 The MobileCoin Foundation
+
+//! Code for syncing transactions from a [BridgeSource] into the SQLite
+//! database, and matching the deposits and withdrawals they contain with
+//! MobileCoin mints and burns.
+//!
+//! NOTE: Right now, if the audited safes configuration changes, one should
+//! delete the SQLite database and re-audit. The code is not smart enough to
+//! handle adding/removing safes/tokens for transactions that were already
+//! processed.
+
+use super::{BridgeEvent, BridgeSource, BridgeTx};
+use crate::{
+    db::{
+        AuditedBurn, AuditedMint, Conn, GnosisSafeDeposit, GnosisSafeTx, GnosisSafeWithdrawal,
+        MintAuditorDb,
+    },
+    error::Error,
+};
+use mc_common::logger::{log, Logger};
+
+/// An object for syncing transaction data from a [BridgeSource] into the
+/// SQLite database.
+pub struct BridgeSync<S: BridgeSource> {
+    source: S,
+    mint_auditor_db: MintAuditorDb,
+    logger: Logger,
+}
+
+impl<S: BridgeSource> BridgeSync<S> {
+    /// Instantiate a new [BridgeSync] object.
+    pub fn new(source: S, mint_auditor_db: MintAuditorDb, logger: Logger) -> Self {
+        Self {
+            source,
+            mint_auditor_db,
+            logger,
+        }
+    }
+
+    /// Poll the source for transaction data.
+    pub fn poll(&self) {
+        match self.source.fetch_transactions() {
+            Ok(transactions) => {
+                self.process_transactions(transactions);
+            }
+            Err(err) => {
+                log::error!(
+                    self.logger,
+                    "Failed to fetch transactions from {}: {}",
+                    self.source.description(),
+                    err
+                );
+            }
+        }
+    }
+
+    /// Process transactions and insert them to the database.
+    pub fn process_transactions(&self, transactions: Vec<S::Tx>) {
+        for tx in transactions {
+            let conn = self
+                .mint_auditor_db
+                .get_conn()
+                .expect("failed getting connection");
+
+            // SQLite3 does not like concurrent writes. Since we are going to be writing to
+            // the database, ensure we are the only writers.
+            conn.exclusive_transaction(|| {
+                match GnosisSafeTx::insert(&tx, &conn) {
+                    Ok(_) => {}
+                    Err(Error::AlreadyExists(_)) => {
+                        log::trace!(
+                            self.logger,
+                            "Skipping already-processed eth transaction {:?}",
+                            tx.tx_hash()
+                        );
+                        return Ok(());
+                    }
+                    Err(err) => {
+                        log::error!(self.logger, "Failed to insert GnosisSafeTx: {}", err);
+                        return Err(err);
+                    }
+                };
+
+                for event in self.source.decode_events(&tx)? {
+                    match event {
+                        BridgeEvent::Deposit(deposit) => {
+                            self.process_deposit(&conn, deposit)?;
+                        }
+                        BridgeEvent::Withdrawal(withdrawal) => {
+                            self.process_withdrawal(&conn, withdrawal)?;
+                        }
+                    }
+                }
+
+                Ok(())
+            })
+            .expect("failed processing transaction");
+        }
+    }
+
+    /// Insert a deposit and attempt to match it with an existing MintTx.
+    fn process_deposit(&self, conn: &Conn, mut deposit: GnosisSafeDeposit) -> Result<(), Error> {
+        deposit.insert(conn)?;
+
+        match AuditedMint::try_match_deposit_with_mint(&deposit, self.source.audited_safe(), conn) {
+            Ok(mint_tx) => {
+                log::info!(
+                    self.logger,
+                    "Deposit eth_tx_hash={} matched MintTx nonce={}",
+                    deposit.eth_tx_hash(),
+                    mint_tx.nonce_hex(),
+                )
+            }
+            Err(Error::NotFound) => {
+                log::debug!(self.logger, "Deposit eth_tx_hash={} does not currently have matching MintTx, this could be fine if the ledger is not fully synced.", deposit.eth_tx_hash());
+            }
+            Err(err) => {
+                log::error!(
+                    self.logger,
+                    "Deposit eth_tx_hash={} failed matching to a MintTx: {}",
+                    deposit.eth_tx_hash(),
+                    err
+                );
+            }
+        };
+
+        Ok(())
+    }
+
+    /// Insert a withdrawal and attempt to match it with an existing BurnTxOut.
+    fn process_withdrawal(
+        &self,
+        conn: &Conn,
+        mut withdrawal: GnosisSafeWithdrawal,
+    ) -> Result<(), Error> {
+        withdrawal.insert(conn)?;
+
+        match AuditedBurn::try_match_withdrawal_with_burn(
+            &withdrawal,
+            self.source.audited_safe(),
+            conn,
+        ) {
+            Ok(burn_tx_out) => {
+                log::info!(
+                    self.logger,
+                    "Withdrawal eth_tx_hash={} matched BurnTxOut pub_key={}",
+                    withdrawal.eth_tx_hash(),
+                    burn_tx_out.public_key_hex(),
+                )
+            }
+            Err(Error::NotFound) => {
+                log::debug!(self.logger, "Withdrawal eth_tx_hash={} does not currently have matching BurnTxOut, this could be fine if the ledger is not fully synced.", withdrawal.eth_tx_hash());
+            }
+            Err(err) => {
+                log::error!(
+                    self.logger,
+                    "Withdrawal eth_tx_hash={} failed matching to a BurnTxOut: {}",
+                    withdrawal.eth_tx_hash(),
+                    err
+                );
+            }
+        };
+
+        Ok(())
+    }
+}
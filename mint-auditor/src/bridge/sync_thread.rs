@@ -0,0 +1,125 @@
+// Copyright (c) 2018-2022 The MobileCoin Foundation
+
+//! Background thread for periodically fetching data from a bridge source and
+//! inserting it into the database.
+
+use super::{BridgeSource, BridgeSourceConfig, BridgeSync, LedgerFileSource};
+use crate::{
+    db::MintAuditorDb,
+    error::Error,
+    gnosis::{AuditedSafeConfig, GnosisSafeSource},
+};
+use mc_common::logger::{log, Logger};
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread::{sleep, spawn, JoinHandle},
+    time::Duration,
+};
+
+/// Background thread for periodically fetching data from a bridge source and
+/// inserting it into the database.
+pub struct BridgeSyncThread {
+    stop_requested: Arc<AtomicBool>,
+    join_handle: Option<JoinHandle<()>>,
+    logger: Logger,
+}
+
+impl BridgeSyncThread {
+    /// Start a sync thread for an audited safe, using the source its
+    /// configuration specifies.
+    pub fn start_for_safe(
+        audited_safe: &AuditedSafeConfig,
+        mint_auditor_db: MintAuditorDb,
+        poll_interval: Duration,
+        logger: Logger,
+    ) -> Result<Self, Error> {
+        match &audited_safe.source {
+            BridgeSourceConfig::Gnosis { .. } => Self::start(
+                GnosisSafeSource::new(audited_safe.clone(), logger.clone())?,
+                mint_auditor_db,
+                poll_interval,
+                logger,
+            ),
+            BridgeSourceConfig::LedgerFile(_) => Self::start(
+                LedgerFileSource::new(audited_safe.clone(), logger.clone())?,
+                mint_auditor_db,
+                poll_interval,
+                logger,
+            ),
+        }
+    }
+
+    /// Start the sync thread.
+    pub fn start<S: BridgeSource + Send + 'static>(
+        source: S,
+        mint_auditor_db: MintAuditorDb,
+        poll_interval: Duration,
+        logger: Logger,
+    ) -> Result<Self, Error> {
+        let stop_requested = Arc::new(AtomicBool::new(false));
+
+        let thread_stop_requested = stop_requested.clone();
+        let thread_logger = logger.clone();
+
+        let join_handle = Some(spawn(move || {
+            thread_entry_point(
+                thread_stop_requested,
+                source,
+                mint_auditor_db,
+                poll_interval,
+                thread_logger,
+            )
+        }));
+
+        Ok(Self {
+            stop_requested,
+            join_handle,
+            logger,
+        })
+    }
+
+    /// Stop the sync thread.
+    pub fn stop(&mut self) {
+        log::info!(self.logger, "Stopping bridge sync thread...");
+        self.stop_requested.store(true, Ordering::Relaxed);
+        if let Some(join_nandle) = self.join_handle.take() {
+            join_nandle
+                .join()
+                .expect("failed joining bridge sync thread");
+        }
+    }
+}
+
+impl Drop for BridgeSyncThread {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+fn thread_entry_point<S: BridgeSource>(
+    stop_requested: Arc<AtomicBool>,
+    source: S,
+    mint_auditor_db: MintAuditorDb,
+    poll_interval: Duration,
+    logger: Logger,
+) {
+    log::info!(
+        logger,
+        "Bridge sync thread started for {}",
+        source.description()
+    );
+    let sync = BridgeSync::new(source, mint_auditor_db, logger.clone());
+
+    loop {
+        if stop_requested.load(Ordering::Relaxed) {
+            log::info!(logger, "Bridge sync thread stop trigger received");
+            break;
+        }
+
+        sync.poll();
+        sleep(poll_interval);
+    }
+}
@@ -49,4 +49,10 @@ lazy_static::lazy_static! {
 
     /// Number of times we failed to fetch gnosis transactions.
     pub static ref NUM_FAILED_GNOSIS_GET_ALL_TRANSACTION_DATA: IntCounter = OP_COUNTERS.counter("num_failed_gnosis_get_all_transaction_data");
+
+    /// Number of times we failed to read or verify a ledger file.
+    pub static ref NUM_FAILED_LEDGER_FILE_READS: IntCounter = OP_COUNTERS.counter("num_failed_ledger_file_reads");
+
+    /// Number of ledger file transactions rejected for having several deposits or withdrawals.
+    pub static ref NUM_REJECTED_LEDGER_TXS: IntCounter = OP_COUNTERS.counter("num_rejected_ledger_txs");
 }
@@ -1,6 +1,7 @@
 // Copyright (c) 2018-2022 The MobileCoin Foundation
 
 use crate::{
+    bridge::BridgeTx,
     db::{schema::gnosis_safe_txs, Conn},
     error::Error,
     gnosis::api_data_types::RawGnosisTransaction,
@@ -9,7 +10,8 @@ use diesel::prelude::*;
 use serde::{Deserialize, Serialize};
 
 /// Diesel model for the `gnosis_safe_txs` table.
-/// This table stores txs into the monitored gnosis safe.
+/// This table stores txs into the monitored gnosis safe, or any other
+/// [BridgeTx] obtained from a bridge source.
 #[derive(
     Clone, Debug, Default, Deserialize, Eq, Hash, Insertable, PartialEq, Queryable, Serialize,
 )]
@@ -18,13 +20,13 @@ pub struct GnosisSafeTx {
     pub eth_tx_hash: String,
 
     /// The JSON representation of the transaction, as served from the gnosis
-    /// API.
+    /// API or read from a ledger file.
     pub raw_tx_json: String,
 }
 
 impl GnosisSafeTx {
-    /// Insert a raw Gnosis Safe transaction into the database.
-    pub fn insert(raw_tx: &RawGnosisTransaction, conn: &Conn) -> Result<(), Error> {
+    /// Insert a raw bridge transaction into the database.
+    pub fn insert(raw_tx: &impl BridgeTx, conn: &Conn) -> Result<(), Error> {
         let obj = Self {
             eth_tx_hash: raw_tx.tx_hash()?.to_string(),
             raw_tx_json: raw_tx.to_json_string(),
@@ -1,6 +1,7 @@
 // Copyright (c) 2018-2022 The MobileCoin Foundation
 
 use crate::{
+    bridge::BridgeSourceConfig,
     db::{
         BurnTxOut, Conn, Error, GnosisSafeDeposit, GnosisSafeTx, GnosisSafeWithdrawal,
         MintAuditorDb, MintTx, SyncBlockData,
@@ -35,7 +36,9 @@ pub fn test_gnosis_config() -> GnosisSafeConfig {
     GnosisSafeConfig {
         safes: vec![AuditedSafeConfig {
             safe_addr: EthAddr::from_str(SAFE_ADDR).unwrap(),
-            api_url: Url::parse("https://safe-api.example.com").unwrap(),
+            source: BridgeSourceConfig::Gnosis {
+                api_url: Url::parse("https://safe-api.example.com").unwrap(),
+            },
             tokens: vec![AuditedToken {
                 token_id: TokenId::from(1),
                 eth_token_contract_addr: EthAddr::from_str(ETH_TOKEN_CONTRACT_ADDR).unwrap(),
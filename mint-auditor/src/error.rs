@@ -12,11 +12,12 @@ use displaydoc::Display;
 use hex::FromHexError;
 use mc_api::display::Error as ApiDisplayError;
 use mc_blockchain_types::BlockIndex;
-use mc_crypto_keys::KeyError;
+use mc_crypto_keys::{KeyError, SignatureError};
 use mc_ledger_db::Error as LedgerDbError;
 use mc_transaction_core::ViewKeyMatchError;
 use mc_transaction_std::MemoDecodingError;
 use mc_util_serial::DecodeError;
+use serde_json::Error as JsonError;
 use std::io::Error as IoError;
 
 /// Mint auditor error data type.
@@ -76,6 +77,15 @@ pub enum Error {
     /// Crypto key: {0}
     Key(KeyError),
 
+    /// Signature: {0}
+    Signature(SignatureError),
+
+    /// JSON: {0}
+    Json(JsonError),
+
+    /// CSV: {0}
+    Csv(csv::Error),
+
     /// Invalid length: expected {0}, got {1}
     InvalidLength(usize, usize),
 
@@ -161,6 +171,24 @@ impl From<KeyError> for Error {
     }
 }
 
+impl From<SignatureError> for Error {
+    fn from(err: SignatureError) -> Self {
+        Self::Signature(err)
+    }
+}
+
+impl From<JsonError> for Error {
+    fn from(err: JsonError) -> Self {
+        Self::Json(err)
+    }
+}
+
+impl From<csv::Error> for Error {
+    fn from(err: csv::Error) -> Self {
+        Self::Csv(err)
+    }
+}
+
 impl From<ViewKeyMatchError> for Error {
     fn from(err: ViewKeyMatchError) -> Self {
         Self::ViewKeyMatch(err)
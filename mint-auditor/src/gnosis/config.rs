@@ -3,10 +3,10 @@
 //! Configuration for Gnosis safe auditing
 
 use super::{Error, EthAddr};
+use crate::bridge::BridgeSourceConfig;
 use mc_transaction_core::TokenId;
 use serde::{Deserialize, Serialize};
 use std::{fs, path::Path};
-use url::Url;
 
 /// Configuration for a token we want to audit.
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
@@ -27,14 +27,17 @@ pub struct AuditedToken {
     pub aux_burn_function_sig: [u8; 4],
 }
 
-/// Configuration for a single safe we want to audit.
+/// Configuration for a single safe we want to audit. The safe address
+/// identifies the custody account, which need not be a Gnosis safe when syncing
+/// from a ledger file.
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub struct AuditedSafeConfig {
     /// The safe address.
     pub safe_addr: EthAddr,
 
-    /// The Gnosis safe transaction service API endpoint to sync from.
-    pub api_url: Url,
+    /// Where the safe transactions are synced from.
+    #[serde(flatten)]
+    pub source: BridgeSourceConfig,
 
     /// The tokens we want to audit.
     pub tokens: Vec<AuditedToken>,
@@ -95,6 +98,7 @@ mod tests {
     use super::*;
     use std::str::FromStr;
     use tempfile::tempdir;
+    use url::Url;
 
     static INPUT_TOML: &str = r#"
         [[safes]]
@@ -151,7 +155,9 @@ mod tests {
                 safes: vec![AuditedSafeConfig {
                     safe_addr: EthAddr::from_str("0x90213de428E9Ce4C77dD4943755Aa69cb2F803b7")
                         .unwrap(),
-                    api_url: Url::parse("https://safe-api.example.com").unwrap(),
+                    source: BridgeSourceConfig::Gnosis {
+                        api_url: Url::parse("https://safe-api.example.com").unwrap(),
+                    },
                     tokens: vec![
                         AuditedToken {
                             token_id: TokenId::from(1),
@@ -1,133 +1,66 @@
 // Copyright (c) 2018-2022 The MobileCoin Foundation
 
-//! Code for syncing transactions from the Gnosis API into the SQLite database.
-//!
-//! NOTE: Right now, if the audited safes configuration changes, one should
-//! delete the SQLite database and re-audit. The code is not smart enough to
-//! handle adding/removing safes/tokens for Gnosis transactions that were
-//! already processed.
+//! A [BridgeSource] that gets transactions from the Gnosis API.
 
 use crate::{
+    bridge::{BridgeEvent, BridgeSource, BridgeSourceConfig, BridgeTx},
     counters,
-    db::{
-        AuditedBurn, AuditedMint, Conn, GnosisSafeDeposit, GnosisSafeTx, GnosisSafeWithdrawal,
-        MintAuditorDb,
-    },
+    db::{GnosisSafeDeposit, GnosisSafeWithdrawal},
     error::Error,
     gnosis::{
         api_data_types::{
             EthereumTransaction, MultiSigTransaction, RawGnosisTransaction, Transaction,
         },
         fetcher::GnosisSafeFetcher,
-        AuditedSafeConfig, Error as GnosisError,
+        AuditedSafeConfig, Error as GnosisError, EthTxHash,
     },
 };
 use mc_common::logger::{log, Logger};
 
-/// An object for syncing transaction data from the Gnosis API into the SQLite
-/// database.
-pub struct GnosisSync {
+impl BridgeTx for RawGnosisTransaction {
+    fn tx_hash(&self) -> Result<EthTxHash, Error> {
+        Ok(RawGnosisTransaction::tx_hash(self)?)
+    }
+
+    fn to_json_string(&self) -> String {
+        RawGnosisTransaction::to_json_string(self)
+    }
+}
+
+/// A [BridgeSource] that gets the transactions of a Gnosis safe from the
+/// Gnosis API.
+pub struct GnosisSafeSource {
     fetcher: GnosisSafeFetcher,
     audited_safe: AuditedSafeConfig,
-    mint_auditor_db: MintAuditorDb,
     logger: Logger,
 }
 
-impl GnosisSync {
-    /// Instantiate a new [GnosisSync] object.
-    pub fn new(
-        audited_safe: AuditedSafeConfig,
-        mint_auditor_db: MintAuditorDb,
-        logger: Logger,
-    ) -> Result<Self, GnosisError> {
+impl GnosisSafeSource {
+    /// Instantiate a new [GnosisSafeSource] object for a safe that is
+    /// configured with a Gnosis API URL.
+    pub fn new(audited_safe: AuditedSafeConfig, logger: Logger) -> Result<Self, GnosisError> {
+        let api_url = match &audited_safe.source {
+            BridgeSourceConfig::Gnosis { api_url } => api_url.clone(),
+            source => {
+                return Err(GnosisError::Other(format!(
+                    "Safe {} is not configured with a Gnosis API source: {:?}",
+                    audited_safe.safe_addr, source
+                )))
+            }
+        };
+
         Ok(Self {
-            fetcher: GnosisSafeFetcher::new(audited_safe.api_url.clone(), logger.clone())?,
+            fetcher: GnosisSafeFetcher::new(api_url, logger.clone())?,
             audited_safe,
-            mint_auditor_db,
             logger,
         })
     }
 
-    /// Poll the Gnosis API for transaction data.
-    pub fn poll(&self) {
-        // TODO: This is inefficient since it repeatedly fetches all transactions, even
-        // once we reached ones we have seen before. The gnosis safe API returns
-        // transactions from the newest to the oldest, and right now there is no way to
-        // change that (see https://github.com/safe-global/safe-transaction-service/issues/847).
-        // For this to be more efficient we need to first fetch everything once, until
-        // we are certain we have synced all transactions. After that, we could
-        // add an optimization that stops fetching once we've reached a transaction
-        // we've seen before. This requires keeping track of whether we have
-        // managed to complete a full fetch. Since right now we are not
-        // expecting a large amount of transactions, this optimization is postponed to a
-        // future PR.
-        match self
-            .fetcher
-            .get_all_transaction_data(&self.audited_safe.safe_addr)
-        {
-            Ok(transactions) => {
-                self.process_transactions(transactions);
-            }
-            Err(err) => {
-                counters::NUM_FAILED_GNOSIS_GET_ALL_TRANSACTION_DATA.inc();
-                log::error!(self.logger, "Failed to fetch Gnosis transactions: {}", err);
-            }
-        }
-    }
-
-    /// Process transactions and insert them to the database.
-    pub fn process_transactions(&self, transactions: Vec<RawGnosisTransaction>) {
-        for tx in transactions {
-            let conn = self
-                .mint_auditor_db
-                .get_conn()
-                .expect("failed getting connection");
-
-            // SQLite3 does not like concurrent writes. Since we are going to be writing to
-            // the database, ensure we are the only writers.
-            conn.exclusive_transaction(|| {
-                match GnosisSafeTx::insert(&tx, &conn) {
-                    Ok(_) => {}
-                    Err(Error::AlreadyExists(_)) => {
-                        log::trace!(
-                            self.logger,
-                            "Skipping already-processed eth transaction {:?}",
-                            tx.tx_hash()
-                        );
-                        return Ok(());
-                    }
-                    Err(err) => {
-                        log::error!(self.logger, "Failed to insert GnosisSafeTx: {}", err);
-                        return Err(err);
-                    }
-                };
-
-                match tx.decode()? {
-                    Transaction::Ethereum(eth_tx) => {
-                        self.process_eth_transaction(&conn, &eth_tx)?;
-                    }
-                    Transaction::MultiSig(multi_sig_tx) => {
-                        self.process_multi_sig_transaction(&conn, &multi_sig_tx)?;
-                    }
-                    Transaction::Module(value) => {
-                        log::warn!(
-                            self.logger,
-                            "Got unexpected \"Module\" transaction: {:?}",
-                            value
-                        );
-                    }
-                };
-
-                Ok(())
-            })
-            .expect("failed processing transaction");
-        }
-    }
-
-    /// Process an Ethereum transaction.
-    fn process_eth_transaction(&self, conn: &Conn, tx: &EthereumTransaction) -> Result<(), Error> {
+    /// Extract the deposits contained in an Ethereum transaction.
+    fn decode_eth_transaction(&self, tx: &EthereumTransaction) -> Vec<BridgeEvent> {
         log::trace!(self.logger, "Processing Ethereum transaction: {:?}", tx);
 
+        let mut events = Vec::new();
         for transfer in &tx.transfers {
             // See if this is a deposit to the safe.
             if transfer.to == self.audited_safe.safe_addr {
@@ -140,38 +73,14 @@ impl GnosisSync {
                 // Empty token address means ETH
                 let token_addr = transfer.token_addr.clone().unwrap_or_default();
 
-                let mut deposit = GnosisSafeDeposit::new(
+                events.push(BridgeEvent::Deposit(GnosisSafeDeposit::new(
                     None,
                     transfer.tx_hash,
                     tx.eth_block_number,
                     transfer.to.clone(),
                     token_addr,
                     u64::from(transfer.value),
-                );
-                deposit.insert(conn)?;
-
-                // Attempt to match the deposit with an existing MintTx.
-                match AuditedMint::try_match_deposit_with_mint(&deposit, &self.audited_safe, conn) {
-                    Ok(mint_tx) => {
-                        log::info!(
-                            self.logger,
-                            "Gnosis deposit eth_tx_hash={} matched MintTx nonce={}",
-                            deposit.eth_tx_hash(),
-                            mint_tx.nonce_hex(),
-                        )
-                    }
-                    Err(Error::NotFound) => {
-                        log::debug!(self.logger, "Gnosis deposit eth_tx_hash={} does not currently have matching MintTx, this could be fine if the ledger is not fully synced.", deposit.eth_tx_hash());
-                    }
-                    Err(err) => {
-                        log::error!(
-                            self.logger,
-                            "Gnosis deposit eth_tx_hash={} failed matching to a MintTx: {}",
-                            deposit.eth_tx_hash(),
-                            err
-                        );
-                    }
-                };
+                )));
             }
             // We don't know what this is.
             else {
@@ -184,15 +93,14 @@ impl GnosisSync {
             }
         }
 
-        Ok(())
+        events
     }
 
-    /// Process a MultiSig transaction.
-    fn process_multi_sig_transaction(
+    /// Extract the withdrawal contained in a MultiSig transaction, if any.
+    fn decode_multi_sig_transaction(
         &self,
-        conn: &Conn,
         multi_sig_tx: &MultiSigTransaction,
-    ) -> Result<(), Error> {
+    ) -> Option<BridgeEvent> {
         // See if this is a withdrawal from the safe we are tracking. In theory we
         // should never receive a response for a different safe since the Gnosis API
         // filters by safe address.
@@ -203,45 +111,17 @@ impl GnosisSync {
                 self.audited_safe.safe_addr,
                 multi_sig_tx
             );
-            return Ok(());
+            return None;
         }
 
         match self.parse_withdrawal_with_pub_key_multi_sig_tx(multi_sig_tx) {
-            Ok(mut withdrawal) => {
+            Ok(withdrawal) => {
                 log::info!(
                     self.logger,
                     "Processing withdrawal from multi-sig tx: {:?}",
                     withdrawal
                 );
-
-                withdrawal.insert(conn)?;
-
-                // Attempt to match the withdrawal with an existing BurnTxOut.
-                match AuditedBurn::try_match_withdrawal_with_burn(
-                    &withdrawal,
-                    &self.audited_safe,
-                    conn,
-                ) {
-                    Ok(burn_tx_out) => {
-                        log::info!(
-                            self.logger,
-                            "Gnosis deposit eth_tx_hash={} matched BurnTxOut pub_key={}",
-                            withdrawal.eth_tx_hash(),
-                            burn_tx_out.public_key_hex(),
-                        )
-                    }
-                    Err(Error::NotFound) => {
-                        log::debug!(self.logger, "Gnosis withdrawal eth_tx_hash={} does not currently have matching BurnTxOut, this could be fine if the ledger is not fully synced.", withdrawal.eth_tx_hash());
-                    }
-                    Err(err) => {
-                        log::error!(
-                            self.logger,
-                            "Gnosis withdrawal eth_tx_hash={} failed matching to a BurnTxOut: {}",
-                            withdrawal.eth_tx_hash(),
-                            err
-                        );
-                    }
-                };
+                Some(BridgeEvent::Withdrawal(withdrawal))
             }
 
             Err(err) => {
@@ -251,10 +131,9 @@ impl GnosisSync {
                     multi_sig_tx.tx_hash,
                     err
                 );
+                None
             }
-        };
-
-        Ok(())
+        }
     }
 
     /// See if this is a multi-sig withdrawal that uses the auxiliary contract
@@ -410,10 +289,61 @@ impl GnosisSync {
     }
 }
 
+impl BridgeSource for GnosisSafeSource {
+    type Tx = RawGnosisTransaction;
+
+    fn description(&self) -> String {
+        format!("gnosis safe {}", self.audited_safe.safe_addr)
+    }
+
+    fn audited_safe(&self) -> &AuditedSafeConfig {
+        &self.audited_safe
+    }
+
+    fn fetch_transactions(&self) -> Result<Vec<RawGnosisTransaction>, Error> {
+        // TODO: This is inefficient since it repeatedly fetches all transactions, even
+        // once we reached ones we have seen before. The gnosis safe API returns
+        // transactions from the newest to the oldest, and right now there is no way to
+        // change that (see https://github.com/safe-global/safe-transaction-service/issues/847).
+        // For this to be more efficient we need to first fetch everything once, until
+        // we are certain we have synced all transactions. After that, we could
+        // add an optimization that stops fetching once we've reached a transaction
+        // we've seen before. This requires keeping track of whether we have
+        // managed to complete a full fetch. Since right now we are not
+        // expecting a large amount of transactions, this optimization is postponed to a
+        // future PR.
+        self.fetcher
+            .get_all_transaction_data(&self.audited_safe.safe_addr)
+            .map_err(|err| {
+                counters::NUM_FAILED_GNOSIS_GET_ALL_TRANSACTION_DATA.inc();
+                Error::from(err)
+            })
+    }
+
+    fn decode_events(&self, tx: &RawGnosisTransaction) -> Result<Vec<BridgeEvent>, Error> {
+        match tx.decode()? {
+            Transaction::Ethereum(eth_tx) => Ok(self.decode_eth_transaction(&eth_tx)),
+            Transaction::MultiSig(multi_sig_tx) => Ok(self
+                .decode_multi_sig_transaction(&multi_sig_tx)
+                .into_iter()
+                .collect()),
+            Transaction::Module(value) => {
+                log::warn!(
+                    self.logger,
+                    "Got unexpected \"Module\" transaction: {:?}",
+                    value
+                );
+                Ok(vec![])
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
     use crate::{
+        bridge::BridgeSync,
         db::{
             schema::{gnosis_safe_deposits, gnosis_safe_withdrawals},
             test_utils::{
@@ -461,7 +391,9 @@ mod test {
         // Must match the contents of the test JSON file.
         let audited_safe_config = AuditedSafeConfig {
             safe_addr: EthAddr::from_str(SAFE_ADDR).unwrap(),
-            api_url: Url::parse("http://unused:8545").unwrap(),
+            source: BridgeSourceConfig::Gnosis {
+                api_url: Url::parse("http://unused:8545").unwrap(),
+            },
             tokens: vec![AuditedToken {
                 token_id: TokenId::from(1),
                 eth_token_contract_addr: EthAddr::from_str(ETH_TOKEN_CONTRACT_ADDR).unwrap(),
@@ -517,7 +449,8 @@ mod test {
         );
 
         // Perform a gnosis sync.
-        let sync = GnosisSync::new(audited_safe_config, mint_auditor_db, logger).unwrap();
+        let source = GnosisSafeSource::new(audited_safe_config, logger.clone()).unwrap();
+        let sync = BridgeSync::new(source, mint_auditor_db, logger);
         sync.process_transactions(raw_transactions);
 
         // Validate that we are seeing the expected deposits.
@@ -579,7 +512,9 @@ mod test {
         // (Except the safe address, since that is what we are testing here)
         let unknown_safe_audited_safe_config = AuditedSafeConfig {
             safe_addr: EthAddr::from_str("0x0000000000000000000000000000000000000000").unwrap(),
-            api_url: Url::parse("http://unused:8545").unwrap(),
+            source: BridgeSourceConfig::Gnosis {
+                api_url: Url::parse("http://unused:8545").unwrap(),
+            },
             tokens: vec![AuditedToken {
                 token_id: TokenId::from(1),
                 eth_token_contract_addr: EthAddr::from_str(ETH_TOKEN_CONTRACT_ADDR).unwrap(),
@@ -593,7 +528,9 @@ mod test {
         // ignored)
         let unknown_token_audited_safe_config = AuditedSafeConfig {
             safe_addr: EthAddr::from_str(SAFE_ADDR).unwrap(),
-            api_url: Url::parse("http://unused:8545").unwrap(),
+            source: BridgeSourceConfig::Gnosis {
+                api_url: Url::parse("http://unused:8545").unwrap(),
+            },
             tokens: vec![
                 // Unknown token contract address
                 AuditedToken {
@@ -626,16 +563,14 @@ mod test {
         };
 
         // Perform a gnosis sync.
-        let sync = GnosisSync::new(
-            unknown_safe_audited_safe_config,
-            mint_auditor_db.clone(),
-            logger.clone(),
-        )
-        .unwrap();
+        let source =
+            GnosisSafeSource::new(unknown_safe_audited_safe_config, logger.clone()).unwrap();
+        let sync = BridgeSync::new(source, mint_auditor_db.clone(), logger.clone());
         sync.process_transactions(raw_transactions.clone());
 
-        let sync =
-            GnosisSync::new(unknown_token_audited_safe_config, mint_auditor_db, logger).unwrap();
+        let source =
+            GnosisSafeSource::new(unknown_token_audited_safe_config, logger.clone()).unwrap();
+        let sync = BridgeSync::new(source, mint_auditor_db, logger);
         sync.process_transactions(raw_transactions);
 
         // Validate that we are seeing no deposits/withdrawals.
@@ -5,6 +5,7 @@
 #![feature(proc_macro_hygiene, decl_macro)]
 #![deny(missing_docs)]
 
+pub mod bridge;
 pub mod counters;
 pub mod db;
 pub mod gnosis;
@@ -7,8 +7,10 @@
 
 use clap::Parser;
 use mc_common::logger::{create_app_logger, log, o};
-use mc_mobilecoind_dev_faucet::{data_types::*, Config, State};
+use mc_mobilecoind_dev_faucet::{data_types::*, Config, HistoryQuery, RecipientKey, State};
+use mc_transaction_core::TokenId;
 use rocket::{get, post, routes, serde::json::Json, Shutdown};
+use std::{net::IpAddr, str::FromStr};
 
 /// Request payment from the faucet, and map the rust result onto json for
 /// rocket appropriately
@@ -16,8 +18,9 @@ use rocket::{get, post, routes, serde::json::Json, Shutdown};
 async fn post(
     state: &rocket::State<State>,
     req: Json<JsonFaucetRequest>,
+    client_ip: Option<IpAddr>,
 ) -> Json<JsonSubmitTxResponse> {
-    Json(state.handle_post(&req).await.into())
+    Json(state.handle_post(&req, client_ip).await.into())
 }
 
 /// Request to initiate a slam, and map the rust result onto json for
@@ -48,6 +51,31 @@ async fn status(state: &rocket::State<State>) -> Json<JsonFaucetStatus> {
     Json(state.handle_status().await.into())
 }
 
+/// Request the history of payments made by the faucet, optionally filtered by
+/// recipient address and token id, and map the rust result onto json for
+/// rocket appropriately. Payments to the recipient with any fog fields match
+/// the address.
+#[get("/history?<b58_address>&<token_id>&<limit>")]
+async fn history(
+    state: &rocket::State<State>,
+    b58_address: Option<String>,
+    token_id: Option<u64>,
+    limit: Option<usize>,
+) -> Json<JsonFaucetHistory> {
+    let result = b58_address
+        .as_deref()
+        .map(RecipientKey::from_str)
+        .transpose()
+        .and_then(|recipient| {
+            state.handle_history(&HistoryQuery {
+                recipient,
+                token_id: token_id.map(TokenId::from),
+                limit,
+            })
+        });
+    Json(result.into())
+}
+
 #[rocket::main]
 async fn main() -> Result<(), rocket::Error> {
     mc_common::setup_panic_handler();
@@ -64,14 +92,21 @@ async fn main() -> Result<(), rocket::Error> {
         config.mobilecoind_uri,
     );
 
-    let figment = rocket::Config::figment()
+    let mut figment = rocket::Config::figment()
         .merge(("port", config.listen_port))
         .merge(("address", config.listen_host.clone()));
+    if !config.trusted_proxy {
+        // Otherwise rocket takes the client IP from a header any client can set.
+        figment = figment.merge(("ip_header", false));
+    }
 
     let state = State::new(&config, &logger);
 
     let _rocket = rocket::custom(figment)
-        .mount("/", routes![post, post_slam, post_cancel_slam, status])
+        .mount(
+            "/",
+            routes![post, post_slam, post_cancel_slam, status, history],
+        )
         .manage(state)
         .launch()
         .await?;
@@ -197,6 +197,79 @@ impl From<Result<api::SubmitTxResponse, String>> for JsonSubmitTxResponse {
     }
 }
 
+/// A payment made by the faucet
+pub struct FaucetDisbursement {
+    /// The time of the payment, in seconds since the epoch
+    pub timestamp: u64,
+    /// The canonical b58 address of the recipient
+    pub b58_address: String,
+    /// The token id of the payment
+    pub token_id: TokenId,
+    /// The payout amount (the recipient gets a little less because of fees)
+    pub amount: u64,
+    /// The hex-encoded hash of the TxOut sent to the recipient
+    pub tx_out_hash: String,
+}
+
+/// A json encoded payment made by the faucet
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct JsonFaucetDisbursement {
+    /// The time of the payment, in seconds since the epoch
+    pub timestamp: JsonU64,
+    /// The canonical b58 address of the recipient
+    pub b58_address: String,
+    /// The token id of the payment
+    pub token_id: JsonU64,
+    /// The payout amount (the recipient gets a little less because of fees)
+    pub amount: JsonU64,
+    /// The hex-encoded hash of the TxOut sent to the recipient
+    pub tx_out_hash: String,
+}
+
+impl From<FaucetDisbursement> for JsonFaucetDisbursement {
+    fn from(src: FaucetDisbursement) -> Self {
+        Self {
+            timestamp: JsonU64(src.timestamp),
+            b58_address: src.b58_address,
+            token_id: JsonU64(*src.token_id),
+            amount: JsonU64(src.amount),
+            tx_out_hash: src.tx_out_hash,
+        }
+    }
+}
+
+/// A response listing payments made by the faucet, most recent first
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct JsonFaucetHistory {
+    /// Whether the history request was successful
+    pub success: bool,
+    /// The error message in case of failure
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub err_str: String,
+    /// The payments matching the request
+    pub disbursements: Vec<JsonFaucetDisbursement>,
+}
+
+impl From<Result<Vec<FaucetDisbursement>, String>> for JsonFaucetHistory {
+    fn from(src: Result<Vec<FaucetDisbursement>, String>) -> Self {
+        match src {
+            Ok(disbursements) => Self {
+                success: true,
+                err_str: String::default(),
+                disbursements: disbursements
+                    .into_iter()
+                    .map(JsonFaucetDisbursement::from)
+                    .collect(),
+            },
+            Err(err_str) => Self {
+                success: false,
+                err_str,
+                ..Default::default()
+            },
+        }
+    }
+}
+
 /// A json request from a user to initiate a slam
 #[derive(Clone, Debug, Default, Deserialize, Serialize)]
 pub struct JsonSlamRequest {
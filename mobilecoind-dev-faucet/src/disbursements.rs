@@ -0,0 +1,616 @@
+// Copyright (c) 2018-2022 The MobileCoin Foundation
+
+//! A record of the payments made by the faucet, which is used to enforce the
+//! rate limits and to serve the payment history.
+//!
+//! If a path is configured, every payment is appended to it as a line of JSON
+//! and the file is replayed on startup, so that rate limits survive restarts.
+//! Payments are only kept for as long as the rate limits need them, or
+//! `MIN_RETENTION` if that is longer, and the file is compacted once enough of
+//! its lines have been dropped.
+
+use crate::limits::{RateLimit, RateLimits, RecipientKey};
+use mc_transaction_core::TokenId;
+use serde_derive::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fs::{self, File, OpenOptions},
+    io::{BufRead, BufReader, Write},
+    net::IpAddr,
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// A payment made by the faucet.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct DisbursementRecord {
+    /// When the payment was made, in seconds since the epoch
+    pub timestamp: u64,
+    /// The (canonically encoded) b58 public address which was paid
+    pub b58_address: String,
+    /// The recipient which was paid, which the rate limits are counted for
+    pub recipient: RecipientKey,
+    /// The IP address of the client which requested the payment, if known
+    pub client_ip: Option<IpAddr>,
+    /// The token id of the payment
+    pub token_id: u64,
+    /// The value of the TxOut which was spent to make the payment
+    pub amount: u64,
+    /// The hex-encoded hash of the TxOut sent to the recipient
+    pub tx_out_hash: String,
+}
+
+/// A filter for the payment history.
+#[derive(Clone, Debug, Default)]
+pub struct HistoryQuery {
+    /// Only include payments to this recipient
+    pub recipient: Option<RecipientKey>,
+    /// Only include payments in this token id
+    pub token_id: Option<TokenId>,
+    /// Return at most this many of the most recent payments
+    pub limit: Option<usize>,
+}
+
+/// Payments are kept for at least this long, so that the history is useful
+/// even without long rate limit periods.
+pub const MIN_RETENTION: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// The file is rewritten without the dropped payments once it holds this many
+/// more lines than there are kept payments.
+const COMPACTION_SLACK: usize = 1024;
+
+#[derive(Default)]
+struct Inner {
+    /// Completed payments, in the order they were made
+    records: Vec<DisbursementRecord>,
+    /// Payments which passed the rate limits and are in progress. These count
+    /// against the limits, so that concurrent requests cannot exceed them.
+    pending: HashMap<u64, DisbursementRecord>,
+    next_pending_id: u64,
+    /// How long completed payments are kept
+    retention: Duration,
+    file: Option<RecordFile>,
+}
+
+struct RecordFile {
+    path: PathBuf,
+    file: File,
+    /// The number of records in the file, including dropped ones
+    num_lines: usize,
+}
+
+/// The record of payments made by the faucet.
+#[derive(Default)]
+pub struct Disbursements {
+    inner: Mutex<Inner>,
+}
+
+impl Disbursements {
+    /// Create a record which is only kept in memory, and drops payments older
+    /// than `retention`.
+    pub fn new(retention: Duration) -> Self {
+        Self {
+            inner: Mutex::new(Inner {
+                retention,
+                ..Default::default()
+            }),
+        }
+    }
+
+    /// Open (or create) a persistent record at the given path, loading any
+    /// payments recorded there which are within `retention`.
+    ///
+    /// A corrupt last line, as left behind by a crash while appending, is
+    /// dropped. Corruption anywhere else is an error.
+    pub fn open(path: &Path, retention: Duration) -> Result<Self, String> {
+        let mut records = Vec::new();
+        if path.exists() {
+            let file = File::open(path)
+                .map_err(|err| format!("Could not open {}: {}", path.display(), err))?;
+            let lines = BufReader::new(file)
+                .lines()
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|err| format!("Could not read {}: {}", path.display(), err))?;
+            let num_lines = lines.len();
+            for (line_number, line) in lines.into_iter().enumerate() {
+                if line.trim().is_empty() {
+                    continue;
+                }
+                match serde_json::from_str(&line) {
+                    Ok(record) => records.push(record),
+                    Err(_) if line_number + 1 == num_lines => {}
+                    Err(err) => {
+                        return Err(format!(
+                            "Invalid record at {}:{}: {}",
+                            path.display(),
+                            line_number + 1,
+                            err
+                        ))
+                    }
+                }
+            }
+        }
+
+        let mut inner = Inner {
+            records,
+            retention,
+            ..Default::default()
+        };
+        if let Some(newest) = inner.records.iter().map(|record| record.timestamp).max() {
+            inner.prune(newest);
+        }
+        // Rewriting the file drops both the pruned records and a corrupt last
+        // line, which would otherwise be joined with the next record.
+        inner.file = Some(RecordFile::rewrite(path.to_owned(), &inner.records)?);
+
+        Ok(Self {
+            inner: Mutex::new(inner),
+        })
+    }
+
+    /// Check a payment against the rate limits, and if it is within them,
+    /// reserve it. The reservation counts against the limits until it is
+    /// either committed, or dropped because the payment failed.
+    ///
+    /// `record.tx_out_hash` is not known yet at this point and is ignored.
+    pub fn reserve<'a>(
+        &'a self,
+        record: DisbursementRecord,
+        address_limits: &RateLimits,
+        ip_limits: &RateLimits,
+    ) -> Result<PendingDisbursement<'a>, String> {
+        let mut inner = self.inner.lock().expect("mutex poisoned");
+        let token_id = TokenId::from(record.token_id);
+
+        if let Some(limit) = address_limits.get(token_id) {
+            let count =
+                inner.count_recent(&record, limit, |other| other.recipient == record.recipient);
+            if count >= limit.count as usize {
+                return Err(format!(
+                    "Rate limit exceeded for address in token id {}: at most {} payments",
+                    token_id, limit
+                ));
+            }
+        }
+        if let (Some(limit), Some(client_ip)) = (ip_limits.get(token_id), record.client_ip) {
+            let count =
+                inner.count_recent(&record, limit, |other| other.client_ip == Some(client_ip));
+            if count >= limit.count as usize {
+                return Err(format!(
+                    "Rate limit exceeded for client IP in token id {}: at most {} payments",
+                    token_id, limit
+                ));
+            }
+        }
+
+        let id = inner.next_pending_id;
+        inner.next_pending_id += 1;
+        inner.pending.insert(id, record);
+        Ok(PendingDisbursement {
+            disbursements: self,
+            id: Some(id),
+        })
+    }
+
+    /// Get the recorded payments matching a query, most recent first.
+    pub fn history(&self, query: &HistoryQuery) -> Vec<DisbursementRecord> {
+        let inner = self.inner.lock().expect("mutex poisoned");
+        inner
+            .records
+            .iter()
+            .rev()
+            .filter(|record| {
+                query
+                    .recipient
+                    .map_or(true, |recipient| record.recipient == recipient)
+                    && query
+                        .token_id
+                        .map_or(true, |token_id| record.token_id == *token_id)
+            })
+            .take(query.limit.unwrap_or(usize::MAX))
+            .cloned()
+            .collect()
+    }
+
+    fn commit(&self, id: u64, tx_out_hash: String) -> Result<(), String> {
+        let mut inner = self.inner.lock().expect("mutex poisoned");
+        let mut record = inner
+            .pending
+            .remove(&id)
+            .ok_or_else(|| "Unknown pending disbursement".to_owned())?;
+        record.tx_out_hash = tx_out_hash;
+
+        // The payment was made, so it is recorded in memory even if we fail to
+        // persist it.
+        inner.records.push(record.clone());
+        inner.prune(record.timestamp);
+
+        let Inner { records, file, .. } = &mut *inner;
+        if let Some(file) = file.as_mut() {
+            file.append(&record)?;
+            if file.num_lines > 2 * records.len() + COMPACTION_SLACK {
+                *file = RecordFile::rewrite(file.path.clone(), records)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn release(&self, id: u64) {
+        let mut inner = self.inner.lock().expect("mutex poisoned");
+        inner.pending.remove(&id);
+    }
+}
+
+impl Inner {
+    /// Drop the completed payments made more than `retention` before `now`.
+    fn prune(&mut self, now: u64) {
+        let since = now.saturating_sub(self.retention.as_secs());
+        self.records.retain(|record| record.timestamp > since);
+    }
+
+    /// Count the completed and pending payments in the same token id as
+    /// `record`, within the period of `limit` before it, which match a
+    /// predicate.
+    fn count_recent(
+        &self,
+        record: &DisbursementRecord,
+        limit: RateLimit,
+        predicate: impl Fn(&DisbursementRecord) -> bool,
+    ) -> usize {
+        let since = record.timestamp.saturating_sub(limit.period.as_secs());
+        self.records
+            .iter()
+            .chain(self.pending.values())
+            .filter(|other| {
+                other.token_id == record.token_id && other.timestamp > since && predicate(other)
+            })
+            .count()
+    }
+}
+
+impl RecordFile {
+    /// Replace the file at `path` with one holding exactly `records`, and
+    /// open it for appending.
+    fn rewrite(path: PathBuf, records: &[DisbursementRecord]) -> Result<Self, String> {
+        let mut tmp_path = path.as_os_str().to_owned();
+        tmp_path.push(".tmp");
+
+        let mut contents = String::new();
+        for record in records {
+            contents.push_str(&to_line(record)?);
+        }
+        fs::write(&tmp_path, contents)
+            .and_then(|_| fs::rename(&tmp_path, &path))
+            .map_err(|err| format!("Could not write {}: {}", path.display(), err))?;
+
+        let file = OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .map_err(|err| format!("Could not open {} for writing: {}", path.display(), err))?;
+        Ok(Self {
+            path,
+            file,
+            num_lines: records.len(),
+        })
+    }
+
+    fn append(&mut self, record: &DisbursementRecord) -> Result<(), String> {
+        self.file
+            .write_all(to_line(record)?.as_bytes())
+            .and_then(|_| self.file.flush())
+            .map_err(|err| format!("Could not persist disbursement: {}", err))?;
+        self.num_lines += 1;
+        Ok(())
+    }
+}
+
+fn to_line(record: &DisbursementRecord) -> Result<String, String> {
+    let mut line = serde_json::to_string(record)
+        .map_err(|err| format!("Could not serialize disbursement: {}", err))?;
+    line.push('\n');
+    Ok(line)
+}
+
+/// A payment which passed the rate limits and is in progress. Dropping it
+/// without committing releases the reservation.
+pub struct PendingDisbursement<'a> {
+    disbursements: &'a Disbursements,
+    id: Option<u64>,
+}
+
+impl<'a> PendingDisbursement<'a> {
+    /// Record that the payment was made.
+    pub fn commit(mut self, tx_out_hash: String) -> Result<(), String> {
+        let id = self
+            .id
+            .take()
+            .expect("pending disbursement already resolved");
+        self.disbursements.commit(id, tx_out_hash)
+    }
+}
+
+impl<'a> Drop for PendingDisbursement<'a> {
+    fn drop(&mut self) {
+        if let Some(id) = self.id.take() {
+            self.disbursements.release(id);
+        }
+    }
+}
+
+/// The current time in seconds since the epoch.
+pub fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::limits::TokenRateLimit;
+    use std::str::FromStr;
+    use tempdir::TempDir;
+
+    // A made up recipient, identified by a short name
+    fn recipient(name: &str) -> RecipientKey {
+        let mut view_public_key = [0u8; 32];
+        view_public_key[..name.len()].copy_from_slice(name.as_bytes());
+        RecipientKey {
+            view_public_key,
+            spend_public_key: [1u8; 32],
+        }
+    }
+
+    fn record(timestamp: u64, name: &str, client_ip: &str, token_id: u64) -> DisbursementRecord {
+        DisbursementRecord {
+            timestamp,
+            b58_address: name.to_owned(),
+            recipient: recipient(name),
+            client_ip: Some(client_ip.parse().unwrap()),
+            token_id,
+            amount: 100,
+            tx_out_hash: String::new(),
+        }
+    }
+
+    fn limits(src: &str) -> RateLimits {
+        RateLimits::new(&[TokenRateLimit::from_str(src).unwrap()])
+    }
+
+    #[test]
+    fn limits_are_enforced_per_address_ip_and_token() {
+        let disbursements = Disbursements::new(Duration::from_secs(100));
+        let address_limits = limits("2/100");
+        let ip_limits = limits("3/100");
+
+        disbursements
+            .reserve(
+                record(1000, "a", "10.0.0.1", 0),
+                &address_limits,
+                &ip_limits,
+            )
+            .unwrap()
+            .commit("01".to_owned())
+            .unwrap();
+
+        // A reservation counts against the limit while it is pending, and is
+        // released when dropped.
+        let pending = disbursements
+            .reserve(
+                record(1001, "a", "10.0.0.1", 0),
+                &address_limits,
+                &ip_limits,
+            )
+            .unwrap();
+        assert!(disbursements
+            .reserve(
+                record(1002, "a", "10.0.0.2", 0),
+                &address_limits,
+                &ip_limits
+            )
+            .is_err());
+        drop(pending);
+        disbursements
+            .reserve(
+                record(1002, "a", "10.0.0.2", 0),
+                &address_limits,
+                &ip_limits,
+            )
+            .unwrap()
+            .commit("02".to_owned())
+            .unwrap();
+
+        // The address is now at its limit in token 0, but not in token 1.
+        assert!(disbursements
+            .reserve(
+                record(1003, "a", "10.0.0.3", 0),
+                &address_limits,
+                &ip_limits
+            )
+            .is_err());
+        assert!(disbursements
+            .reserve(
+                record(1003, "a", "10.0.0.3", 1),
+                &address_limits,
+                &ip_limits
+            )
+            .is_ok());
+
+        // The first IP can still be used for other addresses until its own
+        // limit is reached.
+        disbursements
+            .reserve(
+                record(1004, "b", "10.0.0.1", 0),
+                &address_limits,
+                &ip_limits,
+            )
+            .unwrap()
+            .commit("03".to_owned())
+            .unwrap();
+        assert!(disbursements
+            .reserve(
+                record(1004, "c", "10.0.0.1", 0),
+                &address_limits,
+                &ip_limits
+            )
+            .is_ok());
+        disbursements
+            .reserve(
+                record(1005, "d", "10.0.0.1", 0),
+                &address_limits,
+                &ip_limits,
+            )
+            .unwrap()
+            .commit("04".to_owned())
+            .unwrap();
+        assert!(disbursements
+            .reserve(
+                record(1006, "e", "10.0.0.1", 0),
+                &address_limits,
+                &ip_limits
+            )
+            .is_err());
+
+        // Once the period has passed, payments are allowed again.
+        assert!(disbursements
+            .reserve(
+                record(1100, "a", "10.0.0.1", 0),
+                &address_limits,
+                &ip_limits
+            )
+            .is_ok());
+    }
+
+    #[test]
+    fn history_survives_restart() {
+        let dir = TempDir::new("faucet_disbursements").unwrap();
+        let path = dir.path().join("disbursements.jsonl");
+        let address_limits = limits("1/100");
+        let ip_limits = RateLimits::default();
+
+        {
+            let disbursements = Disbursements::open(&path, Duration::from_secs(100)).unwrap();
+            for (timestamp, address, token_id) in [(1000, "a", 0), (1001, "b", 0), (1002, "a", 1)] {
+                disbursements
+                    .reserve(
+                        record(timestamp, address, "10.0.0.1", token_id),
+                        &address_limits,
+                        &ip_limits,
+                    )
+                    .unwrap()
+                    .commit(format!("{:02x}", timestamp % 256))
+                    .unwrap();
+            }
+        }
+
+        let disbursements = Disbursements::open(&path, Duration::from_secs(100)).unwrap();
+        assert!(disbursements
+            .reserve(
+                record(1010, "a", "10.0.0.1", 0),
+                &address_limits,
+                &ip_limits
+            )
+            .is_err());
+
+        let history = disbursements.history(&HistoryQuery::default());
+        assert_eq!(
+            history
+                .iter()
+                .map(|record| record.timestamp)
+                .collect::<Vec<_>>(),
+            vec![1002, 1001, 1000]
+        );
+        let history = disbursements.history(&HistoryQuery {
+            recipient: Some(recipient("a")),
+            token_id: Some(TokenId::from(0)),
+            limit: None,
+        });
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].tx_out_hash, format!("{:02x}", 1000 % 256));
+        let history = disbursements.history(&HistoryQuery {
+            limit: Some(1),
+            ..Default::default()
+        });
+        assert_eq!(history[0].timestamp, 1002);
+    }
+
+    #[test]
+    fn old_payments_are_dropped() {
+        let dir = TempDir::new("faucet_disbursements").unwrap();
+        let path = dir.path().join("disbursements.jsonl");
+        let address_limits = limits("1/100");
+        let ip_limits = RateLimits::default();
+        let retention = Duration::from_secs(100);
+
+        let disbursements = Disbursements::open(&path, retention).unwrap();
+        for timestamp in (0..3 * COMPACTION_SLACK as u64).map(|i| 1000 + i) {
+            disbursements
+                .reserve(
+                    record(timestamp, &timestamp.to_string(), "10.0.0.1", 0),
+                    &address_limits,
+                    &ip_limits,
+                )
+                .unwrap()
+                .commit("01".to_owned())
+                .unwrap();
+        }
+
+        // Only the payments within the retention period are kept, in memory
+        // and on disk.
+        let num_kept = disbursements.history(&HistoryQuery::default()).len();
+        assert_eq!(num_kept, 100);
+        let num_lines = fs::read_to_string(&path).unwrap().lines().count();
+        assert!(num_lines <= 2 * num_kept + COMPACTION_SLACK);
+        drop(disbursements);
+
+        let disbursements = Disbursements::open(&path, retention).unwrap();
+        assert_eq!(disbursements.history(&HistoryQuery::default()).len(), 100);
+        assert_eq!(fs::read_to_string(&path).unwrap().lines().count(), 100);
+    }
+
+    #[test]
+    fn corrupt_last_line_is_dropped() {
+        let dir = TempDir::new("faucet_disbursements").unwrap();
+        let path = dir.path().join("disbursements.jsonl");
+        let address_limits = limits("1/100");
+        let ip_limits = RateLimits::default();
+        let retention = Duration::from_secs(100);
+
+        let line = |timestamp| to_line(&record(timestamp, "a", "10.0.0.1", 0)).unwrap();
+        let truncated = line(1001);
+        fs::write(
+            &path,
+            format!("{}{}", line(1000), &truncated[..truncated.len() / 2]),
+        )
+        .unwrap();
+
+        let disbursements = Disbursements::open(&path, retention).unwrap();
+        assert_eq!(disbursements.history(&HistoryQuery::default()).len(), 1);
+        disbursements
+            .reserve(
+                record(1200, "a", "10.0.0.1", 0),
+                &address_limits,
+                &ip_limits,
+            )
+            .unwrap()
+            .commit("02".to_owned())
+            .unwrap();
+        drop(disbursements);
+
+        // The new record was not appended to the truncated line.
+        let disbursements = Disbursements::open(&path, retention).unwrap();
+        let history = disbursements.history(&HistoryQuery::default());
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].timestamp, 1200);
+
+        // Corruption before the last line is still an error.
+        fs::write(
+            &path,
+            format!("{}{}", &truncated[..truncated.len() / 2], line(1300)),
+        )
+        .unwrap();
+        assert!(Disbursements::open(&path, retention).is_err());
+    }
+}
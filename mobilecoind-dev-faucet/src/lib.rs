@@ -7,6 +7,16 @@
 pub mod data_types;
 use data_types::*;
 
+mod disbursements;
+use disbursements::{now_secs, DisbursementRecord, Disbursements, MIN_RETENTION};
+
+pub use disbursements::HistoryQuery;
+
+mod limits;
+use limits::{decode_b58_public_address, Access, AccessLists, RateLimits};
+
+pub use limits::{AccessListEntry, RateLimit, RecipientKey, TokenRateLimit};
+
 mod slam;
 use slam::{SlamParams, SlamState};
 
@@ -18,14 +28,16 @@ use worker::{GetUtxoError, UtxoRecord, Worker};
 use clap::Parser;
 use grpcio::ChannelBuilder;
 use mc_account_keys::AccountKey;
-use mc_api::printable::PrintableWrapper;
+use mc_api::{external::PublicAddress, printable::PrintableWrapper};
 use mc_common::logger::{log, o, Logger};
 use mc_mobilecoind_api::{self as api, mobilecoind_api_grpc::MobilecoindApiClient, MobilecoindUri};
 use mc_transaction_core::{ring_signature::KeyImage, TokenId};
 use mc_util_grpc::ConnectionUriGrpcioChannel;
 use mc_util_keyfile::read_keyfile;
 use mc_util_uri::ConsensusClientUri;
-use std::{collections::HashMap, future::Future, path::PathBuf, sync::Arc, time::Duration};
+use std::{
+    collections::HashMap, future::Future, net::IpAddr, path::PathBuf, sync::Arc, time::Duration,
+};
 use tokio::select;
 
 /// Command line config, set with defaults that will work with
@@ -81,10 +93,80 @@ pub struct Config {
     ///     env MC_PEER=mc://foo:123,mc://bar:456
     #[clap(long = "peer", env = "MC_PEER", use_value_delimiter = true)]
     pub peers: Option<Vec<ConsensusClientUri>>,
+
+    /// Rate limits on payments to a single recipient address, of the form
+    /// `[token_id:]count/period`, where period is in seconds or suffixed with
+    /// `s`, `m`, `h` or `d`. A limit without a token id applies to all token
+    /// ids which do not have their own limit. No limit is applied by
+    /// default.
+    ///
+    /// Sample usages:
+    ///     --address-limit 10/1d --address-limit 1:2/1h
+    ///     env MC_ADDRESS_LIMIT=10/1d,1:2/1h
+    #[clap(
+        long = "address-limit",
+        env = "MC_ADDRESS_LIMIT",
+        use_value_delimiter = true
+    )]
+    pub address_limits: Vec<TokenRateLimit>,
+
+    /// Rate limits on payments requested by a single client IP address, in
+    /// the same format as --address-limit.
+    ///
+    /// The client IP is the address of the connecting peer, unless
+    /// --trusted-proxy is set.
+    #[clap(long = "ip-limit", env = "MC_IP_LIMIT", use_value_delimiter = true)]
+    pub ip_limits: Vec<TokenRateLimit>,
+
+    /// The faucet is behind a reverse proxy which sets the client IP header
+    /// configured with ROCKET_IP_HEADER (X-Real-IP by default). Without this,
+    /// the header is ignored, since any client could set it to evade the IP
+    /// rate limits and access lists.
+    #[clap(long, env = "MC_TRUSTED_PROXY")]
+    pub trusted_proxy: bool,
+
+    /// Path of a file recording all payments made by the faucet, which is
+    /// used to enforce rate limits across restarts. If omitted, payments are
+    /// only tracked in memory.
+    #[clap(long, env = "MC_DISBURSEMENTS_PATH")]
+    pub disbursements_path: Option<PathBuf>,
+
+    /// Recipient b58 addresses or client IP addresses which are exempt from
+    /// rate limits. Recipients are matched on their view and spend public keys,
+    /// whatever their fog fields.
+    #[clap(long = "allow", env = "MC_ALLOW", use_value_delimiter = true)]
+    pub allow: Vec<AccessListEntry>,
+
+    /// Recipient b58 addresses or client IP addresses which are refused.
+    #[clap(long = "deny", env = "MC_DENY", use_value_delimiter = true)]
+    pub deny: Vec<AccessListEntry>,
+
+    /// Refuse requests which do not match an --allow entry.
+    #[clap(long, env = "MC_ALLOWLIST_ONLY")]
+    pub allowlist_only: bool,
+}
+
+/// Decode the b58 public address of a recipient, and re-encode it
+/// canonically for the payment history. Rate limits and access lists are keyed
+/// on the returned recipient instead, so that they cannot be evaded by
+/// encoding the same account differently, or with different fog fields.
+fn parse_recipient(b58_address: &str) -> Result<(PublicAddress, String, RecipientKey), String> {
+    let public_address = decode_b58_public_address(b58_address)?;
+    let recipient = RecipientKey::try_from(&public_address)?;
+
+    let mut wrapper = PrintableWrapper::new();
+    wrapper.set_public_address(public_address.clone());
+    let b58_address = wrapper
+        .b58_encode()
+        .map_err(|err| format!("Could not encode b58 address: {}", err))?;
+
+    Ok((public_address, b58_address, recipient))
 }
 
 /// Connection to the mobilecoind client, and other state tracked by the running
-/// server (Note that this can all be recovered by restarting the server.)
+/// server (Note that apart from the record of payments, which can be persisted
+/// with --disbursements-path, this can all be recovered by restarting the
+/// server.)
 ///
 /// This is intended to be used with as the State of the http server in the
 /// rocket framework.
@@ -108,6 +190,14 @@ pub struct State {
     pub slam_state: Arc<SlamState>,
     /// List of consensus uri's to submit to during slam operation
     pub consensus_uris: Option<Vec<ConsensusClientUri>>,
+    /// Rate limits per recipient address
+    pub address_limits: RateLimits,
+    /// Rate limits per client IP address
+    pub ip_limits: RateLimits,
+    /// The allowlist and denylist
+    pub access_lists: AccessLists,
+    /// The record of payments made by the faucet
+    pub disbursements: Disbursements,
     /// Logger
     pub logger: Logger,
 }
@@ -157,6 +247,22 @@ impl State {
 
         let slam_state = SlamState::new(grpc_env);
 
+        let address_limits = RateLimits::new(&config.address_limits);
+        let ip_limits = RateLimits::new(&config.ip_limits);
+
+        // Payments only need to be kept for as long as they count against a
+        // rate limit.
+        let retention = address_limits
+            .max_period()
+            .max(ip_limits.max_period())
+            .map_or(MIN_RETENTION, |period| period.max(MIN_RETENTION));
+        let disbursements = match config.disbursements_path.as_ref() {
+            Some(path) => {
+                Disbursements::open(path, retention).expect("Could not load disbursements")
+            }
+            None => Disbursements::new(retention),
+        };
+
         State {
             mobilecoind_api_client,
             account_key,
@@ -166,6 +272,10 @@ impl State {
             worker,
             slam_state,
             consensus_uris: config.peers.clone(),
+            address_limits,
+            ip_limits,
+            access_lists: AccessLists::new(&config.allow, &config.deny, config.allowlist_only),
+            disbursements,
             logger: logger.clone(),
         }
     }
@@ -242,25 +352,43 @@ impl State {
     }
 
     /// Handle a "post" to the faucet, which requests a payment from the faucet.
+    /// The client IP address, if known, is subject to the IP rate limits and
+    /// access lists.
     /// Returns either the mobilecoind success response or an error string.
     pub async fn handle_post(
         &self,
         req: &JsonFaucetRequest,
+        client_ip: Option<IpAddr>,
     ) -> Result<api::SubmitTxResponse, String> {
-        let printable_wrapper = PrintableWrapper::b58_decode(req.b58_address.clone())
-            .map_err(|err| format!("Could not decode b58 address: {}", err))?;
-
-        let public_address = if printable_wrapper.has_public_address() {
-            printable_wrapper.get_public_address()
-        } else {
-            return Err(format!(
-                "b58 address '{}' is not a public address",
-                req.b58_address
-            ));
-        };
-
+        let (public_address, b58_address, recipient) = parse_recipient(&req.b58_address)?;
         let token_id = TokenId::from(req.token_id.as_ref());
 
+        // Allowlisted payments are recorded too, so that they appear in the
+        // history, but they are never rate limited.
+        let no_limits = RateLimits::default();
+        let (address_limits, ip_limits) = match self.access_lists.check(&recipient, client_ip) {
+            Access::Denied => return Err("Request denied".to_owned()),
+            Access::Exempt => (&no_limits, &no_limits),
+            Access::Limited => (&self.address_limits, &self.ip_limits),
+        };
+        let pending_disbursement = self.disbursements.reserve(
+            DisbursementRecord {
+                timestamp: now_secs(),
+                b58_address,
+                recipient,
+                client_ip,
+                token_id: *token_id,
+                amount: self
+                    .faucet_payout_amounts
+                    .get(&token_id)
+                    .copied()
+                    .unwrap_or_default(),
+                tx_out_hash: String::new(),
+            },
+            address_limits,
+            ip_limits,
+        )?;
+
         let utxo_record = self.worker.get_utxo(token_id).map_err(|x| x.to_string())?;
         log::trace!(
             self.logger,
@@ -273,7 +401,7 @@ impl State {
         let mut req = api::GenerateTxFromTxOutListRequest::new();
         req.set_account_key((&self.account_key).into());
         req.set_input_list(vec![utxo_record.utxo].into());
-        req.set_receiver(public_address.clone());
+        req.set_receiver(public_address);
         req.set_token_id(*token_id);
 
         let resp = self
@@ -302,9 +430,38 @@ impl State {
                 "Could not send SubmitTxResponse to worker thread"
             );
         }
+
+        // Record the payment, so that it counts against the rate limits
+        let tx_out_hash = resp
+            .get_receiver_tx_receipt_list()
+            .first()
+            .map(|receipt| hex::encode(receipt.get_tx_out_hash()))
+            .unwrap_or_default();
+        if let Err(err) = pending_disbursement.commit(tx_out_hash) {
+            log::error!(self.logger, "Could not record payment: {}", err);
+        }
+
         Ok(resp)
     }
 
+    /// Handle a "get history" request to the faucet, which lists the recorded
+    /// payments matching the query, most recent first.
+    /// Returns either the list of payments or an error string.
+    pub fn handle_history(&self, query: &HistoryQuery) -> Result<Vec<FaucetDisbursement>, String> {
+        Ok(self
+            .disbursements
+            .history(query)
+            .into_iter()
+            .map(|record| FaucetDisbursement {
+                timestamp: record.timestamp,
+                b58_address: record.b58_address,
+                token_id: TokenId::from(record.token_id),
+                amount: record.amount,
+                tx_out_hash: record.tx_out_hash,
+            })
+            .collect())
+    }
+
     /// Handle a "get status" request to the faucet.
     /// Returns either the json status report or an error string.
     pub async fn handle_status(&self) -> Result<FaucetStatus, String> {
@@ -0,0 +1,388 @@
+// Copyright (c) 2018-2022 The MobileCoin Foundation
+
+//! Rate limits and access lists, which restrict who the faucet pays and how
+//! often.
+//!
+//! Rate limits are counted per token id, separately for each recipient
+//! and for each client IP address. Access lists can exempt callers from the
+//! rate limits (allow), or refuse them outright (deny). Recipients are
+//! identified by the view and spend public keys of their address only.
+
+use mc_api::{external::PublicAddress, printable::PrintableWrapper};
+use mc_transaction_core::TokenId;
+use serde_derive::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    net::IpAddr,
+    str::FromStr,
+    time::Duration,
+};
+
+/// At most `count` payments per `period`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct RateLimit {
+    /// The number of payments allowed in any window of length `period`
+    pub count: u32,
+    /// The length of the window
+    pub period: Duration,
+}
+
+impl fmt::Display for RateLimit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}/{}s", self.count, self.period.as_secs())
+    }
+}
+
+/// A rate limit as given on the command line: `[token_id:]count/period`,
+/// where period is a number of seconds, optionally suffixed with `s`, `m`,
+/// `h` or `d`. Without a token id, the limit applies to all token ids which
+/// do not have a limit of their own.
+///
+/// For example, `10/1d,1:2/1h` allows 10 payments per day in any token, except
+/// token id 1 for which 2 payments per hour are allowed.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct TokenRateLimit {
+    /// The token id this limit applies to, or None for the default limit
+    pub token_id: Option<TokenId>,
+    /// The limit
+    pub limit: RateLimit,
+}
+
+impl FromStr for TokenRateLimit {
+    type Err = String;
+
+    fn from_str(src: &str) -> Result<Self, String> {
+        let (token_id, limit) = match src.split_once(':') {
+            Some((token_id, limit)) => {
+                let token_id = token_id
+                    .trim()
+                    .parse::<u64>()
+                    .map_err(|err| format!("Invalid token id in rate limit '{}': {}", src, err))?;
+                (Some(TokenId::from(token_id)), limit)
+            }
+            None => (None, src),
+        };
+
+        let (count, period) = limit
+            .split_once('/')
+            .ok_or_else(|| format!("Rate limit '{}' is not of the form count/period", src))?;
+        let count = count
+            .trim()
+            .parse::<u32>()
+            .map_err(|err| format!("Invalid count in rate limit '{}': {}", src, err))?;
+        let period = parse_period(period.trim())
+            .ok_or_else(|| format!("Invalid period in rate limit '{}'", src))?;
+
+        Ok(Self {
+            token_id,
+            limit: RateLimit { count, period },
+        })
+    }
+}
+
+fn parse_period(src: &str) -> Option<Duration> {
+    let (number, multiplier) = match src.char_indices().last()? {
+        (idx, 's') => (&src[..idx], 1),
+        (idx, 'm') => (&src[..idx], 60),
+        (idx, 'h') => (&src[..idx], 60 * 60),
+        (idx, 'd') => (&src[..idx], 24 * 60 * 60),
+        _ => (src, 1),
+    };
+    let secs = number.parse::<u64>().ok()?.checked_mul(multiplier)?;
+    if secs == 0 {
+        return None;
+    }
+    Some(Duration::from_secs(secs))
+}
+
+/// A set of rate limits, with a default and per-token overrides.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct RateLimits {
+    default: Option<RateLimit>,
+    per_token: HashMap<TokenId, RateLimit>,
+}
+
+impl RateLimits {
+    /// Build the limits from the command line values. Later values for the
+    /// same token id override earlier ones.
+    pub fn new(limits: &[TokenRateLimit]) -> Self {
+        let mut result = Self::default();
+        for limit in limits {
+            match limit.token_id {
+                Some(token_id) => {
+                    result.per_token.insert(token_id, limit.limit);
+                }
+                None => result.default = Some(limit.limit),
+            }
+        }
+        result
+    }
+
+    /// Get the limit which applies to a token id, if any
+    pub fn get(&self, token_id: TokenId) -> Option<RateLimit> {
+        self.per_token.get(&token_id).copied().or(self.default)
+    }
+
+    /// The longest period of any limit, which is how much history needs to be
+    /// kept to enforce the limits.
+    pub fn max_period(&self) -> Option<Duration> {
+        self.default
+            .iter()
+            .chain(self.per_token.values())
+            .map(|limit| limit.period)
+            .max()
+    }
+}
+
+/// Decode a b58 public address.
+pub fn decode_b58_public_address(b58_address: &str) -> Result<PublicAddress, String> {
+    let printable_wrapper = PrintableWrapper::b58_decode(b58_address.to_owned())
+        .map_err(|err| format!("Could not decode b58 address: {}", err))?;
+
+    if !printable_wrapper.has_public_address() {
+        return Err(format!(
+            "b58 address '{}' is not a public address",
+            b58_address
+        ));
+    }
+    Ok(printable_wrapper.get_public_address().clone())
+}
+
+/// A recipient, as the rate limits and access lists see it: the view and
+/// spend public keys of its public address. The fog fields of the address are
+/// left out, so that the same account cannot evade the limits by asking to be
+/// paid with a different fog report url, id or signature.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+pub struct RecipientKey {
+    /// The view public key of the recipient
+    pub view_public_key: [u8; 32],
+    /// The spend public key of the recipient
+    pub spend_public_key: [u8; 32],
+}
+
+impl TryFrom<&PublicAddress> for RecipientKey {
+    type Error = String;
+
+    fn try_from(src: &PublicAddress) -> Result<Self, String> {
+        let key = |bytes: &[u8]| {
+            <[u8; 32]>::try_from(bytes)
+                .map_err(|_| format!("Invalid public key length {}", bytes.len()))
+        };
+        Ok(Self {
+            view_public_key: key(src.get_view_public_key().get_data())?,
+            spend_public_key: key(src.get_spend_public_key().get_data())?,
+        })
+    }
+}
+
+impl FromStr for RecipientKey {
+    type Err = String;
+
+    /// Parse the recipient of a b58 public address.
+    fn from_str(src: &str) -> Result<Self, String> {
+        Self::try_from(&decode_b58_public_address(src)?)
+    }
+}
+
+/// An entry of the allowlist or denylist: either a recipient or a client IP
+/// address.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum AccessListEntry {
+    /// A client IP address
+    Ip(IpAddr),
+    /// A recipient, given as a b58 public address
+    Recipient(RecipientKey),
+}
+
+impl FromStr for AccessListEntry {
+    type Err = String;
+
+    fn from_str(src: &str) -> Result<Self, String> {
+        let src = src.trim();
+        if let Ok(ip) = IpAddr::from_str(src) {
+            return Ok(Self::Ip(ip));
+        }
+        if src.is_empty() {
+            return Err("Empty access list entry".to_owned());
+        }
+        Ok(Self::Recipient(RecipientKey::from_str(src)?))
+    }
+}
+
+/// The decision of the access lists for a request.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Access {
+    /// The request is allowlisted, and not subject to rate limits
+    Exempt,
+    /// The request is subject to rate limits
+    Limited,
+    /// The request is refused
+    Denied,
+}
+
+/// The allowlist and denylist.
+#[derive(Clone, Debug, Default)]
+pub struct AccessLists {
+    allow: HashSet<AccessListEntry>,
+    deny: HashSet<AccessListEntry>,
+    allowlist_only: bool,
+}
+
+impl AccessLists {
+    /// Create new access lists.
+    ///
+    /// If `allowlist_only` is set, requests which do not match the allowlist
+    /// are refused instead of being rate limited.
+    pub fn new(allow: &[AccessListEntry], deny: &[AccessListEntry], allowlist_only: bool) -> Self {
+        Self {
+            allow: allow.iter().cloned().collect(),
+            deny: deny.iter().cloned().collect(),
+            allowlist_only,
+        }
+    }
+
+    /// Decide how to treat a request for a recipient, from a client IP
+    /// address, if known. The denylist takes precedence over the allowlist.
+    pub fn check(&self, recipient: &RecipientKey, client_ip: Option<IpAddr>) -> Access {
+        let entries = [
+            Some(AccessListEntry::Recipient(*recipient)),
+            client_ip.map(AccessListEntry::Ip),
+        ];
+        let matches = |list: &HashSet<AccessListEntry>| {
+            entries.iter().flatten().any(|entry| list.contains(entry))
+        };
+
+        if matches(&self.deny) {
+            Access::Denied
+        } else if matches(&self.allow) {
+            Access::Exempt
+        } else if self.allowlist_only {
+            Access::Denied
+        } else {
+            Access::Limited
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mc_account_keys::AccountKey;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    // The b58 encoding of a public address
+    fn b58_encode(public_address: &mc_account_keys::PublicAddress) -> String {
+        let mut wrapper = PrintableWrapper::new();
+        wrapper.set_public_address(public_address.into());
+        wrapper.b58_encode().unwrap()
+    }
+
+    #[test]
+    fn parse_token_rate_limits() {
+        assert_eq!(
+            TokenRateLimit::from_str("10/1d").unwrap(),
+            TokenRateLimit {
+                token_id: None,
+                limit: RateLimit {
+                    count: 10,
+                    period: Duration::from_secs(86400)
+                }
+            }
+        );
+        assert_eq!(
+            TokenRateLimit::from_str("1:2/90").unwrap(),
+            TokenRateLimit {
+                token_id: Some(TokenId::from(1)),
+                limit: RateLimit {
+                    count: 2,
+                    period: Duration::from_secs(90)
+                }
+            }
+        );
+        for invalid in ["10", "x/1d", "1:2/0", "1:2/1w", "a:2/1h"] {
+            assert!(TokenRateLimit::from_str(invalid).is_err(), "{}", invalid);
+        }
+
+        let limits = RateLimits::new(&[
+            TokenRateLimit::from_str("10/1d").unwrap(),
+            TokenRateLimit::from_str("1:2/1h").unwrap(),
+        ]);
+        assert_eq!(limits.get(TokenId::from(0)).unwrap().count, 10);
+        assert_eq!(limits.get(TokenId::from(1)).unwrap().count, 2);
+        assert_eq!(limits.max_period(), Some(Duration::from_secs(86400)));
+        assert_eq!(RateLimits::new(&[]).get(TokenId::from(0)), None);
+    }
+
+    #[test]
+    fn access_lists() {
+        let mut rng: StdRng = SeedableRng::from_seed([1u8; 32]);
+        let recipient = |b58_address: &str| RecipientKey::from_str(b58_address).unwrap();
+        let partner = b58_encode(&AccountKey::random(&mut rng).default_subaddress());
+        let spammer = b58_encode(&AccountKey::random(&mut rng).default_subaddress());
+        let someone = b58_encode(&AccountKey::random(&mut rng).default_subaddress());
+
+        let ip: IpAddr = "10.0.0.1".parse().unwrap();
+        let other_ip: IpAddr = "10.0.0.2".parse().unwrap();
+        let lists = AccessLists::new(
+            &[AccessListEntry::from_str(&partner).unwrap()],
+            &[
+                AccessListEntry::from_str("10.0.0.1").unwrap(),
+                AccessListEntry::from_str(&spammer).unwrap(),
+            ],
+            false,
+        );
+
+        assert_eq!(
+            lists.check(&recipient(&partner), Some(other_ip)),
+            Access::Exempt
+        );
+        assert_eq!(lists.check(&recipient(&partner), Some(ip)), Access::Denied);
+        assert_eq!(lists.check(&recipient(&spammer), None), Access::Denied);
+        assert_eq!(
+            lists.check(&recipient(&someone), Some(other_ip)),
+            Access::Limited
+        );
+
+        let lists = AccessLists::new(&[AccessListEntry::Ip(other_ip)], &[], true);
+        assert_eq!(
+            lists.check(&recipient(&someone), Some(other_ip)),
+            Access::Exempt
+        );
+        assert_eq!(lists.check(&recipient(&someone), Some(ip)), Access::Denied);
+        assert_eq!(lists.check(&recipient(&someone), None), Access::Denied);
+
+        assert!(AccessListEntry::from_str("not an address").is_err());
+    }
+
+    #[test]
+    fn recipients_ignore_fog_fields() {
+        let mut rng: StdRng = SeedableRng::from_seed([2u8; 32]);
+        let account = AccountKey::random_with_fog(&mut rng);
+        let address = account.default_subaddress();
+        let without_fog = mc_account_keys::PublicAddress::new(
+            address.spend_public_key(),
+            address.view_public_key(),
+        );
+
+        let recipient = RecipientKey::from_str(&b58_encode(&address)).unwrap();
+        assert_eq!(
+            recipient,
+            RecipientKey::from_str(&b58_encode(&without_fog)).unwrap()
+        );
+
+        // Denying the address with fog also denies it without.
+        let lists = AccessLists::new(
+            &[],
+            &[AccessListEntry::from_str(&b58_encode(&address)).unwrap()],
+            false,
+        );
+        assert_eq!(
+            lists.check(
+                &RecipientKey::from_str(&b58_encode(&without_fog)).unwrap(),
+                None
+            ),
+            Access::Denied
+        );
+    }
+}
@@ -6,14 +6,22 @@
 #![feature(proc_macro_hygiene, decl_macro)]
 
 use clap::Parser;
+use futures::StreamExt;
 use grpcio::ChannelBuilder;
-use mc_api::external::{CompressedRistretto, PublicAddress, RistrettoPrivate};
+use mc_api::external::{
+    CompressedRistretto, PublicAddress, RistrettoPrivate, SignedContingentInput,
+};
 use mc_common::logger::{create_app_logger, log, o};
 use mc_mobilecoind_api::{self as api, mobilecoind_api_grpc::MobilecoindApiClient, MobilecoindUri};
-use mc_mobilecoind_json::data_types::*;
+use mc_mobilecoind_json::{data_types::*, openapi::openapi_spec};
 use mc_util_grpc::ConnectionUriGrpcioChannel;
 use protobuf::RepeatedField;
-use rocket::{delete, get, post, routes, serde::json::Json};
+use rocket::{
+    delete, get, post,
+    response::stream::{Event, EventStream},
+    routes,
+    serde::json::{Json, Value},
+};
 use std::sync::Arc;
 
 /// Command line config, set with defaults that will work with
@@ -226,12 +234,48 @@ fn monitor_status(
     Ok(Json(JsonMonitorStatusResponse::from(&resp)))
 }
 
-/// Balance check using a created monitor and subaddress index
-#[get("/monitors/<monitor_hex>/subaddresses/<subaddress_index>/balance")]
+/// Stream the events of a monitor as server-sent events, starting at a given
+/// block (by default, the first block of the monitor).
+#[get("/monitors/<monitor_hex>/events?<from_block>")]
+fn monitor_events(
+    state: &rocket::State<State>,
+    monitor_hex: String,
+    from_block: Option<u64>,
+) -> Result<EventStream![], String> {
+    let monitor_id =
+        hex::decode(monitor_hex).map_err(|err| format!("Failed to decode monitor hex: {}", err))?;
+
+    let mut req = api::SubscribeMonitorEventsRequest::new();
+    req.set_monitor_id(monitor_id);
+    req.set_from_block(from_block.unwrap_or(0));
+
+    let mut events = state
+        .mobilecoind_api_client
+        .subscribe_monitor_events(&req)
+        .map_err(|err| format!("Failed subscribing to monitor events: {}", err))?;
+
+    Ok(EventStream! {
+        while let Some(result) = events.next().await {
+            match result {
+                Ok(event) => yield Event::json(&JsonMonitorEvent::from(&event)),
+                Err(err) => {
+                    yield Event::data(format!("Failed getting monitor event: {}", err))
+                        .event("error");
+                    break;
+                }
+            }
+        }
+    })
+}
+
+/// Balance check using a created monitor and subaddress index. The token id
+/// defaults to MOB.
+#[get("/monitors/<monitor_hex>/subaddresses/<subaddress_index>/balance?<token_id>")]
 fn balance(
     state: &rocket::State<State>,
     monitor_hex: String,
     subaddress_index: u64,
+    token_id: Option<u64>,
 ) -> Result<Json<JsonBalanceResponse>, String> {
     let monitor_id =
         hex::decode(monitor_hex).map_err(|err| format!("Failed to decode monitor hex: {}", err))?;
@@ -239,6 +283,7 @@ fn balance(
     let mut req = api::GetBalanceRequest::new();
     req.set_monitor_id(monitor_id);
     req.set_subaddress_index(subaddress_index);
+    req.set_token_id(token_id.unwrap_or(0));
 
     let resp = state
         .mobilecoind_api_client
@@ -248,11 +293,14 @@ fn balance(
     Ok(Json(JsonBalanceResponse::from(&resp)))
 }
 
-#[get("/monitors/<monitor_hex>/subaddresses/<subaddress_index>/utxos")]
+/// Unspent TxOuts of a given token for a created monitor and subaddress index.
+/// The token id defaults to MOB.
+#[get("/monitors/<monitor_hex>/subaddresses/<subaddress_index>/utxos?<token_id>")]
 fn utxos(
     state: &rocket::State<State>,
     monitor_hex: String,
     subaddress_index: u64,
+    token_id: Option<u64>,
 ) -> Result<Json<JsonUtxosResponse>, String> {
     let monitor_id =
         hex::decode(monitor_hex).map_err(|err| format!("Failed to decode monitor hex: {}", err))?;
@@ -260,6 +308,7 @@ fn utxos(
     let mut req = api::GetUnspentTxOutListRequest::new();
     req.set_monitor_id(monitor_id);
     req.set_subaddress_index(subaddress_index);
+    req.set_token_id(token_id.unwrap_or(0));
 
     let resp = state
         .mobilecoind_api_client
@@ -310,6 +359,18 @@ fn create_request_code(
     if let Some(memo) = request.memo.clone() {
         req.set_memo(memo);
     }
+    if let Some(token_id) = request.token_id {
+        req.set_token_id(u64::from(token_id));
+    }
+    if let Some(payment_request_id) = request.payment_request_id {
+        req.set_payment_request_id(u64::from(payment_request_id));
+    }
+    if let Some(expiration_timestamp) = request.expiration_timestamp {
+        req.set_expiration_timestamp(u64::from(expiration_timestamp));
+    }
+    if let Some(line_items) = request.line_items.as_ref() {
+        req.set_line_items(line_items.iter().map(Into::into).collect());
+    }
 
     let resp = state
         .mobilecoind_api_client
@@ -376,6 +437,94 @@ fn parse_address_code(
     Ok(Json(JsonParseAddressCodeResponse::from(&resp)))
 }
 
+/// Generates a transfer code for funds sent with generate-transfer-code-tx
+#[post("/codes/transfer", format = "json", data = "<request>")]
+fn create_transfer_code(
+    state: &rocket::State<State>,
+    request: Json<JsonCreateTransferCodeRequest>,
+) -> Result<Json<JsonCreateTransferCodeResponse>, String> {
+    let mut tx_public_key = CompressedRistretto::new();
+    tx_public_key.set_data(
+        hex::decode(&request.tx_public_key)
+            .map_err(|err| format!("Failed to decode tx public key hex: {}", err))?,
+    );
+
+    let mut req = api::CreateTransferCodeRequest::new();
+    req.set_tx_public_key(tx_public_key);
+    req.set_memo(request.memo.clone());
+    req.set_bip39_entropy(
+        hex::decode(&request.bip39_entropy)
+            .map_err(|err| format!("Failed to decode bip39 entropy hex: {}", err))?,
+    );
+
+    let resp = state
+        .mobilecoind_api_client
+        .create_transfer_code(&req)
+        .map_err(|err| format!("Failed creating transfer code: {}", err))?;
+
+    Ok(Json(JsonCreateTransferCodeResponse::from(&resp)))
+}
+
+/// Retrieves the data in a transfer b58_code
+#[get("/codes/transfer/<b58_code>")]
+fn parse_transfer_code(
+    state: &rocket::State<State>,
+    b58_code: String,
+) -> Result<Json<JsonParseTransferCodeResponse>, String> {
+    let mut req = api::ParseTransferCodeRequest::new();
+    req.set_b58_code(b58_code);
+    let resp = state
+        .mobilecoind_api_client
+        .parse_transfer_code(&req)
+        .map_err(|err| format!("Failed parsing transfer code: {}", err))?;
+
+    // The response contains the UTXO holding the funds, which can be spent with
+    // the account key derived from the bip39 entropy.
+    Ok(Json(JsonParseTransferCodeResponse::from(&resp)))
+}
+
+/// Retrieves the signed contingent input in a swap b58_code, and describes the
+/// swap it offers. Offers with partial fill rules are refused.
+#[get("/codes/swap/<b58_code>")]
+fn parse_swap_code(
+    state: &rocket::State<State>,
+    b58_code: String,
+) -> Result<Json<JsonParseSwapCodeResponse>, String> {
+    let mut req = api::ParseSwapCodeRequest::new();
+    req.set_b58_code(b58_code);
+    let resp = state
+        .mobilecoind_api_client
+        .parse_swap_code(&req)
+        .map_err(|err| format!("Failed parsing swap code: {}", err))?;
+
+    // The signed contingent input in the response can be filled with
+    // generate-swap-tx.
+    Ok(Json(JsonParseSwapCodeResponse::from(&resp)))
+}
+
+/// The outlays of a payment: either the multi-token outlays, if given, or a
+/// single outlay to the receiver of the request code.
+fn outlay_list(
+    request_data: &JsonParseRequestCodeResponse,
+    multi_token_outlays: &Option<Vec<JsonOutlay>>,
+) -> Result<Vec<api::Outlay>, String> {
+    match multi_token_outlays {
+        Some(outlays) => outlays
+            .iter()
+            .map(|outlay| {
+                api::Outlay::try_from(outlay)
+                    .map_err(|err| format!("Failed to convert outlay: {}", err))
+            })
+            .collect(),
+        None => {
+            let mut outlay = api::Outlay::new();
+            outlay.set_receiver(PublicAddress::try_from(&request_data.receiver)?);
+            outlay.set_value(request_data.value.into());
+            Ok(vec![outlay])
+        }
+    }
+}
+
 /// Performs a transfer from a monitor and subaddress. The public keys and
 /// amount are in the POST data.
 #[post(
@@ -392,12 +541,7 @@ fn build_and_submit(
     let monitor_id =
         hex::decode(monitor_hex).map_err(|err| format!("Failed to decode monitor hex: {}", err))?;
 
-    let public_address = PublicAddress::try_from(&transfer.request_data.receiver)?;
-
-    // Generate an outlay
-    let mut outlay = api::Outlay::new();
-    outlay.set_receiver(public_address);
-    outlay.set_value(transfer.request_data.value.into());
+    let outlays = outlay_list(&transfer.request_data, &transfer.multi_token_outlays)?;
 
     // Get max_input_utxo_value.
     let max_input_utxo_value = transfer
@@ -410,12 +554,19 @@ fn build_and_submit(
     let mut req = api::SendPaymentRequest::new();
     req.set_sender_monitor_id(monitor_id);
     req.set_sender_subaddress(subaddress_index);
-    req.set_outlay_list(RepeatedField::from_vec(vec![outlay]));
+    req.set_outlay_list(RepeatedField::from_vec(outlays));
+    req.set_multi_token_outlays(transfer.multi_token_outlays.is_some());
     req.set_max_input_utxo_value(max_input_utxo_value);
     if let Some(subaddress) = transfer.change_subaddress.as_ref() {
         req.set_override_change_subaddress(true);
         req.set_change_subaddress(u64::from(subaddress))
     }
+    req.set_token_id(transfer.request_data.token_id.into());
+    req.set_payment_request_id(transfer.request_data.payment_request_id.into());
+    req.set_expiration_timestamp(transfer.request_data.expiration_timestamp.into());
+    if let Some(strategy) = transfer.utxo_selection_strategy {
+        req.set_utxo_selection_strategy(strategy.into());
+    }
 
     let resp = state
         .mobilecoind_api_client
@@ -463,6 +614,10 @@ fn pay_address_code(
         req.set_override_change_subaddress(true);
         req.set_change_subaddress(u64::from(subaddress))
     }
+    req.set_token_id(transfer.token_id.into());
+    if let Some(strategy) = transfer.utxo_selection_strategy {
+        req.set_utxo_selection_strategy(strategy.into());
+    }
 
     let resp = state
         .mobilecoind_api_client
@@ -490,12 +645,7 @@ fn generate_request_code_transaction(
     let monitor_id =
         hex::decode(monitor_hex).map_err(|err| format!("Failed to decode monitor hex: {}", err))?;
 
-    let public_address = PublicAddress::try_from(&request.transfer.receiver)?;
-
-    // Generate an outlay
-    let mut outlay = api::Outlay::new();
-    outlay.set_receiver(public_address);
-    outlay.set_value(request.transfer.value.into());
+    let outlays = outlay_list(&request.transfer, &request.multi_token_outlays)?;
 
     let inputs: Vec<api::UnspentTxOut> = request
         .input_list
@@ -510,8 +660,13 @@ fn generate_request_code_transaction(
     let mut req = api::GenerateTxRequest::new();
     req.set_sender_monitor_id(monitor_id);
     req.set_change_subaddress(subaddress_index);
-    req.set_outlay_list(RepeatedField::from_vec(vec![outlay]));
+    req.set_outlay_list(RepeatedField::from_vec(outlays));
+    req.set_multi_token_outlays(request.multi_token_outlays.is_some());
     req.set_input_list(RepeatedField::from_vec(inputs));
+    req.set_token_id(request.transfer.token_id.into());
+    if let Some(strategy) = request.utxo_selection_strategy {
+        req.set_utxo_selection_strategy(strategy.into());
+    }
 
     let resp = state
         .mobilecoind_api_client
@@ -521,6 +676,261 @@ fn generate_request_code_transaction(
     Ok(Json(JsonCreateTxProposalResponse::from(&resp)))
 }
 
+/// Creates a transaction proposal that merges the smallest UTXOs of a
+/// subaddress into one, reducing the number of inputs future transactions
+/// need.
+#[post(
+    "/monitors/<monitor_hex>/subaddresses/<subaddress_index>/generate-optimization-tx",
+    format = "json",
+    data = "<request>"
+)]
+fn generate_optimization_tx(
+    state: &rocket::State<State>,
+    monitor_hex: String,
+    subaddress_index: u64,
+    request: Json<JsonGenerateOptimizationTxRequest>,
+) -> Result<Json<JsonCreateTxProposalResponse>, String> {
+    let monitor_id =
+        hex::decode(monitor_hex).map_err(|err| format!("Failed to decode monitor hex: {}", err))?;
+
+    let mut req = api::GenerateOptimizationTxRequest::new();
+    req.set_monitor_id(monitor_id);
+    req.set_subaddress(subaddress_index);
+    req.set_fee(request.fee.as_ref().map(u64::from).unwrap_or(0));
+    req.set_token_id(request.token_id.into());
+
+    let resp = state
+        .mobilecoind_api_client
+        .generate_optimization_tx(&req)
+        .map_err(|err| format!("Failed to generate optimization tx: {}", err))?;
+
+    Ok(Json(JsonCreateTxProposalResponse::from(&resp)))
+}
+
+/// Creates a transaction proposal that sends funds to a new one-time account,
+/// together with the transfer code that allows the recipient to claim them.
+#[post(
+    "/monitors/<monitor_hex>/subaddresses/<subaddress_index>/generate-transfer-code-tx",
+    format = "json",
+    data = "<request>"
+)]
+fn generate_transfer_code_tx(
+    state: &rocket::State<State>,
+    monitor_hex: String,
+    subaddress_index: u64,
+    request: Json<JsonGenerateTransferCodeTxRequest>,
+) -> Result<Json<JsonGenerateTransferCodeTxResponse>, String> {
+    let monitor_id =
+        hex::decode(monitor_hex).map_err(|err| format!("Failed to decode monitor hex: {}", err))?;
+
+    let inputs: Vec<api::UnspentTxOut> = request
+        .input_list
+        .iter()
+        .map(|input| {
+            api::UnspentTxOut::try_from(input)
+                .map_err(|err| format!("Failed to convert input: {}", err))
+        })
+        .collect::<Result<_, String>>()?;
+
+    let mut req = api::GenerateTransferCodeTxRequest::new();
+    req.set_sender_monitor_id(monitor_id);
+    req.set_change_subaddress(subaddress_index);
+    req.set_input_list(RepeatedField::from_vec(inputs));
+    req.set_value(request.value.into());
+    req.set_fee(request.fee.as_ref().map(u64::from).unwrap_or(0));
+    req.set_tombstone(request.tombstone.as_ref().map(u64::from).unwrap_or(0));
+    req.set_memo(request.memo.clone());
+    req.set_token_id(request.token_id.into());
+
+    let resp = state
+        .mobilecoind_api_client
+        .generate_transfer_code_tx(&req)
+        .map_err(|err| format!("Failed to generate transfer code tx: {}", err))?;
+
+    Ok(Json(JsonGenerateTransferCodeTxResponse::from(&resp)))
+}
+
+/// Creates a transaction proposal that burns funds, with a memo identifying
+/// the redemption they are burnt for.
+#[post(
+    "/monitors/<monitor_hex>/subaddresses/<subaddress_index>/generate-burn-redemption-tx",
+    format = "json",
+    data = "<request>"
+)]
+fn generate_burn_redemption_tx(
+    state: &rocket::State<State>,
+    monitor_hex: String,
+    subaddress_index: u64,
+    request: Json<JsonGenerateBurnRedemptionTxRequest>,
+) -> Result<Json<JsonCreateTxProposalResponse>, String> {
+    let monitor_id =
+        hex::decode(monitor_hex).map_err(|err| format!("Failed to decode monitor hex: {}", err))?;
+
+    let inputs: Vec<api::UnspentTxOut> = request
+        .input_list
+        .iter()
+        .map(|input| {
+            api::UnspentTxOut::try_from(input)
+                .map_err(|err| format!("Failed to convert input: {}", err))
+        })
+        .collect::<Result<_, String>>()?;
+
+    let mut req = api::GenerateBurnRedemptionTxRequest::new();
+    req.set_sender_monitor_id(monitor_id);
+    req.set_change_subaddress(subaddress_index);
+    req.set_input_list(RepeatedField::from_vec(inputs));
+    req.set_burn_amount(request.burn_amount.into());
+    req.set_fee(request.fee.as_ref().map(u64::from).unwrap_or(0));
+    req.set_tombstone(request.tombstone.as_ref().map(u64::from).unwrap_or(0));
+    req.set_token_id(request.token_id.into());
+    if let Some(redemption_memo) = request.redemption_memo.as_ref() {
+        req.set_redemption_memo(
+            hex::decode(redemption_memo)
+                .map_err(|err| format!("Failed to decode redemption memo hex: {}", err))?,
+        );
+    }
+    req.set_enable_destination_memo(request.enable_destination_memo);
+
+    let resp = state
+        .mobilecoind_api_client
+        .generate_burn_redemption_tx(&req)
+        .map_err(|err| format!("Failed to generate burn redemption tx: {}", err))?;
+
+    Ok(Json(JsonCreateTxProposalResponse::from(&resp)))
+}
+
+/// Creates a signed contingent input offering a UTXO of a monitor in exchange
+/// for a counter amount paid to the subaddress, together with the swap code
+/// that can be handed to the counterparty.
+#[post(
+    "/monitors/<monitor_hex>/subaddresses/<subaddress_index>/generate-swap",
+    format = "json",
+    data = "<request>"
+)]
+fn generate_swap(
+    state: &rocket::State<State>,
+    monitor_hex: String,
+    subaddress_index: u64,
+    request: Json<JsonGenerateSwapRequest>,
+) -> Result<Json<JsonGenerateSwapResponse>, String> {
+    let monitor_id =
+        hex::decode(monitor_hex).map_err(|err| format!("Failed to decode monitor hex: {}", err))?;
+
+    let input = api::UnspentTxOut::try_from(&request.input)
+        .map_err(|err| format!("Failed to convert input: {}", err))?;
+
+    let mut req = api::GenerateSwapRequest::new();
+    req.set_sender_monitor_id(monitor_id);
+    req.set_receiver_subaddress(subaddress_index);
+    req.set_input(input);
+    req.set_counter_value(request.counter_value.into());
+    req.set_counter_token_id(request.counter_token_id.into());
+    req.set_tombstone(request.tombstone.as_ref().map(u64::from).unwrap_or(0));
+
+    let resp = state
+        .mobilecoind_api_client
+        .generate_swap(&req)
+        .map_err(|err| format!("Failed to generate swap: {}", err))?;
+
+    Ok(Json(JsonGenerateSwapResponse::from(&resp)))
+}
+
+/// Creates a transaction proposal that fills a signed contingent input, paying
+/// the outputs it requires out of the monitor's inputs. The value it offers,
+/// and any change, go to the subaddress. Signed contingent inputs with partial
+/// fill rules are refused.
+#[post(
+    "/monitors/<monitor_hex>/subaddresses/<subaddress_index>/generate-swap-tx",
+    format = "json",
+    data = "<request>"
+)]
+fn generate_swap_tx(
+    state: &rocket::State<State>,
+    monitor_hex: String,
+    subaddress_index: u64,
+    request: Json<JsonGenerateSwapTxRequest>,
+) -> Result<Json<JsonCreateTxProposalResponse>, String> {
+    let monitor_id =
+        hex::decode(monitor_hex).map_err(|err| format!("Failed to decode monitor hex: {}", err))?;
+
+    let inputs: Vec<api::UnspentTxOut> = request
+        .input_list
+        .iter()
+        .map(|input| {
+            api::UnspentTxOut::try_from(input)
+                .map_err(|err| format!("Failed to convert input: {}", err))
+        })
+        .collect::<Result<_, String>>()?;
+
+    let sci = SignedContingentInput::try_from(&request.sci)
+        .map_err(|err| format!("Failed to convert signed contingent input: {}", err))?;
+
+    let mut req = api::GenerateSwapTxRequest::new();
+    req.set_sender_monitor_id(monitor_id);
+    req.set_change_subaddress(subaddress_index);
+    req.set_input_list(RepeatedField::from_vec(inputs));
+    req.set_sci(sci);
+    req.set_fee(request.fee.as_ref().map(u64::from).unwrap_or(0));
+    req.set_tombstone(request.tombstone.as_ref().map(u64::from).unwrap_or(0));
+    req.set_fee_token_id(request.fee_token_id.into());
+    if let Some(strategy) = request.utxo_selection_strategy {
+        req.set_utxo_selection_strategy(strategy.into());
+    }
+
+    let resp = state
+        .mobilecoind_api_client
+        .generate_swap_tx(&req)
+        .map_err(|err| format!("Failed to generate swap tx: {}", err))?;
+
+    Ok(Json(JsonCreateTxProposalResponse::from(&resp)))
+}
+
+/// Creates a transaction proposal that sends the given TxOuts, minus the fee,
+/// to a single receiver. This does not require a monitor, since the account
+/// key owning the TxOuts is provided.
+#[post("/tx/generate-from-tx-out-list", format = "json", data = "<request>")]
+fn generate_tx_from_tx_out_list(
+    state: &rocket::State<State>,
+    request: Json<JsonGenerateTxFromTxOutListRequest>,
+) -> Result<Json<JsonCreateTxProposalResponse>, String> {
+    let mut account_key = api::external::AccountKey::new();
+    let mut view_private_key = RistrettoPrivate::new();
+    view_private_key.set_data(
+        hex::decode(&request.account_key.view_private_key)
+            .map_err(|err| format!("Failed to decode hex key: {}", err))?,
+    );
+    let mut spend_private_key = RistrettoPrivate::new();
+    spend_private_key.set_data(
+        hex::decode(&request.account_key.spend_private_key)
+            .map_err(|err| format!("Failed to decode hex key: {}", err))?,
+    );
+    account_key.set_view_private_key(view_private_key);
+    account_key.set_spend_private_key(spend_private_key);
+
+    let inputs: Vec<api::UnspentTxOut> = request
+        .input_list
+        .iter()
+        .map(|input| {
+            api::UnspentTxOut::try_from(input)
+                .map_err(|err| format!("Failed to convert input: {}", err))
+        })
+        .collect::<Result<_, String>>()?;
+
+    let mut req = api::GenerateTxFromTxOutListRequest::new();
+    req.set_account_key(account_key);
+    req.set_input_list(RepeatedField::from_vec(inputs));
+    req.set_receiver(PublicAddress::try_from(&request.receiver)?);
+    req.set_fee(request.fee.as_ref().map(u64::from).unwrap_or(0));
+    req.set_token_id(request.token_id.into());
+
+    let resp = state
+        .mobilecoind_api_client
+        .generate_tx_from_tx_out_list(&req)
+        .map_err(|err| format!("Failed to generate tx: {}", err))?;
+
+    Ok(Json(JsonCreateTxProposalResponse::from(&resp)))
+}
+
 /// Submit a prepared TxProposal
 #[post("/submit-tx", format = "json", data = "<proposal>")]
 fn submit_tx(
@@ -614,6 +1024,17 @@ fn ledger_info(state: &rocket::State<State>) -> Result<Json<JsonLedgerInfoRespon
     Ok(Json(JsonLedgerInfoResponse::from(&resp)))
 }
 
+/// Gets the status of the network, as seen by mobilecoind
+#[get("/ledger/network-status")]
+fn network_status(state: &rocket::State<State>) -> Result<Json<JsonNetworkStatusResponse>, String> {
+    let resp = state
+        .mobilecoind_api_client
+        .get_network_status(&api::Empty::new())
+        .map_err(|err| format!("Failed getting network status: {}", err))?;
+
+    Ok(Json(JsonNetworkStatusResponse::from(&resp)))
+}
+
 /// Retrieves the data in a request code
 #[get("/ledger/blocks/<block_num>/header")]
 fn block_info(
@@ -776,6 +1197,62 @@ fn get_mixins(
     Ok(Json(response))
 }
 
+/// OpenAPI document describing all of the routes above
+#[get("/openapi.json")]
+fn openapi() -> Json<Value> {
+    Json(openapi_spec())
+}
+
+/// All routes served by mobilecoind-json. Each of them must be described in
+/// `mc_mobilecoind_json::openapi::ENDPOINTS`.
+fn all_routes() -> Vec<rocket::Route> {
+    routes![
+        set_password,
+        unlock_db,
+        version,
+        entropy,
+        account_key_from_root_entropy,
+        mnemonic,
+        account_key_from_mnemonic,
+        add_monitor,
+        remove_monitor,
+        monitors,
+        monitor_status,
+        monitor_events,
+        balance,
+        utxos,
+        public_address,
+        create_request_code,
+        parse_request_code,
+        create_address_code,
+        parse_address_code,
+        create_transfer_code,
+        parse_transfer_code,
+        parse_swap_code,
+        build_and_submit,
+        pay_address_code,
+        generate_request_code_transaction,
+        generate_optimization_tx,
+        generate_transfer_code_tx,
+        generate_burn_redemption_tx,
+        generate_swap,
+        generate_swap_tx,
+        generate_tx_from_tx_out_list,
+        submit_tx,
+        check_transfer_status,
+        check_receiver_transfer_status,
+        ledger_info,
+        network_status,
+        block_info,
+        block_details,
+        processed_block,
+        tx_out_get_block_index_by_public_key,
+        get_mixins,
+        get_proof_of_membership,
+        openapi,
+    ]
+}
+
 #[rocket::main]
 async fn main() -> Result<(), rocket::Error> {
     mc_common::setup_panic_handler();
@@ -806,42 +1283,7 @@ async fn main() -> Result<(), rocket::Error> {
         .merge(("address", config.listen_host.clone()));
 
     let _rocket = rocket::custom(figment)
-        .mount(
-            "/",
-            routes![
-                set_password,
-                unlock_db,
-                version,
-                entropy,
-                account_key_from_root_entropy,
-                mnemonic,
-                account_key_from_mnemonic,
-                add_monitor,
-                remove_monitor,
-                monitors,
-                monitor_status,
-                balance,
-                utxos,
-                public_address,
-                create_request_code,
-                parse_request_code,
-                create_address_code,
-                parse_address_code,
-                build_and_submit,
-                pay_address_code,
-                generate_request_code_transaction,
-                submit_tx,
-                check_transfer_status,
-                check_receiver_transfer_status,
-                ledger_info,
-                block_info,
-                block_details,
-                processed_block,
-                tx_out_get_block_index_by_public_key,
-                get_mixins,
-                get_proof_of_membership,
-            ],
-        )
+        .mount("/", all_routes())
         .manage(State {
             mobilecoind_api_client,
         })
@@ -849,3 +1291,33 @@ async fn main() -> Result<(), rocket::Error> {
         .await?;
     Ok(())
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use mc_mobilecoind_json::openapi::ENDPOINTS;
+
+    /// Every mounted route must be described in the OpenAPI document, and
+    /// vice versa.
+    #[test]
+    fn test_openapi_covers_all_routes() {
+        let mut routes: Vec<(String, String)> = all_routes()
+            .iter()
+            .map(|route| {
+                (
+                    route.method.as_str().to_lowercase(),
+                    route.uri.path().to_string(),
+                )
+            })
+            .collect();
+        routes.sort();
+
+        let mut endpoints: Vec<(String, String)> = ENDPOINTS
+            .iter()
+            .map(|endpoint| (endpoint.method.to_owned(), endpoint.path.to_owned()))
+            .collect();
+        endpoints.sort();
+
+        assert_eq!(routes, endpoints);
+    }
+}
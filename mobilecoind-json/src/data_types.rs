@@ -3,36 +3,39 @@
 //! Serializeable data types that wrap the mobilecoind API.
 
 use mc_api::external::{
-    CompressedRistretto, EncryptedFogHint, EncryptedMemo, InputRules, KeyImage, MaskedAmount,
-    PublicAddress, RingMLSAG, SignatureRctBulletproofs, Tx, TxIn, TxOutMembershipElement,
-    TxOutMembershipHash, TxOutMembershipProof, TxPrefix,
+    CompressedRistretto, CurveScalar, EncryptedFogHint, EncryptedMemo, InputRules, KeyImage,
+    MaskedAmount, PublicAddress, RevealedTxOut, RingMLSAG, SignatureRctBulletproofs,
+    SignedContingentInput, Tx, TxIn, TxOutMembershipElement, TxOutMembershipHash,
+    TxOutMembershipProof, TxPrefix, UnmaskedAmount,
 };
 use mc_mobilecoind_api as api;
 use mc_util_serial::JsonU64;
 use protobuf::RepeatedField;
+use schemars::JsonSchema;
 use serde_derive::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
-#[derive(Deserialize, Default, Debug)]
+#[derive(Deserialize, Default, Debug, JsonSchema)]
 pub struct JsonPasswordRequest {
     pub password: String,
 }
 
-#[derive(Serialize, Default, Debug)]
+#[derive(Serialize, Default, Debug, JsonSchema)]
 pub struct JsonPasswordResponse {
     pub success: bool,
 }
 
-#[derive(Deserialize, Default, Debug)]
+#[derive(Deserialize, Default, Debug, JsonSchema)]
 pub struct JsonUnlockDbRequest {
     pub password: String,
 }
 
-#[derive(Serialize, Default, Debug)]
+#[derive(Serialize, Default, Debug, JsonSchema)]
 pub struct JsonUnlockDbResponse {
     pub success: bool,
 }
 
-#[derive(Serialize, Default, Debug)]
+#[derive(Serialize, Default, Debug, JsonSchema)]
 pub struct JsonRootEntropyResponse {
     pub entropy: String,
 }
@@ -45,7 +48,7 @@ impl From<&api::GenerateRootEntropyResponse> for JsonRootEntropyResponse {
     }
 }
 
-#[derive(Deserialize, Serialize, Default, Debug)]
+#[derive(Deserialize, Serialize, Default, Debug, JsonSchema)]
 pub struct JsonMnemonicResponse {
     pub mnemonic: String,
 }
@@ -58,7 +61,7 @@ impl From<&api::GenerateMnemonicResponse> for JsonMnemonicResponse {
     }
 }
 
-#[derive(Deserialize, Serialize, Default, Debug)]
+#[derive(Deserialize, Serialize, Default, Debug, JsonSchema)]
 pub struct JsonAccountKeyResponse {
     pub view_private_key: String,
     pub spend_private_key: String,
@@ -75,14 +78,14 @@ impl From<&api::GetAccountKeyResponse> for JsonAccountKeyResponse {
     }
 }
 
-#[derive(Deserialize, Default, Debug)]
+#[derive(Deserialize, Default, Debug, JsonSchema)]
 pub struct JsonMonitorRequest {
     pub account_key: JsonAccountKeyResponse,
     pub first_subaddress: u64,
     pub num_subaddresses: u64,
 }
 
-#[derive(Serialize, Default, Debug)]
+#[derive(Serialize, Default, Debug, JsonSchema)]
 pub struct JsonMonitorResponse {
     pub monitor_id: String,
     pub is_new: bool,
@@ -97,7 +100,7 @@ impl From<&api::AddMonitorResponse> for JsonMonitorResponse {
     }
 }
 
-#[derive(Serialize, Default, Debug)]
+#[derive(Serialize, Default, Debug, JsonSchema)]
 pub struct JsonMonitorListResponse {
     pub monitor_ids: Vec<String>,
 }
@@ -110,7 +113,7 @@ impl From<&api::GetMonitorListResponse> for JsonMonitorListResponse {
     }
 }
 
-#[derive(Serialize, Default, Debug)]
+#[derive(Serialize, Default, Debug, JsonSchema)]
 pub struct JsonMonitorStatusResponse {
     pub first_subaddress: u64,
     pub num_subaddresses: u64,
@@ -131,7 +134,7 @@ impl From<&api::GetMonitorStatusResponse> for JsonMonitorStatusResponse {
     }
 }
 
-#[derive(Serialize, Default, Debug)]
+#[derive(Serialize, Default, Debug, JsonSchema)]
 pub struct JsonBalanceResponse {
     pub balance: String,
 }
@@ -144,7 +147,7 @@ impl From<&api::GetBalanceResponse> for JsonBalanceResponse {
     }
 }
 
-#[derive(Serialize, Deserialize, Default, Debug)]
+#[derive(Serialize, Deserialize, Default, Debug, JsonSchema)]
 pub struct JsonUnspentTxOut {
     pub tx_out: JsonTxOut,
     pub subaddress_index: u64,
@@ -153,6 +156,8 @@ pub struct JsonUnspentTxOut {
     pub attempted_spend_height: u64,
     pub attempted_spend_tombstone: u64,
     pub monitor_id: String,
+    #[serde(default)]
+    pub token_id: JsonU64,
 }
 
 impl From<&api::UnspentTxOut> for JsonUnspentTxOut {
@@ -165,6 +170,7 @@ impl From<&api::UnspentTxOut> for JsonUnspentTxOut {
             attempted_spend_height: src.get_attempted_spend_height(),
             attempted_spend_tombstone: src.get_attempted_spend_tombstone(),
             monitor_id: hex::encode(&src.get_monitor_id()),
+            token_id: JsonU64(src.get_token_id()),
         }
     }
 }
@@ -195,12 +201,13 @@ impl TryFrom<&JsonUnspentTxOut> for api::UnspentTxOut {
             hex::decode(&src.monitor_id)
                 .map_err(|err| format!("Failed to decode monitor id hex: {}", err))?,
         );
+        utxo.set_token_id(src.token_id.into());
 
         Ok(utxo)
     }
 }
 
-#[derive(Serialize, Default, Debug)]
+#[derive(Serialize, Default, Debug, JsonSchema)]
 pub struct JsonUtxosResponse {
     pub output_list: Vec<JsonUnspentTxOut>,
 }
@@ -217,14 +224,43 @@ impl From<&api::GetUnspentTxOutListResponse> for JsonUtxosResponse {
     }
 }
 
-#[derive(Deserialize, Default, Debug)]
+#[derive(Deserialize, Serialize, Default, Debug, Clone, JsonSchema)]
+pub struct JsonPaymentRequestLineItem {
+    pub description: String,
+    pub value: JsonU64,
+}
+
+impl From<&api::printable::PaymentRequestLineItem> for JsonPaymentRequestLineItem {
+    fn from(src: &api::printable::PaymentRequestLineItem) -> Self {
+        Self {
+            description: src.get_description().to_string(),
+            value: JsonU64(src.get_value()),
+        }
+    }
+}
+
+impl From<&JsonPaymentRequestLineItem> for api::printable::PaymentRequestLineItem {
+    fn from(src: &JsonPaymentRequestLineItem) -> Self {
+        let mut line_item = api::printable::PaymentRequestLineItem::new();
+        line_item.set_description(src.description.clone());
+        line_item.set_value(src.value.into());
+        line_item
+    }
+}
+
+#[derive(Deserialize, Default, Debug, JsonSchema)]
 pub struct JsonCreateRequestCodeRequest {
     pub receiver: JsonPublicAddress,
     pub value: Option<JsonU64>,
     pub memo: Option<String>,
+    pub token_id: Option<JsonU64>,
+    pub payment_request_id: Option<JsonU64>,
+    /// Seconds since the Unix epoch after which the request must not be paid
+    pub expiration_timestamp: Option<JsonU64>,
+    pub line_items: Option<Vec<JsonPaymentRequestLineItem>>,
 }
 
-#[derive(Serialize, Default, Debug)]
+#[derive(Serialize, Default, Debug, JsonSchema)]
 pub struct JsonCreateRequestCodeResponse {
     pub b58_request_code: String,
 }
@@ -237,7 +273,7 @@ impl From<&api::CreateRequestCodeResponse> for JsonCreateRequestCodeResponse {
     }
 }
 
-#[derive(Deserialize, Serialize, Default, Debug, Clone)]
+#[derive(Deserialize, Serialize, Default, Debug, Clone, JsonSchema)]
 pub struct JsonPublicAddress {
     /// Hex encoded compressed ristretto bytes
     pub view_public_key: String,
@@ -267,7 +303,7 @@ impl From<&PublicAddress> for JsonPublicAddress {
     }
 }
 
-#[derive(Deserialize, Serialize, Default, Debug)]
+#[derive(Deserialize, Serialize, Default, Debug, JsonSchema)]
 pub struct JsonPublicAddressResponse {
     /// Hex encoded compressed ristretto bytes
     pub view_public_key: String,
@@ -334,11 +370,19 @@ impl TryFrom<&JsonPublicAddress> for PublicAddress {
     }
 }
 
-#[derive(Deserialize, Serialize, Default, Debug)]
+#[derive(Deserialize, Serialize, Default, Debug, JsonSchema)]
 pub struct JsonParseRequestCodeResponse {
     pub receiver: JsonPublicAddress,
     pub value: JsonU64,
     pub memo: String,
+    #[serde(default)]
+    pub token_id: JsonU64,
+    #[serde(default)]
+    pub payment_request_id: JsonU64,
+    #[serde(default)]
+    pub expiration_timestamp: JsonU64,
+    #[serde(default)]
+    pub line_items: Vec<JsonPaymentRequestLineItem>,
 }
 
 impl From<&api::ParseRequestCodeResponse> for JsonParseRequestCodeResponse {
@@ -347,16 +391,24 @@ impl From<&api::ParseRequestCodeResponse> for JsonParseRequestCodeResponse {
             receiver: JsonPublicAddress::from(src.get_receiver()),
             value: JsonU64(src.get_value()),
             memo: src.get_memo().to_string(),
+            token_id: JsonU64(src.get_token_id()),
+            payment_request_id: JsonU64(src.get_payment_request_id()),
+            expiration_timestamp: JsonU64(src.get_expiration_timestamp()),
+            line_items: src
+                .get_line_items()
+                .iter()
+                .map(JsonPaymentRequestLineItem::from)
+                .collect(),
         }
     }
 }
 
-#[derive(Deserialize, Default, Debug)]
+#[derive(Deserialize, Default, Debug, JsonSchema)]
 pub struct JsonCreateAddressCodeRequest {
     pub receiver: JsonPublicAddress,
 }
 
-#[derive(Serialize, Default, Debug)]
+#[derive(Serialize, Default, Debug, JsonSchema)]
 pub struct JsonCreateAddressCodeResponse {
     pub b58_code: String,
 }
@@ -369,7 +421,7 @@ impl From<&api::CreateAddressCodeResponse> for JsonCreateAddressCodeResponse {
     }
 }
 
-#[derive(Deserialize, Serialize, Default, Debug)]
+#[derive(Deserialize, Serialize, Default, Debug, JsonSchema)]
 pub struct JsonParseAddressCodeResponse {
     pub receiver: JsonPublicAddress,
 }
@@ -382,7 +434,48 @@ impl From<&api::ParseAddressCodeResponse> for JsonParseAddressCodeResponse {
     }
 }
 
-#[derive(Deserialize, Serialize, Default, Debug)]
+#[derive(Deserialize, Default, Debug, JsonSchema)]
+pub struct JsonCreateTransferCodeRequest {
+    /// Hex encoded compressed ristretto bytes
+    pub tx_public_key: String,
+    pub memo: String,
+    /// Hex encoded bip39 entropy of the account holding the funds
+    pub bip39_entropy: String,
+}
+
+#[derive(Serialize, Default, Debug, JsonSchema)]
+pub struct JsonCreateTransferCodeResponse {
+    pub b58_code: String,
+}
+
+impl From<&api::CreateTransferCodeResponse> for JsonCreateTransferCodeResponse {
+    fn from(src: &api::CreateTransferCodeResponse) -> Self {
+        Self {
+            b58_code: String::from(src.get_b58_code()),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Default, Debug, JsonSchema)]
+pub struct JsonParseTransferCodeResponse {
+    pub tx_public_key: String,
+    pub memo: String,
+    pub utxo: JsonUnspentTxOut,
+    pub bip39_entropy: String,
+}
+
+impl From<&api::ParseTransferCodeResponse> for JsonParseTransferCodeResponse {
+    fn from(src: &api::ParseTransferCodeResponse) -> Self {
+        Self {
+            tx_public_key: hex::encode(src.get_tx_public_key().get_data()),
+            memo: src.get_memo().to_string(),
+            utxo: src.get_utxo().into(),
+            bip39_entropy: hex::encode(src.get_bip39_entropy()),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Default, Debug, JsonSchema)]
 pub struct JsonSenderTxReceipt {
     pub key_images: Vec<String>,
     pub tombstone: u64,
@@ -401,7 +494,7 @@ impl From<&api::SenderTxReceipt> for JsonSenderTxReceipt {
     }
 }
 
-#[derive(Deserialize, Serialize, Default, Debug)]
+#[derive(Deserialize, Serialize, Default, Debug, JsonSchema)]
 pub struct JsonReceiverTxReceipt {
     pub recipient: JsonPublicAddress,
     pub tx_public_key: String,
@@ -422,14 +515,19 @@ impl From<&api::ReceiverTxReceipt> for JsonReceiverTxReceipt {
     }
 }
 
-#[derive(Deserialize, Serialize, Default, Debug)]
+#[derive(Deserialize, Serialize, Default, Debug, JsonSchema)]
 pub struct JsonSendPaymentRequest {
     pub request_data: JsonParseRequestCodeResponse,
     pub max_input_utxo_value: Option<JsonU64>,
     pub change_subaddress: Option<JsonU64>,
+    pub utxo_selection_strategy: Option<JsonUtxoSelectionStrategy>,
+    /// Outlays to pay instead of the receiver and value of the request data,
+    /// each in its own token id, atomically in a single transaction. The fee
+    /// is paid in the token id of the request data.
+    pub multi_token_outlays: Option<Vec<JsonOutlay>>,
 }
 
-#[derive(Deserialize, Serialize, Default, Debug)]
+#[derive(Deserialize, Serialize, Default, Debug, JsonSchema)]
 pub struct JsonSendPaymentResponse {
     pub sender_tx_receipt: JsonSenderTxReceipt,
     pub receiver_tx_receipt_list: Vec<JsonReceiverTxReceipt>,
@@ -448,18 +546,49 @@ impl From<&api::SendPaymentResponse> for JsonSendPaymentResponse {
     }
 }
 
-#[derive(Deserialize, Serialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, JsonSchema)]
 pub struct JsonPayAddressCodeRequest {
     pub receiver_b58_address_code: String,
     pub value: JsonU64,
     pub max_input_utxo_value: Option<JsonU64>,
     pub change_subaddress: Option<JsonU64>,
+    #[serde(default)]
+    pub token_id: JsonU64,
+    pub utxo_selection_strategy: Option<JsonUtxoSelectionStrategy>,
+}
+
+/// Strategy used to choose the inputs of a transaction.
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum JsonUtxoSelectionStrategy {
+    /// Spend the smallest UTXOs first.
+    SmallestFirst,
+    /// Spend the largest UTXOs first, minimizing the number of inputs.
+    LargestFirst,
+    /// Look for inputs that need no change output, falling back to
+    /// smallest first.
+    BranchAndBound,
+    /// Spend UTXOs in a random order.
+    Randomized,
+}
+
+impl From<JsonUtxoSelectionStrategy> for api::UtxoSelectionStrategy {
+    fn from(src: JsonUtxoSelectionStrategy) -> Self {
+        match src {
+            JsonUtxoSelectionStrategy::SmallestFirst => api::UtxoSelectionStrategy::SmallestFirst,
+            JsonUtxoSelectionStrategy::LargestFirst => api::UtxoSelectionStrategy::LargestFirst,
+            JsonUtxoSelectionStrategy::BranchAndBound => api::UtxoSelectionStrategy::BranchAndBound,
+            JsonUtxoSelectionStrategy::Randomized => api::UtxoSelectionStrategy::Randomized,
+        }
+    }
 }
 
-#[derive(Deserialize, Serialize, Default, Debug, Clone)]
+#[derive(Deserialize, Serialize, Default, Debug, Clone, JsonSchema)]
 pub struct JsonOutlay {
     pub value: JsonU64,
     pub receiver: JsonPublicAddress,
+    #[serde(default)]
+    pub token_id: JsonU64,
 }
 
 impl From<&api::Outlay> for JsonOutlay {
@@ -467,6 +596,7 @@ impl From<&api::Outlay> for JsonOutlay {
         Self {
             value: JsonU64(src.get_value()),
             receiver: src.get_receiver().into(),
+            token_id: JsonU64(src.get_token_id()),
         }
     }
 }
@@ -481,12 +611,13 @@ impl TryFrom<&JsonOutlay> for api::Outlay {
             PublicAddress::try_from(&src.receiver)
                 .map_err(|err| format!("Could not convert receiver: {}", err))?,
         );
+        outlay.set_token_id(src.token_id.into());
 
         Ok(outlay)
     }
 }
 
-#[derive(Deserialize, Serialize, Default, Debug, Clone)]
+#[derive(Deserialize, Serialize, Default, Debug, Clone, JsonSchema)]
 pub struct JsonMaskedAmount {
     pub commitment: String,
     pub masked_value: JsonU64,
@@ -503,7 +634,7 @@ impl From<&MaskedAmount> for JsonMaskedAmount {
     }
 }
 
-#[derive(Deserialize, Serialize, Default, Debug, Clone)]
+#[derive(Deserialize, Serialize, Default, Debug, Clone, JsonSchema)]
 pub struct JsonTxOut {
     pub masked_amount: JsonMaskedAmount,
     pub target_key: String,
@@ -575,13 +706,13 @@ impl TryFrom<&JsonTxOut> for mc_api::external::TxOut {
     }
 }
 
-#[derive(Deserialize, Serialize, Default, Debug, Clone)]
+#[derive(Deserialize, Serialize, Default, Debug, Clone, JsonSchema)]
 pub struct JsonRange {
     pub from: JsonU64,
     pub to: JsonU64,
 }
 
-#[derive(Deserialize, Serialize, Default, Debug, Clone)]
+#[derive(Deserialize, Serialize, Default, Debug, Clone, JsonSchema)]
 pub struct JsonTxOutMembershipElement {
     pub range: JsonRange,
     pub hash: String,
@@ -599,7 +730,7 @@ impl From<&TxOutMembershipElement> for JsonTxOutMembershipElement {
     }
 }
 
-#[derive(Deserialize, Serialize, Default, Debug, Clone)]
+#[derive(Deserialize, Serialize, Default, Debug, Clone, JsonSchema)]
 pub struct JsonTxOutMembershipProof {
     pub index: JsonU64,
     pub highest_index: JsonU64,
@@ -651,7 +782,7 @@ impl TryFrom<&JsonTxOutMembershipProof> for TxOutMembershipProof {
     }
 }
 
-#[derive(Deserialize, Serialize, Default, Debug)]
+#[derive(Deserialize, Serialize, Default, Debug, JsonSchema)]
 /// A request for randomly sampled TxOuts for use as mixins.
 pub struct JsonMixinRequest {
     /// Number of mixins requested.
@@ -660,7 +791,7 @@ pub struct JsonMixinRequest {
     pub excluded: Vec<JsonTxOut>,
 }
 
-#[derive(Deserialize, Serialize, Default, Debug)]
+#[derive(Deserialize, Serialize, Default, Debug, JsonSchema)]
 /// Randomly sampled TxOuts for use as mixins, with membership proofs.
 pub struct JsonMixinResponse {
     /// TxOuts to use as mixins.
@@ -669,13 +800,13 @@ pub struct JsonMixinResponse {
     pub membership_proofs: Vec<JsonTxOutMembershipProof>,
 }
 
-#[derive(Deserialize, Serialize, Default, Debug)]
+#[derive(Deserialize, Serialize, Default, Debug, JsonSchema)]
 /// Requests Merkle proof-of-membership for each queried TxOut
 pub struct JsonMembershipProofRequest {
     pub outputs: Vec<JsonTxOut>,
 }
 
-#[derive(Deserialize, Serialize, Default, Debug)]
+#[derive(Deserialize, Serialize, Default, Debug, JsonSchema)]
 /// Outputs and their corresponding proofs of membership.
 pub struct JsonMembershipProofResponse {
     /// Queried outputs.
@@ -684,10 +815,50 @@ pub struct JsonMembershipProofResponse {
     pub membership_proofs: Vec<JsonTxOutMembershipProof>,
 }
 
-#[derive(Deserialize, Serialize, Default, Debug, Clone)]
+#[derive(Deserialize, Serialize, Default, Debug, Clone, JsonSchema)]
+pub struct JsonRevealedTxOut {
+    pub tx_out: JsonTxOut,
+    pub shared_secret: String,
+}
+
+impl From<&RevealedTxOut> for JsonRevealedTxOut {
+    fn from(src: &RevealedTxOut) -> Self {
+        Self {
+            tx_out: src.get_tx_out().into(),
+            shared_secret: hex::encode(src.get_shared_secret().get_data()),
+        }
+    }
+}
+
+impl TryFrom<&JsonRevealedTxOut> for RevealedTxOut {
+    type Error = String;
+
+    fn try_from(src: &JsonRevealedTxOut) -> Result<RevealedTxOut, String> {
+        let mut shared_secret = CompressedRistretto::new();
+        shared_secret.set_data(
+            hex::decode(&src.shared_secret)
+                .map_err(|err| format!("Failed to decode shared secret hex: {}", err))?,
+        );
+
+        let mut revealed_tx_out = RevealedTxOut::new();
+        revealed_tx_out.set_tx_out(
+            mc_api::external::TxOut::try_from(&src.tx_out)
+                .map_err(|err| format!("Could not get TxOut: {}", err))?,
+        );
+        revealed_tx_out.set_shared_secret(shared_secret);
+        Ok(revealed_tx_out)
+    }
+}
+
+#[derive(Deserialize, Serialize, Default, Debug, Clone, JsonSchema)]
 pub struct JsonInputRules {
     pub required_outputs: Vec<JsonTxOut>,
     pub max_tombstone_block: u64,
+    #[serde(default)]
+    pub partial_fill_outputs: Vec<JsonRevealedTxOut>,
+    pub partial_fill_change: Option<JsonRevealedTxOut>,
+    #[serde(default)]
+    pub min_partial_fill_value: u64,
 }
 
 impl From<&InputRules> for JsonInputRules {
@@ -699,6 +870,16 @@ impl From<&InputRules> for JsonInputRules {
                 .map(JsonTxOut::from)
                 .collect(),
             max_tombstone_block: src.max_tombstone_block,
+            partial_fill_outputs: src
+                .get_partial_fill_outputs()
+                .iter()
+                .map(JsonRevealedTxOut::from)
+                .collect(),
+            partial_fill_change: src
+                .partial_fill_change
+                .as_ref()
+                .map(JsonRevealedTxOut::from),
+            min_partial_fill_value: src.min_partial_fill_value,
         }
     }
 }
@@ -718,11 +899,21 @@ impl TryFrom<&JsonInputRules> for InputRules {
                 .collect::<Result<_, String>>()?,
         );
         input_rules.max_tombstone_block = src.max_tombstone_block;
+        input_rules.set_partial_fill_outputs(
+            src.partial_fill_outputs
+                .iter()
+                .map(RevealedTxOut::try_from)
+                .collect::<Result<_, String>>()?,
+        );
+        if let Some(partial_fill_change) = src.partial_fill_change.as_ref() {
+            input_rules.set_partial_fill_change(RevealedTxOut::try_from(partial_fill_change)?);
+        }
+        input_rules.min_partial_fill_value = src.min_partial_fill_value;
         Ok(input_rules)
     }
 }
 
-#[derive(Deserialize, Serialize, Default, Debug, Clone)]
+#[derive(Deserialize, Serialize, Default, Debug, Clone, JsonSchema)]
 pub struct JsonTxIn {
     pub ring: Vec<JsonTxOut>,
     pub proofs: Vec<JsonTxOutMembershipProof>,
@@ -778,7 +969,7 @@ impl TryFrom<&JsonTxIn> for TxIn {
     }
 }
 
-#[derive(Deserialize, Serialize, Default, Debug, Clone)]
+#[derive(Deserialize, Serialize, Default, Debug, Clone, JsonSchema)]
 pub struct JsonTxPrefix {
     pub inputs: Vec<JsonTxIn>,
     pub outputs: Vec<JsonTxOut>,
@@ -825,7 +1016,7 @@ impl TryFrom<&JsonTxPrefix> for TxPrefix {
     }
 }
 
-#[derive(Deserialize, Serialize, Default, Debug, Clone)]
+#[derive(Deserialize, Serialize, Default, Debug, Clone, JsonSchema)]
 pub struct JsonRingMLSAG {
     pub c_zero: String,
     pub responses: Vec<String>,
@@ -846,7 +1037,41 @@ impl From<&RingMLSAG> for JsonRingMLSAG {
     }
 }
 
-#[derive(Deserialize, Serialize, Default, Debug, Clone)]
+impl TryFrom<&JsonRingMLSAG> for RingMLSAG {
+    type Error = String;
+
+    fn try_from(src: &JsonRingMLSAG) -> Result<RingMLSAG, String> {
+        let mut c_zero = CurveScalar::new();
+        c_zero.set_data(
+            hex::decode(&src.c_zero)
+                .map_err(|err| format!("Could not decode from hex: {}", err))?,
+        );
+
+        let mut responses: Vec<CurveScalar> = Vec::new();
+        for resp in &src.responses {
+            let mut response = CurveScalar::new();
+            response.set_data(
+                hex::decode(resp).map_err(|err| format!("Could not decode from hex: {}", err))?,
+            );
+            responses.push(response);
+        }
+
+        let mut key_image = KeyImage::new();
+        key_image.set_data(
+            hex::decode(&src.key_image)
+                .map_err(|err| format!("Could not decode from hex: {}", err))?,
+        );
+
+        let mut ring_sig = RingMLSAG::new();
+        ring_sig.set_c_zero(c_zero);
+        ring_sig.set_responses(RepeatedField::from_vec(responses));
+        ring_sig.set_key_image(key_image);
+
+        Ok(ring_sig)
+    }
+}
+
+#[derive(Deserialize, Serialize, Default, Debug, Clone, JsonSchema)]
 pub struct JsonSignatureRctBulletproofs {
     pub ring_signatures: Vec<JsonRingMLSAG>,
     pub pseudo_output_commitments: Vec<String>,
@@ -881,37 +1106,11 @@ impl TryFrom<&JsonSignatureRctBulletproofs> for SignatureRctBulletproofs {
     type Error = String;
 
     fn try_from(src: &JsonSignatureRctBulletproofs) -> Result<SignatureRctBulletproofs, String> {
-        let mut ring_sigs: Vec<RingMLSAG> = Vec::new();
-        for sig in &src.ring_signatures {
-            let mut c_zero = mc_api::external::CurveScalar::new();
-            c_zero.set_data(
-                hex::decode(&sig.c_zero)
-                    .map_err(|err| format!("Could not decode from hex: {}", err))?,
-            );
-
-            let mut responses: Vec<mc_api::external::CurveScalar> = Vec::new();
-            for resp in &sig.responses {
-                let mut response = mc_api::external::CurveScalar::new();
-                response.set_data(
-                    hex::decode(resp)
-                        .map_err(|err| format!("Could not decode from hex: {}", err))?,
-                );
-                responses.push(response);
-            }
-
-            let mut key_image = KeyImage::new();
-            key_image.set_data(
-                hex::decode(&sig.key_image)
-                    .map_err(|err| format!("Could not decode from hex: {}", err))?,
-            );
-
-            let mut ring_sig = RingMLSAG::new();
-            ring_sig.set_c_zero(c_zero);
-            ring_sig.set_responses(RepeatedField::from_vec(responses));
-            ring_sig.set_key_image(key_image);
-
-            ring_sigs.push(ring_sig);
-        }
+        let ring_sigs = src
+            .ring_signatures
+            .iter()
+            .map(RingMLSAG::try_from)
+            .collect::<Result<Vec<_>, String>>()?;
 
         let mut commitments: Vec<CompressedRistretto> = Vec::new();
         for comm in &src.pseudo_output_commitments {
@@ -955,7 +1154,7 @@ impl TryFrom<&JsonSignatureRctBulletproofs> for SignatureRctBulletproofs {
     }
 }
 
-#[derive(Deserialize, Serialize, Default, Debug, Clone)]
+#[derive(Deserialize, Serialize, Default, Debug, Clone, JsonSchema)]
 pub struct JsonTx {
     pub prefix: JsonTxPrefix,
     pub signature: JsonSignatureRctBulletproofs,
@@ -989,7 +1188,99 @@ impl TryFrom<&JsonTx> for Tx {
     }
 }
 
-#[derive(Deserialize, Serialize, Default, Debug)]
+#[derive(Deserialize, Serialize, Default, Debug, Clone, JsonSchema)]
+pub struct JsonUnmaskedAmount {
+    pub value: JsonU64,
+    pub token_id: JsonU64,
+    /// Hex encoded blinding factor of the amount commitment
+    pub blinding: String,
+}
+
+impl From<&UnmaskedAmount> for JsonUnmaskedAmount {
+    fn from(src: &UnmaskedAmount) -> Self {
+        Self {
+            value: JsonU64(src.get_value()),
+            token_id: JsonU64(src.get_token_id()),
+            blinding: hex::encode(src.get_blinding().get_data()),
+        }
+    }
+}
+
+impl TryFrom<&JsonUnmaskedAmount> for UnmaskedAmount {
+    type Error = String;
+
+    fn try_from(src: &JsonUnmaskedAmount) -> Result<UnmaskedAmount, String> {
+        let mut blinding = CurveScalar::new();
+        blinding.set_data(
+            hex::decode(&src.blinding)
+                .map_err(|err| format!("Could not decode blinding from hex: {}", err))?,
+        );
+
+        let mut amount = UnmaskedAmount::new();
+        amount.set_value(src.value.into());
+        amount.set_token_id(src.token_id.into());
+        amount.set_blinding(blinding);
+        Ok(amount)
+    }
+}
+
+#[derive(Deserialize, Serialize, Default, Debug, Clone, JsonSchema)]
+pub struct JsonSignedContingentInput {
+    pub block_version: u32,
+    pub tx_in: JsonTxIn,
+    pub mlsag: JsonRingMLSAG,
+    pub pseudo_output_amount: JsonUnmaskedAmount,
+    pub required_output_amounts: Vec<JsonUnmaskedAmount>,
+    pub tx_out_global_indices: Vec<JsonU64>,
+}
+
+impl From<&SignedContingentInput> for JsonSignedContingentInput {
+    fn from(src: &SignedContingentInput) -> Self {
+        Self {
+            block_version: src.get_block_version(),
+            tx_in: src.get_tx_in().into(),
+            mlsag: src.get_mlsag().into(),
+            pseudo_output_amount: src.get_pseudo_output_amount().into(),
+            required_output_amounts: src
+                .get_required_output_amounts()
+                .iter()
+                .map(JsonUnmaskedAmount::from)
+                .collect(),
+            tx_out_global_indices: src
+                .get_tx_out_global_indices()
+                .iter()
+                .map(Into::into)
+                .collect(),
+        }
+    }
+}
+
+impl TryFrom<&JsonSignedContingentInput> for SignedContingentInput {
+    type Error = String;
+
+    fn try_from(src: &JsonSignedContingentInput) -> Result<SignedContingentInput, String> {
+        let mut sci = SignedContingentInput::new();
+        sci.set_block_version(src.block_version);
+        sci.set_tx_in(
+            TxIn::try_from(&src.tx_in).map_err(|err| format!("Could not get TxIn: {}", err))?,
+        );
+        sci.set_mlsag(
+            RingMLSAG::try_from(&src.mlsag)
+                .map_err(|err| format!("Could not get signature: {}", err))?,
+        );
+        sci.set_pseudo_output_amount(UnmaskedAmount::try_from(&src.pseudo_output_amount)?);
+        sci.set_required_output_amounts(
+            src.required_output_amounts
+                .iter()
+                .map(UnmaskedAmount::try_from)
+                .collect::<Result<_, String>>()?,
+        );
+        sci.set_tx_out_global_indices(src.tx_out_global_indices.iter().map(Into::into).collect());
+        Ok(sci)
+    }
+}
+
+#[derive(Deserialize, Serialize, Default, Debug, JsonSchema)]
 pub struct JsonTxProposal {
     pub input_list: Vec<JsonUnspentTxOut>,
     pub outlay_list: Vec<JsonOutlay>,
@@ -1061,13 +1352,18 @@ impl TryFrom<&JsonTxProposal> for api::TxProposal {
     }
 }
 
-#[derive(Deserialize, Serialize, Default, Debug)]
+#[derive(Deserialize, Serialize, Default, Debug, JsonSchema)]
 pub struct JsonCreateTxProposalRequest {
     pub input_list: Vec<JsonUnspentTxOut>,
     pub transfer: JsonParseRequestCodeResponse,
+    pub utxo_selection_strategy: Option<JsonUtxoSelectionStrategy>,
+    /// Outlays to pay instead of the receiver and value of the transfer,
+    /// each in its own token id, atomically in a single transaction. The fee
+    /// is paid in the token id of the transfer.
+    pub multi_token_outlays: Option<Vec<JsonOutlay>>,
 }
 
-#[derive(Deserialize, Serialize, Default, Debug)]
+#[derive(Deserialize, Serialize, Default, Debug, JsonSchema)]
 pub struct JsonCreateTxProposalResponse {
     pub tx_proposal: JsonTxProposal,
 }
@@ -1080,12 +1376,196 @@ impl From<&api::GenerateTxResponse> for JsonCreateTxProposalResponse {
     }
 }
 
-#[derive(Deserialize, Serialize, Default, Debug)]
+impl From<&api::GenerateOptimizationTxResponse> for JsonCreateTxProposalResponse {
+    fn from(src: &api::GenerateOptimizationTxResponse) -> Self {
+        Self {
+            tx_proposal: src.get_tx_proposal().into(),
+        }
+    }
+}
+
+impl From<&api::GenerateTxFromTxOutListResponse> for JsonCreateTxProposalResponse {
+    fn from(src: &api::GenerateTxFromTxOutListResponse) -> Self {
+        Self {
+            tx_proposal: src.get_tx_proposal().into(),
+        }
+    }
+}
+
+impl From<&api::GenerateBurnRedemptionTxResponse> for JsonCreateTxProposalResponse {
+    fn from(src: &api::GenerateBurnRedemptionTxResponse) -> Self {
+        Self {
+            tx_proposal: src.get_tx_proposal().into(),
+        }
+    }
+}
+
+impl From<&api::GenerateSwapTxResponse> for JsonCreateTxProposalResponse {
+    fn from(src: &api::GenerateSwapTxResponse) -> Self {
+        Self {
+            tx_proposal: src.get_tx_proposal().into(),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Default, Debug, JsonSchema)]
+pub struct JsonGenerateOptimizationTxRequest {
+    pub fee: Option<JsonU64>,
+    #[serde(default)]
+    pub token_id: JsonU64,
+}
+
+#[derive(Deserialize, Serialize, Default, Debug, JsonSchema)]
+pub struct JsonGenerateTransferCodeTxRequest {
+    pub input_list: Vec<JsonUnspentTxOut>,
+    pub value: JsonU64,
+    pub fee: Option<JsonU64>,
+    pub tombstone: Option<JsonU64>,
+    #[serde(default)]
+    pub memo: String,
+    #[serde(default)]
+    pub token_id: JsonU64,
+}
+
+#[derive(Deserialize, Serialize, Default, Debug, JsonSchema)]
+pub struct JsonGenerateTransferCodeTxResponse {
+    pub tx_proposal: JsonTxProposal,
+    pub tx_public_key: String,
+    pub memo: String,
+    pub b58_code: String,
+    pub bip39_entropy: String,
+}
+
+impl From<&api::GenerateTransferCodeTxResponse> for JsonGenerateTransferCodeTxResponse {
+    fn from(src: &api::GenerateTransferCodeTxResponse) -> Self {
+        Self {
+            tx_proposal: src.get_tx_proposal().into(),
+            tx_public_key: hex::encode(src.get_tx_public_key().get_data()),
+            memo: src.get_memo().to_string(),
+            b58_code: src.get_b58_code().to_string(),
+            bip39_entropy: hex::encode(src.get_bip39_entropy()),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Default, Debug, JsonSchema)]
+pub struct JsonGenerateTxFromTxOutListRequest {
+    pub account_key: JsonAccountKeyResponse,
+    pub input_list: Vec<JsonUnspentTxOut>,
+    pub receiver: JsonPublicAddress,
+    pub fee: Option<JsonU64>,
+    #[serde(default)]
+    pub token_id: JsonU64,
+}
+
+#[derive(Deserialize, Serialize, Default, Debug, JsonSchema)]
+pub struct JsonGenerateBurnRedemptionTxRequest {
+    pub input_list: Vec<JsonUnspentTxOut>,
+    pub burn_amount: JsonU64,
+    pub fee: Option<JsonU64>,
+    pub tombstone: Option<JsonU64>,
+    #[serde(default)]
+    pub token_id: JsonU64,
+    /// Hex encoded memo data, at most 64 bytes. Zeros are used if omitted.
+    pub redemption_memo: Option<String>,
+    #[serde(default)]
+    pub enable_destination_memo: bool,
+}
+
+#[derive(Deserialize, Serialize, Default, Debug, JsonSchema)]
+pub struct JsonTokenAmount {
+    pub value: JsonU64,
+    pub token_id: JsonU64,
+}
+
+impl From<&api::TokenAmount> for JsonTokenAmount {
+    fn from(src: &api::TokenAmount) -> Self {
+        Self {
+            value: JsonU64(src.get_value()),
+            token_id: JsonU64(src.get_token_id()),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Default, Debug, JsonSchema)]
+pub struct JsonParseSwapCodeResponse {
+    pub sci: JsonSignedContingentInput,
+    /// The value of the input, less any outputs its rules require in the same
+    /// token id.
+    pub offered_amount: JsonTokenAmount,
+    /// The amounts requested in exchange, by token id.
+    pub requested_amounts: Vec<JsonTokenAmount>,
+    /// The block index at which the offer expires, or 0 if it does not expire.
+    pub max_tombstone_block: JsonU64,
+    pub block_version: u32,
+    /// Whether the input was already spent, according to the local ledger.
+    pub spent: bool,
+    /// Whether the offer has expired, according to the local ledger.
+    pub expired: bool,
+}
+
+impl From<&api::ParseSwapCodeResponse> for JsonParseSwapCodeResponse {
+    fn from(src: &api::ParseSwapCodeResponse) -> Self {
+        Self {
+            sci: src.get_sci().into(),
+            offered_amount: src.get_offered_amount().into(),
+            requested_amounts: src
+                .get_requested_amounts()
+                .iter()
+                .map(JsonTokenAmount::from)
+                .collect(),
+            max_tombstone_block: JsonU64(src.get_max_tombstone_block()),
+            block_version: src.get_block_version(),
+            spent: src.get_spent(),
+            expired: src.get_expired(),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Default, Debug, JsonSchema)]
+pub struct JsonGenerateSwapRequest {
+    /// The UTXO offered. Its whole value is offered.
+    pub input: JsonUnspentTxOut,
+    pub counter_value: JsonU64,
+    #[serde(default)]
+    pub counter_token_id: JsonU64,
+    /// Block index at which the offer expires. It does not expire if omitted.
+    pub tombstone: Option<JsonU64>,
+}
+
+#[derive(Deserialize, Serialize, Default, Debug, JsonSchema)]
+pub struct JsonGenerateSwapResponse {
+    pub sci: JsonSignedContingentInput,
+    pub b58_code: String,
+}
+
+impl From<&api::GenerateSwapResponse> for JsonGenerateSwapResponse {
+    fn from(src: &api::GenerateSwapResponse) -> Self {
+        Self {
+            sci: src.get_sci().into(),
+            b58_code: src.get_b58_code().to_string(),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Default, Debug, JsonSchema)]
+pub struct JsonGenerateSwapTxRequest {
+    pub input_list: Vec<JsonUnspentTxOut>,
+    /// The signed contingent input to fill, e.g. from a parsed swap code.
+    pub sci: JsonSignedContingentInput,
+    pub fee: Option<JsonU64>,
+    pub tombstone: Option<JsonU64>,
+    #[serde(default)]
+    pub fee_token_id: JsonU64,
+    pub utxo_selection_strategy: Option<JsonUtxoSelectionStrategy>,
+}
+
+#[derive(Deserialize, Serialize, Default, Debug, JsonSchema)]
 pub struct JsonTxProposalRequest {
     pub tx_proposal: JsonTxProposal,
 }
 
-#[derive(Deserialize, Serialize, Default, Debug)]
+#[derive(Deserialize, Serialize, Default, Debug, JsonSchema)]
 pub struct JsonSubmitTxResponse {
     pub sender_tx_receipt: JsonSenderTxReceipt,
     pub receiver_tx_receipt_list: Vec<JsonReceiverTxReceipt>,
@@ -1161,7 +1641,7 @@ impl TryFrom<&JsonSubmitTxResponse> for api::SubmitTxResponse {
     }
 }
 
-#[derive(Serialize, Default, Debug)]
+#[derive(Serialize, Default, Debug, JsonSchema)]
 pub struct JsonStatusResponse {
     pub status: String,
 }
@@ -1210,7 +1690,7 @@ impl From<&api::GetTxStatusAsReceiverResponse> for JsonStatusResponse {
     }
 }
 
-#[derive(Serialize, Default, Debug)]
+#[derive(Serialize, Default, Debug, JsonSchema)]
 pub struct JsonLedgerInfoResponse {
     pub block_count: JsonU64,
     pub txo_count: JsonU64,
@@ -1225,7 +1705,7 @@ impl From<&api::GetLedgerInfoResponse> for JsonLedgerInfoResponse {
     }
 }
 
-#[derive(Serialize, Default, Debug)]
+#[derive(Serialize, Default, Debug, JsonSchema)]
 pub struct JsonBlockInfoResponse {
     pub key_image_count: JsonU64,
     pub txo_count: JsonU64,
@@ -1240,7 +1720,85 @@ impl From<&api::GetBlockInfoResponse> for JsonBlockInfoResponse {
     }
 }
 
-#[derive(Serialize, Default, Debug)]
+#[derive(Serialize, Default, Debug, JsonSchema)]
+pub struct JsonLastBlockInfo {
+    pub index: JsonU64,
+    /// Map of token id -> minimum fee
+    pub minimum_fees: BTreeMap<String, JsonU64>,
+    pub network_block_version: u32,
+}
+
+#[derive(Serialize, Default, Debug, JsonSchema)]
+pub struct JsonNetworkStatusResponse {
+    pub network_highest_block_index: JsonU64,
+    /// Map of node responder id -> block index reported by it
+    pub peer_block_index_map: BTreeMap<String, JsonU64>,
+    pub local_block_index: JsonU64,
+    pub is_behind: bool,
+    pub last_block_info: JsonLastBlockInfo,
+    /// Health of each consensus node
+    pub peer_health: Vec<JsonPeerHealth>,
+}
+
+#[derive(Serialize, Default, Debug, JsonSchema)]
+pub struct JsonPeerHealth {
+    pub responder_id: String,
+    pub score: f64,
+    pub latency_ms: JsonU64,
+    pub error_rate: f64,
+    pub block_index: JsonU64,
+    pub blocks_behind: JsonU64,
+    pub is_quarantined: bool,
+    pub quarantine_remaining_ms: JsonU64,
+}
+
+impl From<&api::PeerHealth> for JsonPeerHealth {
+    fn from(src: &api::PeerHealth) -> Self {
+        Self {
+            responder_id: src.responder_id.clone(),
+            score: src.score,
+            latency_ms: JsonU64(src.latency_ms),
+            error_rate: src.error_rate,
+            block_index: JsonU64(src.block_index),
+            blocks_behind: JsonU64(src.blocks_behind),
+            is_quarantined: src.is_quarantined,
+            quarantine_remaining_ms: JsonU64(src.quarantine_remaining_ms),
+        }
+    }
+}
+
+impl From<&api::GetNetworkStatusResponse> for JsonNetworkStatusResponse {
+    fn from(src: &api::GetNetworkStatusResponse) -> Self {
+        let last_block_info = src.get_last_block_info();
+
+        Self {
+            network_highest_block_index: JsonU64(src.network_highest_block_index),
+            peer_block_index_map: src
+                .get_peer_block_index_map()
+                .iter()
+                .map(|(responder_id, block_index)| (responder_id.clone(), JsonU64(*block_index)))
+                .collect(),
+            local_block_index: JsonU64(src.local_block_index),
+            is_behind: src.is_behind,
+            last_block_info: JsonLastBlockInfo {
+                index: JsonU64(last_block_info.index),
+                minimum_fees: last_block_info
+                    .get_minimum_fees()
+                    .iter()
+                    .map(|(token_id, fee)| (token_id.to_string(), JsonU64(*fee)))
+                    .collect(),
+                network_block_version: last_block_info.network_block_version,
+            },
+            peer_health: src
+                .get_peer_health()
+                .iter()
+                .map(JsonPeerHealth::from)
+                .collect(),
+        }
+    }
+}
+
+#[derive(Serialize, Default, Debug, JsonSchema)]
 pub struct JsonBlockDetailsResponse {
     pub block_id: String,
     pub version: u32,
@@ -1273,7 +1831,7 @@ impl From<&api::GetBlockResponse> for JsonBlockDetailsResponse {
     }
 }
 
-#[derive(Serialize, Default, Debug)]
+#[derive(Serialize, Default, Debug, JsonSchema)]
 pub struct JsonProcessedTxOut {
     pub monitor_id: String,
     pub subaddress_index: u64,
@@ -1281,6 +1839,8 @@ pub struct JsonProcessedTxOut {
     pub key_image: String,
     pub value: JsonU64,
     pub direction: String,
+    pub address_code: String,
+    pub token_id: JsonU64,
 }
 
 impl From<&api::ProcessedTxOut> for JsonProcessedTxOut {
@@ -1298,11 +1858,13 @@ impl From<&api::ProcessedTxOut> for JsonProcessedTxOut {
             key_image: hex::encode(&src.get_key_image().get_data()),
             value: JsonU64(src.value),
             direction: direction_str.to_owned(),
+            address_code: src.get_address_code().to_string(),
+            token_id: JsonU64(src.token_id),
         }
     }
 }
 
-#[derive(Serialize, Default, Debug)]
+#[derive(Serialize, Default, Debug, JsonSchema)]
 pub struct JsonProcessedBlockResponse {
     pub tx_outs: Vec<JsonProcessedTxOut>,
 }
@@ -1319,7 +1881,53 @@ impl From<&api::GetProcessedBlockResponse> for JsonProcessedBlockResponse {
     }
 }
 
-#[derive(Serialize, Default, Debug)]
+/// An event on the stream of a monitor, sent as a server-sent event.
+#[derive(Serialize, Default, Debug, JsonSchema)]
+pub struct JsonMonitorEvent {
+    pub monitor_id: String,
+    pub block_index: JsonU64,
+    /// One of "utxo_received", "utxo_spent", "block_processed" or
+    /// "caught_up".
+    pub event_type: String,
+    /// Set for "utxo_received" and "utxo_spent" events
+    pub tx_out: Option<JsonProcessedTxOut>,
+    /// Set for "block_processed" events
+    pub num_received: Option<JsonU64>,
+    /// Set for "block_processed" events
+    pub num_spent: Option<JsonU64>,
+    /// Set for "caught_up" events
+    pub next_block: Option<JsonU64>,
+}
+
+impl From<&api::MonitorEvent> for JsonMonitorEvent {
+    fn from(src: &api::MonitorEvent) -> Self {
+        let mut event = Self {
+            monitor_id: hex::encode(src.get_monitor_id()),
+            block_index: JsonU64(src.block_index),
+            ..Default::default()
+        };
+
+        if src.has_utxo_received() {
+            event.event_type = "utxo_received".to_owned();
+            event.tx_out = Some(src.get_utxo_received().into());
+        } else if src.has_utxo_spent() {
+            event.event_type = "utxo_spent".to_owned();
+            event.tx_out = Some(src.get_utxo_spent().into());
+        } else if src.has_block_processed() {
+            let block_processed = src.get_block_processed();
+            event.event_type = "block_processed".to_owned();
+            event.num_received = Some(JsonU64(block_processed.num_received));
+            event.num_spent = Some(JsonU64(block_processed.num_spent));
+        } else if src.has_caught_up() {
+            event.event_type = "caught_up".to_owned();
+            event.next_block = Some(JsonU64(src.get_caught_up().next_block));
+        }
+
+        event
+    }
+}
+
+#[derive(Serialize, Default, Debug, JsonSchema)]
 pub struct JsonBlockIndexByTxPubKeyResponse {
     pub block_index: String,
 }
@@ -1332,7 +1940,7 @@ impl From<&api::GetBlockIndexByTxPubKeyResponse> for JsonBlockIndexByTxPubKeyRes
     }
 }
 
-#[derive(Serialize, Default, Debug)]
+#[derive(Serialize, Default, Debug, JsonSchema)]
 pub struct JsonMobilecoindVersionResponse {
     pub version: String,
 }
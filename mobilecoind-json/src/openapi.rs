@@ -0,0 +1,489 @@
+// Copyright (c) 2018-2022 The MobileCoin Foundation
+
+//! OpenAPI description of the mobilecoind-json routes.
+//!
+//! Request and response schemas are generated from the types in
+//! `data_types`, so the document stays in sync with what the server actually
+//! accepts and returns. The list of endpoints is checked against the mounted
+//! routes by a test in the binary.
+
+use crate::data_types::*;
+use schemars::{
+    gen::{SchemaGenerator, SchemaSettings},
+    schema::Schema,
+    JsonSchema,
+};
+use serde_json::{json, Map, Value};
+
+/// Description of a single route.
+pub struct Endpoint {
+    /// Lowercase HTTP method, e.g. "get".
+    pub method: &'static str,
+    /// Route path, using rocket's `<param>` syntax for path parameters.
+    pub path: &'static str,
+    /// One line summary of the route.
+    pub summary: &'static str,
+    /// Names of the optional integer query parameters of the route.
+    pub query: &'static [&'static str],
+    /// Schema of the JSON request body, if any.
+    pub request: Option<fn(&mut SchemaGenerator) -> Schema>,
+    /// Schema of the JSON response body, if any.
+    pub response: Option<fn(&mut SchemaGenerator) -> Schema>,
+    /// Whether the response is a stream of server-sent events, each carrying
+    /// a JSON payload.
+    pub event_stream: bool,
+}
+
+fn schema<T: JsonSchema>(gen: &mut SchemaGenerator) -> Schema {
+    gen.subschema_for::<T>()
+}
+
+const fn endpoint(method: &'static str, path: &'static str, summary: &'static str) -> Endpoint {
+    Endpoint {
+        method,
+        path,
+        summary,
+        query: &[],
+        request: None,
+        response: None,
+        event_stream: false,
+    }
+}
+
+/// All routes served by mobilecoind-json.
+pub const ENDPOINTS: &[Endpoint] = &[
+    Endpoint {
+        request: Some(schema::<JsonPasswordRequest>),
+        response: Some(schema::<JsonPasswordResponse>),
+        ..endpoint("post", "/set-password", "Set the password for the mobilecoind-db")
+    },
+    Endpoint {
+        request: Some(schema::<JsonUnlockDbRequest>),
+        response: Some(schema::<JsonUnlockDbResponse>),
+        ..endpoint("post", "/unlock-db", "Unlock a previously-encrypted mobilecoind-db")
+    },
+    Endpoint {
+        response: Some(schema::<JsonMobilecoindVersionResponse>),
+        ..endpoint("get", "/version", "Get the mobilecoind version")
+    },
+    Endpoint {
+        response: Some(schema::<JsonRootEntropyResponse>),
+        ..endpoint("post", "/entropy", "Generate a new root entropy")
+    },
+    Endpoint {
+        response: Some(schema::<JsonAccountKeyResponse>),
+        ..endpoint(
+            "get",
+            "/entropy/<root_entropy>",
+            "Get the account key for a root entropy",
+        )
+    },
+    Endpoint {
+        response: Some(schema::<JsonMnemonicResponse>),
+        ..endpoint("post", "/mnemonic", "Generate a new mnemonic")
+    },
+    Endpoint {
+        request: Some(schema::<JsonMnemonicResponse>),
+        response: Some(schema::<JsonAccountKeyResponse>),
+        ..endpoint(
+            "post",
+            "/account-key-from-mnemonic",
+            "Get the account key for a mnemonic",
+        )
+    },
+    Endpoint {
+        request: Some(schema::<JsonMonitorRequest>),
+        response: Some(schema::<JsonMonitorResponse>),
+        ..endpoint("post", "/monitors", "Add a monitor")
+    },
+    endpoint("delete", "/monitors/<monitor_hex>", "Remove a monitor"),
+    Endpoint {
+        response: Some(schema::<JsonMonitorListResponse>),
+        ..endpoint("get", "/monitors", "List the existing monitors")
+    },
+    Endpoint {
+        response: Some(schema::<JsonMonitorStatusResponse>),
+        ..endpoint("get", "/monitors/<monitor_hex>", "Get the status of a monitor")
+    },
+    Endpoint {
+        query: &["from_block"],
+        response: Some(schema::<JsonMonitorEvent>),
+        event_stream: true,
+        ..endpoint(
+            "get",
+            "/monitors/<monitor_hex>/events",
+            "Stream the events of a monitor",
+        )
+    },
+    Endpoint {
+        query: &["token_id"],
+        response: Some(schema::<JsonBalanceResponse>),
+        ..endpoint(
+            "get",
+            "/monitors/<monitor_hex>/subaddresses/<subaddress_index>/balance",
+            "Get the balance of a subaddress in a given token",
+        )
+    },
+    Endpoint {
+        query: &["token_id"],
+        response: Some(schema::<JsonUtxosResponse>),
+        ..endpoint(
+            "get",
+            "/monitors/<monitor_hex>/subaddresses/<subaddress_index>/utxos",
+            "Get the unspent TxOuts of a subaddress in a given token",
+        )
+    },
+    Endpoint {
+        response: Some(schema::<JsonPublicAddressResponse>),
+        ..endpoint(
+            "get",
+            "/monitors/<monitor_hex>/subaddresses/<subaddress_index>/public-address",
+            "Get the public address of a subaddress",
+        )
+    },
+    Endpoint {
+        request: Some(schema::<JsonCreateRequestCodeRequest>),
+        response: Some(schema::<JsonCreateRequestCodeResponse>),
+        ..endpoint("post", "/codes/request", "Create a request code")
+    },
+    Endpoint {
+        response: Some(schema::<JsonParseRequestCodeResponse>),
+        ..endpoint("get", "/codes/request/<b58_code>", "Parse a request code")
+    },
+    Endpoint {
+        request: Some(schema::<JsonCreateAddressCodeRequest>),
+        response: Some(schema::<JsonCreateAddressCodeResponse>),
+        ..endpoint("post", "/codes/address", "Create an address code")
+    },
+    Endpoint {
+        response: Some(schema::<JsonParseAddressCodeResponse>),
+        ..endpoint("get", "/codes/address/<b58_code>", "Parse an address code")
+    },
+    Endpoint {
+        request: Some(schema::<JsonCreateTransferCodeRequest>),
+        response: Some(schema::<JsonCreateTransferCodeResponse>),
+        ..endpoint("post", "/codes/transfer", "Create a transfer code")
+    },
+    Endpoint {
+        response: Some(schema::<JsonParseTransferCodeResponse>),
+        ..endpoint("get", "/codes/transfer/<b58_code>", "Parse a transfer code")
+    },
+    Endpoint {
+        response: Some(schema::<JsonParseSwapCodeResponse>),
+        ..endpoint("get", "/codes/swap/<b58_code>", "Parse a swap code")
+    },
+    Endpoint {
+        request: Some(schema::<JsonSendPaymentRequest>),
+        response: Some(schema::<JsonSendPaymentResponse>),
+        ..endpoint(
+            "post",
+            "/monitors/<monitor_hex>/subaddresses/<subaddress_index>/build-and-submit",
+            "Build and submit a payment",
+        )
+    },
+    Endpoint {
+        request: Some(schema::<JsonPayAddressCodeRequest>),
+        response: Some(schema::<JsonSendPaymentResponse>),
+        ..endpoint(
+            "post",
+            "/monitors/<monitor_hex>/subaddresses/<subaddress_index>/pay-address-code",
+            "Pay an address code",
+        )
+    },
+    Endpoint {
+        request: Some(schema::<JsonCreateTxProposalRequest>),
+        response: Some(schema::<JsonCreateTxProposalResponse>),
+        ..endpoint(
+            "post",
+            "/monitors/<monitor_hex>/subaddresses/<subaddress_index>/generate-request-code-transaction",
+            "Generate a transaction proposal from a list of inputs",
+        )
+    },
+    Endpoint {
+        request: Some(schema::<JsonGenerateOptimizationTxRequest>),
+        response: Some(schema::<JsonCreateTxProposalResponse>),
+        ..endpoint(
+            "post",
+            "/monitors/<monitor_hex>/subaddresses/<subaddress_index>/generate-optimization-tx",
+            "Generate a transaction that merges small UTXOs",
+        )
+    },
+    Endpoint {
+        request: Some(schema::<JsonGenerateTransferCodeTxRequest>),
+        response: Some(schema::<JsonGenerateTransferCodeTxResponse>),
+        ..endpoint(
+            "post",
+            "/monitors/<monitor_hex>/subaddresses/<subaddress_index>/generate-transfer-code-tx",
+            "Generate a transaction funding a transfer code",
+        )
+    },
+    Endpoint {
+        request: Some(schema::<JsonGenerateBurnRedemptionTxRequest>),
+        response: Some(schema::<JsonCreateTxProposalResponse>),
+        ..endpoint(
+            "post",
+            "/monitors/<monitor_hex>/subaddresses/<subaddress_index>/generate-burn-redemption-tx",
+            "Generate a transaction burning funds for redemption",
+        )
+    },
+    Endpoint {
+        request: Some(schema::<JsonGenerateSwapRequest>),
+        response: Some(schema::<JsonGenerateSwapResponse>),
+        ..endpoint(
+            "post",
+            "/monitors/<monitor_hex>/subaddresses/<subaddress_index>/generate-swap",
+            "Generate a signed contingent input offering a UTXO, and its swap code",
+        )
+    },
+    Endpoint {
+        request: Some(schema::<JsonGenerateSwapTxRequest>),
+        response: Some(schema::<JsonCreateTxProposalResponse>),
+        ..endpoint(
+            "post",
+            "/monitors/<monitor_hex>/subaddresses/<subaddress_index>/generate-swap-tx",
+            "Generate a transaction filling a signed contingent input",
+        )
+    },
+    Endpoint {
+        request: Some(schema::<JsonGenerateTxFromTxOutListRequest>),
+        response: Some(schema::<JsonCreateTxProposalResponse>),
+        ..endpoint(
+            "post",
+            "/tx/generate-from-tx-out-list",
+            "Generate a transaction spending a list of TxOuts of an account key",
+        )
+    },
+    Endpoint {
+        request: Some(schema::<JsonTxProposalRequest>),
+        response: Some(schema::<JsonSubmitTxResponse>),
+        ..endpoint("post", "/submit-tx", "Submit a transaction proposal")
+    },
+    Endpoint {
+        request: Some(schema::<JsonSubmitTxResponse>),
+        response: Some(schema::<JsonStatusResponse>),
+        ..endpoint(
+            "post",
+            "/tx/status-as-sender",
+            "Get the status of a transaction as its sender",
+        )
+    },
+    Endpoint {
+        request: Some(schema::<JsonReceiverTxReceipt>),
+        response: Some(schema::<JsonStatusResponse>),
+        ..endpoint(
+            "post",
+            "/monitors/<monitor_hex>/tx-status-as-receiver",
+            "Get the status of a transaction as its receiver",
+        )
+    },
+    Endpoint {
+        response: Some(schema::<JsonLedgerInfoResponse>),
+        ..endpoint("get", "/ledger/local", "Get information about the local ledger")
+    },
+    Endpoint {
+        response: Some(schema::<JsonNetworkStatusResponse>),
+        ..endpoint("get", "/ledger/network-status", "Get the status of the network")
+    },
+    Endpoint {
+        response: Some(schema::<JsonBlockInfoResponse>),
+        ..endpoint(
+            "get",
+            "/ledger/blocks/<block_num>/header",
+            "Get the number of key images and TxOuts in a block",
+        )
+    },
+    Endpoint {
+        response: Some(schema::<JsonBlockDetailsResponse>),
+        ..endpoint("get", "/ledger/blocks/<block_num>", "Get the contents of a block")
+    },
+    Endpoint {
+        response: Some(schema::<JsonProcessedBlockResponse>),
+        ..endpoint(
+            "get",
+            "/monitors/<monitor_hex>/processed-block/<block_num>",
+            "Get the TxOuts of a monitor that were processed in a block",
+        )
+    },
+    Endpoint {
+        response: Some(schema::<JsonBlockIndexByTxPubKeyResponse>),
+        ..endpoint(
+            "get",
+            "/tx-out/<public_key_hex>/block-index",
+            "Get the index of the block containing a TxOut",
+        )
+    },
+    Endpoint {
+        request: Some(schema::<JsonMixinRequest>),
+        response: Some(schema::<JsonMixinResponse>),
+        ..endpoint("post", "/tx-out/mixin", "Get TxOuts for use as mixins")
+    },
+    Endpoint {
+        request: Some(schema::<JsonMembershipProofRequest>),
+        response: Some(schema::<JsonMembershipProofResponse>),
+        ..endpoint(
+            "post",
+            "/tx-out/proof-of-membership",
+            "Get membership proofs for TxOuts",
+        )
+    },
+    endpoint("get", "/openapi.json", "Get this OpenAPI document"),
+];
+
+/// Path parameters whose value is an integer. All other path parameters are
+/// strings.
+const INTEGER_PATH_PARAMS: &[&str] = &["subaddress_index", "block_num"];
+
+/// Convert a rocket route path to an OpenAPI path, returning it together with
+/// the names of its path parameters.
+fn openapi_path(path: &str) -> (String, Vec<&str>) {
+    let mut params = Vec::new();
+    let segments: Vec<String> = path
+        .split('/')
+        .map(|segment| match segment.strip_prefix('<') {
+            Some(param) => {
+                let param = param.trim_end_matches('>');
+                params.push(param);
+                format!("{{{}}}", param)
+            }
+            None => segment.to_owned(),
+        })
+        .collect();
+    (segments.join("/"), params)
+}
+
+fn parameter(name: &str, location: &str, required: bool, integer: bool) -> Value {
+    let schema = if integer {
+        json!({ "type": "integer", "format": "uint64", "minimum": 0 })
+    } else {
+        json!({ "type": "string" })
+    };
+    json!({
+        "name": name,
+        "in": location,
+        "required": required,
+        "schema": schema,
+    })
+}
+
+/// Build the OpenAPI document describing all routes in `ENDPOINTS`.
+pub fn openapi_spec() -> Value {
+    let mut gen = SchemaSettings::openapi3().into_generator();
+    let mut paths = Map::new();
+
+    for endpoint in ENDPOINTS {
+        let (path, path_params) = openapi_path(endpoint.path);
+
+        let parameters: Vec<Value> = path_params
+            .iter()
+            .map(|name| parameter(name, "path", true, INTEGER_PATH_PARAMS.contains(name)))
+            .chain(
+                endpoint
+                    .query
+                    .iter()
+                    .map(|name| parameter(name, "query", false, true)),
+            )
+            .collect();
+
+        let mut operation = json!({
+            "summary": endpoint.summary,
+            "parameters": parameters,
+        });
+
+        if let Some(request) = endpoint.request {
+            operation["requestBody"] = json!({
+                "required": true,
+                "content": { "application/json": { "schema": request(&mut gen) } },
+            });
+        }
+
+        let content_type = if endpoint.event_stream {
+            "text/event-stream"
+        } else {
+            "application/json"
+        };
+        let success = match endpoint.response {
+            Some(response) => json!({
+                "description": "Success",
+                "content": { content_type: { "schema": response(&mut gen) } },
+            }),
+            None => json!({ "description": "Success" }),
+        };
+        operation["responses"] = json!({
+            "200": success,
+            "500": {
+                "description": "The request failed",
+                "content": { "text/plain": { "schema": { "type": "string" } } },
+            },
+        });
+
+        paths
+            .entry(path)
+            .or_insert_with(|| json!({}))
+            .as_object_mut()
+            .expect("path item is an object")
+            .insert(endpoint.method.to_owned(), operation);
+    }
+
+    json!({
+        "openapi": "3.0.3",
+        "info": {
+            "title": "mobilecoind-json",
+            "description": "A REST frontend for mobilecoind",
+            "version": env!("CARGO_PKG_VERSION"),
+        },
+        "paths": paths,
+        "components": { "schemas": gen.take_definitions() },
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_openapi_path() {
+        assert_eq!(
+            openapi_path("/monitors/<monitor_hex>/processed-block/<block_num>"),
+            (
+                "/monitors/{monitor_hex}/processed-block/{block_num}".to_owned(),
+                vec!["monitor_hex", "block_num"]
+            )
+        );
+        assert_eq!(openapi_path("/version"), ("/version".to_owned(), vec![]));
+    }
+
+    /// Every schema reference in the document must resolve to a component.
+    #[test]
+    fn test_openapi_spec_references_resolve() {
+        fn check_refs(value: &Value, schemas: &Map<String, Value>) {
+            match value {
+                Value::Object(map) => {
+                    if let Some(Value::String(reference)) = map.get("$ref") {
+                        let name = reference
+                            .strip_prefix("#/components/schemas/")
+                            .unwrap_or_else(|| panic!("unexpected reference {}", reference));
+                        assert!(schemas.contains_key(name), "missing schema {}", name);
+                    }
+                    map.values().for_each(|value| check_refs(value, schemas));
+                }
+                Value::Array(values) => values.iter().for_each(|value| check_refs(value, schemas)),
+                _ => {}
+            }
+        }
+
+        let spec = openapi_spec();
+        let schemas = spec["components"]["schemas"].as_object().unwrap();
+        assert!(schemas.contains_key("JsonNetworkStatusResponse"));
+        assert!(schemas.contains_key("JsonUtxoSelectionStrategy"));
+        check_refs(&spec, schemas);
+
+        let paths = spec["paths"].as_object().unwrap();
+        assert_eq!(
+            paths
+                .values()
+                .map(|item| item.as_object().unwrap().len())
+                .sum::<usize>(),
+            ENDPOINTS.len()
+        );
+    }
+}
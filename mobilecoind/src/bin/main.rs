@@ -123,6 +123,7 @@ fn main() {
                 mobilecoind_db.clone(),
                 peer_manager,
                 config.get_fog_resolver_factory(logger.clone()),
+                config.target_confirmation_blocks,
                 logger.clone(),
             );
 
@@ -0,0 +1,152 @@
+// Copyright (c) 2018-2022 The MobileCoin Foundation
+#![deny(missing_docs)]
+
+//! Offline signer for transactions built by view-only mobilecoind monitors.
+//!
+//! This reads an `UnsignedTxProposal` (as returned in `GenerateTxResponse`),
+//! signs it using the spend key in an account keyfile, and writes the resulting
+//! `TxProposal`, which can then be passed to mobilecoind's `SubmitTx`. It does
+//! not need network access, and is intended to run on an air-gapped machine.
+//!
+//! Before signing, the amounts of the outputs are checked against their
+//! commitments, each outlay output is checked to pay its recipient, and every
+//! other output is checked to pay the account's change subaddress. The outlays
+//! are then shown for confirmation.
+//!
+//! The view-only monitor only learns that its TxOuts are spent when the signed
+//! `TxProposal` is submitted through `SubmitTx` of that same mobilecoind
+//! instance.
+
+use clap::Parser;
+use mc_crypto_ring_signature_signer::LocalRingSigner;
+use mc_mobilecoind::payments::UnsignedTxProposal;
+use mc_mobilecoind_api as api;
+use protobuf::Message;
+use std::{
+    fs,
+    io::{self, BufRead, Write},
+    path::PathBuf,
+};
+
+/// Command line configuration for the offline signer.
+#[derive(Debug, Parser)]
+#[clap(
+    name = "mobilecoind-offline-signer",
+    about = "Sign an UnsignedTxProposal produced by a view-only mobilecoind monitor"
+)]
+struct Config {
+    /// Path to the account keyfile (root entropy or mnemonic json) holding
+    /// the spend key.
+    #[clap(long, env = "MC_KEYFILE")]
+    pub keyfile: PathBuf,
+
+    /// Path to a protobuf-encoded UnsignedTxProposal.
+    #[clap(long, env = "MC_UNSIGNED_TX_PROPOSAL")]
+    pub input: PathBuf,
+
+    /// Path to write the protobuf-encoded, signed TxProposal to.
+    #[clap(long, env = "MC_TX_PROPOSAL")]
+    pub output: PathBuf,
+
+    /// Sign without asking for confirmation.
+    #[clap(long, short = 'y')]
+    pub yes: bool,
+}
+
+fn main() {
+    let config = Config::parse();
+
+    let account_key =
+        mc_util_keyfile::read_keyfile(&config.keyfile).expect("Could not read keyfile");
+
+    let bytes = fs::read(&config.input).expect("Could not read unsigned tx proposal");
+    let proto = api::UnsignedTxProposal::parse_from_bytes(&bytes)
+        .expect("Could not parse unsigned tx proposal");
+    let unsigned_tx_proposal =
+        UnsignedTxProposal::try_from(&proto).expect("Invalid unsigned tx proposal");
+    unsigned_tx_proposal
+        .check_outputs()
+        .expect("Unsigned tx proposal does not match its outputs");
+    unsigned_tx_proposal
+        .check_recipients(&account_key)
+        .expect("Unsigned tx proposal pays unexpected recipients");
+
+    print_summary(&unsigned_tx_proposal);
+    if !config.yes && !confirm("Sign this transaction?") {
+        eprintln!("Not signing");
+        std::process::exit(1);
+    }
+
+    let signer = LocalRingSigner::from(&account_key);
+    let tx_proposal = unsigned_tx_proposal
+        .sign(&signer, &mut rand::thread_rng())
+        .expect("Could not sign tx proposal");
+
+    let bytes = api::TxProposal::from(&tx_proposal)
+        .write_to_bytes()
+        .expect("Could not serialize tx proposal");
+    fs::write(&config.output, bytes).expect("Could not write tx proposal");
+
+    println!(
+        "Signed tx spending {} inputs with fee {}, written to {:?}",
+        tx_proposal.utxos.len(),
+        tx_proposal.fee(),
+        config.output
+    );
+}
+
+/// Print what the transaction pays, and to whom.
+fn print_summary(unsigned_tx_proposal: &UnsignedTxProposal) {
+    println!("Outlays:");
+    for outlay in &unsigned_tx_proposal.outlays {
+        let mut wrapper = api::printable::PrintableWrapper::new();
+        wrapper.set_public_address((&outlay.receiver).into());
+        let recipient = wrapper
+            .b58_encode()
+            .expect("Could not encode recipient address");
+        println!(
+            "  {} of token {} to {}",
+            outlay.value, outlay.token_id, recipient
+        );
+    }
+
+    // `check_recipients` made sure outputs that do not pay an outlay are
+    // change.
+    let outlay_tx_out_indices: Vec<usize> = unsigned_tx_proposal
+        .outlay_index_to_tx_out_index
+        .values()
+        .cloned()
+        .collect();
+    for (index, secret) in unsigned_tx_proposal
+        .unsigned_tx
+        .output_secrets
+        .iter()
+        .enumerate()
+        .filter(|(index, _)| !outlay_tx_out_indices.contains(index))
+    {
+        println!(
+            "Output {}: {} of token {} (change to subaddress {})",
+            index,
+            secret.amount.value,
+            secret.amount.token_id,
+            unsigned_tx_proposal.change_subaddress
+        );
+    }
+
+    let fee = unsigned_tx_proposal.unsigned_tx.fee();
+    println!("Fee: {} of token {}", fee.value, fee.token_id);
+    println!("Inputs: {}", unsigned_tx_proposal.utxos.len());
+}
+
+/// Ask a yes/no question on the terminal, defaulting to no.
+fn confirm(question: &str) -> bool {
+    print!("{} [y/N] ", question);
+    io::stdout().flush().expect("Could not flush stdout");
+
+    let mut answer = String::new();
+    io::stdin()
+        .lock()
+        .read_line(&mut answer)
+        .expect("Could not read answer");
+    matches!(answer.trim(), "y" | "Y" | "yes")
+}
@@ -103,6 +103,13 @@ pub struct Config {
     /// An authorization token for the ipinfo.io service, if available
     #[clap(long, env = "MC_IP_INFO_TOKEN", default_value = "")]
     pub ip_info_token: String,
+
+    /// Target confirmation mode: when a request does not specify a fee, pay
+    /// enough for the transaction to be included within this many blocks,
+    /// based on the pending transactions of the consensus nodes. By default
+    /// the minimum fee is paid.
+    #[clap(long, env = "MC_TARGET_CONFIRMATION_BLOCKS")]
+    pub target_confirmation_blocks: Option<u64>,
 }
 
 fn parse_quorum_set_from_json(src: &str) -> Result<QuorumSet<ResponderId>, String> {
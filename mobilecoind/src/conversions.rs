@@ -4,17 +4,25 @@
 //! types.
 
 use crate::{
-    payments::{Outlay, TxProposal},
+    payments::{Outlay, TxProposal, UnsignedTxProposal},
+    utxo_selection::{
+        BranchAndBound, LargestFirst, Randomized, SmallestFirst, UtxoSelectionStrategy,
+    },
     utxo_store::UnspentTxOut,
 };
-use mc_account_keys::PublicAddress;
-use mc_api::ConversionError;
+use mc_account_keys::{PublicAddress, ViewAccountKey};
+use mc_api::{external, ConversionError};
 use mc_common::HashMap;
+use mc_crypto_keys::{RistrettoPrivate, RistrettoPublic};
+use mc_crypto_ring_signature_signer::{InputSecret, OneTimeKeyDeriveData, SignableInputRing};
 use mc_mobilecoind_api as api;
 use mc_transaction_core::{
-    ring_signature::KeyImage,
-    tx::{Tx, TxOut, TxOutConfirmationNumber},
+    ring_ct::{InputRing, OutputSecret},
+    ring_signature::{CurveScalar, KeyImage, ReducedTxOut, Scalar},
+    tx::{Tx, TxOut, TxOutConfirmationNumber, TxPrefix},
+    Amount, BlockVersion, TokenId,
 };
+use mc_transaction_std::UnsignedTx;
 use protobuf::RepeatedField;
 
 impl From<&UnspentTxOut> for api::UnspentTxOut {
@@ -63,6 +71,7 @@ impl From<&Outlay> for api::Outlay {
 
         dst.set_value(src.value);
         dst.set_receiver((&src.receiver).into());
+        dst.set_token_id(*src.token_id);
 
         dst
     }
@@ -74,8 +83,41 @@ impl TryFrom<&api::Outlay> for Outlay {
     fn try_from(src: &api::Outlay) -> Result<Self, Self::Error> {
         let value = src.value;
         let receiver = PublicAddress::try_from(src.get_receiver())?;
+        let token_id = TokenId::from(src.token_id);
 
-        Ok(Self { value, receiver })
+        Ok(Self {
+            value,
+            receiver,
+            token_id,
+        })
+    }
+}
+
+impl From<&Amount> for api::TokenAmount {
+    fn from(src: &Amount) -> Self {
+        let mut dst = Self::new();
+
+        dst.set_value(src.value);
+        dst.set_token_id(*src.token_id);
+
+        dst
+    }
+}
+
+impl From<&api::TokenAmount> for Amount {
+    fn from(src: &api::TokenAmount) -> Self {
+        Amount::new(src.value, TokenId::from(src.token_id))
+    }
+}
+
+impl From<api::UtxoSelectionStrategy> for Box<dyn UtxoSelectionStrategy> {
+    fn from(src: api::UtxoSelectionStrategy) -> Self {
+        match src {
+            api::UtxoSelectionStrategy::SmallestFirst => Box::new(SmallestFirst),
+            api::UtxoSelectionStrategy::LargestFirst => Box::new(LargestFirst),
+            api::UtxoSelectionStrategy::BranchAndBound => Box::new(BranchAndBound::default()),
+            api::UtxoSelectionStrategy::Randomized => Box::new(Randomized),
+        }
     }
 }
 
@@ -130,42 +172,304 @@ impl TryFrom<&api::TxProposal> for TxProposal {
 
         let tx = Tx::try_from(src.get_tx())?;
 
-        let outlay_index_to_tx_out_index = src
-            .get_outlay_index_to_tx_out_index()
-            .iter()
-            .map(|(key, val)| (*key as usize, *val as usize))
-            .collect::<HashMap<_, _>>();
+        let outlay_index_to_tx_out_index = outlay_index_to_tx_out_index_from_api(
+            src.get_outlay_index_to_tx_out_index(),
+            outlays.len(),
+            tx.prefix.outputs.len(),
+        )?;
+
+        let outlay_confirmation_numbers =
+            confirmation_numbers_from_api(src.get_outlay_confirmation_numbers())?;
 
-        // Check that none of the indices are out of bound.
-        if outlay_index_to_tx_out_index.len() != outlays.len() {
+        Ok(Self {
+            utxos,
+            outlays,
+            tx,
+            outlay_index_to_tx_out_index,
+            outlay_confirmation_numbers,
+        })
+    }
+}
+
+/// Convert and validate the outlay index -> TxOut index map of a proposal.
+fn outlay_index_to_tx_out_index_from_api(
+    src: &HashMap<u64, u64>,
+    num_outlays: usize,
+    num_outputs: usize,
+) -> Result<HashMap<usize, usize>, ConversionError> {
+    let outlay_index_to_tx_out_index = src
+        .iter()
+        .map(|(key, val)| (*key as usize, *val as usize))
+        .collect::<HashMap<_, _>>();
+
+    // Check that none of the indices are out of bound.
+    if outlay_index_to_tx_out_index.len() != num_outlays {
+        return Err(ConversionError::IndexOutOfBounds);
+    }
+
+    for (outlay_index, tx_out_index) in outlay_index_to_tx_out_index.iter() {
+        if *outlay_index >= num_outlays || *tx_out_index >= num_outputs {
             return Err(ConversionError::IndexOutOfBounds);
         }
+    }
 
-        for (outlay_index, tx_out_index) in outlay_index_to_tx_out_index.iter() {
-            if *outlay_index >= outlays.len() || *tx_out_index >= tx.prefix.outputs.len() {
-                return Err(ConversionError::IndexOutOfBounds);
+    Ok(outlay_index_to_tx_out_index)
+}
+
+/// Convert the outlay confirmation numbers of a proposal.
+fn confirmation_numbers_from_api(
+    src: &[Vec<u8>],
+) -> Result<Vec<TxOutConfirmationNumber>, ConversionError> {
+    src.iter()
+        .map(|src| match src.len() {
+            32 => {
+                let mut bytes = [0u8; 32];
+                bytes.copy_from_slice(src);
+                Ok(TxOutConfirmationNumber::from(bytes))
             }
+            _ => Err(ConversionError::IndexOutOfBounds),
+        })
+        .collect()
+}
+
+impl From<&ViewAccountKey> for api::ViewOnlyAccountKey {
+    fn from(src: &ViewAccountKey) -> Self {
+        let mut dst = Self::new();
+
+        dst.set_view_private_key(src.view_private_key().into());
+        dst.set_spend_public_key(src.spend_public_key().into());
+
+        dst
+    }
+}
+
+impl TryFrom<&api::ViewOnlyAccountKey> for ViewAccountKey {
+    type Error = ConversionError;
+
+    fn try_from(src: &api::ViewOnlyAccountKey) -> Result<Self, Self::Error> {
+        let view_private_key = RistrettoPrivate::try_from(src.get_view_private_key())?;
+        let spend_public_key = RistrettoPublic::try_from(src.get_spend_public_key())?;
+
+        Ok(Self::new(view_private_key, spend_public_key))
+    }
+}
+
+/// Convert an amount and its blinding factor to an external::UnmaskedAmount.
+fn unmasked_amount_to_api(amount: &Amount, blinding: &Scalar) -> external::UnmaskedAmount {
+    let mut dst = external::UnmaskedAmount::new();
+
+    dst.set_value(amount.value);
+    dst.set_token_id(*amount.token_id);
+    dst.set_blinding((&CurveScalar::from(*blinding)).into());
+
+    dst
+}
+
+/// Convert an external::UnmaskedAmount to an amount and its blinding factor.
+fn unmasked_amount_from_api(
+    src: &external::UnmaskedAmount,
+) -> Result<(Amount, Scalar), ConversionError> {
+    let amount = Amount::new(src.get_value(), TokenId::from(src.get_token_id()));
+    let blinding = CurveScalar::try_from(src.get_blinding())?;
+
+    Ok((amount, blinding.scalar))
+}
+
+/// Convert UnsignedTx --> api::UnsignedTx.
+///
+/// This fails for presigned inputs, and for inputs carrying a onetime private
+/// key, since neither can be represented without leaking key material.
+impl TryFrom<&UnsignedTx> for api::UnsignedTx {
+    type Error = ConversionError;
+
+    fn try_from(src: &UnsignedTx) -> Result<Self, Self::Error> {
+        let inputs = src
+            .rings
+            .iter()
+            .map(|ring| match ring {
+                InputRing::Signable(ring) => {
+                    let subaddress_index = match ring.input_secret.onetime_key_derive_data {
+                        OneTimeKeyDeriveData::SubaddressIndex(index) => index,
+                        OneTimeKeyDeriveData::OneTimeKey(_) => {
+                            return Err(ConversionError::InvalidContents)
+                        }
+                    };
+
+                    let mut input = api::UnsignedInput::new();
+                    input.set_real_input_index(ring.real_input_index as u64);
+                    input.set_subaddress_index(subaddress_index);
+                    input.set_amount(unmasked_amount_to_api(
+                        &ring.input_secret.amount,
+                        &ring.input_secret.blinding,
+                    ));
+                    Ok(input)
+                }
+                InputRing::Presigned(_) => Err(ConversionError::InvalidContents),
+            })
+            .collect::<Result<Vec<_>, ConversionError>>()?;
+
+        let output_secrets = src
+            .output_secrets
+            .iter()
+            .map(|secret| unmasked_amount_to_api(&secret.amount, &secret.blinding))
+            .collect::<Vec<_>>();
+
+        let mut dst = Self::new();
+        dst.set_tx_prefix((&src.tx_prefix).into());
+        dst.set_inputs(RepeatedField::from_vec(inputs));
+        dst.set_output_secrets(RepeatedField::from_vec(output_secrets));
+        dst.set_block_version(*src.block_version);
+
+        Ok(dst)
+    }
+}
+
+/// Convert api::UnsignedTx --> UnsignedTx.
+///
+/// The ring members of each input are taken from the matching TxIn in the tx
+/// prefix.
+impl TryFrom<&api::UnsignedTx> for UnsignedTx {
+    type Error = ConversionError;
+
+    fn try_from(src: &api::UnsignedTx) -> Result<Self, Self::Error> {
+        let tx_prefix = TxPrefix::try_from(src.get_tx_prefix())?;
+
+        if src.get_inputs().len() != tx_prefix.inputs.len()
+            || src.get_output_secrets().len() != tx_prefix.outputs.len()
+        {
+            return Err(ConversionError::IndexOutOfBounds);
         }
 
-        let outlay_confirmation_numbers = src
-            .get_outlay_confirmation_numbers()
+        let rings = tx_prefix
+            .inputs
             .iter()
-            .map(|src| match src.len() {
-                32 => {
-                    let mut bytes = [0u8; 32];
-                    bytes.copy_from_slice(src);
-                    Ok(TxOutConfirmationNumber::from(bytes))
+            .zip(src.get_inputs())
+            .map(|(tx_in, input)| {
+                let real_input_index = input.get_real_input_index() as usize;
+                if real_input_index >= tx_in.ring.len() {
+                    return Err(ConversionError::IndexOutOfBounds);
                 }
-                _ => Err(ConversionError::IndexOutOfBounds),
+
+                let (amount, blinding) = unmasked_amount_from_api(input.get_amount())?;
+
+                Ok(InputRing::Signable(SignableInputRing {
+                    members: tx_in.ring.iter().map(ReducedTxOut::from).collect(),
+                    real_input_index,
+                    input_secret: InputSecret {
+                        onetime_key_derive_data: OneTimeKeyDeriveData::SubaddressIndex(
+                            input.get_subaddress_index(),
+                        ),
+                        amount,
+                        blinding,
+                    },
+                }))
+            })
+            .collect::<Result<Vec<_>, ConversionError>>()?;
+
+        let output_secrets = src
+            .get_output_secrets()
+            .iter()
+            .map(|secret| {
+                let (amount, blinding) = unmasked_amount_from_api(secret)?;
+                Ok(OutputSecret { amount, blinding })
             })
-            .collect::<Result<Vec<TxOutConfirmationNumber>, ConversionError>>()?;
+            .collect::<Result<Vec<_>, ConversionError>>()?;
+
+        let block_version = BlockVersion::try_from(src.get_block_version())
+            .map_err(|_| ConversionError::InvalidContents)?;
+
+        Ok(Self {
+            tx_prefix,
+            rings,
+            output_secrets,
+            block_version,
+        })
+    }
+}
+
+impl TryFrom<&UnsignedTxProposal> for api::UnsignedTxProposal {
+    type Error = ConversionError;
+
+    fn try_from(src: &UnsignedTxProposal) -> Result<Self, Self::Error> {
+        let mut dst = api::UnsignedTxProposal::new();
+
+        dst.set_input_list(RepeatedField::from_vec(
+            src.utxos.iter().map(|utxo| utxo.into()).collect(),
+        ));
+        dst.set_outlay_list(RepeatedField::from_vec(
+            src.outlays.iter().map(|outlay| outlay.into()).collect(),
+        ));
+        dst.set_unsigned_tx(api::UnsignedTx::try_from(&src.unsigned_tx)?);
+        dst.set_fee(src.fee());
+        dst.set_outlay_index_to_tx_out_index(
+            src.outlay_index_to_tx_out_index
+                .iter()
+                .map(|(key, val)| (*key as u64, *val as u64))
+                .collect(),
+        );
+        dst.set_outlay_confirmation_numbers(
+            src.outlay_confirmation_numbers
+                .iter()
+                .map(|val| val.to_vec())
+                .collect(),
+        );
+        dst.set_outlay_shared_secrets(
+            src.outlay_shared_secrets
+                .iter()
+                .map(external::CompressedRistretto::from)
+                .collect(),
+        );
+        dst.set_change_subaddress(src.change_subaddress);
+
+        Ok(dst)
+    }
+}
+
+impl TryFrom<&api::UnsignedTxProposal> for UnsignedTxProposal {
+    type Error = ConversionError;
+
+    fn try_from(src: &api::UnsignedTxProposal) -> Result<Self, Self::Error> {
+        if src.fee != src.get_unsigned_tx().get_tx_prefix().fee {
+            return Err(ConversionError::FeeMismatch);
+        }
+
+        let utxos = src
+            .get_input_list()
+            .iter()
+            .map(UnspentTxOut::try_from)
+            .collect::<Result<Vec<UnspentTxOut>, ConversionError>>()?;
+
+        let outlays = src
+            .get_outlay_list()
+            .iter()
+            .map(Outlay::try_from)
+            .collect::<Result<Vec<Outlay>, ConversionError>>()?;
+
+        let unsigned_tx = UnsignedTx::try_from(src.get_unsigned_tx())?;
+
+        let outlay_index_to_tx_out_index = outlay_index_to_tx_out_index_from_api(
+            src.get_outlay_index_to_tx_out_index(),
+            outlays.len(),
+            unsigned_tx.tx_prefix.outputs.len(),
+        )?;
+
+        let outlay_confirmation_numbers =
+            confirmation_numbers_from_api(src.get_outlay_confirmation_numbers())?;
+
+        let outlay_shared_secrets = src
+            .get_outlay_shared_secrets()
+            .iter()
+            .map(RistrettoPublic::try_from)
+            .collect::<Result<Vec<_>, _>>()?;
 
         Ok(Self {
             utxos,
             outlays,
-            tx,
+            unsigned_tx,
             outlay_index_to_tx_out_index,
             outlay_confirmation_numbers,
+            outlay_shared_secrets,
+            change_subaddress: src.change_subaddress,
         })
     }
 }
@@ -245,10 +549,12 @@ mod test {
         let rust = Outlay {
             receiver: public_addr.clone(),
             value: 1234,
+            token_id: TokenId::from(2),
         };
         let proto = api::Outlay::from(&rust);
 
         assert_eq!(proto.value, rust.value);
+        assert_eq!(proto.token_id, 2);
         assert_eq!(
             PublicAddress::try_from(proto.get_receiver()).unwrap(),
             public_addr
@@ -318,6 +624,7 @@ mod test {
             Outlay {
                 receiver: public_addr,
                 value: 1234,
+                token_id: Mob::ID,
             }
         };
 
@@ -353,4 +660,117 @@ mod test {
         // Proto -> Rust
         assert_eq!(rust, TxProposal::try_from(&proto).unwrap());
     }
+
+    #[test]
+    fn test_view_only_account_key_conversion() {
+        let mut rng: StdRng = SeedableRng::from_seed([1u8; 32]);
+        let account_key = AccountKey::random(&mut rng);
+        let rust = ViewAccountKey::from(&account_key);
+
+        // Rust -> Proto
+        let proto = api::ViewOnlyAccountKey::from(&rust);
+        assert_eq!(
+            RistrettoPrivate::try_from(proto.get_view_private_key()).unwrap(),
+            *account_key.view_private_key()
+        );
+
+        // Proto -> Rust
+        assert_eq!(rust, ViewAccountKey::try_from(&proto).unwrap());
+    }
+
+    #[test]
+    fn test_unsigned_tx_proposal_conversion() {
+        let mut rng: StdRng = SeedableRng::from_seed([1u8; 32]);
+
+        let tx = {
+            let mut ledger = create_ledger();
+            let sender = AccountKey::random(&mut rng);
+            let recipient = AccountKey::random(&mut rng);
+            initialize_ledger(BlockVersion::MAX, &mut ledger, 1, &sender, &mut rng);
+
+            let block_contents = ledger.get_block_contents(0).unwrap();
+            let tx_out = block_contents.outputs[0].clone();
+
+            create_transaction(
+                BlockVersion::MAX,
+                &mut ledger,
+                &tx_out,
+                &sender,
+                &recipient.default_subaddress(),
+                10,
+                &mut rng,
+            )
+        };
+
+        let unsigned_tx = UnsignedTx {
+            rings: tx
+                .prefix
+                .inputs
+                .iter()
+                .map(|tx_in| {
+                    InputRing::Signable(SignableInputRing {
+                        members: tx_in.ring.iter().map(ReducedTxOut::from).collect(),
+                        real_input_index: 0,
+                        input_secret: InputSecret {
+                            onetime_key_derive_data: OneTimeKeyDeriveData::SubaddressIndex(3),
+                            amount: Amount::new(1234, Mob::ID),
+                            blinding: Scalar::from(5u64),
+                        },
+                    })
+                })
+                .collect(),
+            output_secrets: tx
+                .prefix
+                .outputs
+                .iter()
+                .map(|_| OutputSecret {
+                    amount: Amount::new(1224, Mob::ID),
+                    blinding: Scalar::from(7u64),
+                })
+                .collect(),
+            tx_prefix: tx.prefix,
+            block_version: BlockVersion::MAX,
+        };
+
+        let outlay = {
+            let public_addr = AccountKey::random(&mut rng).default_subaddress();
+            Outlay {
+                receiver: public_addr,
+                value: 1234,
+                token_id: Mob::ID,
+            }
+        };
+
+        let rust = UnsignedTxProposal {
+            utxos: vec![],
+            outlays: vec![outlay],
+            unsigned_tx,
+            outlay_index_to_tx_out_index: HashMap::from_iter(vec![(0, 0)]),
+            outlay_confirmation_numbers: vec![TxOutConfirmationNumber::from([0u8; 32])],
+            outlay_shared_secrets: vec![RistrettoPublic::from_random(&mut rng)],
+            change_subaddress: 5,
+        };
+
+        // Rust -> Proto
+        let proto = api::UnsignedTxProposal::try_from(&rust).unwrap();
+        assert_eq!(proto.fee, rust.fee());
+        assert_eq!(
+            proto.get_unsigned_tx().get_inputs().len(),
+            rust.unsigned_tx.rings.len()
+        );
+        assert_eq!(proto.get_unsigned_tx().get_inputs()[0].subaddress_index, 3);
+
+        // Proto -> Rust -> Proto
+        let rust2 = UnsignedTxProposal::try_from(&proto).unwrap();
+        assert_eq!(rust.outlays, rust2.outlays);
+        assert_eq!(rust.unsigned_tx.tx_prefix, rust2.unsigned_tx.tx_prefix);
+        assert_eq!(rust.outlay_shared_secrets, rust2.outlay_shared_secrets);
+        assert_eq!(rust2.change_subaddress, 5);
+        assert_eq!(proto, api::UnsignedTxProposal::try_from(&rust2).unwrap());
+
+        // A real input index outside of the ring is rejected.
+        let mut bad_proto = proto;
+        bad_proto.mut_unsigned_tx().mut_inputs()[0].set_real_input_index(1000);
+        assert!(UnsignedTxProposal::try_from(&bad_proto).is_err());
+    }
 }
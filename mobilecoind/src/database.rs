@@ -18,7 +18,7 @@ use mc_common::{
     HashMap,
 };
 use mc_transaction_core::ring_signature::KeyImage;
-use mc_util_lmdb::{MetadataStore, MetadataStoreSettings};
+use mc_util_lmdb::{MetadataStore, MetadataStoreSettings, Migrations};
 use std::{path::Path, sync::Arc};
 
 // LMDB Constants
@@ -42,6 +42,12 @@ impl MetadataStoreSettings for MobilecoindDbMetadataStoreSettings {
     const DB_NAME: &'static str = "mobilecoind_db_metadata";
 }
 
+/// The migration steps of the mobilecoind database. Steps are added here
+/// when `LATEST_VERSION` is bumped, and run when the database is opened.
+pub fn mobilecoind_db_migrations() -> Migrations<MobilecoindDbMetadataStoreSettings> {
+    Migrations::new()
+}
+
 /// The main mobilecoind database.
 #[derive(Clone)]
 pub struct Database {
@@ -79,14 +85,14 @@ impl Database {
         let metadata_store =
             MetadataStore::<MobilecoindDbMetadataStoreSettings>::open_or_create(&env)?;
 
-        let db_txn = env.begin_ro_txn()?;
-        let version = metadata_store.get_version(&db_txn)?;
+        let version = mobilecoind_db_migrations().migrate(&env, &metadata_store, |event| {
+            log::info!(logger, "Mobilecoind db {}", event)
+        })?;
         log::info!(
             logger,
             "Mobilecoind db is currently at version: {:?}",
             version
         );
-        db_txn.commit()?;
 
         version.is_compatible_with_latest()?;
 
@@ -239,6 +245,23 @@ impl Database {
         Ok(())
     }
 
+    /// Replace placeholder key images of utxos owned by a view-only monitor
+    /// with their real key images.
+    pub fn replace_utxo_key_images(
+        &self,
+        monitor_id: &MonitorId,
+        key_images: &[(KeyImage, KeyImage)],
+    ) -> Result<(), Error> {
+        let mut db_txn = self.env.begin_rw_txn()?;
+
+        self.utxo_store
+            .replace_key_images(&mut db_txn, monitor_id, key_images)?;
+
+        db_txn.commit()?;
+
+        Ok(())
+    }
+
     /// Feed data processed from a given block into the various stores.
     pub fn block_processed(
         &self,
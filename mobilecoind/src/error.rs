@@ -9,7 +9,7 @@ use mc_connection::Error as ConnectionError;
 use mc_consensus_api::ConversionError;
 use mc_crypto_keys::KeyError;
 use mc_ledger_db::Error as LedgerDbError;
-use mc_util_lmdb::MetadataStoreError;
+use mc_util_lmdb::{MetadataStoreError, MigrationError};
 use mc_util_serial::{decode::Error as DecodeError, encode::Error as EncodeError};
 use prost::DecodeError as ProstDecodeError;
 use retry::Error as RetryError;
@@ -106,11 +106,23 @@ pub enum Error {
     /// Metadata store error: {0}
     MetadataStore(MetadataStoreError),
 
+    /// Migration error: {0}
+    Migration(MigrationError),
+
     /// No peers configured - running in offline mode
     NoPeersConfigured,
 
     /// Db encryption: {0}
     DbCrypto(DbCryptoError),
+
+    /// Monitor only holds view keys and cannot sign transactions
+    ViewOnlyMonitor,
+
+    /// The transaction does not spend the expected inputs: {0}
+    UnexpectedTxInputs(String),
+
+    /// The transaction outputs do not match the proposal: {0}
+    UnexpectedTxOutputs(String),
 }
 
 impl From<RetryError<ConnectionError>> for Error {
@@ -179,6 +191,12 @@ impl From<MetadataStoreError> for Error {
     }
 }
 
+impl From<MigrationError> for Error {
+    fn from(e: MigrationError) -> Self {
+        Self::Migration(e)
+    }
+}
+
 impl From<DbCryptoError> for Error {
     fn from(e: DbCryptoError) -> Self {
         Self::DbCrypto(e)
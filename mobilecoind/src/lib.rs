@@ -8,11 +8,13 @@ pub mod config;
 pub mod database;
 pub mod payments;
 pub mod service;
+pub mod utxo_selection;
 
 mod conversions;
 mod database_key;
 mod db_crypto;
 mod error;
+mod monitor_events;
 mod monitor_store;
 mod processed_block_store;
 mod subaddress_store;
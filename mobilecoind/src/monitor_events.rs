@@ -0,0 +1,343 @@
+// Copyright (c) 2018-2022 The MobileCoin Foundation
+
+//! Monitor event notifications.
+//! * The sync thread bumps a shared `MonitorEventNotifier` every time it
+//!   commits a processed block for any monitor.
+//! * Subscribers keep a `MonitorEventCursor` per monitor, which turns the data
+//!   in the processed block store into a sequence of typed events. Since the
+//!   cursor reads everything from the database, a subscriber can resume from
+//!   any block index the monitor has already processed without missing events.
+
+use crate::{
+    database::Database,
+    error::Error,
+    monitor_store::MonitorId,
+    processed_block_store::{ProcessedTxOut, ProcessedTxOutDirection},
+};
+use mc_ledger_db::{Ledger, LedgerDB};
+use std::{
+    sync::{Arc, Condvar, Mutex},
+    time::Duration,
+};
+
+/// Wakes up monitor event subscribers whenever the sync thread finishes
+/// processing a block.
+#[derive(Clone, Default)]
+pub struct MonitorEventNotifier {
+    /// A counter that is incremented on every notification, and a condition
+    /// variable used to signal waiters about the change.
+    generation: Arc<(Mutex<u64>, Condvar)>,
+}
+
+impl MonitorEventNotifier {
+    /// Signal all waiters that a block has been processed.
+    pub fn notify(&self) {
+        let (lock, condvar) = &*self.generation;
+        let mut generation = lock.lock().expect("mutex poisoned");
+        *generation = generation.wrapping_add(1);
+        condvar.notify_all();
+    }
+
+    /// Get the current notification generation.
+    pub fn generation(&self) -> u64 {
+        *self.generation.0.lock().expect("mutex poisoned")
+    }
+
+    /// Block until the generation moves past `last_seen`, or until `timeout`
+    /// elapses. Returns the current generation.
+    pub fn wait_for_change(&self, last_seen: u64, timeout: Duration) -> u64 {
+        let (lock, condvar) = &*self.generation;
+        let generation = lock.lock().expect("mutex poisoned");
+        let (generation, _timeout_result) = condvar
+            .wait_timeout_while(generation, timeout, |generation| *generation == last_seen)
+            .expect("mutex poisoned");
+        *generation
+    }
+}
+
+/// A single event emitted for a monitor.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum MonitorEvent {
+    /// A TxOut belonging to the monitor was received in `block_index`.
+    UtxoReceived {
+        block_index: u64,
+        tx_out: ProcessedTxOut,
+    },
+
+    /// A TxOut belonging to the monitor was spent in `block_index`.
+    UtxoSpent {
+        block_index: u64,
+        tx_out: ProcessedTxOut,
+    },
+
+    /// The monitor finished processing `block_index`. This is always emitted
+    /// after all the `UtxoReceived`/`UtxoSpent` events of that block.
+    BlockProcessed {
+        block_index: u64,
+        num_received: u64,
+        num_spent: u64,
+    },
+
+    /// The monitor has processed every block currently in the local ledger.
+    /// This is emitted once every time the monitor transitions from lagging
+    /// behind the ledger to being in sync with it.
+    CaughtUp { next_block: u64 },
+}
+
+/// Tracks the progress of a single subscriber through the events of a
+/// monitor.
+pub struct MonitorEventCursor {
+    /// The monitor we are producing events for.
+    monitor_id: MonitorId,
+
+    /// The next block whose events should be emitted.
+    next_block: u64,
+
+    /// Whether a `CaughtUp` event was emitted since the monitor last fell
+    /// behind the ledger.
+    caught_up_reported: bool,
+}
+
+impl MonitorEventCursor {
+    /// Create a new cursor that starts emitting events from `from_block`.
+    /// A `from_block` smaller than the monitor's first block is treated as
+    /// the monitor's first block, so zero can be used to request all events.
+    pub fn new(
+        mobilecoind_db: &Database,
+        monitor_id: &MonitorId,
+        from_block: u64,
+    ) -> Result<Self, Error> {
+        let monitor_data = mobilecoind_db.get_monitor_data(monitor_id)?;
+
+        Ok(Self {
+            monitor_id: *monitor_id,
+            next_block: from_block.max(monitor_data.first_block),
+            caught_up_reported: false,
+        })
+    }
+
+    /// The next block whose events this cursor will emit.
+    pub fn next_block(&self) -> u64 {
+        self.next_block
+    }
+
+    /// Get the next batch of events, covering at most one block.
+    /// An empty result means there is currently nothing new to report, and
+    /// the caller should wait for a notification before trying again.
+    pub fn next_events(
+        &mut self,
+        ledger_db: &LedgerDB,
+        mobilecoind_db: &Database,
+    ) -> Result<Vec<MonitorEvent>, Error> {
+        let monitor_data = mobilecoind_db.get_monitor_data(&self.monitor_id)?;
+        let mut events = Vec::new();
+
+        if self.next_block < monitor_data.next_block {
+            let block_index = self.next_block;
+            let processed_tx_outs =
+                mobilecoind_db.get_processed_block(&self.monitor_id, block_index)?;
+
+            let mut num_received = 0;
+            let mut num_spent = 0;
+            for tx_out in processed_tx_outs {
+                match ProcessedTxOutDirection::from_i32(tx_out.direction) {
+                    Some(ProcessedTxOutDirection::Received) => {
+                        num_received += 1;
+                        events.push(MonitorEvent::UtxoReceived {
+                            block_index,
+                            tx_out,
+                        });
+                    }
+                    Some(ProcessedTxOutDirection::Spent) => {
+                        num_spent += 1;
+                        events.push(MonitorEvent::UtxoSpent {
+                            block_index,
+                            tx_out,
+                        });
+                    }
+                    Some(ProcessedTxOutDirection::Invalid) | None => {}
+                }
+            }
+
+            events.push(MonitorEvent::BlockProcessed {
+                block_index,
+                num_received,
+                num_spent,
+            });
+            self.next_block += 1;
+        }
+
+        if self.next_block >= monitor_data.next_block {
+            let num_blocks = ledger_db.num_blocks()?;
+            if monitor_data.next_block < num_blocks {
+                self.caught_up_reported = false;
+            } else if !self.caught_up_reported {
+                self.caught_up_reported = true;
+                events.push(MonitorEvent::CaughtUp {
+                    next_block: self.next_block,
+                });
+            }
+        }
+
+        Ok(events)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        monitor_store::MonitorData,
+        test_utils::{get_test_databases, BlockVersion, DEFAULT_PER_RECIPIENT_AMOUNT},
+        utxo_store::UnspentTxOut,
+    };
+    use mc_account_keys::{AccountKey, DEFAULT_SUBADDRESS_INDEX};
+    use mc_common::logger::{test_with_logger, Logger};
+    use mc_transaction_core::ring_signature::KeyImage;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[test_with_logger]
+    fn test_cursor_replays_and_resumes(logger: Logger) {
+        let mut rng: StdRng = SeedableRng::from_seed([71u8; 32]);
+        let account_key = AccountKey::random(&mut rng);
+
+        let (ledger_db, mobilecoind_db) = get_test_databases(
+            BlockVersion::MAX,
+            0,
+            &[account_key.default_subaddress()],
+            3,
+            logger,
+            &mut rng,
+        );
+
+        let data = MonitorData::new(account_key, DEFAULT_SUBADDRESS_INDEX, 1, 0, "").unwrap();
+        let monitor_id = mobilecoind_db.add_monitor(&data).unwrap();
+
+        // Nothing has been processed yet, so there is nothing to report.
+        let mut cursor = MonitorEventCursor::new(&mobilecoind_db, &monitor_id, 0).unwrap();
+        assert_eq!(
+            cursor.next_events(&ledger_db, &mobilecoind_db).unwrap(),
+            vec![]
+        );
+
+        // Process block 0 with one received utxo, and block 1 that spends it.
+        let utxo = UnspentTxOut {
+            tx_out: ledger_db.get_tx_out_by_index(0).unwrap(),
+            subaddress_index: DEFAULT_SUBADDRESS_INDEX,
+            key_image: KeyImage::from(1),
+            value: DEFAULT_PER_RECIPIENT_AMOUNT,
+            attempted_spend_height: 0,
+            attempted_spend_tombstone: 0,
+            token_id: 0,
+        };
+        mobilecoind_db
+            .block_processed(&monitor_id, 0, &[utxo.clone()], &[])
+            .unwrap();
+        mobilecoind_db
+            .block_processed(&monitor_id, 1, &[], &[utxo.key_image])
+            .unwrap();
+
+        assert_eq!(
+            cursor.next_events(&ledger_db, &mobilecoind_db).unwrap(),
+            vec![
+                MonitorEvent::UtxoReceived {
+                    block_index: 0,
+                    tx_out: ProcessedTxOut::from_received_utxo(&utxo),
+                },
+                MonitorEvent::BlockProcessed {
+                    block_index: 0,
+                    num_received: 1,
+                    num_spent: 0,
+                },
+            ]
+        );
+        assert_eq!(
+            cursor.next_events(&ledger_db, &mobilecoind_db).unwrap(),
+            vec![
+                MonitorEvent::UtxoSpent {
+                    block_index: 1,
+                    tx_out: ProcessedTxOut::from_spent_utxo(&utxo),
+                },
+                MonitorEvent::BlockProcessed {
+                    block_index: 1,
+                    num_received: 0,
+                    num_spent: 1,
+                },
+            ]
+        );
+
+        // The ledger has three blocks, so we are not caught up yet.
+        assert_eq!(
+            cursor.next_events(&ledger_db, &mobilecoind_db).unwrap(),
+            vec![]
+        );
+
+        mobilecoind_db
+            .block_processed(&monitor_id, 2, &[], &[])
+            .unwrap();
+        assert_eq!(
+            cursor.next_events(&ledger_db, &mobilecoind_db).unwrap(),
+            vec![
+                MonitorEvent::BlockProcessed {
+                    block_index: 2,
+                    num_received: 0,
+                    num_spent: 0,
+                },
+                MonitorEvent::CaughtUp { next_block: 3 },
+            ]
+        );
+
+        // Being caught up is only reported once.
+        assert_eq!(
+            cursor.next_events(&ledger_db, &mobilecoind_db).unwrap(),
+            vec![]
+        );
+
+        // A reconnecting subscriber can resume from block 1 and sees the same
+        // events again.
+        let mut resumed_cursor = MonitorEventCursor::new(&mobilecoind_db, &monitor_id, 1).unwrap();
+        assert_eq!(resumed_cursor.next_block(), 1);
+        assert_eq!(
+            resumed_cursor
+                .next_events(&ledger_db, &mobilecoind_db)
+                .unwrap(),
+            vec![
+                MonitorEvent::UtxoSpent {
+                    block_index: 1,
+                    tx_out: ProcessedTxOut::from_spent_utxo(&utxo),
+                },
+                MonitorEvent::BlockProcessed {
+                    block_index: 1,
+                    num_received: 0,
+                    num_spent: 1,
+                },
+            ]
+        );
+
+        // Removing the monitor ends the event sequence.
+        mobilecoind_db.remove_monitor(&monitor_id).unwrap();
+        assert!(matches!(
+            cursor.next_events(&ledger_db, &mobilecoind_db),
+            Err(Error::MonitorIdNotFound)
+        ));
+    }
+
+    #[test]
+    fn test_notifier_wakes_waiters() {
+        let notifier = MonitorEventNotifier::default();
+        let generation = notifier.generation();
+
+        // Without a notification, waiting times out and the generation is unchanged.
+        assert_eq!(
+            notifier.wait_for_change(generation, Duration::from_millis(10)),
+            generation
+        );
+
+        let thread_notifier = notifier.clone();
+        let join_handle = std::thread::spawn(move || {
+            thread_notifier.wait_for_change(generation, Duration::from_secs(60))
+        });
+        notifier.notify();
+        assert_eq!(join_handle.join().unwrap(), generation + 1);
+    }
+}
@@ -7,26 +7,74 @@
 use crate::{database_key::DatabaseByteArrayKey, db_crypto::DbCryptoProvider, error::Error};
 
 use lmdb::{Cursor, Database, DatabaseFlags, Environment, RwTransaction, Transaction, WriteFlags};
-use mc_account_keys::AccountKey;
+use mc_account_keys::{AccountKey, PublicAddress, ViewAccountKey};
 use mc_common::{
     logger::{log, Logger},
     HashMap,
 };
 use mc_crypto_digestible::{Digestible, MerlinTranscript};
-use mc_crypto_keys::RistrettoPublic;
+use mc_crypto_keys::{RistrettoPrivate, RistrettoPublic};
 use mc_util_serial::Message;
+use prost::Oneof;
 use std::{ops::Range, sync::Arc};
 
 // LMDB Database Names
 pub const MONITOR_ID_TO_MONITOR_DATA_DB_NAME: &str =
     "mobilecoind_db:monitor_store:monitor_id_to_monitor_data";
 
+/// The keys a monitor uses to watch an account.
+#[derive(Clone, Eq, Hash, PartialEq, Oneof)]
+pub enum MonitorKey {
+    /// The private key pair for the account. Monitors holding it can sign
+    /// transactions.
+    #[prost(message, tag = "1")]
+    AccountKey(AccountKey),
+
+    /// The view private key and spend public key for the account. Monitors
+    /// holding it can only build unsigned transactions, which need to be
+    /// signed by the holder of the spend private key.
+    #[prost(message, tag = "7")]
+    ViewAccountKey(ViewAccountKey),
+}
+
+impl MonitorKey {
+    /// The view private key of the account.
+    pub fn view_private_key(&self) -> &RistrettoPrivate {
+        match self {
+            Self::AccountKey(account_key) => account_key.view_private_key(),
+            Self::ViewAccountKey(view_account_key) => view_account_key.view_private_key(),
+        }
+    }
+
+    /// The i^th subaddress of the account.
+    ///
+    /// View-only keys do not carry fog information, so their subaddresses
+    /// never include a fog report url.
+    pub fn subaddress(&self, index: u64) -> PublicAddress {
+        match self {
+            Self::AccountKey(account_key) => account_key.subaddress(index),
+            Self::ViewAccountKey(view_account_key) => view_account_key.subaddress(index),
+        }
+    }
+
+    /// The default subaddress of the account.
+    pub fn default_subaddress(&self) -> PublicAddress {
+        match self {
+            Self::AccountKey(account_key) => account_key.default_subaddress(),
+            Self::ViewAccountKey(view_account_key) => view_account_key.default_subaddress(),
+        }
+    }
+}
+
 /// Type used as the stored data in the monitor_id_to_monitor_data database.
 #[derive(Clone, Eq, Hash, PartialEq, Message)]
 pub struct MonitorData {
-    /// The private key pair for the account this monitor watches.
-    #[prost(message, required, tag = "1")]
-    pub account_key: AccountKey,
+    /// The keys for the account this monitor watches.
+    ///
+    /// Tag 1 is shared with the `account_key` field used by earlier versions,
+    /// so existing monitors decode as `MonitorKey::AccountKey`.
+    #[prost(oneof = "MonitorKey", tags = "1, 7")]
+    pub key: Option<MonitorKey>,
 
     /// The smallest subaddress index in the range this monitor watches.
     #[prost(uint64, tag = "2")]
@@ -56,6 +104,40 @@ impl MonitorData {
         num_subaddresses: u64,
         first_block: u64,
         name: &str,
+    ) -> Result<Self, Error> {
+        Self::new_with_key(
+            MonitorKey::AccountKey(account_key),
+            first_subaddress,
+            num_subaddresses,
+            first_block,
+            name,
+        )
+    }
+
+    /// Create data for a monitor that only holds view keys. Such monitors
+    /// track received outputs, but spending requires offline signing.
+    pub fn new_view_only(
+        view_account_key: ViewAccountKey,
+        first_subaddress: u64,
+        num_subaddresses: u64,
+        first_block: u64,
+        name: &str,
+    ) -> Result<Self, Error> {
+        Self::new_with_key(
+            MonitorKey::ViewAccountKey(view_account_key),
+            first_subaddress,
+            num_subaddresses,
+            first_block,
+            name,
+        )
+    }
+
+    fn new_with_key(
+        key: MonitorKey,
+        first_subaddress: u64,
+        num_subaddresses: u64,
+        first_block: u64,
+        name: &str,
     ) -> Result<Self, Error> {
         if num_subaddresses == 0 {
             return Err(Error::InvalidArgument(
@@ -65,7 +147,7 @@ impl MonitorData {
         }
 
         Ok(Self {
-            account_key,
+            key: Some(key),
             first_subaddress,
             num_subaddresses,
             first_block,
@@ -78,6 +160,26 @@ impl MonitorData {
     pub fn subaddress_indexes(&self) -> Range<u64> {
         self.first_subaddress..self.first_subaddress + self.num_subaddresses
     }
+
+    /// The keys for the account this monitor watches.
+    pub fn monitor_key(&self) -> &MonitorKey {
+        // Every constructor sets the key, and the database only holds monitor
+        // data created by them.
+        self.key.as_ref().expect("monitor data is missing its key")
+    }
+
+    /// The full account key, if this monitor holds one.
+    pub fn account_key(&self) -> Option<&AccountKey> {
+        match self.monitor_key() {
+            MonitorKey::AccountKey(account_key) => Some(account_key),
+            MonitorKey::ViewAccountKey(_) => None,
+        }
+    }
+
+    /// Whether this monitor only holds view keys.
+    pub fn is_view_only(&self) -> bool {
+        self.account_key().is_none()
+    }
 }
 
 /// Type used as the key in the monitor_id_to_monitor_data database
@@ -119,7 +221,7 @@ impl From<&MonitorData> for MonitorId {
             pub first_block: u64,
         }
 
-        let real_subaddress = src.account_key.default_subaddress();
+        let real_subaddress = src.monitor_key().default_subaddress();
 
         let const_data = ConstMonitorData {
             address: PublicAddress {
@@ -478,4 +580,49 @@ pKZkdp8MQU5TLFOE9qjNeVsCAwEAAQ==
             Err(Error::MonitorIdNotFound)
         );
     }
+
+    // View-only monitors watch the same subaddresses as full monitors, and
+    // survive a round trip through the database.
+    #[test_with_logger]
+    fn test_view_only_monitor(logger: Logger) {
+        let mut rng = ChaChaRng::from_seed([123u8; 32]);
+
+        let (_ledger_db, mobilecoind_db) =
+            get_test_databases(BlockVersion::MAX, 3, &[], 10, logger, &mut rng);
+
+        let account_key = AccountKey::random(&mut rng);
+        let full_data = MonitorData::new(account_key.clone(), 0, 5, 0, "full")
+            .expect("Could not create monitor data");
+        let view_data =
+            MonitorData::new_view_only(ViewAccountKey::from(&account_key), 0, 5, 0, "view")
+                .expect("Could not create view-only monitor data");
+
+        assert!(!full_data.is_view_only());
+        assert!(view_data.is_view_only());
+        assert!(view_data.account_key().is_none());
+        assert_eq!(
+            full_data.monitor_key().view_private_key(),
+            view_data.monitor_key().view_private_key()
+        );
+        for index in full_data.subaddress_indexes() {
+            assert_eq!(
+                full_data.monitor_key().subaddress(index),
+                view_data.monitor_key().subaddress(index)
+            );
+        }
+
+        // A non-fog account has the same monitor id whether or not the spend
+        // private key is known.
+        assert_eq!(MonitorId::from(&full_data), MonitorId::from(&view_data));
+
+        let view_id = mobilecoind_db
+            .add_monitor(&view_data)
+            .expect("failed inserting view-only monitor");
+        assert_eq!(
+            mobilecoind_db
+                .get_monitor_data(&view_id)
+                .expect("failed getting view-only monitor data"),
+            view_data
+        );
+    }
 }
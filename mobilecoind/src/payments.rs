@@ -2,42 +2,50 @@
 
 //! Construct and submit transactions to the validator network.
 
-use crate::{database::Database, error::Error, monitor_store::MonitorId, utxo_store::UnspentTxOut};
-use mc_account_keys::{AccountKey, PublicAddress};
+use crate::{
+    database::Database,
+    error::Error,
+    monitor_store::{MonitorId, MonitorKey},
+    utxo_selection::UtxoSelectionStrategy,
+    utxo_store::UnspentTxOut,
+};
+use mc_account_keys::{AccountKey, PublicAddress, GIFT_CODE_SUBADDRESS_INDEX};
 use mc_blockchain_types::{BlockIndex, BlockVersion};
 use mc_common::{
     logger::{log, o, Logger},
     HashMap, HashSet,
 };
 use mc_connection::{
-    BlockInfo, BlockchainConnection, ConnectionManager, RetryableUserTxConnection, UserTxConnection,
+    BlockInfo, BlockchainConnection, ConnectionManager, Error as ConnectionError, PoolStats,
+    RetryError, RetryableUserTxConnection, UserTxConnection,
 };
-use mc_crypto_keys::RistrettoPublic;
+use mc_consensus_enclave_api::SMALLEST_MINIMUM_FEE_LOG2;
+use mc_crypto_keys::{CompressedRistrettoPublic, RistrettoPublic};
 use mc_crypto_rand::{CryptoRng, RngCore};
-use mc_crypto_ring_signature_signer::NoKeysRingSigner;
+use mc_crypto_ring_signature_signer::{LocalRingSigner, OneTimeKeyDeriveData, RingSigner};
 use mc_fog_report_validation::FogPubkeyResolver;
 use mc_ledger_db::{Error as LedgerError, Ledger, LedgerDB};
 use mc_transaction_core::{
     constants::{MAX_INPUTS, MILLIMOB_TO_PICOMOB, RING_SIZE},
-    onetime_keys::recover_onetime_private_key,
-    ring_signature::KeyImage,
+    onetime_keys::create_tx_out_target_key_from_shared_secret,
+    ring_signature::{generators, KeyImage},
+    subaddress_matches_tx_out,
     tx::{Tx, TxOut, TxOutConfirmationNumber, TxOutMembershipProof},
-    Amount, TokenId,
+    Amount, CompressedCommitment, SignedContingentInput, TokenId,
 };
 use mc_transaction_std::{
-    EmptyMemoBuilder, InputCredentials, MemoBuilder, ReservedSubaddresses, TransactionBuilder,
-    TxOutContext,
+    EmptyMemoBuilder, InputCredentials, MemoBuilder, ReservedSubaddresses,
+    SignedContingentInputBuilder, TransactionBuilder, TxOutContext, UnsignedTx,
 };
 use mc_util_uri::FogUri;
 use rand::Rng;
 use std::{
     cmp::{max, Reverse},
-    iter::empty,
+    collections::BTreeMap,
+    iter::{empty, once},
     str::FromStr,
-    sync::{
-        atomic::{AtomicUsize, Ordering},
-        Arc,
-    },
+    sync::Arc,
+    time::Instant,
 };
 
 /// Default number of blocks used for calculating transaction tombstone block
@@ -52,6 +60,10 @@ pub const DEFAULT_RING_SIZE: usize = RING_SIZE;
 /// consensus fail or we have no peers.
 const FALLBACK_FEE: u64 = 10 * MILLIMOB_TO_PICOMOB;
 
+/// In target confirmation mode, fees are capped at this multiple of the
+/// minimum fee.
+const MAX_TARGET_CONFIRMATION_FEE_MULTIPLIER: u64 = 100;
+
 /// An outlay - the API representation of a desired transaction output.
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct Outlay {
@@ -60,6 +72,9 @@ pub struct Outlay {
 
     /// Destination.
     pub receiver: PublicAddress,
+
+    /// Token id of the value being sent.
+    pub token_id: TokenId,
 }
 
 /// A single pending transaction.
@@ -89,6 +104,210 @@ impl TxProposal {
     }
 }
 
+/// A pending transaction that still needs to be signed.
+///
+/// View-only monitors cannot sign transactions, so mobilecoind builds this
+/// instead of a `TxProposal` for them. It holds no spend key material, and can
+/// be signed by any `RingSigner` holding the account's spend private key, for
+/// example on an offline machine.
+#[derive(Clone, Debug)]
+pub struct UnsignedTxProposal {
+    /// UTXOs used as inputs for this transaction.
+    pub utxos: Vec<UnspentTxOut>,
+
+    /// Destinations the transaction is being sent to.
+    pub outlays: Vec<Outlay>,
+
+    /// The transaction, without its signature.
+    pub unsigned_tx: UnsignedTx,
+
+    /// A map of outlay index -> TxOut index in the Tx object.
+    /// This is needed to map recipients to their respective TxOuts.
+    pub outlay_index_to_tx_out_index: HashMap<usize, usize>,
+
+    /// A list of the confirmation numbers, in the same order
+    /// as the outlays.
+    pub outlay_confirmation_numbers: Vec<TxOutConfirmationNumber>,
+
+    /// The shared secrets of the outlay outputs, in the same order as the
+    /// outlays. These let a signer check who each outlay pays.
+    pub outlay_shared_secrets: Vec<RistrettoPublic>,
+
+    /// The subaddress index every output that is not an outlay pays.
+    pub change_subaddress: u64,
+}
+
+impl UnsignedTxProposal {
+    pub fn fee(&self) -> u64 {
+        self.unsigned_tx.tx_prefix.fee
+    }
+
+    /// Check that the output secrets open the output commitments, and that
+    /// each outlay is paid by the output it is mapped to.
+    ///
+    /// This lets a signer trust the amounts and token ids it is shown, rather
+    /// than whoever built the proposal. Recipients are checked by
+    /// `check_recipients`.
+    pub fn check_outputs(&self) -> Result<(), Error> {
+        let outputs = &self.unsigned_tx.tx_prefix.outputs;
+        let output_secrets = &self.unsigned_tx.output_secrets;
+        if outputs.len() != output_secrets.len() {
+            return Err(Error::UnexpectedTxOutputs(format!(
+                "{} outputs but {} output secrets",
+                outputs.len(),
+                output_secrets.len()
+            )));
+        }
+
+        for (index, (output, secret)) in outputs.iter().zip(output_secrets).enumerate() {
+            let commitment = CompressedCommitment::new(
+                secret.amount.value,
+                secret.blinding,
+                &generators(*secret.amount.token_id),
+            );
+            if commitment != output.masked_amount.commitment {
+                return Err(Error::UnexpectedTxOutputs(format!(
+                    "output {} does not match its secret",
+                    index
+                )));
+            }
+        }
+
+        for (outlay_index, outlay) in self.outlays.iter().enumerate() {
+            let amount = self
+                .outlay_index_to_tx_out_index
+                .get(&outlay_index)
+                .and_then(|tx_out_index| output_secrets.get(*tx_out_index))
+                .map(|secret| secret.amount);
+            if amount != Some(Amount::new(outlay.value, outlay.token_id)) {
+                return Err(Error::UnexpectedTxOutputs(format!(
+                    "outlay {} is not paid by its output",
+                    outlay_index
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Check that each outlay output pays its outlay's recipient, and that
+    /// every other output pays the change subaddress of `account_key`.
+    ///
+    /// Outlay outputs are checked against their shared secrets, so a
+    /// malicious builder can at worst make an outlay unrecoverable, but not
+    /// pay it to anyone else.
+    pub fn check_recipients(&self, account_key: &AccountKey) -> Result<(), Error> {
+        let outputs = &self.unsigned_tx.tx_prefix.outputs;
+        if self.outlay_shared_secrets.len() != self.outlays.len()
+            || self.outlay_confirmation_numbers.len() != self.outlays.len()
+        {
+            return Err(Error::UnexpectedTxOutputs(format!(
+                "{} outlays but {} shared secrets and {} confirmation numbers",
+                self.outlays.len(),
+                self.outlay_shared_secrets.len(),
+                self.outlay_confirmation_numbers.len()
+            )));
+        }
+
+        let mut outlay_tx_out_indices = HashSet::default();
+        for (outlay_index, outlay) in self.outlays.iter().enumerate() {
+            let output = self
+                .outlay_index_to_tx_out_index
+                .get(&outlay_index)
+                .and_then(|tx_out_index| {
+                    outlay_tx_out_indices.insert(*tx_out_index);
+                    outputs.get(*tx_out_index)
+                })
+                .ok_or_else(|| {
+                    Error::UnexpectedTxOutputs(format!("outlay {} has no output", outlay_index))
+                })?;
+
+            let shared_secret = &self.outlay_shared_secrets[outlay_index];
+            let target_key = create_tx_out_target_key_from_shared_secret(
+                shared_secret,
+                outlay.receiver.spend_public_key(),
+            );
+            if CompressedRistrettoPublic::from(&target_key) != output.target_key
+                || TxOutConfirmationNumber::from(shared_secret)
+                    != self.outlay_confirmation_numbers[outlay_index]
+            {
+                return Err(Error::UnexpectedTxOutputs(format!(
+                    "outlay {} is not paid to its recipient",
+                    outlay_index
+                )));
+            }
+        }
+
+        for (index, output) in outputs.iter().enumerate() {
+            if outlay_tx_out_indices.contains(&index) {
+                continue;
+            }
+            if !subaddress_matches_tx_out(account_key, self.change_subaddress, output)
+                .unwrap_or(false)
+            {
+                return Err(Error::UnexpectedTxOutputs(format!(
+                    "output {} is neither an outlay nor change",
+                    index
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Sign the transaction, producing a `TxProposal` that can be submitted.
+    ///
+    /// The key images of the returned UTXOs are the ones revealed by the
+    /// signature. For view-only monitors these replace the placeholder key
+    /// images mobilecoind stores.
+    pub fn sign<S: RingSigner + ?Sized>(
+        &self,
+        ring_signer: &S,
+        rng: &mut (impl RngCore + CryptoRng),
+    ) -> Result<TxProposal, Error> {
+        let tx = self
+            .unsigned_tx
+            .sign(ring_signer, rng)
+            .map_err(|err| Error::TxBuild(format!("sign tx failed: {}", err)))?;
+
+        let utxos = self
+            .utxos
+            .iter()
+            .map(|utxo| {
+                let key_image = spent_key_image(&tx, &utxo.tx_out).ok_or_else(|| {
+                    Error::UnexpectedTxInputs(format!(
+                        "utxo {:?} is not an input",
+                        utxo.tx_out.public_key
+                    ))
+                })?;
+                Ok(UnspentTxOut {
+                    key_image,
+                    ..utxo.clone()
+                })
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        Ok(TxProposal {
+            utxos,
+            outlays: self.outlays.clone(),
+            tx,
+            outlay_index_to_tx_out_index: self.outlay_index_to_tx_out_index.clone(),
+            outlay_confirmation_numbers: self.outlay_confirmation_numbers.clone(),
+        })
+    }
+}
+
+/// The key image a signed transaction reveals for a given TxOut, if the TxOut
+/// is one of the transaction's inputs.
+pub fn spent_key_image(tx: &Tx, tx_out: &TxOut) -> Option<KeyImage> {
+    tx.prefix
+        .inputs
+        .iter()
+        .zip(tx.signature.ring_signatures.iter())
+        .find(|(tx_in, _)| tx_in.ring.contains(tx_out))
+        .map(|(_, ring_signature)| ring_signature.key_image)
+}
+
 pub struct TransactionsManager<
     T: BlockchainConnection + UserTxConnection + 'static,
     FPR: FogPubkeyResolver,
@@ -102,15 +321,16 @@ pub struct TransactionsManager<
     /// Peer manager, for communicating with validator nodes.
     peer_manager: ConnectionManager<T>,
 
-    /// Monotonically increasing counter. This is used for node round-robin
-    /// selection.
-    submit_node_offset: Arc<AtomicUsize>,
-
     /// Fog resolver maker, used when constructing outputs to fog recipients.
     /// This is abstracted because in tests, we don't want to form grpc
     /// connections to fog
     fog_resolver_factory: Arc<dyn Fn(&[FogUri]) -> Result<FPR, String> + Send + Sync>,
 
+    /// If set, transactions whose fee is not given explicitly pay enough for
+    /// them to be included within this many blocks, based on the pending
+    /// transaction pools of the consensus nodes, instead of the minimum fee.
+    target_confirmation_blocks: Option<u64>,
+
     /// Logger.
     logger: Logger,
 }
@@ -123,8 +343,8 @@ impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolve
             ledger_db: self.ledger_db.clone(),
             mobilecoind_db: self.mobilecoind_db.clone(),
             peer_manager: self.peer_manager.clone(),
-            submit_node_offset: self.submit_node_offset.clone(),
             fog_resolver_factory: self.fog_resolver_factory.clone(),
+            target_confirmation_blocks: self.target_confirmation_blocks,
             logger: self.logger.clone(),
         }
     }
@@ -155,6 +375,47 @@ fn get_fee(block_infos: &[BlockInfo], token_id: TokenId, opt_fee: u64) -> u64 {
     }
 }
 
+/// Estimate the fee that gets a transaction included within `target_blocks`
+/// blocks, given the pending pool statistics reported by consensus nodes.
+///
+/// When a node has more pending transactions than fit in `target_blocks`
+/// blocks, we outbid the lowest priority percentile that leaves few enough
+/// transactions ahead of ours. The result is never below `minimum_fee`, and
+/// never above `MAX_TARGET_CONFIRMATION_FEE_MULTIPLIER` times it.
+fn get_target_confirmation_fee(
+    pool_stats: &[PoolStats],
+    minimum_fee: u64,
+    target_blocks: u64,
+) -> u64 {
+    let required_priority = pool_stats
+        .iter()
+        .map(|stats| {
+            let capacity = stats
+                .max_transactions_per_block
+                .max(1)
+                .saturating_mul(target_blocks.max(1));
+            let priorities = &stats.priorities;
+            if priorities.num_txs < capacity {
+                0
+            } else if capacity.saturating_mul(2) >= priorities.num_txs {
+                priorities.p50.saturating_add(1)
+            } else if capacity.saturating_mul(10) >= priorities.num_txs {
+                priorities.p90.saturating_add(1)
+            } else {
+                priorities.max.saturating_add(1)
+            }
+        })
+        .max()
+        .unwrap_or(0);
+
+    // The enclave computes priority as fee / (minimum_fee >> 7).
+    let fee = required_priority.saturating_mul(minimum_fee >> SMALLEST_MINIMUM_FEE_LOG2);
+    fee.clamp(
+        minimum_fee,
+        minimum_fee.saturating_mul(MAX_TARGET_CONFIRMATION_FEE_MULTIPLIER),
+    )
+}
+
 impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolver>
     TransactionsManager<T, FPR>
 {
@@ -163,22 +424,43 @@ impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolve
         mobilecoind_db: Database,
         peer_manager: ConnectionManager<T>,
         fog_resolver_factory: Arc<dyn Fn(&[FogUri]) -> Result<FPR, String> + Send + Sync>,
+        target_confirmation_blocks: Option<u64>,
         logger: Logger,
     ) -> Self {
-        let mut rng = rand::thread_rng();
         Self {
             ledger_db,
             mobilecoind_db,
             peer_manager,
-            submit_node_offset: Arc::new(AtomicUsize::new(rng.next_u64() as usize)),
             fog_resolver_factory,
+            target_confirmation_blocks,
             logger,
         }
     }
 
+    // Gets the pending pool statistics of every peer that answers.
+    fn get_pool_stats(&self) -> Vec<PoolStats> {
+        self.peer_manager
+            .conns()
+            .iter()
+            .filter_map(|conn| match conn.fetch_pool_stats(empty()) {
+                Ok(pool_stats) => Some(pool_stats),
+                Err(err) => {
+                    log::warn!(
+                        self.logger,
+                        "Could not get pool stats from {}: {:?}",
+                        conn,
+                        err
+                    );
+                    None
+                }
+            })
+            .collect()
+    }
+
     // Gets the network fee and block_version, unless opt_fee is nonzero.
     // If opt fee is nonzero then we use local ledger block version and this fee,
-    // and don't make a network call
+    // and don't make a network call. In target confirmation mode, the network fee
+    // is raised according to the pending pools of our peers.
     fn get_network_fee_and_block_version(
         &self,
         token_id: TokenId,
@@ -191,7 +473,10 @@ impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolve
         Ok(if opt_fee != 0 {
             (opt_fee, candidate_block_version)
         } else {
-            let fee = get_fee(block_infos, token_id, opt_fee);
+            let mut fee = get_fee(block_infos, token_id, opt_fee);
+            if let Some(target_blocks) = self.target_confirmation_blocks {
+                fee = get_target_confirmation_fee(&self.get_pool_stats(), fee, target_blocks);
+            }
             let block_version = max(
                 candidate_block_version,
                 get_network_block_version(block_infos),
@@ -202,23 +487,32 @@ impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolve
 
     /// Create a TxProposal.
     ///
+    /// This requires the sender monitor to hold a full account key. View-only
+    /// monitors should use `build_unsigned_transaction` instead.
+    ///
     /// # Arguments
     /// * `sender_monitor_id` - Indicates the the account key needed to spend
     ///   the txo's.
-    /// * `token_id` - The token id to transact in.
+    /// * `fee_token_id` - The token id the fee is paid in.
     /// * `change_subaddress` - Recipient of any change.
-    /// * `inputs` - UTXOs that will be spent by the transaction.
-    /// * `outlays` - Output amounts and recipients.
+    /// * `inputs` - UTXOs that will be spent by the transaction. Each must be
+    ///   in the fee token id or the token id of one of the outlays.
+    /// * `outlays` - Output amounts and recipients. Outlays in token ids other
+    ///   than `fee_token_id` make a mixed transaction, which pays them all
+    ///   atomically, with change returned in each token id. This requires a
+    ///   block version which supports mixed transactions.
     /// * `last_block_infos` - Last block info responses from the network, for
     ///   determining fees. This should normally come from polling_network_state
     /// * `opt_fee` - Transaction fee in picoMOB. If zero, defaults to MIN_FEE.
     /// * `opt_tombstone` - Tombstone block. If zero, sets to default.
     /// * `opt_memo_builder` - Optional memo builder to use instead of the
     ///   default one (EmptyMemoBuilder).
+    /// * `utxo_selection_strategy` - Strategy used for choosing which of the
+    ///   `inputs` get spent.
     pub fn build_transaction(
         &self,
         sender_monitor_id: &MonitorId,
-        token_id: TokenId,
+        fee_token_id: TokenId,
         change_subaddress: u64,
         inputs: &[UnspentTxOut],
         outlays: &[Outlay],
@@ -226,15 +520,81 @@ impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolve
         opt_fee: u64,
         opt_tombstone: u64,
         opt_memo_builder: Option<Box<dyn MemoBuilder + 'static + Send + Sync>>,
+        utxo_selection_strategy: &dyn UtxoSelectionStrategy,
     ) -> Result<TxProposal, Error> {
+        // Get sender monitor data.
+        let sender_monitor_data = self.mobilecoind_db.get_monitor_data(sender_monitor_id)?;
+        let account_key = sender_monitor_data
+            .account_key()
+            .ok_or(Error::ViewOnlyMonitor)?;
+
+        let unsigned_tx_proposal = self.build_unsigned_transaction(
+            sender_monitor_id,
+            fee_token_id,
+            change_subaddress,
+            inputs,
+            outlays,
+            last_block_infos,
+            opt_fee,
+            opt_tombstone,
+            opt_memo_builder,
+            utxo_selection_strategy,
+        )?;
+
+        let mut rng = rand::thread_rng();
+        let tx_proposal =
+            unsigned_tx_proposal.sign(&LocalRingSigner::from(account_key), &mut rng)?;
+        log::trace!(
+            self.logger,
+            "Tx constructed, hash={}",
+            tx_proposal.tx.tx_hash()
+        );
+
+        Ok(tx_proposal)
+    }
+
+    /// Create an UnsignedTxProposal. This works for any monitor, including
+    /// view-only ones.
+    ///
+    /// # Arguments
+    /// Same as `build_transaction`.
+    pub fn build_unsigned_transaction(
+        &self,
+        sender_monitor_id: &MonitorId,
+        fee_token_id: TokenId,
+        change_subaddress: u64,
+        inputs: &[UnspentTxOut],
+        outlays: &[Outlay],
+        last_block_infos: &[BlockInfo],
+        opt_fee: u64,
+        opt_tombstone: u64,
+        opt_memo_builder: Option<Box<dyn MemoBuilder + 'static + Send + Sync>>,
+        utxo_selection_strategy: &dyn UtxoSelectionStrategy,
+    ) -> Result<UnsignedTxProposal, Error> {
         let logger = self.logger.new(o!("sender_monitor_id" => sender_monitor_id.to_string(), "outlays" => format!("{:?}", outlays)));
         log::trace!(logger, "Building pending transaction...");
 
-        // All inputs must be of the correct token id.
-        if inputs.iter().any(|utxo| utxo.token_id != *token_id) {
+        // Figure out the total amount of transaction in each token id
+        // (excluding fee).
+        let outlay_values =
+            total_value_by_token_id(outlays.iter().map(|outlay| (outlay.token_id, outlay.value)))?;
+        log::trace!(
+            logger,
+            "Total transaction values excluding fees: {:?}",
+            outlay_values
+        );
+
+        // All inputs must be of the fee token id, or of an outlay's token id.
+        if inputs.iter().any(|utxo| {
+            let utxo_token_id = TokenId::from(utxo.token_id);
+            utxo_token_id != fee_token_id && !outlay_values.contains_key(&utxo_token_id)
+        }) {
             return Err(Error::InvalidArgument(
                 "inputs".to_string(),
-                format!("All inputs must be of token_id {}", token_id),
+                format!(
+                    "All inputs must be of token_id {} or of the token id of an outlay",
+                    fee_token_id
+                ),
             ));
         }
 
@@ -246,26 +606,47 @@ impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolve
         // Get sender monitor data.
         let sender_monitor_data = self.mobilecoind_db.get_monitor_data(sender_monitor_id)?;
 
-        // Figure out total amount of transaction (excluding fee).
-        let total_value: u64 = outlays.iter().map(|outlay| outlay.value).sum();
-        log::trace!(
-            logger,
-            "Total transaction value excluding fees: {}",
-            total_value
-        );
-
         // Figure out the block_version and fee (involves network round-trips to
         // consensus, unless opt_fee is non-zero)
         let (fee, block_version) =
-            self.get_network_fee_and_block_version(token_id, opt_fee, last_block_infos)?;
+            self.get_network_fee_and_block_version(fee_token_id, opt_fee, last_block_infos)?;
 
         // Confirm that we understand this block version
         let block_version =
             BlockVersion::try_from(block_version).map_err(|err| Error::TxBuild(err.to_string()))?;
 
-        // Select the UTXOs to be used for this transaction.
-        let selected_utxos =
-            Self::select_utxos_for_value(token_id, inputs, total_value + fee, MAX_INPUTS as usize)?;
+        // The value the inputs must cover in each token id, including the fee.
+        let required_values =
+            total_value_by_token_id(outlay_values.into_iter().chain(once((fee_token_id, fee))))?;
+        if required_values.len() > 1 && !block_version.mixed_transactions_are_supported() {
+            return Err(Error::TxBuild(format!(
+                "Block version {} does not support transactions in several token ids",
+                block_version
+            )));
+        }
+
+        // Select the UTXOs to be used for this transaction, for each token id in
+        // turn. Every token id needs at least one input, and all of them share
+        // the limit on the number of inputs.
+        let mut rng = rand::thread_rng();
+        let mut selected_utxos = Vec::new();
+        let mut remaining_token_ids = required_values.len();
+        for (token_id, value) in required_values {
+            remaining_token_ids -= 1;
+            if value == 0 {
+                continue;
+            }
+            let max_inputs =
+                (MAX_INPUTS as usize).saturating_sub(selected_utxos.len() + remaining_token_ids);
+            selected_utxos.extend(Self::select_utxos_for_value(
+                utxo_selection_strategy,
+                token_id,
+                inputs,
+                value,
+                max_inputs,
+                &mut rng,
+            )?);
+        }
         log::trace!(
             logger,
             "Selected {} utxos ({:?})",
@@ -309,26 +690,30 @@ impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolve
         };
         log::trace!(logger, "Tombstone block set to {}", tombstone_block);
 
-        // Build and return the TxProposal object
-        let mut rng = rand::thread_rng();
-        let tx_proposal = Self::build_tx_proposal(
+        // Build and return the UnsignedTxProposal object
+        let unsigned_tx_proposal = Self::build_unsigned_tx_proposal(
             &selected_utxos_with_proofs,
             rings,
             block_version,
-            token_id,
+            fee_token_id,
             fee,
-            &sender_monitor_data.account_key,
+            sender_monitor_data.monitor_key(),
             change_subaddress,
             outlays,
+            &[],
             tombstone_block,
             &self.fog_resolver_factory,
             opt_memo_builder,
             &mut rng,
             &self.logger,
         )?;
-        log::trace!(logger, "Tx constructed, hash={}", tx_proposal.tx.tx_hash());
+        log::trace!(
+            logger,
+            "Unsigned tx constructed, prefix hash={}",
+            unsigned_tx_proposal.unsigned_tx.tx_prefix.hash()
+        );
 
-        Ok(tx_proposal)
+        Ok(unsigned_tx_proposal)
     }
 
     /// Create a TxProposal that attempts to merge multiple UTXOs into a single
@@ -357,6 +742,7 @@ impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolve
 
         // Get monitor data.
         let monitor_data = self.mobilecoind_db.get_monitor_data(monitor_id)?;
+        let account_key = monitor_data.account_key().ok_or(Error::ViewOnlyMonitor)?;
 
         let num_blocks_in_ledger = self.ledger_db.num_blocks()?;
 
@@ -431,8 +817,9 @@ impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolve
 
         // We are paying ourselves the entire amount.
         let outlays = vec![Outlay {
-            receiver: monitor_data.account_key.subaddress(subaddress_index),
+            receiver: account_key.subaddress(subaddress_index),
             value: total_value - fee,
+            token_id,
         }];
 
         // Build and return the TxProposal object
@@ -443,9 +830,10 @@ impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolve
             block_version,
             token_id,
             fee,
-            &monitor_data.account_key,
+            account_key,
             subaddress_index,
             &outlays,
+            &[],
             tombstone_block,
             &self.fog_resolver_factory,
             None,
@@ -540,6 +928,7 @@ impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolve
         let outlays = vec![Outlay {
             receiver: receiver.clone(),
             value: total_value - fee,
+            token_id,
         }];
 
         // Build and return the TxProposal object
@@ -553,6 +942,7 @@ impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolve
             account_key,
             0,
             &outlays,
+            &[],
             tombstone_block,
             &self.fog_resolver_factory,
             None,
@@ -568,134 +958,479 @@ impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolve
         Ok(tx_proposal)
     }
 
-    /// Submit a previously built tx proposal to the network.
-    pub fn submit_tx_proposal(&self, tx_proposal: &TxProposal) -> Result<u64, Error> {
-        // Pick a peer to submit to.
-        let responder_ids = self.peer_manager.responder_ids();
-        if responder_ids.is_empty() {
-            return Err(Error::NoPeersConfigured);
-        }
-
-        let idx = self.submit_node_offset.fetch_add(1, Ordering::SeqCst);
-        let responder_id = &responder_ids[idx % responder_ids.len()];
-
-        // Try and submit.
-        let block_height = self
-            .peer_manager
-            .conn(responder_id)
-            .ok_or(Error::NodeNotFound)?
-            .propose_tx(&tx_proposal.tx, empty())
-            .map_err(Error::from)?;
+    /// Create a SignedContingentInput (see MCIP #31), which offers the whole
+    /// value of a UTXO to whoever pays the given counter amount to the monitor,
+    /// in the same transaction.
+    ///
+    /// # Arguments
+    /// * `sender_monitor_id` - Monitor owning the UTXO. It must hold a full
+    ///   account key, since the input is signed right away.
+    /// * `receiver_subaddress` - Subaddress the counter amount is paid to.
+    /// * `utxo` - The UTXO offered.
+    /// * `counter_amount` - The amount requested in exchange for the UTXO.
+    /// * `last_block_infos` - Last block info responses from the network, for
+    ///   determining the block version. This should normally come from
+    ///   polling_network_state
+    /// * `opt_tombstone` - Block index at which the offer expires. If zero, the
+    ///   offer stands until the UTXO is spent.
+    pub fn build_signed_contingent_input(
+        &self,
+        sender_monitor_id: &MonitorId,
+        receiver_subaddress: u64,
+        utxo: &UnspentTxOut,
+        counter_amount: Amount,
+        last_block_infos: &[BlockInfo],
+        opt_tombstone: u64,
+    ) -> Result<SignedContingentInput, Error> {
+        let logger = self
+            .logger
+            .new(o!("sender_monitor_id" => sender_monitor_id.to_string()));
+        log::trace!(logger, "Building signed contingent input...");
 
-        log::info!(
-            self.logger,
-            "Tx {} submitted at block height {}",
-            tx_proposal.tx,
-            block_height
+        // Get sender monitor data.
+        let sender_monitor_data = self.mobilecoind_db.get_monitor_data(sender_monitor_id)?;
+        let account_key = sender_monitor_data
+            .account_key()
+            .ok_or(Error::ViewOnlyMonitor)?;
+
+        // The input is signed under the rules of the latest block version we
+        // know of.
+        let block_version = max(
+            self.ledger_db.get_latest_block()?.version,
+            get_network_block_version(last_block_infos),
         );
+        let block_version =
+            BlockVersion::try_from(block_version).map_err(|err| Error::TxBuild(err.to_string()))?;
 
-        // Successfully submitted.
-        Ok(block_height)
-    }
-
-    /// Returns a subset of UTXOs totalling at least the given amount.
-    // TODO: This method should take attempted_spend_height into account.
-    fn select_utxos_for_value(
-        token_id: TokenId,
-        utxos: &[UnspentTxOut],
-        value: u64,
-        max_inputs: usize,
-    ) -> Result<Vec<UnspentTxOut>, Error> {
-        // Sort the utxos in descending order by value.
-        let mut sorted_utxos: Vec<UnspentTxOut> = utxos
-            .iter()
-            .filter(|utxo| utxo.token_id == token_id)
-            .cloned()
-            .collect();
-        sorted_utxos.sort_by_key(|utxo| Reverse(utxo.value));
-
-        // The maximum spendable is limited by the maximal number of inputs we can use.
-        let max_spendable_amount = sorted_utxos
-            .iter()
-            .take(max_inputs)
-            .map(|utxo| utxo.value)
-            .sum();
-        if value > max_spendable_amount {
-            // See if we merged the UTXOs we would be able to spend this amount.
-            let total_utxos_value: u64 = sorted_utxos.iter().map(|utxo| utxo.value).sum();
-            if total_utxos_value >= value {
-                return Err(Error::InsufficientFundsFragmentedUtxos);
-            } else {
-                return Err(Error::InsufficientFunds);
-            }
-        }
+        // The UTXO with its proof of membership, and a ring of mixins for it.
+        let proof = self
+            .get_membership_proofs(core::slice::from_ref(&utxo.tx_out))?
+            .pop()
+            .ok_or_else(|| Error::TxBuild("missing membership proof".into()))?;
+        let (ring, membership_proofs): (Vec<TxOut>, Vec<TxOutMembershipProof>) = self
+            .get_rings(DEFAULT_RING_SIZE, 1, &[proof.index])?
+            .pop()
+            .ok_or_else(|| Error::TxBuild("missing ring".into()))?
+            .into_iter()
+            .unzip();
+        let input_credentials = Self::build_input_credentials(
+            utxo,
+            &proof,
+            ring,
+            membership_proofs,
+            sender_monitor_data.monitor_key(),
+            &logger,
+        )?;
 
-        // Choose utxos to spend.
-        let mut selected_utxos: Vec<UnspentTxOut> = Vec::new();
-        loop {
-            let total: u64 = selected_utxos.iter().map(|utxo| utxo.value).sum();
-            if total >= value {
-                break;
-            }
+        let receiver = account_key.subaddress(receiver_subaddress);
+        let fog_resolver = {
+            let fog_uris: Vec<FogUri> = extract_fog_uri(&receiver)?.into_iter().collect();
+            (self.fog_resolver_factory)(&fog_uris).map_err(Error::Fog)?
+        };
 
-            // Grab the next (smallest utxo)
-            let next_utxo = sorted_utxos.pop().ok_or(Error::InsufficientFunds)?;
-            selected_utxos.push(next_utxo.clone());
+        let mut sci_builder = SignedContingentInputBuilder::new(
+            block_version,
+            input_credentials,
+            fog_resolver,
+            EmptyMemoBuilder::default(),
+        )
+        .map_err(|err| {
+            Error::TxBuild(format!(
+                "Error creating signed contingent input builder: {}",
+                err
+            ))
+        })?;
 
-            // Cap at maximum allowed inputs.
-            if selected_utxos.len() > max_inputs {
-                // Remove the lowest utxo.
-                selected_utxos.remove(0);
-            }
+        let mut rng = rand::thread_rng();
+        sci_builder
+            .add_required_output(counter_amount, &receiver, &mut rng)
+            .map_err(|err| Error::TxBuild(format!("failed adding required output: {}", err)))?;
+        if opt_tombstone > 0 {
+            sci_builder.set_tombstone_block(opt_tombstone);
         }
 
-        // Sanity.
-        assert!(!selected_utxos.is_empty());
-        assert!(selected_utxos.len() <= max_inputs);
+        let sci = sci_builder
+            .build(&LocalRingSigner::from(account_key), &mut rng)
+            .map_err(|err| {
+                Error::TxBuild(format!("build signed contingent input failed: {}", err))
+            })?;
+        log::trace!(
+            logger,
+            "Signed contingent input constructed, key image={:?}",
+            sci.key_image()
+        );
 
-        // Return selected utxos.
-        Ok(selected_utxos)
+        Ok(sci)
     }
 
-    /// Select UTXOs for optimization. The current strategy is to to attempt to
-    /// add the maximum number of small UTXOs into the biggest one, which is
-    /// the one most likely to be used when spending. The assumption is that
-    /// if we maintain it as the biggest, we're less likely to need multiple
-    /// UTXOs in future transactions.
-    /// 1) Filter out UTXOs which we believe are currently still pending/at an
-    /// unknown state. 2) Sort remaining UTXOs in ascending order (by their
-    /// value). 3) Grab the largest available UTXO, and MAX_INPUTS-1
-    /// smallest available UTXOs. 4) If the sum of the smallest available
-    /// UTXOs > fee, we would be able to increase our largest    UTXO and
-    /// we're done. If not, try again without the smallest UTXO.
+    /// Check that a signed contingent input can be filled: it is valid, has no
+    /// partial fill rules, its input is unspent, it has not expired and its
+    /// ring is in the ledger.
     ///
-    /// Returns selected UTXOs
-    fn select_utxos_for_optimization(
-        num_blocks_in_ledger: u64,
-        inputs: &[UnspentTxOut],
-        max_inputs: usize,
-        token_id: TokenId,
-        fee: u64,
-    ) -> Result<Vec<UnspentTxOut>, Error> {
-        if max_inputs < 2 {
+    /// Returns the signed contingent input with the proofs of membership of its
+    /// ring, which are needed to add it to a transaction.
+    pub fn prepare_signed_contingent_input(
+        &self,
+        sci: &SignedContingentInput,
+    ) -> Result<SignedContingentInput, Error> {
+        sci.validate()
+            .map_err(|err| Error::InvalidArgument("sci".to_string(), err.to_string()))?;
+
+        // Swaps fill signed contingent inputs in full, and do not account for
+        // the outputs of partial fill rules.
+        if has_partial_fill_rules(sci) {
             return Err(Error::InvalidArgument(
-                "max_inputs".to_owned(),
-                "need at least 2 inputs to be able to merge".to_owned(),
+                "sci".to_string(),
+                "partial fill offers are not supported".to_string(),
             ));
         }
 
-        // All inputs must be of the correct token id.
-        if inputs.iter().any(|utxo| utxo.token_id != *token_id) {
+        if self.ledger_db.contains_key_image(&sci.key_image())? {
             return Err(Error::InvalidArgument(
-                "inputs".to_string(),
-                format!("All inputs must be of token_id {}", token_id),
+                "sci".to_string(),
+                "input was already spent".to_string(),
             ));
         }
 
-        let mut spendable_inputs: Vec<&UnspentTxOut> = inputs
-            .iter()
-            .filter(|utxo| num_blocks_in_ledger >= utxo.attempted_spend_tombstone)
-            .collect();
+        if let Some(rules) = sci.tx_in.input_rules.as_ref() {
+            if rules.max_tombstone_block != 0
+                && self.ledger_db.num_blocks()? >= rules.max_tombstone_block
+            {
+                return Err(Error::InvalidArgument(
+                    "sci".to_string(),
+                    "offer has expired".to_string(),
+                ));
+            }
+        }
+
+        // The proofs of membership are looked up by global index, so the ring
+        // must match the ledger at these indices.
+        for (index, tx_out) in sci.tx_out_global_indices.iter().zip(sci.tx_in.ring.iter()) {
+            if self.ledger_db.get_tx_out_by_index(*index)? != *tx_out {
+                return Err(Error::InvalidArgument(
+                    "sci".to_string(),
+                    format!("ring member {} does not match the ledger", index),
+                ));
+            }
+        }
+
+        let mut sci = sci.clone();
+        sci.tx_in.proofs = self
+            .ledger_db
+            .get_tx_out_proof_of_memberships(&sci.tx_out_global_indices)?;
+        Ok(sci)
+    }
+
+    /// Create a TxProposal which fills a signed contingent input. Our inputs
+    /// pay for the outputs required by its rules and for the fee, and the
+    /// value it offers is returned to us along with any change.
+    ///
+    /// # Arguments
+    /// * `sender_monitor_id` - Monitor filling the signed contingent input. It
+    ///   must hold a full account key.
+    /// * `fee_token_id` - The token id the fee is paid in.
+    /// * `change_subaddress` - Recipient of the value offered, and of any
+    ///   change.
+    /// * `inputs` - UTXOs that may be spent by the transaction.
+    /// * `sci` - The signed contingent input to fill.
+    /// * `last_block_infos` - Last block info responses from the network, for
+    ///   determining fees. This should normally come from polling_network_state
+    /// * `opt_fee` - Transaction fee. If zero, defaults to the network fee.
+    /// * `opt_tombstone` - Tombstone block. If zero, sets to default. Either
+    ///   way, it is limited by the tombstone block of the signed input.
+    /// * `utxo_selection_strategy` - Strategy used for choosing which of the
+    ///   `inputs` get spent.
+    pub fn build_swap_transaction(
+        &self,
+        sender_monitor_id: &MonitorId,
+        fee_token_id: TokenId,
+        change_subaddress: u64,
+        inputs: &[UnspentTxOut],
+        sci: &SignedContingentInput,
+        last_block_infos: &[BlockInfo],
+        opt_fee: u64,
+        opt_tombstone: u64,
+        utxo_selection_strategy: &dyn UtxoSelectionStrategy,
+    ) -> Result<TxProposal, Error> {
+        let logger = self
+            .logger
+            .new(o!("sender_monitor_id" => sender_monitor_id.to_string()));
+        log::trace!(logger, "Building swap transaction...");
+
+        // Get sender monitor data.
+        let sender_monitor_data = self.mobilecoind_db.get_monitor_data(sender_monitor_id)?;
+        let account_key = sender_monitor_data
+            .account_key()
+            .ok_or(Error::ViewOnlyMonitor)?;
+
+        let sci = self.prepare_signed_contingent_input(sci)?;
+
+        // Figure out the block_version and fee (involves network round-trips to
+        // consensus, unless opt_fee is non-zero)
+        let (fee, block_version) =
+            self.get_network_fee_and_block_version(fee_token_id, opt_fee, last_block_infos)?;
+
+        // Confirm that we understand this block version
+        let block_version =
+            BlockVersion::try_from(block_version).map_err(|err| Error::TxBuild(err.to_string()))?;
+        if !block_version.signed_input_rules_are_supported() {
+            return Err(Error::TxBuild(format!(
+                "Block version {} does not support signed contingent inputs",
+                block_version
+            )));
+        }
+
+        // The value our inputs must cover in each token id: the outputs required
+        // by the signed input and the fee, less the value of the signed input.
+        let offered = TokenId::from(sci.pseudo_output_amount.token_id);
+        let required_values = total_value_by_token_id(
+            sci.required_output_amounts
+                .iter()
+                .map(|amount| (TokenId::from(amount.token_id), amount.value))
+                .chain(once((fee_token_id, fee))),
+        )?;
+
+        // Select the UTXOs to be used for this transaction, for each token id in
+        // turn. The signed input counts towards the limit on the number of inputs.
+        let mut rng = rand::thread_rng();
+        let mut selected_utxos = Vec::new();
+        let mut remaining_token_ids = required_values.len();
+        for (token_id, value) in required_values {
+            remaining_token_ids -= 1;
+            let value = if token_id == offered {
+                value.saturating_sub(sci.pseudo_output_amount.value)
+            } else {
+                value
+            };
+            if value == 0 {
+                continue;
+            }
+            let max_inputs = (MAX_INPUTS as usize)
+                .saturating_sub(1 + selected_utxos.len() + remaining_token_ids);
+            selected_utxos.extend(Self::select_utxos_for_value(
+                utxo_selection_strategy,
+                token_id,
+                inputs,
+                value,
+                max_inputs,
+                &mut rng,
+            )?);
+        }
+        log::trace!(
+            logger,
+            "Selected {} utxos ({:?})",
+            selected_utxos.len(),
+            selected_utxos,
+        );
+
+        // The selected_utxos with corresponding proofs of membership.
+        let selected_utxos_with_proofs: Vec<(UnspentTxOut, TxOutMembershipProof)> = {
+            let outputs: Vec<TxOut> = selected_utxos
+                .iter()
+                .map(|utxo| utxo.tx_out.clone())
+                .collect();
+            let proofs = self.get_membership_proofs(&outputs)?;
+
+            selected_utxos.into_iter().zip(proofs.into_iter()).collect()
+        };
+        log::trace!(logger, "Got membership proofs");
+
+        // A ring of mixins for each UTXO.
+        let rings = {
+            let excluded_tx_out_indices: Vec<u64> = selected_utxos_with_proofs
+                .iter()
+                .map(|(_, proof)| proof.index)
+                .collect();
+
+            self.get_rings(
+                DEFAULT_RING_SIZE,
+                selected_utxos_with_proofs.len(),
+                &excluded_tx_out_indices,
+            )?
+        };
+        log::trace!(logger, "Got {} rings", rings.len());
+
+        // Come up with tombstone block.
+        let tombstone_block = if opt_tombstone > 0 {
+            opt_tombstone
+        } else {
+            self.ledger_db.num_blocks()? + DEFAULT_NEW_TX_BLOCK_ATTEMPTS
+        };
+        log::trace!(logger, "Tombstone block set to {}", tombstone_block);
+
+        // Build and return the TxProposal object
+        let tx_proposal = Self::build_tx_proposal(
+            &selected_utxos_with_proofs,
+            rings,
+            block_version,
+            fee_token_id,
+            fee,
+            account_key,
+            change_subaddress,
+            &[],
+            &[sci],
+            tombstone_block,
+            &self.fog_resolver_factory,
+            None,
+            &mut rng,
+            &self.logger,
+        )?;
+        log::trace!(
+            logger,
+            "Swap tx constructed, hash={}",
+            tx_proposal.tx.tx_hash()
+        );
+
+        Ok(tx_proposal)
+    }
+
+    /// Submit a previously built tx proposal to the network.
+    ///
+    /// Peers are tried in order of health, as ranked by the peer manager,
+    /// until one of them responds.
+    pub fn submit_tx_proposal(&self, tx_proposal: &TxProposal) -> Result<u64, Error> {
+        let responder_ids = self.peer_manager.ranked_responder_ids();
+        if responder_ids.is_empty() {
+            return Err(Error::NoPeersConfigured);
+        }
+
+        let mut last_err = Error::NodeNotFound;
+        for responder_id in responder_ids.iter() {
+            let conn = match self.peer_manager.conn(responder_id) {
+                Some(conn) => conn,
+                None => continue,
+            };
+
+            let start = Instant::now();
+            match conn.propose_tx(&tx_proposal.tx, empty()) {
+                Ok(block_height) => {
+                    self.peer_manager
+                        .record_success(responder_id, start.elapsed());
+                    log::info!(
+                        self.logger,
+                        "Tx {} submitted to {} at block height {}",
+                        tx_proposal.tx,
+                        responder_id,
+                        block_height
+                    );
+                    return Ok(block_height);
+                }
+                // The peer rejected the transaction itself, so there is no point
+                // in trying the other peers.
+                Err(
+                    err @ RetryError::Operation {
+                        error: ConnectionError::TransactionValidation(_),
+                        ..
+                    },
+                ) => {
+                    self.peer_manager
+                        .record_success(responder_id, start.elapsed());
+                    return Err(err.into());
+                }
+                Err(err) => {
+                    self.peer_manager.record_failure(responder_id);
+                    log::warn!(
+                        self.logger,
+                        "Failed to submit tx {} to {}, trying the next peer: {}",
+                        tx_proposal.tx,
+                        responder_id,
+                        err
+                    );
+                    last_err = Error::from(err);
+                }
+            }
+        }
+
+        Err(last_err)
+    }
+
+    /// Returns a subset of UTXOs totalling at least the given amount, chosen
+    /// by the given selection strategy.
+    // TODO: This method should take attempted_spend_height into account.
+    fn select_utxos_for_value(
+        utxo_selection_strategy: &dyn UtxoSelectionStrategy,
+        token_id: TokenId,
+        utxos: &[UnspentTxOut],
+        value: u64,
+        max_inputs: usize,
+        rng: &mut dyn RngCore,
+    ) -> Result<Vec<UnspentTxOut>, Error> {
+        // Sort the utxos in descending order by value.
+        let mut sorted_utxos: Vec<UnspentTxOut> = utxos
+            .iter()
+            .filter(|utxo| utxo.token_id == token_id)
+            .cloned()
+            .collect();
+        sorted_utxos.sort_by_key(|utxo| Reverse(utxo.value));
+
+        // The maximum spendable is limited by the maximal number of inputs we can use.
+        let max_spendable_amount = sorted_utxos
+            .iter()
+            .take(max_inputs)
+            .map(|utxo| utxo.value)
+            .sum();
+        if value > max_spendable_amount {
+            // See if we merged the UTXOs we would be able to spend this amount.
+            let total_utxos_value: u64 = sorted_utxos.iter().map(|utxo| utxo.value).sum();
+            if total_utxos_value >= value {
+                return Err(Error::InsufficientFundsFragmentedUtxos);
+            } else {
+                return Err(Error::InsufficientFunds);
+            }
+        }
+
+        // Choose utxos to spend.
+        let selected_utxos =
+            utxo_selection_strategy.select_utxos(&sorted_utxos, value, max_inputs, rng)?;
+
+        // Sanity.
+        assert!(!selected_utxos.is_empty());
+        assert!(selected_utxos.len() <= max_inputs);
+        assert!(selected_utxos.iter().map(|utxo| utxo.value).sum::<u64>() >= value);
+
+        // Return selected utxos.
+        Ok(selected_utxos)
+    }
+
+    /// Select UTXOs for optimization. The current strategy is to to attempt to
+    /// add the maximum number of small UTXOs into the biggest one, which is
+    /// the one most likely to be used when spending. The assumption is that
+    /// if we maintain it as the biggest, we're less likely to need multiple
+    /// UTXOs in future transactions.
+    /// 1) Filter out UTXOs which we believe are currently still pending/at an
+    /// unknown state. 2) Sort remaining UTXOs in ascending order (by their
+    /// value). 3) Grab the largest available UTXO, and MAX_INPUTS-1
+    /// smallest available UTXOs. 4) If the sum of the smallest available
+    /// UTXOs > fee, we would be able to increase our largest    UTXO and
+    /// we're done. If not, try again without the smallest UTXO.
+    ///
+    /// Returns selected UTXOs
+    fn select_utxos_for_optimization(
+        num_blocks_in_ledger: u64,
+        inputs: &[UnspentTxOut],
+        max_inputs: usize,
+        token_id: TokenId,
+        fee: u64,
+    ) -> Result<Vec<UnspentTxOut>, Error> {
+        if max_inputs < 2 {
+            return Err(Error::InvalidArgument(
+                "max_inputs".to_owned(),
+                "need at least 2 inputs to be able to merge".to_owned(),
+            ));
+        }
+
+        // All inputs must be of the correct token id.
+        if inputs.iter().any(|utxo| utxo.token_id != *token_id) {
+            return Err(Error::InvalidArgument(
+                "inputs".to_string(),
+                format!("All inputs must be of token_id {}", token_id),
+            ));
+        }
+
+        let mut spendable_inputs: Vec<&UnspentTxOut> = inputs
+            .iter()
+            .filter(|utxo| num_blocks_in_ledger >= utxo.attempted_spend_tombstone)
+            .collect();
 
         // No point in merging if we are able to spend all inputs at once.
         if spendable_inputs.len() < max_inputs {
@@ -817,39 +1552,83 @@ impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolve
         Ok(result)
     }
 
-    /// Create a TxProposal.
+    /// Create a TxProposal, signed with the given account key.
+    ///
+    /// # Arguments
+    /// See `build_unsigned_tx_proposal`.
+    fn build_tx_proposal(
+        inputs: &[(UnspentTxOut, TxOutMembershipProof)],
+        rings: Vec<Vec<(TxOut, TxOutMembershipProof)>>,
+        block_version: BlockVersion,
+        fee_token_id: TokenId,
+        fee: u64,
+        from_account_key: &AccountKey,
+        change_subaddress: u64,
+        destinations: &[Outlay],
+        scis: &[SignedContingentInput],
+        tombstone_block: BlockIndex,
+        fog_resolver_factory: &Arc<dyn Fn(&[FogUri]) -> Result<FPR, String> + Send + Sync>,
+        opt_memo_builder: Option<Box<dyn MemoBuilder + 'static + Send + Sync>>,
+        rng: &mut (impl RngCore + CryptoRng),
+        logger: &Logger,
+    ) -> Result<TxProposal, Error> {
+        let unsigned_tx_proposal = Self::build_unsigned_tx_proposal(
+            inputs,
+            rings,
+            block_version,
+            fee_token_id,
+            fee,
+            &MonitorKey::AccountKey(from_account_key.clone()),
+            change_subaddress,
+            destinations,
+            scis,
+            tombstone_block,
+            fog_resolver_factory,
+            opt_memo_builder,
+            rng,
+            logger,
+        )?;
+
+        unsigned_tx_proposal.sign(&LocalRingSigner::from(from_account_key), rng)
+    }
+
+    /// Create an UnsignedTxProposal.
     ///
     /// # Arguments
     /// * `inputs` - UTXOs to spend, with membership proofs.
     /// * `rings` - A set of mixins for each input, with membership proofs.
     /// * `block_version` - The block version to target for this transaction
-    /// * `token_id` - The token id to transact in
-    /// * `fee` - Transaction fee, in picoMOB.
-    /// * `from_account_key` - Owns the inputs. Also the recipient of any
-    ///   change.
+    /// * `fee_token_id` - The token id of the fee
+    /// * `fee` - Transaction fee, in the smallest unit of the fee token.
+    /// * `from_key` - Keys of the account that owns the inputs. Also the
+    ///   recipient of any change.
     /// * `change_subaddress` - Subaddress for change recipient.
-    /// * `destinations` - Outputs of the transaction.
+    /// * `destinations` - Outputs of the transaction. Change is returned in
+    ///   each token id of the inputs.
+    /// * `scis` - Signed contingent inputs to add to the transaction, with
+    ///   membership proofs. The outputs their rules require are added too.
     /// * `tombstone_block` - Tombstone block of the transaciton.
     /// * `fog_pubkey_resolver` - Provides Fog key report, when Fog is enabled.
     /// * `opt_memo_builder` - Optional memo builder to use instead of the
     ///   default one (EmptyMemoBuilder).
     /// * `rng` - randomness
     /// * `logger` - Logger
-    fn build_tx_proposal(
+    fn build_unsigned_tx_proposal(
         inputs: &[(UnspentTxOut, TxOutMembershipProof)],
         rings: Vec<Vec<(TxOut, TxOutMembershipProof)>>,
         block_version: BlockVersion,
-        token_id: TokenId,
+        fee_token_id: TokenId,
         fee: u64,
-        from_account_key: &AccountKey,
+        from_key: &MonitorKey,
         change_subaddress: u64,
         destinations: &[Outlay],
+        scis: &[SignedContingentInput],
         tombstone_block: BlockIndex,
         fog_resolver_factory: &Arc<dyn Fn(&[FogUri]) -> Result<FPR, String> + Send + Sync>,
         opt_memo_builder: Option<Box<dyn MemoBuilder + 'static + Send + Sync>>,
         rng: &mut (impl RngCore + CryptoRng),
         logger: &Logger,
-    ) -> Result<TxProposal, Error> {
+    ) -> Result<UnsignedTxProposal, Error> {
         // Check that number of rings matches number of inputs.
         if rings.len() != inputs.len() {
             let err = format!(
@@ -861,15 +1640,16 @@ impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolve
             return Err(Error::TxBuild(err));
         }
 
-        // Check that we have at least one destination.
-        if destinations.is_empty() {
+        // Check that we have at least one destination, or a presigned input
+        // whose rules require outputs.
+        if destinations.is_empty() && scis.is_empty() {
             return Err(Error::TxBuild("Must have at least one destination".into()));
         }
 
         // Collect all required FogUris from public addresses, then pass to resolver
         // factory
         let fog_resolver = {
-            let change_address = from_account_key.subaddress(change_subaddress);
+            let change_address = from_key.subaddress(change_subaddress);
             let fog_uris = core::slice::from_ref(&change_address)
                 .iter()
                 .chain(destinations.iter().map(|x| &x.receiver))
@@ -883,7 +1663,7 @@ impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolve
         let memo_builder: Box<dyn MemoBuilder + Send + Sync> =
             opt_memo_builder.unwrap_or_else(|| Box::new(EmptyMemoBuilder::default()));
 
-        let fee_amount = Amount::new(fee, token_id);
+        let fee_amount = Amount::new(fee, fee_token_id);
         let mut tx_builder =
             TransactionBuilder::new_with_box(block_version, fee_amount, fog_resolver, memo_builder)
                 .map_err(|err| {
@@ -898,142 +1678,104 @@ impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolve
 
         // Add inputs to the tx.
         for (utxo, proof) in inputs {
-            let (mut ring, mut membership_proofs) = rings_and_proofs
+            let (ring, membership_proofs) = rings_and_proofs
                 .pop()
                 .ok_or_else(|| Error::TxBuild("rings_and_proofs was empty".to_string()))?;
-            assert_eq!(
-                ring.len(),
-                membership_proofs.len(),
-                "Each ring element must have a corresponding membership proof."
-            );
-
-            // Add the input to the ring.
-            let position_opt = ring.iter().position(|tx_out| *tx_out == utxo.tx_out);
-            let real_key_index = match position_opt {
-                Some(position) => {
-                    // The input is already present in the ring.
-                    // This could happen if ring elements are sampled randomly from the ledger.
-                    position
-                }
-                None => {
-                    // The input is not already in the ring.
-                    if ring.is_empty() {
-                        // Append the input and its proof of membership.
-                        ring.push(utxo.tx_out.clone());
-                        membership_proofs.push(proof.clone());
-                    } else {
-                        // Replace the first element of the ring.
-                        ring[0] = utxo.tx_out.clone();
-                        membership_proofs[0] = proof.clone();
-                    }
-                    // The real input is always the first element. This is safe because
-                    // TransactionBuilder sorts each ring.
-                    0
-                }
-            };
-
-            assert_eq!(
-                ring.len(),
-                membership_proofs.len(),
-                "Each ring element must have a corresponding membership proof."
-            );
-
-            let public_key = RistrettoPublic::try_from(&utxo.tx_out.public_key).unwrap();
-            let onetime_private_key = recover_onetime_private_key(
-                &public_key,
-                from_account_key.view_private_key(),
-                &from_account_key.subaddress_spend_private(utxo.subaddress_index),
-            );
-
-            let key_image = KeyImage::from(&onetime_private_key);
-            log::debug!(
-                logger,
-                "Adding input: ring {:?}, utxo index {:?}, key image {:?}, pubkey {:?}",
+            tx_builder.add_input(Self::build_input_credentials(
+                utxo,
+                proof,
                 ring,
-                real_key_index,
-                key_image,
-                public_key
-            );
+                membership_proofs,
+                from_key,
+                logger,
+            )?);
+        }
 
-            tx_builder.add_input(
-                InputCredentials::new(
-                    ring,
-                    membership_proofs,
-                    real_key_index,
-                    onetime_private_key,
-                    *from_account_key.view_private_key(),
-                )
-                .map_err(|_| Error::TxBuild("failed creating InputCredentials".into()))?,
-            );
+        // Add presigned inputs to the tx, along with the outputs their rules
+        // require.
+        for sci in scis {
+            tx_builder
+                .add_presigned_input(sci.clone())
+                .map_err(|err| Error::TxBuild(format!("failed adding presigned input: {}", err)))?;
         }
 
         // Add outputs to our destinations.
-        let mut total_value = 0;
         let mut tx_out_to_outlay_index = HashMap::default();
         let mut outlay_confirmation_numbers = Vec::default();
+        let mut outlay_shared_secrets = Vec::default();
         for (i, outlay) in destinations.iter().enumerate() {
-            // TODO (GH #1867): If you want to support mixed transactions, use
-            // outlay-specific token id here
             let amount = Amount {
                 value: outlay.value,
-                token_id,
+                token_id: outlay.token_id,
             };
             let TxOutContext {
                 tx_out,
                 confirmation,
-                ..
+                shared_secret,
             } = tx_builder
                 .add_output(amount, &outlay.receiver, rng)
                 .map_err(|err| Error::TxBuild(format!("failed adding output: {}", err)))?;
 
             tx_out_to_outlay_index.insert(tx_out, i);
             outlay_confirmation_numbers.push(confirmation);
-
-            total_value += outlay.value;
+            outlay_shared_secrets.push(shared_secret);
         }
 
-        // Figure out if we have change.
-        let input_value = inputs
-            .iter()
-            .fold(0, |acc, (utxo, _proof)| acc + utxo.value);
-        if total_value > input_value {
-            return Err(Error::InsufficientFunds);
+        // Figure out if we have change, in each token id: the value of the
+        // inputs, less the outlays and the fee.
+        let mut change_values = total_value_by_token_id(
+            inputs
+                .iter()
+                .map(|(utxo, _proof)| (TokenId::from(utxo.token_id), utxo.value))
+                .chain(scis.iter().map(|sci| {
+                    let amount = &sci.pseudo_output_amount;
+                    (TokenId::from(amount.token_id), amount.value)
+                })),
+        )?;
+        let spent_values = total_value_by_token_id(
+            destinations
+                .iter()
+                .map(|outlay| (outlay.token_id, outlay.value))
+                .chain(scis.iter().flat_map(|sci| {
+                    sci.required_output_amounts
+                        .iter()
+                        .map(|amount| (TokenId::from(amount.token_id), amount.value))
+                }))
+                .chain(once((fee_token_id, tx_builder.get_fee()))),
+        )?;
+        for (token_id, spent) in spent_values {
+            let change = change_values.entry(token_id).or_default();
+            *change = change.checked_sub(spent).ok_or(Error::InsufficientFunds)?;
         }
-        let change = input_value - total_value - tx_builder.get_fee();
 
         // If we do have nonzero change, add an output for that as well.
         // TODO (GH #1522): Should the exchange write destination memos?
         // If so then we must always write a change output, even if the change is zero
-        if change > 0 {
-            // TODO: If you want to support mixed transactions, use outlay-specific token id
-            // here
-            let change_amount = Amount {
-                value: change,
-                token_id,
-            };
-
-            let change_dest = ReservedSubaddresses::from_subaddress_index(
-                from_account_key,
-                Some(change_subaddress),
-                None,
-            );
-
+        let change_dest = ReservedSubaddresses {
+            primary_address: from_key.default_subaddress(),
+            change_subaddress: from_key.subaddress(change_subaddress),
+            gift_code_subaddress: from_key.subaddress(GIFT_CODE_SUBADDRESS_INDEX),
+        };
+        for (token_id, change) in change_values {
+            if change == 0 {
+                continue;
+            }
             tx_builder
-                .add_change_output(change_amount, &change_dest, rng)
+                .add_change_output(Amount::new(change, token_id), &change_dest, rng)
                 .map_err(|err| Error::TxBuild(format!("failed adding output (change): {}", err)))?;
         }
 
         // Set tombstone block.
         tx_builder.set_tombstone_block(tombstone_block);
 
-        // Build tx.
-        let tx = tx_builder
-            .build(&NoKeysRingSigner {}, rng)
+        // Build the unsigned tx.
+        let unsigned_tx = tx_builder
+            .build_unsigned()
             .map_err(|err| Error::TxBuild(format!("build tx failed: {}", err)))?;
 
         // Map each TxOut in the constructed transaction to its respective outlay.
-        let outlay_index_to_tx_out_index = tx
-            .prefix
+        let outlay_index_to_tx_out_index = unsigned_tx
+            .tx_prefix
             .outputs
             .iter()
             .enumerate()
@@ -1056,20 +1798,113 @@ impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolve
             }
         }
 
-        // Return the TxProposal
+        // Return the UnsignedTxProposal
         let selected_utxos = inputs
             .iter()
             .map(|(utxo, _membership_proof)| utxo.clone())
             .collect();
 
-        Ok(TxProposal {
+        Ok(UnsignedTxProposal {
             utxos: selected_utxos,
             outlays: destinations.to_vec(),
-            tx,
+            unsigned_tx,
             outlay_index_to_tx_out_index,
             outlay_confirmation_numbers,
+            outlay_shared_secrets,
+            change_subaddress,
         })
     }
+
+    /// Create the InputCredentials for spending a UTXO, hiding it in a ring of
+    /// mixins.
+    ///
+    /// # Arguments
+    /// * `utxo` - The UTXO to spend.
+    /// * `proof` - Membership proof of the UTXO.
+    /// * `ring` - Mixins for the UTXO.
+    /// * `membership_proofs` - Membership proofs of the mixins.
+    /// * `from_key` - Keys of the account that owns the UTXO.
+    /// * `logger` - Logger
+    fn build_input_credentials(
+        utxo: &UnspentTxOut,
+        proof: &TxOutMembershipProof,
+        mut ring: Vec<TxOut>,
+        mut membership_proofs: Vec<TxOutMembershipProof>,
+        from_key: &MonitorKey,
+        logger: &Logger,
+    ) -> Result<InputCredentials, Error> {
+        assert_eq!(
+            ring.len(),
+            membership_proofs.len(),
+            "Each ring element must have a corresponding membership proof."
+        );
+
+        // Add the input to the ring.
+        let position_opt = ring.iter().position(|tx_out| *tx_out == utxo.tx_out);
+        let real_key_index = match position_opt {
+            Some(position) => {
+                // The input is already present in the ring.
+                // This could happen if ring elements are sampled randomly from the ledger.
+                position
+            }
+            None => {
+                // The input is not already in the ring.
+                if ring.is_empty() {
+                    // Append the input and its proof of membership.
+                    ring.push(utxo.tx_out.clone());
+                    membership_proofs.push(proof.clone());
+                } else {
+                    // Replace the first element of the ring.
+                    ring[0] = utxo.tx_out.clone();
+                    membership_proofs[0] = proof.clone();
+                }
+                // The real input is always the first element. This is safe because
+                // TransactionBuilder sorts each ring.
+                0
+            }
+        };
+
+        assert_eq!(
+            ring.len(),
+            membership_proofs.len(),
+            "Each ring element must have a corresponding membership proof."
+        );
+
+        let public_key = RistrettoPublic::try_from(&utxo.tx_out.public_key).unwrap();
+        log::debug!(
+            logger,
+            "Adding input: ring {:?}, utxo index {:?}, subaddress index {}, pubkey {:?}",
+            ring,
+            real_key_index,
+            utxo.subaddress_index,
+            public_key
+        );
+
+        // Only the subaddress index is recorded here. The signer uses it to derive the
+        // onetime private key, so that building the transaction needs no spend keys.
+        InputCredentials::new(
+            ring,
+            membership_proofs,
+            real_key_index,
+            OneTimeKeyDeriveData::SubaddressIndex(utxo.subaddress_index),
+            *from_key.view_private_key(),
+        )
+        .map_err(|_| Error::TxBuild("failed creating InputCredentials".into()))
+    }
+}
+
+// Helper which sums values by token id, or returns an error on overflow
+fn total_value_by_token_id(
+    values: impl IntoIterator<Item = (TokenId, u64)>,
+) -> Result<BTreeMap<TokenId, u64>, Error> {
+    let mut totals = BTreeMap::new();
+    for (token_id, value) in values {
+        let total: &mut u64 = totals.entry(token_id).or_default();
+        *total = total.checked_add(value).ok_or_else(|| {
+            Error::TxBuild(format!("Total value of token id {} overflows", token_id))
+        })?;
+    }
+    Ok(totals)
 }
 
 // Helper which extracts FogUri from PublicAddress or returns None, or returns
@@ -1084,15 +1919,37 @@ fn extract_fog_uri(addr: &PublicAddress) -> Result<Option<FogUri>, Error> {
     }
 }
 
+/// Whether a signed contingent input may be filled partially. Swaps built by
+/// mobilecoind only fill signed contingent inputs in full.
+pub fn has_partial_fill_rules(sci: &SignedContingentInput) -> bool {
+    sci.tx_in
+        .input_rules
+        .as_ref()
+        .map(|rules| rules.has_partial_fill_rules())
+        .unwrap_or(false)
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
-    use mc_connection::{HardcodedCredentialsProvider, ThickClient};
+    use crate::{
+        test_utils::get_test_databases,
+        utxo_selection::{BranchAndBound, LargestFirst, Randomized, SmallestFirst},
+    };
+    use mc_common::logger::{test_with_logger, Logger};
+    use mc_connection::{Connection, HardcodedCredentialsProvider, PriorityStats, ThickClient};
+    use mc_connection_test_utils::{test_client_uri, MockBlockchainConnection};
+    use mc_consensus_enclave_api::FeeMap;
     use mc_crypto_keys::RistrettoPrivate;
     use mc_fog_report_validation::MockFogPubkeyResolver;
-    use mc_transaction_core::{constants::MILLIMOB_TO_PICOMOB, tokens::Mob, Amount, Token};
+    use mc_fog_report_validation_test_utils::MockFogResolver;
+    use mc_transaction_core::{
+        constants::MILLIMOB_TO_PICOMOB, tokens::Mob, validation::TransactionValidationError,
+        Amount, Token,
+    };
     use mc_util_from_random::FromRandom;
     use rand::{rngs::StdRng, SeedableRng};
+    use std::time::Duration;
 
     fn generate_utxos(num_utxos: usize) -> Vec<UnspentTxOut> {
         let mut rng: StdRng = SeedableRng::from_seed([1u8; 32]);
@@ -1124,6 +1981,36 @@ mod test {
             .collect()
     }
 
+    fn all_strategies() -> Vec<Box<dyn UtxoSelectionStrategy>> {
+        vec![
+            Box::new(SmallestFirst),
+            Box::new(LargestFirst),
+            Box::new(BranchAndBound::default()),
+            Box::new(Randomized),
+        ]
+    }
+
+    fn select_utxos_for_value(
+        utxo_selection_strategy: &dyn UtxoSelectionStrategy,
+        utxos: &[UnspentTxOut],
+        value: u64,
+        max_inputs: usize,
+    ) -> Result<Vec<UnspentTxOut>, Error> {
+        let mut rng: StdRng = SeedableRng::from_seed([2u8; 32]);
+        TransactionsManager::<ThickClient<HardcodedCredentialsProvider>, MockFogPubkeyResolver>::select_utxos_for_value(
+            utxo_selection_strategy,
+            Mob::ID,
+            utxos,
+            value,
+            max_inputs,
+            &mut rng,
+        )
+    }
+
+    fn total_value(utxos: &[UnspentTxOut]) -> u64 {
+        utxos.iter().map(|utxo| utxo.value).sum()
+    }
+
     #[test]
     fn test_select_utxos_for_value_selects_smallest_inputs() {
         let mut utxos = generate_utxos(5);
@@ -1135,20 +2022,14 @@ mod test {
         utxos[4].value = 1000;
 
         // Sending 300 should select 100 + 200 when 2 inputs are allowed.
-        let selected_utxos = TransactionsManager::<
-            ThickClient<HardcodedCredentialsProvider>,
-            MockFogPubkeyResolver,
-        >::select_utxos_for_value(Mob::ID, &utxos, 300, utxos.len())
-        .unwrap();
+        let selected_utxos =
+            select_utxos_for_value(&SmallestFirst, &utxos, 300, utxos.len()).unwrap();
 
         assert_eq!(selected_utxos, vec![utxos[0].clone(), utxos[1].clone()]);
 
         // Sending 301 should select 100 + 200 + 300 when 3 inputs are allowed.
-        let selected_utxos = TransactionsManager::<
-            ThickClient<HardcodedCredentialsProvider>,
-            MockFogPubkeyResolver,
-        >::select_utxos_for_value(Mob::ID, &utxos, 301, utxos.len())
-        .unwrap();
+        let selected_utxos =
+            select_utxos_for_value(&SmallestFirst, &utxos, 301, utxos.len()).unwrap();
 
         assert_eq!(
             selected_utxos,
@@ -1156,43 +2037,201 @@ mod test {
         );
 
         // Sending 301 should select 200 + 300 when only 2  inputs are allowed.
-        let selected_utxos = TransactionsManager::<
-            ThickClient<HardcodedCredentialsProvider>,
-            MockFogPubkeyResolver,
-        >::select_utxos_for_value(Mob::ID, &utxos, 301, 2)
-        .unwrap();
+        let selected_utxos = select_utxos_for_value(&SmallestFirst, &utxos, 301, 2).unwrap();
 
         assert_eq!(selected_utxos, vec![utxos[1].clone(), utxos[2].clone()]);
     }
 
+    #[test]
+    fn test_select_utxos_for_value_selects_largest_inputs() {
+        let mut utxos = generate_utxos(5);
+
+        utxos[0].value = 100;
+        utxos[1].value = 200;
+        utxos[2].value = 300;
+        utxos[3].value = 2000;
+        utxos[4].value = 1000;
+
+        // Sending 300 should select 2000.
+        let selected_utxos =
+            select_utxos_for_value(&LargestFirst, &utxos, 300, utxos.len()).unwrap();
+        assert_eq!(selected_utxos, vec![utxos[3].clone()]);
+
+        // Sending 2500 should select 2000 + 1000.
+        let selected_utxos =
+            select_utxos_for_value(&LargestFirst, &utxos, 2500, utxos.len()).unwrap();
+        assert_eq!(selected_utxos, vec![utxos[3].clone(), utxos[4].clone()]);
+    }
+
+    #[test]
+    fn test_select_utxos_for_value_branch_and_bound_finds_exact_match() {
+        let mut utxos = generate_utxos(5);
+
+        utxos[0].value = 100;
+        utxos[1].value = 200;
+        utxos[2].value = 300;
+        utxos[3].value = 2000;
+        utxos[4].value = 1000;
+
+        // 1300 can be made exactly from 1000 + 300.
+        let selected_utxos =
+            select_utxos_for_value(&BranchAndBound::default(), &utxos, 1300, utxos.len()).unwrap();
+        assert_eq!(selected_utxos, vec![utxos[4].clone(), utxos[2].clone()]);
+
+        // 3600 requires all five utxos, unless the number of inputs is capped.
+        let selected_utxos =
+            select_utxos_for_value(&BranchAndBound::default(), &utxos, 3600, utxos.len()).unwrap();
+        assert_eq!(total_value(&selected_utxos), 3600);
+        assert_eq!(selected_utxos.len(), 5);
+
+        // 1500 can be made exactly from 1000 + 300 + 200, but not when only two
+        // inputs are allowed, in which case we fall back to smallest-first.
+        let selected_utxos =
+            select_utxos_for_value(&BranchAndBound::default(), &utxos, 1500, 3).unwrap();
+        assert_eq!(
+            selected_utxos,
+            vec![utxos[4].clone(), utxos[2].clone(), utxos[1].clone()]
+        );
+
+        let selected_utxos =
+            select_utxos_for_value(&BranchAndBound::default(), &utxos, 1500, 2).unwrap();
+        assert_eq!(
+            selected_utxos,
+            select_utxos_for_value(&SmallestFirst, &utxos, 1500, 2).unwrap()
+        );
+
+        // 150 has no exact match at all, so we fall back to smallest-first.
+        let selected_utxos =
+            select_utxos_for_value(&BranchAndBound::default(), &utxos, 150, utxos.len()).unwrap();
+        assert_eq!(selected_utxos, vec![utxos[0].clone(), utxos[1].clone()]);
+    }
+
+    #[test]
+    fn test_select_utxos_for_value_branch_and_bound_many_utxos() {
+        // The search must not be limited by the stack depth, however many utxos
+        // it goes through.
+        let mut utxos = generate_utxos(100_000);
+        for (i, utxo) in utxos.iter_mut().enumerate() {
+            utxo.value = 2 * (i as u64 + 1);
+        }
+
+        // An odd value has no exact match, so the search runs out of steps and
+        // we fall back to smallest-first.
+        let selected_utxos =
+            select_utxos_for_value(&BranchAndBound::default(), &utxos, 1_000_001, utxos.len())
+                .unwrap();
+        assert_eq!(
+            selected_utxos,
+            select_utxos_for_value(&SmallestFirst, &utxos, 1_000_001, utxos.len()).unwrap()
+        );
+
+        // 749_994 is matched exactly, by the three largest utxos and 150_000,
+        // a quarter of the way down the list.
+        let selected_utxos =
+            select_utxos_for_value(&BranchAndBound::default(), &utxos, 749_994, utxos.len())
+                .unwrap();
+        assert_eq!(total_value(&selected_utxos), 749_994);
+        assert_eq!(selected_utxos.len(), 4);
+    }
+
+    #[test]
+    fn test_select_utxos_for_value_randomized_varies_selection() {
+        let mut utxos = generate_utxos(20);
+        for (i, utxo) in utxos.iter_mut().enumerate() {
+            utxo.value = 100 + i as u64;
+        }
+
+        // Different rng seeds should lead to different selections, each of which
+        // covers the requested value.
+        let selections: HashSet<Vec<u64>> = (0..10u8)
+            .map(|seed| {
+                let mut rng: StdRng = SeedableRng::from_seed([seed; 32]);
+                let selected_utxos = TransactionsManager::<
+                    ThickClient<HardcodedCredentialsProvider>,
+                    MockFogPubkeyResolver,
+                >::select_utxos_for_value(
+                    &Randomized, Mob::ID, &utxos, 250, 3, &mut rng
+                )
+                .unwrap();
+                assert!(total_value(&selected_utxos) >= 250);
+                assert!(selected_utxos.len() <= 3);
+                selected_utxos.iter().map(|utxo| utxo.value).collect()
+            })
+            .collect();
+        assert!(selections.len() > 1);
+    }
+
+    #[test]
+    fn test_select_utxos_for_value_covers_value_with_every_strategy() {
+        let mut utxos = generate_utxos(10);
+        for (i, utxo) in utxos.iter_mut().enumerate() {
+            utxo.value = (i as u64 + 1) * 10;
+        }
+
+        for strategy in all_strategies() {
+            for value in [1, 10, 55, 100, 270, 340, 550] {
+                for max_inputs in [4, 10] {
+                    match select_utxos_for_value(strategy.as_ref(), &utxos, value, max_inputs) {
+                        Ok(selected_utxos) => {
+                            assert!(!selected_utxos.is_empty());
+                            assert!(selected_utxos.len() <= max_inputs);
+                            assert!(total_value(&selected_utxos) >= value);
+                        }
+                        // The four largest utxos only sum up to 340.
+                        Err(Error::InsufficientFundsFragmentedUtxos) => {
+                            assert_eq!(max_inputs, 4);
+                            assert!(value > 340);
+                        }
+                        Err(err) => panic!("unexpected error {:?}", err),
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_select_utxos_for_value_ignores_other_token_ids() {
+        let mut utxos = generate_utxos(3);
+        utxos[0].value = 100;
+        utxos[1].value = 200;
+        utxos[2].value = 300;
+        utxos[2].token_id = 2;
+
+        for strategy in all_strategies() {
+            let selected_utxos =
+                select_utxos_for_value(strategy.as_ref(), &utxos, 300, utxos.len()).unwrap();
+            assert!(selected_utxos.iter().all(|utxo| utxo.token_id == *Mob::ID));
+            assert_eq!(total_value(&selected_utxos), 300);
+        }
+    }
+
     #[test]
     fn test_select_utxos_for_value_errors_if_too_many_inputs_are_needed() {
         let utxos = generate_utxos(10);
         // While we have enough utxos to sum to 5, if the input limit is 4 we should
         // fail.
-        match TransactionsManager::<ThickClient<HardcodedCredentialsProvider>, MockFogPubkeyResolver>::select_utxos_for_value(
-            Mob::ID, &utxos, 5, 4,
-        ) {
-            Err(Error::InsufficientFundsFragmentedUtxos) => {
-                // Expected.
-            }
-            _ => panic!("Did not get expected error"),
-        };
+        for strategy in all_strategies() {
+            match select_utxos_for_value(strategy.as_ref(), &utxos, 5, 4) {
+                Err(Error::InsufficientFundsFragmentedUtxos) => {
+                    // Expected.
+                }
+                _ => panic!("Did not get expected error"),
+            };
+        }
     }
 
     #[test]
     fn test_select_utxos_for_value_errors_if_insufficient_funds() {
         let utxos = generate_utxos(10);
-        // While we have enough utxos to sum to 5, if the input limit is 4 we should
-        // fail.
-        match TransactionsManager::<ThickClient<HardcodedCredentialsProvider>, MockFogPubkeyResolver>::select_utxos_for_value(
-            Mob::ID, &utxos, 50, 100,
-        ) {
-            Err(Error::InsufficientFunds) => {
-                // Expected.
-            }
-            _ => panic!("Did not get expected error"),
-        };
+        // We only have 10 utxos of value 1 each, so we cannot send 50.
+        for strategy in all_strategies() {
+            match select_utxos_for_value(strategy.as_ref(), &utxos, 50, 100) {
+                Err(Error::InsufficientFunds) => {
+                    // Expected.
+                }
+                _ => panic!("Did not get expected error"),
+            };
+        }
     }
 
     #[test]
@@ -1358,4 +2397,181 @@ mod test {
         );
         assert!(result.is_err());
     }
+
+    fn pool_stats(
+        max_transactions_per_block: u64,
+        num_txs: u64,
+        p50: u64,
+        p90: u64,
+        max: u64,
+    ) -> PoolStats {
+        PoolStats {
+            max_transactions_per_block,
+            priorities: PriorityStats {
+                num_txs,
+                p50,
+                p90,
+                max,
+            },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_get_target_confirmation_fee() {
+        let minimum_fee = Mob::MINIMUM_FEE;
+        let priority_unit = minimum_fee >> SMALLEST_MINIMUM_FEE_LOG2;
+
+        // Without pool stats, pay the minimum fee.
+        assert_eq!(
+            get_target_confirmation_fee(&[], minimum_fee, 1),
+            minimum_fee
+        );
+
+        // Uncongested pools only require the minimum fee.
+        let uncongested = pool_stats(5000, 100, 128, 256, 512);
+        assert_eq!(
+            get_target_confirmation_fee(&[uncongested.clone()], minimum_fee, 1),
+            minimum_fee
+        );
+
+        // Half of the pending transactions fit in the target blocks.
+        let stats = pool_stats(10, 30, 256, 1000, 5000);
+        assert_eq!(
+            get_target_confirmation_fee(&[stats.clone()], minimum_fee, 2),
+            257 * priority_unit
+        );
+
+        // A tenth of the pending transactions fit in the target blocks.
+        assert_eq!(
+            get_target_confirmation_fee(&[stats], minimum_fee, 1),
+            1001 * priority_unit
+        );
+        let stats = pool_stats(10, 150, 256, 1000, 5000);
+        assert_eq!(
+            get_target_confirmation_fee(&[stats], minimum_fee, 2),
+            1001 * priority_unit
+        );
+
+        // Fewer than a tenth fit, outbid everyone.
+        let congested = pool_stats(10, 1000, 256, 1000, 5000);
+        assert_eq!(
+            get_target_confirmation_fee(&[congested.clone()], minimum_fee, 2),
+            5001 * priority_unit
+        );
+
+        // The most congested node decides.
+        assert_eq!(
+            get_target_confirmation_fee(&[uncongested, congested], minimum_fee, 2),
+            5001 * priority_unit
+        );
+
+        // Fees are capped.
+        let stats = pool_stats(10, 1000, 256, 1000, 1_000_000);
+        assert_eq!(
+            get_target_confirmation_fee(&[stats], minimum_fee, 1),
+            MAX_TARGET_CONFIRMATION_FEE_MULTIPLIER * minimum_fee
+        );
+    }
+
+    /// A TransactionsManager whose first peer fails every proposed
+    /// transaction with the given error, and whose second peer accepts them.
+    /// The second peer is made slow, so that the failing peer is tried first.
+    fn transactions_manager_with_failing_peer(
+        propose_tx_error: fn() -> ConnectionError,
+        logger: Logger,
+    ) -> TransactionsManager<MockBlockchainConnection<LedgerDB>, MockFogResolver> {
+        let mut rng: StdRng = SeedableRng::from_seed([3u8; 32]);
+        let (ledger_db, mobilecoind_db) =
+            get_test_databases(BlockVersion::MAX, 1, &[], 2, logger.clone(), &mut rng);
+
+        let mut failing_peer = MockBlockchainConnection::new(
+            test_client_uri(1),
+            ledger_db.clone(),
+            0,
+            FeeMap::default(),
+        );
+        failing_peer.propose_tx_error = Some(propose_tx_error);
+        let slow_peer = MockBlockchainConnection::new(
+            test_client_uri(2),
+            ledger_db.clone(),
+            0,
+            FeeMap::default(),
+        );
+        let slow_peer_id = slow_peer.uri().host_and_port_responder_id().unwrap();
+
+        let peer_manager = ConnectionManager::new(vec![failing_peer, slow_peer], logger.clone());
+        peer_manager.record_success(&slow_peer_id, Duration::from_secs(2));
+
+        TransactionsManager::new(
+            ledger_db,
+            mobilecoind_db,
+            peer_manager,
+            Arc::new(|_| Ok(MockFogResolver::default())),
+            None,
+            logger,
+        )
+    }
+
+    fn num_proposed_txs(
+        manager: &TransactionsManager<MockBlockchainConnection<LedgerDB>, MockFogResolver>,
+        node_id: u32,
+    ) -> usize {
+        let responder_id = test_client_uri(node_id)
+            .host_and_port_responder_id()
+            .unwrap();
+        let conn = manager.peer_manager.conn(&responder_id).unwrap();
+        let num_proposed_txs = conn.read().proposed_txs.len();
+        num_proposed_txs
+    }
+
+    fn tx_proposal() -> TxProposal {
+        TxProposal {
+            utxos: vec![],
+            outlays: vec![],
+            tx: Tx::default(),
+            outlay_index_to_tx_out_index: HashMap::default(),
+            outlay_confirmation_numbers: vec![],
+        }
+    }
+
+    #[test_with_logger]
+    fn test_submit_tx_proposal_fails_over_to_next_peer(logger: Logger) {
+        let manager = transactions_manager_with_failing_peer(
+            || ConnectionError::Other("unavailable".to_owned()),
+            logger,
+        );
+
+        manager.submit_tx_proposal(&tx_proposal()).unwrap();
+        assert_eq!(num_proposed_txs(&manager, 2), 1);
+
+        // The failure counts against the failing peer.
+        let scores = manager.peer_manager.health_scores();
+        let failing_peer_id = test_client_uri(1).host_and_port_responder_id().unwrap();
+        assert!(scores[&failing_peer_id].error_rate > 0.0);
+    }
+
+    #[test_with_logger]
+    fn test_submit_tx_proposal_does_not_retry_rejected_tx(logger: Logger) {
+        let manager = transactions_manager_with_failing_peer(
+            || {
+                ConnectionError::TransactionValidation(
+                    TransactionValidationError::ContainsSpentKeyImage,
+                )
+            },
+            logger,
+        );
+
+        assert!(matches!(
+            manager.submit_tx_proposal(&tx_proposal()),
+            Err(Error::Connection(RetryError::Operation {
+                error: ConnectionError::TransactionValidation(
+                    TransactionValidationError::ContainsSpentKeyImage
+                ),
+                ..
+            }))
+        ));
+        // The other peer was not asked.
+        assert_eq!(num_proposed_txs(&manager, 2), 0);
+    }
 }
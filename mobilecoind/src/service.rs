@@ -9,20 +9,27 @@
 use crate::{
     database::Database,
     error::Error,
-    monitor_store::{MonitorData, MonitorId},
-    payments::{Outlay, TransactionsManager, TxProposal},
+    monitor_events::{MonitorEvent, MonitorEventCursor, MonitorEventNotifier},
+    monitor_store::{MonitorData, MonitorId, MonitorKey},
+    payments::{has_partial_fill_rules, spent_key_image, Outlay, TransactionsManager, TxProposal},
+    processed_block_store::ProcessedTxOut,
     sync::SyncThread,
+    utxo_selection::{SmallestFirst, UtxoSelectionStrategy},
     utxo_store::{UnspentTxOut, UtxoId},
 };
 use bip39::{Language, Mnemonic, MnemonicType};
-use grpcio::{EnvBuilder, RpcContext, RpcStatus, RpcStatusCode, ServerBuilder, UnarySink};
+use futures::{channel::mpsc, executor::block_on, FutureExt, SinkExt, StreamExt, TryFutureExt};
+use grpcio::{
+    EnvBuilder, RpcContext, RpcStatus, RpcStatusCode, ServerBuilder, ServerStreamingSink,
+    UnarySink, WriteFlags,
+};
 use mc_account_keys::{
-    burn_address, AccountKey, PublicAddress, RootIdentity, DEFAULT_SUBADDRESS_INDEX,
+    burn_address, AccountKey, PublicAddress, RootIdentity, ViewAccountKey, DEFAULT_SUBADDRESS_INDEX,
 };
 use mc_account_keys_slip10::Slip10KeyGenerator;
 use mc_common::{
     logger::{log, Logger},
-    HashMap,
+    HashMap, HashSet,
 };
 use mc_connection::{BlockInfo, BlockchainConnection, UserTxConnection};
 use mc_crypto_keys::{CompressedRistrettoPublic, RistrettoPublic};
@@ -39,9 +46,12 @@ use mc_transaction_core::{
     onetime_keys::recover_onetime_private_key,
     ring_signature::KeyImage,
     tx::{TxOut, TxOutConfirmationNumber, TxOutMembershipProof},
-    TokenId,
+    Amount, SignedContingentInput, TokenId,
+};
+use mc_transaction_std::{
+    BurnRedemptionMemo, BurnRedemptionMemoBuilder, MemoBuilder, RTHMemoBuilder,
+    SenderMemoCredential,
 };
-use mc_transaction_std::{BurnRedemptionMemo, BurnRedemptionMemoBuilder};
 use mc_util_from_random::FromRandom;
 use mc_util_grpc::{
     rpc_internal_error, rpc_invalid_arg_error, rpc_logger, send_result, AdminService,
@@ -49,7 +59,20 @@ use mc_util_grpc::{
 };
 use mc_watcher::watcher_db::WatcherDB;
 use protobuf::{ProtobufEnum, RepeatedField};
-use std::sync::{Arc, Mutex, RwLock};
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex, RwLock},
+    thread,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// The number of monitor events that can be buffered for a subscriber before
+/// the streaming thread blocks waiting for the client to catch up.
+const MONITOR_EVENTS_STREAM_BUFFER_SIZE: usize = 128;
+
+/// The maximal amount of time a monitor event stream waits for a notification
+/// from the sync thread before checking for new events on its own.
+const MONITOR_EVENTS_POLL_INTERVAL: Duration = Duration::from_secs(1);
 
 pub struct Service {
     /// Sync thread.
@@ -73,6 +96,8 @@ impl Service {
         num_workers: Option<usize>,
         logger: Logger,
     ) -> Self {
+        let monitor_event_notifier = MonitorEventNotifier::default();
+
         let sync_thread = if mobilecoind_db.is_db_encrypted() {
             log::info!(logger, "Db encryption enabled, sync task would start once password is provided via the API.");
             Arc::new(Mutex::new(None))
@@ -81,6 +106,7 @@ impl Service {
             Arc::new(Mutex::new(Some(SyncThread::start(
                 ledger_db.clone(),
                 mobilecoind_db.clone(),
+                monitor_event_notifier.clone(),
                 num_workers,
                 logger.clone(),
             ))))
@@ -89,6 +115,7 @@ impl Service {
         let start_sync_thread = {
             let ledger_db = ledger_db.clone();
             let mobilecoind_db = mobilecoind_db.clone();
+            let monitor_event_notifier = monitor_event_notifier.clone();
             let logger = logger.clone();
             let sync_thread = sync_thread.clone();
             Arc::new(move || {
@@ -98,6 +125,7 @@ impl Service {
                 *sync_thread = Some(SyncThread::start(
                     ledger_db.clone(),
                     mobilecoind_db.clone(),
+                    monitor_event_notifier.clone(),
                     num_workers,
                     logger.clone(),
                 ));
@@ -110,6 +138,7 @@ impl Service {
             mobilecoind_db,
             watcher_db,
             network_state,
+            monitor_event_notifier,
             start_sync_thread,
             logger.clone(),
         );
@@ -167,6 +196,7 @@ pub struct ServiceApi<
     mobilecoind_db: Database,
     watcher_db: Option<WatcherDB>,
     network_state: Arc<RwLock<PollingNetworkState<T>>>,
+    monitor_event_notifier: MonitorEventNotifier,
     start_sync_thread: Arc<dyn Fn() + Send + Sync>,
     logger: Logger,
 }
@@ -181,6 +211,7 @@ impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolve
             mobilecoind_db: self.mobilecoind_db.clone(),
             watcher_db: self.watcher_db.clone(),
             network_state: self.network_state.clone(),
+            monitor_event_notifier: self.monitor_event_notifier.clone(),
             start_sync_thread: self.start_sync_thread.clone(),
             logger: self.logger.clone(),
         }
@@ -196,6 +227,7 @@ impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolve
         mobilecoind_db: Database,
         watcher_db: Option<WatcherDB>,
         network_state: Arc<RwLock<PollingNetworkState<T>>>,
+        monitor_event_notifier: MonitorEventNotifier,
         start_sync_thread: Arc<dyn Fn() + Send + Sync>,
         logger: Logger,
     ) -> Self {
@@ -205,6 +237,7 @@ impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolve
             mobilecoind_db,
             watcher_db,
             network_state,
+            monitor_event_notifier,
             start_sync_thread,
             logger,
         }
@@ -222,6 +255,27 @@ impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolve
             .collect()
     }
 
+    // Convert the outlays of a request. Unless multi_token_outlays is set, they
+    // are all in the token id of the request.
+    fn get_outlays(
+        &self,
+        outlay_list: &[api::Outlay],
+        token_id: u64,
+        multi_token_outlays: bool,
+    ) -> Result<Vec<Outlay>, RpcStatus> {
+        outlay_list
+            .iter()
+            .map(|outlay_proto| {
+                let mut outlay = Outlay::try_from(outlay_proto)
+                    .map_err(|err| rpc_internal_error("outlay.try_from", err, &self.logger))?;
+                if !multi_token_outlays {
+                    outlay.token_id = TokenId::from(token_id);
+                }
+                Ok(outlay)
+            })
+            .collect()
+    }
+
     fn get_version_impl(
         &self,
         _request: api::Empty,
@@ -235,21 +289,45 @@ impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolve
         &mut self,
         request: api::AddMonitorRequest,
     ) -> Result<api::AddMonitorResponse, RpcStatus> {
-        // Get the AccountKey from the GRPC request.
-        let proto_account_key = request.account_key.as_ref().ok_or_else(|| {
-            RpcStatus::with_message(RpcStatusCode::INVALID_ARGUMENT, "account_key".into())
-        })?;
-        let account_key = AccountKey::try_from(proto_account_key)
-            .map_err(|err| rpc_internal_error("account_key.try_from", err, &self.logger))?;
+        // Populate a new `MonitorData` instance, using either the AccountKey or the
+        // ViewOnlyAccountKey from the GRPC request.
+        let data = match (
+            request.account_key.as_ref(),
+            request.view_only_account_key.as_ref(),
+        ) {
+            (Some(proto_account_key), None) => {
+                let account_key = AccountKey::try_from(proto_account_key)
+                    .map_err(|err| rpc_internal_error("account_key.try_from", err, &self.logger))?;
+
+                MonitorData::new(
+                    account_key,
+                    request.first_subaddress,
+                    request.num_subaddresses,
+                    request.first_block,
+                    &request.name,
+                )
+            }
+            (None, Some(proto_view_only_account_key)) => {
+                let view_account_key = ViewAccountKey::try_from(proto_view_only_account_key)
+                    .map_err(|err| {
+                        rpc_internal_error("view_only_account_key.try_from", err, &self.logger)
+                    })?;
 
-        // Populate a new `MonitorData` instance.
-        let data = MonitorData::new(
-            account_key,
-            request.first_subaddress,
-            request.num_subaddresses,
-            request.first_block,
-            &request.name,
-        )
+                MonitorData::new_view_only(
+                    view_account_key,
+                    request.first_subaddress,
+                    request.num_subaddresses,
+                    request.first_block,
+                    &request.name,
+                )
+            }
+            _ => {
+                return Err(RpcStatus::with_message(
+                    RpcStatusCode::INVALID_ARGUMENT,
+                    "exactly one of account_key and view_only_account_key must be set".into(),
+                ));
+            }
+        }
         .map_err(|err| rpc_internal_error("monitor_data.new", err, &self.logger))?;
 
         // Insert into database. Return the id and flag if the monitor already existed.
@@ -318,7 +396,14 @@ impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolve
             })?;
 
         let mut status = api::MonitorStatus::new();
-        status.set_account_key(mc_api::external::AccountKey::from(&data.account_key));
+        match data.monitor_key() {
+            MonitorKey::AccountKey(account_key) => {
+                status.set_account_key(mc_api::external::AccountKey::from(account_key));
+            }
+            MonitorKey::ViewAccountKey(view_account_key) => {
+                status.set_view_only_account_key(api::ViewOnlyAccountKey::from(view_account_key));
+            }
+        }
         status.set_first_subaddress(data.first_subaddress);
         status.set_num_subaddresses(data.num_subaddresses);
         status.set_first_block(data.first_block);
@@ -360,6 +445,125 @@ impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolve
         Ok(response)
     }
 
+    fn subscribe_monitor_events_impl(
+        &mut self,
+        ctx: RpcContext,
+        request: api::SubscribeMonitorEventsRequest,
+        sink: ServerStreamingSink<api::MonitorEvent>,
+    ) {
+        let logger = rpc_logger(&ctx, &self.logger);
+
+        let receiver = match self.start_monitor_events_thread(request, &logger) {
+            Ok(receiver) => receiver,
+            Err(err) => {
+                ctx.spawn(
+                    sink.fail(err)
+                        .map_err(move |err| log::error!(logger, "failed to reply: {}", err))
+                        .map(|_| ()),
+                );
+                return;
+            }
+        };
+
+        ctx.spawn(
+            forward_monitor_events(receiver, sink)
+                .map_err(move |err| log::debug!(logger, "monitor events stream ended: {}", err))
+                .map(|_| ()),
+        );
+    }
+
+    /// Start a thread that produces the events of a given monitor into a
+    /// bounded channel, and return the receiving end of that channel.
+    /// Since the channel is bounded, a slow client causes the thread to block
+    /// rather than buffer an unbounded number of events in memory.
+    fn start_monitor_events_thread(
+        &self,
+        request: api::SubscribeMonitorEventsRequest,
+        logger: &Logger,
+    ) -> Result<mpsc::Receiver<Result<api::MonitorEvent, RpcStatus>>, RpcStatus> {
+        let monitor_id = MonitorId::try_from(&request.monitor_id)
+            .map_err(|err| rpc_internal_error("monitor_id.try_from.bytes", err, logger))?;
+
+        // We will use the monitor's keys to compute the Address Code of processed
+        // TxOuts.
+        let monitor_key = self
+            .mobilecoind_db
+            .get_monitor_data(&monitor_id)
+            .map_err(|err| rpc_internal_error("mobilecoind_db.get_monitor_data", err, logger))?
+            .monitor_key()
+            .clone();
+
+        let mut cursor =
+            MonitorEventCursor::new(&self.mobilecoind_db, &monitor_id, request.from_block)
+                .map_err(|err| rpc_internal_error("MonitorEventCursor.new", err, logger))?;
+
+        let (mut sender, receiver) = mpsc::channel(MONITOR_EVENTS_STREAM_BUFFER_SIZE);
+
+        let ledger_db = self.ledger_db.clone();
+        let mobilecoind_db = self.mobilecoind_db.clone();
+        let notifier = self.monitor_event_notifier.clone();
+        let thread_logger = logger.clone();
+
+        thread::Builder::new()
+            .name(format!("monitor_events_{}", monitor_id))
+            .spawn(move || {
+                log::debug!(
+                    thread_logger,
+                    "Streaming events for monitor {} starting at block {}",
+                    monitor_id,
+                    cursor.next_block()
+                );
+
+                // Stop once the client goes away.
+                while !sender.is_closed() {
+                    // Grab the notification generation before looking at the database, so
+                    // that a block processed while we are busy wakes us up immediately.
+                    let generation = notifier.generation();
+
+                    let events = match cursor.next_events(&ledger_db, &mobilecoind_db) {
+                        Ok(events) => events,
+
+                        // The monitor was removed, there is nothing more to stream.
+                        Err(Error::MonitorIdNotFound) => break,
+
+                        Err(err) => {
+                            let status = rpc_internal_error(
+                                "monitor_event_cursor.next_events",
+                                err,
+                                &thread_logger,
+                            );
+                            let _ = block_on(sender.send(Err(status)));
+                            break;
+                        }
+                    };
+
+                    if events.is_empty() {
+                        notifier.wait_for_change(generation, MONITOR_EVENTS_POLL_INTERVAL);
+                        continue;
+                    }
+
+                    for event in events.iter() {
+                        let item =
+                            monitor_event_to_api(&monitor_id, &monitor_key, event).map_err(|err| {
+                                rpc_internal_error("monitor_event_to_api", err, &thread_logger)
+                            });
+                        if block_on(sender.send(item)).is_err() {
+                            break;
+                        }
+                    }
+                }
+
+                log::debug!(
+                    thread_logger,
+                    "Stopped streaming events for monitor {}",
+                    monitor_id
+                );
+            })
+            .map_err(|err| rpc_internal_error("thread.spawn", err, logger))?;
+
+        Ok(receiver)
+    }
+
     fn generate_root_entropy_impl(
         &mut self,
         _request: api::Empty,
@@ -450,7 +654,7 @@ impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolve
         }
 
         // Get the subaddress.
-        let subaddress = data.account_key.subaddress(request.subaddress_index);
+        let subaddress = data.monitor_key().subaddress(request.subaddress_index);
 
         // Also build the b58 wrapper
         let mut wrapper = api::printable::PrintableWrapper::new();
@@ -486,6 +690,9 @@ impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolve
             response.set_value(payment_request.get_value());
             response.set_memo(payment_request.get_memo().to_string());
             response.set_token_id(payment_request.get_token_id());
+            response.set_payment_request_id(payment_request.get_payment_request_id());
+            response.set_expiration_timestamp(payment_request.get_expiration_timestamp());
+            response.set_line_items(payment_request.get_line_items().into());
             Ok(response)
         } else if wrapper.has_public_address() {
             let public_address = wrapper.get_public_address();
@@ -509,11 +716,39 @@ impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolve
         let receiver = PublicAddress::try_from(request.get_receiver())
             .map_err(|err| rpc_internal_error("PublicAddress.try_from", err, &self.logger))?;
 
+        if payment_request_expired(request.get_expiration_timestamp()) {
+            return Err(rpc_invalid_arg_error(
+                "expiration_timestamp",
+                "payment request has already expired",
+                &self.logger,
+            ));
+        }
+
+        // Line items, if any, must add up to the requested value.
+        if !request.get_line_items().is_empty() {
+            let line_items_value = request
+                .get_line_items()
+                .iter()
+                .try_fold(0u64, |total, line_item| {
+                    total.checked_add(line_item.get_value())
+                });
+            if line_items_value != Some(request.get_value()) {
+                return Err(rpc_invalid_arg_error(
+                    "line_items",
+                    "line item values do not add up to value",
+                    &self.logger,
+                ));
+            }
+        }
+
         let mut payment_request = api::printable::PaymentRequest::new();
         payment_request.set_public_address((&receiver).into());
         payment_request.set_value(request.get_value());
         payment_request.set_memo(request.get_memo().to_string());
         payment_request.set_token_id(request.get_token_id());
+        payment_request.set_payment_request_id(request.get_payment_request_id());
+        payment_request.set_expiration_timestamp(request.get_expiration_timestamp());
+        payment_request.set_line_items(request.get_line_items().into());
 
         let mut wrapper = api::printable::PrintableWrapper::new();
         wrapper.set_payment_request(payment_request);
@@ -737,6 +972,70 @@ impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolve
         Ok(response)
     }
 
+    fn parse_swap_code_impl(
+        &mut self,
+        request: api::ParseSwapCodeRequest,
+    ) -> Result<api::ParseSwapCodeResponse, RpcStatus> {
+        let wrapper =
+            api::printable::PrintableWrapper::b58_decode(request.get_b58_code().to_string())
+                .map_err(|err| {
+                    rpc_internal_error("PrintableWrapper_b58_decode", err, &self.logger)
+                })?;
+
+        if !wrapper.has_signed_contingent_input() {
+            return Err(RpcStatus::with_message(
+                RpcStatusCode::INVALID_ARGUMENT,
+                "has_signed_contingent_input".into(),
+            ));
+        }
+
+        let sci = SignedContingentInput::try_from(wrapper.get_signed_contingent_input()).map_err(
+            |err| rpc_invalid_arg_error("SignedContingentInput.try_from", err, &self.logger),
+        )?;
+        sci.validate()
+            .map_err(|err| rpc_invalid_arg_error("sci.validate", err, &self.logger))?;
+        if has_partial_fill_rules(&sci) {
+            return Err(rpc_invalid_arg_error(
+                "sci",
+                "partial fill offers are not supported",
+                &self.logger,
+            ));
+        }
+
+        // Check whether the offer can still be filled.
+        let spent = self
+            .ledger_db
+            .contains_key_image(&sci.key_image())
+            .map_err(|err| rpc_internal_error("ledger_db.contains_key_image", err, &self.logger))?;
+        let num_blocks = self
+            .ledger_db
+            .num_blocks()
+            .map_err(|err| rpc_internal_error("ledger_db.num_blocks", err, &self.logger))?;
+        let max_tombstone_block = sci
+            .tx_in
+            .input_rules
+            .as_ref()
+            .map(|rules| rules.max_tombstone_block)
+            .unwrap_or(0);
+
+        let (offered_amount, requested_amounts) = swap_amounts(&sci);
+
+        let mut response = api::ParseSwapCodeResponse::new();
+        response.set_sci(wrapper.get_signed_contingent_input().clone());
+        response.set_offered_amount((&offered_amount).into());
+        response.set_requested_amounts(
+            requested_amounts
+                .iter()
+                .map(api::TokenAmount::from)
+                .collect(),
+        );
+        response.set_max_tombstone_block(max_tombstone_block);
+        response.set_block_version(sci.block_version);
+        response.set_spent(spent);
+        response.set_expired(max_tombstone_block != 0 && num_blocks >= max_tombstone_block);
+        Ok(response)
+    }
+
     /// Get mixins
     fn get_mixins_impl(
         &mut self,
@@ -840,6 +1139,14 @@ impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolve
             ));
         }
 
+        // Get the list of outlays.
+        let outlays = self.get_outlays(
+            request.get_outlay_list(),
+            request.token_id,
+            request.multi_token_outlays,
+        )?;
+        let input_token_ids = input_token_ids(request.token_id, &outlays);
+
         // Get the list of potential inputs passed to.
         let input_list: Vec<UnspentTxOut> = request
             .get_input_list()
@@ -852,7 +1159,7 @@ impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolve
                 })?;
 
                 // Verify token id matches.
-                if utxo.token_id != request.token_id {
+                if !input_token_ids.contains(&utxo.token_id) {
                     return Err(RpcStatus::with_message(
                         RpcStatusCode::INVALID_ARGUMENT,
                         format!("input_list[{}].token_id", i),
@@ -883,15 +1190,43 @@ impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolve
             })
             .collect::<Result<Vec<UnspentTxOut>, RpcStatus>>()?;
 
-        // Get the list of outlays.
-        let outlays: Vec<Outlay> = request
-            .get_outlay_list()
-            .iter()
-            .map(|outlay_proto| {
-                Outlay::try_from(outlay_proto)
-                    .map_err(|err| rpc_internal_error("outlay.try_from", err, &self.logger))
-            })
-            .collect::<Result<Vec<Outlay>, RpcStatus>>()?;
+        let utxo_selection_strategy: Box<dyn UtxoSelectionStrategy> =
+            request.get_utxo_selection_strategy().into();
+
+        let mut response = api::GenerateTxResponse::new();
+
+        // View-only monitors cannot sign, so they get an unsigned transaction that
+        // needs to be signed offline and then handed back to SubmitTx.
+        if sender_monitor_data.is_view_only() {
+            let unsigned_tx_proposal = self
+                .transactions_manager
+                .build_unsigned_transaction(
+                    &sender_monitor_id,
+                    TokenId::from(request.token_id),
+                    request.change_subaddress,
+                    &input_list,
+                    &outlays,
+                    &self.get_last_block_infos(),
+                    request.fee,
+                    request.tombstone,
+                    None,
+                    utxo_selection_strategy.as_ref(),
+                )
+                .map_err(|err| {
+                    rpc_internal_error(
+                        "transactions_manager.build_unsigned_transaction",
+                        err,
+                        &self.logger,
+                    )
+                })?;
+
+            let proto_unsigned_tx_proposal =
+                api::UnsignedTxProposal::try_from(&unsigned_tx_proposal).map_err(|err| {
+                    rpc_internal_error("unsigned_tx_proposal.try_from", err, &self.logger)
+                })?;
+            response.set_unsigned_tx_proposal(proto_unsigned_tx_proposal);
+            return Ok(response);
+        }
 
         // Attempt to construct a transaction.
         let tx_proposal = self
@@ -906,13 +1241,13 @@ impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolve
                 request.fee,
                 request.tombstone,
                 None,
+                utxo_selection_strategy.as_ref(),
             )
             .map_err(|err| {
                 rpc_internal_error("transactions_manager.build_transaction", err, &self.logger)
             })?;
 
         // Success.
-        let mut response = api::GenerateTxResponse::new();
         response.set_tx_proposal((&tx_proposal).into());
         Ok(response)
     }
@@ -1084,6 +1419,7 @@ impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolve
         let outlays = vec![Outlay {
             value: request.get_burn_amount(),
             receiver: burn_address(),
+            token_id: TokenId::from(request.token_id),
         }];
 
         // Create memo builder.
@@ -1113,6 +1449,7 @@ impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolve
                 request.fee,
                 request.tombstone,
                 Some(Box::new(memo_builder)),
+                &SmallestFirst,
             )
             .map_err(|err| {
                 rpc_internal_error("transactions_manager.build_transaction", err, &self.logger)
@@ -1124,115 +1461,355 @@ impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolve
         Ok(response)
     }
 
-    fn generate_transfer_code_tx_impl(
+    fn generate_swap_impl(
         &mut self,
-        request: api::GenerateTransferCodeTxRequest,
-    ) -> Result<api::GenerateTransferCodeTxResponse, RpcStatus> {
-        // Generate entropy.
-        let mnemonic_response = self.generate_mnemonic_impl(api::Empty::new())?;
-        let mnemonic_str = mnemonic_response.get_mnemonic().to_string();
-        let bip39_entropy = mnemonic_response.get_bip39_entropy();
-
-        // Generate a new account using this mnemonic.
-        let mut account_key_request = api::GetAccountKeyFromMnemonicRequest::new();
-        account_key_request.set_mnemonic(mnemonic_str);
-
-        let account_key_response = self.get_account_key_from_mnemonic_impl(account_key_request)?;
-        let account_key = AccountKey::try_from(account_key_response.get_account_key())
-            .map_err(|err| rpc_internal_error("account_key.try_from", err, &self.logger))?;
-
-        // The outlay we are sending the money to.
-        let outlay = Outlay {
-            receiver: account_key.default_subaddress(),
-            value: request.value,
-        };
-
-        // Generate transaction.
-        let mut generate_tx_request = api::GenerateTxRequest::new();
-        generate_tx_request.set_sender_monitor_id(request.get_sender_monitor_id().to_vec());
-        generate_tx_request.set_change_subaddress(request.change_subaddress);
-        generate_tx_request.set_input_list(RepeatedField::from_vec(request.input_list.to_vec()));
-        generate_tx_request.set_outlay_list(RepeatedField::from_vec(vec![(&outlay).into()]));
-        generate_tx_request.set_fee(request.fee);
-        generate_tx_request.set_tombstone(request.tombstone);
-        generate_tx_request.set_token_id(request.token_id);
-
-        let mut generate_tx_response = self.generate_tx_impl(generate_tx_request)?;
-        let tx_proposal = generate_tx_response.take_tx_proposal();
-
-        // Grab the public key of the relevant tx out.
-        let proto_tx_public_key = {
-            // We expect only a single outlay.
-            if tx_proposal.get_outlay_index_to_tx_out_index().len() != 1 {
-                return Err(RpcStatus::with_message(
-                    RpcStatusCode::INTERNAL,
-                    format!(
-                        "outlay_index_to_tx_out_index contains {} elements, was expecting 1",
-                        tx_proposal.get_outlay_index_to_tx_out_index().len()
-                    ),
-                ));
-            }
-
-            // Get the TxOut index of our single outlay.
-            let tx_out_index = tx_proposal
-                .get_outlay_index_to_tx_out_index()
-                .get(&0)
-                .ok_or_else(|| {
-                    RpcStatus::with_message(
-                        RpcStatusCode::INTERNAL,
-                        "outlay_index_to_tx_out_index doesn't contain index 0".to_owned(),
-                    )
-                })?;
+        request: api::GenerateSwapRequest,
+    ) -> Result<api::GenerateSwapResponse, RpcStatus> {
+        // Get sender monitor id from request.
+        let sender_monitor_id = MonitorId::try_from(&request.sender_monitor_id)
+            .map_err(|err| rpc_internal_error("monitor_id.try_from.bytes", err, &self.logger))?;
 
-            // Get the TxOut
-            let tx_out = tx_proposal
-                .get_tx()
-                .get_prefix()
-                .get_outputs()
-                .get(*tx_out_index as usize)
-                .ok_or_else(|| {
-                    RpcStatus::with_message(
-                        RpcStatusCode::INTERNAL,
-                        format!("tx out index {} not found", tx_out_index),
-                    )
-                })?;
+        // Get monitor data for this monitor.
+        let sender_monitor_data = self
+            .mobilecoind_db
+            .get_monitor_data(&sender_monitor_id)
+            .map_err(|err| {
+                rpc_internal_error("mobilecoind_db.get_monitor_data", err, &self.logger)
+            })?;
 
-            // Get the public key
-            tx_out.get_public_key().clone()
-        };
+        // Check that receiver_subaddress is covered by this monitor.
+        if !sender_monitor_data
+            .subaddress_indexes()
+            .contains(&request.receiver_subaddress)
+        {
+            return Err(RpcStatus::with_message(
+                RpcStatusCode::INVALID_ARGUMENT,
+                "receiver_subaddress".into(),
+            ));
+        }
 
-        let tx_public_key = RistrettoPublic::try_from(&proto_tx_public_key)
-            .map_err(|err| rpc_internal_error("ristretto_public.try_from", err, &self.logger))?;
+        let utxo = UnspentTxOut::try_from(request.get_input())
+            .map_err(|err| rpc_internal_error("unspent_tx_out.try_from", err, &self.logger))?;
 
-        let mut transfer_payload = api::printable::TransferPayload::new();
-        transfer_payload.set_bip39_entropy(bip39_entropy.to_vec());
-        transfer_payload.set_tx_out_public_key((&tx_public_key).into());
-        transfer_payload.set_memo(request.get_memo().to_string());
+        // Verify the input belongs to the monitor.
+        let subaddress_id = self
+            .mobilecoind_db
+            .get_subaddress_id_by_utxo_id(&UtxoId::from(&utxo))
+            .map_err(|err| {
+                rpc_internal_error(
+                    "mobilecoind_db.get_subaddress_id_by_utxo_id",
+                    err,
+                    &self.logger,
+                )
+            })?;
+        if subaddress_id.monitor_id != sender_monitor_id {
+            return Err(RpcStatus::with_message(
+                RpcStatusCode::INVALID_ARGUMENT,
+                "input.monitor_id".into(),
+            ));
+        }
 
-        let mut transfer_wrapper = api::printable::PrintableWrapper::new();
-        transfer_wrapper.set_transfer_payload(transfer_payload);
+        let sci = self
+            .transactions_manager
+            .build_signed_contingent_input(
+                &sender_monitor_id,
+                request.receiver_subaddress,
+                &utxo,
+                Amount::new(
+                    request.counter_value,
+                    TokenId::from(request.counter_token_id),
+                ),
+                &self.get_last_block_infos(),
+                request.tombstone,
+            )
+            .map_err(|err| {
+                rpc_internal_error(
+                    "transactions_manager.build_signed_contingent_input",
+                    err,
+                    &self.logger,
+                )
+            })?;
 
-        let b58_code = transfer_wrapper
+        let proto_sci = api::external::SignedContingentInput::from(&sci);
+        let mut wrapper = api::printable::PrintableWrapper::new();
+        wrapper.set_signed_contingent_input(proto_sci.clone());
+        let b58_code = wrapper
             .b58_encode()
             .map_err(|err| rpc_internal_error("b58_encode", err, &self.logger))?;
 
-        // Construct response.
-        let mut response = api::GenerateTransferCodeTxResponse::new();
-        response.set_tx_proposal(tx_proposal);
-        response.set_bip39_entropy(bip39_entropy.to_vec());
-        response.set_tx_public_key(proto_tx_public_key);
-        response.set_memo(request.get_memo().to_string());
+        let mut response = api::GenerateSwapResponse::new();
+        response.set_sci(proto_sci);
         response.set_b58_code(b58_code);
         Ok(response)
     }
 
-    fn submit_tx_impl(
+    fn generate_swap_tx_impl(
         &mut self,
-        request: api::SubmitTxRequest,
-    ) -> Result<api::SubmitTxResponse, RpcStatus> {
-        // Get TxProposal from request.
-        let tx_proposal = TxProposal::try_from(request.get_tx_proposal())
-            .map_err(|err| rpc_internal_error("tx_proposal.try_from", err, &self.logger))?;
+        request: api::GenerateSwapTxRequest,
+    ) -> Result<api::GenerateSwapTxResponse, RpcStatus> {
+        // Get sender monitor id from request.
+        let sender_monitor_id = MonitorId::try_from(&request.sender_monitor_id)
+            .map_err(|err| rpc_internal_error("monitor_id.try_from.bytes", err, &self.logger))?;
+
+        // Get monitor data for this monitor.
+        let sender_monitor_data = self
+            .mobilecoind_db
+            .get_monitor_data(&sender_monitor_id)
+            .map_err(|err| {
+                rpc_internal_error("mobilecoind_db.get_monitor_data", err, &self.logger)
+            })?;
+
+        // Check that change_subaddress is covered by this monitor.
+        if !sender_monitor_data
+            .subaddress_indexes()
+            .contains(&request.change_subaddress)
+        {
+            return Err(RpcStatus::with_message(
+                RpcStatusCode::INVALID_ARGUMENT,
+                "change_subaddress".into(),
+            ));
+        }
+
+        let sci = SignedContingentInput::try_from(request.get_sci()).map_err(|err| {
+            rpc_invalid_arg_error("SignedContingentInput.try_from", err, &self.logger)
+        })?;
+
+        // Get the list of potential inputs passed to.
+        let input_list: Vec<UnspentTxOut> = request
+            .get_input_list()
+            .iter()
+            .enumerate()
+            .map(|(i, proto_utxo)| {
+                // Proto -> Rust struct conversion.
+                let utxo = UnspentTxOut::try_from(proto_utxo).map_err(|err| {
+                    rpc_internal_error("unspent_tx_out.try_from", err, &self.logger)
+                })?;
+
+                // Verify this output belongs to the monitor.
+                let subaddress_id = self
+                    .mobilecoind_db
+                    .get_subaddress_id_by_utxo_id(&UtxoId::from(&utxo))
+                    .map_err(|err| {
+                        rpc_internal_error(
+                            "mobilecoind_db.get_subaddress_id_by_utxo_id",
+                            err,
+                            &self.logger,
+                        )
+                    })?;
+
+                if subaddress_id.monitor_id != sender_monitor_id {
+                    return Err(RpcStatus::with_message(
+                        RpcStatusCode::INVALID_ARGUMENT,
+                        format!("input_list.{}", i),
+                    ));
+                }
+
+                // Success.
+                Ok(utxo)
+            })
+            .collect::<Result<Vec<UnspentTxOut>, RpcStatus>>()?;
+
+        let utxo_selection_strategy: Box<dyn UtxoSelectionStrategy> =
+            request.get_utxo_selection_strategy().into();
+
+        // Attempt to construct a transaction. Signed contingent inputs which
+        // cannot be filled are invalid arguments.
+        let tx_proposal = self
+            .transactions_manager
+            .build_swap_transaction(
+                &sender_monitor_id,
+                TokenId::from(request.fee_token_id),
+                request.change_subaddress,
+                &input_list,
+                &sci,
+                &self.get_last_block_infos(),
+                request.fee,
+                request.tombstone,
+                utxo_selection_strategy.as_ref(),
+            )
+            .map_err(|err| match err {
+                Error::InvalidArgument(..) => rpc_invalid_arg_error(
+                    "transactions_manager.build_swap_transaction",
+                    err,
+                    &self.logger,
+                ),
+                err => rpc_internal_error(
+                    "transactions_manager.build_swap_transaction",
+                    err,
+                    &self.logger,
+                ),
+            })?;
+
+        // Success.
+        let mut response = api::GenerateSwapTxResponse::new();
+        response.set_tx_proposal((&tx_proposal).into());
+        Ok(response)
+    }
+
+    fn generate_transfer_code_tx_impl(
+        &mut self,
+        request: api::GenerateTransferCodeTxRequest,
+    ) -> Result<api::GenerateTransferCodeTxResponse, RpcStatus> {
+        // Generate entropy.
+        let mnemonic_response = self.generate_mnemonic_impl(api::Empty::new())?;
+        let mnemonic_str = mnemonic_response.get_mnemonic().to_string();
+        let bip39_entropy = mnemonic_response.get_bip39_entropy();
+
+        // Generate a new account using this mnemonic.
+        let mut account_key_request = api::GetAccountKeyFromMnemonicRequest::new();
+        account_key_request.set_mnemonic(mnemonic_str);
+
+        let account_key_response = self.get_account_key_from_mnemonic_impl(account_key_request)?;
+        let account_key = AccountKey::try_from(account_key_response.get_account_key())
+            .map_err(|err| rpc_internal_error("account_key.try_from", err, &self.logger))?;
+
+        // The outlay we are sending the money to.
+        let outlay = Outlay {
+            receiver: account_key.default_subaddress(),
+            value: request.value,
+            token_id: TokenId::from(request.token_id),
+        };
+
+        // Generate transaction.
+        let mut generate_tx_request = api::GenerateTxRequest::new();
+        generate_tx_request.set_sender_monitor_id(request.get_sender_monitor_id().to_vec());
+        generate_tx_request.set_change_subaddress(request.change_subaddress);
+        generate_tx_request.set_input_list(RepeatedField::from_vec(request.input_list.to_vec()));
+        generate_tx_request.set_outlay_list(RepeatedField::from_vec(vec![(&outlay).into()]));
+        generate_tx_request.set_fee(request.fee);
+        generate_tx_request.set_tombstone(request.tombstone);
+        generate_tx_request.set_token_id(request.token_id);
+
+        let mut generate_tx_response = self.generate_tx_impl(generate_tx_request)?;
+        let tx_proposal = generate_tx_response.take_tx_proposal();
+
+        // Grab the public key of the relevant tx out.
+        let proto_tx_public_key = {
+            // We expect only a single outlay.
+            if tx_proposal.get_outlay_index_to_tx_out_index().len() != 1 {
+                return Err(RpcStatus::with_message(
+                    RpcStatusCode::INTERNAL,
+                    format!(
+                        "outlay_index_to_tx_out_index contains {} elements, was expecting 1",
+                        tx_proposal.get_outlay_index_to_tx_out_index().len()
+                    ),
+                ));
+            }
+
+            // Get the TxOut index of our single outlay.
+            let tx_out_index = tx_proposal
+                .get_outlay_index_to_tx_out_index()
+                .get(&0)
+                .ok_or_else(|| {
+                    RpcStatus::with_message(
+                        RpcStatusCode::INTERNAL,
+                        "outlay_index_to_tx_out_index doesn't contain index 0".to_owned(),
+                    )
+                })?;
+
+            // Get the TxOut
+            let tx_out = tx_proposal
+                .get_tx()
+                .get_prefix()
+                .get_outputs()
+                .get(*tx_out_index as usize)
+                .ok_or_else(|| {
+                    RpcStatus::with_message(
+                        RpcStatusCode::INTERNAL,
+                        format!("tx out index {} not found", tx_out_index),
+                    )
+                })?;
+
+            // Get the public key
+            tx_out.get_public_key().clone()
+        };
+
+        let tx_public_key = RistrettoPublic::try_from(&proto_tx_public_key)
+            .map_err(|err| rpc_internal_error("ristretto_public.try_from", err, &self.logger))?;
+
+        let mut transfer_payload = api::printable::TransferPayload::new();
+        transfer_payload.set_bip39_entropy(bip39_entropy.to_vec());
+        transfer_payload.set_tx_out_public_key((&tx_public_key).into());
+        transfer_payload.set_memo(request.get_memo().to_string());
+
+        let mut transfer_wrapper = api::printable::PrintableWrapper::new();
+        transfer_wrapper.set_transfer_payload(transfer_payload);
+
+        let b58_code = transfer_wrapper
+            .b58_encode()
+            .map_err(|err| rpc_internal_error("b58_encode", err, &self.logger))?;
+
+        // Construct response.
+        let mut response = api::GenerateTransferCodeTxResponse::new();
+        response.set_tx_proposal(tx_proposal);
+        response.set_bip39_entropy(bip39_entropy.to_vec());
+        response.set_tx_public_key(proto_tx_public_key);
+        response.set_memo(request.get_memo().to_string());
+        response.set_b58_code(b58_code);
+        Ok(response)
+    }
+
+    /// Replace the placeholder key images of any view-only monitor inputs of a
+    /// signed transaction with the key images revealed by its signature.
+    fn replace_placeholder_key_images(&self, tx_proposal: &TxProposal) -> Result<(), RpcStatus> {
+        let mut monitor_id_to_key_images =
+            HashMap::<MonitorId, Vec<(KeyImage, KeyImage)>>::default();
+
+        for (i, utxo) in tx_proposal.utxos.iter().enumerate() {
+            let placeholder = UnspentTxOut::placeholder_key_image(&utxo.tx_out);
+            let subaddress_id = match self
+                .mobilecoind_db
+                .get_subaddress_id_by_utxo_id(&UtxoId::from(&placeholder))
+            {
+                Ok(subaddress_id) => subaddress_id,
+                // Not stored under a placeholder, nothing to do.
+                Err(Error::UtxoIdNotFound) => continue,
+                Err(err) => {
+                    return Err(rpc_internal_error(
+                        "mobilecoind_db.get_subaddress_id_by_utxo_id",
+                        err,
+                        &self.logger,
+                    ))
+                }
+            };
+
+            // The key image we are told about has to be the one the signature reveals.
+            if spent_key_image(&tx_proposal.tx, &utxo.tx_out) != Some(utxo.key_image) {
+                return Err(RpcStatus::with_message(
+                    RpcStatusCode::INVALID_ARGUMENT,
+                    format!("tx_proposal.input_list.{}.key_image", i),
+                ));
+            }
+
+            monitor_id_to_key_images
+                .entry(subaddress_id.monitor_id)
+                .or_insert_with(Vec::new)
+                .push((placeholder, utxo.key_image));
+        }
+
+        for (monitor_id, key_images) in monitor_id_to_key_images.iter() {
+            self.mobilecoind_db
+                .replace_utxo_key_images(monitor_id, key_images)
+                .map_err(|err| {
+                    rpc_internal_error("mobilecoind_db.replace_utxo_key_images", err, &self.logger)
+                })?;
+        }
+
+        Ok(())
+    }
+
+    fn submit_tx_impl(
+        &mut self,
+        request: api::SubmitTxRequest,
+    ) -> Result<api::SubmitTxResponse, RpcStatus> {
+        // Get TxProposal from request.
+        let tx_proposal = TxProposal::try_from(request.get_tx_proposal())
+            .map_err(|err| rpc_internal_error("tx_proposal.try_from", err, &self.logger))?;
+
+        // Inputs owned by view-only monitors are stored under placeholder key images.
+        // Now that the transaction is signed, replace them with the real ones
+        // so that the spend gets detected once it lands in the ledger.
+        self.replace_placeholder_key_images(&tx_proposal)?;
 
         // Submit to network.
         let block_height = self
@@ -1623,7 +2200,7 @@ impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolve
                                         &self.logger,
                                     )
                                 })?;
-                        let view_private_key = monitor_data.account_key.view_private_key();
+                        let view_private_key = monitor_data.monitor_key().view_private_key();
 
                         if request.get_receipt().get_confirmation_number().len() != 32 {
                             return Err(RpcStatus::with_message(
@@ -1701,14 +2278,15 @@ impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolve
         let monitor_id = MonitorId::try_from(&request.monitor_id)
             .map_err(|err| rpc_internal_error("monitor_id.try_from.bytes", err, &self.logger))?;
 
-        // We will use the AccountKey to compute the Address Code
-        let account_key = self
+        // We will use the monitor's keys to compute the Address Code
+        let monitor_key = self
             .mobilecoind_db
             .get_monitor_data(&monitor_id)
             .map_err(|err| {
                 rpc_internal_error("mobilecoind_db.get_monitor_data", err, &self.logger)
             })?
-            .account_key;
+            .monitor_key()
+            .clone();
 
         // Get all processed block data for the requested block.
         let processed_tx_outs = self
@@ -1719,26 +2297,8 @@ impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolve
             })?
             .iter()
             .map(|src| {
-                let mut dst = api::ProcessedTxOut::new();
-                dst.set_monitor_id(monitor_id.to_vec());
-                dst.set_subaddress_index(src.subaddress_index);
-                dst.set_public_key((&src.public_key).into());
-                dst.set_key_image((&src.key_image).into());
-                dst.set_value(src.value);
-                dst.set_direction(
-                    api::ProcessedTxOutDirection::from_i32(src.direction)
-                        .unwrap_or(api::ProcessedTxOutDirection::Invalid),
-                );
-
-                let subaddress = account_key.subaddress(src.subaddress_index);
-                let mut wrapper = api::printable::PrintableWrapper::new();
-                wrapper.set_public_address((&subaddress).into());
-                let encoded = wrapper
-                    .b58_encode()
-                    .map_err(|err| rpc_internal_error("wrapper.b58_encode", err, &self.logger))?;
-                dst.set_address_code(encoded);
-                dst.set_token_id(src.token_id);
-                Ok(dst)
+                processed_tx_out_to_api(&monitor_id, &monitor_key, src)
+                    .map_err(|err| rpc_internal_error("wrapper.b58_encode", err, &self.logger))
             })
             .collect::<Result<Vec<_>, _>>()?;
 
@@ -1829,6 +2389,49 @@ impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolve
         &mut self,
         request: api::SendPaymentRequest,
     ) -> Result<api::SendPaymentResponse, RpcStatus> {
+        // Take the payment request id and expiration timestamp from the request code
+        // being paid, if there is one.
+        let (payment_request_id, expiration_timestamp) =
+            if request.get_payment_request_b58_code().is_empty() {
+                (request.payment_request_id, request.expiration_timestamp)
+            } else {
+                if request.payment_request_id != 0 || request.expiration_timestamp != 0 {
+                    return Err(rpc_invalid_arg_error(
+                        "payment_request_b58_code",
+                        "cannot be combined with payment_request_id or expiration_timestamp",
+                        &self.logger,
+                    ));
+                }
+                let mut parse_request_code_request = api::ParseRequestCodeRequest::new();
+                parse_request_code_request
+                    .set_b58_code(request.get_payment_request_b58_code().to_owned());
+                let parse_request_code_response =
+                    self.parse_request_code_impl(parse_request_code_request)?;
+                (
+                    parse_request_code_response.payment_request_id,
+                    parse_request_code_response.expiration_timestamp,
+                )
+            };
+
+        // Refuse to pay a payment request that has expired.
+        if payment_request_expired(expiration_timestamp) {
+            return Err(rpc_invalid_arg_error(
+                "expiration_timestamp",
+                "payment request has expired",
+                &self.logger,
+            ));
+        }
+
+        // The memos referencing a payment request only support outputs in a single
+        // token id.
+        if payment_request_id != 0 && request.multi_token_outlays {
+            return Err(rpc_invalid_arg_error(
+                "payment_request_id",
+                "cannot be combined with multi_token_outlays",
+                &self.logger,
+            ));
+        }
+
         // Get sender monitor id from request.
         let sender_monitor_id = MonitorId::try_from(&request.sender_monitor_id)
             .map_err(|err| rpc_internal_error("monitor_id.try_from.bytes", err, &self.logger))?;
@@ -1846,18 +2449,16 @@ impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolve
             utxos.retain(|utxo| utxo.value <= request.max_input_utxo_value);
         }
 
-        // Filter for requested token id.
-        utxos.retain(|utxo| utxo.token_id == request.token_id);
-
         // Get the list of outlays.
-        let outlays: Vec<Outlay> = request
-            .get_outlay_list()
-            .iter()
-            .map(|outlay_proto| {
-                Outlay::try_from(outlay_proto)
-                    .map_err(|err| rpc_internal_error("outlay.try_from", err, &self.logger))
-            })
-            .collect::<Result<Vec<Outlay>, RpcStatus>>()?;
+        let outlays = self.get_outlays(
+            request.get_outlay_list(),
+            request.token_id,
+            request.multi_token_outlays,
+        )?;
+
+        // Filter for requested token ids.
+        let input_token_ids = input_token_ids(request.token_id, &outlays);
+        utxos.retain(|utxo| input_token_ids.contains(&utxo.token_id));
 
         // Set change address to sender address unless it has been overridden
         let change_subaddress = if request.override_change_subaddress {
@@ -1866,6 +2467,40 @@ impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolve
             request.sender_subaddress
         };
 
+        let utxo_selection_strategy: Box<dyn UtxoSelectionStrategy> =
+            request.get_utxo_selection_strategy().into();
+
+        // Reference the payment request in the memos of the outputs, if it has an id.
+        let opt_memo_builder = if payment_request_id != 0 {
+            let monitor_data = self
+                .mobilecoind_db
+                .get_monitor_data(&sender_monitor_id)
+                .map_err(|err| {
+                    rpc_internal_error("mobilecoind_db.get_monitor_data", err, &self.logger)
+                })?;
+            let account_key = monitor_data.account_key().ok_or_else(|| {
+                rpc_invalid_arg_error(
+                    "sender_monitor_id",
+                    "payment request ids require a monitor with a spend key",
+                    &self.logger,
+                )
+            })?;
+
+            // The memo authenticates the subaddress the payment is sent from.
+            let sender_credential = SenderMemoCredential::new_from_address_and_spend_private_key(
+                &account_key.subaddress(request.sender_subaddress),
+                account_key.subaddress_spend_private(request.sender_subaddress),
+            );
+
+            let mut memo_builder = RTHMemoBuilder::default();
+            memo_builder.set_sender_credential(sender_credential);
+            memo_builder.set_payment_request_id(payment_request_id);
+            memo_builder.enable_destination_memo();
+            Some(Box::new(memo_builder) as Box<dyn MemoBuilder + Send + Sync>)
+        } else {
+            None
+        };
+
         // Attempt to construct a transaction.
         let tx_proposal = self
             .transactions_manager
@@ -1878,7 +2513,8 @@ impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolve
                 &self.get_last_block_infos(),
                 request.fee,
                 request.tombstone,
-                None,
+                opt_memo_builder,
+                utxo_selection_strategy.as_ref(),
             )
             .map_err(|err| {
                 rpc_internal_error("transactions_manager.build_transaction", err, &self.logger)
@@ -1932,6 +2568,7 @@ impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolve
         send_payment_request.set_override_change_subaddress(request.override_change_subaddress);
         send_payment_request.set_change_subaddress(request.change_subaddress);
         send_payment_request.set_token_id(request.token_id);
+        send_payment_request.set_utxo_selection_strategy(request.get_utxo_selection_strategy());
 
         self.send_payment_impl(send_payment_request)
     }
@@ -1979,6 +2616,34 @@ impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolve
         response.set_local_block_index(local_block_index);
         response.set_is_behind(network_state.is_behind(local_block_index));
         response.set_last_block_info(last_block_info.into());
+        response.set_peer_health(
+            network_state
+                .peer_health_scores()
+                .into_iter()
+                .map(|(responder_id, health)| {
+                    let mut peer_health = api::PeerHealth::new();
+                    peer_health.set_responder_id(responder_id.to_string());
+                    peer_health.set_score(health.score);
+                    peer_health.set_latency_ms(
+                        health
+                            .latency
+                            .map(|latency| latency.as_millis() as u64)
+                            .unwrap_or(0),
+                    );
+                    peer_health.set_error_rate(health.error_rate);
+                    peer_health.set_block_index(health.block_index.unwrap_or(0));
+                    peer_health.set_blocks_behind(health.blocks_behind);
+                    peer_health.set_is_quarantined(health.is_quarantined());
+                    peer_health.set_quarantine_remaining_ms(
+                        health
+                            .quarantine_remaining
+                            .map(|remaining| remaining.as_millis() as u64)
+                            .unwrap_or(0),
+                    );
+                    peer_health
+                })
+                .collect(),
+        );
 
         Ok(response)
     }
@@ -2026,12 +2691,157 @@ impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolve
     }
 }
 
-macro_rules! build_api {
-    ($( $service_function_name:ident $service_request_type:ident $service_response_type:ident $service_function_impl:ident $(,)?)+)
-    =>
-    (
-        impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolver> MobilecoindApi for ServiceApi<T, FPR> {
-            $(
+/// Whether a payment request with the given expiration timestamp, in seconds
+/// since the Unix epoch, has expired. A timestamp of 0 never expires.
+fn payment_request_expired(expiration_timestamp: u64) -> bool {
+    if expiration_timestamp == 0 {
+        return false;
+    }
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0);
+    now >= expiration_timestamp
+}
+
+/// The swap a signed contingent input offers: the amount offered, which is the
+/// value of its input less any outputs its rules require in the same token id,
+/// and the amounts requested in exchange, by token id. Partial fill rules are
+/// not accounted for, so signed contingent inputs with such rules are refused
+/// before this is called.
+fn swap_amounts(sci: &SignedContingentInput) -> (Amount, Vec<Amount>) {
+    let offered_token_id = TokenId::from(sci.pseudo_output_amount.token_id);
+    let mut offered_value = sci.pseudo_output_amount.value;
+
+    let mut requested_values = BTreeMap::<TokenId, u64>::new();
+    for amount in sci.required_output_amounts.iter() {
+        let token_id = TokenId::from(amount.token_id);
+        let mut value = amount.value;
+        if token_id == offered_token_id {
+            let returned_value = value.min(offered_value);
+            offered_value -= returned_value;
+            value -= returned_value;
+        }
+        if value > 0 {
+            let total = requested_values.entry(token_id).or_default();
+            *total = total.saturating_add(value);
+        }
+    }
+
+    let requested_amounts = requested_values
+        .into_iter()
+        .map(|(token_id, value)| Amount::new(value, token_id))
+        .collect();
+    (
+        Amount::new(offered_value, offered_token_id),
+        requested_amounts,
+    )
+}
+
+/// The token ids inputs may be in when paying the given outlays with a fee in
+/// `fee_token_id`.
+fn input_token_ids(fee_token_id: u64, outlays: &[Outlay]) -> HashSet<u64> {
+    outlays
+        .iter()
+        .map(|outlay| *outlay.token_id)
+        .chain(std::iter::once(fee_token_id))
+        .collect()
+}
+
+/// Convert a processed block store entry into its API representation.
+fn processed_tx_out_to_api(
+    monitor_id: &MonitorId,
+    monitor_key: &MonitorKey,
+    src: &ProcessedTxOut,
+) -> Result<api::ProcessedTxOut, mc_api::display::Error> {
+    let mut dst = api::ProcessedTxOut::new();
+    dst.set_monitor_id(monitor_id.to_vec());
+    dst.set_subaddress_index(src.subaddress_index);
+    dst.set_public_key((&src.public_key).into());
+    dst.set_key_image((&src.key_image).into());
+    dst.set_value(src.value);
+    dst.set_direction(
+        api::ProcessedTxOutDirection::from_i32(src.direction)
+            .unwrap_or(api::ProcessedTxOutDirection::Invalid),
+    );
+
+    let subaddress = monitor_key.subaddress(src.subaddress_index);
+    let mut wrapper = api::printable::PrintableWrapper::new();
+    wrapper.set_public_address((&subaddress).into());
+    dst.set_address_code(wrapper.b58_encode()?);
+    dst.set_token_id(src.token_id);
+    Ok(dst)
+}
+
+/// Convert a monitor event into its API representation.
+fn monitor_event_to_api(
+    monitor_id: &MonitorId,
+    monitor_key: &MonitorKey,
+    src: &MonitorEvent,
+) -> Result<api::MonitorEvent, mc_api::display::Error> {
+    let mut dst = api::MonitorEvent::new();
+    dst.set_monitor_id(monitor_id.to_vec());
+
+    match src {
+        MonitorEvent::UtxoReceived {
+            block_index,
+            tx_out,
+        } => {
+            dst.set_block_index(*block_index);
+            dst.set_utxo_received(processed_tx_out_to_api(monitor_id, monitor_key, tx_out)?);
+        }
+        MonitorEvent::UtxoSpent {
+            block_index,
+            tx_out,
+        } => {
+            dst.set_block_index(*block_index);
+            dst.set_utxo_spent(processed_tx_out_to_api(monitor_id, monitor_key, tx_out)?);
+        }
+        MonitorEvent::BlockProcessed {
+            block_index,
+            num_received,
+            num_spent,
+        } => {
+            let mut event = api::BlockProcessedEvent::new();
+            event.set_num_received(*num_received);
+            event.set_num_spent(*num_spent);
+
+            dst.set_block_index(*block_index);
+            dst.set_block_processed(event);
+        }
+        MonitorEvent::CaughtUp { next_block } => {
+            let mut event = api::MonitorCaughtUpEvent::new();
+            event.set_next_block(*next_block);
+
+            dst.set_block_index(next_block.saturating_sub(1));
+            dst.set_caught_up(event);
+        }
+    }
+
+    Ok(dst)
+}
+
+/// Forward monitor events produced by a streaming thread to a gRPC sink.
+async fn forward_monitor_events(
+    mut receiver: mpsc::Receiver<Result<api::MonitorEvent, RpcStatus>>,
+    mut sink: ServerStreamingSink<api::MonitorEvent>,
+) -> grpcio::Result<()> {
+    while let Some(item) = receiver.next().await {
+        match item {
+            Ok(event) => sink.send((event, WriteFlags::default())).await?,
+            Err(status) => return sink.fail(status).await,
+        }
+    }
+
+    sink.close().await
+}
+
+macro_rules! build_api {
+    ($( $service_function_name:ident $service_request_type:ident $service_response_type:ident $service_function_impl:ident $(,)?)+)
+    =>
+    (
+        impl<T: BlockchainConnection + UserTxConnection + 'static, FPR: FogPubkeyResolver> MobilecoindApi for ServiceApi<T, FPR> {
+            $(
                 fn $service_function_name(
                     &mut self,
                     ctx: RpcContext,
@@ -2047,6 +2857,15 @@ macro_rules! build_api {
                     )
                 }
             )+
+
+            fn subscribe_monitor_events(
+                &mut self,
+                ctx: RpcContext,
+                request: api::SubscribeMonitorEventsRequest,
+                sink: ServerStreamingSink<api::MonitorEvent>,
+            ) {
+                self.subscribe_monitor_events_impl(ctx, request, sink)
+            }
         }
     );
 }
@@ -2073,6 +2892,7 @@ build_api! {
     create_transfer_code CreateTransferCodeRequest CreateTransferCodeResponse create_transfer_code_impl,
     parse_address_code ParseAddressCodeRequest ParseAddressCodeResponse parse_address_code_impl,
     create_address_code CreateAddressCodeRequest CreateAddressCodeResponse create_address_code_impl,
+    parse_swap_code ParseSwapCodeRequest ParseSwapCodeResponse parse_swap_code_impl,
 
     // Transactions
     get_mixins GetMixinsRequest GetMixinsResponse get_mixins_impl,
@@ -2082,6 +2902,8 @@ build_api! {
     generate_transfer_code_tx GenerateTransferCodeTxRequest GenerateTransferCodeTxResponse generate_transfer_code_tx_impl,
     generate_tx_from_tx_out_list GenerateTxFromTxOutListRequest GenerateTxFromTxOutListResponse generate_tx_from_tx_out_list_impl,
     generate_burn_redemption_tx GenerateBurnRedemptionTxRequest GenerateBurnRedemptionTxResponse generate_burn_redemption_tx_impl,
+    generate_swap GenerateSwapRequest GenerateSwapResponse generate_swap_impl,
+    generate_swap_tx GenerateSwapTxRequest GenerateSwapTxResponse generate_swap_tx_impl,
     submit_tx SubmitTxRequest SubmitTxResponse submit_tx_impl,
 
     // Databases
@@ -2113,7 +2935,7 @@ build_api! {
 mod test {
     use super::*;
     use crate::{
-        payments::DEFAULT_NEW_TX_BLOCK_ATTEMPTS,
+        payments::{UnsignedTxProposal, DEFAULT_NEW_TX_BLOCK_ATTEMPTS},
         subaddress_store::SubaddressSPKId,
         test_utils::{
             self, add_block_to_ledger, add_txos_to_ledger, get_testing_environment,
@@ -2121,6 +2943,7 @@ mod test {
         },
         utxo_store::UnspentTxOut,
     };
+    use futures::{executor::block_on, StreamExt};
     use grpcio::Error as GrpcError;
     use mc_account_keys::{
         burn_address_view_private, AccountKey, PublicAddress, ShortAddressHash,
@@ -2130,6 +2953,7 @@ mod test {
     use mc_common::{logger::test_with_logger, HashSet};
     use mc_crypto_keys::RistrettoPrivate;
     use mc_crypto_rand::RngCore;
+    use mc_crypto_ring_signature_signer::{LocalRingSigner, NoKeysRingSigner};
     use mc_fog_report_validation::{FullyValidatedFogPubkey, MockFogPubkeyResolver};
     use mc_fog_report_validation_test_utils::MockFogResolver;
     use mc_ledger_db::test_utils::add_txos_and_key_images_to_ledger;
@@ -2142,7 +2966,10 @@ mod test {
         tx::{Tx, TxOut},
         Amount, Token,
     };
-    use mc_transaction_std::{EmptyMemoBuilder, MemoType, TransactionBuilder, TxOutContext};
+    use mc_transaction_std::{
+        test_utils::get_input_credentials, EmptyMemoBuilder, MemoType, ReservedSubaddresses,
+        SignedContingentInputBuilder, TransactionBuilder, TxOutContext,
+    };
     use mc_util_repr_bytes::{typenum::U32, GenericArray, ReprBytes};
     use mc_util_uri::FogUri;
     use rand::{rngs::StdRng, SeedableRng};
@@ -2169,7 +2996,9 @@ mod test {
         .expect("failed to create data");
 
         let mut request = api::AddMonitorRequest::new();
-        request.set_account_key(mc_api::external::AccountKey::from(&data.account_key));
+        request.set_account_key(mc_api::external::AccountKey::from(
+            data.account_key().unwrap(),
+        ));
         request.set_first_subaddress(data.first_subaddress);
         request.set_num_subaddresses(data.num_subaddresses);
         request.set_first_block(data.first_block);
@@ -2333,8 +3162,8 @@ mod test {
 
         // Verify the data we got matches what we expected
         assert_eq!(
-            data.account_key,
-            AccountKey::try_from(status.account_key.as_ref().unwrap()).unwrap(),
+            data.account_key().unwrap(),
+            &AccountKey::try_from(status.account_key.as_ref().unwrap()).unwrap(),
         );
         assert_eq!(status.first_subaddress, data.first_subaddress);
         assert_eq!(status.num_subaddresses, data.num_subaddresses);
@@ -3344,6 +4173,119 @@ mod test {
         assert!(client.get_processed_block(&request).is_err());
     }
 
+    #[test_with_logger]
+    fn test_subscribe_monitor_events(logger: Logger) {
+        let mut rng: StdRng = SeedableRng::from_seed([23u8; 32]);
+
+        let account_key = AccountKey::random(&mut rng);
+        let monitor_data = MonitorData::new(
+            account_key.clone(),
+            0,  // first_subaddress
+            20, // num_subaddresses
+            0,  // first_block
+            "", // name
+        )
+        .unwrap();
+
+        // 1 known recipient, 3 random recipients and no monitors.
+        let (mut ledger_db, mobilecoind_db, client, _server, _server_conn_manager) =
+            get_testing_environment(
+                BLOCK_VERSION,
+                3,
+                &[account_key.default_subaddress()],
+                &[],
+                logger.clone(),
+                &mut rng,
+            );
+
+        // Insert into database and allow the new monitor to process the ledger.
+        let monitor_id = mobilecoind_db.add_monitor(&monitor_data).unwrap();
+        wait_for_monitors(&mobilecoind_db, &ledger_db, &logger);
+        let num_blocks = ledger_db.num_blocks().expect("failed getting num blocks");
+
+        // Subscribing from the beginning replays every processed block, followed by a
+        // caught up event.
+        let mut request = api::SubscribeMonitorEventsRequest::new();
+        request.set_monitor_id(monitor_id.to_vec());
+        request.set_from_block(0);
+        let mut stream = client
+            .subscribe_monitor_events(&request)
+            .expect("failed to subscribe to monitor events");
+
+        for block_index in 0..num_blocks {
+            // Each block in the test ledger contains a single TxOut for our account.
+            let event = block_on(stream.next()).unwrap().unwrap();
+            assert_eq!(event.get_monitor_id(), monitor_id.to_vec());
+            assert_eq!(event.get_block_index(), block_index);
+            assert!(event.has_utxo_received());
+            assert_eq!(
+                event.get_utxo_received().get_value(),
+                test_utils::DEFAULT_PER_RECIPIENT_AMOUNT
+            );
+
+            let event = block_on(stream.next()).unwrap().unwrap();
+            assert_eq!(event.get_block_index(), block_index);
+            assert!(event.has_block_processed());
+            assert_eq!(event.get_block_processed().get_num_received(), 1);
+            assert_eq!(event.get_block_processed().get_num_spent(), 0);
+        }
+
+        let event = block_on(stream.next()).unwrap().unwrap();
+        assert!(event.has_caught_up());
+        assert_eq!(event.get_caught_up().get_next_block(), num_blocks);
+
+        // Spend one of our outputs in a new block. The stream should report it as soon
+        // as the sync thread processes it.
+        let utxos = mobilecoind_db
+            .get_utxos_for_subaddress(&monitor_id, 0)
+            .unwrap();
+        add_block_to_ledger(
+            &mut ledger_db,
+            BLOCK_VERSION,
+            &[AccountKey::random(&mut rng).default_subaddress()],
+            Amount::new(DEFAULT_PER_RECIPIENT_AMOUNT, Mob::ID),
+            &[utxos[0].key_image],
+            &mut rng,
+        )
+        .unwrap();
+
+        let event = block_on(stream.next()).unwrap().unwrap();
+        assert_eq!(event.get_block_index(), num_blocks);
+        assert!(event.has_utxo_spent());
+        assert_eq!(
+            event.get_utxo_spent().get_key_image(),
+            &mc_api::external::KeyImage::from(&utxos[0].key_image)
+        );
+
+        let event = block_on(stream.next()).unwrap().unwrap();
+        assert_eq!(event.get_block_index(), num_blocks);
+        assert_eq!(event.get_block_processed().get_num_received(), 0);
+        assert_eq!(event.get_block_processed().get_num_spent(), 1);
+
+        let event = block_on(stream.next()).unwrap().unwrap();
+        assert_eq!(event.get_caught_up().get_next_block(), num_blocks + 1);
+
+        // A reconnecting client can resume from the last block it saw.
+        request.set_from_block(num_blocks);
+        let mut resumed_stream = client
+            .subscribe_monitor_events(&request)
+            .expect("failed to subscribe to monitor events");
+
+        let event = block_on(resumed_stream.next()).unwrap().unwrap();
+        assert_eq!(event.get_block_index(), num_blocks);
+        assert!(event.has_utxo_spent());
+
+        // Removing the monitor ends the stream.
+        mobilecoind_db.remove_monitor(&monitor_id).unwrap();
+        assert!(block_on(stream.next()).is_none());
+
+        // Subscribing to an unknown monitor fails.
+        let mut stream = client
+            .subscribe_monitor_events(&request)
+            .expect("failed to subscribe to monitor events");
+        assert!(block_on(stream.next()).unwrap().is_err());
+    }
+
     #[test_with_logger]
     /// Get mixins should return the correct number of distinct mixins.
     fn test_get_mixins(logger: Logger) {
@@ -3646,10 +4588,12 @@ mod test {
             Outlay {
                 value: 123,
                 receiver: receiver1.default_subaddress(),
+                token_id: Mob::ID,
             },
             Outlay {
                 value: 456,
                 receiver: receiver2.default_subaddress(),
+                token_id: Mob::ID,
             },
         ];
 
@@ -3830,6 +4774,110 @@ mod test {
             );
         }
 
+        // Test the happy flow for outlays in both MOB and TokenId(2), with the fee in
+        // MOB.
+        {
+            let multi_token_outlays = vec![
+                outlays[0].clone(),
+                Outlay {
+                    token_id: TokenId::from(2),
+                    ..outlays[1].clone()
+                },
+            ];
+
+            let mut request = api::GenerateTxRequest::new();
+            request.set_sender_monitor_id(monitor_id.to_vec());
+            request.set_change_subaddress(0);
+            request.set_input_list(RepeatedField::from_vec(
+                utxos.iter().map(api::UnspentTxOut::from).collect(),
+            ));
+            request.set_outlay_list(RepeatedField::from_vec(
+                multi_token_outlays.iter().map(api::Outlay::from).collect(),
+            ));
+            request.set_multi_token_outlays(true);
+
+            let response = client.generate_tx(&request).unwrap();
+
+            // Sanity test the response.
+            let tx_proposal = response.get_tx_proposal();
+
+            // A single input of each token id covers the outlays.
+            assert_eq!(tx_proposal.get_input_list().len(), 2);
+            assert_eq!(tx_proposal.get_tx().get_prefix().get_inputs().len(), 2);
+            assert_eq!(tx_proposal.get_outlay_list(), request.get_outlay_list());
+
+            let tx = Tx::try_from(tx_proposal.get_tx()).unwrap();
+
+            // The transaction should contain an output for each outlay, and one for change
+            // in each token id.
+            assert_eq!(tx.prefix.outputs.len(), multi_token_outlays.len() + 2);
+
+            let mut received = tx
+                .prefix
+                .outputs
+                .iter()
+                .filter_map(|tx_out| {
+                    let output_public_key = RistrettoPublic::try_from(&tx_out.public_key).unwrap();
+                    [&receiver1, &receiver2, &sender]
+                        .iter()
+                        .enumerate()
+                        .find_map(|(i, account_key)| {
+                            let shared_secret = get_tx_out_shared_secret(
+                                account_key.view_private_key(),
+                                &output_public_key,
+                            );
+                            let (amount, _blinding) =
+                                tx_out.masked_amount.get_value(&shared_secret).ok()?;
+                            Some((i, amount))
+                        })
+                })
+                .collect::<Vec<_>>();
+            received.sort_by_key(|(i, amount)| (*i, amount.token_id));
+
+            assert_eq!(
+                received,
+                vec![
+                    (0, Amount::new(outlays[0].value, Mob::ID)),
+                    (1, Amount::new(outlays[1].value, TokenId::from(2))),
+                    (
+                        2,
+                        Amount::new(
+                            test_utils::DEFAULT_PER_RECIPIENT_AMOUNT
+                                - outlays[0].value
+                                - Mob::MINIMUM_FEE,
+                            Mob::ID
+                        )
+                    ),
+                    (
+                        2,
+                        Amount::new(1_000_000_000_000 - outlays[1].value, TokenId::from(2))
+                    ),
+                ]
+            );
+
+            // The fee is paid in MOB.
+            assert_eq!(tx_proposal.get_fee(), Mob::MINIMUM_FEE);
+            assert_eq!(tx.prefix.fee_token_id, *Mob::ID);
+        }
+
+        // Every UTXO selection strategy should produce a valid transaction.
+        for strategy in &[
+            api::UtxoSelectionStrategy::LargestFirst,
+            api::UtxoSelectionStrategy::BranchAndBound,
+            api::UtxoSelectionStrategy::Randomized,
+        ] {
+            let mut request = request.clone();
+            request.set_utxo_selection_strategy(*strategy);
+
+            let response = client.generate_tx(&request).unwrap();
+            let tx_proposal = response.get_tx_proposal();
+
+            // All the MOB utxos have the same value, so a single one covers the outlays.
+            assert_eq!(tx_proposal.get_input_list().len(), 1);
+            assert_eq!(tx_proposal.get_tx().get_prefix().get_inputs().len(), 1);
+            assert_eq!(tx_proposal.get_outlay_list(), request.get_outlay_list());
+        }
+
         // Invalid input scenarios should result in an error.
         {
             // No monitor id
@@ -3875,6 +4923,7 @@ mod test {
             request.set_outlay_list(RepeatedField::from_vec(vec![api::Outlay::from(&Outlay {
                 receiver: receiver1.default_subaddress(),
                 value: test_utils::DEFAULT_PER_RECIPIENT_AMOUNT * num_blocks,
+                token_id: Mob::ID,
             })]));
             assert!(client.generate_tx(&request).is_err());
         }
@@ -3891,6 +4940,11 @@ mod test {
                 outlays.iter().map(api::Outlay::from).collect(),
             ));
             assert!(client.generate_tx(&request).is_err());
+
+            // Inputs in a token id which is neither the fee's nor an outlay's are
+            // rejected even when multi_token_outlays is set.
+            request.set_multi_token_outlays(true);
+            assert!(client.generate_tx(&request).is_err());
         }
     }
 
@@ -4244,7 +5298,7 @@ mod test {
         assert_eq!(tx_proposal.outlays.len(), 1);
         assert_eq!(
             tx_proposal.outlays[0].receiver,
-            data.account_key.subaddress(0)
+            data.account_key().unwrap().subaddress(0)
         );
         assert_eq!(
             tx_proposal.outlays[0].value,
@@ -4260,7 +5314,7 @@ mod test {
         let tx_out = &tx_proposal.tx.prefix.outputs[0];
         let tx_public_key = RistrettoPublic::try_from(&tx_out.public_key).unwrap();
         let shared_secret =
-            get_tx_out_shared_secret(data.account_key.view_private_key(), &tx_public_key);
+            get_tx_out_shared_secret(data.monitor_key().view_private_key(), &tx_public_key);
         let (amount, _blinding) = tx_out.masked_amount.get_value(&shared_secret).unwrap();
         assert_eq!(amount.value, tx_proposal.outlays[0].value);
         assert_eq!(amount.token_id, Mob::ID);
@@ -4277,6 +5331,313 @@ mod test {
         );
     }
 
+    #[test_with_logger]
+    fn test_swap(logger: Logger) {
+        let mut rng: StdRng = SeedableRng::from_seed([23u8; 32]);
+
+        let alice = AccountKey::random(&mut rng);
+        let alice_data = MonitorData::new(
+            alice.clone(),
+            0,  // first_subaddress
+            20, // num_subaddresses
+            0,  // first_block
+            "", // name
+        )
+        .unwrap();
+
+        let bob = AccountKey::random(&mut rng);
+        let bob_data = MonitorData::new(
+            bob.clone(),
+            0,  // first_subaddress
+            20, // num_subaddresses
+            0,  // first_block
+            "", // name
+        )
+        .unwrap();
+
+        // Bob is a known recipient of MOB, with 3 random recipients and no monitors.
+        let (mut ledger_db, mobilecoind_db, client, _server, _server_conn_manager) =
+            get_testing_environment(
+                BLOCK_VERSION,
+                3,
+                &[bob.default_subaddress()],
+                &[],
+                logger.clone(),
+                &mut rng,
+            );
+
+        // Alice receives a non-MOB token ID.
+        let token2_amount = Amount::new(1_000_000_000_000, TokenId::from(2));
+        add_block_to_ledger(
+            &mut ledger_db,
+            BLOCK_VERSION,
+            &[
+                AccountKey::random(&mut rng).default_subaddress(),
+                alice.default_subaddress(),
+            ],
+            token2_amount,
+            &[KeyImage::from(101)],
+            &mut rng,
+        )
+        .unwrap();
+
+        // Insert into database.
+        let alice_monitor_id = mobilecoind_db.add_monitor(&alice_data).unwrap();
+        let bob_monitor_id = mobilecoind_db.add_monitor(&bob_data).unwrap();
+
+        // Allow the new monitors to process the ledger.
+        wait_for_monitors(&mobilecoind_db, &ledger_db, &logger);
+
+        // Alice offers her token id 2 for 1 MOB, paid to her subaddress 1.
+        let alice_utxos = mobilecoind_db
+            .get_utxos_for_subaddress(&alice_monitor_id, 0)
+            .unwrap();
+        assert_eq!(alice_utxos.len(), 1);
+
+        let counter_amount = Amount::new(1_000_000_000_000, Mob::ID);
+        let mut request = api::GenerateSwapRequest::new();
+        request.set_sender_monitor_id(alice_monitor_id.to_vec());
+        request.set_receiver_subaddress(1);
+        request.set_input((&alice_utxos[0]).into());
+        request.set_counter_value(counter_amount.value);
+        request.set_counter_token_id(*counter_amount.token_id);
+        request.set_tombstone(1000);
+
+        // Bob cannot offer alice's input.
+        {
+            let mut request = request.clone();
+            request.set_sender_monitor_id(bob_monitor_id.to_vec());
+            assert!(client.generate_swap(&request).is_err());
+        }
+
+        let swap_response = client.generate_swap(&request).unwrap();
+
+        // Bob parses the swap code.
+        let mut request = api::ParseSwapCodeRequest::new();
+        request.set_b58_code(swap_response.get_b58_code().to_string());
+        let parse_response = client.parse_swap_code(&request).unwrap();
+
+        assert_eq!(parse_response.get_sci(), swap_response.get_sci());
+        assert_eq!(
+            Amount::from(parse_response.get_offered_amount()),
+            token2_amount
+        );
+        assert_eq!(
+            parse_response
+                .get_requested_amounts()
+                .iter()
+                .map(Amount::from)
+                .collect::<Vec<_>>(),
+            vec![counter_amount]
+        );
+        assert_eq!(parse_response.get_max_tombstone_block(), 1000);
+        assert_eq!(parse_response.get_block_version(), *BLOCK_VERSION);
+        assert!(!parse_response.get_spent());
+        assert!(!parse_response.get_expired());
+
+        // Other codes are refused.
+        {
+            let mut wrapper = api::printable::PrintableWrapper::new();
+            wrapper.set_public_address((&alice.default_subaddress()).into());
+            request.set_b58_code(wrapper.b58_encode().unwrap());
+            assert!(client.parse_swap_code(&request).is_err());
+        }
+
+        // Bob fills the offer.
+        let bob_utxos = mobilecoind_db
+            .get_utxos_for_subaddress(&bob_monitor_id, 0)
+            .unwrap();
+
+        let mut request = api::GenerateSwapTxRequest::new();
+        request.set_sender_monitor_id(bob_monitor_id.to_vec());
+        request.set_change_subaddress(0);
+        request.set_input_list(RepeatedField::from_vec(
+            bob_utxos.iter().map(api::UnspentTxOut::from).collect(),
+        ));
+        request.set_sci(parse_response.get_sci().clone());
+
+        let response = client.generate_swap_tx(&request).unwrap();
+        let tx_proposal = TxProposal::try_from(response.get_tx_proposal()).unwrap();
+        let tx = &tx_proposal.tx;
+
+        // The transaction spends one of Bob's inputs and Alice's signed input, and
+        // expires no later than Alice's offer.
+        assert_eq!(tx_proposal.utxos.len(), 1);
+        assert_eq!(tx.prefix.inputs.len(), 2);
+        assert_eq!(
+            tx.prefix
+                .inputs
+                .iter()
+                .filter(|tx_in| tx_in.input_rules.is_some())
+                .count(),
+            1
+        );
+        assert!(tx.prefix.tombstone_block <= 1000);
+
+        // Alice receives 1 MOB, and Bob receives her token id 2 and his change.
+        let amounts_received = |account_key: &AccountKey| -> HashSet<(u64, u64)> {
+            tx.prefix
+                .outputs
+                .iter()
+                .filter_map(|tx_out| tx_out.view_key_match(account_key.view_private_key()).ok())
+                .map(|(amount, _)| (amount.value, *amount.token_id))
+                .collect()
+        };
+        assert_eq!(
+            amounts_received(&alice),
+            HashSet::from_iter([(counter_amount.value, *counter_amount.token_id)])
+        );
+        assert_eq!(
+            amounts_received(&bob),
+            HashSet::from_iter([
+                (token2_amount.value, *token2_amount.token_id),
+                (
+                    tx_proposal.utxos[0].value - counter_amount.value - tx.prefix.fee,
+                    *Mob::ID
+                ),
+            ])
+        );
+
+        // Once Alice's input is spent, the offer can no longer be filled.
+        let sci = SignedContingentInput::try_from(parse_response.get_sci()).unwrap();
+        add_block_to_ledger(
+            &mut ledger_db,
+            BLOCK_VERSION,
+            &[AccountKey::random(&mut rng).default_subaddress()],
+            Amount::new(DEFAULT_PER_RECIPIENT_AMOUNT, Mob::ID),
+            &[sci.key_image()],
+            &mut rng,
+        )
+        .unwrap();
+
+        let mut parse_request = api::ParseSwapCodeRequest::new();
+        parse_request.set_b58_code(swap_response.get_b58_code().to_string());
+        assert!(client.parse_swap_code(&parse_request).unwrap().get_spent());
+
+        match client.generate_swap_tx(&request) {
+            Err(GrpcError::RpcFailure(status)) => {
+                assert_eq!(status.code(), RpcStatusCode::INVALID_ARGUMENT);
+            }
+            result => panic!("Unexpected result {:?}", result),
+        }
+    }
+
+    #[test_with_logger]
+    fn test_swap_partial_fill_refused(logger: Logger) {
+        let mut rng: StdRng = SeedableRng::from_seed([23u8; 32]);
+
+        let bob = AccountKey::random(&mut rng);
+        let bob_data = MonitorData::new(
+            bob.clone(),
+            0,  // first_subaddress
+            20, // num_subaddresses
+            0,  // first_block
+            "", // name
+        )
+        .unwrap();
+
+        // Bob is a known recipient of MOB, with 3 random recipients and no monitors.
+        let (ledger_db, mobilecoind_db, client, _server, _server_conn_manager) =
+            get_testing_environment(
+                BLOCK_VERSION,
+                3,
+                &[bob.default_subaddress()],
+                &[],
+                logger.clone(),
+                &mut rng,
+            );
+        let bob_monitor_id = mobilecoind_db.add_monitor(&bob_data).unwrap();
+        wait_for_monitors(&mobilecoind_db, &ledger_db, &logger);
+
+        // Alice offers her token id 2 for MOB, and allows the offer to be filled
+        // partially.
+        let alice = AccountKey::random(&mut rng);
+        let fog_resolver = MockFogResolver::default();
+        let input_credentials = get_input_credentials(
+            BLOCK_VERSION,
+            Amount::new(1_000_000, TokenId::from(2)),
+            &alice,
+            &fog_resolver,
+            &mut rng,
+        );
+        let mut builder = SignedContingentInputBuilder::new(
+            BLOCK_VERSION,
+            input_credentials,
+            fog_resolver,
+            EmptyMemoBuilder::default(),
+        )
+        .unwrap();
+        builder
+            .add_partial_fill_output(
+                Amount::new(1_000_000_000, Mob::ID),
+                &alice.default_subaddress(),
+                &mut rng,
+            )
+            .unwrap();
+        builder
+            .add_partial_fill_change_output(
+                Amount::new(1_000_000, TokenId::from(2)),
+                &ReservedSubaddresses::from(&alice),
+                &mut rng,
+            )
+            .unwrap();
+        let sci = builder.build(&NoKeysRingSigner {}, &mut rng).unwrap();
+        sci.validate().unwrap();
+
+        // Neither the swap code nor the transaction account for partial fill rules,
+        // so the offer is refused.
+        let assert_partial_fill_refused = |result: Result<(), GrpcError>| match result {
+            Err(GrpcError::RpcFailure(status)) => {
+                assert_eq!(status.code(), RpcStatusCode::INVALID_ARGUMENT);
+                assert!(status.message().contains("partial fill"));
+            }
+            result => panic!("Unexpected result {:?}", result),
+        };
+
+        let mut wrapper = api::printable::PrintableWrapper::new();
+        wrapper.set_signed_contingent_input((&sci).into());
+        let mut request = api::ParseSwapCodeRequest::new();
+        request.set_b58_code(wrapper.b58_encode().unwrap());
+        assert_partial_fill_refused(client.parse_swap_code(&request).map(|_| ()));
+
+        let bob_utxos = mobilecoind_db
+            .get_utxos_for_subaddress(&bob_monitor_id, 0)
+            .unwrap();
+        let mut request = api::GenerateSwapTxRequest::new();
+        request.set_sender_monitor_id(bob_monitor_id.to_vec());
+        request.set_change_subaddress(0);
+        request.set_input_list(RepeatedField::from_vec(
+            bob_utxos.iter().map(api::UnspentTxOut::from).collect(),
+        ));
+        request.set_sci((&sci).into());
+        assert_partial_fill_refused(client.generate_swap_tx(&request).map(|_| ()));
+
+        // A malformed signed contingent input is an invalid argument too.
+        let mut malformed = mc_api::external::SignedContingentInput::from(&sci);
+        malformed
+            .mut_pseudo_output_amount()
+            .mut_blinding()
+            .set_data(vec![1, 2, 3]);
+        request.set_sci(malformed.clone());
+        match client.generate_swap_tx(&request) {
+            Err(GrpcError::RpcFailure(status)) => {
+                assert_eq!(status.code(), RpcStatusCode::INVALID_ARGUMENT)
+            }
+            result => panic!("Unexpected result {:?}", result),
+        }
+
+        let mut wrapper = api::printable::PrintableWrapper::new();
+        wrapper.set_signed_contingent_input(malformed);
+        let mut request = api::ParseSwapCodeRequest::new();
+        request.set_b58_code(wrapper.b58_encode().unwrap());
+        match client.parse_swap_code(&request) {
+            Err(GrpcError::RpcFailure(status)) => {
+                assert_eq!(status.code(), RpcStatusCode::INVALID_ARGUMENT)
+            }
+            result => panic!("Unexpected result {:?}", result),
+        }
+    }
+
     #[test_with_logger]
     fn test_generate_tx_from_tx_out_list(logger: Logger) {
         let mut rng: StdRng = SeedableRng::from_seed([23u8; 32]);
@@ -4388,10 +5749,12 @@ mod test {
             Outlay {
                 value: 123,
                 receiver: receiver1.default_subaddress(),
+                token_id: Mob::ID,
             },
             Outlay {
                 value: 456,
                 receiver: receiver2.default_subaddress(),
+                token_id: Mob::ID,
             },
         ];
 
@@ -4527,6 +5890,188 @@ mod test {
         }
     }
 
+    #[test_with_logger]
+    fn test_view_only_generate_and_submit_tx(logger: Logger) {
+        let mut rng: StdRng = SeedableRng::from_seed([23u8; 32]);
+
+        let sender = AccountKey::random(&mut rng);
+
+        // 1 known recipient, 3 random recipients and no monitors.
+        let (ledger_db, mobilecoind_db, client, _server, server_conn_manager) =
+            get_testing_environment(
+                BLOCK_VERSION,
+                3,
+                &[sender.default_subaddress()],
+                &[],
+                logger.clone(),
+                &mut rng,
+            );
+
+        // Add a view-only monitor for the sender.
+        let mut request = api::AddMonitorRequest::new();
+        request.set_view_only_account_key(api::ViewOnlyAccountKey::from(&ViewAccountKey::from(
+            &sender,
+        )));
+        request.set_first_subaddress(0);
+        request.set_num_subaddresses(20);
+        let response = client.add_monitor(&request).unwrap();
+        let monitor_id = MonitorId::try_from(&response.monitor_id).unwrap();
+
+        // Setting both keys is rejected.
+        request.set_account_key((&sender).into());
+        assert!(client.add_monitor(&request).is_err());
+
+        // Allow the new monitor to process the ledger.
+        wait_for_monitors(&mobilecoind_db, &ledger_db, &logger);
+
+        // The monitor status only exposes view keys.
+        let mut request = api::GetMonitorStatusRequest::new();
+        request.set_monitor_id(monitor_id.to_vec());
+        let status = client.get_monitor_status(&request).unwrap();
+        assert!(!status.get_status().has_account_key());
+        assert_eq!(
+            ViewAccountKey::try_from(status.get_status().get_view_only_account_key()).unwrap(),
+            ViewAccountKey::from(&sender)
+        );
+
+        // Utxos are stored under placeholder key images.
+        let utxos = mobilecoind_db
+            .get_utxos_for_subaddress(&monitor_id, 0)
+            .unwrap();
+        assert!(!utxos.is_empty());
+        for utxo in utxos.iter() {
+            assert_eq!(
+                utxo.key_image,
+                UnspentTxOut::placeholder_key_image(&utxo.tx_out)
+            );
+        }
+
+        let receiver = AccountKey::random(&mut rng);
+        let outlays = vec![Outlay {
+            value: 123,
+            receiver: receiver.default_subaddress(),
+            token_id: Mob::ID,
+        }];
+
+        // Generate tx returns an unsigned proposal.
+        let mut request = api::GenerateTxRequest::new();
+        request.set_sender_monitor_id(monitor_id.to_vec());
+        request.set_change_subaddress(0);
+        request.set_input_list(RepeatedField::from_vec(
+            utxos.iter().map(api::UnspentTxOut::from).collect(),
+        ));
+        request.set_outlay_list(RepeatedField::from_vec(
+            outlays.iter().map(api::Outlay::from).collect(),
+        ));
+
+        let response = client.generate_tx(&request).unwrap();
+        assert!(!response.has_tx_proposal());
+        let unsigned_tx_proposal =
+            UnsignedTxProposal::try_from(response.get_unsigned_tx_proposal()).unwrap();
+        assert_eq!(unsigned_tx_proposal.outlays, outlays);
+
+        // The offline signer checks the outputs against the outlays before signing.
+        unsigned_tx_proposal.check_outputs().unwrap();
+        {
+            let mut bad_unsigned_tx_proposal = unsigned_tx_proposal.clone();
+            bad_unsigned_tx_proposal.outlays[0].value += 1;
+            assert_matches!(
+                bad_unsigned_tx_proposal.check_outputs(),
+                Err(Error::UnexpectedTxOutputs(_))
+            );
+
+            let mut bad_unsigned_tx_proposal = unsigned_tx_proposal.clone();
+            bad_unsigned_tx_proposal.unsigned_tx.output_secrets[0]
+                .amount
+                .value += 1;
+            assert_matches!(
+                bad_unsigned_tx_proposal.check_outputs(),
+                Err(Error::UnexpectedTxOutputs(_))
+            );
+        }
+
+        // It also checks who the outlays and the change pay.
+        unsigned_tx_proposal.check_recipients(&sender).unwrap();
+        {
+            let mut bad_unsigned_tx_proposal = unsigned_tx_proposal.clone();
+            bad_unsigned_tx_proposal.outlays[0].receiver =
+                AccountKey::random(&mut rng).default_subaddress();
+            assert_matches!(
+                bad_unsigned_tx_proposal.check_recipients(&sender),
+                Err(Error::UnexpectedTxOutputs(_))
+            );
+
+            let mut bad_unsigned_tx_proposal = unsigned_tx_proposal.clone();
+            bad_unsigned_tx_proposal.outlay_shared_secrets[0] =
+                RistrettoPublic::from_random(&mut rng);
+            assert_matches!(
+                bad_unsigned_tx_proposal.check_recipients(&sender),
+                Err(Error::UnexpectedTxOutputs(_))
+            );
+
+            // The change output pays subaddress 0, not 1.
+            let mut bad_unsigned_tx_proposal = unsigned_tx_proposal.clone();
+            bad_unsigned_tx_proposal.change_subaddress = 1;
+            assert_matches!(
+                bad_unsigned_tx_proposal.check_recipients(&sender),
+                Err(Error::UnexpectedTxOutputs(_))
+            );
+
+            // Nor does it pay another account.
+            assert_matches!(
+                unsigned_tx_proposal.check_recipients(&AccountKey::random(&mut rng)),
+                Err(Error::UnexpectedTxOutputs(_))
+            );
+        }
+
+        // Sign it, as the offline signer would.
+        let tx_proposal = unsigned_tx_proposal
+            .sign(&LocalRingSigner::from(&sender), &mut rng)
+            .unwrap();
+        let placeholders: Vec<KeyImage> = tx_proposal
+            .utxos
+            .iter()
+            .map(|utxo| UnspentTxOut::placeholder_key_image(&utxo.tx_out))
+            .collect();
+        assert_eq!(
+            HashSet::from_iter(tx_proposal.utxos.iter().map(|utxo| utxo.key_image)),
+            HashSet::from_iter(tx_proposal.tx.key_images()),
+        );
+
+        // A proposal claiming the wrong key image for a view-only utxo is rejected.
+        {
+            let mut bad_tx_proposal = tx_proposal.clone();
+            bad_tx_proposal.utxos[0].key_image = KeyImage::from(1234);
+
+            let mut request = api::SubmitTxRequest::new();
+            request.set_tx_proposal(api::TxProposal::from(&bad_tx_proposal));
+            assert!(client.submit_tx(&request).is_err());
+        }
+
+        // Submitting the signed proposal re-keys the spent utxos.
+        let mut request = api::SubmitTxRequest::new();
+        request.set_tx_proposal(api::TxProposal::from(&tx_proposal));
+        client.submit_tx(&request).unwrap();
+
+        let num_submitted: usize = server_conn_manager
+            .conns()
+            .iter()
+            .map(|mock_peer| mock_peer.read().proposed_txs.len())
+            .sum();
+        assert_eq!(num_submitted, 1);
+
+        for (utxo, placeholder) in tx_proposal.utxos.iter().zip(placeholders.iter()) {
+            let subaddress_id = mobilecoind_db
+                .get_subaddress_id_by_utxo_id(&UtxoId::from(&utxo.key_image))
+                .unwrap();
+            assert_eq!(subaddress_id.monitor_id, monitor_id);
+            assert_matches!(
+                mobilecoind_db.get_subaddress_id_by_utxo_id(&UtxoId::from(placeholder)),
+                Err(Error::UtxoIdNotFound)
+            );
+        }
+    }
+
     #[test_with_logger]
     fn test_get_balance_impl(logger: Logger) {
         let mut rng: StdRng = SeedableRng::from_seed([23u8; 32]);
@@ -4640,10 +6185,12 @@ mod test {
             Outlay {
                 value: 123,
                 receiver: receiver1.default_subaddress(),
+                token_id: Mob::ID,
             },
             Outlay {
                 value: 456,
                 receiver: receiver2.default_subaddress(),
+                token_id: Mob::ID,
             },
         ];
 
@@ -4768,6 +6315,314 @@ mod test {
         assert_eq!(matched_utxos, tx_proposal.utxos.len());
     }
 
+    #[test_with_logger]
+    fn test_send_payment_with_multi_token_outlays(logger: Logger) {
+        let mut rng: StdRng = SeedableRng::from_seed([23u8; 32]);
+
+        let sender = AccountKey::random(&mut rng);
+        let data = MonitorData::new(
+            sender.clone(),
+            0,  // first_subaddress
+            20, // num_subaddresses
+            0,  // first_block
+            "", // name
+        )
+        .unwrap();
+
+        // 1 known recipient, 3 random recipients and no monitors.
+        let (mut ledger_db, mobilecoind_db, client, _server, server_conn_manager) =
+            get_testing_environment(
+                BLOCK_VERSION,
+                3,
+                &[sender.default_subaddress()],
+                &[],
+                logger.clone(),
+                &mut rng,
+            );
+
+        // Add a block with a non-MOB token ID.
+        add_block_to_ledger(
+            &mut ledger_db,
+            BLOCK_VERSION,
+            &[sender.default_subaddress()],
+            Amount::new(1_000_000_000_000, TokenId::from(2)),
+            &[KeyImage::from(101)],
+            &mut rng,
+        )
+        .unwrap();
+
+        // Insert into database.
+        let monitor_id = mobilecoind_db.add_monitor(&data).unwrap();
+
+        // Allow the new monitor to process the ledger.
+        wait_for_monitors(&mobilecoind_db, &ledger_db, &logger);
+
+        // Generate two random recipients, one paid in MOB and one in TokenId(2).
+        let receiver1 = AccountKey::random(&mut rng);
+        let receiver2 = AccountKey::random(&mut rng);
+
+        let outlays = vec![
+            Outlay {
+                value: 123,
+                receiver: receiver1.default_subaddress(),
+                token_id: Mob::ID,
+            },
+            Outlay {
+                value: 456,
+                receiver: receiver2.default_subaddress(),
+                token_id: TokenId::from(2),
+            },
+        ];
+
+        // Call send payment, with the fee in MOB.
+        let mut request = api::SendPaymentRequest::new();
+        request.set_sender_monitor_id(monitor_id.to_vec());
+        request.set_sender_subaddress(0);
+        request.set_outlay_list(RepeatedField::from_vec(
+            outlays.iter().map(api::Outlay::from).collect(),
+        ));
+        request.set_multi_token_outlays(true);
+
+        let response = client.send_payment(&request).unwrap();
+
+        // A single transaction was submitted.
+        let proposed_txs: Vec<Tx> = server_conn_manager
+            .conns()
+            .iter()
+            .flat_map(|mock_peer| mock_peer.read().proposed_txs.clone())
+            .collect();
+        assert_eq!(proposed_txs.len(), 1);
+        let submitted_tx = &proposed_txs[0];
+        assert_eq!(
+            submitted_tx,
+            &Tx::try_from(response.get_tx_proposal().get_tx()).unwrap()
+        );
+        assert_eq!(submitted_tx.prefix.fee_token_id, *Mob::ID);
+
+        // There is an output for each outlay, and change in each token id.
+        assert_eq!(response.get_receiver_tx_receipt_list().len(), outlays.len());
+        assert_eq!(submitted_tx.prefix.outputs.len(), outlays.len() + 2);
+
+        // Each receiver got their outlay in its token id.
+        for (outlay, receiver) in outlays.iter().zip([&receiver1, &receiver2]) {
+            let amounts: Vec<Amount> = submitted_tx
+                .prefix
+                .outputs
+                .iter()
+                .filter_map(|tx_out| {
+                    let shared_secret = get_tx_out_shared_secret(
+                        receiver.view_private_key(),
+                        &RistrettoPublic::try_from(&tx_out.public_key).unwrap(),
+                    );
+                    let (amount, _blinding) =
+                        tx_out.masked_amount.get_value(&shared_secret).ok()?;
+                    Some(amount)
+                })
+                .collect();
+            assert_eq!(amounts, vec![Amount::new(outlay.value, outlay.token_id)]);
+        }
+    }
+
+    #[test_with_logger]
+    fn test_send_payment_with_multi_token_outlays_requires_mixed_transactions(logger: Logger) {
+        let mut rng: StdRng = SeedableRng::from_seed([23u8; 32]);
+
+        // Block version 2 supports token ids, but not mixed transactions.
+        let block_version = BlockVersion::TWO;
+        assert!(!block_version.mixed_transactions_are_supported());
+
+        let sender = AccountKey::random(&mut rng);
+        let data = MonitorData::new(
+            sender.clone(),
+            0,  // first_subaddress
+            20, // num_subaddresses
+            0,  // first_block
+            "", // name
+        )
+        .unwrap();
+
+        let (mut ledger_db, mobilecoind_db, client, _server, server_conn_manager) =
+            get_testing_environment(
+                block_version,
+                3,
+                &[sender.default_subaddress()],
+                &[],
+                logger.clone(),
+                &mut rng,
+            );
+
+        add_block_to_ledger(
+            &mut ledger_db,
+            block_version,
+            &[sender.default_subaddress()],
+            Amount::new(1_000_000_000_000, TokenId::from(2)),
+            &[KeyImage::from(101)],
+            &mut rng,
+        )
+        .unwrap();
+
+        let monitor_id = mobilecoind_db.add_monitor(&data).unwrap();
+        wait_for_monitors(&mobilecoind_db, &ledger_db, &logger);
+
+        let outlays = vec![
+            Outlay {
+                value: 123,
+                receiver: AccountKey::random(&mut rng).default_subaddress(),
+                token_id: Mob::ID,
+            },
+            Outlay {
+                value: 456,
+                receiver: AccountKey::random(&mut rng).default_subaddress(),
+                token_id: TokenId::from(2),
+            },
+        ];
+
+        // With an explicit fee, the block version is that of the ledger rather
+        // than the one reported by the (mock) network.
+        let mut request = api::SendPaymentRequest::new();
+        request.set_sender_monitor_id(monitor_id.to_vec());
+        request.set_sender_subaddress(0);
+        request.set_outlay_list(RepeatedField::from_vec(
+            outlays.iter().map(api::Outlay::from).collect(),
+        ));
+        request.set_multi_token_outlays(true);
+        request.set_fee(Mob::MINIMUM_FEE);
+
+        assert!(client.send_payment(&request).is_err());
+
+        // Nothing was submitted.
+        for mock_peer in server_conn_manager.conns() {
+            assert!(mock_peer.read().proposed_txs.is_empty());
+        }
+
+        // Outlays in a single token id are still fine.
+        request.set_outlay_list(RepeatedField::from_vec(vec![api::Outlay::from(
+            &outlays[0],
+        )]));
+        client.send_payment(&request).unwrap();
+    }
+
+    #[test_with_logger]
+    fn test_send_payment_for_payment_request(logger: Logger) {
+        let mut rng: StdRng = SeedableRng::from_seed([23u8; 32]);
+
+        let sender = AccountKey::random(&mut rng);
+        let data = MonitorData::new(
+            sender.clone(),
+            0,  // first_subaddress
+            20, // num_subaddresses
+            0,  // first_block
+            "", // name
+        )
+        .unwrap();
+
+        // 1 known recipient, 3 random recipients and no monitors.
+        let (ledger_db, mobilecoind_db, client, _server, _server_conn_manager) =
+            get_testing_environment(
+                BLOCK_VERSION,
+                3,
+                &[sender.subaddress(1)],
+                &[],
+                logger.clone(),
+                &mut rng,
+            );
+
+        // Insert into database.
+        let monitor_id = mobilecoind_db.add_monitor(&data).unwrap();
+
+        // Allow the new monitor to process the ledger.
+        wait_for_monitors(&mobilecoind_db, &ledger_db, &logger);
+
+        let receiver = AccountKey::random(&mut rng);
+        let outlay = Outlay {
+            value: 123,
+            receiver: receiver.default_subaddress(),
+            token_id: Mob::ID,
+        };
+
+        // The payment request id in the memo of the receiver's output, which must be
+        // authenticated by the subaddress the payment was sent from.
+        let payment_request_id_in_memo = |response: &api::SendPaymentResponse| {
+            let tx = Tx::try_from(response.get_tx_proposal().get_tx()).unwrap();
+            let receiver_tx_out = tx
+                .prefix
+                .outputs
+                .iter()
+                .find(|tx_out| tx_out.view_key_match(receiver.view_private_key()).is_ok())
+                .expect("Didn't find receiver's output");
+
+            let ss = get_tx_out_shared_secret(
+                receiver.view_private_key(),
+                &RistrettoPublic::try_from(&receiver_tx_out.public_key).unwrap(),
+            );
+            let memo = receiver_tx_out.e_memo.unwrap().decrypt(&ss);
+            match MemoType::try_from(&memo).expect("Couldn't decrypt memo") {
+                MemoType::AuthenticatedSenderWithPaymentRequestId(memo) => {
+                    assert_eq!(
+                        memo.sender_address_hash(),
+                        ShortAddressHash::from(&sender.subaddress(1))
+                    );
+                    memo.payment_request_id()
+                }
+                memo_type => panic!("Unexpected memo {:?}", memo_type),
+            }
+        };
+
+        let assert_invalid_arg =
+            |request: &api::SendPaymentRequest| match client.send_payment(request) {
+                Err(GrpcError::RpcFailure(status)) => {
+                    assert_eq!(status.code(), RpcStatusCode::INVALID_ARGUMENT);
+                }
+                result => panic!("Unexpected result {:?}", result),
+            };
+
+        let mut request = api::SendPaymentRequest::new();
+        request.set_sender_monitor_id(monitor_id.to_vec());
+        request.set_sender_subaddress(1);
+        request.set_outlay_list(RepeatedField::from_vec(vec![api::Outlay::from(&outlay)]));
+        request.set_payment_request_id(42);
+
+        // Expired payment requests are refused.
+        request.set_expiration_timestamp(1);
+        assert_invalid_arg(&request);
+
+        // Payment request ids cannot be paid with multi-token outlays.
+        request.set_expiration_timestamp(u64::MAX);
+        request.set_multi_token_outlays(true);
+        assert_invalid_arg(&request);
+        request.set_multi_token_outlays(false);
+
+        // Payment requests that have not expired are paid, and the payment request
+        // id is in the memo of the receiver's output.
+        let response = client.send_payment(&request).unwrap();
+        assert_eq!(payment_request_id_in_memo(&response), 42);
+
+        // When paying a request code, its id and expiration timestamp are used.
+        let request_code = |expiration_timestamp| {
+            let mut payment_request = mc_api::printable::PaymentRequest::new();
+            payment_request.set_public_address((&receiver.default_subaddress()).into());
+            payment_request.set_value(123);
+            payment_request.set_payment_request_id(43);
+            payment_request.set_expiration_timestamp(expiration_timestamp);
+
+            let mut wrapper = mc_api::printable::PrintableWrapper::new();
+            wrapper.set_payment_request(payment_request);
+            wrapper.b58_encode().unwrap()
+        };
+
+        // The id and expiration timestamp cannot also be given by hand.
+        request.set_payment_request_b58_code(request_code(u64::MAX));
+        assert_invalid_arg(&request);
+
+        request.set_payment_request_id(0);
+        request.set_expiration_timestamp(0);
+        let response = client.send_payment(&request).unwrap();
+        assert_eq!(payment_request_id_in_memo(&response), 43);
+
+        request.set_payment_request_b58_code(request_code(1));
+        assert_invalid_arg(&request);
+    }
+
     #[test_with_logger]
     fn test_send_payment_with_max_input_utxo_value(logger: Logger) {
         let mut rng: StdRng = SeedableRng::from_seed([23u8; 32]);
@@ -4825,10 +6680,12 @@ mod test {
             Outlay {
                 value: 10,
                 receiver: receiver1.default_subaddress(),
+                token_id: Mob::ID,
             },
             Outlay {
                 value: 20,
                 receiver: receiver2.default_subaddress(),
+                token_id: Mob::ID,
             },
         ];
 
@@ -4969,10 +6826,12 @@ mod test {
             Outlay {
                 value: 123,
                 receiver: receiver1.default_subaddress(),
+                token_id: Mob::ID,
             },
             Outlay {
                 value: 456,
                 receiver: receiver2.default_subaddress(),
+                token_id: Mob::ID,
             },
         ];
 
@@ -5093,10 +6952,12 @@ mod test {
             Outlay {
                 value: 123,
                 receiver: receiver1.default_subaddress(),
+                token_id: Mob::ID,
             },
             Outlay {
                 value: 456,
                 receiver: receiver2.default_subaddress(),
+                token_id: Mob::ID,
             },
         ];
 
@@ -5423,6 +7284,80 @@ mod test {
         }
     }
 
+    #[test_with_logger]
+    fn test_invoice_request_code(logger: Logger) {
+        let mut rng: StdRng = SeedableRng::from_seed([23u8; 32]);
+
+        // no known recipient, 3 random recipients and no monitors.
+        let (_ledger_db, _mobilecoind_db, client, _server, _server_conn_manager) =
+            get_testing_environment(BLOCK_VERSION, 3, &[], &[], logger.clone(), &mut rng);
+
+        // Random receiver address.
+        let receiver = AccountKey::random(&mut rng).default_subaddress();
+
+        let line_items = [("coffee", 300), ("bagel", 700)]
+            .iter()
+            .map(|(description, value)| {
+                let mut line_item = mc_api::printable::PaymentRequestLineItem::new();
+                line_item.set_description(description.to_string());
+                line_item.set_value(*value);
+                line_item
+            })
+            .collect::<Vec<_>>();
+        let in_an_hour = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs()
+            + 3600;
+
+        // An invoice round-trips.
+        {
+            let mut request = api::CreateRequestCodeRequest::new();
+            request.set_receiver(mc_api::external::PublicAddress::from(&receiver));
+            request.set_value(1000);
+            request.set_memo("breakfast".to_owned());
+            request.set_payment_request_id(42);
+            request.set_expiration_timestamp(in_an_hour);
+            request.set_line_items(RepeatedField::from_vec(line_items.clone()));
+
+            let response = client.create_request_code(&request).unwrap();
+
+            let mut request = api::ParseRequestCodeRequest::new();
+            request.set_b58_code(response.get_b58_code().to_string());
+
+            let response = client.parse_request_code(&request).unwrap();
+            assert_eq!(
+                PublicAddress::try_from(response.get_receiver()).unwrap(),
+                receiver
+            );
+            assert_eq!(response.value, 1000);
+            assert_eq!(response.get_memo(), "breakfast");
+            assert_eq!(response.get_payment_request_id(), 42);
+            assert_eq!(response.get_expiration_timestamp(), in_an_hour);
+            assert_eq!(response.get_line_items(), &line_items[..]);
+        }
+
+        // Line items must add up to the value.
+        {
+            let mut request = api::CreateRequestCodeRequest::new();
+            request.set_receiver(mc_api::external::PublicAddress::from(&receiver));
+            request.set_value(999);
+            request.set_line_items(RepeatedField::from_vec(line_items));
+
+            assert!(client.create_request_code(&request).is_err());
+        }
+
+        // Expired requests cannot be created.
+        {
+            let mut request = api::CreateRequestCodeRequest::new();
+            request.set_receiver(mc_api::external::PublicAddress::from(&receiver));
+            request.set_value(1000);
+            request.set_expiration_timestamp(1);
+
+            assert!(client.create_request_code(&request).is_err());
+        }
+    }
+
     #[test_with_logger]
     fn test_transfer_code_root_entropy(logger: Logger) {
         let mut rng: StdRng = SeedableRng::from_seed([23u8; 32]);
@@ -5729,6 +7664,21 @@ mod test {
             network_status.local_block_index,
             ledger_db.num_blocks().unwrap() - 1
         );
+
+        // Every peer was polled successfully, and is healthy.
+        assert_eq!(
+            network_status.get_peer_health().len(),
+            network_status.get_peer_block_index_map().len()
+        );
+        for peer_health in network_status.get_peer_health() {
+            assert!(network_status
+                .get_peer_block_index_map()
+                .contains_key(&peer_health.responder_id));
+            assert_eq!(peer_health.block_index, ledger_db.num_blocks().unwrap() - 1);
+            assert_eq!(peer_health.blocks_behind, 0);
+            assert!(!peer_health.is_quarantined);
+            assert!(peer_health.score > 0.0);
+        }
     }
 
     #[test_with_logger]
@@ -5757,7 +7707,9 @@ mod test {
             );
 
         let mut request = api::AddMonitorRequest::new();
-        request.set_account_key(mc_api::external::AccountKey::from(&data.account_key));
+        request.set_account_key(mc_api::external::AccountKey::from(
+            data.account_key().unwrap(),
+        ));
         request.set_first_subaddress(data.first_subaddress);
         request.set_num_subaddresses(data.num_subaddresses);
         request.set_first_block(data.first_block);
@@ -5846,7 +7798,9 @@ mod test {
 
         // Re-add the monitor.
         let mut request = api::AddMonitorRequest::new();
-        request.set_account_key(mc_api::external::AccountKey::from(&data.account_key));
+        request.set_account_key(mc_api::external::AccountKey::from(
+            data.account_key().unwrap(),
+        ));
         request.set_first_subaddress(data.first_subaddress);
         request.set_num_subaddresses(data.num_subaddresses);
         request.set_first_block(data.first_block);
@@ -23,7 +23,8 @@
 use crate::{
     database::Database,
     error::Error,
-    monitor_store::{MonitorData, MonitorId},
+    monitor_events::MonitorEventNotifier,
+    monitor_store::{MonitorData, MonitorId, MonitorKey},
     subaddress_store::SubaddressSPKId,
     utxo_store::UnspentTxOut,
 };
@@ -80,6 +81,7 @@ impl SyncThread {
     pub fn start(
         ledger_db: LedgerDB,
         mobilecoind_db: Database,
+        notifier: MonitorEventNotifier,
         num_workers: Option<usize>,
         logger: Logger,
     ) -> Self {
@@ -96,6 +98,7 @@ impl SyncThread {
         for idx in 0..num_workers.unwrap_or_else(num_cpus::get) {
             let thread_ledger_db = ledger_db.clone();
             let thread_mobilecoind_db = mobilecoind_db.clone();
+            let thread_notifier = notifier.clone();
             let thread_sender = sender.clone();
             let thread_receiver = receiver.clone();
             let thread_queued_monitor_ids = queued_monitor_ids.clone();
@@ -106,6 +109,7 @@ impl SyncThread {
                     sync_thread_entry_point(
                         thread_ledger_db,
                         thread_mobilecoind_db,
+                        thread_notifier,
                         thread_sender,
                         thread_receiver,
                         thread_queued_monitor_ids,
@@ -234,6 +238,7 @@ impl Drop for SyncThread {
 fn sync_thread_entry_point(
     ledger_db: LedgerDB,
     mobilecoind_db: Database,
+    notifier: MonitorEventNotifier,
     sender: crossbeam_channel::Sender<SyncMsg>,
     receiver: crossbeam_channel::Receiver<SyncMsg>,
     queued_monitor_ids: Arc<Mutex<HashSet<MonitorId>>>,
@@ -242,7 +247,7 @@ fn sync_thread_entry_point(
     for msg in receiver.iter() {
         match msg {
             SyncMsg::SyncMonitor(monitor_id) => {
-                match sync_monitor(&ledger_db, &mobilecoind_db, &monitor_id, &logger) {
+                match sync_monitor(&ledger_db, &mobilecoind_db, &notifier, &monitor_id, &logger) {
                     // Success - No more blocks are currently available.
                     Ok(SyncMonitorOk::NoMoreBlocks) => {
                         // Remove the monitor id from the list of queued ones so that the main
@@ -289,6 +294,7 @@ fn sync_thread_entry_point(
 fn sync_monitor(
     ledger_db: &LedgerDB,
     mobilecoind_db: &Database,
+    notifier: &MonitorEventNotifier,
     monitor_id: &MonitorId,
     logger: &Logger,
 ) -> Result<SyncMonitorOk, Error> {
@@ -331,6 +337,9 @@ fn sync_monitor(
             &utxos,
             &block_contents.key_images,
         )?;
+
+        // Let monitor event subscribers know there is new data available.
+        notifier.notify();
     }
 
     Ok(SyncMonitorOk::MoreBlocksPotentiallyAvailable)
@@ -344,7 +353,7 @@ fn match_tx_outs_into_utxos(
     monitor_data: &MonitorData,
     logger: &Logger,
 ) -> Result<Vec<UnspentTxOut>, Error> {
-    let account_key = &monitor_data.account_key;
+    let monitor_key = monitor_data.monitor_key();
     let mut results = Vec::new();
 
     for tx_out in outputs {
@@ -353,7 +362,7 @@ fn match_tx_outs_into_utxos(
         let tx_public_key = RistrettoPublic::try_from(&tx_out.public_key)?;
 
         let subaddress_spk = SubaddressSPKId::from(&recover_public_subaddress_spend_key(
-            account_key.view_private_key(),
+            monitor_key.view_private_key(),
             &tx_out_target_key,
             &tx_public_key,
         ));
@@ -380,20 +389,27 @@ fn match_tx_outs_into_utxos(
         assert_eq!(monitor_id, &subaddress_id.monitor_id);
 
         let shared_secret =
-            get_tx_out_shared_secret(account_key.view_private_key(), &tx_public_key);
+            get_tx_out_shared_secret(monitor_key.view_private_key(), &tx_public_key);
 
         let (amount, _blinding) = tx_out
             .masked_amount
             .get_value(&shared_secret)
             .expect("Malformed amount"); // TODO
 
-        let onetime_private_key = recover_onetime_private_key(
-            &tx_public_key,
-            account_key.view_private_key(),
-            &account_key.subaddress_spend_private(subaddress_id.index),
-        );
-
-        let key_image = KeyImage::from(&onetime_private_key);
+        // View-only monitors cannot compute key images, so their outputs are
+        // stored under a placeholder until a signed transaction spending them
+        // reveals the real key image.
+        let key_image = match monitor_key {
+            MonitorKey::AccountKey(account_key) => {
+                let onetime_private_key = recover_onetime_private_key(
+                    &tx_public_key,
+                    account_key.view_private_key(),
+                    &account_key.subaddress_spend_private(subaddress_id.index),
+                );
+                KeyImage::from(&onetime_private_key)
+            }
+            MonitorKey::ViewAccountKey(_) => UnspentTxOut::placeholder_key_image(tx_out),
+        };
 
         results.push(UnspentTxOut {
             tx_out: tx_out.clone(),
@@ -419,7 +435,7 @@ mod test {
             DEFAULT_PER_RECIPIENT_AMOUNT,
         },
     };
-    use mc_account_keys::{AccountKey, PublicAddress, DEFAULT_SUBADDRESS_INDEX};
+    use mc_account_keys::{AccountKey, PublicAddress, ViewAccountKey, DEFAULT_SUBADDRESS_INDEX};
     use mc_common::logger::{test_with_logger, Logger};
     use mc_transaction_core::{tokens::Mob, tx::TxOut, Amount, Token};
     use rand::{rngs::StdRng, SeedableRng};
@@ -440,6 +456,7 @@ mod test {
         .unwrap();
 
         let monitor_id = MonitorId::from(&data);
+        let notifier = MonitorEventNotifier::default();
 
         let recipients: Vec<PublicAddress> = account_keys
             .iter()
@@ -488,7 +505,8 @@ mod test {
         assert_eq!(monitor_data.next_block, 0);
 
         // Process the first MAX_BLOCKS_PROCESSING_CHUNK_SIZE blocks.
-        let result = sync_monitor(&ledger_db, &mobilecoind_db, &monitor_id, &logger).unwrap();
+        let result =
+            sync_monitor(&ledger_db, &mobilecoind_db, &notifier, &monitor_id, &logger).unwrap();
         assert_eq!(result, SyncMonitorOk::MoreBlocksPotentiallyAvailable);
 
         // We should now discover some outputs. Each block has 1 output per recipient,
@@ -513,7 +531,8 @@ mod test {
         }
 
         // Process the second MAX_BLOCKS_PROCESSING_CHUNK_SIZE blocks.
-        let result = sync_monitor(&ledger_db, &mobilecoind_db, &monitor_id, &logger).unwrap();
+        let result =
+            sync_monitor(&ledger_db, &mobilecoind_db, &notifier, &monitor_id, &logger).unwrap();
         assert_eq!(result, SyncMonitorOk::MoreBlocksPotentiallyAvailable);
 
         let monitor_data = mobilecoind_db.get_monitor_data(&monitor_id).unwrap();
@@ -536,7 +555,8 @@ mod test {
         }
 
         // Process the last remaining block.
-        let result = sync_monitor(&ledger_db, &mobilecoind_db, &monitor_id, &logger).unwrap();
+        let result =
+            sync_monitor(&ledger_db, &mobilecoind_db, &notifier, &monitor_id, &logger).unwrap();
         assert_eq!(result, SyncMonitorOk::NoMoreBlocks);
 
         let monitor_data = mobilecoind_db.get_monitor_data(&monitor_id).unwrap();
@@ -556,7 +576,8 @@ mod test {
         }
 
         // Calling sync_monitor again should not change the results.
-        let result = sync_monitor(&ledger_db, &mobilecoind_db, &monitor_id, &logger).unwrap();
+        let result =
+            sync_monitor(&ledger_db, &mobilecoind_db, &notifier, &monitor_id, &logger).unwrap();
         assert_eq!(result, SyncMonitorOk::NoMoreBlocks);
 
         let monitor_data = mobilecoind_db.get_monitor_data(&monitor_id).unwrap();
@@ -591,7 +612,8 @@ mod test {
         )
         .unwrap();
 
-        let result = sync_monitor(&ledger_db, &mobilecoind_db, &monitor_id, &logger).unwrap();
+        let result =
+            sync_monitor(&ledger_db, &mobilecoind_db, &notifier, &monitor_id, &logger).unwrap();
         assert_eq!(result, SyncMonitorOk::NoMoreBlocks);
 
         let utxos = mobilecoind_db
@@ -618,6 +640,7 @@ mod test {
         .unwrap();
 
         let monitor_id = MonitorId::from(&data);
+        let notifier = MonitorEventNotifier::default();
 
         let recipients: Vec<PublicAddress> = account_keys
             .iter()
@@ -638,7 +661,8 @@ mod test {
         assert_eq!(mobilecoind_db.add_monitor(&data).unwrap(), monitor_id);
 
         // Sync.
-        let result = sync_monitor(&ledger_db, &mobilecoind_db, &monitor_id, &logger).unwrap();
+        let result =
+            sync_monitor(&ledger_db, &mobilecoind_db, &notifier, &monitor_id, &logger).unwrap();
         assert_eq!(result, SyncMonitorOk::NoMoreBlocks);
 
         // Should have a single non-zero utxo for our monitor.
@@ -660,7 +684,8 @@ mod test {
         )
         .unwrap();
 
-        let result = sync_monitor(&ledger_db, &mobilecoind_db, &monitor_id, &logger).unwrap();
+        let result =
+            sync_monitor(&ledger_db, &mobilecoind_db, &notifier, &monitor_id, &logger).unwrap();
         assert_eq!(result, SyncMonitorOk::NoMoreBlocks);
 
         // We should now have only a zero utxo.
@@ -671,4 +696,69 @@ mod test {
         assert_eq!(utxos.len(), 1);
         assert_eq!(utxos[0].value, 0);
     }
+
+    // View-only monitors discover outputs, storing them under placeholder key
+    // images since they cannot compute the real ones.
+    #[test_with_logger]
+    fn test_sync_view_only_monitor(logger: Logger) {
+        let mut rng: StdRng = SeedableRng::from_seed([98u8; 32]);
+
+        let account_keys: Vec<_> = (0..2).map(|_i| AccountKey::random(&mut rng)).collect();
+
+        let data = MonitorData::new_view_only(
+            ViewAccountKey::from(&account_keys[0]),
+            DEFAULT_SUBADDRESS_INDEX, // first subaddress
+            5,                        // number of subaddresses
+            0,                        // first block
+            "",                       // name
+        )
+        .unwrap();
+
+        let monitor_id = MonitorId::from(&data);
+        let notifier = MonitorEventNotifier::default();
+
+        let recipients: Vec<PublicAddress> = account_keys
+            .iter()
+            .map(AccountKey::default_subaddress)
+            .collect();
+
+        // Fewer blocks than MAX_BLOCKS_PROCESSING_CHUNK_SIZE, so a single sync
+        // processes all of them.
+        let num_blocks = MAX_BLOCKS_PROCESSING_CHUNK_SIZE - 2;
+        let (ledger_db, mobilecoind_db) = get_test_databases(
+            BlockVersion::MAX,
+            0,
+            &recipients,
+            num_blocks,
+            logger.clone(),
+            &mut rng,
+        );
+
+        let account0_tx_outs: Vec<TxOut> = (0..num_blocks)
+            .map(|idx| {
+                let block_contents = ledger_db.get_block_contents(idx as u64).unwrap();
+                block_contents.outputs[0].clone()
+            })
+            .collect();
+
+        assert_eq!(mobilecoind_db.add_monitor(&data).unwrap(), monitor_id);
+
+        let result =
+            sync_monitor(&ledger_db, &mobilecoind_db, &notifier, &monitor_id, &logger).unwrap();
+        assert_eq!(result, SyncMonitorOk::NoMoreBlocks);
+
+        let utxos = mobilecoind_db
+            .get_utxos_for_subaddress(&monitor_id, DEFAULT_SUBADDRESS_INDEX)
+            .unwrap();
+        assert_eq!(utxos.len(), num_blocks);
+
+        for tx_out in account0_tx_outs.iter() {
+            let utxo = utxos
+                .iter()
+                .find(|utxo| utxo.tx_out == *tx_out)
+                .expect("tx out not found");
+            assert_eq!(utxo.key_image, UnspentTxOut::placeholder_key_image(tx_out));
+            assert_eq!(utxo.value, DEFAULT_PER_RECIPIENT_AMOUNT);
+        }
+    }
 }
@@ -163,6 +163,7 @@ pub fn setup_server<FPR: FogPubkeyResolver + Default + Send + Sync + 'static>(
         mobilecoind_db.clone(),
         conn_manager.clone(),
         fog_resolver_factory.unwrap_or_else(|| Arc::new(|_| Ok(FPR::default()))),
+        None,
         logger.clone(),
     );
 
@@ -0,0 +1,258 @@
+// Copyright (c) 2018-2022 The MobileCoin Foundation
+
+//! Strategies for selecting which UTXOs to spend in a transaction.
+//!
+//! All strategies are handed a list of UTXOs that were already filtered to a
+//! single token id, and that is known to contain a subset of at most
+//! `max_inputs` UTXOs whose total value covers the requested value.
+
+use crate::{error::Error, utxo_store::UnspentTxOut};
+use mc_crypto_rand::RngCore;
+use rand::seq::SliceRandom;
+use std::cmp::Reverse;
+
+/// The default number of search steps `BranchAndBound` takes before giving up
+/// on finding an exact match.
+pub const DEFAULT_BRANCH_AND_BOUND_MAX_STEPS: usize = 100_000;
+
+/// A strategy for choosing the inputs of a transaction.
+pub trait UtxoSelectionStrategy: Send + Sync {
+    /// Select a subset of `utxos` totalling at least `value`, using no more
+    /// than `max_inputs` inputs.
+    fn select_utxos(
+        &self,
+        utxos: &[UnspentTxOut],
+        value: u64,
+        max_inputs: usize,
+        rng: &mut dyn RngCore,
+    ) -> Result<Vec<UnspentTxOut>, Error>;
+}
+
+/// Spend the smallest UTXOs first. This keeps the number of UTXOs in the
+/// wallet low, at the cost of using more inputs per transaction.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SmallestFirst;
+
+impl UtxoSelectionStrategy for SmallestFirst {
+    fn select_utxos(
+        &self,
+        utxos: &[UnspentTxOut],
+        value: u64,
+        max_inputs: usize,
+        _rng: &mut dyn RngCore,
+    ) -> Result<Vec<UnspentTxOut>, Error> {
+        // Sort the utxos in descending order by value.
+        let mut sorted_utxos = utxos.to_vec();
+        sorted_utxos.sort_by_key(|utxo| Reverse(utxo.value));
+
+        let mut selected_utxos: Vec<UnspentTxOut> = Vec::new();
+        loop {
+            let total: u64 = selected_utxos.iter().map(|utxo| utxo.value).sum();
+            if total >= value {
+                break;
+            }
+
+            // Grab the next (smallest utxo)
+            let next_utxo = sorted_utxos.pop().ok_or(Error::InsufficientFunds)?;
+            selected_utxos.push(next_utxo);
+
+            // Cap at maximum allowed inputs.
+            if selected_utxos.len() > max_inputs {
+                // Remove the lowest utxo.
+                selected_utxos.remove(0);
+            }
+        }
+
+        Ok(selected_utxos)
+    }
+}
+
+/// Spend the largest UTXOs first. This minimizes the number of inputs (and
+/// therefore the size of the transaction), but leaves small UTXOs behind.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct LargestFirst;
+
+impl UtxoSelectionStrategy for LargestFirst {
+    fn select_utxos(
+        &self,
+        utxos: &[UnspentTxOut],
+        value: u64,
+        max_inputs: usize,
+        _rng: &mut dyn RngCore,
+    ) -> Result<Vec<UnspentTxOut>, Error> {
+        let mut sorted_utxos = utxos.to_vec();
+        sorted_utxos.sort_by_key(|utxo| Reverse(utxo.value));
+
+        let mut selected_utxos = Vec::new();
+        let mut total: u64 = 0;
+        for utxo in sorted_utxos.into_iter().take(max_inputs) {
+            if total >= value {
+                break;
+            }
+            total += utxo.value;
+            selected_utxos.push(utxo);
+        }
+
+        if total < value {
+            return Err(Error::InsufficientFunds);
+        }
+
+        Ok(selected_utxos)
+    }
+}
+
+/// Search for a set of UTXOs whose total is exactly the requested value, so
+/// that the transaction does not need a change output. The search is a
+/// depth-first branch and bound over the UTXOs sorted by descending value,
+/// limited to `max_steps` steps. If no exact match is found, selection falls
+/// back to `SmallestFirst`.
+#[derive(Clone, Copy, Debug)]
+pub struct BranchAndBound {
+    /// The maximal number of search steps before giving up.
+    pub max_steps: usize,
+}
+
+impl Default for BranchAndBound {
+    fn default() -> Self {
+        Self {
+            max_steps: DEFAULT_BRANCH_AND_BOUND_MAX_STEPS,
+        }
+    }
+}
+
+impl BranchAndBound {
+    /// Try to find a subset of `sorted_utxos` (sorted in descending order)
+    /// summing exactly to `value`. Returns the indices of the selected UTXOs.
+    ///
+    /// The depth-first search keeps its state in `selected` rather than on
+    /// the call stack, since a wallet may hold more UTXOs than the stack can
+    /// take frames.
+    fn find_exact_match(
+        &self,
+        sorted_utxos: &[UnspentTxOut],
+        value: u64,
+        max_inputs: usize,
+    ) -> Option<Vec<usize>> {
+        // remaining[i] is the sum of the values of sorted_utxos[i..].
+        let mut remaining = vec![0u64; sorted_utxos.len() + 1];
+        for i in (0..sorted_utxos.len()).rev() {
+            remaining[i] = remaining[i + 1].saturating_add(sorted_utxos[i].value);
+        }
+
+        // Excluding a utxo also skips over the following utxos of identical
+        // value, which avoids exploring equivalent branches.
+        let skip_equal = |index: usize| {
+            let utxo_value = sorted_utxos[index].value;
+            let mut next_index = index + 1;
+            while next_index < sorted_utxos.len() && sorted_utxos[next_index].value == utxo_value {
+                next_index += 1;
+            }
+            next_index
+        };
+
+        // The indices of the utxos included on the current branch, and the
+        // next utxo to decide on.
+        let mut selected: Vec<usize> = Vec::new();
+        let mut total = 0u64;
+        let mut index = 0;
+        let mut steps = 0;
+        loop {
+            if total == value {
+                return Some(selected);
+            }
+
+            steps += 1;
+            if steps > self.max_steps {
+                return None;
+            }
+
+            if index < sorted_utxos.len()
+                && selected.len() < max_inputs
+                && total.saturating_add(remaining[index]) >= value
+            {
+                // Include the current utxo, unless doing so overshoots the
+                // target, in which case exclude it.
+                let utxo_value = sorted_utxos[index].value;
+                if total.saturating_add(utxo_value) <= value {
+                    selected.push(index);
+                    total += utxo_value;
+                    index += 1;
+                } else {
+                    index = skip_equal(index);
+                }
+            } else {
+                // This branch is exhausted. Backtrack to the most recently
+                // included utxo, and try excluding it instead.
+                let last = selected.pop()?;
+                total -= sorted_utxos[last].value;
+                index = skip_equal(last);
+            }
+        }
+    }
+}
+
+impl UtxoSelectionStrategy for BranchAndBound {
+    fn select_utxos(
+        &self,
+        utxos: &[UnspentTxOut],
+        value: u64,
+        max_inputs: usize,
+        rng: &mut dyn RngCore,
+    ) -> Result<Vec<UnspentTxOut>, Error> {
+        let mut sorted_utxos = utxos.to_vec();
+        sorted_utxos.sort_by_key(|utxo| Reverse(utxo.value));
+
+        match self.find_exact_match(&sorted_utxos, value, max_inputs) {
+            Some(indices) => Ok(indices
+                .into_iter()
+                .map(|index| sorted_utxos[index].clone())
+                .collect()),
+            None => SmallestFirst.select_utxos(utxos, value, max_inputs, rng),
+        }
+    }
+}
+
+/// Select UTXOs in a random order. This avoids leaking information about the
+/// wallet through a predictable selection pattern. Whenever more than
+/// `max_inputs` UTXOs are needed, the smallest selected UTXO is dropped.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Randomized;
+
+impl UtxoSelectionStrategy for Randomized {
+    fn select_utxos(
+        &self,
+        utxos: &[UnspentTxOut],
+        value: u64,
+        max_inputs: usize,
+        rng: &mut dyn RngCore,
+    ) -> Result<Vec<UnspentTxOut>, Error> {
+        let mut shuffled_utxos = utxos.to_vec();
+        shuffled_utxos.shuffle(rng);
+
+        let mut selected_utxos: Vec<UnspentTxOut> = Vec::new();
+        let mut total: u64 = 0;
+        for utxo in shuffled_utxos {
+            if total >= value {
+                break;
+            }
+            total += utxo.value;
+            selected_utxos.push(utxo);
+
+            // Cap at maximum allowed inputs by dropping the smallest selected utxo.
+            if selected_utxos.len() > max_inputs {
+                let (smallest_index, _) = selected_utxos
+                    .iter()
+                    .enumerate()
+                    .min_by_key(|(_, utxo)| utxo.value)
+                    .expect("selected_utxos is not empty");
+                total -= selected_utxos.swap_remove(smallest_index).value;
+            }
+        }
+
+        if total < value {
+            return Err(Error::InsufficientFunds);
+        }
+
+        Ok(selected_utxos)
+    }
+}
@@ -62,6 +62,16 @@ pub struct UnspentTxOut {
     pub token_id: u64,
 }
 
+impl UnspentTxOut {
+    /// The key image stored for outputs owned by view-only monitors, which
+    /// cannot compute real key images. It is the TxOut public key, which is
+    /// unique in the ledger. It is replaced by the real key image once a
+    /// signed transaction spending the output is submitted.
+    pub fn placeholder_key_image(tx_out: &TxOut) -> KeyImage {
+        KeyImage::from(*tx_out.public_key.as_bytes())
+    }
+}
+
 /// Type used as the key in the utxo_id_to_utxo  database.
 pub type UtxoId = DatabaseByteArrayKey;
 
@@ -385,6 +395,59 @@ impl UtxoStore {
         Ok(())
     }
 
+    /// Replace the key images of utxos owned by a given monitor.
+    /// This is used for view-only monitors, whose utxos are stored under a
+    /// placeholder key image until the real one is known. Each pair is
+    /// (current key image, new key image). Key images that are not found in
+    /// the database, or that belong to a different monitor, are silently
+    /// ignored.
+    pub fn replace_key_images<'env>(
+        &self,
+        db_txn: &mut RwTransaction<'env>,
+        monitor_id: &MonitorId,
+        key_images: &[(KeyImage, KeyImage)],
+    ) -> Result<(), Error> {
+        for (old_key_image, new_key_image) in key_images.iter() {
+            let old_utxo_id = UtxoId::from(old_key_image);
+            let subaddress_id = match self.get_subaddress_id_by_utxo_id(db_txn, &old_utxo_id) {
+                Ok(subaddress_id) => subaddress_id,
+                Err(Error::UtxoIdNotFound) => continue,
+                Err(err) => return Err(err),
+            };
+            if subaddress_id.monitor_id != *monitor_id {
+                continue;
+            }
+
+            let mut utxo = self.get_utxo_by_id(db_txn, &old_utxo_id)?;
+            utxo.key_image = *new_key_image;
+
+            let subaddress_id_bytes = subaddress_id.to_vec();
+            let new_utxo_id = UtxoId::from(new_key_image);
+
+            // Remove the entries stored under the old key image.
+            db_txn.del(self.utxo_id_to_utxo, &old_utxo_id, None)?;
+            db_txn.del(self.key_image_to_subaddress_id, &old_utxo_id, None)?;
+            db_txn.del(
+                self.subaddress_id_to_utxo_id,
+                &subaddress_id_bytes,
+                Some(&old_utxo_id[..]),
+            )?;
+
+            // Store them again under the new one.
+            self.append_utxo(db_txn, monitor_id, subaddress_id.index, &utxo)?;
+
+            log::debug!(
+                self.logger,
+                "replaced key image {:?} with {:?} for utxo id {}",
+                old_key_image,
+                new_key_image,
+                new_utxo_id,
+            );
+        }
+
+        Ok(())
+    }
+
     /// Get all UtxoIds associated with a given subaddress.
     fn get_utxo_ids(
         &self,
@@ -977,4 +1040,75 @@ mod test {
             }
         }
     }
+
+    #[test_with_logger]
+    fn test_replace_key_images(logger: Logger) {
+        let mut rng: StdRng = SeedableRng::from_seed([123u8; 32]);
+        let (env, _ledger_db, utxo_store, mut utxos) = setup_test_utxo_store(&mut rng, &logger);
+
+        let (_monitor_data, monitor_id0) = get_test_monitor_data_and_id(&mut rng);
+        let (_monitor_data, monitor_id1) = get_test_monitor_data_and_id(&mut rng);
+
+        // Store the first two utxos under placeholder key images for monitor_id0,
+        // and the third one for monitor_id1.
+        for utxo in utxos.iter_mut().take(3) {
+            utxo.key_image = UnspentTxOut::placeholder_key_image(&utxo.tx_out);
+        }
+        {
+            let mut db_txn = env.begin_rw_txn().unwrap();
+            utxo_store
+                .append_utxo(&mut db_txn, &monitor_id0, 0, &utxos[0])
+                .unwrap();
+            utxo_store
+                .append_utxo(&mut db_txn, &monitor_id0, 0, &utxos[1])
+                .unwrap();
+            utxo_store
+                .append_utxo(&mut db_txn, &monitor_id1, 0, &utxos[2])
+                .unwrap();
+            db_txn.commit().unwrap();
+        }
+
+        // Replace the first placeholder, one belonging to another monitor, and one
+        // that does not exist.
+        let new_key_image = KeyImage::from(1000);
+        {
+            let mut db_txn = env.begin_rw_txn().unwrap();
+            utxo_store
+                .replace_key_images(
+                    &mut db_txn,
+                    &monitor_id0,
+                    &[
+                        (utxos[0].key_image, new_key_image),
+                        (utxos[2].key_image, KeyImage::from(1001)),
+                        (KeyImage::from(1002), KeyImage::from(1003)),
+                    ],
+                )
+                .unwrap();
+            db_txn.commit().unwrap();
+        }
+
+        // Only the first utxo was re-keyed.
+        let db_txn = env.begin_ro_txn().unwrap();
+        let mut expected0 = utxos[0].clone();
+        expected0.key_image = new_key_image;
+        assert_eq!(
+            HashSet::from_iter(utxo_store.get_utxos(&db_txn, &monitor_id0, 0).unwrap()),
+            HashSet::from_iter(vec![expected0, utxos[1].clone()]),
+        );
+        assert_eq!(
+            utxo_store.get_utxos(&db_txn, &monitor_id1, 0).unwrap(),
+            vec![utxos[2].clone()],
+        );
+
+        assert_eq!(
+            utxo_store
+                .get_subaddress_id_by_utxo_id(&db_txn, &UtxoId::from(&new_key_image))
+                .unwrap(),
+            SubaddressId::new(&monitor_id0, 0),
+        );
+        assert!(matches!(
+            utxo_store.get_subaddress_id_by_utxo_id(&db_txn, &UtxoId::from(&utxos[0].key_image)),
+            Err(Error::UtxoIdNotFound)
+        ));
+    }
 }
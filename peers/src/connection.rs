@@ -34,7 +34,7 @@ use mc_consensus_api::{
 };
 use mc_consensus_enclave_api::{ConsensusEnclave, TxContext, WellFormedEncryptedTx};
 use mc_transaction_core::tx::TxHash;
-use mc_util_grpc::ConnectionUriGrpcioChannel;
+use mc_util_grpc::{trace_context_call_option, ConnectionUriGrpcioChannel};
 use mc_util_serial::{deserialize, serialize};
 use mc_util_uri::{ConnectionUri, ConsensusPeerUri as PeerUri};
 use protobuf::RepeatedField;
@@ -280,7 +280,8 @@ impl<Enclave: ConsensusEnclave + Clone + Send + Sync> ConsensusConnection
         grpc_msg.set_payload(serialize(&msg)?);
 
         let response = self.log_attested_call("send_consensus_msg", |this| {
-            this.consensus_api_client.send_consensus_msg(&grpc_msg)
+            this.consensus_api_client
+                .send_consensus_msg_opt(&grpc_msg, trace_context_call_option())
         })?;
         Ok(response)
     }
@@ -306,7 +307,8 @@ impl<Enclave: ConsensusEnclave + Clone + Send + Sync> ConsensusConnection
         )?;
 
         self.log_attested_call("txs_for_peer", |this| {
-            this.consensus_api_client.peer_tx_propose(&request.into())
+            this.consensus_api_client
+                .peer_tx_propose_opt(&request.into(), trace_context_call_option())
         })?;
 
         Ok(())
@@ -324,7 +326,8 @@ impl<Enclave: ConsensusEnclave + Clone + Send + Sync> ConsensusConnection
         ));
 
         let mut response = self.log_attested_call("get_txs", |this| {
-            this.consensus_api_client.get_txs(&request)
+            this.consensus_api_client
+                .get_txs_opt(&request, trace_context_call_option())
         })?;
         if response.has_tx_hashes_not_in_cache() {
             let tx_hashes = response
@@ -18,6 +18,7 @@ use mc_consensus_api::consensus_peer::ConsensusMsgResult;
 use mc_consensus_enclave_api::WellFormedEncryptedTx;
 use mc_crypto_digestible::{Digestible, MerlinTranscript};
 use mc_transaction_core::tx::TxHash;
+use mc_util_telemetry::{tracer, Context, SpanKind, TraceContextExt, Tracer};
 use mc_util_uri::ConnectionUri;
 use std::{
     sync::{
@@ -137,6 +138,11 @@ impl<RP: RetryPolicy> ThreadedBroadcaster<RP> {
         let arc_encrypted_tx = Arc::new(encrypted_tx);
         let arc_origin_node = Arc::new(origin_node.clone());
 
+        // The peer threads send the transaction on behalf of the current span
+        // (e.g. the client request that proposed it), so that it can be
+        // followed to the receiving peers.
+        let trace_context = Context::current();
+
         // Broadcast to all peers except the originating one.
         for peer_thread in self.peer_threads.iter() {
             // Do not broadcast to the originating node or the sender node.
@@ -151,6 +157,7 @@ impl<RP: RetryPolicy> ThreadedBroadcaster<RP> {
                 arc_encrypted_tx.clone(),
                 arc_origin_node.clone(),
                 deadline,
+                trace_context.clone(),
             ) {
                 log::error!(
                     self.logger,
@@ -243,6 +250,7 @@ enum ThreadMsg {
         encrypted_tx: Arc<WellFormedEncryptedTx>,
         origin_node: Arc<NodeID>,
         deadline: Instant,
+        trace_context: Context,
     },
 
     /// Request the worker thread to stop.
@@ -306,12 +314,14 @@ impl PeerThread {
         encrypted_tx: Arc<WellFormedEncryptedTx>,
         origin_node: Arc<NodeID>,
         deadline: Instant,
+        trace_context: Context,
     ) -> Result<(), Error> {
         self.sender
             .send(ThreadMsg::HandleProposeTx {
                 encrypted_tx,
                 origin_node,
                 deadline,
+                trace_context,
             })
             .map_err(|_err| Error::ChannelSend)
     }
@@ -361,12 +371,14 @@ impl PeerThread {
                         encrypted_tx,
                         origin_node,
                         deadline,
+                        trace_context,
                     } => Self::do_handle_propose_tx_msg(
                         &conn,
                         &retry_policy,
                         &encrypted_tx,
                         &origin_node,
                         deadline,
+                        trace_context,
                         &logger,
                     ),
                     ThreadMsg::StopTrigger => {
@@ -423,12 +435,20 @@ impl PeerThread {
         encrypted_tx: &WellFormedEncryptedTx,
         origin_node: &NodeID,
         deadline: Instant,
+        trace_context: Context,
         logger: &Logger,
     ) {
         if Instant::now() > deadline {
             return;
         }
 
+        let tracer = tracer!();
+        let span = tracer
+            .span_builder("send_propose_tx")
+            .with_kind(SpanKind::Client)
+            .start_with_context(&tracer, &trace_context);
+        let _active = trace_context.with_span(span).attach();
+
         let retry_iterator = retry_policy.get_delay_iterator().with_deadline(deadline);
 
         if let Err(err) = conn.send_propose_tx(encrypted_tx, origin_node, retry_iterator) {
@@ -1,5 +1,5 @@
 use mc_account_keys::{AccountKey, RootIdentity};
-use mc_api::printable::PrintableWrapper;
+use mc_api::printable::{PaymentRequest, PaymentRequestLineItem, PrintableWrapper};
 use mc_test_vectors_definitions::b58_encodings::*;
 use mc_util_test_vector::write_jsonl;
 
@@ -47,4 +47,66 @@ fn main() {
             .collect::<Vec<_>>()
     })
     .expect("Unable to write test vectors");
+
+    write_jsonl("../vectors", || {
+        (0..10)
+            .map(|n| {
+                let account_key = AccountKey::from(&RootIdentity::from(&[n; 32]));
+                let public_address = account_key.default_subaddress();
+                let line_items = (0..n % 3)
+                    .map(|i| B58PaymentRequestLineItem {
+                        description: format!("item {}", i),
+                        value: 100 * (n as u64 + 1) * (i as u64 + 1),
+                    })
+                    .collect::<Vec<_>>();
+                let value = if line_items.is_empty() {
+                    1000 * (n as u64 + 1)
+                } else {
+                    line_items.iter().map(|line_item| line_item.value).sum()
+                };
+                let memo = format!("invoice {}", n);
+                let token_id = n as u64 % 2;
+                let payment_request_id = if n % 4 == 0 { 0 } else { 1000 + n as u64 };
+                let expiration_timestamp = if n % 2 == 0 {
+                    0
+                } else {
+                    1_700_000_000 + n as u64 * 86_400
+                };
+
+                let mut payment_request = PaymentRequest::new();
+                payment_request.set_public_address((&public_address).try_into().unwrap());
+                payment_request.set_value(value);
+                payment_request.set_memo(memo.clone());
+                payment_request.set_token_id(token_id);
+                payment_request.set_payment_request_id(payment_request_id);
+                payment_request.set_expiration_timestamp(expiration_timestamp);
+                payment_request.set_line_items(
+                    line_items
+                        .iter()
+                        .map(|line_item| {
+                            let mut proto_line_item = PaymentRequestLineItem::new();
+                            proto_line_item.set_description(line_item.description.clone());
+                            proto_line_item.set_value(line_item.value);
+                            proto_line_item
+                        })
+                        .collect(),
+                );
+                let mut wrapper = PrintableWrapper::new();
+                wrapper.set_payment_request(payment_request);
+                let b58_encoded = wrapper.b58_encode().unwrap();
+                B58EncodePaymentRequest {
+                    view_public_key: public_address.view_public_key().to_bytes(),
+                    spend_public_key: public_address.spend_public_key().to_bytes(),
+                    value,
+                    memo,
+                    token_id,
+                    payment_request_id,
+                    expiration_timestamp,
+                    line_items,
+                    b58_encoded,
+                }
+            })
+            .collect::<Vec<_>>()
+    })
+    .expect("Unable to write test vectors");
 }
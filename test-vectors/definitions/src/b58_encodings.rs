@@ -27,3 +27,27 @@ impl TestVector for B58EncodePublicAddressWithFog {
     const FILE_NAME: &'static str = "b58_encode_public_address_with_fog";
     const MODULE_SUBDIR: &'static str = "b58_encodings";
 }
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct B58PaymentRequestLineItem {
+    pub description: String,
+    pub value: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct B58EncodePaymentRequest {
+    pub view_public_key: [u8; 32],
+    pub spend_public_key: [u8; 32],
+    pub value: u64,
+    pub memo: String,
+    pub token_id: u64,
+    pub payment_request_id: u64,
+    pub expiration_timestamp: u64,
+    pub line_items: Vec<B58PaymentRequestLineItem>,
+    pub b58_encoded: String,
+}
+
+impl TestVector for B58EncodePaymentRequest {
+    const FILE_NAME: &'static str = "b58_encode_payment_request";
+    const MODULE_SUBDIR: &'static str = "b58_encodings";
+}
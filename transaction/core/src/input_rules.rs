@@ -9,8 +9,9 @@
 //! followed, to facilitate trustless interactions.
 
 use crate::{
+    ring_signature::Scalar,
     tx::{Tx, TxOut},
-    BlockVersion,
+    Amount, BlockVersion, RevealedTxOut,
 };
 use alloc::vec::Vec;
 use displaydoc::Display;
@@ -34,11 +35,30 @@ pub struct InputRules {
     /// transaction to be valid
     #[prost(fixed64, tag = "2")]
     pub max_tombstone_block: u64,
+
+    /// Outputs that are required to appear in the Tx prefix, but which may be
+    /// filled partially. Each of them must appear with the same keys and a
+    /// value at least proportional to the fill fraction, which is inferred
+    /// from the partial fill change output. (MCIP #42)
+    #[prost(message, repeated, tag = "3")]
+    pub partial_fill_outputs: Vec<RevealedTxOut>,
+
+    /// A change output returning the unfilled part of this input to its
+    /// owner. Its value here is the value returned if nothing is filled, and
+    /// the Tx must contain this output with a value no larger than that.
+    #[prost(message, optional, tag = "4")]
+    pub partial_fill_change: Option<RevealedTxOut>,
+
+    /// A lower bound on the value which must be filled, i.e. the difference
+    /// between the value of `partial_fill_change` here and in the Tx.
+    #[prost(fixed64, tag = "5")]
+    #[digestible(omit_when = 0)]
+    pub min_partial_fill_value: u64,
 }
 
 impl InputRules {
     /// Verify that a Tx conforms to the rules.
-    pub fn verify(&self, _block_version: BlockVersion, tx: &Tx) -> Result<(), InputRuleError> {
+    pub fn verify(&self, block_version: BlockVersion, tx: &Tx) -> Result<(), InputRuleError> {
         // NOTE: If this function gets too busy, we should split it into several smaller
         // functions NOTE: The tests for this function are in
         // transaction/core/tests/input_rules.rs
@@ -53,8 +73,90 @@ impl InputRules {
                 return Err(InputRuleError::MissingRequiredOutput);
             }
         }
+        // Verify partial fill rules
+        if self.has_partial_fill_rules() {
+            if !block_version.partial_fill_input_rules_are_supported() {
+                return Err(InputRuleError::PartialFillInputRulesNotAllowed);
+            }
+            self.verify_partial_fill_rules(tx)?;
+        }
+        Ok(())
+    }
+
+    /// Whether any of the partial fill rules are set.
+    pub fn has_partial_fill_rules(&self) -> bool {
+        !self.partial_fill_outputs.is_empty()
+            || self.partial_fill_change.is_some()
+            || self.min_partial_fill_value != 0
+    }
+
+    /// Verify the partial fill rules.
+    ///
+    /// The fill fraction is `filled_value / nominal_change_value`, where
+    /// `filled_value` is how much smaller the change output is in the Tx than
+    /// in the rules. Every partial fill output must then appear in the Tx
+    /// with a value of at least `fill fraction * nominal value`. A nominal
+    /// change value of zero leaves the fill fraction undefined, so it is
+    /// rejected when there are partial fill outputs.
+    fn verify_partial_fill_rules(&self, tx: &Tx) -> Result<(), InputRuleError> {
+        let partial_fill_change = self
+            .partial_fill_change
+            .as_ref()
+            .ok_or(InputRuleError::MissingPartialFillChange)?;
+
+        let (nominal_change_amount, _) = partial_fill_change
+            .reveal_amount()
+            .map_err(|_| InputRuleError::InvalidRevealedTxOut)?;
+        if nominal_change_amount.value == 0 && !self.partial_fill_outputs.is_empty() {
+            return Err(InputRuleError::ZeroPartialFillChange);
+        }
+        let (real_change_amount, _) = Self::find_modified_output(tx, partial_fill_change)?;
+        if real_change_amount.token_id != nominal_change_amount.token_id {
+            return Err(InputRuleError::PartialFillOutputMismatch);
+        }
+        let filled_value = nominal_change_amount
+            .value
+            .checked_sub(real_change_amount.value)
+            .ok_or(InputRuleError::ExcessivePartialFillChange)?;
+        if filled_value < self.min_partial_fill_value {
+            return Err(InputRuleError::MinPartialFillValueNotMet);
+        }
+
+        for partial_fill_output in self.partial_fill_outputs.iter() {
+            let (nominal_amount, _) = partial_fill_output
+                .reveal_amount()
+                .map_err(|_| InputRuleError::InvalidRevealedTxOut)?;
+            let (real_amount, _) = Self::find_modified_output(tx, partial_fill_output)?;
+            if real_amount.token_id != nominal_amount.token_id {
+                return Err(InputRuleError::PartialFillOutputMismatch);
+            }
+            // real_value / nominal_value >= filled_value / nominal_change_value,
+            // checked with multiplication in u128 to avoid rounding
+            if (real_amount.value as u128) * (nominal_change_amount.value as u128)
+                < (nominal_amount.value as u128) * (filled_value as u128)
+            {
+                return Err(InputRuleError::InsufficientPartialFillOutput);
+            }
+        }
         Ok(())
     }
+
+    /// Find the output of the Tx with the same public key as a revealed TxOut,
+    /// and reveal its amount.
+    fn find_modified_output(
+        tx: &Tx,
+        revealed: &RevealedTxOut,
+    ) -> Result<(Amount, Scalar), InputRuleError> {
+        let output = tx
+            .prefix
+            .outputs
+            .iter()
+            .find(|output| output.public_key == revealed.tx_out.public_key)
+            .ok_or(InputRuleError::MissingPartialFillOutput)?;
+        revealed
+            .reveal_amount_of_modified(output)
+            .map_err(|_| InputRuleError::PartialFillOutputMismatch)
+    }
 }
 
 /// An error that occurs when checking input rules
@@ -64,4 +166,23 @@ pub enum InputRuleError {
     MissingRequiredOutput,
     /// The tombstone block exceeds the limit
     MaxTombstoneBlockExceeded,
+    /// Partial fill input rules are not allowed at this block version
+    PartialFillInputRulesNotAllowed,
+    /// The partial fill rules are missing a partial fill change output
+    MissingPartialFillChange,
+    /// The amount of a partial fill output in the rules could not be revealed
+    InvalidRevealedTxOut,
+    /// The transaction is missing a partial fill output
+    MissingPartialFillOutput,
+    /// A partial fill output differs from the rules in more than its value
+    PartialFillOutputMismatch,
+    /// The partial fill change exceeds the value allowed by the rules
+    ExcessivePartialFillChange,
+    /// The filled value is less than the minimum partial fill value
+    MinPartialFillValueNotMet,
+    /// A partial fill output has insufficient value for the fill fraction
+    InsufficientPartialFillOutput,
+    /// The partial fill change has a value of zero, but partial fill outputs
+    /// are required
+    ZeroPartialFillChange,
 }
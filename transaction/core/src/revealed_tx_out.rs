@@ -0,0 +1,106 @@
+// Copyright (c) 2022 The MobileCoin Foundation
+
+//! A TxOut together with the shared secret needed to reveal its amount, as
+//! used by partial fill input rules (MCIP #42).
+
+use crate::{tx::TxOut, Amount, AmountError, MaskedAmount};
+use displaydoc::Display;
+use mc_crypto_digestible::Digestible;
+use mc_crypto_keys::{CompressedRistrettoPublic, RistrettoPublic};
+use mc_crypto_ring_signature::Scalar;
+use prost::Message;
+use serde::{Deserialize, Serialize};
+
+/// A TxOut whose amount is revealed to anyone who sees it, by publishing the
+/// TxOut shared secret alongside it.
+///
+/// Partial fill rules need this so that the party filling a signed contingent
+/// input can create an output with the same keys and a smaller amount, and so
+/// that consensus can check that the amount was scaled correctly.
+///
+/// Note: The shared secret is also used to encrypt the memo of the TxOut, so
+/// the memo of a revealed TxOut is visible to anyone who sees the rules.
+#[derive(Clone, Digestible, Eq, Hash, Message, PartialEq, Serialize, Deserialize)]
+pub struct RevealedTxOut {
+    /// The TxOut whose amount is revealed
+    #[prost(message, required, tag = "1")]
+    pub tx_out: TxOut,
+
+    /// The shared secret of the TxOut, which unmasks its amount
+    #[prost(message, required, tag = "2")]
+    pub shared_secret: CompressedRistrettoPublic,
+}
+
+impl RevealedTxOut {
+    /// Reveal the amount and blinding factor of the TxOut, checking that they
+    /// are consistent with its commitment.
+    pub fn reveal_amount(&self) -> Result<(Amount, Scalar), RevealedTxOutError> {
+        Ok(self
+            .tx_out
+            .masked_amount
+            .get_value(&self.decompressed_shared_secret()?)?)
+    }
+
+    /// Reveal the amount of another TxOut, which was created from this one by
+    /// changing only its amount, using the shared secret of this one.
+    ///
+    /// Fails if the other TxOut differs from this one in anything besides
+    /// its masked amount, or if its amount is not consistent with the shared
+    /// secret.
+    pub fn reveal_amount_of_modified(
+        &self,
+        other: &TxOut,
+    ) -> Result<(Amount, Scalar), RevealedTxOutError> {
+        if other.target_key != self.tx_out.target_key
+            || other.public_key != self.tx_out.public_key
+            || other.e_fog_hint != self.tx_out.e_fog_hint
+            || other.e_memo != self.tx_out.e_memo
+        {
+            return Err(RevealedTxOutError::TxOutMismatch);
+        }
+        Ok(other.masked_amount.get_value(&self.decompressed_shared_secret()?)?)
+    }
+
+    /// Create a copy of the TxOut with a different value, keeping its keys,
+    /// fog hint, memo and token id.
+    ///
+    /// Returns the new TxOut together with its amount and blinding factor.
+    pub fn change_value(&self, value: u64) -> Result<(TxOut, Amount, Scalar), RevealedTxOutError> {
+        let shared_secret = self.decompressed_shared_secret()?;
+        let (old_amount, _) = self.tx_out.masked_amount.get_value(&shared_secret)?;
+        let amount = Amount::new(value, old_amount.token_id);
+
+        let mut masked_amount = MaskedAmount::new(amount, &shared_secret)?;
+        // Preserve the absence of a masked token id on outputs which don't have one
+        if self.tx_out.masked_amount.masked_token_id.is_empty() {
+            masked_amount.masked_token_id.clear();
+        }
+
+        let mut tx_out = self.tx_out.clone();
+        tx_out.masked_amount = masked_amount;
+        let (amount, blinding) = tx_out.masked_amount.get_value(&shared_secret)?;
+        Ok((tx_out, amount, blinding))
+    }
+
+    fn decompressed_shared_secret(&self) -> Result<RistrettoPublic, RevealedTxOutError> {
+        RistrettoPublic::try_from(&self.shared_secret)
+            .map_err(|_| RevealedTxOutError::InvalidSharedSecret)
+    }
+}
+
+/// An error which can occur when revealing the amount of a RevealedTxOut
+#[derive(Clone, Debug, Display, Eq, PartialEq)]
+pub enum RevealedTxOutError {
+    /// The shared secret is not a valid curve point
+    InvalidSharedSecret,
+    /// Amount: {0}
+    Amount(AmountError),
+    /// The TxOut differs from the revealed TxOut in more than its amount
+    TxOutMismatch,
+}
+
+impl From<AmountError> for RevealedTxOutError {
+    fn from(src: AmountError) -> Self {
+        Self::Amount(src)
+    }
+}
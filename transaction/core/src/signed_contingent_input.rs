@@ -5,7 +5,7 @@
 use crate::{
     ring_ct::{GeneratorCache, OutputSecret, PresignedInputRing, SignedInputRing},
     tx::TxIn,
-    Amount, TokenId,
+    Amount, RevealedTxOutError, TokenId,
 };
 use alloc::vec::Vec;
 use displaydoc::Display;
@@ -75,6 +75,7 @@ impl SignedContingentInput {
     /// * The ring MLSAG actually signs the pseudo-output as claimed
     /// * The required output amounts actually correspond to the required
     ///   outputs
+    /// * The amounts of any partial fill outputs can be revealed
     ///
     /// Note: This does check any other rules like tombstone block, or
     /// confirm proofs of membership, which are normally added only when this
@@ -124,6 +125,20 @@ impl SignedContingentInput {
                     return Err(SignedContingentInputError::RequiredOutputMismatch);
                 }
             }
+
+            if rules.has_partial_fill_rules() {
+                let partial_fill_change = rules
+                    .partial_fill_change
+                    .as_ref()
+                    .ok_or(SignedContingentInputError::MissingPartialFillChange)?;
+                let (change_amount, _) = partial_fill_change.reveal_amount()?;
+                if change_amount.value == 0 && !rules.partial_fill_outputs.is_empty() {
+                    return Err(SignedContingentInputError::ZeroPartialFillChange);
+                }
+                for partial_fill_output in rules.partial_fill_outputs.iter() {
+                    partial_fill_output.reveal_amount()?;
+                }
+            }
         }
 
         Ok(())
@@ -182,6 +197,17 @@ pub enum SignedContingentInputError {
     MissingProofs,
     /// Invalid Ring signature: {0}
     RingSignature(RingSignatureError),
+    /// Partial fill rules are missing a partial fill change output
+    MissingPartialFillChange,
+    /// Invalid revealed TxOut: {0}
+    RevealedTxOut(RevealedTxOutError),
+    /// Fill value {0} is less than the minimum partial fill value {1}
+    MinPartialFillValueNotMet(u64, u64),
+    /// Fill value {0} exceeds the value {1} offered by the partial fill change
+    ExcessiveFillValue(u64, u64),
+    /// The partial fill change has a value of zero, but partial fill outputs
+    /// are required
+    ZeroPartialFillChange,
 }
 
 impl From<RingSignatureError> for SignedContingentInputError {
@@ -189,3 +215,9 @@ impl From<RingSignatureError> for SignedContingentInputError {
         Self::RingSignature(src)
     }
 }
+
+impl From<RevealedTxOutError> for SignedContingentInputError {
+    fn from(src: RevealedTxOutError) -> Self {
+        Self::RevealedTxOut(src)
+    }
+}
@@ -1,6 +1,15 @@
 mod util;
 
-use mc_transaction_core::{tx::Tx, BlockVersion, InputRules};
+use mc_account_keys::AccountKey;
+use mc_crypto_keys::RistrettoPrivate;
+use mc_transaction_core::{
+    encrypted_fog_hint::EncryptedFogHint,
+    onetime_keys::create_shared_secret,
+    tx::{Tx, TxOut},
+    Amount, BlockVersion, InputRuleError, InputRules, RevealedTxOut,
+};
+use mc_util_from_random::FromRandom;
+use mc_util_test_helper::{CryptoRng, RngCore, RngType, SeedableRng};
 
 use util::create_test_tx;
 
@@ -9,6 +18,29 @@ fn get_first_rules(tx: &Tx) -> &InputRules {
     tx.prefix.inputs[0].input_rules.as_ref().unwrap()
 }
 
+// Creates a TxOut to a random recipient, together with its shared secret
+fn create_revealed_tx_out<RNG: CryptoRng + RngCore>(
+    block_version: BlockVersion,
+    amount: Amount,
+    rng: &mut RNG,
+) -> RevealedTxOut {
+    let recipient = AccountKey::random(rng).default_subaddress();
+    let tx_private_key = RistrettoPrivate::from_random(rng);
+    let tx_out = TxOut::new(
+        block_version,
+        amount,
+        &recipient,
+        &tx_private_key,
+        EncryptedFogHint::fake_onetime_hint(rng),
+    )
+    .unwrap();
+    let shared_secret = create_shared_secret(recipient.view_public_key(), &tx_private_key);
+    RevealedTxOut {
+        tx_out,
+        shared_secret: shared_secret.into(),
+    }
+}
+
 // Gets the set of rules from the first input of a Tx, mutably
 fn get_first_rules_mut(tx: &mut Tx) -> &mut InputRules {
     tx.prefix.inputs[0].input_rules.as_mut().unwrap()
@@ -26,6 +58,7 @@ fn test_input_rules_verify_required_outputs() {
     tx.prefix.inputs[0].input_rules = Some(InputRules {
         required_outputs: vec![],
         max_tombstone_block: 0,
+        ..Default::default()
     });
 
     // Check that the Tx is following input rules (vacuously)
@@ -63,6 +96,7 @@ fn test_input_rules_verify_max_tombstone() {
     tx.prefix.inputs[0].input_rules = Some(InputRules {
         required_outputs: vec![],
         max_tombstone_block: 0,
+        ..Default::default()
     });
 
     // Check that the Tx is following input rules (vacuously)
@@ -72,6 +106,7 @@ fn test_input_rules_verify_max_tombstone() {
     tx.prefix.inputs[0].input_rules = Some(InputRules {
         required_outputs: vec![],
         max_tombstone_block: tx.prefix.tombstone_block - 1,
+        ..Default::default()
     });
 
     assert!(get_first_rules(&tx).verify(block_version, &tx).is_err());
@@ -82,3 +117,128 @@ fn test_input_rules_verify_max_tombstone() {
 
     get_first_rules(&tx).verify(block_version, &tx).unwrap();
 }
+
+// Test that input rules verification is working for partial fill rules
+#[test]
+fn test_input_rules_verify_partial_fill() {
+    let block_version = BlockVersion::FOUR;
+    let mut rng: RngType = SeedableRng::from_seed([7u8; 32]);
+
+    let (mut tx, _ledger) = create_test_tx(block_version);
+
+    // The signer offers 1000 of token 1, and asks for 500 of token 2 in return,
+    // filling at least 100 of token 1.
+    let change = create_revealed_tx_out(block_version, Amount::new(1000, 1.into()), &mut rng);
+    let output = create_revealed_tx_out(block_version, Amount::new(500, 2.into()), &mut rng);
+
+    // Modify the Tx to have partial fill rules.
+    // (This invalidates the signature, but we aren't checking that here)
+    tx.prefix.inputs[0].input_rules = Some(InputRules {
+        partial_fill_outputs: vec![output.clone()],
+        partial_fill_change: Some(change.clone()),
+        min_partial_fill_value: 100,
+        ..Default::default()
+    });
+
+    // Fill 400 of the 1000, which requires at least 200 of token 2.
+    let set_outputs = |tx: &mut Tx, change_value: u64, output_value: u64| {
+        tx.prefix.outputs = vec![
+            change.change_value(change_value).unwrap().0,
+            output.change_value(output_value).unwrap().0,
+        ];
+    };
+    set_outputs(&mut tx, 600, 200);
+    get_first_rules(&tx).verify(block_version, &tx).unwrap();
+
+    // Paying more than the fill fraction requires is fine
+    set_outputs(&mut tx, 600, 250);
+    get_first_rules(&tx).verify(block_version, &tx).unwrap();
+
+    // Filling completely is fine
+    set_outputs(&mut tx, 0, 500);
+    get_first_rules(&tx).verify(block_version, &tx).unwrap();
+
+    // Paying less than the fill fraction requires is not
+    set_outputs(&mut tx, 600, 199);
+    assert_eq!(
+        get_first_rules(&tx).verify(block_version, &tx),
+        Err(InputRuleError::InsufficientPartialFillOutput)
+    );
+
+    // Filling less than the minimum is not
+    set_outputs(&mut tx, 950, 25);
+    assert_eq!(
+        get_first_rules(&tx).verify(block_version, &tx),
+        Err(InputRuleError::MinPartialFillValueNotMet)
+    );
+
+    // Returning more change than offered is not
+    set_outputs(&mut tx, 1001, 0);
+    assert_eq!(
+        get_first_rules(&tx).verify(block_version, &tx),
+        Err(InputRuleError::ExcessivePartialFillChange)
+    );
+
+    // Omitting the partial fill change is not
+    set_outputs(&mut tx, 600, 200);
+    tx.prefix.outputs.remove(0);
+    assert_eq!(
+        get_first_rules(&tx).verify(block_version, &tx),
+        Err(InputRuleError::MissingPartialFillOutput)
+    );
+
+    // Modifying a partial fill output in more than its value is not
+    set_outputs(&mut tx, 600, 200);
+    tx.prefix.outputs[1].e_fog_hint = EncryptedFogHint::fake_onetime_hint(&mut rng);
+    assert_eq!(
+        get_first_rules(&tx).verify(block_version, &tx),
+        Err(InputRuleError::PartialFillOutputMismatch)
+    );
+
+    // Partial fill rules are not allowed before block version four
+    set_outputs(&mut tx, 600, 200);
+    assert_eq!(
+        get_first_rules(&tx).verify(BlockVersion::THREE, &tx),
+        Err(InputRuleError::PartialFillInputRulesNotAllowed)
+    );
+
+    // Partial fill rules without a change output are malformed
+    get_first_rules_mut(&mut tx).partial_fill_change = None;
+    assert_eq!(
+        get_first_rules(&tx).verify(block_version, &tx),
+        Err(InputRuleError::MissingPartialFillChange)
+    );
+}
+
+// Test that a partial fill change of zero value cannot make the partial fill
+// outputs optional
+#[test]
+fn test_input_rules_verify_zero_partial_fill_change() {
+    let block_version = BlockVersion::FOUR;
+    let mut rng: RngType = SeedableRng::from_seed([8u8; 32]);
+
+    let (mut tx, _ledger) = create_test_tx(block_version);
+
+    let change = create_revealed_tx_out(block_version, Amount::new(0, 1.into()), &mut rng);
+    let output = create_revealed_tx_out(block_version, Amount::new(500, 2.into()), &mut rng);
+    tx.prefix.inputs[0].input_rules = Some(InputRules {
+        partial_fill_outputs: vec![output.clone()],
+        partial_fill_change: Some(change.clone()),
+        ..Default::default()
+    });
+
+    // With a fill fraction of 0 / 0, any value of the partial fill output
+    // would pass.
+    tx.prefix.outputs = vec![
+        change.change_value(0).unwrap().0,
+        output.change_value(0).unwrap().0,
+    ];
+    assert_eq!(
+        get_first_rules(&tx).verify(block_version, &tx),
+        Err(InputRuleError::ZeroPartialFillChange)
+    );
+
+    // Without partial fill outputs, there is nothing to make optional.
+    get_first_rules_mut(&mut tx).partial_fill_outputs.clear();
+    get_first_rules(&tx).verify(block_version, &tx).unwrap();
+}
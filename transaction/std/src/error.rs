@@ -64,8 +64,17 @@ pub enum TxBuilderError {
     /// Missing membership proof
     MissingMembershipProofs,
 
+    /// A partial fill change output was already added
+    PartialFillChangeAlreadyAdded,
+
+    /// Partial fill rules require a partial fill change output
+    MissingPartialFillChange,
+
     /// Signer: {0}
     Signer(SignerError),
+
+    /// Number of input rings ({0}) does not match number of tx inputs ({1})
+    InputRingsMismatch(usize, usize),
 }
 
 impl From<mc_util_serial::encode::Error> for TxBuilderError {
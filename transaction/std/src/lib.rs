@@ -15,6 +15,7 @@ mod memo_builder;
 mod reserved_subaddresses;
 mod signed_contingent_input_builder;
 mod transaction_builder;
+mod unsigned_tx;
 
 #[cfg(any(test, feature = "test-only"))]
 pub mod test_utils;
@@ -36,6 +37,7 @@ pub use signed_contingent_input_builder::SignedContingentInputBuilder;
 pub use transaction_builder::{
     DefaultTxOutputsOrdering, TransactionBuilder, TxOutContext, TxOutputsOrdering,
 };
+pub use unsigned_tx::UnsignedTx;
 
 // Re-export this to help the exported macros work
 pub use mc_transaction_core::MemoPayload;
@@ -9,13 +9,14 @@ use crate::{
 };
 use core::cmp::min;
 use mc_account_keys::PublicAddress;
+use mc_crypto_keys::RistrettoPublic;
 use mc_crypto_ring_signature_signer::{RingSigner, SignableInputRing};
 use mc_fog_report_validation::FogPubkeyResolver;
 use mc_transaction_core::{
     ring_ct::OutputSecret,
     ring_signature::Scalar,
     tx::{TxIn, TxOut, TxOutConfirmationNumber},
-    Amount, BlockVersion, InputRules, MemoContext, MemoPayload, NewMemoError,
+    Amount, BlockVersion, InputRules, MemoContext, MemoPayload, NewMemoError, RevealedTxOut,
     SignedContingentInput, TokenId, UnmaskedAmount,
 };
 use rand_core::{CryptoRng, RngCore};
@@ -37,6 +38,14 @@ pub struct SignedContingentInputBuilder<FPR: FogPubkeyResolver> {
     /// The outputs required by the rules for this signed input, and associated
     /// secrets
     required_outputs_and_secrets: Vec<(TxOut, OutputSecret)>,
+    /// The outputs which may be filled partially according to the rules for
+    /// this signed input, with their shared secrets revealed
+    partial_fill_outputs: Vec<RevealedTxOut>,
+    /// The change output which returns the unfilled part of the input, with
+    /// its shared secret revealed
+    partial_fill_change: Option<RevealedTxOut>,
+    /// The minimum value which must be filled, if partial fill rules are used
+    min_partial_fill_value: u64,
     /// The tombstone_block value, a block index in which the signed input
     /// expires, and can no longer be used. (This works by implying a limit
     /// on the tombstone block for any transaction which incorporates the signed
@@ -115,6 +124,9 @@ impl<FPR: FogPubkeyResolver> SignedContingentInputBuilder<FPR> {
             block_version,
             input_credentials,
             required_outputs_and_secrets: Vec::new(),
+            partial_fill_outputs: Vec::new(),
+            partial_fill_change: None,
+            min_partial_fill_value: 0,
             tombstone_block: u64::max_value(),
             fog_resolver,
             fog_tombstone_block_limit: u64::max_value(),
@@ -209,6 +221,109 @@ impl<FPR: FogPubkeyResolver> SignedContingentInputBuilder<FPR> {
         result
     }
 
+    /// Add a partial fill output to the input rules.
+    ///
+    /// The output must appear in the transaction with a value of at least
+    /// `amount.value` times the fill fraction, which is how much of the value
+    /// of the partial fill change output was taken, relative to its total.
+    /// Partial fill rules also require a partial fill change output.
+    ///
+    /// Note: The shared secret of this output is revealed in the input rules,
+    /// so its memo can be read by anyone who sees the signed contingent input.
+    ///
+    /// # Arguments
+    /// * `amount` - The amount of this output if the input is filled completely
+    /// * `recipient` - The recipient's public address
+    /// * `rng` - RNG used to generate blinding for commitment
+    pub fn add_partial_fill_output<RNG: CryptoRng + RngCore>(
+        &mut self,
+        amount: Amount,
+        recipient: &PublicAddress,
+        rng: &mut RNG,
+    ) -> Result<(TxOut, TxOutConfirmationNumber), TxBuilderError> {
+        let mut mb = self
+            .memo_builder
+            .take()
+            .expect("memo builder is missing, this is a logic error");
+        let result = self.create_output_with_fog_hint_address(
+            amount,
+            recipient,
+            recipient,
+            |memo_ctxt| mb.make_memo_for_output(amount, recipient, memo_ctxt),
+            rng,
+        );
+        self.memo_builder = Some(mb);
+        let (tx_out, shared_secret) = result?;
+
+        self.partial_fill_outputs.push(RevealedTxOut {
+            tx_out: tx_out.clone(),
+            shared_secret: shared_secret.into(),
+        });
+
+        let confirmation = TxOutConfirmationNumber::from(&shared_secret);
+
+        Ok((tx_out, confirmation))
+    }
+
+    /// Add the partial fill change output to the input rules.
+    ///
+    /// This output returns the part of the input which is not filled to its
+    /// owner. `amount` is the value returned if nothing is filled, which is
+    /// normally the value of the input, less any required outputs in the
+    /// same token. Whoever fills the input may reduce its value by the amount
+    /// they fill, and this determines the fill fraction applied to the
+    /// partial fill outputs.
+    ///
+    /// Note: The shared secret of this output is revealed in the input rules,
+    /// so its memo can be read by anyone who sees the signed contingent input.
+    ///
+    /// # Arguments
+    /// * `amount` - The amount of this change output if nothing is filled
+    /// * `change_destination` - An object including both a primary address and
+    ///   a change subaddress to use to create this change output.
+    /// * `rng` - RNG used to generate blinding for commitment
+    pub fn add_partial_fill_change_output<RNG: CryptoRng + RngCore>(
+        &mut self,
+        amount: Amount,
+        change_destination: &ReservedSubaddresses,
+        rng: &mut RNG,
+    ) -> Result<(TxOut, TxOutConfirmationNumber), TxBuilderError> {
+        if self.partial_fill_change.is_some() {
+            return Err(TxBuilderError::PartialFillChangeAlreadyAdded);
+        }
+        let mut mb = self
+            .memo_builder
+            .take()
+            .expect("memo builder is missing, this is a logic error");
+        let result = self.create_output_with_fog_hint_address(
+            amount,
+            &change_destination.change_subaddress,
+            &change_destination.primary_address,
+            |memo_ctxt| mb.make_memo_for_change_output(amount, change_destination, memo_ctxt),
+            rng,
+        );
+        self.memo_builder = Some(mb);
+        let (tx_out, shared_secret) = result?;
+
+        self.partial_fill_change = Some(RevealedTxOut {
+            tx_out: tx_out.clone(),
+            shared_secret: shared_secret.into(),
+        });
+
+        let confirmation = TxOutConfirmationNumber::from(&shared_secret);
+
+        Ok((tx_out, confirmation))
+    }
+
+    /// Sets the minimum value which must be filled, i.e. taken from the
+    /// partial fill change output, for the signed input to be used.
+    ///
+    /// # Arguments
+    /// * `min_partial_fill_value` - The minimum fill value
+    pub fn set_min_partial_fill_value(&mut self, min_partial_fill_value: u64) {
+        self.min_partial_fill_value = min_partial_fill_value;
+    }
+
     /// Add a required output to the rules, using `fog_hint_address` to
     /// construct the fog hint.
     ///
@@ -235,14 +350,10 @@ impl<FPR: FogPubkeyResolver> SignedContingentInputBuilder<FPR> {
         memo_fn: impl FnOnce(MemoContext) -> Result<MemoPayload, NewMemoError>,
         rng: &mut RNG,
     ) -> Result<(TxOut, TxOutConfirmationNumber), TxBuilderError> {
-        let (hint, pubkey_expiry) =
-            crate::transaction_builder::create_fog_hint(fog_hint_address, &self.fog_resolver, rng)?;
-
-        let (tx_out, shared_secret) = crate::transaction_builder::create_output_with_fog_hint(
-            self.block_version,
+        let (tx_out, shared_secret) = self.create_output_with_fog_hint_address(
             amount,
             recipient,
-            hint,
+            fog_hint_address,
             memo_fn,
             rng,
         )?;
@@ -253,8 +364,6 @@ impl<FPR: FogPubkeyResolver> SignedContingentInputBuilder<FPR> {
             .expect("TransactionBuilder created an invalid Amount");
         let output_secret = OutputSecret { amount, blinding };
 
-        self.impose_tombstone_block_limit(pubkey_expiry);
-
         self.required_outputs_and_secrets
             .push((tx_out.clone(), output_secret));
 
@@ -263,6 +372,35 @@ impl<FPR: FogPubkeyResolver> SignedContingentInputBuilder<FPR> {
         Ok((tx_out, confirmation))
     }
 
+    /// Create an output for the rules, using `fog_hint_address` to construct
+    /// the fog hint, and impose the fog pubkey expiry on the tombstone block.
+    ///
+    /// Returns the output and its shared secret.
+    fn create_output_with_fog_hint_address<RNG: CryptoRng + RngCore>(
+        &mut self,
+        amount: Amount,
+        recipient: &PublicAddress,
+        fog_hint_address: &PublicAddress,
+        memo_fn: impl FnOnce(MemoContext) -> Result<MemoPayload, NewMemoError>,
+        rng: &mut RNG,
+    ) -> Result<(TxOut, RistrettoPublic), TxBuilderError> {
+        let (hint, pubkey_expiry) =
+            crate::transaction_builder::create_fog_hint(fog_hint_address, &self.fog_resolver, rng)?;
+
+        let (tx_out, shared_secret) = crate::transaction_builder::create_output_with_fog_hint(
+            self.block_version,
+            amount,
+            recipient,
+            hint,
+            memo_fn,
+            rng,
+        )?;
+
+        self.impose_tombstone_block_limit(pubkey_expiry);
+
+        Ok((tx_out, shared_secret))
+    }
+
     /// Sets the tombstone block, clamping to smallest pubkey expiry value.
     ///
     /// # Arguments
@@ -303,8 +441,25 @@ impl<FPR: FogPubkeyResolver> SignedContingentInputBuilder<FPR> {
             ));
         }
 
+        let has_partial_fill_rules = !self.partial_fill_outputs.is_empty()
+            || self.partial_fill_change.is_some()
+            || self.min_partial_fill_value != 0;
+        if has_partial_fill_rules {
+            if !self.block_version.partial_fill_input_rules_are_supported() {
+                return Err(TxBuilderError::FeatureNotSupportedAtBlockVersion(
+                    *self.block_version,
+                    "partial fill input rules",
+                ));
+            }
+            if self.partial_fill_change.is_none() {
+                return Err(TxBuilderError::MissingPartialFillChange);
+            }
+        }
+
         self.required_outputs_and_secrets
             .sort_by(|(a, _), (b, _)| a.public_key.cmp(&b.public_key));
+        self.partial_fill_outputs
+            .sort_by(|a, b| a.tx_out.public_key.cmp(&b.tx_out.public_key));
 
         let (outputs, output_secrets): (Vec<TxOut>, Vec<_>) =
             self.required_outputs_and_secrets.drain(..).unzip();
@@ -316,6 +471,9 @@ impl<FPR: FogPubkeyResolver> SignedContingentInputBuilder<FPR> {
             } else {
                 self.tombstone_block
             },
+            partial_fill_outputs: self.partial_fill_outputs,
+            partial_fill_change: self.partial_fill_change,
+            min_partial_fill_value: self.min_partial_fill_value,
         };
 
         // Get the tx out indices from the proofs in the input credentials,
@@ -2305,4 +2463,221 @@ pub mod tests {
             );
         }
     }
+
+    #[test]
+    // Test that a signed contingent input with partial fill rules can be filled
+    // partially by the Tx builder, and that the result passes input rule checks
+    fn test_partial_fill_contingent_input_spendable() {
+        let mut rng: StdRng = SeedableRng::from_seed([1u8; 32]);
+
+        for block_version in 4..=*BlockVersion::MAX {
+            let block_version = BlockVersion::try_from(block_version).unwrap();
+
+            let alice = AccountKey::random(&mut rng);
+            let bob = AccountKey::random(&mut rng);
+            let fog_resolver = MockFogResolver::default();
+
+            let value = 1000 * MILLIMOB_TO_PICOMOB;
+            let amount = Amount::new(value, Mob::ID);
+            let token2 = TokenId::from(2);
+
+            // Alice offers her Mob, at a price of 100_000 of token id 2 for all of it,
+            // and requires at least a tenth of it to be filled
+            let input_credentials =
+                get_input_credentials(block_version, amount, &alice, &fog_resolver, &mut rng);
+            let proofs = input_credentials.membership_proofs.clone();
+
+            let mut builder = SignedContingentInputBuilder::new(
+                block_version,
+                input_credentials,
+                fog_resolver.clone(),
+                EmptyMemoBuilder::default(),
+            )
+            .unwrap();
+
+            builder
+                .add_partial_fill_output(
+                    Amount::new(100_000, token2),
+                    &alice.default_subaddress(),
+                    &mut rng,
+                )
+                .unwrap();
+            builder
+                .add_partial_fill_change_output(
+                    amount,
+                    &ReservedSubaddresses::from(&alice),
+                    &mut rng,
+                )
+                .unwrap();
+            builder.set_min_partial_fill_value(value / 10);
+
+            let mut sci = builder.build(&NoKeysRingSigner {}, &mut rng).unwrap();
+
+            // The contingent input should have a valid signature.
+            sci.validate().unwrap();
+            sci.tx_in.proofs = proofs;
+
+            let mut builder = TransactionBuilder::new(
+                block_version,
+                Amount::new(Mob::MINIMUM_FEE, Mob::ID),
+                fog_resolver.clone(),
+                EmptyMemoBuilder::default(),
+            )
+            .unwrap();
+
+            // Filling less than the minimum, or more than is offered, is rejected
+            assert_matches!(
+                builder.add_presigned_partial_fill_input(sci.clone(), value / 20),
+                Err(SignedContingentInputError::MinPartialFillValueNotMet(_, _))
+            );
+            assert_matches!(
+                builder.add_presigned_partial_fill_input(sci.clone(), value + 1),
+                Err(SignedContingentInputError::ExcessiveFillValue(_, _))
+            );
+
+            // Bob takes a quarter of the Mob, paying with his token id 2
+            builder
+                .add_presigned_partial_fill_input(sci, value / 4)
+                .unwrap();
+            builder.add_input(get_input_credentials(
+                block_version,
+                Amount::new(300_000, token2),
+                &bob,
+                &fog_resolver,
+                &mut rng,
+            ));
+            builder
+                .add_change_output(
+                    Amount::new(275_000, token2),
+                    &ReservedSubaddresses::from(&bob),
+                    &mut rng,
+                )
+                .unwrap();
+            builder
+                .add_output(
+                    Amount::new(value / 4 - Mob::MINIMUM_FEE, Mob::ID),
+                    &bob.default_subaddress(),
+                    &mut rng,
+                )
+                .unwrap();
+
+            let tx = builder.build(&NoKeysRingSigner {}, &mut rng).unwrap();
+
+            // tx should have a valid signature, and pass all input rule checks
+            validate_signature(block_version, &tx, &mut rng).unwrap();
+            validate_all_input_rules(block_version, &tx).unwrap();
+            validate_outputs_are_sorted(&tx.prefix).unwrap();
+
+            // Alice gets back three quarters of her Mob, and a quarter of her price
+            let alice_change = tx
+                .prefix
+                .outputs
+                .iter()
+                .find(|tx_out| {
+                    subaddress_matches_tx_out(&alice, CHANGE_SUBADDRESS_INDEX, tx_out).unwrap()
+                })
+                .expect("Didn't find alice's change");
+            let alice_output = tx
+                .prefix
+                .outputs
+                .iter()
+                .find(|tx_out| {
+                    subaddress_matches_tx_out(&alice, DEFAULT_SUBADDRESS_INDEX, tx_out).unwrap()
+                })
+                .expect("Didn't find alice's output");
+
+            for (tx_out, expected) in [
+                (alice_change, Amount::new(value - value / 4, Mob::ID)),
+                (alice_output, Amount::new(25_000, token2)),
+            ] {
+                validate_tx_out(block_version, tx_out).unwrap();
+                let ss = get_tx_out_shared_secret(
+                    alice.view_private_key(),
+                    &RistrettoPublic::try_from(&tx_out.public_key).unwrap(),
+                );
+                let (amount, _) = tx_out.masked_amount.get_value(&ss).unwrap();
+                assert_eq!(amount, expected);
+            }
+        }
+    }
+
+    #[test]
+    // Test that partial fill rules cannot be built before they are supported
+    fn test_partial_fill_rules_not_supported_before_block_version_four() {
+        let mut rng: StdRng = SeedableRng::from_seed([1u8; 32]);
+
+        let block_version = BlockVersion::THREE;
+        let alice = AccountKey::random(&mut rng);
+        let fog_resolver = MockFogResolver::default();
+        let amount = Amount::new(1000 * MILLIMOB_TO_PICOMOB, Mob::ID);
+
+        let input_credentials =
+            get_input_credentials(block_version, amount, &alice, &fog_resolver, &mut rng);
+
+        let mut builder = SignedContingentInputBuilder::new(
+            block_version,
+            input_credentials,
+            fog_resolver,
+            EmptyMemoBuilder::default(),
+        )
+        .unwrap();
+        builder
+            .add_partial_fill_output(
+                Amount::new(100_000, 2.into()),
+                &alice.default_subaddress(),
+                &mut rng,
+            )
+            .unwrap();
+        builder
+            .add_partial_fill_change_output(amount, &ReservedSubaddresses::from(&alice), &mut rng)
+            .unwrap();
+
+        assert_matches!(
+            builder.build(&NoKeysRingSigner {}, &mut rng),
+            Err(TxBuilderError::FeatureNotSupportedAtBlockVersion(3, _))
+        );
+    }
+
+    #[test]
+    // Test that a signed contingent input whose partial fill change has no value
+    // is rejected, since it would make its partial fill outputs optional
+    fn test_zero_partial_fill_change_is_rejected() {
+        let mut rng: StdRng = SeedableRng::from_seed([1u8; 32]);
+
+        let block_version = BlockVersion::FOUR;
+        let alice = AccountKey::random(&mut rng);
+        let fog_resolver = MockFogResolver::default();
+        let amount = Amount::new(1000 * MILLIMOB_TO_PICOMOB, Mob::ID);
+
+        let input_credentials =
+            get_input_credentials(block_version, amount, &alice, &fog_resolver, &mut rng);
+
+        let mut builder = SignedContingentInputBuilder::new(
+            block_version,
+            input_credentials,
+            fog_resolver,
+            EmptyMemoBuilder::default(),
+        )
+        .unwrap();
+        builder
+            .add_partial_fill_output(
+                Amount::new(100_000, 2.into()),
+                &alice.default_subaddress(),
+                &mut rng,
+            )
+            .unwrap();
+        builder
+            .add_partial_fill_change_output(
+                Amount::new(0, Mob::ID),
+                &ReservedSubaddresses::from(&alice),
+                &mut rng,
+            )
+            .unwrap();
+
+        let sci = builder.build(&NoKeysRingSigner {}, &mut rng).unwrap();
+        assert_matches!(
+            sci.validate(),
+            Err(SignedContingentInputError::ZeroPartialFillChange)
+        );
+    }
 }
@@ -6,7 +6,7 @@
 
 use crate::{
     input_materials::InputMaterials, InputCredentials, MemoBuilder, ReservedSubaddresses,
-    TxBuilderError,
+    TxBuilderError, UnsignedTx,
 };
 use core::{cmp::min, fmt::Debug};
 use mc_account_keys::PublicAddress;
@@ -17,11 +17,11 @@ use mc_transaction_core::{
     encrypted_fog_hint::EncryptedFogHint,
     fog_hint::FogHint,
     onetime_keys::create_shared_secret,
-    ring_ct::{InputRing, OutputSecret, SignatureRctBulletproofs},
+    ring_ct::{InputRing, OutputSecret},
     tokens::Mob,
     tx::{Tx, TxIn, TxOut, TxOutConfirmationNumber, TxPrefix},
-    Amount, BlockVersion, MemoContext, MemoPayload, NewMemoError, SignedContingentInput,
-    SignedContingentInputError, Token, TokenId,
+    Amount, BlockVersion, InputRules, MemoContext, MemoPayload, NewMemoError,
+    SignedContingentInput, SignedContingentInputError, Token, TokenId,
 };
 use mc_util_from_random::FromRandom;
 use rand_core::{CryptoRng, RngCore};
@@ -166,6 +166,9 @@ impl<FPR: FogPubkeyResolver> TransactionBuilder<FPR> {
     /// requirements imposed by the signed rules, so that our transaction
     /// will be valid.
     ///
+    /// If the signed input has partial fill rules, it is filled completely.
+    /// Use `add_presigned_partial_fill_input` to fill it partially.
+    ///
     /// Note: Before adding a signed_contingent_input, you probably want to:
     /// * validate it (call .validate())
     /// * check if key image appeared already (call .key_image())
@@ -177,6 +180,44 @@ impl<FPR: FogPubkeyResolver> TransactionBuilder<FPR> {
     pub fn add_presigned_input(
         &mut self,
         sci: SignedContingentInput,
+    ) -> Result<(), SignedContingentInputError> {
+        self.add_presigned_input_with_fill_value(sci, None)
+    }
+
+    /// Add a pre-signed Input with partial fill rules to the transaction,
+    /// filling it partially, and also fulfilling any other requirements
+    /// imposed by the signed rules, so that our transaction will be valid.
+    ///
+    /// This adds the partial fill change output, returning the unfilled
+    /// value to the signer, and each partial fill output, with its value
+    /// scaled by the fill fraction and rounded up. The caller is responsible
+    /// for adding an output which receives the `fill_value` taken from the
+    /// signed input.
+    ///
+    /// Note: Before adding a signed_contingent_input, you probably want to:
+    /// * validate it (call .validate())
+    /// * check if key image appeared already (call .key_image())
+    /// * provide merkle proofs of membership for each ring member (see
+    ///   .tx_out_global_indices)
+    ///
+    /// # Arguments
+    /// * `signed_contingent_input` - The pre-signed input we are adding
+    /// * `fill_value` - How much of the value offered by the partial fill
+    ///   change output to take
+    pub fn add_presigned_partial_fill_input(
+        &mut self,
+        sci: SignedContingentInput,
+        fill_value: u64,
+    ) -> Result<(), SignedContingentInputError> {
+        self.add_presigned_input_with_fill_value(sci, Some(fill_value))
+    }
+
+    /// Add a pre-signed Input, fulfilling its rules. If `fill_value` is None,
+    /// any partial fill rules are filled completely.
+    fn add_presigned_input_with_fill_value(
+        &mut self,
+        sci: SignedContingentInput,
+        fill_value: Option<u64>,
     ) -> Result<(), SignedContingentInputError> {
         // TODO: If there is a block version change that could cause an incompatibility,
         // we should check for it here, e.g. if sci.block_version differs from
@@ -191,6 +232,15 @@ impl<FPR: FogPubkeyResolver> TransactionBuilder<FPR> {
             if rules.required_outputs.len() != sci.required_output_amounts.len() {
                 return Err(SignedContingentInputError::WrongNumberOfRequiredOutputAmounts);
             }
+            // Compute any partial fill outputs first, so that we don't add anything
+            // if the fill value is not acceptable
+            let partial_fill_outputs_and_secrets = if rules.has_partial_fill_rules() {
+                Self::fill_partial_fill_rules(rules, fill_value)?
+            } else if fill_value.is_some() {
+                return Err(SignedContingentInputError::MissingPartialFillChange);
+            } else {
+                Vec::new()
+            };
             // 1. Required outputs
             for (required_output, unmasked_amount) in rules
                 .required_outputs
@@ -212,12 +262,69 @@ impl<FPR: FogPubkeyResolver> TransactionBuilder<FPR> {
             if rules.max_tombstone_block != 0 {
                 self.impose_tombstone_block_limit(rules.max_tombstone_block);
             }
+            // 3. Partial fill outputs and change
+            self.outputs_and_secrets
+                .extend(partial_fill_outputs_and_secrets);
+        } else if fill_value.is_some() {
+            return Err(SignedContingentInputError::MissingRules);
         }
 
         self.add_presigned_input_raw(sci);
         Ok(())
     }
 
+    /// Create the partial fill change output and partial fill outputs needed
+    /// to fill some input rules with a given fill value, defaulting to the
+    /// whole value offered by the partial fill change output.
+    fn fill_partial_fill_rules(
+        rules: &InputRules,
+        fill_value: Option<u64>,
+    ) -> Result<Vec<(TxOut, OutputSecret)>, SignedContingentInputError> {
+        let partial_fill_change = rules
+            .partial_fill_change
+            .as_ref()
+            .ok_or(SignedContingentInputError::MissingPartialFillChange)?;
+        let (nominal_change_amount, _) = partial_fill_change.reveal_amount()?;
+        let nominal_change_value = nominal_change_amount.value;
+
+        let fill_value = fill_value.unwrap_or(nominal_change_value);
+        if fill_value > nominal_change_value {
+            return Err(SignedContingentInputError::ExcessiveFillValue(
+                fill_value,
+                nominal_change_value,
+            ));
+        }
+        if fill_value < rules.min_partial_fill_value {
+            return Err(SignedContingentInputError::MinPartialFillValueNotMet(
+                fill_value,
+                rules.min_partial_fill_value,
+            ));
+        }
+
+        let mut result = Vec::with_capacity(rules.partial_fill_outputs.len() + 1);
+
+        let (change, amount, blinding) =
+            partial_fill_change.change_value(nominal_change_value - fill_value)?;
+        result.push((change, OutputSecret { amount, blinding }));
+
+        for partial_fill_output in rules.partial_fill_outputs.iter() {
+            let (nominal_amount, _) = partial_fill_output.reveal_amount()?;
+            // Scale the value by the fill fraction, rounding up. This cannot exceed
+            // the nominal value, since fill_value <= nominal_change_value.
+            let value = if nominal_change_value == 0 {
+                nominal_amount.value
+            } else {
+                let nominal_change_value = nominal_change_value as u128;
+                ((nominal_amount.value as u128 * fill_value as u128 + nominal_change_value - 1)
+                    / nominal_change_value) as u64
+            };
+            let (output, amount, blinding) = partial_fill_output.change_value(value)?;
+            result.push((output, OutputSecret { amount, blinding }));
+        }
+
+        Ok(result)
+    }
+
     /// Add a pre-signed Input to the transaction, without also fulfilling
     /// any of its rules. You will have to add any required outputs, adjust
     /// tombstone block, etc., for the transaction to be valid.
@@ -497,7 +604,15 @@ impl<FPR: FogPubkeyResolver> TransactionBuilder<FPR> {
         ring_signer: &S,
         rng: &mut RNG,
     ) -> Result<Tx, TxBuilderError> {
-        self.build_with_comparer_internal::<RNG, DefaultTxOutputsOrdering, S>(ring_signer, rng)
+        self.build_unsigned()?.sign(ring_signer, rng)
+    }
+
+    /// Consume the builder and return the transaction, without signing it.
+    ///
+    /// The resulting `UnsignedTx` can be signed later, possibly on another
+    /// machine, by a `RingSigner` holding the spend keys for the inputs.
+    pub fn build_unsigned(self) -> Result<UnsignedTx, TxBuilderError> {
+        self.build_unsigned_with_comparer_internal::<DefaultTxOutputsOrdering>()
     }
 
     /// Consume the builder and return the transaction with a comparer.
@@ -512,20 +627,15 @@ impl<FPR: FogPubkeyResolver> TransactionBuilder<FPR> {
         ring_signer: &S,
         rng: &mut RNG,
     ) -> Result<Tx, TxBuilderError> {
-        self.build_with_comparer_internal::<RNG, O, S>(ring_signer, rng)
+        self.build_unsigned_with_comparer_internal::<O>()?
+            .sign(ring_signer, rng)
     }
 
-    /// Consume the builder and return the transaction with a comparer
-    /// (internal usage only).
-    fn build_with_comparer_internal<
-        RNG: CryptoRng + RngCore,
-        O: TxOutputsOrdering,
-        S: RingSigner + ?Sized,
-    >(
+    /// Consume the builder and return the unsigned transaction with a
+    /// comparer (internal usage only).
+    fn build_unsigned_with_comparer_internal<O: TxOutputsOrdering>(
         mut self,
-        ring_signer: &S,
-        rng: &mut RNG,
-    ) -> Result<Tx, TxBuilderError> {
+    ) -> Result<UnsignedTx, TxBuilderError> {
         // Note: Origin block has block version zero, so some clients like slam that
         // start with a bootstrapped ledger will target block version 0. However,
         // block version zero has no special rules and so targeting block version 0
@@ -582,6 +692,19 @@ impl<FPR: FogPubkeyResolver> TransactionBuilder<FPR> {
                     if !self.block_version.signed_input_rules_are_supported() {
                         return Err(TxBuilderError::SignedInputRulesNotAllowed);
                     }
+                    if !self.block_version.partial_fill_input_rules_are_supported()
+                        && input
+                            .tx_in
+                            .input_rules
+                            .as_ref()
+                            .map(InputRules::has_partial_fill_rules)
+                            .unwrap_or(false)
+                    {
+                        return Err(TxBuilderError::FeatureNotSupportedAtBlockVersion(
+                            *self.block_version,
+                            "partial fill input rules",
+                        ));
+                    }
                     // TODO: Also validate membership proofs?
                     if input.tx_in.ring.len() != input.tx_in.proofs.len() {
                         return Err(TxBuilderError::MissingMembershipProofs);
@@ -619,20 +742,11 @@ impl<FPR: FogPubkeyResolver> TransactionBuilder<FPR> {
             .map(Into::into)
             .collect::<Vec<InputRing>>();
 
-        let message = tx_prefix.hash().0;
-        let signature = SignatureRctBulletproofs::sign(
-            self.block_version,
-            &message,
-            &input_rings,
-            &output_secrets,
-            self.fee,
-            ring_signer,
-            rng,
-        )?;
-
-        Ok(Tx {
-            prefix: tx_prefix,
-            signature,
+        Ok(UnsignedTx {
+            tx_prefix,
+            rings: input_rings,
+            output_secrets,
+            block_version: self.block_version,
         })
     }
 }
@@ -713,7 +827,9 @@ pub mod transaction_builder_tests {
         burn_address, burn_address_view_private, AccountKey, ShortAddressHash,
         CHANGE_SUBADDRESS_INDEX, DEFAULT_SUBADDRESS_INDEX, GIFT_CODE_SUBADDRESS_INDEX,
     };
-    use mc_crypto_ring_signature_signer::{InputSecret, NoKeysRingSigner, OneTimeKeyDeriveData};
+    use mc_crypto_ring_signature_signer::{
+        InputSecret, LocalRingSigner, NoKeysRingSigner, OneTimeKeyDeriveData,
+    };
     use mc_fog_report_validation_test_utils::{FullyValidatedFogPubkey, MockFogResolver};
     use mc_transaction_core::{
         constants::{MAX_INPUTS, MAX_OUTPUTS, MILLIMOB_TO_PICOMOB},
@@ -810,6 +926,76 @@ pub mod transaction_builder_tests {
         }
     }
 
+    #[test]
+    // Build a transaction without signing it, then sign it with the sender's
+    // spend keys, as an offline signer would.
+    fn test_build_unsigned_then_sign() {
+        let mut rng: StdRng = SeedableRng::from_seed([1u8; 32]);
+
+        for (block_version, token_id) in get_block_version_token_id_pairs() {
+            let fpr = MockFogResolver::default();
+            let sender = AccountKey::random(&mut rng);
+            let recipient = AccountKey::random(&mut rng);
+            let value = 1475 * MILLIMOB_TO_PICOMOB;
+            let amount = Amount { value, token_id };
+
+            // The input only carries the subaddress index, not the onetime private key.
+            let (ring, real_index) = get_ring(block_version, amount, 3, &sender, &fpr, &mut rng);
+            let real_output = ring[real_index].clone();
+            let membership_proofs = vec![TxOutMembershipProof::default(); ring.len()];
+            let input_credentials = InputCredentials::new(
+                ring,
+                membership_proofs,
+                real_index,
+                OneTimeKeyDeriveData::SubaddressIndex(DEFAULT_SUBADDRESS_INDEX),
+                *sender.view_private_key(),
+            )
+            .unwrap();
+
+            let key_image = KeyImage::from(&recover_onetime_private_key(
+                &RistrettoPublic::try_from(&real_output.public_key).unwrap(),
+                sender.view_private_key(),
+                &sender.subaddress_spend_private(DEFAULT_SUBADDRESS_INDEX),
+            ));
+
+            let mut transaction_builder = TransactionBuilder::new(
+                block_version,
+                Amount::new(Mob::MINIMUM_FEE, token_id),
+                fpr,
+                EmptyMemoBuilder::default(),
+            )
+            .unwrap();
+
+            transaction_builder.add_input(input_credentials);
+            transaction_builder
+                .add_output(
+                    Amount::new(value - Mob::MINIMUM_FEE, token_id),
+                    &recipient.default_subaddress(),
+                    &mut rng,
+                )
+                .unwrap();
+
+            let unsigned_tx = transaction_builder.build_unsigned().unwrap();
+            assert_eq!(unsigned_tx.rings.len(), 1);
+            assert_eq!(unsigned_tx.output_secrets.len(), 1);
+            assert_eq!(unsigned_tx.fee(), Amount::new(Mob::MINIMUM_FEE, token_id));
+
+            // A signer without keys cannot sign this transaction.
+            assert_matches!(
+                unsigned_tx.sign(&NoKeysRingSigner {}, &mut rng),
+                Err(TxBuilderError::RingSignatureFailed(_))
+            );
+
+            // The sender's keys can.
+            let tx = unsigned_tx
+                .sign(&LocalRingSigner::from(&sender), &mut rng)
+                .unwrap();
+            assert_eq!(tx.prefix, unsigned_tx.tx_prefix);
+            assert_eq!(tx.key_images(), vec![key_image]);
+            assert!(validate_signature(block_version, &tx, &mut rng).is_ok());
+        }
+    }
+
     #[test]
     // Spend a single input and send its full value to a single fog recipient.
     fn test_simple_fog_transaction() {
@@ -0,0 +1,78 @@
+// Copyright (c) 2018-2022 The MobileCoin Foundation
+
+//! A transaction which has been fully constructed, except for its signature.
+
+use crate::TxBuilderError;
+use mc_crypto_ring_signature_signer::RingSigner;
+use mc_transaction_core::{
+    ring_ct::{InputRing, OutputSecret, SignatureRctBulletproofs},
+    tx::{Tx, TxPrefix},
+    Amount, BlockVersion, TokenId,
+};
+use rand_core::{CryptoRng, RngCore};
+
+/// A transaction which has been fully constructed, except for its signature.
+///
+/// This is produced by `TransactionBuilder::build_unsigned`, and allows
+/// separating the construction of a transaction (which only requires view
+/// keys) from signing it (which requires spend keys). The signing step may
+/// happen on a different machine, using any `RingSigner` implementation.
+#[derive(Clone, Debug)]
+pub struct UnsignedTx {
+    /// The fully constructed tx prefix. Inputs and outputs are sorted.
+    pub tx_prefix: TxPrefix,
+
+    /// The input rings, in the same order as `tx_prefix.inputs`.
+    pub rings: Vec<InputRing>,
+
+    /// The output secrets, in the same order as `tx_prefix.outputs`.
+    pub output_secrets: Vec<OutputSecret>,
+
+    /// The block version this transaction targets.
+    pub block_version: BlockVersion,
+}
+
+impl UnsignedTx {
+    /// The fee paid by this transaction.
+    pub fn fee(&self) -> Amount {
+        Amount::new(
+            self.tx_prefix.fee,
+            TokenId::from(self.tx_prefix.fee_token_id),
+        )
+    }
+
+    /// Sign the transaction, producing a `Tx` that can be submitted to the
+    /// network.
+    ///
+    /// # Arguments
+    /// * `ring_signer` - A signer holding the keys that own the signable inputs
+    /// * `rng` - Randomness used when creating the signature
+    pub fn sign<RNG: CryptoRng + RngCore, S: RingSigner + ?Sized>(
+        &self,
+        ring_signer: &S,
+        rng: &mut RNG,
+    ) -> Result<Tx, TxBuilderError> {
+        if self.rings.len() != self.tx_prefix.inputs.len() {
+            return Err(TxBuilderError::InputRingsMismatch(
+                self.rings.len(),
+                self.tx_prefix.inputs.len(),
+            ));
+        }
+
+        let message = self.tx_prefix.hash().0;
+        let signature = SignatureRctBulletproofs::sign(
+            self.block_version,
+            &message,
+            &self.rings,
+            &self.output_secrets,
+            self.fee(),
+            ring_signer,
+            rng,
+        )?;
+
+        Ok(Tx {
+            prefix: self.tx_prefix.clone(),
+            signature,
+        })
+    }
+}
@@ -57,7 +57,7 @@ impl FromStr for BlockVersion {
 impl BlockVersion {
     /// The maximum value of block_version that this build of
     /// mc-transaction-core has support for
-    pub const MAX: Self = Self(3);
+    pub const MAX: Self = Self(4);
 
     /// Refers to the block version number at network launch.
     pub const ZERO: Self = Self(0);
@@ -71,6 +71,9 @@ impl BlockVersion {
     /// Constant for block version three
     pub const THREE: Self = Self(3);
 
+    /// Constant for block version four
+    pub const FOUR: Self = Self(4);
+
     /// Iterator over block versions from one up to max, inclusive. For use in
     /// tests.
     pub fn iterator() -> BlockVersionIterator {
@@ -120,6 +123,12 @@ impl BlockVersion {
         self.0 >= 3
     }
 
+    /// Partial fill rules for signed contingent inputs are introduced in v4.
+    /// [MCIP #42](https://github.com/mobilecoinfoundation/mcips/pull/42)
+    pub fn partial_fill_input_rules_are_supported(&self) -> bool {
+        self.0 >= 4
+    }
+
     /// `BlockData.metadata` is required starting from v3.
     /// [MCIP #43](https://github.com/mobilecoinfoundation/mcips/pull/43)
     pub fn require_block_metadata(&self) -> bool {
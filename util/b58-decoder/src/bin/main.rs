@@ -30,6 +30,26 @@ fn main() {
                     "Memo: {}",
                     printable_wrapper.get_payment_request().get_memo()
                 );
+                let payment_request = printable_wrapper.get_payment_request();
+                if payment_request.get_payment_request_id() != 0 {
+                    println!(
+                        "Payment request id: {}",
+                        payment_request.get_payment_request_id()
+                    );
+                }
+                if payment_request.get_expiration_timestamp() != 0 {
+                    println!(
+                        "Expiration timestamp: {}",
+                        payment_request.get_expiration_timestamp()
+                    );
+                }
+                for line_item in payment_request.get_line_items() {
+                    println!(
+                        "Line item: {} ({})",
+                        line_item.get_description(),
+                        line_item.get_value()
+                    );
+                }
             } else if printable_wrapper.has_transfer_payload() {
                 println!("B58 decoded successfully to a PrintableWrapper with a TransferPayload");
                 println!(
@@ -53,6 +73,32 @@ fn main() {
                     "BIP39 entropy: {}",
                     hex::encode(printable_wrapper.get_transfer_payload().get_bip39_entropy())
                 );
+            } else if printable_wrapper.has_signed_contingent_input() {
+                println!(
+                    "B58 decoded successfully to a PrintableWrapper with a SignedContingentInput"
+                );
+                let sci = printable_wrapper.get_signed_contingent_input();
+                println!("Block version: {}", sci.get_block_version());
+                println!(
+                    "Key image: {}",
+                    hex::encode(sci.get_mlsag().get_key_image().get_data())
+                );
+                println!(
+                    "Offered: {} (token id {})",
+                    sci.get_pseudo_output_amount().get_value(),
+                    sci.get_pseudo_output_amount().get_token_id()
+                );
+                for amount in sci.get_required_output_amounts() {
+                    println!(
+                        "Requested: {} (token id {})",
+                        amount.get_value(),
+                        amount.get_token_id()
+                    );
+                }
+                println!(
+                    "Max tombstone block: {}",
+                    sci.get_tx_in().get_input_rules().get_max_tombstone_block()
+                );
             }
         }
 
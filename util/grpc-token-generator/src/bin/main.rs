@@ -5,7 +5,7 @@
 
 use clap::Parser;
 use mc_common::time::SystemTimeProvider;
-use mc_util_grpc::TokenBasicCredentialsGenerator;
+use mc_util_grpc::{TokenBasicCredentialsGenerator, TokenSigningKey, DEFAULT_TOKEN_SIGNING_KEY_ID};
 use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
 
 /// Configuration.
@@ -19,18 +19,36 @@ pub struct Config {
     #[clap(long, parse(try_from_str = hex::FromHex::from_hex), env = "MC_SHARED_SECRET")]
     pub shared_secret: [u8; 32],
 
+    /// The id of the shared secret, as configured on the token validator.
+    /// Only included in scoped tokens.
+    #[clap(long, default_value = DEFAULT_TOKEN_SIGNING_KEY_ID, env = "MC_KEY_ID")]
+    pub key_id: String,
+
     /// Username to generator the token for
     #[clap(long, env = "MC_USERNAME")]
     pub username: String,
+
+    /// gRPC services or methods the token grants access to, e.g.
+    /// `fog_view.FogViewAPI` or
+    /// `consensus_client.ConsensusClientAPI/ClientTxPropose`. When omitted,
+    /// the token grants access to all methods.
+    #[clap(long = "scope", use_value_delimiter = true, env = "MC_SCOPES")]
+    pub scopes: Vec<String>,
 }
 
 fn main() {
     let config = Config::parse();
+    let signing_key =
+        TokenSigningKey::new(&config.key_id, config.shared_secret).expect("Invalid key id");
     let token_generator =
-        TokenBasicCredentialsGenerator::new(config.shared_secret, SystemTimeProvider::default());
-    let creds = token_generator
-        .generate_for(&config.username)
-        .expect("Failed generating token");
+        TokenBasicCredentialsGenerator::new_with_key(signing_key, SystemTimeProvider::default());
+    let creds = if config.scopes.is_empty() {
+        token_generator.generate_for(&config.username)
+    } else {
+        let scopes = config.scopes.iter().map(String::as_str).collect::<Vec<_>>();
+        token_generator.generate_scoped_for(&config.username, &scopes)
+    }
+    .expect("Failed generating token");
     println!("Username: {}", creds.username());
     println!("Password: {}", creds.password());
     println!(
@@ -8,6 +8,8 @@ mod token_authenticator;
 pub use anonymous_authenticator::{AnonymousAuthenticator, ANONYMOUS_USER};
 pub use token_authenticator::{
     TokenAuthenticator, TokenBasicCredentialsGenerator, TokenBasicCredentialsGeneratorError,
+    TokenSigningKey, TokenSigningKeyParseError, TokenSigningKeys, ALL_SCOPES,
+    DEFAULT_TOKEN_SIGNING_KEY_ID,
 };
 
 use displaydoc::Display;
@@ -31,6 +33,9 @@ pub enum AuthenticatorError {
     /// Authorization header error: {0}
     AuthorizationHeader(AuthorizationHeaderError),
 
+    /// Permission denied: {0}
+    PermissionDenied(String),
+
     /// Other: {0}
     Other(String),
 }
@@ -43,10 +48,11 @@ impl From<AuthorizationHeaderError> for AuthenticatorError {
 
 impl<T> From<AuthenticatorError> for Result<T, RpcStatus> {
     fn from(src: AuthenticatorError) -> Result<T, RpcStatus> {
-        Err(RpcStatus::with_message(
-            RpcStatusCode::UNAUTHENTICATED,
-            src.to_string(),
-        ))
+        let code = match src {
+            AuthenticatorError::PermissionDenied(_) => RpcStatusCode::PERMISSION_DENIED,
+            _ => RpcStatusCode::UNAUTHENTICATED,
+        };
+        Err(RpcStatus::with_message(code, src.to_string()))
     }
 }
 
@@ -59,34 +65,55 @@ pub trait Authenticator {
         maybe_credentials: Option<BasicCredentials>,
     ) -> Result<String, AuthenticatorError>;
 
+    /// Attempt to authenticate a user given their credentials, for a call to
+    /// a specific gRPC method (e.g. `/fog_view.FogViewAPI/Query`)
+    ///
+    /// By default the method is ignored and this calls authenticate
+    fn authenticate_for_method(
+        &self,
+        maybe_credentials: Option<BasicCredentials>,
+        _method: &str,
+    ) -> Result<String, AuthenticatorError> {
+        self.authenticate(maybe_credentials)
+    }
+
     /// Attempt to authenticate a user given their Metadata object
     ///
     /// By default this extracts the BasicCredentials from the Metadata
     fn authenticate_metadata(&self, metadata: &Metadata) -> Result<String, AuthenticatorError> {
-        let creds = metadata
-            .iter()
-            .find_map(|(key, value)| {
-                if key.to_lowercase() == "authorization" {
-                    Some(value)
-                } else {
-                    None
-                }
-            })
-            .map(BasicCredentials::try_from)
-            .transpose()?;
-
-        self.authenticate(creds)
+        self.authenticate(credentials_from_metadata(metadata)?)
     }
 
     /// Attempt to authenticate a user given the RpcContext
     ///
-    /// By default this extracts the request headers and calls
-    /// authenticate_metadata
+    /// By default this extracts the BasicCredentials from the request headers
+    /// and calls authenticate_for_method with the called method
     fn authenticate_rpc(&self, context: &RpcContext) -> Result<String, AuthenticatorError> {
-        self.authenticate_metadata(context.request_headers())
+        let creds = credentials_from_metadata(context.request_headers())?;
+        let method = str::from_utf8(context.method())
+            .map_err(|_| AuthenticatorError::Other("Invalid method name".to_owned()))?;
+        self.authenticate_for_method(creds, method)
     }
 }
 
+/// Extract the BasicCredentials from the authorization header in a Metadata
+/// object, if there is one.
+fn credentials_from_metadata(
+    metadata: &Metadata,
+) -> Result<Option<BasicCredentials>, AuthorizationHeaderError> {
+    metadata
+        .iter()
+        .find_map(|(key, value)| {
+            if key.to_lowercase() == "authorization" {
+                Some(value)
+            } else {
+                None
+            }
+        })
+        .map(BasicCredentials::try_from)
+        .transpose()
+}
+
 /// Standard username/password credentials.
 #[derive(Clone, Default)]
 pub struct BasicCredentials {
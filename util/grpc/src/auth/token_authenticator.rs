@@ -1,25 +1,221 @@
 // Copyright (c) 2018-2022 The MobileCoin Foundation
 
-//! GRPC authenticator that relies on a shared secret for generating and
+//! GRPC authenticator that relies on shared secrets for generating and
 //! verifying tokens.
+//!
+//! Two token formats are supported:
+//! * Legacy tokens, `username:timestamp:signature`, which grant access to all
+//!   methods and are accepted if any of the active signing keys verifies them.
+//! * Scoped tokens, `username:timestamp:key_id:scopes:signature`, which name
+//!   the signing key that produced them and carry a comma-separated list of
+//!   scopes. A scope is either `*`, a fully qualified gRPC service name (e.g.
+//!   `fog_view.FogViewAPI`), or a single method of a service (e.g.
+//!   `consensus_client.ConsensusClientAPI/ClientTxPropose`).
+//!
+//! Having multiple active signing keys allows rotating the shared secret
+//! without downtime: the new key is added to the servers first, then the
+//! token generators are switched over, and finally the old key is removed.
 
 use super::*;
 
 use displaydoc::Display;
-use hmac::{Hmac, Mac};
+use hmac::{digest::InvalidLength, Hmac, Mac};
 use mc_common::time::TimeProvider;
+use serde::{Serialize, Serializer};
 use sha2::Sha256;
-use std::{str, time::Duration};
+use std::{fmt, str, str::FromStr, time::Duration};
 use subtle::ConstantTimeEq;
 use zeroize::Zeroize;
 
+/// The key id assigned to signing keys which are given without an explicit
+/// id, and used by the generator when constructed from a bare secret.
+pub const DEFAULT_TOKEN_SIGNING_KEY_ID: &str = "0";
+
+/// The scope granting access to all methods.
+pub const ALL_SCOPES: &str = "*";
+
+/// The number of bytes of the HMAC output that are included in a token.
+const SIGNATURE_LEN: usize = 10;
+
+/// Error values for parsing token signing keys.
+#[derive(Display, Debug, PartialEq)]
+pub enum TokenSigningKeyParseError {
+    /// Invalid key id: {0}
+    InvalidKeyId(String),
+
+    /// Invalid secret, expected 32 hex-encoded bytes
+    InvalidSecret,
+
+    /// Duplicate key id: {0}
+    DuplicateKeyId(String),
+
+    /// No signing keys were provided
+    Empty,
+}
+
+impl std::error::Error for TokenSigningKeyParseError {}
+
+/// A secret used for signing and verifying tokens, together with the id by
+/// which scoped tokens refer to it.
+#[derive(Clone)]
+pub struct TokenSigningKey {
+    id: String,
+    secret: [u8; 32],
+}
+
+impl TokenSigningKey {
+    /// Create a new signing key.
+    ///
+    /// The id must be non-empty and may not contain ':', ',' or '='.
+    pub fn new(id: &str, secret: [u8; 32]) -> Result<Self, TokenSigningKeyParseError> {
+        if id.is_empty() || id.contains(|c| matches!(c, ':' | ',' | '=')) {
+            return Err(TokenSigningKeyParseError::InvalidKeyId(id.to_owned()));
+        }
+        Ok(Self {
+            id: id.to_owned(),
+            secret,
+        })
+    }
+
+    /// Get the id of this key.
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn sign(&self, data: &str) -> Result<Vec<u8>, InvalidLength> {
+        let mut mac = Hmac::<Sha256>::new_from_slice(&self.secret)?;
+        mac.update(data.as_bytes());
+        Ok(mac.finalize().into_bytes()[..SIGNATURE_LEN].to_vec())
+    }
+}
+
+impl From<[u8; 32]> for TokenSigningKey {
+    fn from(secret: [u8; 32]) -> Self {
+        Self {
+            id: DEFAULT_TOKEN_SIGNING_KEY_ID.to_owned(),
+            secret,
+        }
+    }
+}
+
+impl Drop for TokenSigningKey {
+    fn drop(&mut self) {
+        self.secret.zeroize();
+    }
+}
+
+impl fmt::Debug for TokenSigningKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TokenSigningKey")
+            .field("id", &self.id)
+            .finish_non_exhaustive()
+    }
+}
+
+impl FromStr for TokenSigningKey {
+    type Err = TokenSigningKeyParseError;
+
+    /// Parse either `id=hex_secret`, or a bare `hex_secret` which is given the
+    /// default key id.
+    fn from_str(src: &str) -> Result<Self, Self::Err> {
+        let (id, hex_secret) = src
+            .split_once('=')
+            .unwrap_or((DEFAULT_TOKEN_SIGNING_KEY_ID, src));
+        let secret = <[u8; 32] as hex::FromHex>::from_hex(hex_secret.trim())
+            .map_err(|_| TokenSigningKeyParseError::InvalidSecret)?;
+        Self::new(id.trim(), secret)
+    }
+}
+
+/// The set of signing keys accepted by a `TokenAuthenticator`.
+#[derive(Clone, Debug)]
+pub struct TokenSigningKeys(Vec<TokenSigningKey>);
+
+impl TokenSigningKeys {
+    /// Command line help for options taking a set of signing keys, in the
+    /// format parsed by `from_str`.
+    pub const HELP: &'static str = "Enables authenticating client requests \
+        using Authorization tokens using the provided hex-encoded 32 bytes shared secrets. \
+        Multiple secrets can be given as a comma-separated list of `key_id=hex_secret` entries, \
+        in which case tokens signed by any of them are accepted. This allows rotating secrets \
+        without downtime. A bare `hex_secret` is given the key id \"0\".";
+
+    /// Create a new set of signing keys. Key ids must be unique.
+    pub fn new(keys: Vec<TokenSigningKey>) -> Result<Self, TokenSigningKeyParseError> {
+        if keys.is_empty() {
+            return Err(TokenSigningKeyParseError::Empty);
+        }
+        for (i, key) in keys.iter().enumerate() {
+            if keys[..i].iter().any(|other| other.id == key.id) {
+                return Err(TokenSigningKeyParseError::DuplicateKeyId(key.id.clone()));
+            }
+        }
+        Ok(Self(keys))
+    }
+
+    /// Iterate over the keys.
+    pub fn iter(&self) -> impl Iterator<Item = &TokenSigningKey> {
+        self.0.iter()
+    }
+
+    fn get(&self, id: &str) -> Option<&TokenSigningKey> {
+        self.0.iter().find(|key| key.id == id)
+    }
+}
+
+impl From<[u8; 32]> for TokenSigningKeys {
+    fn from(secret: [u8; 32]) -> Self {
+        Self(vec![TokenSigningKey::from(secret)])
+    }
+}
+
+impl FromStr for TokenSigningKeys {
+    type Err = TokenSigningKeyParseError;
+
+    /// Parse a comma-separated list of signing keys, e.g.
+    /// `2022-01=<hex>,2022-07=<hex>`.
+    fn from_str(src: &str) -> Result<Self, Self::Err> {
+        Self::new(
+            src.split(',')
+                .map(TokenSigningKey::from_str)
+                .collect::<Result<_, _>>()?,
+        )
+    }
+}
+
+/// Only the key ids are serialized, so that secrets never end up in config
+/// dumps.
+impl Serialize for TokenSigningKeys {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.0.iter().map(TokenSigningKey::id))
+    }
+}
+
+/// Check whether a scope grants access to a method.
+///
+/// `method` is the full gRPC method path, e.g. `/fog_view.FogViewAPI/Query`.
+fn scope_matches(scope: &str, method: &str) -> bool {
+    let method = method.trim_start_matches('/');
+    scope == ALL_SCOPES
+        || method == scope
+        || method
+            .strip_prefix(scope)
+            .map_or(false, |rest| rest.starts_with('/'))
+}
+
+fn is_valid_scope(scope: &str) -> bool {
+    !scope.is_empty()
+        && !scope.starts_with('/')
+        && !scope.contains(|c: char| c == ':' || c == ',' || c.is_whitespace())
+}
+
 /// Token-based authentication: An object that implements `Authenticator`,
 /// allowing to authenticate users using HMAC-generated tokens.
 pub struct TokenAuthenticator<TP: TimeProvider> {
-    /// Secret shared between the authenticator and then token generator,
+    /// Secrets shared between the authenticator and the token generators,
     /// allowing for generated tokens to be cryptographically-verified by
     /// the authenticator.
-    shared_secret: [u8; 32],
+    signing_keys: TokenSigningKeys,
 
     /// The maximum duration a token is valid for.
     max_token_lifetime: Duration,
@@ -28,46 +224,37 @@ pub struct TokenAuthenticator<TP: TimeProvider> {
     time_provider: TP,
 }
 
-impl<TP: TimeProvider> Drop for TokenAuthenticator<TP> {
-    fn drop(&mut self) {
-        self.shared_secret.zeroize();
-    }
-}
-
 impl<TP: TimeProvider> Authenticator for TokenAuthenticator<TP> {
     fn authenticate(
         &self,
         maybe_credentials: Option<BasicCredentials>,
     ) -> Result<String, AuthenticatorError> {
         let credentials = maybe_credentials.ok_or(AuthenticatorError::Unauthenticated)?;
-        let mut parts = credentials.password.split(':');
-        let username = parts
-            .next()
-            .ok_or(AuthenticatorError::InvalidAuthorizationToken)?;
-        let timestamp = parts
-            .next()
-            .ok_or(AuthenticatorError::InvalidAuthorizationToken)?;
-        let signature = parts
-            .next()
-            .ok_or(AuthenticatorError::InvalidAuthorizationToken)?;
-        if parts.next().is_some() {
-            return Err(AuthenticatorError::InvalidAuthorizationToken);
-        }
-        if username != credentials.username {
-            return Err(AuthenticatorError::InvalidAuthorizationToken);
-        }
-        if !self.is_valid_time(timestamp)? {
-            return Err(AuthenticatorError::ExpiredAuthorizationToken);
+        let scopes = self.verify_token(&credentials)?;
+        if !scopes.contains(&ALL_SCOPES) {
+            return Err(AuthenticatorError::PermissionDenied(
+                "token is restricted to specific methods".to_owned(),
+            ));
         }
-        if !self.is_valid_signature(&format!("{}:{}", username, timestamp), signature)? {
-            return Err(AuthenticatorError::InvalidAuthorizationToken);
+        Ok(credentials.username)
+    }
+
+    fn authenticate_for_method(
+        &self,
+        maybe_credentials: Option<BasicCredentials>,
+        method: &str,
+    ) -> Result<String, AuthenticatorError> {
+        let credentials = maybe_credentials.ok_or(AuthenticatorError::Unauthenticated)?;
+        let scopes = self.verify_token(&credentials)?;
+        if !scopes.iter().any(|scope| scope_matches(scope, method)) {
+            return Err(AuthenticatorError::PermissionDenied(method.to_owned()));
         }
         Ok(credentials.username)
     }
 }
 
 impl<TP: TimeProvider> TokenAuthenticator<TP> {
-    /// Create a new Token authenticator
+    /// Create a new Token authenticator with a single signing key
     ///
     /// Arguments:
     /// * shared_secret: The shared secret which is used as a key to hmac to
@@ -77,13 +264,77 @@ impl<TP: TimeProvider> TokenAuthenticator<TP> {
     /// * time_provider: A generic object that provides "Duration since the
     ///   epoch"
     pub fn new(shared_secret: [u8; 32], max_token_lifetime: Duration, time_provider: TP) -> Self {
+        Self::new_with_keys(
+            TokenSigningKeys::from(shared_secret),
+            max_token_lifetime,
+            time_provider,
+        )
+    }
+
+    /// Create a new Token authenticator accepting tokens signed by any of
+    /// several keys
+    ///
+    /// Arguments:
+    /// * signing_keys: The currently active signing keys
+    /// * max_token_lifetime: The duration of time that the tokens that we hand
+    ///   out are valid for
+    /// * time_provider: A generic object that provides "Duration since the
+    ///   epoch"
+    pub fn new_with_keys(
+        signing_keys: TokenSigningKeys,
+        max_token_lifetime: Duration,
+        time_provider: TP,
+    ) -> Self {
         Self {
-            shared_secret,
+            signing_keys,
             max_token_lifetime,
             time_provider,
         }
     }
 
+    /// Check the token in the credentials and return the scopes it grants.
+    /// Legacy tokens grant all scopes.
+    fn verify_token<'a>(
+        &self,
+        credentials: &'a BasicCredentials,
+    ) -> Result<Vec<&'a str>, AuthenticatorError> {
+        let parts: Vec<&str> = credentials.password.split(':').collect();
+        let (username, timestamp, signature) = match parts[..] {
+            [username, timestamp, signature] => (username, timestamp, signature),
+            [username, timestamp, _, _, signature] => (username, timestamp, signature),
+            _ => return Err(AuthenticatorError::InvalidAuthorizationToken),
+        };
+        if username != credentials.username {
+            return Err(AuthenticatorError::InvalidAuthorizationToken);
+        }
+        if !self.is_valid_time(timestamp)? {
+            return Err(AuthenticatorError::ExpiredAuthorizationToken);
+        }
+
+        // The signature covers everything up to the last separator.
+        let data = &credentials.password[..credentials.password.len() - signature.len() - 1];
+        match parts[..] {
+            [_, _, key_id, scopes, _] => {
+                let key = self
+                    .signing_keys
+                    .get(key_id)
+                    .ok_or(AuthenticatorError::InvalidAuthorizationToken)?;
+                if !Self::is_valid_signature(key, data, signature)? {
+                    return Err(AuthenticatorError::InvalidAuthorizationToken);
+                }
+                Ok(scopes.split(',').collect())
+            }
+            _ => {
+                for key in self.signing_keys.iter() {
+                    if Self::is_valid_signature(key, data, signature)? {
+                        return Ok(vec![ALL_SCOPES]);
+                    }
+                }
+                Err(AuthenticatorError::InvalidAuthorizationToken)
+            }
+        }
+    }
+
     fn is_valid_time(&self, timestamp: &str) -> Result<bool, AuthenticatorError> {
         let token_time: Duration = Duration::from_secs(
             timestamp
@@ -100,16 +351,17 @@ impl<TP: TimeProvider> TokenAuthenticator<TP> {
         Ok(distance < self.max_token_lifetime)
     }
 
-    fn is_valid_signature(&self, data: &str, signature: &str) -> Result<bool, AuthenticatorError> {
+    fn is_valid_signature(
+        key: &TokenSigningKey,
+        data: &str,
+        signature: &str,
+    ) -> Result<bool, AuthenticatorError> {
         let their_suffix: Vec<u8> =
             hex::decode(signature).map_err(|_| AuthenticatorError::InvalidAuthorizationToken)?;
 
-        let mut mac = Hmac::<Sha256>::new_from_slice(&self.shared_secret)
+        let our_suffix = key
+            .sign(data)
             .map_err(|_| AuthenticatorError::Other("Invalid HMAC key".to_owned()))?;
-        mac.update(data.as_bytes());
-        let our_signature = mac.finalize().into_bytes();
-
-        let our_suffix: &[u8] = &our_signature[..10];
         Ok(bool::from(our_suffix.ct_eq(&their_suffix)))
     }
 }
@@ -122,11 +374,14 @@ pub enum TokenBasicCredentialsGeneratorError {
 
     /// Invalid HMAC key
     InvalidHmacKey,
+
+    /// Invalid scope: {0}
+    InvalidScope(String),
 }
 
 /// Token generator - an object that can generate HMAC authentication tokens.
 pub struct TokenBasicCredentialsGenerator<TP: TimeProvider> {
-    shared_secret: [u8; 32],
+    signing_key: TokenSigningKey,
     time_provider: TP,
 }
 
@@ -138,47 +393,84 @@ impl<TP: TimeProvider> TokenBasicCredentialsGenerator<TP> {
     /// * time_provider: A generic object that provides "Duration since the
     ///   epoch"
     pub fn new(shared_secret: [u8; 32], time_provider: TP) -> Self {
+        Self::new_with_key(TokenSigningKey::from(shared_secret), time_provider)
+    }
+
+    /// Create a new token credential generator which signs using a specific
+    /// key
+    ///
+    /// Arguments:
+    /// * signing_key: The key used as hmac key, and whose id is included in
+    ///   scoped tokens
+    /// * time_provider: A generic object that provides "Duration since the
+    ///   epoch"
+    pub fn new_with_key(signing_key: TokenSigningKey, time_provider: TP) -> Self {
         Self {
-            shared_secret,
+            signing_key,
             time_provider,
         }
     }
 
-    /// Generate a token for a user-id
+    /// Generate a token for a user-id, granting access to all methods
     pub fn generate_for(
         &self,
         user_id: &str,
     ) -> Result<BasicCredentials, TokenBasicCredentialsGeneratorError> {
-        let current_time_seconds = self
+        let prefix = format!("{}:{}", user_id, self.current_time_seconds()?);
+        self.sign(user_id, prefix)
+    }
+
+    /// Generate a token for a user-id, granting access only to the methods
+    /// matched by the given scopes
+    pub fn generate_scoped_for(
+        &self,
+        user_id: &str,
+        scopes: &[&str],
+    ) -> Result<BasicCredentials, TokenBasicCredentialsGeneratorError> {
+        if scopes.is_empty() {
+            return Err(TokenBasicCredentialsGeneratorError::InvalidScope(
+                String::new(),
+            ));
+        }
+        if let Some(scope) = scopes.iter().find(|scope| !is_valid_scope(scope)) {
+            return Err(TokenBasicCredentialsGeneratorError::InvalidScope(
+                scope.to_string(),
+            ));
+        }
+        let prefix = format!(
+            "{}:{}:{}:{}",
+            user_id,
+            self.current_time_seconds()?,
+            self.signing_key.id(),
+            scopes.join(",")
+        );
+        self.sign(user_id, prefix)
+    }
+
+    fn current_time_seconds(&self) -> Result<u64, TokenBasicCredentialsGeneratorError> {
+        Ok(self
             .time_provider
             .since_epoch()
             .map_err(|_| TokenBasicCredentialsGeneratorError::TimeProvider)?
-            .as_secs();
-        let prefix = format!("{}:{}", user_id, current_time_seconds);
+            .as_secs())
+    }
 
-        let mut mac = Hmac::<Sha256>::new_from_slice(&self.shared_secret)
+    fn sign(
+        &self,
+        user_id: &str,
+        prefix: String,
+    ) -> Result<BasicCredentials, TokenBasicCredentialsGeneratorError> {
+        let signature = self
+            .signing_key
+            .sign(&prefix)
             .map_err(|_| TokenBasicCredentialsGeneratorError::InvalidHmacKey)?;
-        mac.update(prefix.as_bytes());
-        let signature = mac.finalize().into_bytes();
-
         Ok(BasicCredentials::new(
             user_id,
-            &format!(
-                "{}:{}:{}",
-                user_id,
-                current_time_seconds,
-                hex::encode(&signature[..10])
-            ),
+            &format!("{}:{}", prefix, hex::encode(signature)),
         ))
     }
 }
 
-impl<TP: TimeProvider> Drop for TokenBasicCredentialsGenerator<TP> {
-    fn drop(&mut self) {
-        self.shared_secret.zeroize();
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -261,4 +553,155 @@ mod tests {
             .is_valid_time(&now_in_seconds.to_string())
             .unwrap());
     }
+
+    #[test]
+    fn scoped_token_is_restricted_to_its_scopes() {
+        const TEST_USERNAME: &str = "test user";
+        let key = TokenSigningKey::new("view", [5; 32]).unwrap();
+        let generator = TokenBasicCredentialsGenerator::new_with_key(
+            key.clone(),
+            SystemTimeProvider::default(),
+        );
+        let authenticator = TokenAuthenticator::new_with_keys(
+            TokenSigningKeys::new(vec![key]).unwrap(),
+            TOKEN_MAX_LIFETIME,
+            SystemTimeProvider::default(),
+        );
+
+        let creds = generator
+            .generate_scoped_for(
+                TEST_USERNAME,
+                &[
+                    "fog_view.FogViewAPI",
+                    "consensus_client.ConsensusClientAPI/ClientTxPropose",
+                ],
+            )
+            .unwrap();
+
+        for method in [
+            "/fog_view.FogViewAPI/Query",
+            "/fog_view.FogViewAPI/Auth",
+            "/consensus_client.ConsensusClientAPI/ClientTxPropose",
+        ] {
+            assert_eq!(
+                authenticator.authenticate_for_method(Some(creds.clone()), method),
+                Ok(TEST_USERNAME.to_owned()),
+                "{}",
+                method
+            );
+        }
+        for method in [
+            "/fog_view.FogViewAPIAdmin/Query",
+            "/consensus_client.ConsensusClientAPI/GetNodeConfig",
+            "/fog_ledger.FogKeyImageAPI/CheckKeyImages",
+        ] {
+            assert_eq!(
+                authenticator.authenticate_for_method(Some(creds.clone()), method),
+                Err(AuthenticatorError::PermissionDenied(method.to_owned())),
+            );
+        }
+
+        // Without knowing the method, only unrestricted tokens are accepted.
+        assert!(matches!(
+            authenticator.authenticate(Some(creds)),
+            Err(AuthenticatorError::PermissionDenied(_))
+        ));
+
+        // Tampering with the scopes invalidates the signature.
+        let tampered = generator
+            .generate_scoped_for(TEST_USERNAME, &["fog_view.FogViewAPI"])
+            .unwrap();
+        let password = tampered
+            .password()
+            .replace("fog_view.FogViewAPI", ALL_SCOPES);
+        assert_eq!(
+            authenticator.authenticate(Some(BasicCredentials::new(TEST_USERNAME, &password))),
+            Err(AuthenticatorError::InvalidAuthorizationToken)
+        );
+
+        assert!(matches!(
+            generator.generate_scoped_for(TEST_USERNAME, &["a:b"]),
+            Err(TokenBasicCredentialsGeneratorError::InvalidScope(_))
+        ));
+    }
+
+    #[test]
+    fn tokens_from_any_active_key_are_accepted() {
+        const TEST_USERNAME: &str = "test user";
+        let old_key = TokenSigningKey::from([6; 32]);
+        let new_key = TokenSigningKey::new("2", [7; 32]).unwrap();
+        let retired_key = TokenSigningKey::new("1", [8; 32]).unwrap();
+
+        let authenticator = TokenAuthenticator::new_with_keys(
+            TokenSigningKeys::new(vec![old_key.clone(), new_key.clone()]).unwrap(),
+            TOKEN_MAX_LIFETIME,
+            SystemTimeProvider::default(),
+        );
+
+        for key in [old_key, new_key] {
+            let generator =
+                TokenBasicCredentialsGenerator::new_with_key(key, SystemTimeProvider::default());
+            let legacy = generator.generate_for(TEST_USERNAME).unwrap();
+            assert_eq!(
+                authenticator.authenticate(Some(legacy)),
+                Ok(TEST_USERNAME.to_owned())
+            );
+            let scoped = generator
+                .generate_scoped_for(TEST_USERNAME, &[ALL_SCOPES])
+                .unwrap();
+            assert_eq!(
+                authenticator.authenticate(Some(scoped)),
+                Ok(TEST_USERNAME.to_owned())
+            );
+        }
+
+        // Tokens signed by a key which is no longer active are rejected.
+        let generator = TokenBasicCredentialsGenerator::new_with_key(
+            retired_key,
+            SystemTimeProvider::default(),
+        );
+        for creds in [
+            generator.generate_for(TEST_USERNAME).unwrap(),
+            generator
+                .generate_scoped_for(TEST_USERNAME, &[ALL_SCOPES])
+                .unwrap(),
+        ] {
+            assert_eq!(
+                authenticator.authenticate(Some(creds)),
+                Err(AuthenticatorError::InvalidAuthorizationToken)
+            );
+        }
+    }
+
+    #[test]
+    fn parse_signing_keys() {
+        let hex_a = hex::encode([1; 32]);
+        let hex_b = hex::encode([2; 32]);
+
+        let keys = TokenSigningKeys::from_str(&hex_a).unwrap();
+        assert_eq!(
+            keys.iter().map(TokenSigningKey::id).collect::<Vec<_>>(),
+            vec![DEFAULT_TOKEN_SIGNING_KEY_ID]
+        );
+
+        let keys = TokenSigningKeys::from_str(&format!("old={},new={}", hex_a, hex_b)).unwrap();
+        assert_eq!(
+            keys.iter().map(TokenSigningKey::id).collect::<Vec<_>>(),
+            vec!["old", "new"]
+        );
+        assert!(!format!("{:?}", keys).contains(&hex_a));
+
+        assert_eq!(
+            TokenSigningKeys::from_str(&format!("a={},a={}", hex_a, hex_b)).unwrap_err(),
+            TokenSigningKeyParseError::DuplicateKeyId("a".to_owned())
+        );
+        assert_eq!(
+            TokenSigningKeys::from_str("a=1234").unwrap_err(),
+            TokenSigningKeyParseError::InvalidSecret
+        );
+        assert_eq!(
+            TokenSigningKeys::from_str(&format!("a:b={}", hex_a)).unwrap_err(),
+            TokenSigningKeyParseError::InvalidKeyId("a:b".to_owned())
+        );
+    }
 }
@@ -30,6 +30,7 @@ mod grpcio_extensions;
 mod health_service;
 mod retry_config;
 mod server_cert_reloader;
+mod trace_context;
 
 pub use crate::{
     admin_server::AdminServer,
@@ -37,7 +38,8 @@ pub use crate::{
     auth::{
         AnonymousAuthenticator, Authenticator, AuthenticatorError, AuthorizationHeaderError,
         BasicCredentials, TokenAuthenticator, TokenBasicCredentialsGenerator,
-        TokenBasicCredentialsGeneratorError, ANONYMOUS_USER,
+        TokenBasicCredentialsGeneratorError, TokenSigningKey, TokenSigningKeyParseError,
+        TokenSigningKeys, ALL_SCOPES, ANONYMOUS_USER, DEFAULT_TOKEN_SIGNING_KEY_ID,
     },
     autogenerated_code::*,
     build_info_service::BuildInfoService,
@@ -47,6 +49,9 @@ pub use crate::{
     health_service::{HealthCheckStatus, HealthService, ReadinessIndicator},
     retry_config::GrpcRetryConfig,
     server_cert_reloader::{ServerCertReloader, ServerCertReloaderError},
+    trace_context::{
+        extract_trace_context, inject_trace_context, start_rpc_span, trace_context_call_option,
+    },
 };
 
 use futures::prelude::*;
@@ -0,0 +1,120 @@
+// Copyright (c) 2018-2022 The MobileCoin Foundation
+
+//! Propagation of OpenTelemetry trace context through gRPC request metadata,
+//! so that a request can be followed across processes.
+//!
+//! The context is encoded by the global propagator, which the tracer setup in
+//! `mc_util_telemetry` configures to use W3C trace context (`traceparent`
+//! headers). Without a configured tracer nothing is injected.
+
+use grpcio::{CallOption, Metadata, MetadataBuilder, RpcContext};
+use mc_util_telemetry::{
+    extract_context, inject_context, Context, ContextGuard, Extractor, Injector, SpanKind,
+    TraceContextExt, Tracer,
+};
+use std::str;
+
+struct MetadataInjector<'a>(&'a mut MetadataBuilder);
+
+impl<'a> Injector for MetadataInjector<'a> {
+    fn set(&mut self, key: &str, value: String) {
+        // Trace context is best-effort, a header we fail to add is dropped.
+        let _ = self.0.add_str(key, &value);
+    }
+}
+
+struct MetadataExtractor<'a>(&'a Metadata);
+
+impl<'a> Extractor for MetadataExtractor<'a> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.iter().find_map(|(header, value)| {
+            if header.eq_ignore_ascii_case(key) {
+                str::from_utf8(value).ok()
+            } else {
+                None
+            }
+        })
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.iter().map(|(header, _)| header).collect()
+    }
+}
+
+/// Add the current trace context to outgoing request metadata.
+pub fn inject_trace_context(metadata_builder: &mut MetadataBuilder) {
+    inject_context(&Context::current(), &mut MetadataInjector(metadata_builder));
+}
+
+/// A CallOption carrying the current trace context, for calls which do not
+/// otherwise set any headers.
+pub fn trace_context_call_option() -> CallOption {
+    let mut metadata_builder = MetadataBuilder::new();
+    inject_trace_context(&mut metadata_builder);
+    CallOption::default().headers(metadata_builder.build())
+}
+
+/// Extract the trace context of the caller from incoming request metadata.
+pub fn extract_trace_context(metadata: &Metadata) -> Context {
+    extract_context(&MetadataExtractor(metadata))
+}
+
+/// Start a server span for an incoming request, as a child of the caller's
+/// trace context if it sent one, and make it the current span until the
+/// returned guard is dropped.
+pub fn start_rpc_span<T>(tracer: &T, ctx: &RpcContext, span_name: &'static str) -> ContextGuard
+where
+    T: Tracer,
+    T::Span: Send + Sync + 'static,
+{
+    let parent = extract_trace_context(ctx.request_headers());
+    let span = tracer
+        .span_builder(span_name)
+        .with_kind(SpanKind::Server)
+        .start_with_context(tracer, &parent);
+    parent.with_span(span).attach()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mc_util_telemetry::set_trace_context_propagator;
+    use opentelemetry::trace::{SpanContext, SpanId, TraceFlags, TraceId, TraceState};
+
+    #[test]
+    fn trace_context_round_trips_through_metadata() {
+        set_trace_context_propagator();
+
+        let span_context = SpanContext::new(
+            TraceId::from_u128(0x1234),
+            SpanId::from_u64(0x5678),
+            TraceFlags::SAMPLED,
+            false,
+            TraceState::default(),
+        );
+        let cx = Context::new().with_remote_span_context(span_context.clone());
+
+        let mut metadata_builder = MetadataBuilder::new();
+        metadata_builder.add_str("chain-id", "local").unwrap();
+        {
+            let _guard = cx.attach();
+            inject_trace_context(&mut metadata_builder);
+        }
+        let metadata = metadata_builder.build();
+
+        let extracted = extract_trace_context(&metadata);
+        let extracted_span = extracted.span();
+        assert_eq!(
+            extracted_span.span_context().trace_id(),
+            span_context.trace_id()
+        );
+        assert_eq!(
+            extracted_span.span_context().span_id(),
+            span_context.span_id()
+        );
+
+        // Requests without trace context get an empty context.
+        let extracted = extract_trace_context(&MetadataBuilder::new().build());
+        assert!(!extracted.span().span_context().is_valid());
+    }
+}
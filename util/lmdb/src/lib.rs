@@ -3,7 +3,12 @@
 //! LMDB utilities / common features.
 
 mod metadata_store;
+mod migration;
 
 pub use metadata_store::{
     MetadataStore, MetadataStoreError, MetadataStoreSettings, MetadataVersion,
 };
+pub use migration::{
+    MigrationError, MigrationEvent, MigrationPlan, MigrationStep, Migrations, PlannedStep,
+    StepProgress,
+};
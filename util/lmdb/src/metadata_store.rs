@@ -1,7 +1,8 @@
 // Copyright (c) 2018-2022 The MobileCoin Foundation
 
 //! MetadataStore - an LMDB database that stores metadata about the database.
-//! Right now this is limited to versioning information.
+//! Right now this is limited to versioning information, and the checkpoint of
+//! an interrupted migration.
 
 use displaydoc::Display;
 use lmdb::{
@@ -98,6 +99,23 @@ impl<S: MetadataStoreSettings> From<&MetadataVersion<S>> for StoredMetadataVersi
     }
 }
 
+/// The progress of a migration step which has not completed yet, stored so
+/// that the step can be resumed.
+#[derive(Clone, Eq, Message, PartialEq)]
+pub(crate) struct StoredMigrationCheckpoint {
+    /// Database format version the step migrates from.
+    #[prost(uint64)]
+    pub from_version: u64,
+
+    /// Database format version the step migrates to.
+    #[prost(uint64)]
+    pub to_version: u64,
+
+    /// Checkpoint of the last batch the step committed.
+    #[prost(uint64)]
+    pub checkpoint: u64,
+}
+
 /// Metadata information about a database instance.
 #[derive(Clone, Debug)]
 pub struct MetadataVersion<S: MetadataStoreSettings> {
@@ -145,6 +163,7 @@ impl<S: MetadataStoreSettings> MetadataVersion<S> {
 
 // Keys in the metadata database
 const METADATA_VERSION_KEY: &str = "version";
+const MIGRATION_CHECKPOINT_KEY: &str = "migration_checkpoint";
 
 #[derive(Clone)]
 pub struct MetadataStore<S: MetadataStoreSettings> {
@@ -230,4 +249,41 @@ impl<S: MetadataStoreSettings> MetadataStore<S> {
             WriteFlags::empty(),
         )?)
     }
+
+    /// Get the checkpoint of an interrupted migration step, if any.
+    pub(crate) fn get_migration_checkpoint(
+        &self,
+        db_txn: &impl Transaction,
+    ) -> Result<Option<StoredMigrationCheckpoint>, MetadataStoreError> {
+        match db_txn.get(self.metadata, &MIGRATION_CHECKPOINT_KEY) {
+            Ok(bytes) => Ok(Some(decode(bytes)?)),
+            Err(LmdbError::NotFound) => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Set the checkpoint of the migration step in progress.
+    pub(crate) fn set_migration_checkpoint(
+        &self,
+        db_txn: &mut RwTransaction,
+        checkpoint: &StoredMigrationCheckpoint,
+    ) -> Result<(), MetadataStoreError> {
+        Ok(db_txn.put(
+            self.metadata,
+            &MIGRATION_CHECKPOINT_KEY,
+            &encode(checkpoint),
+            WriteFlags::empty(),
+        )?)
+    }
+
+    /// Remove the checkpoint of a migration step, once it has completed.
+    pub(crate) fn clear_migration_checkpoint(
+        &self,
+        db_txn: &mut RwTransaction,
+    ) -> Result<(), MetadataStoreError> {
+        match db_txn.del(self.metadata, &MIGRATION_CHECKPOINT_KEY, None) {
+            Ok(()) | Err(LmdbError::NotFound) => Ok(()),
+            Err(err) => Err(err.into()),
+        }
+    }
 }
@@ -0,0 +1,613 @@
+// Copyright (c) 2018-2022 The MobileCoin Foundation
+
+//! Migrations - ordered steps which upgrade an LMDB database from one
+//! `database_format_version` to the next, until it reaches the latest version
+//! of its `MetadataStoreSettings`.
+//!
+//! A step does its work in batches. Each batch is committed together with a
+//! checkpoint of the step's progress, and the new database version is only
+//! written once the step has completed. A migration which was interrupted can
+//! therefore be run again, and continues from the last committed batch.
+
+use crate::{
+    metadata_store::StoredMigrationCheckpoint, MetadataStore, MetadataStoreError,
+    MetadataStoreSettings, MetadataVersion,
+};
+use displaydoc::Display;
+use lmdb::{Environment, Error as LmdbError, RwTransaction, Transaction};
+use std::fmt;
+
+/// A migration error.
+#[derive(Debug, Display, Eq, PartialEq, Clone)]
+pub enum MigrationError {
+    /// LMDB Error: {0}
+    Lmdb(LmdbError),
+
+    /// Metadata store: {0}
+    MetadataStore(MetadataStoreError),
+
+    /// Migration step from version {0} does not migrate to a newer version
+    /// ({1})
+    StepOutOfOrder(u64, u64),
+
+    /// Migration step failed: {0}
+    StepFailed(String),
+}
+
+impl From<LmdbError> for MigrationError {
+    fn from(src: LmdbError) -> Self {
+        Self::Lmdb(src)
+    }
+}
+
+impl From<MetadataStoreError> for MigrationError {
+    fn from(src: MetadataStoreError) -> Self {
+        Self::MetadataStore(src)
+    }
+}
+
+impl From<mc_util_serial::DecodeError> for MigrationError {
+    fn from(_: mc_util_serial::DecodeError) -> Self {
+        Self::MetadataStore(MetadataStoreError::Deserialization)
+    }
+}
+
+/// A step migrating a database from one version to the next.
+pub trait MigrationStep<S: MetadataStoreSettings> {
+    /// The database format version this step migrates from.
+    fn from_version(&self) -> u64;
+
+    /// The database format version this step migrates to.
+    fn to_version(&self) -> u64;
+
+    /// A short description of the step, used for progress reporting.
+    fn description(&self) -> &'static str;
+
+    /// Run the step.
+    ///
+    /// The step should resume from `progress.checkpoint()`, which is zero
+    /// when the step starts, and commit its work in batches using
+    /// `progress.commit_batch()`. Anything done outside of a batch, such as
+    /// creating LMDB databases, is repeated when the step is resumed and must
+    /// be idempotent.
+    fn run(&self, env: &Environment, progress: &mut StepProgress<S>) -> Result<(), MigrationError>;
+}
+
+/// A progress report of a migration.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum MigrationEvent {
+    /// A step started, or was resumed from a non-zero checkpoint.
+    StepStarted {
+        from_version: u64,
+        to_version: u64,
+        description: &'static str,
+        resume_checkpoint: u64,
+    },
+
+    /// A step committed a batch, and has done `completed` out of `total`
+    /// units of work.
+    BatchCommitted {
+        from_version: u64,
+        to_version: u64,
+        completed: u64,
+        total: u64,
+    },
+
+    /// A step completed, and the database is now at `to_version`.
+    StepCompleted { from_version: u64, to_version: u64 },
+}
+
+impl fmt::Display for MigrationEvent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::StepStarted {
+                from_version,
+                to_version,
+                description,
+                resume_checkpoint,
+            } => {
+                write!(
+                    f,
+                    "migrating from version {} to {} ({})",
+                    from_version, to_version, description
+                )?;
+                if *resume_checkpoint > 0 {
+                    write!(f, ", resuming from checkpoint {}", resume_checkpoint)?;
+                }
+                Ok(())
+            }
+            Self::BatchCommitted {
+                from_version,
+                to_version,
+                completed,
+                total,
+            } => write!(
+                f,
+                "migrating from version {} to {}: {}/{} ({}% complete)",
+                from_version,
+                to_version,
+                completed,
+                total,
+                completed * 100 / (*total).max(1)
+            ),
+            Self::StepCompleted {
+                from_version,
+                to_version,
+            } => write!(
+                f,
+                "migration from version {} complete, now at version {}",
+                from_version, to_version
+            ),
+        }
+    }
+}
+
+/// The handle through which a running step resumes and commits its work.
+pub struct StepProgress<'a, S: MetadataStoreSettings> {
+    metadata_store: &'a MetadataStore<S>,
+    from_version: u64,
+    to_version: u64,
+    checkpoint: u64,
+    on_event: &'a mut dyn FnMut(&MigrationEvent),
+}
+
+impl<'a, S: MetadataStoreSettings> StepProgress<'a, S> {
+    /// The checkpoint of the last batch the step committed, or zero if it
+    /// has not committed any.
+    pub fn checkpoint(&self) -> u64 {
+        self.checkpoint
+    }
+
+    /// Record `checkpoint` in `db_txn` and commit it, reporting that
+    /// `checkpoint` out of `total` units of work are done.
+    pub fn commit_batch(
+        &mut self,
+        mut db_txn: RwTransaction,
+        checkpoint: u64,
+        total: u64,
+    ) -> Result<(), MigrationError> {
+        self.metadata_store.set_migration_checkpoint(
+            &mut db_txn,
+            &StoredMigrationCheckpoint {
+                from_version: self.from_version,
+                to_version: self.to_version,
+                checkpoint,
+            },
+        )?;
+        db_txn.commit()?;
+        self.checkpoint = checkpoint;
+
+        (self.on_event)(&MigrationEvent::BatchCommitted {
+            from_version: self.from_version,
+            to_version: self.to_version,
+            completed: checkpoint,
+            total,
+        });
+        Ok(())
+    }
+}
+
+/// A step which a migration would run.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PlannedStep {
+    /// The database format version the step migrates from.
+    pub from_version: u64,
+
+    /// The database format version the step migrates to.
+    pub to_version: u64,
+
+    /// A short description of the step.
+    pub description: &'static str,
+}
+
+/// The steps a migration would run, as found by a dry run.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MigrationPlan {
+    /// The current database format version.
+    pub current_version: u64,
+
+    /// The database format version the migration upgrades to.
+    pub latest_version: u64,
+
+    /// The steps to run, in order.
+    pub steps: Vec<PlannedStep>,
+
+    /// The checkpoint the first step resumes from, if it was interrupted.
+    pub resume_checkpoint: Option<u64>,
+}
+
+impl MigrationPlan {
+    /// Check if the database is already at the latest version.
+    pub fn is_up_to_date(&self) -> bool {
+        self.steps.is_empty()
+    }
+}
+
+/// The migration steps registered for a database.
+pub struct Migrations<S: MetadataStoreSettings> {
+    steps: Vec<Box<dyn MigrationStep<S>>>,
+}
+
+impl<S: MetadataStoreSettings> Default for Migrations<S> {
+    fn default() -> Self {
+        Self { steps: Vec::new() }
+    }
+}
+
+impl<S: MetadataStoreSettings> Migrations<S> {
+    /// Create an empty set of migration steps.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a migration step.
+    pub fn with_step(mut self, step: impl MigrationStep<S> + 'static) -> Self {
+        self.steps.push(Box::new(step));
+        self
+    }
+
+    /// Find the steps which would run to migrate the database, without
+    /// modifying it.
+    pub fn plan(
+        &self,
+        env: &Environment,
+        metadata_store: &MetadataStore<S>,
+    ) -> Result<MigrationPlan, MigrationError> {
+        let db_txn = env.begin_ro_txn()?;
+        let version = metadata_store.get_version(&db_txn)?;
+        let checkpoint = metadata_store.get_migration_checkpoint(&db_txn)?;
+        db_txn.commit()?;
+
+        let steps = self.steps_from(version.database_format_version)?;
+        let resume_checkpoint = steps
+            .first()
+            .and_then(|step| resume_checkpoint(*step, checkpoint.as_ref()));
+
+        Ok(MigrationPlan {
+            current_version: version.database_format_version,
+            latest_version: S::LATEST_VERSION,
+            steps: steps
+                .iter()
+                .map(|step| PlannedStep {
+                    from_version: step.from_version(),
+                    to_version: step.to_version(),
+                    description: step.description(),
+                })
+                .collect(),
+            resume_checkpoint,
+        })
+    }
+
+    /// Migrate the database to the latest version, resuming an interrupted
+    /// step if there is one, and return the resulting version.
+    ///
+    /// If no registered step migrates from the current version, a
+    /// `MetadataStoreError::VersionIncompatible` error is returned.
+    pub fn migrate(
+        &self,
+        env: &Environment,
+        metadata_store: &MetadataStore<S>,
+        mut on_event: impl FnMut(&MigrationEvent),
+    ) -> Result<MetadataVersion<S>, MigrationError> {
+        let db_txn = env.begin_ro_txn()?;
+        let version = metadata_store.get_version(&db_txn)?;
+        let mut checkpoint = metadata_store.get_migration_checkpoint(&db_txn)?;
+        db_txn.commit()?;
+
+        for step in self.steps_from(version.database_format_version)? {
+            let resume_from = resume_checkpoint(step, checkpoint.take().as_ref()).unwrap_or(0);
+            on_event(&MigrationEvent::StepStarted {
+                from_version: step.from_version(),
+                to_version: step.to_version(),
+                description: step.description(),
+                resume_checkpoint: resume_from,
+            });
+
+            let mut progress = StepProgress {
+                metadata_store,
+                from_version: step.from_version(),
+                to_version: step.to_version(),
+                checkpoint: resume_from,
+                on_event: &mut on_event,
+            };
+            step.run(env, &mut progress)?;
+
+            let mut db_txn = env.begin_rw_txn()?;
+            metadata_store.set_version(&mut db_txn, step.to_version())?;
+            metadata_store.clear_migration_checkpoint(&mut db_txn)?;
+            db_txn.commit()?;
+
+            on_event(&MigrationEvent::StepCompleted {
+                from_version: step.from_version(),
+                to_version: step.to_version(),
+            });
+        }
+
+        let db_txn = env.begin_ro_txn()?;
+        let version = metadata_store.get_version(&db_txn)?;
+        db_txn.commit()?;
+        Ok(version)
+    }
+
+    /// The chain of steps leading from `version` to the latest version.
+    fn steps_from(&self, mut version: u64) -> Result<Vec<&dyn MigrationStep<S>>, MigrationError> {
+        let mut steps = Vec::new();
+        while version != S::LATEST_VERSION {
+            let step = self
+                .steps
+                .iter()
+                .find(|step| step.from_version() == version)
+                .ok_or(MetadataStoreError::VersionIncompatible(
+                    version,
+                    S::LATEST_VERSION,
+                ))?;
+            if step.to_version() <= version {
+                return Err(MigrationError::StepOutOfOrder(
+                    step.from_version(),
+                    step.to_version(),
+                ));
+            }
+            version = step.to_version();
+            steps.push(step.as_ref());
+        }
+        Ok(steps)
+    }
+}
+
+/// The checkpoint `step` resumes from, if the stored checkpoint belongs to it.
+fn resume_checkpoint<S: MetadataStoreSettings>(
+    step: &dyn MigrationStep<S>,
+    checkpoint: Option<&StoredMigrationCheckpoint>,
+) -> Option<u64> {
+    checkpoint
+        .filter(|checkpoint| {
+            checkpoint.from_version == step.from_version()
+                && checkpoint.to_version == step.to_version()
+        })
+        .map(|checkpoint| checkpoint.checkpoint)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lmdb::{Cursor, DatabaseFlags, WriteFlags};
+    use std::cell::Cell;
+    use tempdir::TempDir;
+
+    const DATA_DB_NAME: &str = "test_data";
+
+    #[derive(Clone, Default, Debug)]
+    struct TestSettings;
+    impl MetadataStoreSettings for TestSettings {
+        const LATEST_VERSION: u64 = 3;
+        const CRATE_VERSION: &'static str = env!("CARGO_PKG_VERSION");
+        const DB_NAME: &'static str = "test_metadata";
+    }
+
+    /// Writes one key per unit of work, two units per batch, and fails once
+    /// when reaching `fail_at`.
+    struct WriteKeysStep {
+        from_version: u64,
+        total: u64,
+        fail_at: Cell<Option<u64>>,
+    }
+
+    impl WriteKeysStep {
+        fn new(from_version: u64, total: u64) -> Self {
+            Self {
+                from_version,
+                total,
+                fail_at: Cell::new(None),
+            }
+        }
+    }
+
+    impl MigrationStep<TestSettings> for WriteKeysStep {
+        fn from_version(&self) -> u64 {
+            self.from_version
+        }
+
+        fn to_version(&self) -> u64 {
+            self.from_version + 1
+        }
+
+        fn description(&self) -> &'static str {
+            "write keys"
+        }
+
+        fn run(
+            &self,
+            env: &Environment,
+            progress: &mut StepProgress<TestSettings>,
+        ) -> Result<(), MigrationError> {
+            let data = env.create_db(Some(DATA_DB_NAME), DatabaseFlags::empty())?;
+
+            let mut start = progress.checkpoint();
+            while start < self.total {
+                if self.fail_at.get() == Some(start) {
+                    self.fail_at.set(None);
+                    return Err(MigrationError::StepFailed("interrupted".to_owned()));
+                }
+
+                let end = (start + 2).min(self.total);
+                let mut db_txn = env.begin_rw_txn()?;
+                for i in start..end {
+                    // Redoing committed work would fail.
+                    db_txn.put(
+                        data,
+                        &format!("{}:{}", self.from_version, i),
+                        &[],
+                        WriteFlags::NO_OVERWRITE,
+                    )?;
+                }
+                progress.commit_batch(db_txn, end, self.total)?;
+                start = end;
+            }
+            Ok(())
+        }
+    }
+
+    fn create_env(version: u64) -> (TempDir, Environment, MetadataStore<TestSettings>) {
+        let temp_dir = TempDir::new("migration").unwrap();
+        let env = Environment::new()
+            .set_max_dbs(10)
+            .open(temp_dir.path())
+            .unwrap();
+        let metadata_store = MetadataStore::<TestSettings>::open_or_create(&env).unwrap();
+
+        let mut db_txn = env.begin_rw_txn().unwrap();
+        metadata_store.set_version(&mut db_txn, version).unwrap();
+        db_txn.commit().unwrap();
+
+        (temp_dir, env, metadata_store)
+    }
+
+    fn count_keys(env: &Environment) -> usize {
+        let data = env.open_db(Some(DATA_DB_NAME)).unwrap();
+        let db_txn = env.begin_ro_txn().unwrap();
+        let mut cursor = db_txn.open_ro_cursor(data).unwrap();
+        cursor.iter_start().count()
+    }
+
+    #[test]
+    fn migrate_runs_steps_in_order() {
+        let (_temp_dir, env, metadata_store) = create_env(1);
+        let migrations = Migrations::new()
+            .with_step(WriteKeysStep::new(2, 2))
+            .with_step(WriteKeysStep::new(1, 3));
+
+        let plan = migrations.plan(&env, &metadata_store).unwrap();
+        assert_eq!(plan.current_version, 1);
+        assert_eq!(plan.latest_version, 3);
+        assert_eq!(
+            plan.steps
+                .iter()
+                .map(|step| (step.from_version, step.to_version))
+                .collect::<Vec<_>>(),
+            vec![(1, 2), (2, 3)]
+        );
+        assert_eq!(plan.resume_checkpoint, None);
+
+        let mut events = Vec::new();
+        let version = migrations
+            .migrate(&env, &metadata_store, |event| events.push(event.clone()))
+            .unwrap();
+        assert_eq!(version.database_format_version, 3);
+        assert_eq!(count_keys(&env), 5);
+        assert_eq!(
+            events,
+            vec![
+                MigrationEvent::StepStarted {
+                    from_version: 1,
+                    to_version: 2,
+                    description: "write keys",
+                    resume_checkpoint: 0,
+                },
+                MigrationEvent::BatchCommitted {
+                    from_version: 1,
+                    to_version: 2,
+                    completed: 2,
+                    total: 3,
+                },
+                MigrationEvent::BatchCommitted {
+                    from_version: 1,
+                    to_version: 2,
+                    completed: 3,
+                    total: 3,
+                },
+                MigrationEvent::StepCompleted {
+                    from_version: 1,
+                    to_version: 2,
+                },
+                MigrationEvent::StepStarted {
+                    from_version: 2,
+                    to_version: 3,
+                    description: "write keys",
+                    resume_checkpoint: 0,
+                },
+                MigrationEvent::BatchCommitted {
+                    from_version: 2,
+                    to_version: 3,
+                    completed: 2,
+                    total: 2,
+                },
+                MigrationEvent::StepCompleted {
+                    from_version: 2,
+                    to_version: 3,
+                },
+            ]
+        );
+
+        // Migrating again does nothing.
+        assert!(migrations
+            .plan(&env, &metadata_store)
+            .unwrap()
+            .is_up_to_date());
+        let mut events = Vec::new();
+        migrations
+            .migrate(&env, &metadata_store, |event| events.push(event.clone()))
+            .unwrap();
+        assert!(events.is_empty());
+    }
+
+    #[test]
+    fn migrate_resumes_interrupted_step() {
+        let (_temp_dir, env, metadata_store) = create_env(2);
+        let step = WriteKeysStep::new(2, 7);
+        step.fail_at.set(Some(4));
+        let migrations = Migrations::new().with_step(step);
+
+        assert_eq!(
+            migrations
+                .migrate(&env, &metadata_store, |_| {})
+                .unwrap_err(),
+            MigrationError::StepFailed("interrupted".to_owned())
+        );
+        assert_eq!(count_keys(&env), 4);
+
+        let plan = migrations.plan(&env, &metadata_store).unwrap();
+        assert_eq!(plan.current_version, 2);
+        assert_eq!(plan.resume_checkpoint, Some(4));
+
+        let mut events = Vec::new();
+        let version = migrations
+            .migrate(&env, &metadata_store, |event| events.push(event.clone()))
+            .unwrap();
+        assert_eq!(version.database_format_version, 3);
+        assert_eq!(count_keys(&env), 7);
+        assert_eq!(
+            events[0],
+            MigrationEvent::StepStarted {
+                from_version: 2,
+                to_version: 3,
+                description: "write keys",
+                resume_checkpoint: 4,
+            }
+        );
+
+        // The checkpoint is gone once the step has completed.
+        let db_txn = env.begin_ro_txn().unwrap();
+        assert_eq!(
+            metadata_store.get_migration_checkpoint(&db_txn).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn migrate_without_step_is_incompatible() {
+        let (_temp_dir, env, metadata_store) = create_env(1);
+        let migrations = Migrations::new().with_step(WriteKeysStep::new(2, 2));
+
+        let expected = MigrationError::MetadataStore(MetadataStoreError::VersionIncompatible(1, 3));
+        assert_eq!(
+            migrations.plan(&env, &metadata_store).unwrap_err(),
+            expected
+        );
+        assert_eq!(
+            migrations
+                .migrate(&env, &metadata_store, |_| {})
+                .unwrap_err(),
+            expected
+        );
+    }
+}
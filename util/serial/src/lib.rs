@@ -116,6 +116,24 @@ mod json_u64 {
             &self.0
         }
     }
+
+    /// JsonU64 is described as a string in JSON schemas, since that is how it
+    /// is serialized.
+    #[cfg(feature = "schemars")]
+    impl schemars::JsonSchema for JsonU64 {
+        fn schema_name() -> String {
+            "JsonU64".to_owned()
+        }
+
+        fn json_schema(_gen: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
+            schemars::schema::SchemaObject {
+                instance_type: Some(schemars::schema::InstanceType::String.into()),
+                format: Some("uint64".to_owned()),
+                ..Default::default()
+            }
+            .into()
+        }
+    }
 }
 
 /// JsonU64 is exported if it is available -- the serde_with crate which it
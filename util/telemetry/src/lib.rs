@@ -3,12 +3,14 @@
 //! OpenTelemetry wrappers and helper utilities.
 
 pub use opentelemetry::{
+    propagation::{Extractor, Injector},
     trace::{mark_span_as_active, Span, SpanKind, TraceContextExt, Tracer},
-    Context, Key,
+    Context, ContextGuard, Key,
 };
 
 use opentelemetry::{
-    global::{tracer_provider, BoxedTracer},
+    global::{get_text_map_propagator, set_text_map_propagator, tracer_provider, BoxedTracer},
+    sdk::propagation::TraceContextPropagator,
     trace::{SpanBuilder, TraceId, TracerProvider},
 };
 use std::borrow::Cow;
@@ -36,6 +38,10 @@ macro_rules! telemetry_static_key {
     };
 }
 
+/// The attribute holding the hash of a transaction, set on the spans of every
+/// service a transaction passes through, so that they can all be found by it.
+pub const TELEMETRY_TX_HASH_KEY: Key = Key::from_static_str("mobilecoin.com/tx-hash");
+
 /// A magic value that is used as part of the predictable trace id generated by
 /// `block_index_to_trace_id`. (Hex encoding of 'BLKID', chosen arbitrarily)
 pub const BLOCK_INDEX_TRACE_ID_MAGIC: u128 = 0x424c4b4944;
@@ -83,8 +89,72 @@ pub fn start_block_span<T: Tracer>(
     block_span_builder(tracer, span_name, block_index).start(tracer)
 }
 
+/// Install the W3C trace context propagator as the global propagator, so that
+/// `inject_context` and `extract_context` carry trace context between
+/// processes.
+///
+/// This is done by the tracer setup functions, and only needs to be called
+/// directly when not using them.
+pub fn set_trace_context_propagator() {
+    set_text_map_propagator(TraceContextPropagator::new());
+}
+
+/// Inject a context into a carrier (e.g. request headers), using the global
+/// propagator.
+pub fn inject_context(cx: &Context, injector: &mut dyn Injector) {
+    get_text_map_propagator(|propagator| propagator.inject_context(cx, injector))
+}
+
+/// Extract a context from a carrier (e.g. request headers), using the global
+/// propagator. Returns an empty context if the carrier has no trace context.
+pub fn extract_context(extractor: &dyn Extractor) -> Context {
+    get_text_map_propagator(|propagator| propagator.extract(extractor))
+}
+
 #[cfg(feature = "jaeger")]
 mod jaeger;
 
-#[cfg(feature = "jaeger")]
-pub use jaeger::{setup_default_tracer, setup_default_tracer_with_tags, Error};
+#[cfg(feature = "otlp")]
+mod otlp;
+
+#[cfg(any(feature = "jaeger", feature = "otlp"))]
+mod setup;
+
+#[cfg(any(feature = "jaeger", feature = "otlp"))]
+pub use setup::{
+    setup_default_tracer, setup_default_tracer_with_tags, setup_tracer, Error, TelemetryExporter,
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn trace_context_round_trip() {
+        set_trace_context_propagator();
+
+        let span_context = opentelemetry::trace::SpanContext::new(
+            block_index_to_trace_id(7),
+            opentelemetry::trace::SpanId::from_u64(42),
+            opentelemetry::trace::TraceFlags::SAMPLED,
+            false,
+            Default::default(),
+        );
+        let cx = Context::new().with_remote_span_context(span_context.clone());
+
+        let mut headers = HashMap::<String, String>::new();
+        inject_context(&cx, &mut headers);
+        assert!(headers.contains_key("traceparent"));
+
+        let extracted = extract_context(&headers);
+        let extracted_span_context = extracted.span().span_context().clone();
+        assert_eq!(extracted_span_context.trace_id(), span_context.trace_id());
+        assert_eq!(extracted_span_context.span_id(), span_context.span_id());
+        assert!(extracted_span_context.is_remote());
+
+        // A carrier without trace context yields an empty context.
+        let empty = extract_context(&HashMap::<String, String>::new());
+        assert!(!empty.span().span_context().is_valid());
+    }
+}
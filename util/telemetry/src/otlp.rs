@@ -0,0 +1,203 @@
+// Copyright (c) 2018-2022 The MobileCoin Foundation
+
+//! OTLP exporters, over gRPC and over HTTP (protobuf encoded).
+//!
+//! When no endpoint is given, the exporters honor the standard
+//! OTEL_EXPORTER_OTLP_ENDPOINT and OTEL_EXPORTER_OTLP_TIMEOUT variables, and
+//! otherwise fall back to the local collector defaults.
+
+use opentelemetry::{sdk, trace::TraceError};
+use opentelemetry_otlp::WithExportConfig;
+
+/// Install an OTLP/gRPC pipeline as the global tracer provider.
+pub fn install_grpc(
+    endpoint: Option<&str>,
+    trace_config: sdk::trace::Config,
+) -> Result<sdk::trace::Tracer, TraceError> {
+    let mut exporter = opentelemetry_otlp::new_exporter().grpcio().with_env();
+    if let Some(endpoint) = endpoint {
+        exporter = exporter.with_endpoint(endpoint);
+    }
+    opentelemetry_otlp::new_pipeline()
+        .tracing()
+        .with_exporter(exporter)
+        .with_trace_config(trace_config)
+        .install_simple()
+}
+
+/// Install an OTLP/HTTP pipeline as the global tracer provider.
+///
+/// The endpoint is the full URL spans are posted to, e.g.
+/// `http://localhost:4318/v1/traces`.
+pub fn install_http(
+    endpoint: Option<&str>,
+    trace_config: sdk::trace::Config,
+) -> Result<sdk::trace::Tracer, TraceError> {
+    let mut exporter = opentelemetry_otlp::new_exporter().http().with_env();
+    if let Some(endpoint) = endpoint {
+        exporter = exporter.with_endpoint(endpoint);
+    }
+    opentelemetry_otlp::new_pipeline()
+        .tracing()
+        .with_exporter(exporter)
+        .with_trace_config(trace_config)
+        .install_simple()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{setup_tracer, Span, TelemetryExporter, Tracer};
+    use grpcio::{
+        EnvBuilder, Marshaller, Method, MethodType, RpcContext, Server, ServerBuilder,
+        ServiceBuilder, UnarySink,
+    };
+    use lazy_static::lazy_static;
+    use protobuf::{well_known_types::Empty, Message};
+    use std::{
+        io::{BufRead, BufReader, Read, Write},
+        net::TcpListener,
+        sync::{mpsc, Arc, Mutex},
+        thread,
+        time::Duration,
+    };
+
+    lazy_static! {
+        /// Held by tests which install a global tracer provider, so that they
+        /// do not export each other's spans.
+        static ref GLOBAL_TRACER_LOCK: Mutex<()> = Mutex::new(());
+    }
+
+    /// The OTLP trace export method. Requests are decoded as `Empty`, which
+    /// keeps their fields as unknown fields, so that no generated OTLP types
+    /// are needed.
+    const METHOD_EXPORT: Method<Empty, Empty> = Method {
+        ty: MethodType::Unary,
+        name: "/opentelemetry.proto.collector.trace.v1.TraceService/Export",
+        req_mar: Marshaller {
+            ser: grpcio::pb_ser,
+            de: grpcio::pb_de,
+        },
+        resp_mar: Marshaller {
+            ser: grpcio::pb_ser,
+            de: grpcio::pb_de,
+        },
+    };
+
+    /// A stand-in for an OTLP/gRPC collector, which hands back the body of
+    /// each export request.
+    fn spawn_grpc_collector() -> (Server, String, mpsc::Receiver<Vec<u8>>) {
+        let (sender, receiver) = mpsc::channel();
+        let service = ServiceBuilder::new()
+            .add_unary_handler(
+                &METHOD_EXPORT,
+                move |ctx: RpcContext, request: Empty, sink: UnarySink<Empty>| {
+                    let _ = sender.send(request.write_to_bytes().unwrap());
+                    ctx.spawn(async move {
+                        let _ = sink.success(Empty::new()).await;
+                    });
+                },
+            )
+            .build();
+
+        let env = Arc::new(EnvBuilder::new().build());
+        let mut server = ServerBuilder::new(env)
+            .register_service(service)
+            .bind("127.0.0.1", 0)
+            .build()
+            .unwrap();
+        server.start();
+        let port = server.bind_addrs().next().unwrap().1;
+
+        (server, format!("127.0.0.1:{}", port), receiver)
+    }
+
+    fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+        haystack
+            .windows(needle.len())
+            .any(|window| window == needle)
+    }
+
+    /// A stand-in for an OTLP/HTTP collector: accepts a single export request
+    /// and hands back its request line and body.
+    fn spawn_collector() -> (String, mpsc::Receiver<(String, Vec<u8>)>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let endpoint = format!("http://{}/v1/traces", listener.local_addr().unwrap());
+        let (sender, receiver) = mpsc::channel();
+
+        thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+
+            let mut request_line = String::new();
+            reader.read_line(&mut request_line).unwrap();
+            let mut content_length = 0;
+            loop {
+                let mut header = String::new();
+                reader.read_line(&mut header).unwrap();
+                if header.trim().is_empty() {
+                    break;
+                }
+                if let Some((name, value)) = header.split_once(':') {
+                    if name.eq_ignore_ascii_case("content-length") {
+                        content_length = value.trim().parse().unwrap();
+                    }
+                }
+            }
+            let mut body = vec![0; content_length];
+            reader.read_exact(&mut body).unwrap();
+
+            let mut stream = stream;
+            stream
+                .write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\n\r\n")
+                .unwrap();
+            sender.send((request_line, body)).unwrap();
+        });
+
+        (endpoint, receiver)
+    }
+
+    #[test]
+    fn spans_are_exported_over_grpc() {
+        let _lock = GLOBAL_TRACER_LOCK
+            .lock()
+            .unwrap_or_else(|err| err.into_inner());
+        let (_server, endpoint, receiver) = spawn_grpc_collector();
+
+        let tracer = install_grpc(Some(&endpoint), sdk::trace::config()).unwrap();
+        tracer.start("otlp_grpc_test_span").end();
+        opentelemetry::global::shutdown_tracer_provider();
+
+        let body = receiver.recv_timeout(Duration::from_secs(30)).unwrap();
+        assert!(contains(&body, b"otlp_grpc_test_span"));
+    }
+
+    #[test]
+    fn spans_are_exported_over_http() {
+        let _lock = GLOBAL_TRACER_LOCK
+            .lock()
+            .unwrap_or_else(|err| err.into_inner());
+        let (endpoint, receiver) = spawn_collector();
+
+        let tracer = setup_tracer(
+            "mc-util-telemetry-test",
+            &TelemetryExporter::OtlpHttp {
+                endpoint: Some(endpoint),
+            },
+            &[("test_tag", "test_value".to_owned())],
+        )
+        .unwrap();
+        tracer.start("otlp_test_span").end();
+        opentelemetry::global::shutdown_tracer_provider();
+
+        let (request_line, body) = receiver.recv_timeout(Duration::from_secs(30)).unwrap();
+        assert!(request_line.starts_with("POST /v1/traces "));
+        for expected in [
+            &b"otlp_test_span"[..],
+            b"mc-util-telemetry-test",
+            b"test_value",
+        ] {
+            assert!(contains(&body, expected));
+        }
+    }
+}
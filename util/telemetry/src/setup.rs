@@ -0,0 +1,171 @@
+// Copyright (c) 2018-2022 The MobileCoin Foundation
+
+//! Environment-driven setup of the global tracer.
+
+use crate::set_trace_context_propagator;
+use displaydoc::Display;
+use opentelemetry::{sdk, trace::TraceError, KeyValue};
+use std::str::FromStr;
+
+#[derive(Debug, Display)]
+pub enum Error {
+    /// Trace error: {0}
+    Trace(TraceError),
+
+    /// Get hostname error: {0}
+    GetHostname(std::io::Error),
+
+    /// Failed converting hostname to string
+    HostnameToString,
+
+    /// Unknown telemetry exporter: {0}
+    UnknownExporter(String),
+
+    /// Telemetry exporter {0} is not enabled in this build
+    ExporterNotEnabled(TelemetryExporter),
+}
+
+impl From<TraceError> for Error {
+    fn from(src: TraceError) -> Self {
+        Self::Trace(src)
+    }
+}
+
+/// The exporter used to ship spans to the tracing backend.
+#[derive(Clone, Debug, Display, Eq, PartialEq)]
+pub enum TelemetryExporter {
+    /// jaeger
+    Jaeger,
+
+    /// otlp-grpc
+    OtlpGrpc {
+        /// The collector endpoint, or None to use OTEL_EXPORTER_OTLP_ENDPOINT
+        /// or the exporter default.
+        endpoint: Option<String>,
+    },
+
+    /// otlp-http
+    OtlpHttp {
+        /// The collector endpoint, or None to use OTEL_EXPORTER_OTLP_ENDPOINT
+        /// or the exporter default.
+        endpoint: Option<String>,
+    },
+}
+
+impl FromStr for TelemetryExporter {
+    type Err = Error;
+
+    fn from_str(src: &str) -> Result<Self, Error> {
+        match src.to_lowercase().as_str() {
+            "jaeger" => Ok(Self::Jaeger),
+            "otlp" | "otlp-grpc" => Ok(Self::OtlpGrpc { endpoint: None }),
+            "otlp-http" => Ok(Self::OtlpHttp { endpoint: None }),
+            _ => Err(Error::UnknownExporter(src.to_owned())),
+        }
+    }
+}
+
+impl TelemetryExporter {
+    /// Read the exporter from the environment.
+    ///
+    /// env.MC_TELEMETRY_EXPORTER selects the exporter ("jaeger", "otlp-grpc"
+    /// or "otlp-http", defaults to "jaeger"), and
+    /// env.MC_TELEMETRY_OTLP_ENDPOINT optionally sets the OTLP collector
+    /// endpoint.
+    pub fn from_env() -> Result<Self, Error> {
+        let exporter = match std::env::var("MC_TELEMETRY_EXPORTER") {
+            Ok(val) if !val.is_empty() => Self::from_str(&val)?,
+            _ => Self::Jaeger,
+        };
+        let otlp_endpoint = std::env::var("MC_TELEMETRY_OTLP_ENDPOINT")
+            .ok()
+            .filter(|val| !val.is_empty());
+        Ok(match exporter {
+            Self::Jaeger => Self::Jaeger,
+            Self::OtlpGrpc { .. } => Self::OtlpGrpc {
+                endpoint: otlp_endpoint,
+            },
+            Self::OtlpHttp { .. } => Self::OtlpHttp {
+                endpoint: otlp_endpoint,
+            },
+        })
+    }
+}
+
+/// Set up a default tracer with no additional tags.
+/// Telemetry is enabled iff env.MC_TELEMETRY is set to "1" or "true".
+pub fn setup_default_tracer(service_name: &str) -> Result<Option<sdk::trace::Tracer>, Error> {
+    setup_default_tracer_with_tags(service_name, &[])
+}
+
+/// Set up a default tracer with the given extra tags.
+/// Telemetry is enabled iff env.MC_TELEMETRY is set to "1" or "true", and the
+/// exporter is selected by `TelemetryExporter::from_env`.
+pub fn setup_default_tracer_with_tags(
+    service_name: &str,
+    extra_tags: &[(&'static str, String)],
+) -> Result<Option<sdk::trace::Tracer>, Error> {
+    let telemetry_enabled = std::env::var("MC_TELEMETRY")
+        .map(|val| val == "1" || val.to_lowercase() == "true")
+        .unwrap_or(false);
+    if !telemetry_enabled {
+        return Ok(None);
+    }
+
+    setup_tracer(service_name, &TelemetryExporter::from_env()?, extra_tags).map(Some)
+}
+
+/// Set up the global tracer, exporting spans with the given exporter.
+///
+/// This also installs the W3C trace context propagator, so that trace context
+/// is carried across gRPC calls.
+pub fn setup_tracer(
+    service_name: &str,
+    exporter: &TelemetryExporter,
+    extra_tags: &[(&'static str, String)],
+) -> Result<sdk::trace::Tracer, Error> {
+    let local_hostname = hostname::get().map_err(Error::GetHostname)?;
+
+    let mut tags = vec![KeyValue::new(
+        "hostname",
+        local_hostname
+            .to_str()
+            .ok_or(Error::HostnameToString)?
+            .to_owned(),
+    )];
+    for (key, value) in extra_tags.iter() {
+        tags.push(KeyValue::new(*key, value.clone()));
+    }
+    let trace_config = sdk::trace::Config::default();
+
+    set_trace_context_propagator();
+
+    match exporter {
+        #[cfg(feature = "jaeger")]
+        TelemetryExporter::Jaeger => Ok(crate::jaeger::install(
+            service_name,
+            trace_config.with_resource(sdk::Resource::new(tags)),
+        )?),
+
+        #[cfg(feature = "otlp")]
+        TelemetryExporter::OtlpGrpc { endpoint } => {
+            tags.push(KeyValue::new("service.name", service_name.to_owned()));
+            Ok(crate::otlp::install_grpc(
+                endpoint.as_deref(),
+                trace_config.with_resource(sdk::Resource::new(tags)),
+            )?)
+        }
+
+        #[cfg(feature = "otlp")]
+        TelemetryExporter::OtlpHttp { endpoint } => {
+            tags.push(KeyValue::new("service.name", service_name.to_owned()));
+            Ok(crate::otlp::install_http(
+                endpoint.as_deref(),
+                trace_config.with_resource(sdk::Resource::new(tags)),
+            )?)
+        }
+
+        #[allow(unreachable_patterns)]
+        exporter => Err(Error::ExporterNotEnabled(exporter.clone())),
+    }
+}
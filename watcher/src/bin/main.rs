@@ -7,6 +7,8 @@
 use displaydoc::Display;
 use mc_watcher::{
     config::WatcherConfig,
+    http_api::start_http_server,
+    service::WatcherService,
     verification_reports_collector::VerificationReportsCollector,
     watcher::{SyncResult, Watcher},
     watcher_db::create_or_open_rw_watcher_db,
@@ -43,7 +45,7 @@ fn main() {
         .expect("Failed creating watcher");
 
     let _verification_reports_collector = <VerificationReportsCollector>::new(
-        watcher_db,
+        watcher_db.clone(),
         sources_config.sources().to_vec(),
         config.poll_interval,
         logger.clone(),
@@ -58,6 +60,7 @@ fn main() {
         Arc::new(move |_| HealthCheckStatus::SERVING);
     let health_service =
         HealthService::new(Some(health_check_callback), logger.clone()).into_service();
+    let watcher_service = WatcherService::new(watcher_db.clone(), logger.clone()).into_service();
 
     let env = Arc::new(
         EnvBuilder::new()
@@ -67,6 +70,7 @@ fn main() {
 
     let server_builder = ServerBuilder::new(env)
         .register_service(health_service)
+        .register_service(watcher_service)
         .bind_using_uri(&config.client_listen_uri, logger.clone());
 
     let mut server = server_builder.build().unwrap();
@@ -76,6 +80,27 @@ fn main() {
         log::info!(logger, "gRPC API listening on {}:{}", host, port);
     }
 
+    // Start the HTTP (JSON) server, if requested.
+    if let Some(http_listen_port) = config.http_listen_port {
+        let http_listen_host = config.http_listen_host;
+        log::info!(
+            logger,
+            "HTTP API listening on {}:{}",
+            http_listen_host,
+            http_listen_port
+        );
+        ThreadBuilder::new()
+            .name("WatcherHttp".to_string())
+            .spawn(move || {
+                rocket::execute(start_http_server(
+                    watcher_db,
+                    http_listen_host,
+                    http_listen_port,
+                ))
+            })
+            .expect("Failed starting watcher HTTP server thread");
+    }
+
     // Wait forever for sync thread to exit. If it ever exits, shut down the gRPC
     // server.
     sync_thread
@@ -7,7 +7,7 @@ use clap::Parser;
 use mc_util_parse::parse_duration_in_seconds;
 use mc_util_uri::{ConsensusClientUri, WatcherUri};
 use serde::{Deserialize, Serialize};
-use std::{fs, path::PathBuf, str::FromStr, time::Duration};
+use std::{fs, net::IpAddr, path::PathBuf, str::FromStr, time::Duration};
 use url::Url;
 
 #[derive(Clone, Debug, Parser)]
@@ -49,6 +49,15 @@ pub struct WatcherConfig {
         env = "MC_CLIENT_LISTEN_URI"
     )]
     pub client_listen_uri: WatcherUri,
+
+    /// (Optional) Port for the HTTP (JSON) query server. The HTTP server is
+    /// only started when this is set.
+    #[clap(long, env = "MC_HTTP_LISTEN_PORT")]
+    pub http_listen_port: Option<u16>,
+
+    /// Host for the HTTP (JSON) query server.
+    #[clap(long, default_value = "127.0.0.1", env = "MC_HTTP_LISTEN_HOST")]
+    pub http_listen_host: IpAddr,
 }
 
 impl WatcherConfig {
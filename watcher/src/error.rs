@@ -6,7 +6,7 @@ use displaydoc::Display;
 use mc_connection::Error as ConnectionError;
 use mc_crypto_keys::KeyError;
 use mc_ledger_sync::ReqwestTransactionsFetcherError;
-use mc_util_lmdb::MetadataStoreError;
+use mc_util_lmdb::{MetadataStoreError, MigrationError};
 use std::string::FromUtf8Error;
 
 /// Watcher Errors
@@ -53,7 +53,7 @@ impl From<ConnectionError> for WatcherError {
 }
 
 /// WatcherDB Errors
-#[derive(Debug, Eq, PartialEq, Copy, Clone, Display)]
+#[derive(Debug, Eq, PartialEq, Clone, Display)]
 pub enum WatcherDBError {
     /// Not found
     NotFound,
@@ -82,6 +82,9 @@ pub enum WatcherDBError {
     /// Metadata store: {0}
     MetadataStore(MetadataStoreError),
 
+    /// Migration error: {0}
+    Migration(MigrationError),
+
     /// UTF8
     Utf8,
 
@@ -126,6 +129,12 @@ impl From<MetadataStoreError> for WatcherDBError {
     }
 }
 
+impl From<MigrationError> for WatcherDBError {
+    fn from(src: MigrationError) -> Self {
+        Self::Migration(src)
+    }
+}
+
 impl From<FromUtf8Error> for WatcherDBError {
     fn from(_src: FromUtf8Error) -> Self {
         Self::Utf8
@@ -0,0 +1,102 @@
+// Copyright (c) 2018-2022 The MobileCoin Foundation
+
+//! Response types
+
+use crate::watcher_db::BlockSignatureData;
+use mc_blockchain_types::VerificationReport;
+use mc_watcher_api::TimestampResultCode;
+use rocket::serde::Serialize;
+use url::Url;
+
+/// A block signature, and where it was obtained from
+#[derive(Serialize, Debug, Eq, PartialEq)]
+#[allow(missing_docs)]
+pub struct BlockSignatureResponse {
+    pub src_url: String,
+    pub archive_filename: String,
+    /// Hex-encoded signer public key
+    pub signer: String,
+    /// Hex-encoded signature
+    pub signature: String,
+    pub signed_at: u64,
+}
+
+impl From<&BlockSignatureData> for BlockSignatureResponse {
+    fn from(src: &BlockSignatureData) -> Self {
+        Self {
+            src_url: src.src_url.clone(),
+            archive_filename: src.archive_filename.clone(),
+            signer: hex::encode(src.block_signature.signer()),
+            signature: hex::encode(src.block_signature.signature()),
+            signed_at: src.block_signature.signed_at(),
+        }
+    }
+}
+
+/// The timestamp of a block
+#[derive(Serialize, Debug, Eq, PartialEq)]
+#[allow(missing_docs)]
+pub struct BlockTimestampResponse {
+    pub timestamp: u64,
+    pub result_code: TimestampResultCode,
+}
+
+/// The highest block synced for every watched node
+#[derive(Serialize, Debug, Eq, PartialEq)]
+#[allow(missing_docs)]
+pub struct HighestCommonBlockResponse {
+    pub block_index: u64,
+}
+
+/// The last block synced for a watched node
+#[derive(Serialize, Debug, Eq, PartialEq)]
+#[allow(missing_docs)]
+pub struct LastSyncedBlockResponse {
+    pub src_url: String,
+    pub block_index: Option<u64>,
+}
+
+/// A verification report
+#[derive(Serialize, Debug, Eq, PartialEq)]
+#[allow(missing_docs)]
+pub struct VerificationReportResponse {
+    /// Hex-encoded report signature
+    pub sig: String,
+    /// Hex-encoded DER certificates
+    pub chain: Vec<String>,
+    pub http_body: String,
+}
+
+impl From<&VerificationReport> for VerificationReportResponse {
+    fn from(src: &VerificationReport) -> Self {
+        Self {
+            sig: hex::encode(&src.sig),
+            chain: src.chain.iter().map(hex::encode).collect(),
+            http_body: src.http_body.clone(),
+        }
+    }
+}
+
+/// The verification reports obtained from a watched node for a signer.
+/// A report is null if the watched node provided a report for a different
+/// signer.
+#[derive(Serialize, Debug, Eq, PartialEq)]
+#[allow(missing_docs)]
+pub struct SourceVerificationReportsResponse {
+    pub src_url: String,
+    pub verification_reports: Vec<Option<VerificationReportResponse>>,
+}
+
+impl SourceVerificationReportsResponse {
+    /// Create a new SourceVerificationReportsResponse from a source url and
+    /// the reports obtained from it
+    pub fn new(src_url: &Url, verification_reports: &[Option<VerificationReport>]) -> Self {
+        Self {
+            src_url: src_url.to_string(),
+            verification_reports: verification_reports
+                .iter()
+                .map(|report| report.as_ref().map(VerificationReportResponse::from))
+                .collect(),
+        }
+    }
+}
@@ -0,0 +1,45 @@
+// Copyright (c) 2018-2022 The MobileCoin Foundation
+
+//! HTTP (JSON) server for querying the watcher database
+
+mod api_types;
+mod routes;
+mod service;
+
+pub use service::WatcherHttpService;
+
+use crate::watcher_db::WatcherDB;
+use rocket::{custom, routes, Config};
+use std::net::IpAddr;
+
+/// Start the http server
+pub async fn start_http_server(watcher_db: WatcherDB, host: IpAddr, port: u16) {
+    let service = WatcherHttpService::new(watcher_db);
+
+    let config = Config {
+        address: host,
+        port,
+        ..Config::release_default()
+    };
+
+    if let Err(e) = custom(&config)
+        .manage(service)
+        .mount(
+            "/",
+            routes![
+                routes::index,
+                routes::get_block_signatures,
+                routes::get_block_timestamp,
+                routes::get_highest_common_block,
+                routes::get_last_synced_blocks,
+                routes::get_verification_reports_for_signer,
+            ],
+        )
+        .launch()
+        .await
+    {
+        println!("Whoops! Rocket didn't launch!");
+        // We drop the error to get a Rocket-formatted panic.
+        drop(e);
+    }
+}
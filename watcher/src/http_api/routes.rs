@@ -0,0 +1,80 @@
+// Copyright (c) 2018-2022 The MobileCoin Foundation
+
+//! Routing for the http server
+
+use crate::http_api::{
+    api_types::{
+        BlockSignatureResponse, BlockTimestampResponse, HighestCommonBlockResponse,
+        LastSyncedBlockResponse, SourceVerificationReportsResponse,
+    },
+    service::WatcherHttpService,
+};
+use mc_crypto_keys::Ed25519Public;
+use rocket::{get, serde::json::Json, State};
+
+/// Index route
+#[get("/")]
+pub fn index() -> &'static str {
+    "Welcome to the watcher"
+}
+
+/// Get the signatures for a block
+#[get("/block_signatures/<block_index>")]
+pub fn get_block_signatures(
+    block_index: u64,
+    service: &State<WatcherHttpService>,
+) -> Result<Json<Vec<BlockSignatureResponse>>, String> {
+    match service.get_block_signatures(block_index) {
+        Ok(signatures) => Ok(Json(signatures)),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// Get the earliest timestamp for a block
+#[get("/block_timestamp/<block_index>")]
+pub fn get_block_timestamp(
+    block_index: u64,
+    service: &State<WatcherHttpService>,
+) -> Result<Json<BlockTimestampResponse>, String> {
+    match service.get_block_timestamp(block_index) {
+        Ok(timestamp) => Ok(Json(timestamp)),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// Get the highest block that all watched nodes have synced
+#[get("/highest_common_block")]
+pub fn get_highest_common_block(
+    service: &State<WatcherHttpService>,
+) -> Result<Json<HighestCommonBlockResponse>, String> {
+    match service.get_highest_common_block() {
+        Ok(highest_common_block) => Ok(Json(highest_common_block)),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// Get the last block synced for each watched node
+#[get("/last_synced_blocks")]
+pub fn get_last_synced_blocks(
+    service: &State<WatcherHttpService>,
+) -> Result<Json<Vec<LastSyncedBlockResponse>>, String> {
+    match service.get_last_synced_blocks() {
+        Ok(last_synced_blocks) => Ok(Json(last_synced_blocks)),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// Get the verification reports seen for a hex-encoded block signer key
+#[get("/verification_reports/<signer>")]
+pub fn get_verification_reports_for_signer(
+    signer: &str,
+    service: &State<WatcherHttpService>,
+) -> Result<Json<Vec<SourceVerificationReportsResponse>>, String> {
+    let signer_bytes = hex::decode(signer).map_err(|e| e.to_string())?;
+    let signer = Ed25519Public::try_from(&signer_bytes[..]).map_err(|e| e.to_string())?;
+
+    match service.get_verification_reports_for_signer(&signer) {
+        Ok(reports) => Ok(Json(reports)),
+        Err(e) => Err(e.to_string()),
+    }
+}
@@ -0,0 +1,226 @@
+// Copyright (c) 2018-2022 The MobileCoin Foundation
+
+//! Watcher service for handling HTTP requests
+
+use crate::{
+    error::WatcherDBError,
+    http_api::api_types::{
+        BlockSignatureResponse, BlockTimestampResponse, HighestCommonBlockResponse,
+        LastSyncedBlockResponse, SourceVerificationReportsResponse,
+    },
+    watcher_db::WatcherDB,
+};
+use mc_crypto_keys::Ed25519Public;
+
+/// Service for handling watcher queries
+pub struct WatcherHttpService {
+    /// Watcher database.
+    watcher_db: WatcherDB,
+}
+
+impl WatcherHttpService {
+    /// Create a new watcher HTTP service.
+    pub fn new(watcher_db: WatcherDB) -> Self {
+        Self { watcher_db }
+    }
+
+    /// Get the signatures for a block
+    pub fn get_block_signatures(
+        &self,
+        block_index: u64,
+    ) -> Result<Vec<BlockSignatureResponse>, WatcherDBError> {
+        Ok(self
+            .watcher_db
+            .get_block_signatures(block_index)?
+            .iter()
+            .map(BlockSignatureResponse::from)
+            .collect())
+    }
+
+    /// Get the earliest timestamp for a block
+    pub fn get_block_timestamp(
+        &self,
+        block_index: u64,
+    ) -> Result<BlockTimestampResponse, WatcherDBError> {
+        let (timestamp, result_code) = self.watcher_db.get_block_timestamp(block_index)?;
+        Ok(BlockTimestampResponse {
+            timestamp,
+            result_code,
+        })
+    }
+
+    /// Get the highest block that all watched nodes have synced
+    pub fn get_highest_common_block(&self) -> Result<HighestCommonBlockResponse, WatcherDBError> {
+        Ok(HighestCommonBlockResponse {
+            block_index: self.watcher_db.highest_common_block()?,
+        })
+    }
+
+    /// Get the last block synced for each watched node, ordered by url
+    pub fn get_last_synced_blocks(&self) -> Result<Vec<LastSyncedBlockResponse>, WatcherDBError> {
+        let mut last_synced_blocks = self
+            .watcher_db
+            .last_synced_blocks()?
+            .into_iter()
+            .collect::<Vec<_>>();
+        last_synced_blocks.sort();
+
+        Ok(last_synced_blocks
+            .into_iter()
+            .map(|(src_url, block_index)| LastSyncedBlockResponse {
+                src_url: src_url.to_string(),
+                block_index,
+            })
+            .collect())
+    }
+
+    /// Get the verification reports seen for a block signer, ordered by url
+    pub fn get_verification_reports_for_signer(
+        &self,
+        signer: &Ed25519Public,
+    ) -> Result<Vec<SourceVerificationReportsResponse>, WatcherDBError> {
+        let mut reports = self
+            .watcher_db
+            .get_verification_reports_for_signer(signer)?
+            .into_iter()
+            .collect::<Vec<_>>();
+        reports.sort_by(|(url1, _), (url2, _)| url1.cmp(url2));
+
+        Ok(reports
+            .iter()
+            .map(|(src_url, verification_reports)| {
+                SourceVerificationReportsResponse::new(src_url, verification_reports)
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        http_api::api_types::VerificationReportResponse,
+        watcher_db::tests::{setup_blocks, setup_watcher_db},
+    };
+    use mc_blockchain_types::{BlockSignature, VerificationReport};
+    use mc_common::logger::{test_with_logger, Logger};
+    use mc_crypto_keys::Ed25519Pair;
+    use mc_util_from_random::FromRandom;
+    use mc_util_test_helper::run_with_one_seed;
+    use mc_watcher_api::TimestampResultCode;
+    use url::Url;
+
+    #[test_with_logger]
+    fn test_get_block_signatures_and_synced_blocks(logger: Logger) {
+        run_with_one_seed(|mut rng| {
+            let url1 = Url::parse("http://www.my_url1.com").unwrap();
+            let url2 = Url::parse("http://www.my_url2.com").unwrap();
+            let urls = [url1.clone(), url2.clone()];
+            let watcher_db = setup_watcher_db(&urls, logger.clone());
+            let service = WatcherHttpService::new(watcher_db.clone());
+
+            let blocks = setup_blocks();
+            let signing_key = Ed25519Pair::from_random(&mut rng);
+            let mut signed_block =
+                BlockSignature::from_block_and_keypair(blocks[1].block(), &signing_key).unwrap();
+            signed_block.set_signed_at(1594679718);
+            watcher_db
+                .add_block_signature(&url1, 1, signed_block.clone(), "00/01".to_owned())
+                .unwrap();
+
+            assert_eq!(
+                service.get_block_signatures(1).unwrap(),
+                vec![BlockSignatureResponse {
+                    src_url: url1.to_string(),
+                    archive_filename: "00/01".to_owned(),
+                    signer: hex::encode(signing_key.public_key()),
+                    signature: hex::encode(signed_block.signature()),
+                    signed_at: 1594679718,
+                }]
+            );
+            assert_eq!(service.get_block_signatures(2).unwrap(), vec![]);
+
+            assert_eq!(
+                service.get_block_timestamp(1).unwrap(),
+                BlockTimestampResponse {
+                    timestamp: 1594679718,
+                    result_code: TimestampResultCode::TimestampFound,
+                }
+            );
+
+            // url2 has not synced anything yet.
+            assert_eq!(service.get_highest_common_block().unwrap().block_index, 0);
+            assert_eq!(
+                service.get_last_synced_blocks().unwrap(),
+                vec![
+                    LastSyncedBlockResponse {
+                        src_url: url1.to_string(),
+                        block_index: Some(1),
+                    },
+                    LastSyncedBlockResponse {
+                        src_url: url2.to_string(),
+                        block_index: None,
+                    },
+                ]
+            );
+        });
+    }
+
+    #[test_with_logger]
+    fn test_get_verification_reports_for_signer(logger: Logger) {
+        run_with_one_seed(|mut rng| {
+            let url1 = Url::parse("http://www.my_url1.com").unwrap();
+            let url2 = Url::parse("http://www.my_url2.com").unwrap();
+            let urls = [url1.clone(), url2.clone()];
+            let watcher_db = setup_watcher_db(&urls, logger.clone());
+            let service = WatcherHttpService::new(watcher_db.clone());
+
+            let signing_key_a = Ed25519Pair::from_random(&mut rng).public_key();
+            let signing_key_b = Ed25519Pair::from_random(&mut rng).public_key();
+            let signing_key_c = Ed25519Pair::from_random(&mut rng).public_key();
+
+            let verification_report_a = VerificationReport {
+                sig: vec![1u8; 32].into(),
+                chain: vec![vec![2; 16], vec![3; 32]],
+                http_body: "test body a".to_owned(),
+            };
+            watcher_db
+                .add_verification_report(
+                    &url2,
+                    &signing_key_a,
+                    &verification_report_a,
+                    &[signing_key_b],
+                )
+                .unwrap();
+
+            assert_eq!(
+                service
+                    .get_verification_reports_for_signer(&signing_key_a)
+                    .unwrap(),
+                vec![SourceVerificationReportsResponse {
+                    src_url: url2.to_string(),
+                    verification_reports: vec![Some(VerificationReportResponse {
+                        sig: hex::encode(vec![1u8; 32]),
+                        chain: vec![hex::encode(vec![2; 16]), hex::encode(vec![3; 32])],
+                        http_body: "test body a".to_owned(),
+                    })],
+                }]
+            );
+            assert_eq!(
+                service
+                    .get_verification_reports_for_signer(&signing_key_b)
+                    .unwrap(),
+                vec![SourceVerificationReportsResponse {
+                    src_url: url2.to_string(),
+                    verification_reports: vec![None],
+                }]
+            );
+            assert_eq!(
+                service
+                    .get_verification_reports_for_signer(&signing_key_c)
+                    .unwrap(),
+                vec![]
+            );
+        });
+    }
+}
@@ -7,7 +7,9 @@
 pub mod block_data_store;
 pub mod config;
 pub mod error;
+pub mod http_api;
 pub mod metrics;
+pub mod service;
 pub mod verification_reports_collector;
 pub mod watcher;
 pub mod watcher_db;
@@ -0,0 +1,422 @@
+// Copyright (c) 2018-2022 The MobileCoin Foundation
+
+//! Watcher gRPC query service implementation.
+
+use crate::watcher_db::WatcherDB;
+use grpcio::{RpcContext, RpcStatus, Service, UnarySink};
+use mc_api::{blockchain, external, watcher};
+use mc_common::logger::Logger;
+use mc_crypto_keys::Ed25519Public;
+use mc_util_grpc::{rpc_database_err, rpc_invalid_arg_error, rpc_logger, send_result};
+use mc_watcher_grpc_api::{
+    empty::Empty,
+    watcher_service::{
+        BlockSignatureData, GetBlockSignaturesRequest, GetBlockSignaturesResponse,
+        GetBlockTimestampRequest, GetBlockTimestampResponse, GetHighestCommonBlockResponse,
+        GetLastSyncedBlocksResponse, GetVerificationReportsForSignerRequest,
+        GetVerificationReportsForSignerResponse, LastSyncedBlock, SourceVerificationReports,
+        VerificationReportData,
+    },
+    watcher_service_grpc::{create_watcher_api, WatcherApi},
+};
+
+/// Watcher gRPC query service implementation.
+#[derive(Clone)]
+pub struct WatcherService {
+    /// Watcher database.
+    watcher_db: WatcherDB,
+
+    /// Logger.
+    logger: Logger,
+}
+
+impl WatcherService {
+    /// Create a new watcher query service.
+    pub fn new(watcher_db: WatcherDB, logger: Logger) -> Self {
+        Self { watcher_db, logger }
+    }
+
+    /// Convert into a grpc service
+    pub fn into_service(self) -> Service {
+        create_watcher_api(self)
+    }
+
+    fn get_block_signatures_impl(
+        &self,
+        req: &GetBlockSignaturesRequest,
+        logger: &Logger,
+    ) -> Result<GetBlockSignaturesResponse, RpcStatus> {
+        let signatures = self
+            .watcher_db
+            .get_block_signatures(req.get_block_index())
+            .map_err(|err| rpc_database_err(err, logger))?;
+
+        let mut resp = GetBlockSignaturesResponse::new();
+        for signature_data in signatures.iter() {
+            let mut grpc_signature_data = BlockSignatureData::new();
+            grpc_signature_data.set_src_url(signature_data.src_url.clone());
+            grpc_signature_data.set_archive_filename(signature_data.archive_filename.clone());
+            grpc_signature_data.set_block_signature(blockchain::BlockSignature::from(
+                &signature_data.block_signature,
+            ));
+            resp.mut_signatures().push(grpc_signature_data);
+        }
+        Ok(resp)
+    }
+
+    fn get_block_timestamp_impl(
+        &self,
+        req: &GetBlockTimestampRequest,
+        logger: &Logger,
+    ) -> Result<GetBlockTimestampResponse, RpcStatus> {
+        let (timestamp, timestamp_result_code) = self
+            .watcher_db
+            .get_block_timestamp(req.get_block_index())
+            .map_err(|err| rpc_database_err(err, logger))?;
+
+        let mut resp = GetBlockTimestampResponse::new();
+        resp.set_timestamp(timestamp);
+        resp.set_timestamp_result_code(watcher::TimestampResultCode::from(&timestamp_result_code));
+        Ok(resp)
+    }
+
+    fn get_highest_common_block_impl(
+        &self,
+        logger: &Logger,
+    ) -> Result<GetHighestCommonBlockResponse, RpcStatus> {
+        let block_index = self
+            .watcher_db
+            .highest_common_block()
+            .map_err(|err| rpc_database_err(err, logger))?;
+
+        let mut resp = GetHighestCommonBlockResponse::new();
+        resp.set_block_index(block_index);
+        Ok(resp)
+    }
+
+    fn get_last_synced_blocks_impl(
+        &self,
+        logger: &Logger,
+    ) -> Result<GetLastSyncedBlocksResponse, RpcStatus> {
+        let mut last_synced_blocks = self
+            .watcher_db
+            .last_synced_blocks()
+            .map_err(|err| rpc_database_err(err, logger))?
+            .into_iter()
+            .collect::<Vec<_>>();
+        last_synced_blocks.sort();
+
+        let mut resp = GetLastSyncedBlocksResponse::new();
+        for (src_url, block_index) in last_synced_blocks {
+            let mut last_synced_block = LastSyncedBlock::new();
+            last_synced_block.set_src_url(src_url.to_string());
+            if let Some(block_index) = block_index {
+                last_synced_block.set_has_synced_block(true);
+                last_synced_block.set_block_index(block_index);
+            }
+            resp.mut_last_synced_blocks().push(last_synced_block);
+        }
+        Ok(resp)
+    }
+
+    fn get_verification_reports_for_signer_impl(
+        &self,
+        req: &GetVerificationReportsForSignerRequest,
+        logger: &Logger,
+    ) -> Result<GetVerificationReportsForSignerResponse, RpcStatus> {
+        let signer = Ed25519Public::try_from(req.get_signer())
+            .map_err(|err| rpc_invalid_arg_error("signer", err, logger))?;
+
+        let mut reports = self
+            .watcher_db
+            .get_verification_reports_for_signer(&signer)
+            .map_err(|err| rpc_database_err(err, logger))?
+            .into_iter()
+            .collect::<Vec<_>>();
+        reports.sort_by(|(url1, _), (url2, _)| url1.cmp(url2));
+
+        let mut resp = GetVerificationReportsForSignerResponse::new();
+        for (src_url, verification_reports) in reports {
+            let mut source_reports = SourceVerificationReports::new();
+            source_reports.set_src_url(src_url.to_string());
+            for verification_report in verification_reports {
+                let mut report_data = VerificationReportData::new();
+                if let Some(verification_report) = verification_report {
+                    report_data.set_verification_report(external::VerificationReport::from(
+                        &verification_report,
+                    ));
+                }
+                source_reports.mut_verification_reports().push(report_data);
+            }
+            resp.mut_sources().push(source_reports);
+        }
+        Ok(resp)
+    }
+}
+
+impl WatcherApi for WatcherService {
+    fn get_block_signatures(
+        &mut self,
+        ctx: RpcContext,
+        req: GetBlockSignaturesRequest,
+        sink: UnarySink<GetBlockSignaturesResponse>,
+    ) {
+        let logger = rpc_logger(&ctx, &self.logger);
+        send_result(
+            ctx,
+            sink,
+            self.get_block_signatures_impl(&req, &logger),
+            &logger,
+        )
+    }
+
+    fn get_block_timestamp(
+        &mut self,
+        ctx: RpcContext,
+        req: GetBlockTimestampRequest,
+        sink: UnarySink<GetBlockTimestampResponse>,
+    ) {
+        let logger = rpc_logger(&ctx, &self.logger);
+        send_result(
+            ctx,
+            sink,
+            self.get_block_timestamp_impl(&req, &logger),
+            &logger,
+        )
+    }
+
+    fn get_highest_common_block(
+        &mut self,
+        ctx: RpcContext,
+        _req: Empty,
+        sink: UnarySink<GetHighestCommonBlockResponse>,
+    ) {
+        let logger = rpc_logger(&ctx, &self.logger);
+        send_result(
+            ctx,
+            sink,
+            self.get_highest_common_block_impl(&logger),
+            &logger,
+        )
+    }
+
+    fn get_last_synced_blocks(
+        &mut self,
+        ctx: RpcContext,
+        _req: Empty,
+        sink: UnarySink<GetLastSyncedBlocksResponse>,
+    ) {
+        let logger = rpc_logger(&ctx, &self.logger);
+        send_result(
+            ctx,
+            sink,
+            self.get_last_synced_blocks_impl(&logger),
+            &logger,
+        )
+    }
+
+    fn get_verification_reports_for_signer(
+        &mut self,
+        ctx: RpcContext,
+        req: GetVerificationReportsForSignerRequest,
+        sink: UnarySink<GetVerificationReportsForSignerResponse>,
+    ) {
+        let logger = rpc_logger(&ctx, &self.logger);
+        send_result(
+            ctx,
+            sink,
+            self.get_verification_reports_for_signer_impl(&req, &logger),
+            &logger,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::watcher_db::tests::{setup_blocks, setup_watcher_db};
+    use mc_blockchain_types::{BlockSignature, VerificationReport};
+    use mc_common::logger::{test_with_logger, Logger};
+    use mc_crypto_keys::Ed25519Pair;
+    use mc_util_from_random::FromRandom;
+    use mc_util_test_helper::run_with_one_seed;
+    use url::Url;
+
+    #[test_with_logger]
+    fn test_get_block_signatures_and_timestamp(logger: Logger) {
+        run_with_one_seed(|mut rng| {
+            let url1 = Url::parse("http://www.my_url1.com").unwrap();
+            let url2 = Url::parse("http://www.my_url2.com").unwrap();
+            let urls = [url1.clone(), url2.clone()];
+            let watcher_db = setup_watcher_db(&urls, logger.clone());
+            let service = WatcherService::new(watcher_db.clone(), logger.clone());
+
+            let blocks = setup_blocks();
+            let signing_key_a = Ed25519Pair::from_random(&mut rng);
+            let signing_key_b = Ed25519Pair::from_random(&mut rng);
+
+            let mut signed_block_a1 =
+                BlockSignature::from_block_and_keypair(blocks[1].block(), &signing_key_a).unwrap();
+            signed_block_a1.set_signed_at(1594679718);
+            watcher_db
+                .add_block_signature(&url1, 1, signed_block_a1.clone(), "00/01".to_owned())
+                .unwrap();
+
+            let mut signed_block_b1 =
+                BlockSignature::from_block_and_keypair(blocks[1].block(), &signing_key_b).unwrap();
+            signed_block_b1.set_signed_at(1594679727);
+            watcher_db
+                .add_block_signature(&url2, 1, signed_block_b1.clone(), "00/01".to_owned())
+                .unwrap();
+
+            let mut req = GetBlockSignaturesRequest::new();
+            req.set_block_index(1);
+            let resp = service.get_block_signatures_impl(&req, &logger).unwrap();
+
+            let mut signatures = resp
+                .get_signatures()
+                .iter()
+                .map(|signature_data| {
+                    (
+                        signature_data.get_src_url().to_owned(),
+                        BlockSignature::try_from(signature_data.get_block_signature()).unwrap(),
+                    )
+                })
+                .collect::<Vec<_>>();
+            signatures.sort_by(|(url1, _), (url2, _)| url1.cmp(url2));
+            assert_eq!(
+                signatures,
+                vec![
+                    (url1.to_string(), signed_block_a1),
+                    (url2.to_string(), signed_block_b1)
+                ]
+            );
+
+            let mut req = GetBlockTimestampRequest::new();
+            req.set_block_index(1);
+            let resp = service.get_block_timestamp_impl(&req, &logger).unwrap();
+            assert_eq!(resp.get_timestamp(), 1594679718);
+            assert_eq!(
+                resp.get_timestamp_result_code(),
+                watcher::TimestampResultCode::TimestampFound
+            );
+
+            let mut req = GetBlockTimestampRequest::new();
+            req.set_block_index(2);
+            let resp = service.get_block_timestamp_impl(&req, &logger).unwrap();
+            assert_eq!(resp.get_timestamp(), u64::MAX);
+            assert_eq!(
+                resp.get_timestamp_result_code(),
+                watcher::TimestampResultCode::WatcherBehind
+            );
+        });
+    }
+
+    #[test_with_logger]
+    fn test_get_synced_blocks(logger: Logger) {
+        run_with_one_seed(|mut rng| {
+            let url1 = Url::parse("http://www.my_url1.com").unwrap();
+            let url2 = Url::parse("http://www.my_url2.com").unwrap();
+            let urls = [url1.clone(), url2.clone()];
+            let watcher_db = setup_watcher_db(&urls, logger.clone());
+            let service = WatcherService::new(watcher_db.clone(), logger.clone());
+
+            let resp = service.get_highest_common_block_impl(&logger).unwrap();
+            assert_eq!(resp.get_block_index(), 0);
+
+            let blocks = setup_blocks();
+            let signing_key = Ed25519Pair::from_random(&mut rng);
+            for block_index in 1..3 {
+                let signed_block = BlockSignature::from_block_and_keypair(
+                    blocks[block_index as usize].block(),
+                    &signing_key,
+                )
+                .unwrap();
+                watcher_db
+                    .add_block_signature(
+                        &url1,
+                        block_index,
+                        signed_block,
+                        format!("00/0{}", block_index),
+                    )
+                    .unwrap();
+            }
+
+            let resp = service.get_highest_common_block_impl(&logger).unwrap();
+            assert_eq!(resp.get_block_index(), 0);
+
+            let resp = service.get_last_synced_blocks_impl(&logger).unwrap();
+            let last_synced_blocks = resp.get_last_synced_blocks();
+            assert_eq!(last_synced_blocks.len(), 2);
+            assert_eq!(last_synced_blocks[0].get_src_url(), url1.as_str());
+            assert!(last_synced_blocks[0].get_has_synced_block());
+            assert_eq!(last_synced_blocks[0].get_block_index(), 2);
+            assert_eq!(last_synced_blocks[1].get_src_url(), url2.as_str());
+            assert!(!last_synced_blocks[1].get_has_synced_block());
+
+            watcher_db.update_last_synced(&url2, 1).unwrap();
+            let resp = service.get_highest_common_block_impl(&logger).unwrap();
+            assert_eq!(resp.get_block_index(), 1);
+        });
+    }
+
+    #[test_with_logger]
+    fn test_get_verification_reports_for_signer(logger: Logger) {
+        run_with_one_seed(|mut rng| {
+            let url1 = Url::parse("http://www.my_url1.com").unwrap();
+            let url2 = Url::parse("http://www.my_url2.com").unwrap();
+            let urls = [url1.clone(), url2.clone()];
+            let watcher_db = setup_watcher_db(&urls, logger.clone());
+            let service = WatcherService::new(watcher_db.clone(), logger.clone());
+
+            let signing_key_a = Ed25519Pair::from_random(&mut rng).public_key();
+            let signing_key_b = Ed25519Pair::from_random(&mut rng).public_key();
+
+            let verification_report_a = VerificationReport {
+                sig: vec![1u8; 32].into(),
+                chain: vec![vec![2; 16], vec![3; 32]],
+                http_body: "test body a".to_owned(),
+            };
+            watcher_db
+                .add_verification_report(
+                    &url1,
+                    &signing_key_a,
+                    &verification_report_a,
+                    &[signing_key_b],
+                )
+                .unwrap();
+
+            let mut req = GetVerificationReportsForSignerRequest::new();
+            req.set_signer(external::Ed25519Public::from(&signing_key_a));
+            let resp = service
+                .get_verification_reports_for_signer_impl(&req, &logger)
+                .unwrap();
+            assert_eq!(resp.get_sources().len(), 1);
+            assert_eq!(resp.get_sources()[0].get_src_url(), url1.as_str());
+            let reports = resp.get_sources()[0].get_verification_reports();
+            assert_eq!(reports.len(), 1);
+            assert_eq!(
+                VerificationReport::from(reports[0].get_verification_report()),
+                verification_report_a
+            );
+
+            // The report fetched from url1 did not match signing_key_b.
+            req.set_signer(external::Ed25519Public::from(&signing_key_b));
+            let resp = service
+                .get_verification_reports_for_signer_impl(&req, &logger)
+                .unwrap();
+            assert_eq!(resp.get_sources().len(), 1);
+            let reports = resp.get_sources()[0].get_verification_reports();
+            assert_eq!(reports.len(), 1);
+            assert!(!reports[0].has_verification_report());
+
+            // Invalid signer keys are rejected.
+            let mut invalid_signer = external::Ed25519Public::new();
+            invalid_signer.set_data(vec![1; 5]);
+            req.set_signer(invalid_signer);
+            assert!(service
+                .get_verification_reports_for_signer_impl(&req, &logger)
+                .is_err());
+        });
+    }
+}
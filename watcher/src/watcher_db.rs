@@ -11,7 +11,7 @@ use mc_common::{
 };
 use mc_crypto_digestible::{Digestible, MerlinTranscript};
 use mc_crypto_keys::Ed25519Public;
-use mc_util_lmdb::{MetadataStore, MetadataStoreSettings};
+use mc_util_lmdb::{MetadataStore, MetadataStoreSettings, Migrations};
 use mc_util_repr_bytes::ReprBytes;
 use mc_util_serial::{decode, encode, Message};
 use mc_watcher_api::TimestampResultCode;
@@ -52,6 +52,13 @@ impl MetadataStoreSettings for WatcherDbMetadataStoreSettings {
     const DB_NAME: &'static str = "watcher_db_metadata";
 }
 
+/// The migration steps of the watcher database. Steps are added here when
+/// `LATEST_VERSION` is bumped, and run when the database is opened for
+/// read-write operations.
+pub fn watcher_db_migrations() -> Migrations<WatcherDbMetadataStoreSettings> {
+    Migrations::new()
+}
+
 /// Block signatures database name.
 pub const BLOCK_SIGNATURES_DB_NAME: &str = "watcher_db:block_signatures";
 
@@ -161,15 +168,41 @@ pub struct WatcherDB {
 impl WatcherDB {
     /// Open an existing WatcherDB for read-only operations.
     pub fn open_ro(path: &Path, logger: Logger) -> Result<Self, WatcherDBError> {
-        let env = Arc::new(
+        Self::open(Self::open_env(path)?, logger)
+    }
+
+    /// Open an existing WatcherDB for read-write operations, migrating it to
+    /// the latest version first if needed.
+    pub fn open_rw(
+        path: &Path,
+        tx_source_urls: &[Url],
+        logger: Logger,
+    ) -> Result<Self, WatcherDBError> {
+        let env = Self::open_env(path)?;
+
+        let metadata_store = MetadataStore::<WatcherDbMetadataStoreSettings>::new(&env)?;
+        watcher_db_migrations().migrate(&env, &metadata_store, |event| {
+            log::info!(logger, "Watcher db {}", event)
+        })?;
+
+        let mut db = Self::open(env, logger)?;
+        db.write_allowed = true;
+        db.store_config(tx_source_urls)?;
+        Ok(db)
+    }
+
+    fn open_env(path: &Path) -> Result<Arc<Environment>, WatcherDBError> {
+        Ok(Arc::new(
             Environment::new()
                 .set_max_dbs(MAX_DATABASES)
                 .set_map_size(MAX_LMDB_FILE_SIZE)
                 // TODO - needed because currently our test cloud machines have slow disks.
                 .set_flags(EnvironmentFlags::NO_SYNC)
                 .open(path)?,
-        );
+        ))
+    }
 
+    fn open(env: Arc<Environment>, logger: Logger) -> Result<Self, WatcherDBError> {
         let metadata_store = MetadataStore::<WatcherDbMetadataStoreSettings>::new(&env)?;
 
         let db_txn = env.begin_ro_txn()?;
@@ -205,18 +238,6 @@ impl WatcherDB {
         })
     }
 
-    /// Open an existing WatcherDB for read-write operations.
-    pub fn open_rw(
-        path: &Path,
-        tx_source_urls: &[Url],
-        logger: Logger,
-    ) -> Result<Self, WatcherDBError> {
-        let mut db = Self::open_ro(path, logger)?;
-        db.write_allowed = true;
-        db.store_config(tx_source_urls)?;
-        Ok(db)
-    }
-
     /// Create a fresh WatcherDB.
     pub fn create(path: &Path) -> Result<(), WatcherDBError> {
         let env = Arc::new(